@@ -0,0 +1,318 @@
+//! Bookkeeping export: the project's own side of a token's history, one
+//! row per asset moved in or out of its wallets, each tagged with what it
+//! was for and valued in USD at the time it happened, in the CSV layouts
+//! accounting tools import.
+//!
+//! Movements between two of the project's wallets are left out, since
+//! nothing changed hands. Transfers out of a transaction that pays several
+//! wallets, as `airdrop` and `claim` send them, count as an airdrop;
+//! tokens minted straight to someone else count as one too. Every
+//! transaction a project wallet paid for adds a fee row in SOL.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount::TokenAmount;
+use crate::csv;
+use crate::error::{Error, Result};
+use crate::export::{MovementKind, TokenMovement};
+use crate::portfolio::to_f64;
+use crate::pubkey::Pubkey;
+use crate::rpc::ConfirmedTransaction;
+use crate::time::format_rfc3339;
+
+/// CoinGecko's public API, which keeps price history for SOL and for
+/// tokens by contract address.
+pub const DEFAULT_PRICE_HISTORY_API: &str = "https://api.coingecko.com/api/v3";
+
+pub const SOL: &str = "SOL";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Purpose {
+    Mint,
+    Burn,
+    Airdrop,
+    Transfer,
+    Fee,
+}
+
+impl Purpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Purpose::Mint => "mint",
+            Purpose::Burn => "burn",
+            Purpose::Airdrop => "airdrop",
+            Purpose::Transfer => "transfer",
+            Purpose::Fee => "fee",
+        }
+    }
+
+    /// The label Koinly files the row under, where one fits.
+    fn koinly_label(self) -> &'static str {
+        match self {
+            Purpose::Airdrop => "gift",
+            Purpose::Fee => "cost",
+            _ => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    In,
+    Out,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    pub signature: String,
+    pub block_time: Option<i64>,
+    pub purpose: Purpose,
+    pub direction: Direction,
+    /// The token's symbol, or `SOL` for fees.
+    pub asset: String,
+    pub amount: TokenAmount,
+    /// The other side: the recipient of what went out, the sender of what
+    /// came in.
+    pub counterparty: Option<Pubkey>,
+    /// USD per whole unit of the asset when it moved, if known.
+    pub usd_price: Option<f64>,
+    pub memo: Option<String>,
+}
+
+impl Entry {
+    pub fn usd_value(&self) -> Option<f64> {
+        Some(to_f64(self.amount) * self.usd_price?)
+    }
+}
+
+/// Turns a token's movements and the transactions they came from into
+/// entries for the project owning `wallets`. `transactions` maps each
+/// signature to its transaction, for the fees.
+pub fn entries(
+    symbol: &str,
+    wallets: &HashSet<Pubkey>,
+    movements: &[TokenMovement],
+    transactions: &BTreeMap<String, ConfirmedTransaction>,
+) -> Result<Vec<Entry>> {
+    let ours = |owner: Option<Pubkey>| owner.is_some_and(|o| wallets.contains(&o));
+    let mut recipients: BTreeMap<&str, HashSet<Option<Pubkey>>> = BTreeMap::new();
+    for m in movements
+        .iter()
+        .filter(|m| m.kind == MovementKind::Transfer)
+    {
+        recipients
+            .entry(&m.signature)
+            .or_default()
+            .insert(m.destination_owner.or(m.destination));
+    }
+    let mut entries = Vec::new();
+    for m in movements {
+        let (purpose, direction, counterparty) = match m.kind {
+            MovementKind::Mint if ours(m.destination_owner) => (Purpose::Mint, Direction::In, None),
+            MovementKind::Mint if ours(m.authority) => (
+                Purpose::Airdrop,
+                Direction::Out,
+                m.destination_owner.or(m.destination),
+            ),
+            MovementKind::Burn if ours(m.source_owner) => (Purpose::Burn, Direction::Out, None),
+            MovementKind::Transfer => match (ours(m.source_owner), ours(m.destination_owner)) {
+                (true, false) => (
+                    if recipients.get(m.signature.as_str()).map_or(0, HashSet::len) > 1 {
+                        Purpose::Airdrop
+                    } else {
+                        Purpose::Transfer
+                    },
+                    Direction::Out,
+                    m.destination_owner.or(m.destination),
+                ),
+                (false, true) => (
+                    Purpose::Transfer,
+                    Direction::In,
+                    m.source_owner.or(m.source),
+                ),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        entries.push(Entry {
+            signature: m.signature.clone(),
+            block_time: m.block_time,
+            purpose,
+            direction,
+            asset: symbol.to_string(),
+            amount: m.amount,
+            counterparty,
+            usd_price: None,
+            memo: m.memo.clone(),
+        });
+    }
+    for (signature, tx) in transactions {
+        let payer = tx.account_keys()?.first().copied();
+        let fee = tx.meta.as_ref().map_or(0, |m| m.fee);
+        if fee == 0 || !payer.is_some_and(|p| wallets.contains(&p)) {
+            continue;
+        }
+        entries.push(Entry {
+            signature: signature.clone(),
+            block_time: tx.block_time,
+            purpose: Purpose::Fee,
+            direction: Direction::Out,
+            asset: SOL.to_string(),
+            amount: TokenAmount::from_raw(fee.into(), 9),
+            counterparty: None,
+            usd_price: None,
+            memo: None,
+        });
+    }
+    entries.sort_by_key(|e| e.block_time);
+    Ok(entries)
+}
+
+/// USD prices of one asset over time, oldest first, as (unix seconds,
+/// price) points.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PriceHistory {
+    pub points: Vec<(i64, f64)>,
+}
+
+impl PriceHistory {
+    /// The last price quoted at or before `unix`, or the first one after it
+    /// for moments before the history starts.
+    pub fn at(&self, unix: i64) -> Option<f64> {
+        let after = self.points.partition_point(|(t, _)| *t <= unix);
+        let index = after.saturating_sub(1);
+        self.points.get(index).map(|(_, price)| *price)
+    }
+}
+
+#[derive(Deserialize)]
+struct MarketChart {
+    prices: Vec<(f64, f64)>,
+}
+
+/// The USD price history of SOL between `from` and `to`, in unix seconds.
+pub fn sol_price_history(api_url: &str, from: i64, to: i64) -> Result<PriceHistory> {
+    fetch_history(api_url, "coins/solana", from, to)?
+        .ok_or_else(|| Error::Http(format!("{api_url} has no price history for SOL")))
+}
+
+/// The USD price history of `mint`; `None` when the API doesn't list it,
+/// as it won't for a token that never traded.
+pub fn token_price_history(
+    api_url: &str,
+    mint: &Pubkey,
+    from: i64,
+    to: i64,
+) -> Result<Option<PriceHistory>> {
+    fetch_history(api_url, &format!("coins/solana/contract/{mint}"), from, to)
+}
+
+fn fetch_history(api_url: &str, coin: &str, from: i64, to: i64) -> Result<Option<PriceHistory>> {
+    let url = format!(
+        "{}/{coin}/market_chart/range",
+        api_url.trim_end_matches('/')
+    );
+    let chart: MarketChart = match ureq::get(&url)
+        .query("vs_currency", "usd")
+        .query("from", &from.to_string())
+        .query("to", &to.to_string())
+        .call()
+    {
+        Ok(response) => response
+            .into_json()
+            .map_err(|e| Error::Http(format!("GET {url}: unexpected response: {e}")))?,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(Error::Http(format!("GET {url}: {e}"))),
+    };
+    Ok(Some(PriceHistory {
+        points: chart
+            .prices
+            .into_iter()
+            .map(|(ms, price)| ((ms / 1000.0) as i64, price))
+            .collect(),
+    }))
+}
+
+/// Fills in each entry's price from the history of its asset.
+pub fn apply_prices(entries: &mut [Entry], sol: &PriceHistory, token: Option<&PriceHistory>) {
+    for entry in entries {
+        let history = if entry.asset == SOL { Some(sol) } else { token };
+        entry.usd_price = history.zip(entry.block_time).and_then(|(h, t)| h.at(t));
+    }
+}
+
+fn usd(value: Option<f64>) -> String {
+    value.map(|v| format!("{v:.2}")).unwrap_or_default()
+}
+
+pub fn write_csv(entries: &[Entry], mut out: impl Write) -> Result<()> {
+    let key = |k: Option<Pubkey>| k.map(|k| k.to_string()).unwrap_or_default();
+    writeln!(
+        out,
+        "timestamp,purpose,direction,asset,amount,usd_price,usd_value,counterparty,signature,memo"
+    )?;
+    for e in entries {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{}",
+            e.block_time.map(format_rfc3339).unwrap_or_default(),
+            e.purpose.as_str(),
+            match e.direction {
+                Direction::In => "in",
+                Direction::Out => "out",
+            },
+            csv::escape(&e.asset),
+            e.amount,
+            e.usd_price.map(|p| p.to_string()).unwrap_or_default(),
+            usd(e.usd_value()),
+            key(e.counterparty),
+            e.signature,
+            csv::escape(e.memo.as_deref().unwrap_or_default()),
+        )?;
+    }
+    Ok(())
+}
+
+/// Koinly's universal format. Fees are rows of their own, sent as SOL and
+/// labelled as a cost, since a fee may belong to a transaction that moved
+/// nothing of the project's.
+pub fn write_koinly_csv(entries: &[Entry], mut out: impl Write) -> Result<()> {
+    writeln!(
+        out,
+        "Date,Sent Amount,Sent Currency,Received Amount,Received Currency,Fee Amount,Fee Currency,Net Worth Amount,Net Worth Currency,Label,Description,TxHash"
+    )?;
+    for e in entries {
+        let date = e
+            .block_time
+            .map(|t| format_rfc3339(t).replace('T', " ").replace('Z', " UTC"))
+            .unwrap_or_default();
+        let (amount, asset) = (e.amount.to_string(), csv::escape(&e.asset));
+        let (sent, received) = match e.direction {
+            Direction::Out => ((amount.as_str(), asset.as_ref()), ("", "")),
+            Direction::In => (("", ""), (amount.as_str(), asset.as_ref())),
+        };
+        let value = usd(e.usd_value());
+        let description = match &e.memo {
+            Some(memo) => format!("{}: {memo}", e.purpose.as_str()),
+            None => e.purpose.as_str().to_string(),
+        };
+        writeln!(
+            out,
+            "{date},{},{},{},{},,,{value},{},{},{},{}",
+            sent.0,
+            sent.1,
+            received.0,
+            received.1,
+            if value.is_empty() { "" } else { "USD" },
+            e.purpose.koinly_label(),
+            csv::escape(&description),
+            e.signature,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,973 @@
+//! Token airdrops to long recipient lists, many transactions at a time.
+//!
+//! Sending one transaction and waiting for it before the next caps a large
+//! airdrop at a couple of transfers per second. Here each fee-payer wallet
+//! (see `fund`) owns several durable nonce accounts, and each nonce carries
+//! one transaction in flight: payers × nonces transactions are outstanding
+//! at once, each payer sending no faster than its rate. A transaction signed
+//! against a durable nonce doesn't expire, so one that hasn't landed is
+//! simply resent until it lands or fails; it can never land twice, because
+//! landing advances its nonce.
+//!
+//! Every transaction is written to a journal before it is sent, and its
+//! outcome after, so an interrupted airdrop picks up where it stopped
+//! without paying anyone twice.
+//!
+//! Recipients are sent a chunk at a time, read from the CSV as they are
+//! needed (see `recipients`), and the journal notes each finished chunk, so
+//! neither the list nor the journal is held in memory whole and lists of
+//! millions of rows are sent as easily as short ones.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::amount::TokenAmount;
+use crate::compute_budget;
+use crate::error::{Error, Result};
+use crate::explain::explain;
+use crate::extension;
+use crate::keypair::{Keypair, Signature};
+use crate::pack::Packer;
+use crate::pubkey::Pubkey;
+use crate::recipients::{Recipient, RecipientList};
+use crate::rpc::{RpcClient, SendOptions};
+use crate::signer::Signer;
+use crate::system::{self, NONCE_ACCOUNT_LEN, NonceState};
+use crate::token::{self, TokenAccount};
+use crate::tx::{
+    Hash, Instruction, LookupTable, Transaction, VersionedMessage, VersionedTransaction,
+};
+
+/// How often in-flight transactions are polled.
+const POLL: Duration = Duration::from_millis(400);
+/// How long a transaction goes unseen before it is sent again.
+const RESEND_AFTER: Duration = Duration::from_secs(3);
+/// How long a transaction goes unseen before its nonce is checked, to
+/// find out whether it can still land at all.
+const STALE_AFTER: Duration = Duration::from_secs(60);
+/// Sends refused by the RPC before a recipient is given up on.
+const MAX_ATTEMPTS: u32 = 3;
+/// Instructions paying one recipient: its account's creation and the
+/// transfer.
+const RECIPIENT_INSTRUCTIONS: usize = 2;
+const PROGRESS_EVERY: Duration = Duration::from_secs(5);
+/// Recipients read from the list and sent at a time.
+const CHUNK_ROWS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct EngineOptions {
+    /// Nonce accounts, and so transactions in flight, per fee payer.
+    pub nonces_per_payer: usize,
+    /// Transactions each fee payer sends per second at most.
+    pub rate_per_payer: f64,
+    /// Recipients per transaction at most; by default as many as fit.
+    pub max_per_tx: Option<usize>,
+    /// Tables holding recipients' wallets and token accounts, so more of
+    /// them fit in a transaction.
+    pub lookup_tables: Vec<LookupTable>,
+}
+
+/// What is being sent, from where.
+#[derive(Debug, Clone)]
+pub struct Airdrop {
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    /// Holds the tokens and signs every transfer.
+    pub authority: Pubkey,
+    pub source: Pubkey,
+    pub recipients: RecipientList,
+}
+
+impl Airdrop {
+    /// Validates the recipients in a `wallet,amount` CSV, amounts in whole
+    /// tokens as `draw` writes them, and checks `authority`'s associated
+    /// account of `mint` holds enough for them all.
+    pub fn prepare(
+        rpc: &RpcClient,
+        mint: &Pubkey,
+        authority: &Pubkey,
+        recipients_csv: &Path,
+    ) -> Result<Self> {
+        let info = token::fetch_mint(rpc, mint)?;
+        let recipients = RecipientList::scan(recipients_csv, info.mint.decimals)?;
+        if extension::find(&info.extensions, extension::TRANSFER_HOOK).is_some() {
+            return Err(Error::InvalidInput(format!(
+                "{mint} has a transfer hook; airdrops need the hook's extra accounts, \
+                 which are not resolved"
+            )));
+        }
+        let source = token::associated_token_address(authority, mint, &info.program_id);
+        let held = match rpc.get_account(&source)?.value {
+            Some(account) => TokenAccount::unpack(&account.data)?.amount as u128,
+            None => 0,
+        };
+        let airdrop = Airdrop {
+            mint: *mint,
+            program_id: info.program_id,
+            decimals: info.mint.decimals,
+            authority: *authority,
+            source,
+            recipients,
+        };
+        if held < airdrop.total().raw() {
+            return Err(Error::InvalidInput(format!(
+                "{source} holds {}, the airdrop sends {}",
+                TokenAmount::from_raw(held, airdrop.decimals),
+                airdrop.total()
+            )));
+        }
+        Ok(airdrop)
+    }
+
+    pub fn total(&self) -> TokenAmount {
+        self.recipients.total()
+    }
+
+    fn instructions(
+        &self,
+        payer: &Pubkey,
+        recipient: &Recipient,
+    ) -> Result<[Instruction; RECIPIENT_INSTRUCTIONS]> {
+        let destination =
+            token::associated_token_address(&recipient.wallet, &self.mint, &self.program_id);
+        Ok([
+            token::create_associated_token_account_idempotent(
+                payer,
+                &recipient.wallet,
+                &self.mint,
+                &self.program_id,
+            ),
+            token::transfer_checked(
+                &self.program_id,
+                &self.source,
+                &self.mint,
+                &destination,
+                &self.authority,
+                recipient.amount.to_u64()?,
+                self.decimals,
+            ),
+        ])
+    }
+}
+
+/// Derived from the fee payer's key, so the same nonces are found again on
+/// the next run and nothing secret is stored.
+pub fn nonce_keypair(payer: &Keypair, index: usize) -> Keypair {
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(b"launch-solana airdrop nonce")
+        .chain_update(payer.to_bytes())
+        .chain_update(index.to_le_bytes())
+        .finalize()
+        .into();
+    Keypair::from_seed(&seed)
+}
+
+fn nonce_keys(payers: &[Keypair], per_payer: usize) -> Vec<(usize, Pubkey)> {
+    payers
+        .iter()
+        .enumerate()
+        .flat_map(|(p, payer)| (0..per_payer).map(move |i| (p, nonce_keypair(payer, i).pubkey())))
+        .collect()
+}
+
+fn send_all(rpc: &RpcClient, txs: Vec<Transaction>) -> Result<()> {
+    thread::scope(|s| {
+        let handles: Vec<_> = txs
+            .iter()
+            .map(|tx| s.spawn(move || rpc.send_and_confirm(tx)))
+            .collect();
+        handles.into_iter().try_for_each(|handle| {
+            handle
+                .join()
+                .unwrap_or_else(|_| Err(Error::Http("sender thread panicked".into())))
+                .map(|_| ())
+        })
+    })
+}
+
+/// Creates whichever of each payer's first `per_payer` nonce accounts don't
+/// exist yet, paid by the payer. Returns how many were created.
+pub fn open_nonces(rpc: &RpcClient, payers: &[Keypair], per_payer: usize) -> Result<usize> {
+    let keys = nonce_keys(payers, per_payer);
+    let addresses: Vec<Pubkey> = keys.iter().map(|(_, k)| *k).collect();
+    let existing = rpc.get_multiple_accounts(&addresses, None)?.value;
+    let rent = rpc.get_minimum_balance_for_rent_exemption(NONCE_ACCOUNT_LEN)?;
+    let blockhash = rpc.get_latest_blockhash()?;
+    let mut txs = Vec::new();
+    let mut created = 0;
+    for (p, payer) in payers.iter().enumerate() {
+        let missing: Vec<Keypair> = (0..per_payer)
+            .map(|i| nonce_keypair(payer, i))
+            .filter(|nonce| {
+                let at = keys
+                    .iter()
+                    .position(|(q, k)| *q == p && *k == nonce.pubkey());
+                at.is_some_and(|at| existing[at].is_none())
+            })
+            .collect();
+        // Each new account signs too; four keep the transaction well in size.
+        for chunk in missing.chunks(4) {
+            let payer_key = payer.pubkey();
+            let ixs: Vec<Instruction> = chunk
+                .iter()
+                .flat_map(|n| {
+                    system::create_nonce_account(&payer_key, &n.pubkey(), &payer_key, rent)
+                })
+                .collect();
+            let mut signers = vec![payer];
+            signers.extend(chunk);
+            let (tx, _) = rpc.sign_simulated(&ixs, &payer_key, blockhash, &signers)?;
+            txs.push(tx);
+            created += chunk.len();
+        }
+    }
+    send_all(rpc, txs)?;
+    Ok(created)
+}
+
+/// Closes every payer's first `per_payer` nonce accounts, returning their
+/// rent to the payer. Returns the lamports reclaimed.
+pub fn close_nonces(rpc: &RpcClient, payers: &[Keypair], per_payer: usize) -> Result<u64> {
+    let keys = nonce_keys(payers, per_payer);
+    let addresses: Vec<Pubkey> = keys.iter().map(|(_, k)| *k).collect();
+    let accounts = rpc.get_multiple_accounts(&addresses, None)?.value;
+    let blockhash = rpc.get_latest_blockhash()?;
+    let mut txs = Vec::new();
+    let mut reclaimed = 0;
+    for (p, payer) in payers.iter().enumerate() {
+        let payer_key = payer.pubkey();
+        let ixs: Vec<Instruction> = keys
+            .iter()
+            .zip(&accounts)
+            .filter(|((q, _), _)| *q == p)
+            .filter_map(|((_, nonce), account)| {
+                let lamports = account.as_ref()?.lamports;
+                reclaimed += lamports;
+                Some(system::withdraw_nonce_account(
+                    nonce, &payer_key, &payer_key, lamports,
+                ))
+            })
+            .collect();
+        for chunk in ixs.chunks(8) {
+            let (tx, _) = rpc.sign_simulated(chunk, &payer_key, blockhash, &[payer])?;
+            txs.push(tx);
+        }
+    }
+    send_all(rpc, txs)?;
+    Ok(reclaimed)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JournalEntry {
+    Started {
+        mint: Pubkey,
+        authority: Pubkey,
+        /// Digest of the recipients CSV; rows are counted by position, so
+        /// the list must not change under a journal.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        recipients: Option<String>,
+    },
+    Sent {
+        signature: Signature,
+        wallets: Vec<Pubkey>,
+        nonce: Pubkey,
+        nonce_value: String,
+        /// The signed transaction, base64, to resend after a restart.
+        wire: String,
+    },
+    Confirmed {
+        signature: Signature,
+    },
+    Failed {
+        signature: Signature,
+        error: String,
+    },
+    /// The transaction can no longer land; its recipients are sent again.
+    Dropped {
+        signature: Signature,
+    },
+    /// Every recipient in the list's first `rows` rows is paid or reported
+    /// failed, and nothing sent before is still in flight.
+    Checkpoint {
+        rows: usize,
+    },
+}
+
+/// A transaction sent by an earlier run whose outcome was never written.
+#[derive(Debug, Clone)]
+struct Unresolved {
+    signature: Signature,
+    wallets: Vec<Pubkey>,
+    nonce: Pubkey,
+    nonce_value: Hash,
+    wire: Vec<u8>,
+}
+
+/// The airdrop's append-only record of sent transactions and outcomes.
+/// Only what follows its last checkpoint is kept in memory.
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    /// Rows settled as of the last checkpoint.
+    settled: usize,
+    /// Wallets paid since the last checkpoint.
+    done: HashSet<Pubkey>,
+    unresolved: Vec<Unresolved>,
+}
+
+impl Journal {
+    /// `<recipients file>.airdrop.jsonl`, next to the list it tracks.
+    pub fn default_path(recipients: &Path) -> PathBuf {
+        let mut name = recipients.as_os_str().to_owned();
+        name.push(".airdrop.jsonl");
+        PathBuf::from(name)
+    }
+
+    /// Opens or starts the journal of `airdrop`.
+    pub fn open(path: &Path, airdrop: &Airdrop) -> Result<Self> {
+        let (mint, authority) = (&airdrop.mint, &airdrop.authority);
+        let digest = airdrop.recipients.digest();
+        let mut sent: HashMap<Signature, Unresolved> = HashMap::new();
+        let mut order = Vec::new();
+        let mut settled = 0;
+        let mut done = HashSet::new();
+        let mut started = false;
+        if let Ok(file) = File::open(path) {
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry: JournalEntry = serde_json::from_str(&line).map_err(|e| {
+                    Error::InvalidInput(format!("{} line {}: {e}", path.display(), i + 1))
+                })?;
+                match entry {
+                    JournalEntry::Started {
+                        mint: m,
+                        authority: a,
+                        recipients,
+                    } => {
+                        if m != *mint || a != *authority {
+                            return Err(Error::InvalidInput(format!(
+                                "{} is the journal of an airdrop of {m} from {a}",
+                                path.display()
+                            )));
+                        }
+                        if recipients.is_some_and(|r| r != digest) {
+                            return Err(Error::InvalidInput(format!(
+                                "{} changed since {} was started; restore it or start \
+                                 a new journal",
+                                airdrop.recipients.path().display(),
+                                path.display()
+                            )));
+                        }
+                        started = true;
+                    }
+                    JournalEntry::Sent {
+                        signature,
+                        wallets,
+                        nonce,
+                        nonce_value,
+                        wire,
+                    } => {
+                        let wire = BASE64
+                            .decode(&wire)
+                            .map_err(|e| Error::InvalidInput(format!("journal: {e}")))?;
+                        order.push(signature);
+                        sent.insert(
+                            signature,
+                            Unresolved {
+                                signature,
+                                wallets,
+                                nonce,
+                                nonce_value: nonce_value.parse()?,
+                                wire,
+                            },
+                        );
+                    }
+                    JournalEntry::Confirmed { signature } => {
+                        if let Some(tx) = sent.remove(&signature) {
+                            done.extend(tx.wallets);
+                        }
+                    }
+                    JournalEntry::Failed { signature, .. }
+                    | JournalEntry::Dropped { signature } => {
+                        sent.remove(&signature);
+                    }
+                    JournalEntry::Checkpoint { rows } => {
+                        settled = rows;
+                        done.clear();
+                        sent.clear();
+                        order.clear();
+                    }
+                }
+            }
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut journal = Journal {
+            path: path.to_path_buf(),
+            file,
+            settled,
+            done,
+            unresolved: order.iter().filter_map(|s| sent.remove(s)).collect(),
+        };
+        if !started {
+            journal.write(&JournalEntry::Started {
+                mint: *mint,
+                authority: *authority,
+                recipients: Some(digest.to_string()),
+            })?;
+        }
+        Ok(journal)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Recipients earlier runs finished with, paid or reported failed.
+    pub fn finished(&self) -> usize {
+        self.settled + self.done.len()
+    }
+
+    fn checkpoint(&mut self, rows: usize) -> Result<()> {
+        self.write(&JournalEntry::Checkpoint { rows })?;
+        self.settled = rows;
+        self.done.clear();
+        Ok(())
+    }
+
+    fn write(&mut self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedRecipient {
+    pub wallet: Pubkey,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AirdropReport {
+    pub recipients: usize,
+    /// Finished by an earlier run, per the journal.
+    pub already_done: usize,
+    pub confirmed: usize,
+    pub failed: Vec<FailedRecipient>,
+    pub transactions: usize,
+    pub elapsed_secs: f64,
+}
+
+struct Flight {
+    signature: Signature,
+    recipients: Vec<usize>,
+    wire: Vec<u8>,
+    nonce_value: Hash,
+    first_sent: Instant,
+    last_sent: Instant,
+}
+
+/// Why a simulation refused a batch.
+struct Refusal {
+    reason: String,
+    /// Position in the batch of the recipient whose instruction failed,
+    /// when the error names one of theirs.
+    recipient: Option<usize>,
+}
+
+struct Slot {
+    payer: usize,
+    nonce: Pubkey,
+    flight: Option<Flight>,
+}
+
+struct Dispatcher<'a> {
+    rpc: &'a RpcClient,
+    airdrop: &'a Airdrop,
+    authority: &'a dyn Signer,
+    payers: &'a [Keypair],
+    options: EngineOptions,
+    journal: &'a mut Journal,
+    /// The chunk being sent; indexes below refer to it.
+    recipients: Vec<Recipient>,
+    slots: Vec<Slot>,
+    queue: VecDeque<usize>,
+    attempts: Vec<u32>,
+    next_send: Vec<Instant>,
+    report: AirdropReport,
+}
+
+fn fetch_nonces(rpc: &RpcClient, addresses: &[Pubkey]) -> Result<Vec<Hash>> {
+    rpc.get_multiple_accounts(addresses, None)?
+        .value
+        .into_iter()
+        .zip(addresses)
+        .map(|(account, address)| {
+            let account = account.ok_or(Error::AccountNotFound(*address))?;
+            Ok(NonceState::unpack(&account.data)?.nonce)
+        })
+        .collect()
+}
+
+impl Dispatcher<'_> {
+    /// Settles what an earlier run left in flight, before anything new is
+    /// sent: landed transactions count, dead ones are requeued and live ones
+    /// are taken up again.
+    fn resume(&mut self) -> Result<HashSet<usize>> {
+        let unresolved = std::mem::take(&mut self.journal.unresolved);
+        let mut held = HashSet::new();
+        if unresolved.is_empty() {
+            return Ok(held);
+        }
+        eprintln!(
+            "checking {} transaction(s) left in flight by the last run...",
+            unresolved.len()
+        );
+        let signatures: Vec<Signature> = unresolved.iter().map(|u| u.signature).collect();
+        let statuses = self.rpc.get_signature_statuses(&signatures, true)?;
+        let nonces: Vec<Pubkey> = unresolved.iter().map(|u| u.nonce).collect();
+        let current = self.rpc.get_multiple_accounts(&nonces, None)?.value;
+        let index: HashMap<Pubkey, usize> = self
+            .recipients
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r.wallet, i))
+            .collect();
+        for ((tx, status), nonce) in unresolved.into_iter().zip(statuses).zip(current) {
+            let recipients: Vec<usize> = tx
+                .wallets
+                .iter()
+                .filter_map(|w| index.get(w).copied())
+                .collect();
+            match status {
+                // Failed last time; its recipients are queued again below.
+                Some(status) if status.err.is_some() => {
+                    self.journal.write(&JournalEntry::Failed {
+                        signature: tx.signature,
+                        error: status.err.map(|e| e.to_string()).unwrap_or_default(),
+                    })?;
+                }
+                Some(_) => {
+                    self.confirm(tx.signature, &recipients)?;
+                    self.journal.done.extend(&tx.wallets);
+                }
+                None => {
+                    let still_valid = nonce
+                        .and_then(|a| NonceState::unpack(&a.data).ok())
+                        .is_some_and(|state| state.nonce == tx.nonce_value);
+                    let slot = self.slots.iter().position(|s| s.nonce == tx.nonce);
+                    match (still_valid, slot) {
+                        (false, _) => {
+                            self.journal.write(&JournalEntry::Dropped {
+                                signature: tx.signature,
+                            })?;
+                        }
+                        (true, Some(slot)) => {
+                            held.extend(&recipients);
+                            let now = Instant::now();
+                            self.slots[slot].flight = Some(Flight {
+                                signature: tx.signature,
+                                recipients,
+                                wire: tx.wire,
+                                nonce_value: tx.nonce_value,
+                                first_sent: now,
+                                last_sent: now - RESEND_AFTER,
+                            });
+                        }
+                        (true, None) => {
+                            return Err(Error::InvalidInput(format!(
+                                "transaction {} of the last run can still land through nonce {}, \
+                                 which these fee payers don't own; rerun with the same \
+                                 payers and --nonces-per-payer",
+                                tx.signature, tx.nonce
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(held)
+    }
+
+    fn confirm(&mut self, signature: Signature, recipients: &[usize]) -> Result<()> {
+        self.journal.write(&JournalEntry::Confirmed { signature })?;
+        self.journal
+            .done
+            .extend(recipients.iter().map(|&i| self.recipients[i].wallet));
+        self.report.confirmed += recipients.len();
+        self.report.transactions += 1;
+        Ok(())
+    }
+
+    fn fail(&mut self, signature: Signature, recipients: &[usize], error: &str) -> Result<()> {
+        self.journal.write(&JournalEntry::Failed {
+            signature,
+            error: error.to_string(),
+        })?;
+        for &i in recipients {
+            self.report.failed.push(FailedRecipient {
+                wallet: self.recipients[i].wallet,
+                error: error.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Recipients for one transaction, as many as fit, and its message
+    /// with the compute unit limit set to what a simulation of it consumed,
+    /// or why the simulation refused it. Room for the limit is kept while
+    /// packing, right after the nonce advance that must come first.
+    fn take_batch(
+        &mut self,
+        payer: &Pubkey,
+        nonce: &Pubkey,
+        nonce_value: Hash,
+    ) -> Result<(Vec<usize>, VersionedMessage, Option<Refusal>)> {
+        let prefix = vec![
+            system::advance_nonce_account(nonce, payer),
+            compute_budget::set_compute_unit_limit(compute_budget::MAX_COMPUTE_UNIT_LIMIT),
+        ];
+        let prefix_len = prefix.len();
+        let packer = Packer::new(*payer)
+            .with_prefix(prefix)
+            .with_lookup_tables(&self.options.lookup_tables)
+            .with_max_items(self.options.max_per_tx);
+        let mut batch = packer.batch();
+        while let Some(&next) = self.queue.front() {
+            let group = self
+                .airdrop
+                .instructions(payer, &self.recipients[next])?
+                .to_vec();
+            if batch.push(next, group)?.is_err() {
+                break;
+            }
+            self.queue.pop_front();
+        }
+        let (recipients, instructions) = batch.into_parts();
+        let probe = packer.message(&instructions, nonce_value);
+        let simulation = self
+            .rpc
+            .simulate_versioned_transaction(&VersionedTransaction::new_unsigned(probe.clone()))?;
+        if let Some(err) = simulation.err {
+            let logs = simulation.logs.unwrap_or_default();
+            let explanation = explain(&err, &logs, self.rpc.programs(), self.rpc.labels());
+            let recipient = explanation
+                .instruction
+                .and_then(|i| usize::from(i).checked_sub(prefix_len))
+                .map(|i| i / RECIPIENT_INSTRUCTIONS)
+                .filter(|&at| at < recipients.len());
+            let refusal = Refusal {
+                reason: explanation.to_string(),
+                recipient,
+            };
+            return Ok((recipients, probe, Some(refusal)));
+        }
+        let message = match simulation.units_consumed {
+            Some(units) => packer.message(
+                &compute_budget::with_compute_unit_limit(
+                    &instructions,
+                    compute_budget::measured_limit(units),
+                ),
+                nonce_value,
+            ),
+            None => probe,
+        };
+        Ok((recipients, message, None))
+    }
+
+    /// Puts back a batch its simulation refused. Nothing was sent, so
+    /// nothing is journalled. Only the recipient whose instruction failed
+    /// is charged an attempt and goes to the back of the queue; the others
+    /// go back to the front as they were. A refusal that blames no
+    /// recipient, such as the payer running short, charges them all.
+    fn retry_refused(&mut self, recipients: &[usize], refusal: Refusal) {
+        let charged = match refusal.recipient {
+            Some(at) => vec![recipients[at]],
+            None => recipients.to_vec(),
+        };
+        for &i in recipients.iter().rev() {
+            if !charged.contains(&i) {
+                self.queue.push_front(i);
+            }
+        }
+        for i in charged {
+            self.attempts[i] += 1;
+            if self.attempts[i] >= MAX_ATTEMPTS {
+                self.report.failed.push(FailedRecipient {
+                    wallet: self.recipients[i].wallet,
+                    error: refusal.reason.clone(),
+                });
+            } else {
+                self.queue.push_back(i);
+            }
+        }
+    }
+
+    /// Puts back the recipients of a sent transaction that can no longer
+    /// land, giving up on those out of attempts.
+    fn requeue(&mut self, recipients: &[usize], signature: Signature, error: &str) -> Result<()> {
+        let mut given_up = Vec::new();
+        for &i in recipients {
+            self.attempts[i] += 1;
+            if self.attempts[i] >= MAX_ATTEMPTS {
+                given_up.push(i);
+            } else {
+                self.queue.push_back(i);
+            }
+        }
+        if given_up.is_empty() {
+            self.journal.write(&JournalEntry::Dropped { signature })
+        } else {
+            // Recipients still queued are recorded again when resent.
+            self.fail(signature, &given_up, error)
+        }
+    }
+
+    /// Sends a transaction through every free slot whose payer may send.
+    fn fill(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let mut ready: Vec<usize> = Vec::new();
+        let mut payers_taken = HashSet::new();
+        for (i, slot) in self.slots.iter().enumerate() {
+            if slot.flight.is_none()
+                && self.next_send[slot.payer] <= now
+                && payers_taken.insert(slot.payer)
+            {
+                ready.push(i);
+            }
+        }
+        if ready.is_empty() || self.queue.is_empty() {
+            return Ok(());
+        }
+        let addresses: Vec<Pubkey> = ready.iter().map(|&i| self.slots[i].nonce).collect();
+        let values = fetch_nonces(self.rpc, &addresses)?;
+        let interval = Duration::from_secs_f64(1.0 / self.options.rate_per_payer);
+        for (slot_index, nonce_value) in ready.into_iter().zip(values) {
+            if self.queue.is_empty() {
+                break;
+            }
+            let payer = &self.payers[self.slots[slot_index].payer];
+            let payer_key = payer.pubkey();
+            let nonce = self.slots[slot_index].nonce;
+            let (batch, message, refused) = self.take_batch(&payer_key, &nonce, nonce_value)?;
+            // Never signed or sent, so the nonce is unused.
+            if let Some(refusal) = refused {
+                self.retry_refused(&batch, refusal);
+                continue;
+            }
+            let mut tx = VersionedTransaction::new_unsigned(message);
+            let mut signers: Vec<&dyn Signer> = vec![payer];
+            if self.authority.pubkey() != payer_key {
+                signers.push(self.authority);
+            }
+            tx.sign(&signers)?;
+            let wire = tx.serialize();
+            let signature = tx.signature();
+            self.journal.write(&JournalEntry::Sent {
+                signature,
+                wallets: batch.iter().map(|&i| self.recipients[i].wallet).collect(),
+                nonce,
+                nonce_value: nonce_value.to_string(),
+                wire: BASE64.encode(&wire),
+            })?;
+            self.next_send[self.slots[slot_index].payer] = now + interval;
+            match self.rpc.send_raw_transaction(&wire) {
+                Ok(_) => {
+                    self.slots[slot_index].flight = Some(Flight {
+                        signature,
+                        recipients: batch,
+                        wire,
+                        nonce_value,
+                        first_sent: now,
+                        last_sent: now,
+                    });
+                }
+                // Refused in preflight, so never forwarded and the nonce is
+                // unused.
+                Err(e) => self.requeue(&batch, signature, &e.to_string())?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the outcomes of in-flight transactions and resends the
+    /// ones not seen yet.
+    fn poll(&mut self) -> Result<()> {
+        let flying: Vec<usize> = (0..self.slots.len())
+            .filter(|&i| self.slots[i].flight.is_some())
+            .collect();
+        if flying.is_empty() {
+            return Ok(());
+        }
+        let signatures: Vec<Signature> = flying
+            .iter()
+            .map(|&i| self.slots[i].flight.as_ref().unwrap().signature)
+            .collect();
+        let statuses = self.rpc.get_signature_statuses(&signatures, false)?;
+        let commitment = self.rpc.commitment();
+        let now = Instant::now();
+        for (slot_index, status) in flying.into_iter().zip(statuses) {
+            match status {
+                Some(status) if status.err.is_some() => {
+                    let flight = self.slots[slot_index].flight.take().unwrap();
+                    let error = status.err.map(|e| e.to_string()).unwrap_or_default();
+                    self.fail(flight.signature, &flight.recipients, &error)?;
+                    self.report.transactions += 1;
+                }
+                Some(status) if status.reached(commitment) => {
+                    let flight = self.slots[slot_index].flight.take().unwrap();
+                    self.confirm(flight.signature, &flight.recipients)?;
+                }
+                Some(_) => {}
+                None => {
+                    let slot = &self.slots[slot_index];
+                    let flight = slot.flight.as_ref().unwrap();
+                    if now.duration_since(flight.first_sent) >= STALE_AFTER {
+                        let value = fetch_nonces(self.rpc, &[slot.nonce])?.pop();
+                        if value != Some(flight.nonce_value) {
+                            // Advanced by something else, or by this
+                            // transaction an instant ago; ask once more.
+                            let landed =
+                                self.rpc.get_signature_statuses(&[flight.signature], true)?;
+                            let flight = self.slots[slot_index].flight.take().unwrap();
+                            match landed.into_iter().next().flatten() {
+                                Some(status) if status.err.is_none() => {
+                                    self.confirm(flight.signature, &flight.recipients)?
+                                }
+                                Some(status) => {
+                                    let error =
+                                        status.err.map(|e| e.to_string()).unwrap_or_default();
+                                    self.fail(flight.signature, &flight.recipients, &error)?
+                                }
+                                None => self.requeue(
+                                    &flight.recipients,
+                                    flight.signature,
+                                    "nonce advanced without the transaction",
+                                )?,
+                            }
+                            continue;
+                        }
+                    }
+                    let flight = self.slots[slot_index].flight.as_mut().unwrap();
+                    if now.duration_since(flight.last_sent) >= RESEND_AFTER {
+                        // Refused resends are expected once it has landed.
+                        let _ = self.rpc.send_raw_transaction_with(
+                            &flight.wire,
+                            SendOptions {
+                                skip_preflight: true,
+                                max_retries: None,
+                            },
+                        );
+                        flight.last_sent = now;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn in_flight(&self) -> usize {
+        self.slots.iter().filter(|s| s.flight.is_some()).count()
+    }
+}
+
+/// Sends the airdrop. `payers` pay fees and the rent of new token accounts;
+/// their nonce accounts must exist (see [`open_nonces`]). Recipients the
+/// journal shows as paid are skipped.
+pub fn run(
+    rpc: &RpcClient,
+    airdrop: &Airdrop,
+    authority: &dyn Signer,
+    payers: &[Keypair],
+    options: EngineOptions,
+    journal: &mut Journal,
+) -> Result<AirdropReport> {
+    if payers.is_empty() || options.nonces_per_payer == 0 {
+        return Err(Error::InvalidInput(
+            "an airdrop needs at least one fee payer and nonce".into(),
+        ));
+    }
+    if options.rate_per_payer <= 0.0 || options.max_per_tx == Some(0) {
+        return Err(Error::InvalidInput(
+            "rate and recipients per transaction must be positive".into(),
+        ));
+    }
+    let started = Instant::now();
+    let slots = nonce_keys(payers, options.nonces_per_payer)
+        .into_iter()
+        .map(|(payer, nonce)| Slot {
+            payer,
+            nonce,
+            flight: None,
+        })
+        .collect();
+    let already_done = journal.finished();
+    let mut rows = journal.settled;
+    let mut engine = Dispatcher {
+        rpc,
+        airdrop,
+        authority,
+        payers,
+        options,
+        journal,
+        recipients: Vec::new(),
+        slots,
+        queue: VecDeque::new(),
+        attempts: Vec::new(),
+        next_send: vec![started; payers.len()],
+        report: AirdropReport {
+            recipients: airdrop.recipients.len(),
+            already_done,
+            confirmed: 0,
+            failed: Vec::new(),
+            transactions: 0,
+            elapsed_secs: 0.0,
+        },
+    };
+    let mut last_progress = Instant::now();
+    for chunk in airdrop.recipients.chunks(rows, CHUNK_ROWS)? {
+        engine.recipients = chunk?;
+        engine.attempts = vec![0; engine.recipients.len()];
+        rows += engine.recipients.len();
+        // Whatever the last run left in flight belongs to the first chunk.
+        let held = engine.resume()?;
+        let done = &engine.journal.done;
+        engine.queue = (0..engine.recipients.len())
+            .filter(|i| !held.contains(i) && !done.contains(&engine.recipients[*i].wallet))
+            .collect();
+        while !engine.queue.is_empty() || engine.in_flight() > 0 {
+            engine.fill()?;
+            thread::sleep(POLL);
+            engine.poll()?;
+            if last_progress.elapsed() >= PROGRESS_EVERY {
+                eprintln!(
+                    "  {} paid, {} failed, {} queued, {} transaction(s) in flight",
+                    engine.report.already_done + engine.report.confirmed,
+                    engine.report.failed.len(),
+                    engine.queue.len() + airdrop.recipients.len() - rows,
+                    engine.in_flight()
+                );
+                last_progress = Instant::now();
+            }
+        }
+        engine.journal.checkpoint(rows)?;
+    }
+    engine.report.elapsed_secs = started.elapsed().as_secs_f64();
+    Ok(engine.report)
+}
@@ -0,0 +1,337 @@
+//! Token amounts in base units. Amounts are never converted through floats:
+//! parsing, arithmetic and formatting all work on integers with the mint's
+//! decimals carried alongside.
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+
+/// 100% expressed in basis points.
+pub const BPS_DENOMINATOR: u32 = 10_000;
+
+/// Largest number of decimals an SPL mint can have that still fits a u64 of 1.
+pub const MAX_DECIMALS: u8 = 19;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenAmount {
+    raw: u128,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    pub const fn from_raw(raw: u128, decimals: u8) -> Self {
+        TokenAmount { raw, decimals }
+    }
+
+    pub const fn zero(decimals: u8) -> Self {
+        TokenAmount { raw: 0, decimals }
+    }
+
+    /// Parses a human amount such as `1_000_000` or `12.5` into base units.
+    /// Separators `_` and `,` are ignored; more fractional digits than the
+    /// mint supports is an error rather than a silent rounding.
+    pub fn parse(input: &str, decimals: u8) -> Result<Self> {
+        let cleaned: String = input
+            .trim()
+            .chars()
+            .filter(|c| *c != '_' && *c != ',')
+            .collect();
+        let invalid = || Error::InvalidInput(format!("'{input}' is not a valid token amount"));
+        let (whole, frac) = cleaned.split_once('.').unwrap_or((&cleaned, ""));
+        if whole.is_empty() && frac.is_empty() {
+            return Err(invalid());
+        }
+        if !whole
+            .chars()
+            .chain(frac.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        if frac.len() > decimals as usize {
+            return Err(Error::InvalidInput(format!(
+                "'{input}' has more than {decimals} decimal places"
+            )));
+        }
+        let scale = 10u128
+            .checked_pow(decimals as u32)
+            .ok_or_else(|| Error::InvalidInput(format!("{decimals} decimals is out of range")))?;
+        let whole: u128 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().map_err(|_| invalid())?
+        };
+        let frac_raw: u128 = if frac.is_empty() {
+            0
+        } else {
+            frac.parse::<u128>().map_err(|_| invalid())?
+                * 10u128.pow((decimals as usize - frac.len()) as u32)
+        };
+        let raw = whole
+            .checked_mul(scale)
+            .and_then(|w| w.checked_add(frac_raw))
+            .ok_or_else(|| Error::InvalidInput(format!("'{input}' is too large")))?;
+        Ok(TokenAmount { raw, decimals })
+    }
+
+    pub fn raw(self) -> u128 {
+        self.raw
+    }
+
+    pub fn decimals(self) -> u8 {
+        self.decimals
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.raw == 0
+    }
+
+    /// The amount as the u64 that token instructions take.
+    pub fn to_u64(self) -> Result<u64> {
+        u64::try_from(self.raw).map_err(|_| {
+            Error::InvalidInput(format!(
+                "{self} exceeds the largest amount a token account can hold ({})",
+                TokenAmount::from_raw(u64::MAX as u128, self.decimals)
+            ))
+        })
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        (self.decimals == other.decimals).then_some(())?;
+        Some(TokenAmount::from_raw(
+            self.raw.checked_add(other.raw)?,
+            self.decimals,
+        ))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        (self.decimals == other.decimals).then_some(())?;
+        Some(TokenAmount::from_raw(
+            self.raw.checked_sub(other.raw)?,
+            self.decimals,
+        ))
+    }
+
+    pub fn checked_mul(self, factor: u128) -> Option<Self> {
+        Some(TokenAmount::from_raw(
+            self.raw.checked_mul(factor)?,
+            self.decimals,
+        ))
+    }
+
+    /// `bps` basis points of this amount, rounded down.
+    pub fn checked_bps(self, bps: u32) -> Option<Self> {
+        let raw = self.raw.checked_mul(bps as u128)? / BPS_DENOMINATOR as u128;
+        Some(TokenAmount::from_raw(raw, self.decimals))
+    }
+
+    /// Splits the amount by basis-point shares. Rounding dust goes to the
+    /// first share so the parts always add up exactly to the allocated total.
+    pub fn split_bps(self, shares: &[u32]) -> Result<Vec<Self>> {
+        let total_bps: u64 = shares.iter().map(|&s| s as u64).sum();
+        if total_bps > BPS_DENOMINATOR as u64 {
+            return Err(Error::InvalidInput(format!(
+                "shares add up to {}, more than 100%",
+                format_bps(total_bps as u32)
+            )));
+        }
+        let overflow = || Error::InvalidInput(format!("{self} is too large to split"));
+        let mut parts = shares
+            .iter()
+            .map(|&bps| self.checked_bps(bps).ok_or_else(overflow))
+            .collect::<Result<Vec<_>>>()?;
+        let allocated = self.checked_bps(total_bps as u32).ok_or_else(overflow)?;
+        let distributed: u128 = parts.iter().map(|p| p.raw).sum();
+        if let Some(first) = parts.first_mut() {
+            first.raw += allocated.raw - distributed;
+        }
+        Ok(parts)
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = format!("{:0>width$}", self.raw, width = self.decimals as usize + 1);
+        let (whole, frac) = digits.split_at(digits.len() - self.decimals as usize);
+        let frac = frac.trim_end_matches('0');
+        if frac.is_empty() {
+            f.pad(whole)
+        } else {
+            f.pad(&format!("{whole}.{frac}"))
+        }
+    }
+}
+
+/// Serialized like the RPC's `UiTokenAmount`: base units as a string so
+/// large values survive JSON parsers that use doubles.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmountRepr {
+    amount: String,
+    decimals: u8,
+    #[serde(default, skip_deserializing)]
+    ui_amount_string: String,
+}
+
+impl Serialize for TokenAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        AmountRepr {
+            amount: self.raw.to_string(),
+            decimals: self.decimals,
+            ui_amount_string: self.to_string(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TokenAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let repr = AmountRepr::deserialize(deserializer)?;
+        let raw = repr.amount.parse().map_err(serde::de::Error::custom)?;
+        Ok(TokenAmount::from_raw(raw, repr.decimals))
+    }
+}
+
+/// Decimals most wallets and exchanges handle; SPL tokens usually use 6 or 9.
+pub const USUAL_MAX_DECIMALS: u8 = 9;
+/// How many times the supply must fit under the u64 limit before it counts
+/// as comfortably clear of it.
+const HEADROOM_FACTOR: u128 = 1_000;
+
+/// Guidance on a supply's choice of decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecimalsAdvice {
+    /// No decimals: the token can't be divided.
+    Indivisible,
+    /// More decimals than most wallets and exchanges expect.
+    Unusual(u8),
+    /// The supply in base units is within [`HEADROOM_FACTOR`] of the u64
+    /// limit; `factor` is how many times it fits, `limit` the limit.
+    LittleHeadroom { factor: u128, limit: TokenAmount },
+}
+
+impl fmt::Display for DecimalsAdvice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecimalsAdvice::Indivisible => f.write_str(
+                "0 decimals: the token can't be divided, so every transfer, \
+                 airdrop share and fee is a whole number of tokens",
+            ),
+            DecimalsAdvice::Unusual(decimals) => write!(
+                f,
+                "{decimals} decimals: most wallets and exchanges expect at most \
+                 {USUAL_MAX_DECIMALS} and may round or refuse the token"
+            ),
+            DecimalsAdvice::LittleHeadroom { factor, limit } => write!(
+                f,
+                "the supply in base units is {} of the u64 limit; no later mint \
+                 can take the total past {limit} tokens",
+                if *factor < 2 {
+                    "over half".to_string()
+                } else {
+                    format!("1/{factor}")
+                }
+            ),
+        }
+    }
+}
+
+/// The most decimals, below `supply`'s own, at which `supply` still fits
+/// a u64 and keeps all its fractional digits.
+fn fitting_decimals(supply: TokenAmount) -> Option<u8> {
+    (0..supply.decimals).rev().find(|&d| {
+        let scale = 10u128.pow((supply.decimals - d) as u32);
+        supply.raw.is_multiple_of(scale) && supply.raw / scale <= u64::MAX as u128
+    })
+}
+
+/// Checks a launch's supply and decimals: an error if the supply can't be
+/// minted, otherwise whatever advice applies.
+pub fn check_decimals(supply: TokenAmount) -> Result<Vec<DecimalsAdvice>> {
+    let limit = TokenAmount::from_raw(u64::MAX as u128, supply.decimals);
+    if supply > limit {
+        let fix = match fitting_decimals(supply) {
+            Some(d) => format!("use at most {d} decimals or a smaller supply"),
+            None => "use a smaller supply".to_string(),
+        };
+        return Err(Error::InvalidInput(format!(
+            "with {} decimals a supply of {supply} is {} base units, more than a u64 \
+             holds (at most {limit} tokens); {fix}",
+            supply.decimals, supply.raw
+        )));
+    }
+    let mut advice = Vec::new();
+    if supply.decimals == 0 {
+        advice.push(DecimalsAdvice::Indivisible);
+    } else if supply.decimals > USUAL_MAX_DECIMALS {
+        advice.push(DecimalsAdvice::Unusual(supply.decimals));
+    }
+    if let Some(factor) = (u64::MAX as u128).checked_div(supply.raw)
+        && factor < HEADROOM_FACTOR
+    {
+        advice.push(DecimalsAdvice::LittleHeadroom { factor, limit });
+    }
+    Ok(advice)
+}
+
+/// Parses a percentage like `15`, `15%` or `2.5%` into basis points.
+pub fn parse_percent_bps(input: &str) -> Result<u32> {
+    let trimmed = input.trim().trim_end_matches('%');
+    let amount = TokenAmount::parse(trimmed, 2).map_err(|_| {
+        Error::InvalidInput(format!(
+            "'{input}' is not a percentage with at most two decimals"
+        ))
+    })?;
+    let bps = u32::try_from(amount.raw())
+        .ok()
+        .filter(|&bps| bps <= BPS_DENOMINATOR)
+        .ok_or_else(|| Error::InvalidInput(format!("'{input}' is more than 100%")))?;
+    Ok(bps)
+}
+
+pub fn format_bps(bps: u32) -> String {
+    format!("{}%", TokenAmount::from_raw(bps as u128, 2))
+}
+
+/// One row of a supply allocation table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allocation {
+    pub label: String,
+    pub share_bps: u32,
+}
+
+impl Allocation {
+    /// Parses `label=share`, e.g. `team=15%`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let (label, share) = input.split_once('=').ok_or_else(|| {
+            Error::InvalidInput(format!("'{input}' is not in label=percent form"))
+        })?;
+        Ok(Allocation {
+            label: label.trim().to_string(),
+            share_bps: parse_percent_bps(share)?,
+        })
+    }
+}
+
+/// Splits `supply` across the table; unallocated remainder is returned last.
+pub fn allocate(supply: TokenAmount, table: &[Allocation]) -> Result<Vec<(String, TokenAmount)>> {
+    let shares: Vec<u32> = table.iter().map(|a| a.share_bps).collect();
+    let parts = supply.split_bps(&shares)?;
+    let mut rows: Vec<(String, TokenAmount)> =
+        table.iter().map(|a| a.label.clone()).zip(parts).collect();
+    let allocated = rows
+        .iter()
+        .try_fold(TokenAmount::zero(supply.decimals()), |acc, (_, p)| {
+            acc.checked_add(*p)
+        })
+        .ok_or_else(|| Error::InvalidInput("allocation total overflowed".into()))?;
+    let remainder = supply
+        .checked_sub(allocated)
+        .ok_or_else(|| Error::InvalidInput("allocations exceed supply".into()))?;
+    if !remainder.is_zero() {
+        rows.push(("(unallocated)".into(), remainder));
+    }
+    Ok(rows)
+}
@@ -0,0 +1,16 @@
+//! Anchor program conventions shared by the Anchor-built programs this
+//! crate talks to (Raydium CPMM, Squads v4).
+
+use sha2::{Digest, Sha256};
+
+/// Anchor instruction discriminator: first 8 bytes of `sha256("global:<name>")`.
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{name}"));
+    hash[..8].try_into().unwrap()
+}
+
+/// Anchor account discriminator: first 8 bytes of `sha256("account:<Name>")`.
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("account:{name}"));
+    hash[..8].try_into().unwrap()
+}
@@ -0,0 +1,259 @@
+//! An append-only, hash-chained log of every transaction this tool signs.
+//!
+//! Each line records a message's SHA-256, the keys that signed it, when and
+//! for which command, along with the previous line's hash and its own:
+//!
+//! ```text
+//! {"seq":1,"time":"2024-05-01T12:00:00Z","purpose":"launch","message_hash":"9c1e…","signers":["7xKX…"],"signature":"5VER…","prev_hash":"0000…","hash":"41d7…"}
+//! ```
+//!
+//! A line's hash covers every other field, `prev_hash` included, so editing,
+//! removing or reordering lines breaks the chain from that point on, which
+//! [`verify`] reports. Truncating the newest lines can only be detected
+//! against a hash noted elsewhere, such as the one `verify-audit-log` prints.
+//!
+//! Several processes append to the same log (a launch, the emission daemon,
+//! the claim server), so each append holds an exclusive lock on the file
+//! and continues the chain from whatever line is last at that moment.
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::launch::now_unix;
+use crate::pubkey::Pubkey;
+use crate::time;
+use crate::tx;
+
+const AUDIT_FILE: &str = "audit.jsonl";
+/// `prev_hash` of the first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// Bytes read at a time from the end of the log looking for its last line.
+const TAIL_CHUNK: u64 = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    pub seq: u64,
+    pub time: String,
+    /// The command that signed, such as `launch` or `proposal submit`.
+    pub purpose: String,
+    /// SHA-256 of the serialized message, hex.
+    pub message_hash: String,
+    pub signers: Vec<Pubkey>,
+    /// The transaction's first signature, when it is signed by then.
+    pub signature: Option<Signature>,
+    pub prev_hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Line {
+    #[serde(flatten)]
+    entry: Entry,
+    hash: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+impl Entry {
+    pub fn hash(&self) -> String {
+        hex(&Sha256::digest(serde_json::to_vec(self).unwrap()))
+    }
+}
+
+struct State {
+    file: File,
+    /// Messages already logged by this process; a resent transaction is
+    /// logged once.
+    seen: HashSet<String>,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    purpose: String,
+    state: Mutex<State>,
+}
+
+impl AuditLog {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(Config::dir()?.join(AUDIT_FILE))
+    }
+
+    /// Opens `path` for appending. Entries are tagged with `purpose`.
+    pub fn open(path: &Path, purpose: impl Into<String>) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+        // A log that doesn't end in an entry can't be continued.
+        last_line(&mut file)?;
+        Ok(AuditLog {
+            path: path.to_path_buf(),
+            purpose: purpose.into(),
+            state: Mutex::new(State {
+                file,
+                seen: HashSet::new(),
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Logs a signed, serialized transaction about to be submitted.
+    pub fn record_transaction(&self, wire: &[u8]) -> Result<()> {
+        let (signature, message, signers) = tx::inspect_wire_transaction(wire)?;
+        self.record_message(message, &signers, Some(signature))
+    }
+
+    /// Logs `signers`' signatures over `message`, for messages signed here
+    /// but submitted elsewhere or later.
+    pub fn record_message(
+        &self,
+        message: &[u8],
+        signers: &[Pubkey],
+        signature: Option<Signature>,
+    ) -> Result<()> {
+        let message_hash = hex(&Sha256::digest(message));
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.seen.insert(message_hash.clone()) {
+            return Ok(());
+        }
+        let entry = Entry {
+            seq: 0,
+            time: time::format_rfc3339(now_unix()),
+            purpose: self.purpose.clone(),
+            message_hash,
+            signers: signers.to_vec(),
+            signature: signature.filter(|s| *s != Signature::default()),
+            prev_hash: String::new(),
+        };
+        // Other processes append to the same file; the lock keeps any of
+        // them from adding a line between reading the last one and writing.
+        state.file.lock()?;
+        let appended = append(&mut state.file, entry);
+        state.file.unlock()?;
+        appended
+    }
+}
+
+/// Chains `entry` onto the log's last line and writes it; the caller holds
+/// the file's lock.
+fn append(file: &mut File, mut entry: Entry) -> Result<()> {
+    (entry.seq, entry.prev_hash) = match last_line(file)? {
+        Some(last) => (last.entry.seq + 1, last.hash),
+        None => (1, GENESIS_HASH.to_string()),
+    };
+    let line = Line {
+        hash: entry.hash(),
+        entry,
+    };
+    let mut bytes = serde_json::to_vec(&line)?;
+    bytes.push(b'\n');
+    file.write_all(&bytes)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+fn parse_line(number: usize, text: &str) -> Result<Line> {
+    serde_json::from_str(text)
+        .map_err(|e| Error::InvalidInput(format!("audit log line {number}: {e}")))
+}
+
+/// The log's last entry, read backwards from the end so appending stays
+/// cheap however long the log grows.
+fn last_line(file: &mut File) -> Result<Option<Line>> {
+    let parse = |text: &[u8]| {
+        serde_json::from_slice(text)
+            .map(Some)
+            .map_err(|e| Error::InvalidInput(format!("audit log's last line: {e}")))
+    };
+    let mut end = file.seek(SeekFrom::End(0))?;
+    let mut tail = Vec::new();
+    loop {
+        let len = tail
+            .iter()
+            .rposition(|b: &u8| !b.is_ascii_whitespace())
+            .map_or(0, |i| i + 1);
+        if let Some(start) = tail[..len].iter().rposition(|&b| b == b'\n') {
+            return parse(&tail[start + 1..len]);
+        }
+        if end == 0 {
+            return if len == 0 {
+                Ok(None)
+            } else {
+                parse(&tail[..len])
+            };
+        }
+        let chunk = TAIL_CHUNK.min(end);
+        end -= chunk;
+        file.seek(SeekFrom::Start(end))?;
+        let mut read = vec![0; chunk as usize];
+        file.read_exact(&mut read)?;
+        read.extend_from_slice(&tail);
+        tail = read;
+    }
+}
+
+/// What an intact log holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verified {
+    pub entries: u64,
+    /// The newest entry's hash; noting it lets a later check detect lines
+    /// removed from the end.
+    pub head: String,
+}
+
+/// Recomputes the chain; the error names the first line that does not fit.
+pub fn verify(path: &Path) -> Result<Verified> {
+    let file = File::open(path)?;
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut entries = 0;
+    for (i, text) in BufReader::new(file).lines().enumerate() {
+        let text = text?;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let number = i + 1;
+        let line = parse_line(number, &text)?;
+        let broken = |why: &str| {
+            Err(Error::InvalidInput(format!(
+                "audit log line {number}: {why}"
+            )))
+        };
+        if line.entry.seq != entries + 1 {
+            return broken(&format!(
+                "sequence number {} where {} was expected; a line was removed or reordered",
+                line.entry.seq,
+                entries + 1
+            ));
+        }
+        if line.entry.prev_hash != prev_hash {
+            return broken("previous hash does not match the line before it");
+        }
+        if line.entry.hash() != line.hash {
+            return broken("contents do not match the line's hash; it was modified");
+        }
+        entries += 1;
+        prev_hash = line.hash;
+    }
+    Ok(Verified {
+        entries,
+        head: prev_hash,
+    })
+}
@@ -0,0 +1,255 @@
+//! Transaction submission beyond a single RPC node.
+//!
+//! Each signed transaction can go to several endpoints at once: during
+//! congestion a single endpoint often drops transactions before forwarding
+//! them to the leader, and more endpoints means more paths to it. A
+//! dedicated sender endpoint (for example a paid one with staked
+//! connections) can take over submission from the query RPC; it gets its
+//! own resend policy and is never asked for reads.
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::rpc::{CONFIRM_POLL, CONFIRM_TIMEOUT, Commitment, RpcClient, SendOptions};
+use crate::tx::Transaction;
+
+/// How transactions are submitted to a dedicated sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SenderPolicy {
+    /// Times the transaction is sent again while it is unconfirmed.
+    pub resend_attempts: u32,
+    pub resend_interval_ms: u64,
+    /// Staked senders usually require this; they do not simulate.
+    pub skip_preflight: bool,
+}
+
+impl Default for SenderPolicy {
+    fn default() -> Self {
+        SenderPolicy {
+            resend_attempts: 10,
+            resend_interval_ms: 2000,
+            skip_preflight: true,
+        }
+    }
+}
+
+impl SenderPolicy {
+    fn options(&self) -> SendOptions {
+        SendOptions {
+            skip_preflight: self.skip_preflight,
+            // Resending is ours to do, so the node should not queue it again.
+            max_retries: Some(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointResult {
+    pub url: String,
+    /// The endpoint's error when it refused the transaction.
+    pub error: Option<String>,
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastReport {
+    pub signature: Signature,
+    pub endpoints: Vec<EndpointResult>,
+    /// Times the sender was sent the transaction again before it landed.
+    pub resends: u32,
+    /// The endpoint that first reported the transaction confirmed. Any of
+    /// them may have forwarded it; this is the one that saw it land first.
+    pub first_confirmed_by: Option<String>,
+}
+
+impl BroadcastReport {
+    pub fn accepted(&self) -> usize {
+        self.endpoints.iter().filter(|e| e.error.is_none()).count()
+    }
+}
+
+struct Pending {
+    signature: Signature,
+    wire: Vec<u8>,
+}
+
+/// Sends transactions through a dedicated sender and extra endpoints in
+/// addition to, or instead of, the query RPC.
+pub struct Broadcaster {
+    timeout: Duration,
+    extra: Vec<RpcClient>,
+    sender: Option<(RpcClient, SenderPolicy)>,
+    pending: Mutex<Option<Pending>>,
+    reports: Mutex<Vec<BroadcastReport>>,
+}
+
+impl Broadcaster {
+    /// Submits through `urls` as well, each request abandoned after
+    /// `timeout` like the query RPC's.
+    pub fn new(urls: &[String], timeout: Duration) -> Self {
+        Broadcaster {
+            timeout,
+            extra: urls
+                .iter()
+                .map(|u| RpcClient::new(u.clone()).with_timeout(timeout))
+                .collect(),
+            sender: None,
+            pending: Mutex::new(None),
+            reports: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Submits through `url` instead of the query RPC.
+    pub fn with_sender(mut self, url: impl Into<String>, policy: SenderPolicy) -> Self {
+        self.sender = Some((RpcClient::new(url).with_timeout(self.timeout), policy));
+        self
+    }
+
+    /// Endpoints a transaction is submitted to, with their send options.
+    fn submitters<'a>(&'a self, primary: &'a RpcClient) -> Vec<(&'a RpcClient, SendOptions)> {
+        let first = match &self.sender {
+            Some((sender, policy)) => (sender, policy.options()),
+            None => (primary, SendOptions::default()),
+        };
+        std::iter::once(first)
+            .chain(self.extra.iter().map(|rpc| (rpc, SendOptions::default())))
+            .collect()
+    }
+
+    /// Endpoints polled for confirmation; the sender is write-only.
+    fn readers<'a>(&'a self, primary: &'a RpcClient) -> Vec<&'a RpcClient> {
+        std::iter::once(primary).chain(&self.extra).collect()
+    }
+
+    /// Submits `tx` to every submitting endpoint concurrently. Succeeds if
+    /// any endpoint accepts it; otherwise returns the first endpoint's error.
+    pub fn send(&self, primary: &RpcClient, tx: &Transaction) -> Result<Signature> {
+        let wire = tx.serialize();
+        let signature = tx.signature();
+        // The sender and extra endpoints carry no audit log of their own.
+        if let Some(audit) = primary.audit() {
+            audit.record_transaction(&wire)?;
+        }
+        let submitters = self.submitters(primary);
+        let results: Vec<(Result<Signature>, Duration)> = thread::scope(|s| {
+            let handles: Vec<_> = submitters
+                .iter()
+                .map(|(rpc, options)| {
+                    let wire = &wire;
+                    s.spawn(move || {
+                        let started = Instant::now();
+                        let result = rpc.send_raw_transaction_with(wire, *options);
+                        (result, started.elapsed())
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        (
+                            Err(Error::Http("sender thread panicked".into())),
+                            Duration::ZERO,
+                        )
+                    })
+                })
+                .collect()
+        });
+
+        let endpoints = submitters
+            .iter()
+            .zip(&results)
+            .map(|((rpc, _), (result, elapsed))| EndpointResult {
+                url: rpc.url().to_string(),
+                error: result.as_ref().err().map(ToString::to_string),
+                latency_ms: elapsed.as_millis() as u64,
+            })
+            .collect();
+        lock(&self.reports).push(BroadcastReport {
+            signature,
+            endpoints,
+            resends: 0,
+            first_confirmed_by: None,
+        });
+
+        if !results.iter().any(|(r, _)| r.is_ok()) {
+            return results
+                .into_iter()
+                .next()
+                .map(|(r, _)| r)
+                .unwrap_or(Err(Error::Http("no endpoints".into())));
+        }
+        *lock(&self.pending) = Some(Pending { signature, wire });
+        Ok(signature)
+    }
+
+    /// Polls the readers until one sees the transaction reach `commitment`
+    /// or fail, resending it through the sender as its policy allows.
+    pub fn confirm(
+        &self,
+        primary: &RpcClient,
+        signature: &Signature,
+        commitment: Commitment,
+    ) -> Result<()> {
+        let readers = self.readers(primary);
+        let wire = lock(&self.pending)
+            .take()
+            .filter(|p| p.signature == *signature)
+            .map(|p| p.wire);
+        let started = Instant::now();
+        let mut last_sent = Instant::now();
+        let mut resends = 0;
+        while started.elapsed() < CONFIRM_TIMEOUT {
+            for rpc in &readers {
+                // One endpoint lagging or down must not stop the others.
+                let Ok(Some(status)) = rpc.get_signature_status(signature) else {
+                    continue;
+                };
+                if let Some(err) = status.err {
+                    return Err(rpc.transaction_failure(signature, &err));
+                }
+                if status.reached(commitment) {
+                    let mut reports = lock(&self.reports);
+                    if let Some(report) =
+                        reports.iter_mut().rev().find(|r| r.signature == *signature)
+                    {
+                        report.first_confirmed_by = Some(rpc.url().to_string());
+                        report.resends = resends;
+                    }
+                    return Ok(());
+                }
+            }
+            if let (Some((sender, policy)), Some(wire)) = (&self.sender, &wire)
+                && resends < policy.resend_attempts
+                && last_sent.elapsed() >= Duration::from_millis(policy.resend_interval_ms)
+            {
+                // A refused resend is expected once the transaction has landed.
+                let _ = sender.send_raw_transaction_with(wire, policy.options());
+                resends += 1;
+                last_sent = Instant::now();
+            }
+            thread::sleep(CONFIRM_POLL);
+        }
+        Err(Error::Timeout(format!(
+            "transaction {signature} was not {commitment} within {}s",
+            CONFIRM_TIMEOUT.as_secs()
+        )))
+    }
+
+    /// Reports of transactions sent since the last call.
+    pub fn take_reports(&self) -> Vec<BroadcastReport> {
+        std::mem::take(&mut *lock(&self.reports))
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
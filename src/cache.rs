@@ -0,0 +1,94 @@
+//! On-disk cache for large account scans and token metadata, so inspecting
+//! the same mint again within a few minutes does not re-download every
+//! token account or the off-chain JSON.
+//!
+//! Only `getProgramAccounts` and `getMultipleAccounts` are cached by the
+//! client itself; other single account reads stay live so supplies and
+//! authorities are always current. Metadata is cached only where a command
+//! asks for it through `metadata::fetch_metadata_cached` or
+//! `storage::fetch_json_cached`. Entries are keyed by endpoint, method and
+//! parameters, or by the parts a caller passes to `get_entry`.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::error::Result;
+
+const CACHE_DIR: &str = "cache";
+
+pub struct RpcCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl RpcCache {
+    pub fn open(dir: impl Into<PathBuf>, ttl: Duration) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(RpcCache { dir, ttl })
+    }
+
+    pub fn open_default(ttl: Duration) -> Result<Self> {
+        Self::open(Config::dir()?.join(CACHE_DIR), ttl)
+    }
+
+    pub fn is_cacheable(method: &str) -> bool {
+        matches!(method, "getProgramAccounts" | "getMultipleAccounts")
+    }
+
+    fn path(&self, parts: &[&str]) -> PathBuf {
+        let mut hasher = Sha256::new();
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                hasher.update([0]);
+            }
+            hasher.update(part.as_bytes());
+        }
+        self.dir.join(format!(
+            "{}.json",
+            bs58::encode(hasher.finalize()).into_string()
+        ))
+    }
+
+    /// The cached result, unless it is older than the TTL.
+    pub fn get(&self, url: &str, method: &str, params: &Value) -> Option<Value> {
+        self.get_entry(&[url, method, &params.to_string()])
+    }
+
+    /// Best effort: a full disk only costs a re-fetch next time.
+    pub fn put(&self, url: &str, method: &str, params: &Value, result: &Value) {
+        self.put_entry(&[url, method, &params.to_string()], result);
+    }
+
+    /// The entry stored under `parts`, unless it is older than the TTL.
+    pub fn get_entry(&self, parts: &[&str]) -> Option<Value> {
+        let path = self.path(parts);
+        let age = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())?;
+        if age > self.ttl {
+            return None;
+        }
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+    }
+
+    pub fn put_entry(&self, parts: &[&str], value: &Value) {
+        let _ = fs::write(self.path(parts), value.to_string());
+    }
+
+    /// Deletes every cached entry, returning how many there were.
+    pub fn clear(&self) -> Result<usize> {
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            fs::remove_file(entry?.path())?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
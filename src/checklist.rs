@@ -0,0 +1,278 @@
+//! The checklist a token must pass before liquidity is added, when snipers
+//! start watching: metadata that can no longer change, mint and freeze
+//! authorities revoked or held by a multisig, a plan for locking the LP
+//! tokens and the project's links in the metadata.
+//!
+//! Which items are required, and what satisfies them, is set under
+//! `launch_checklist` in the config. `lp deploy` refuses to run until every
+//! required item passes, unless the failing ones are overridden by name.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::launch::{Links, now_unix};
+use crate::metadata;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::squads::{self, SQUADS_PROGRAM_ID};
+use crate::storage;
+use crate::time;
+use crate::token::Mint;
+
+/// Vaults of a configured multisig that count as holding an authority.
+const MULTISIG_VAULTS: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Item {
+    MetadataFinalized,
+    AuthoritiesSecured,
+    LpLock,
+    Socials,
+}
+
+impl Item {
+    pub const ALL: [Item; 4] = [
+        Item::MetadataFinalized,
+        Item::AuthoritiesSecured,
+        Item::LpLock,
+        Item::Socials,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Item::MetadataFinalized => "metadata-finalized",
+            Item::AuthoritiesSecured => "authorities-secured",
+            Item::LpLock => "lp-lock",
+            Item::Socials => "socials",
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for Item {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Item::ALL
+            .into_iter()
+            .find(|item| item.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Item::ALL.iter().map(|i| i.as_str()).collect();
+                Error::InvalidInput(format!(
+                    "unknown checklist item '{s}'; expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// How the LP tokens will be locked once the pool exists.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LpLock {
+    /// `burn`, or the locker holding them, by name or program address.
+    pub method: String,
+    /// When the lock ends, RFC 3339; none for a burn or a lock without end.
+    #[serde(default)]
+    pub until: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Checklist {
+    /// The items liquidity waits for; all of them unless set.
+    pub require: Vec<Item>,
+    /// Squads multisigs whose vaults may hold the mint and freeze
+    /// authorities instead of them being revoked.
+    pub multisigs: Vec<Pubkey>,
+    /// Links the off-chain metadata must carry, of website, twitter,
+    /// telegram and discord.
+    pub socials: Vec<String>,
+    pub lp_lock: Option<LpLock>,
+}
+
+impl Default for Checklist {
+    fn default() -> Self {
+        Checklist {
+            require: Item::ALL.to_vec(),
+            multisigs: Vec::new(),
+            socials: vec!["website".into(), "twitter".into()],
+            lp_lock: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Check {
+    pub item: Item,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(item: Item, passed: bool, detail: impl Into<String>) -> Self {
+        Check {
+            item,
+            passed,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl Checklist {
+    /// Runs the required items against `mint`'s current state.
+    pub fn run(
+        &self,
+        config: &Config,
+        rpc: &RpcClient,
+        address: &Pubkey,
+        mint: &Mint,
+    ) -> Result<Vec<Check>> {
+        let on_chain = metadata::fetch_metadata(rpc, address)?;
+        let mut checks = Vec::new();
+        for item in Item::ALL.into_iter().filter(|i| self.require.contains(i)) {
+            checks.push(match item {
+                Item::MetadataFinalized => match &on_chain {
+                    None => Check::new(item, false, "the mint has no metadata"),
+                    Some(m) if m.is_mutable => Check::new(
+                        item,
+                        false,
+                        format!("still mutable; {} can change it", m.update_authority),
+                    ),
+                    Some(_) => Check::new(item, true, "immutable"),
+                },
+                Item::AuthoritiesSecured => self.authorities(rpc, mint)?,
+                Item::LpLock => self.lp_lock(),
+                Item::Socials => match &on_chain {
+                    None => Check::new(item, false, "the mint has no metadata"),
+                    Some(m) => match storage::fetch_json(&m.data.uri, config.ipfs_gateway()) {
+                        Ok(json) => self.socials(&Links::from_json(&json)),
+                        Err(e) => Check::new(item, false, format!("metadata JSON: {e}")),
+                    },
+                },
+            });
+        }
+        Ok(checks)
+    }
+
+    fn authorities(&self, rpc: &RpcClient, mint: &Mint) -> Result<Check> {
+        let mut problems = Vec::new();
+        let mut held = Vec::new();
+        for (name, authority) in [
+            ("mint", mint.mint_authority),
+            ("freeze", mint.freeze_authority),
+        ] {
+            let Some(authority) = authority else {
+                continue;
+            };
+            if self.held_by_multisig(rpc, &authority)? {
+                held.push(format!("{name} authority held by multisig {authority}"));
+            } else {
+                problems.push(format!("{name} authority held by {authority}"));
+            }
+        }
+        Ok(if !problems.is_empty() {
+            Check::new(Item::AuthoritiesSecured, false, problems.join("; "))
+        } else if held.is_empty() {
+            Check::new(Item::AuthoritiesSecured, true, "both revoked")
+        } else {
+            Check::new(Item::AuthoritiesSecured, true, held.join("; "))
+        })
+    }
+
+    /// Whether `authority` is a configured multisig's vault or a Squads
+    /// multisig itself.
+    fn held_by_multisig(&self, rpc: &RpcClient, authority: &Pubkey) -> Result<bool> {
+        let vault = self.multisigs.iter().any(|multisig| {
+            (0..MULTISIG_VAULTS).any(|i| squads::vault_address(multisig, i) == *authority)
+        });
+        if vault {
+            return Ok(true);
+        }
+        Ok(rpc
+            .get_account(authority)?
+            .value
+            .is_some_and(|account| account.owner == SQUADS_PROGRAM_ID))
+    }
+
+    fn lp_lock(&self) -> Check {
+        let Some(lock) = &self.lp_lock else {
+            return Check::new(
+                Item::LpLock,
+                false,
+                "no lp_lock in the config's launch_checklist",
+            );
+        };
+        match &lock.until {
+            None => Check::new(Item::LpLock, true, lock.method.clone()),
+            Some(until) => match time::parse_rfc3339(until) {
+                Some(end) if end > now_unix() => {
+                    Check::new(Item::LpLock, true, format!("{} until {until}", lock.method))
+                }
+                Some(_) => Check::new(
+                    Item::LpLock,
+                    false,
+                    format!("the lock would end {until}, already past"),
+                ),
+                None => Check::new(
+                    Item::LpLock,
+                    false,
+                    format!("'{until}' is not an RFC 3339 time"),
+                ),
+            },
+        }
+    }
+
+    fn socials(&self, links: &Links) -> Check {
+        let missing: Vec<&str> = self
+            .socials
+            .iter()
+            .map(String::as_str)
+            .filter(|name| {
+                let link = match *name {
+                    "website" => &links.website,
+                    "twitter" => &links.twitter,
+                    "telegram" => &links.telegram,
+                    "discord" => &links.discord,
+                    _ => &None,
+                };
+                link.is_none()
+            })
+            .collect();
+        if missing.is_empty() {
+            Check::new(Item::Socials, true, self.socials.join(", "))
+        } else {
+            Check::new(
+                Item::Socials,
+                false,
+                format!("missing {}", missing.join(", ")),
+            )
+        }
+    }
+}
+
+/// Fails unless every check passed or is in `overrides`.
+pub fn enforce(checks: &[Check], overrides: &[Item]) -> Result<()> {
+    let failed: Vec<String> = checks
+        .iter()
+        .filter(|c| !c.passed && !overrides.contains(&c.item))
+        .map(|c| format!("{} ({})", c.item, c.detail))
+        .collect();
+    if failed.is_empty() {
+        return Ok(());
+    }
+    Err(Error::InvalidInput(format!(
+        "the launch checklist fails: {}; fix it, or pass --override with the item's name",
+        failed.join("; ")
+    )))
+}
@@ -0,0 +1,693 @@
+//! Whitelist claims: the operator signs a voucher per whitelisted wallet
+//! capping what it may claim, and the claim server pays out against
+//! claims the wallet itself signed, recording every payout so no wallet
+//! goes over its cap. The vouchers and messages are plain text a browser
+//! wallet's `signMessage` can show and sign, so a claim page only needs
+//! this core and a wallet adapter.
+
+use std::collections::{BTreeMap, HashSet};
+#[cfg(feature = "native")]
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(feature = "native")]
+use std::net::{TcpListener, TcpStream};
+#[cfg(feature = "native")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "native")]
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native")]
+use serde_json::{Value, json};
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::keypair::{Keypair, Signature};
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::signer::Signer;
+#[cfg(feature = "native")]
+use crate::{extension, launch::now_unix, manager::BLOCKHASH_LIFETIME_SECS, rpc::RpcClient, token};
+
+/// Largest claim request body the server reads.
+#[cfg(feature = "native")]
+const MAX_BODY: usize = 16 * 1024;
+/// Longest request or header line the server reads.
+#[cfg(feature = "native")]
+const MAX_LINE: usize = 8 * 1024;
+/// Most header lines the server reads.
+#[cfg(feature = "native")]
+const MAX_HEADERS: usize = 64;
+/// How long a client has to send its whole request and take the answer.
+/// Requests are served one at a time, so a slow client holds up the rest.
+#[cfg(feature = "native")]
+const REQUEST_DEADLINE: Duration = Duration::from_secs(10);
+
+/// Parses a whitelist CSV of `wallet,cap` rows, caps in whole tokens. A
+/// header row and `#` comments are skipped; listing a wallet twice is an
+/// error since it is unclear which cap was meant.
+pub fn parse_whitelist(text: &str, decimals: u8) -> Result<Vec<(Pubkey, TokenAmount)>> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let Some((wallet, cap)) = parse_whitelist_line(i + 1, line, decimals)? else {
+            continue;
+        };
+        if !seen.insert(wallet) {
+            return Err(Error::InvalidInput(format!(
+                "line {}: {wallet} is listed twice",
+                i + 1
+            )));
+        }
+        entries.push((wallet, cap));
+    }
+    Ok(entries)
+}
+
+/// One `wallet,amount` line, `number` counting from 1. Blank lines,
+/// `#` comments and a header on the first line yield nothing.
+pub fn parse_whitelist_line(
+    number: usize,
+    line: &str,
+    decimals: u8,
+) -> Result<Option<(Pubkey, TokenAmount)>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (wallet, cap) = line
+        .split_once(',')
+        .ok_or_else(|| Error::InvalidInput(format!("line {number}: expected wallet,cap")))?;
+    let wallet = match wallet.trim().parse::<Pubkey>() {
+        Ok(wallet) => wallet,
+        Err(_) if number == 1 => return Ok(None),
+        Err(e) => return Err(Error::InvalidInput(format!("line {number}: {e}"))),
+    };
+    let cap = TokenAmount::parse(cap.trim(), decimals)
+        .map_err(|e| Error::InvalidInput(format!("line {number}: {e}")))?;
+    Ok(Some((wallet, cap)))
+}
+
+/// The text the operator signs to whitelist `wallet` for up to `cap`.
+pub fn voucher_message(mint: &Pubkey, wallet: &Pubkey, cap: TokenAmount) -> String {
+    format!(
+        "launch-solana claim voucher\nmint: {mint}\nwallet: {wallet}\ncap: {}",
+        cap.raw()
+    )
+}
+
+/// The text a wallet signs to claim `amount` base units. `nonce` must grow
+/// with every claim so a signed request cannot be replayed.
+pub fn claim_message(mint: &Pubkey, wallet: &Pubkey, amount: u64, nonce: u64) -> String {
+    format!("launch-solana claim\nmint: {mint}\nwallet: {wallet}\namount: {amount}\nnonce: {nonce}")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Voucher {
+    pub wallet: Pubkey,
+    pub cap: TokenAmount,
+    /// The operator's signature over [`voucher_message`].
+    pub signature: Signature,
+}
+
+/// Vouchers for one mint, as written by `claim vouchers` and hosted by
+/// the claim server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoucherSet {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    /// Key the vouchers are signed with.
+    pub authority: Pubkey,
+    pub vouchers: Vec<Voucher>,
+}
+
+impl VoucherSet {
+    pub fn issue(
+        authority: &Keypair,
+        mint: Pubkey,
+        decimals: u8,
+        whitelist: &[(Pubkey, TokenAmount)],
+    ) -> Self {
+        let vouchers = whitelist
+            .iter()
+            .map(|&(wallet, cap)| Voucher {
+                wallet,
+                cap,
+                signature: authority.sign_message(voucher_message(&mint, &wallet, cap).as_bytes()),
+            })
+            .collect();
+        VoucherSet {
+            mint,
+            decimals,
+            authority: authority.pubkey(),
+            vouchers,
+        }
+    }
+
+    /// Checks every voucher is signed by `authority` for this mint.
+    pub fn verify(&self) -> Result<()> {
+        for v in &self.vouchers {
+            if v.cap.decimals() != self.decimals {
+                return Err(Error::InvalidInput(format!(
+                    "voucher for {} has {} decimals, the set has {}",
+                    v.wallet,
+                    v.cap.decimals(),
+                    self.decimals
+                )));
+            }
+            let message = voucher_message(&self.mint, &v.wallet, v.cap);
+            if !v.signature.verify(&self.authority, message.as_bytes()) {
+                return Err(Error::InvalidInput(format!(
+                    "voucher for {} is not signed by {}",
+                    v.wallet, self.authority
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, wallet: &Pubkey) -> Option<&Voucher> {
+        self.vouchers.iter().find(|v| v.wallet == *wallet)
+    }
+}
+
+/// A claim as posted to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimRequest {
+    pub wallet: Pubkey,
+    /// Base units, as a decimal string.
+    pub amount: String,
+    pub nonce: u64,
+    /// The wallet's signature over [`claim_message`].
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payout {
+    pub amount: TokenAmount,
+    pub nonce: u64,
+    pub transaction: Signature,
+    /// Unix seconds.
+    pub at: i64,
+    /// Sent but not known to have landed; still counts against the cap
+    /// until the transaction is found or its blockhash has expired.
+    #[serde(default)]
+    pub pending: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalletClaims {
+    pub last_nonce: u64,
+    pub payouts: Vec<Payout>,
+}
+
+/// What has been paid out, persisted after every claim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimLedger {
+    pub mint: Pubkey,
+    pub wallets: BTreeMap<Pubkey, WalletClaims>,
+}
+
+impl ClaimLedger {
+    pub fn new(mint: Pubkey) -> Self {
+        ClaimLedger {
+            mint,
+            wallets: BTreeMap::new(),
+        }
+    }
+
+    pub fn claimed(&self, wallet: &Pubkey, decimals: u8) -> TokenAmount {
+        let raw = self
+            .wallets
+            .get(wallet)
+            .map_or(0, |w| w.payouts.iter().map(|p| p.amount.raw()).sum());
+        TokenAmount::from_raw(raw, decimals)
+    }
+
+    pub fn next_nonce(&self, wallet: &Pubkey) -> u64 {
+        self.wallets.get(wallet).map_or(1, |w| w.last_nonce + 1)
+    }
+
+    /// Checks a claim against the wallet's voucher, signature, nonce and
+    /// remaining allowance, returning the amount to pay out.
+    pub fn check(&self, vouchers: &VoucherSet, request: &ClaimRequest) -> Result<TokenAmount> {
+        let voucher = vouchers.get(&request.wallet).ok_or_else(|| {
+            Error::InvalidInput(format!("{} is not on the whitelist", request.wallet))
+        })?;
+        let raw: u64 = request.amount.parse().map_err(|_| {
+            Error::InvalidInput(format!(
+                "'{}' is not an amount in base units",
+                request.amount
+            ))
+        })?;
+        let message = claim_message(&vouchers.mint, &request.wallet, raw, request.nonce);
+        if !request
+            .signature
+            .verify(&request.wallet, message.as_bytes())
+        {
+            return Err(Error::InvalidInput(
+                "claim is not signed by the claiming wallet".into(),
+            ));
+        }
+        let next = self.next_nonce(&request.wallet);
+        if request.nonce < next {
+            return Err(Error::InvalidInput(format!(
+                "nonce {} was already used; the next is {next}",
+                request.nonce
+            )));
+        }
+        let amount = TokenAmount::from_raw(raw as u128, vouchers.decimals);
+        if amount.is_zero() {
+            return Err(Error::InvalidInput("claim amount is zero".into()));
+        }
+        let claimed = self.claimed(&request.wallet, vouchers.decimals);
+        let remaining = voucher
+            .cap
+            .checked_sub(claimed)
+            .unwrap_or(TokenAmount::zero(vouchers.decimals));
+        if amount > remaining {
+            return Err(Error::InvalidInput(format!(
+                "claim of {amount} exceeds the {remaining} left of {}'s cap",
+                request.wallet
+            )));
+        }
+        Ok(amount)
+    }
+
+    pub fn record(&mut self, wallet: Pubkey, payout: Payout) {
+        let entry = self.wallets.entry(wallet).or_default();
+        entry.last_nonce = entry.last_nonce.max(payout.nonce);
+        entry.payouts.push(payout);
+    }
+
+    /// Marks a pending payout paid, or forgets it when it did not land so
+    /// the wallet may claim the amount again.
+    pub fn settle(&mut self, wallet: &Pubkey, transaction: &Signature, landed: bool) {
+        if let Some(entry) = self.wallets.get_mut(wallet) {
+            if landed {
+                for p in entry
+                    .payouts
+                    .iter_mut()
+                    .filter(|p| p.transaction == *transaction)
+                {
+                    p.pending = false;
+                }
+            } else {
+                entry.payouts.retain(|p| p.transaction != *transaction);
+            }
+        }
+    }
+
+    #[cfg(feature = "native")]
+    pub fn load_or_new(path: &Path, mint: Pubkey) -> Result<Self> {
+        if !path.exists() {
+            return Ok(ClaimLedger::new(mint));
+        }
+        let raw = std::fs::read_to_string(path)?;
+        let ledger: ClaimLedger = serde_json::from_str(&raw)
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+        if ledger.mint != mint {
+            return Err(Error::Config(format!(
+                "{} records claims of {}, not {mint}",
+                path.display(),
+                ledger.mint
+            )));
+        }
+        Ok(ledger)
+    }
+
+    /// Writes through a temporary file so a crash never leaves half a ledger.
+    #[cfg(feature = "native")]
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+/// The claim server's state. Requests are handled one at a time, so two
+/// claims by the same wallet can never both pass the cap check.
+#[cfg(feature = "native")]
+pub struct ClaimServer<'a> {
+    pub rpc: &'a RpcClient,
+    /// Pays fees and rent and holds the tokens given out.
+    pub operator: &'a dyn Signer,
+    pub vouchers: VoucherSet,
+    pub ledger: ClaimLedger,
+    pub ledger_path: PathBuf,
+    program_id: Pubkey,
+}
+
+#[cfg(feature = "native")]
+impl<'a> ClaimServer<'a> {
+    pub fn new(
+        rpc: &'a RpcClient,
+        operator: &'a dyn Signer,
+        vouchers: VoucherSet,
+        ledger_path: PathBuf,
+    ) -> Result<Self> {
+        vouchers.verify()?;
+        let info = token::fetch_mint(rpc, &vouchers.mint)?;
+        if info.mint.decimals != vouchers.decimals {
+            return Err(Error::Config(format!(
+                "vouchers use {} decimals but {} has {}",
+                vouchers.decimals, vouchers.mint, info.mint.decimals
+            )));
+        }
+        if extension::find(&info.extensions, extension::TRANSFER_HOOK).is_some() {
+            return Err(Error::InvalidInput(format!(
+                "{} has a transfer hook; payouts need the hook's extra accounts, \
+                 which are not resolved",
+                vouchers.mint
+            )));
+        }
+        let ledger = ClaimLedger::load_or_new(&ledger_path, vouchers.mint)?;
+        Ok(ClaimServer {
+            rpc,
+            operator,
+            vouchers,
+            ledger,
+            ledger_path,
+            program_id: info.program_id,
+        })
+    }
+
+    /// Serves until the process is stopped.
+    ///
+    /// - `GET /vouchers/<wallet>`: the wallet's voucher
+    /// - `GET /status/<wallet>`: cap, claimed, remaining and next nonce
+    /// - `POST /claim`: a [`ClaimRequest`]; pays out and returns the signature
+    pub fn serve(&mut self, listener: TcpListener) -> Result<()> {
+        for stream in listener.incoming() {
+            // One failed accept (a client resetting, too many open files)
+            // says nothing about the next connection.
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("claim server: accept failed: {e}");
+                    continue;
+                }
+            };
+            let mut client = Deadline::new(stream, REQUEST_DEADLINE);
+            let (status, body) = match read_request(&mut client) {
+                Ok((method, path, body)) => self.route(&method, &path, &body),
+                Err(e) => (400, json!({ "error": e.to_string() })),
+            };
+            // A client that hung up is its own problem; keep serving.
+            let _ = write_response(&mut client, status, &body);
+        }
+        Ok(())
+    }
+
+    fn route(&mut self, method: &str, path: &str, body: &[u8]) -> (u16, Value) {
+        let result = match (method, path.split('/').collect::<Vec<_>>().as_slice()) {
+            ("OPTIONS", _) => return (204, Value::Null),
+            ("GET", ["", "vouchers", wallet]) => self.voucher(wallet),
+            ("GET", ["", "status", wallet]) => self.status(wallet),
+            ("POST", ["", "claim"]) => serde_json::from_slice(body)
+                .map_err(|e| Error::InvalidInput(format!("claim request: {e}")))
+                .and_then(|request| self.claim(&request)),
+            _ => return (404, json!({ "error": format!("no route {method} {path}") })),
+        };
+        match result {
+            Ok(value) => (200, value),
+            Err(e @ Error::InvalidInput(_)) => (400, json!({ "error": e.to_string() })),
+            Err(e) => {
+                eprintln!("claim server: {e}");
+                (500, json!({ "error": e.to_string() }))
+            }
+        }
+    }
+
+    fn voucher(&self, wallet: &str) -> Result<Value> {
+        let wallet: Pubkey = wallet.parse()?;
+        let voucher = self
+            .vouchers
+            .get(&wallet)
+            .ok_or_else(|| Error::InvalidInput(format!("{wallet} is not on the whitelist")))?;
+        Ok(json!({
+            "mint": self.vouchers.mint,
+            "authority": self.vouchers.authority,
+            "voucher": voucher,
+        }))
+    }
+
+    fn status(&self, wallet: &str) -> Result<Value> {
+        let wallet: Pubkey = wallet.parse()?;
+        let decimals = self.vouchers.decimals;
+        let cap = self.vouchers.get(&wallet).map(|v| v.cap);
+        let claimed = self.ledger.claimed(&wallet, decimals);
+        let remaining = cap
+            .and_then(|cap| cap.checked_sub(claimed))
+            .unwrap_or(TokenAmount::zero(decimals));
+        Ok(json!({
+            "wallet": wallet,
+            "whitelisted": cap.is_some(),
+            "cap": cap,
+            "claimed": claimed,
+            "remaining": remaining,
+            "nextNonce": self.ledger.next_nonce(&wallet),
+        }))
+    }
+
+    /// The payout is recorded as pending before it is sent and only
+    /// forgotten again once the transaction is known not to have landed:
+    /// it failed, or its blockhash expired without it being found. A crash
+    /// or a confirmation timeout can under-pay but never pay twice.
+    fn claim(&mut self, request: &ClaimRequest) -> Result<Value> {
+        self.settle(&request.wallet)?;
+        let amount = self.ledger.check(&self.vouchers, request)?;
+        let operator = self.operator.pubkey();
+        let mint = self.vouchers.mint;
+        let source = token::associated_token_address(&operator, &mint, &self.program_id);
+        let destination = token::associated_token_address(&request.wallet, &mint, &self.program_id);
+        let ixs = [
+            token::create_associated_token_account_idempotent(
+                &operator,
+                &request.wallet,
+                &mint,
+                &self.program_id,
+            ),
+            token::transfer_checked(
+                &self.program_id,
+                &source,
+                &mint,
+                &destination,
+                &operator,
+                amount.to_u64()?,
+                self.vouchers.decimals,
+            ),
+        ];
+        let tx = self.rpc.build_signed(&ixs, &operator, &[self.operator])?;
+        let signature = tx.signature();
+
+        self.ledger.record(
+            request.wallet,
+            Payout {
+                amount,
+                nonce: request.nonce,
+                transaction: signature,
+                at: now_unix(),
+                pending: true,
+            },
+        );
+        self.ledger.save(&self.ledger_path)?;
+        match self.rpc.send_and_confirm(&tx) {
+            Ok(_) => self.ledger.settle(&request.wallet, &signature, true),
+            // Rejected in simulation or failed on chain: it can't land later.
+            Err(e @ Error::TransactionFailed { .. }) => {
+                self.ledger.settle(&request.wallet, &signature, false);
+                self.ledger.save(&self.ledger_path)?;
+                return Err(e);
+            }
+            // A timeout or dropped connection: it may still land, so it stays
+            // reserved until `settle` learns the outcome.
+            Err(e) => {
+                eprintln!(
+                    "claim server: outcome of {signature} to {} unknown; held until settled: {e}",
+                    request.wallet
+                );
+                return Err(e);
+            }
+        }
+        self.ledger.save(&self.ledger_path)?;
+        eprintln!("paid {amount} to {} ({signature})", request.wallet);
+        Ok(json!({ "amount": amount, "signature": signature }))
+    }
+
+    /// Settles the wallet's pending payouts whose outcome can be known:
+    /// found in the transaction history, or past their blockhash's lifetime
+    /// without being found. Younger ones stay reserved.
+    fn settle(&mut self, wallet: &Pubkey) -> Result<()> {
+        let now = now_unix();
+        let pending: Vec<(Signature, i64)> = self
+            .ledger
+            .wallets
+            .get(wallet)
+            .map(|w| {
+                w.payouts
+                    .iter()
+                    .filter(|p| p.pending)
+                    .map(|p| (p.transaction, p.at))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let signatures: Vec<Signature> = pending.iter().map(|(s, _)| *s).collect();
+        let statuses = self.rpc.get_signature_statuses(&signatures, true)?;
+        let mut changed = false;
+        for ((signature, at), status) in pending.into_iter().zip(statuses) {
+            let landed = match status {
+                Some(status) if status.err.is_some() => false,
+                Some(status) if status.is_confirmed() => true,
+                None if now >= at + BLOCKHASH_LIFETIME_SECS => false,
+                _ => continue,
+            };
+            self.ledger.settle(wallet, &signature, landed);
+            changed = true;
+        }
+        if changed {
+            self.ledger.save(&self.ledger_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// A connection the whole request and response must fit into one
+/// deadline, not just each read, so a client trickling bytes can't hold
+/// the server past it.
+#[cfg(feature = "native")]
+struct Deadline {
+    stream: TcpStream,
+    until: Instant,
+}
+
+#[cfg(feature = "native")]
+impl Deadline {
+    fn new(stream: TcpStream, limit: Duration) -> Self {
+        Deadline {
+            stream,
+            until: Instant::now() + limit,
+        }
+    }
+
+    fn remaining(&self) -> std::io::Result<Duration> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "request took too long",
+            ));
+        }
+        Ok(left)
+    }
+}
+
+#[cfg(feature = "native")]
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stream.set_read_timeout(Some(self.remaining()?))?;
+        self.stream.read(buf)
+    }
+}
+
+#[cfg(feature = "native")]
+impl Write for Deadline {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream.set_write_timeout(Some(self.remaining()?))?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Reads one line of at most [`MAX_LINE`] bytes into `line`, returning
+/// how many were read.
+#[cfg(feature = "native")]
+fn read_line_capped(reader: &mut impl BufRead, line: &mut String) -> Result<usize> {
+    line.clear();
+    let read = reader.take(MAX_LINE as u64 + 1).read_line(line)?;
+    if read > MAX_LINE {
+        return Err(Error::InvalidInput(format!(
+            "request line or header over {MAX_LINE} bytes"
+        )));
+    }
+    Ok(read)
+}
+
+/// Reads a request line, at most [`MAX_HEADERS`] headers and a
+/// `Content-Length` body.
+#[cfg(feature = "native")]
+fn read_request(stream: &mut impl Read) -> Result<(String, String, Vec<u8>)> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    read_line_capped(&mut reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(Error::InvalidInput("malformed request line".into()));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+    let mut length = 0;
+    let mut headers = 0;
+    loop {
+        if read_line_capped(&mut reader, &mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        headers += 1;
+        if headers > MAX_HEADERS {
+            return Err(Error::InvalidInput(format!(
+                "request has over {MAX_HEADERS} headers"
+            )));
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            length = value
+                .trim()
+                .parse()
+                .map_err(|_| Error::InvalidInput("bad Content-Length".into()))?;
+        }
+    }
+    if length > MAX_BODY {
+        return Err(Error::InvalidInput(format!(
+            "request body over {MAX_BODY} bytes"
+        )));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok((method, path, body))
+}
+
+#[cfg(feature = "native")]
+fn write_response(stream: &mut impl Write, status: u16, body: &Value) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Internal Server Error",
+    };
+    let body = if body.is_null() {
+        String::new()
+    } else {
+        body.to_string()
+    };
+    // Claim pages are usually served from elsewhere, so allow any origin.
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    Ok(())
+}
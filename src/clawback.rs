@@ -0,0 +1,279 @@
+//! Taking tokens back from holders through a Token-2022 permanent delegate.
+//!
+//! A mint created with the permanent-delegate extension lets one key move
+//! tokens out of any of its accounts. [`Clawback::prepare`] resolves the
+//! named holders to their token accounts of the mint and what each holds;
+//! [`Clawback::execute`] moves those balances to the treasury's associated
+//! account. Every transaction carries the reason as a memo, and every
+//! account taken from is appended to a log with it.
+//!
+//! Frozen accounts can't send tokens, not even to the permanent delegate.
+//! When the delegate also holds the freeze authority, such an account is
+//! thawed, emptied and frozen again in one transaction; otherwise it is
+//! left alone and reported.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::extension;
+use crate::keypair::Signature;
+use crate::launch::now_unix;
+use crate::memo;
+use crate::pack::Packer;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::signer::Signer;
+use crate::token::{self, AccountState, TokenAccount};
+use crate::tx::Instruction;
+
+/// A token account to take from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holding {
+    pub account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: TokenAmount,
+    pub frozen: bool,
+    /// Why the account is left alone, if it is.
+    pub skip: Option<String>,
+}
+
+/// Everything a clawback takes, worked out before anything is sent.
+#[derive(Debug, Clone)]
+pub struct Clawback {
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    pub delegate: Pubkey,
+    /// The treasury's associated token account, created if missing.
+    pub destination: Pubkey,
+    pub treasury: Pubkey,
+    pub holdings: Vec<Holding>,
+}
+
+/// One line of the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClawbackEntry {
+    pub at: i64,
+    pub mint: Pubkey,
+    pub account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: TokenAmount,
+    pub destination: Pubkey,
+    pub reason: String,
+    pub signature: Option<Signature>,
+    pub error: Option<String>,
+}
+
+impl Clawback {
+    /// Checks `delegate` is the permanent delegate of `mint` and reads what
+    /// each of `holders` holds. A holder is a token account of the mint or
+    /// a wallet, which stands for all of its accounts of the mint.
+    pub fn prepare(
+        rpc: &RpcClient,
+        mint: &Pubkey,
+        delegate: &Pubkey,
+        treasury: &Pubkey,
+        holders: &[Pubkey],
+    ) -> Result<Self> {
+        let info = token::fetch_mint(rpc, mint)?;
+        match extension::permanent_delegate(&info.extensions)? {
+            Some(key) if key == *delegate => {}
+            Some(key) => {
+                return Err(Error::InvalidInput(format!(
+                    "the permanent delegate of {mint} is {key}, not {delegate}"
+                )));
+            }
+            None => {
+                return Err(Error::InvalidInput(format!(
+                    "{mint} has no permanent delegate; its tokens can't be clawed back"
+                )));
+            }
+        }
+        if extension::find(&info.extensions, extension::TRANSFER_HOOK).is_some() {
+            return Err(Error::InvalidInput(format!(
+                "{mint} has a transfer hook; clawbacks need the hook's extra accounts, \
+                 which are not resolved"
+            )));
+        }
+        let destination = token::associated_token_address(treasury, mint, &info.program_id);
+        let can_thaw = info.mint.freeze_authority == Some(*delegate);
+
+        let mut accounts: Vec<(Pubkey, TokenAccount)> = Vec::new();
+        let fetched = rpc.get_multiple_accounts(holders, None)?.value;
+        for (holder, account) in holders.iter().zip(fetched) {
+            let direct = account
+                .filter(|a| a.owner == info.program_id)
+                .and_then(|a| TokenAccount::unpack(&a.data).ok())
+                .filter(|t| t.mint == *mint);
+            if let Some(token) = direct {
+                accounts.push((*holder, token));
+                continue;
+            }
+            let before = accounts.len();
+            for keyed in rpc.get_token_accounts_by_owner(holder, &info.program_id)? {
+                let token = TokenAccount::unpack(&keyed.account.data)?;
+                if token.mint == *mint {
+                    accounts.push((keyed.pubkey, token));
+                }
+            }
+            if accounts.len() == before {
+                return Err(Error::InvalidInput(format!(
+                    "{holder} is neither a token account of {mint} nor a wallet holding it"
+                )));
+            }
+        }
+        accounts.sort_by_key(|(address, _)| *address);
+        accounts.dedup_by_key(|(address, _)| *address);
+
+        let holdings = accounts
+            .into_iter()
+            .map(|(account, token)| {
+                let frozen = token.state == AccountState::Frozen;
+                let skip = if account == destination {
+                    Some("the treasury's own account".to_string())
+                } else if token.amount == 0 {
+                    Some("empty".to_string())
+                } else if frozen && !can_thaw {
+                    Some(format!("frozen, and {delegate} can't thaw it"))
+                } else {
+                    None
+                };
+                Holding {
+                    account,
+                    owner: token.owner,
+                    amount: TokenAmount::from_raw(token.amount.into(), info.mint.decimals),
+                    frozen,
+                    skip,
+                }
+            })
+            .collect();
+        Ok(Clawback {
+            mint: *mint,
+            program_id: info.program_id,
+            decimals: info.mint.decimals,
+            delegate: *delegate,
+            destination,
+            treasury: *treasury,
+            holdings,
+        })
+    }
+
+    /// The holdings that will be taken.
+    pub fn taken(&self) -> impl Iterator<Item = &Holding> {
+        self.holdings.iter().filter(|h| h.skip.is_none())
+    }
+
+    pub fn total(&self) -> TokenAmount {
+        let raw = self.taken().map(|h| h.amount.raw()).sum();
+        TokenAmount::from_raw(raw, self.decimals)
+    }
+
+    /// Instructions moving one holding's balance to the treasury.
+    fn instructions(&self, holding: &Holding) -> Result<Vec<Instruction>> {
+        let p = &self.program_id;
+        let mut ixs = Vec::new();
+        if holding.frozen {
+            ixs.push(token::thaw_account(
+                p,
+                &holding.account,
+                &self.mint,
+                &self.delegate,
+            ));
+        }
+        ixs.push(token::transfer_checked(
+            p,
+            &holding.account,
+            &self.mint,
+            &self.destination,
+            &self.delegate,
+            holding.amount.to_u64()?,
+            self.decimals,
+        ));
+        if holding.frozen {
+            ixs.push(token::freeze_account(
+                p,
+                &holding.account,
+                &self.mint,
+                &self.delegate,
+            ));
+        }
+        Ok(ixs)
+    }
+
+    /// Sends the transfers, as many per transaction as fit, and appends an
+    /// entry for every holding to the log at `log_path`. A failed
+    /// transaction is logged and the rest still sent.
+    pub fn execute(
+        &self,
+        rpc: &RpcClient,
+        delegate: &dyn Signer,
+        reason: &str,
+        log_path: &Path,
+    ) -> Result<Vec<ClawbackEntry>> {
+        memo::validate(reason)?;
+        if delegate.pubkey() != self.delegate {
+            return Err(Error::InvalidInput(format!(
+                "the clawback was prepared for {}, not {}",
+                self.delegate,
+                delegate.pubkey()
+            )));
+        }
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)?;
+        let items = self
+            .taken()
+            .map(|h| Ok((h, self.instructions(h)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let prefix = vec![
+            memo::memo(reason, &[&self.delegate]),
+            token::create_associated_token_account_idempotent(
+                &self.delegate,
+                &self.treasury,
+                &self.mint,
+                &self.program_id,
+            ),
+        ];
+        let batches = Packer::new(self.delegate).with_prefix(prefix).pack(items)?;
+        let mut entries = Vec::new();
+        for (holdings, ixs) in batches {
+            let sent = rpc
+                .build_signed(&ixs, &self.delegate, &[delegate])
+                .and_then(|tx| rpc.send_and_confirm(&tx));
+            let (signature, error) = match sent {
+                Ok(signature) => (Some(signature), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            for holding in holdings {
+                let entry = ClawbackEntry {
+                    at: now_unix(),
+                    mint: self.mint,
+                    account: holding.account,
+                    owner: holding.owner,
+                    amount: holding.amount,
+                    destination: self.destination,
+                    reason: reason.to_string(),
+                    signature,
+                    error: error.clone(),
+                };
+                let mut line = serde_json::to_vec(&entry)?;
+                line.push(b'\n');
+                log.write_all(&line)?;
+                log.sync_data()?;
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// `clawback-<mint>.jsonl` in the working directory.
+pub fn default_log_path(mint: &Pubkey) -> PathBuf {
+    PathBuf::from(format!("clawback-{mint}.jsonl"))
+}
@@ -0,0 +1,171 @@
+//! Grouping a project's fungible tokens under a Metaplex collection.
+//!
+//! A collection is headed by a parent NFT: a mint with metadata and a
+//! master edition. Each member names the parent's mint in the
+//! `collection` field of its own metadata, which its update authority can
+//! set; the entry only counts, and explorers only group the tokens, once
+//! the parent's update authority has verified it. [`set_instructions`]
+//! does both when one wallet holds both authorities.
+
+use crate::error::{Error, Result};
+use crate::metadata::{self, Collection, CollectionDetails, Metadata};
+use crate::pubkey::Pubkey;
+use crate::rehost;
+use crate::rpc::RpcClient;
+use crate::tx::Instruction;
+
+/// The NFT heading a collection.
+#[derive(Debug, Clone)]
+pub struct Parent {
+    pub mint: Pubkey,
+    pub metadata: Metadata,
+    /// Whether verification counts members in the parent's metadata.
+    pub sized: bool,
+}
+
+impl Parent {
+    /// Reads `mint`'s metadata and master edition, refusing a mint that
+    /// can't head a collection.
+    pub fn fetch(rpc: &RpcClient, mint: &Pubkey) -> Result<Self> {
+        let program = rpc.programs().metadata;
+        let metadata = metadata::fetch_metadata(rpc, mint)?.ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "{mint} has no metadata, so it can't head a collection"
+            ))
+        })?;
+        let edition = metadata::master_edition_address(&program, mint);
+        if rpc.get_account(&edition)?.value.is_none() {
+            return Err(Error::InvalidInput(format!(
+                "{mint} has no master edition; a collection is headed by an NFT"
+            )));
+        }
+        let sized = match metadata.collection_details {
+            None => false,
+            Some(CollectionDetails::V1 { .. }) => true,
+            Some(CollectionDetails::V2) => {
+                return Err(Error::InvalidInput(format!(
+                    "{mint} uses collection details V2, whose verification is not supported"
+                )));
+            }
+        };
+        Ok(Parent {
+            mint: *mint,
+            metadata,
+            sized,
+        })
+    }
+
+    pub fn update_authority(&self) -> Pubkey {
+        self.metadata.update_authority
+    }
+}
+
+/// Where a token stands with a collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Membership {
+    None,
+    Unverified(Pubkey),
+    Verified(Pubkey),
+}
+
+impl Membership {
+    pub fn of(metadata: &Metadata) -> Self {
+        match &metadata.data.collection {
+            None => Membership::None,
+            Some(c) if c.verified => Membership::Verified(c.key),
+            Some(c) => Membership::Unverified(c.key),
+        }
+    }
+}
+
+/// Instructions putting `mint` in `parent`'s collection: naming it in the
+/// metadata, signed by `authority` as the token's update authority, and
+/// verifying it when `authority` is also the parent's. Empty when the
+/// token is already a verified member.
+pub fn set_instructions(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    current: &Metadata,
+    parent: &Parent,
+    authority: &Pubkey,
+) -> Result<Vec<Instruction>> {
+    let mut ixs = Vec::new();
+    match Membership::of(current) {
+        Membership::Verified(key) if key == parent.mint => return Ok(ixs),
+        Membership::Verified(key) => {
+            return Err(Error::InvalidInput(format!(
+                "{mint} is a verified member of collection {key}; its authority must \
+                 unverify it before it can join another"
+            )));
+        }
+        Membership::Unverified(key) if key == parent.mint => {}
+        Membership::None | Membership::Unverified(_) => {
+            rehost::ensure_update_authority(current, mint, authority)?;
+            let mut data = current.data.clone();
+            data.collection = Some(Collection {
+                verified: false,
+                key: parent.mint,
+            });
+            ixs.push(metadata::update_metadata_accounts_v2(
+                program_id,
+                mint,
+                authority,
+                Some(&data),
+                None,
+                None,
+                None,
+            ));
+        }
+    }
+    if parent.update_authority() == *authority {
+        ixs.push(metadata::verify_collection(
+            program_id,
+            mint,
+            &parent.mint,
+            authority,
+            authority,
+            parent.sized,
+        ));
+    }
+    Ok(ixs)
+}
+
+/// The instruction verifying `mint`, which already names `parent`, signed
+/// by the parent's update authority.
+pub fn verify_instruction(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    current: &Metadata,
+    parent: &Parent,
+    authority: &Pubkey,
+) -> Result<Instruction> {
+    match Membership::of(current) {
+        Membership::Unverified(key) if key == parent.mint => {}
+        Membership::Verified(key) if key == parent.mint => {
+            return Err(Error::InvalidInput(format!(
+                "{mint} is already verified in collection {key}"
+            )));
+        }
+        _ => {
+            return Err(Error::InvalidInput(format!(
+                "{mint} doesn't name collection {}; run `collection set` first",
+                parent.mint
+            )));
+        }
+    }
+    if parent.update_authority() != *authority {
+        return Err(Error::InvalidInput(format!(
+            "collection {} is verified by its update authority {}, not {authority}",
+            parent.mint,
+            parent.update_authority()
+        )));
+    }
+    Ok(metadata::verify_collection(
+        program_id,
+        mint,
+        &parent.mint,
+        authority,
+        authority,
+        parent.sized,
+    ))
+}
@@ -0,0 +1,131 @@
+//! Spotting a name or symbol an established token already uses, from a
+//! token list such as Jupiter's verified list. Wallets flag lookalikes as
+//! impersonation and listing sites reject them, so this runs before the
+//! metadata is written rather than after.
+
+use serde_json::Value;
+
+use crate::cache::RpcCache;
+use crate::error::{Error, Result};
+use crate::metadata::DataV2;
+use crate::preview::Finding;
+use crate::storage::{self, DEFAULT_IPFS_GATEWAY};
+
+/// Jupiter's verified tokens, the default list on mainnet. Other clusters
+/// have no established tokens worth comparing with.
+pub const DEFAULT_TOKEN_LIST: &str = "https://lite-api.jup.ag/tokens/v2/tag?query=verified";
+/// Collisions listed per field before the rest are only counted.
+const MAX_LISTED: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedToken {
+    pub address: String,
+    pub name: String,
+    pub symbol: String,
+}
+
+/// Reads a token list: an array of tokens, or an object holding one under
+/// `tokens` as the Solana token-list format does. A token's address may be
+/// under `address`, `id` or `mint`; entries without one are skipped.
+pub fn parse(json: &Value) -> Result<Vec<ListedToken>> {
+    let entries = json
+        .as_array()
+        .or_else(|| json.get("tokens").and_then(Value::as_array))
+        .ok_or_else(|| Error::InvalidInput("the token list is not an array of tokens".into()))?;
+    let field = |entry: &Value, keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| entry.get(*k).and_then(Value::as_str))
+            .unwrap_or_default()
+            .to_string()
+    };
+    Ok(entries
+        .iter()
+        .map(|entry| ListedToken {
+            address: field(entry, &["address", "id", "mint"]),
+            name: field(entry, &["name"]),
+            symbol: field(entry, &["symbol"]),
+        })
+        .filter(|t| !t.address.is_empty())
+        .collect())
+}
+
+/// Downloads the token list at `url`, reusing a copy fetched within
+/// `cache`'s TTL.
+pub fn fetch(cache: Option<&RpcCache>, url: &str) -> Result<Vec<ListedToken>> {
+    parse(&storage::fetch_json_cached(
+        cache,
+        url,
+        DEFAULT_IPFS_GATEWAY,
+    )?)
+}
+
+/// `s` reduced to what a reader tells apart at a glance: no case, spaces,
+/// punctuation or leading `$`, and digits that pass for letters read as
+/// those letters.
+fn fold(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | 'i' => 'l',
+            '5' => 's',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Warnings for each listed token whose symbol or name matches `data`'s,
+/// exactly or as a lookalike. `own` is the token's own address, which is
+/// never a collision.
+pub fn check(data: &DataV2, tokens: &[ListedToken], own: Option<&str>) -> Vec<Finding> {
+    let mut findings = collisions("symbol", &data.symbol, tokens, own, |t| &t.symbol);
+    findings.extend(collisions("name", &data.name, tokens, own, |t| &t.name));
+    findings
+}
+
+fn collisions(
+    field: &'static str,
+    ours: &str,
+    tokens: &[ListedToken],
+    own: Option<&str>,
+    theirs: fn(&ListedToken) -> &str,
+) -> Vec<Finding> {
+    let ours = ours.trim();
+    let folded = fold(ours);
+    if folded.is_empty() {
+        return Vec::new();
+    }
+    let matches: Vec<&ListedToken> = tokens
+        .iter()
+        .filter(|t| Some(t.address.as_str()) != own && fold(theirs(t)) == folded)
+        .collect();
+    let mut findings: Vec<Finding> = matches
+        .iter()
+        .take(MAX_LISTED)
+        .map(|token| {
+            let how = if theirs(token).eq_ignore_ascii_case(ours) {
+                "is already used by"
+            } else {
+                "looks like that of"
+            };
+            Finding::warning(
+                field,
+                format!(
+                    "'{ours}' {how} {} ({}, {})",
+                    token.name, token.symbol, token.address
+                ),
+            )
+        })
+        .collect();
+    if matches.len() > MAX_LISTED {
+        findings.push(Finding::warning(
+            field,
+            format!(
+                "'{ours}' matches {} more listed token(s)",
+                matches.len() - MAX_LISTED
+            ),
+        ));
+    }
+    findings
+}
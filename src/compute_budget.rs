@@ -0,0 +1,75 @@
+//! Compute Budget program: a transaction's compute unit limit and the
+//! priority fee paid per unit, which decides how soon a leader includes it
+//! when blocks are contended.
+
+use crate::pubkey::Pubkey;
+use crate::system;
+use crate::tx::Instruction;
+
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
+
+/// Most compute units a transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Headroom over a simulation's consumption, in percent and at least
+/// [`MIN_COMPUTE_UNIT_MARGIN`] units: state can change between simulating
+/// and landing, and the limit instruction itself costs a few units.
+pub const COMPUTE_UNIT_MARGIN_PERCENT: u64 = 10;
+pub const MIN_COMPUTE_UNIT_MARGIN: u64 = 1_000;
+
+const IX_SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const IX_SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![IX_SET_COMPUTE_UNIT_LIMIT];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction {
+        program_id: COMPUTE_BUDGET_PROGRAM_ID,
+        accounts: Vec::new(),
+        data,
+    }
+}
+
+/// The limit to request for a transaction a simulation measured at
+/// `consumed` units.
+pub fn measured_limit(consumed: u64) -> u32 {
+    let margin = (consumed * COMPUTE_UNIT_MARGIN_PERCENT / 100).max(MIN_COMPUTE_UNIT_MARGIN);
+    u32::try_from(consumed.saturating_add(margin))
+        .unwrap_or(u32::MAX)
+        .min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// Whether `instructions` already set a compute unit limit.
+pub fn has_compute_unit_limit(instructions: &[Instruction]) -> bool {
+    instructions.iter().any(is_compute_unit_limit)
+}
+
+/// `instructions` with their compute unit limit set to `units`, replacing
+/// one already there or going first; after the nonce advance of a durable
+/// nonce transaction, which must stay first.
+pub fn with_compute_unit_limit(instructions: &[Instruction], units: u32) -> Vec<Instruction> {
+    let mut out: Vec<Instruction> = instructions
+        .iter()
+        .filter(|ix| !is_compute_unit_limit(ix))
+        .cloned()
+        .collect();
+    let at = usize::from(out.first().is_some_and(system::is_advance_nonce_account));
+    out.insert(at, set_compute_unit_limit(units));
+    out
+}
+
+fn is_compute_unit_limit(ix: &Instruction) -> bool {
+    ix.program_id == COMPUTE_BUDGET_PROGRAM_ID
+        && ix.data.first() == Some(&IX_SET_COMPUTE_UNIT_LIMIT)
+}
+
+/// Priority fee in micro-lamports per requested compute unit.
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let mut data = vec![IX_SET_COMPUTE_UNIT_PRICE];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+    Instruction {
+        program_id: COMPUTE_BUDGET_PROGRAM_ID,
+        accounts: Vec::new(),
+        data,
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::accounting::DEFAULT_PRICE_HISTORY_API;
+use crate::broadcast::SenderPolicy;
+use crate::checklist::Checklist;
+use crate::collision::DEFAULT_TOKEN_LIST;
+use crate::error::{Error, Result};
+use crate::keypair::Keypair;
+pub use crate::network::Network;
+use crate::plugin::PLUGIN_TIMEOUT;
+use crate::policy::{Policy, PolicySigner};
+use crate::portfolio::DEFAULT_PRICE_API;
+use crate::preview::DEFAULT_WARN_TERMS;
+use crate::programs::Programs;
+use crate::pubkey::Pubkey;
+use crate::rpc::{Commitment, RPC_TIMEOUT};
+use crate::signer::{self, Signer};
+use crate::storage::{DEFAULT_IPFS_GATEWAY, PinataUploader};
+use crate::swap::DEFAULT_SWAP_API;
+use crate::validator::STARTUP_TIMEOUT;
+
+const APP_DIR: &str = "launch-solana";
+const CONFIG_FILE: &str = "config.json";
+
+/// Persistent user settings, stored as JSON in the platform config directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub network: Network,
+    /// Overrides the network's public RPC endpoint.
+    pub rpc_url: Option<String>,
+    /// Gateway prefix used to build HTTPS links to IPFS content.
+    pub ipfs_gateway: Option<String>,
+    /// Extra RPC endpoints launch transactions are also sent to.
+    pub broadcast_urls: Vec<String>,
+    /// Endpoint that submits launch transactions in place of the RPC,
+    /// which then only serves reads.
+    pub sender_url: Option<String>,
+    pub sender_policy: SenderPolicy,
+    /// Jupiter-compatible USD price endpoint used by `portfolio`.
+    pub price_api_url: Option<String>,
+    /// CoinGecko-compatible endpoint for the historical USD prices
+    /// `export-accounting` values entries at.
+    pub price_history_api_url: Option<String>,
+    /// What must hold before `lp deploy` adds liquidity.
+    pub launch_checklist: Checklist,
+    /// Jupiter-compatible swap endpoint used by `test-buy`.
+    pub swap_api_url: Option<String>,
+    /// Token list new names and symbols are checked against for
+    /// collisions; Jupiter's verified list on mainnet unless set.
+    pub token_list_url: Option<String>,
+    /// Terms a name or symbol is warned about; the built-in list of
+    /// stablecoin, exchange and wallet names unless set.
+    pub warn_terms: Option<Vec<String>>,
+    /// Terms a name or symbol may not contain at all.
+    pub deny_terms: Vec<String>,
+    /// Show and accept every launch parameter, not just the common ones.
+    pub expert: bool,
+    /// Wallets inspected by address only; their keys are never present.
+    pub watch_wallets: Vec<WatchWallet>,
+    /// Commitment for reads and ordinary confirmations.
+    pub commitment: Commitment,
+    /// Commitment awaited after revoking or handing over an authority;
+    /// finalized unless set.
+    pub critical_commitment: Option<Commitment>,
+    /// Program addresses per network, for clusters that deploy them
+    /// elsewhere; unlisted programs keep their canonical address.
+    pub programs: HashMap<Network, Programs>,
+    pub timeouts: Timeouts,
+    /// Keep local usage statistics (see `stats`); off unless set.
+    pub stats: bool,
+    /// GitHub repository `self-update` takes releases from, as OWNER/NAME.
+    pub update_repo: Option<String>,
+    /// Key that must have signed a release's version and checksums for
+    /// `self-update` to install it.
+    pub update_key: Option<Pubkey>,
+    /// Paths to external programs by name, e.g. `"solana-test-validator":
+    /// "/opt/solana/bin/solana-test-validator"`, for ones not on `PATH`.
+    pub tools: HashMap<String, PathBuf>,
+    /// Labels for addresses the built-in ones don't cover, or replacing
+    /// them, e.g. `"CPMMoo8L…": "Raydium CPMM"`.
+    pub labels: HashMap<Pubkey, String>,
+}
+
+/// Seconds an operation may take before it is abandoned; unset ones keep
+/// their defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timeouts {
+    /// One RPC request.
+    pub rpc: Option<u64>,
+    /// One plugin run.
+    pub plugin: Option<u64>,
+    /// `solana-test-validator` starting to answer, for `--local`.
+    pub validator: Option<u64>,
+}
+
+impl Timeouts {
+    pub fn rpc(&self) -> Duration {
+        self.rpc.map_or(RPC_TIMEOUT, Duration::from_secs)
+    }
+
+    pub fn plugin(&self) -> Duration {
+        self.plugin.map_or(PLUGIN_TIMEOUT, Duration::from_secs)
+    }
+
+    pub fn validator(&self) -> Duration {
+        self.validator.map_or(STARTUP_TIMEOUT, Duration::from_secs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchWallet {
+    pub label: String,
+    pub address: Pubkey,
+}
+
+impl Config {
+    pub fn dir() -> Result<PathBuf> {
+        dirs::config_dir()
+            .map(|d| d.join(APP_DIR))
+            .ok_or_else(|| Error::Config("could not determine config directory".into()))
+    }
+
+    pub fn path() -> Result<PathBuf> {
+        Ok(Self::dir()?.join(CONFIG_FILE))
+    }
+
+    /// Loads the config file, falling back to defaults when it does not exist yet.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = fs::read_to_string(&path)?;
+        serde_json::from_str(&raw).map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn critical_commitment(&self) -> Commitment {
+        self.critical_commitment.unwrap_or(Commitment::Finalized)
+    }
+
+    pub fn rpc_url(&self) -> String {
+        self.rpc_url
+            .clone()
+            .unwrap_or_else(|| self.network.default_rpc_url().to_string())
+    }
+
+    pub fn programs(&self) -> Programs {
+        self.programs
+            .get(&self.network)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn price_api_url(&self) -> &str {
+        self.price_api_url.as_deref().unwrap_or(DEFAULT_PRICE_API)
+    }
+
+    pub fn price_history_api_url(&self) -> &str {
+        self.price_history_api_url
+            .as_deref()
+            .unwrap_or(DEFAULT_PRICE_HISTORY_API)
+    }
+
+    pub fn swap_api_url(&self) -> &str {
+        self.swap_api_url.as_deref().unwrap_or(DEFAULT_SWAP_API)
+    }
+
+    /// The token list to check for collisions on this network, if any.
+    pub fn token_list_url(&self) -> Option<&str> {
+        self.token_list_url
+            .as_deref()
+            .or((self.network == Network::Mainnet).then_some(DEFAULT_TOKEN_LIST))
+    }
+
+    pub fn warn_terms(&self) -> Vec<String> {
+        self.warn_terms
+            .clone()
+            .unwrap_or_else(|| DEFAULT_WARN_TERMS.iter().map(|t| t.to_string()).collect())
+    }
+
+    /// A watched wallet by label or address.
+    pub fn watch_wallet(&self, label_or_address: &str) -> Option<&WatchWallet> {
+        self.watch_wallets
+            .iter()
+            .find(|w| w.label == label_or_address || w.address.to_string() == label_or_address)
+    }
+
+    pub fn tool(&self, name: &str) -> Option<&Path> {
+        self.tools.get(name).map(PathBuf::as_path)
+    }
+
+    pub fn ipfs_gateway(&self) -> &str {
+        self.ipfs_gateway.as_deref().unwrap_or(DEFAULT_IPFS_GATEWAY)
+    }
+}
+
+/// Secrets read from a `.env` file in the working directory, overridden by
+/// real environment variables of the same name.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    pub dev_keypair: Option<String>,
+    pub pinata_api_key: Option<String>,
+    pub pinata_secret_api_key: Option<String>,
+    pub mint_passphrase: Option<String>,
+    /// Where DEV_KEYPAIR's signatures come from instead, as a signer
+    /// specifier (see `signer`).
+    pub dev_signer: Option<String>,
+    pub signer_token: Option<String>,
+    /// Policy file that limits what [`Credentials::signer`] will sign.
+    pub signing_policy: Option<String>,
+}
+
+impl Credentials {
+    pub fn load() -> Result<Self> {
+        Self::load_from(Path::new(".env"))
+    }
+
+    pub fn load_from(dotenv: &Path) -> Result<Self> {
+        let file = if dotenv.exists() {
+            parse_dotenv(&fs::read_to_string(dotenv)?)
+        } else {
+            HashMap::new()
+        };
+        let get = |key: &str| {
+            env::var(key)
+                .ok()
+                .or_else(|| file.get(key).cloned())
+                .filter(|v| !v.is_empty())
+        };
+        Ok(Credentials {
+            dev_keypair: get("DEV_KEYPAIR"),
+            pinata_api_key: get("PINATA_API_KEY"),
+            // Older release archives shipped the misspelled key name.
+            pinata_secret_api_key: get("PINATA_SECRET_API_KEY")
+                .or_else(|| get("PINATA_SECRET_API_LEY")),
+            mint_passphrase: get("MINT_PASSPHRASE"),
+            dev_signer: get("DEV_SIGNER"),
+            signer_token: get("SIGNER_TOKEN"),
+            signing_policy: get("SIGNING_POLICY"),
+        })
+    }
+
+    pub fn keypair(&self) -> Result<Keypair> {
+        let value = self
+            .dev_keypair
+            .as_deref()
+            .ok_or_else(|| Error::Config("DEV_KEYPAIR is not set in .env".into()))?;
+        Keypair::from_setting(value)
+    }
+
+    /// The signer named by DEV_SIGNER, or else the DEV_KEYPAIR keypair,
+    /// held to SIGNING_POLICY when one is set, read against the network's
+    /// `programs`. Only for commands that just sign; those that derive keys
+    /// from the wallet's secret need [`Credentials::keypair`].
+    pub fn signer(&self, programs: &Programs) -> Result<Box<dyn Signer>> {
+        let signer: Box<dyn Signer> = match &self.dev_signer {
+            Some(spec) => signer::open(&spec.parse()?, self.signer_token.clone())?,
+            None => Box::new(self.keypair()?),
+        };
+        Ok(match &self.signing_policy {
+            Some(path) => Box::new(PolicySigner {
+                inner: signer,
+                policy: Policy::read_file(Path::new(path), *programs)?,
+            }),
+            None => signer,
+        })
+    }
+
+    /// The project passphrase mint keys are derived from; see `phrase`.
+    pub fn mint_passphrase(&self) -> Result<&str> {
+        self.mint_passphrase
+            .as_deref()
+            .ok_or_else(|| Error::Config("MINT_PASSPHRASE is not set in .env".into()))
+    }
+
+    pub fn pinata(&self, config: &Config) -> Result<PinataUploader> {
+        match (&self.pinata_api_key, &self.pinata_secret_api_key) {
+            (Some(key), Some(secret)) => Ok(PinataUploader::new(
+                key.clone(),
+                secret.clone(),
+                config.ipfs_gateway(),
+            )),
+            _ => Err(Error::Config(
+                "PINATA_API_KEY and PINATA_SECRET_API_KEY must be set in .env".into(),
+            )),
+        }
+    }
+}
+
+/// Parses `KEY=VALUE` lines, ignoring blanks and `#` comments and stripping quotes.
+fn parse_dotenv(raw: &str) -> HashMap<String, String> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,167 @@
+//! Cron schedules: the five-field `minute hour day-of-month month
+//! day-of-week` expressions crontab uses, in UTC, e.g. `0 12 * * 1` for
+//! noon every Monday. Fields take `*`, numbers, ranges, lists and steps
+//! (`*/15`, `1-5`, `0,30`); `@hourly`, `@daily`, `@weekly` and `@monthly`
+//! stand for the usual expressions. As in crontab, a day matches if either
+//! day field does when both are restricted.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+use crate::time::civil_from_days;
+
+/// How far ahead the next time is looked for; every valid expression
+/// matches within four years, the leap day cycle.
+const SEARCH_DAYS: i64 = 4 * 366;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day-of-month and day-of-week fields are `*`.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// The first time matching the schedule strictly after `unix`, on a
+    /// whole minute.
+    pub fn next_after(&self, unix: i64) -> Option<i64> {
+        let start = unix.div_euclid(60) + 1;
+        let first_day = start.div_euclid(1440);
+        for day in first_day..first_day + SEARCH_DAYS {
+            if !self.matches_day(day) {
+                continue;
+            }
+            let from = if day == first_day {
+                start.rem_euclid(1440)
+            } else {
+                0
+            };
+            for minute_of_day in from..1440 {
+                if has(self.hours, minute_of_day / 60) && has(self.minutes, minute_of_day % 60) {
+                    return Some((day * 1440 + minute_of_day) * 60);
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, day: i64) -> bool {
+        let (_, month, day_of_month) = civil_from_days(day);
+        if !has(self.months, i64::from(month)) {
+            return false;
+        }
+        // 1970-01-01 was a Thursday.
+        let weekday = (day + 4).rem_euclid(7);
+        let by_date = has(self.days, i64::from(day_of_month));
+        let by_weekday = has(self.weekdays, weekday);
+        match (self.any_day, self.any_weekday) {
+            (false, false) => by_date || by_weekday,
+            _ => by_date && by_weekday,
+        }
+    }
+}
+
+fn has(mask: u64, value: i64) -> bool {
+    (0..64).contains(&value) && mask & (1 << value) != 0
+}
+
+/// Parses one field into a bit mask of the values it allows.
+fn field(text: &str, name: &str, min: u32, max: u32) -> Result<u64> {
+    let invalid = || Error::InvalidInput(format!("invalid {name} field '{text}' in cron schedule"));
+    let mut mask = 0u64;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (low, high) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((low, high)) => (
+                    low.parse().map_err(|_| invalid())?,
+                    high.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let value = range.parse().map_err(|_| invalid())?;
+                    // `5/10` means every tenth from 5 onwards.
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if low < min || high > max || low > high {
+            return Err(invalid());
+        }
+        for value in (low..=high).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for Cron {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let source = s.trim();
+        let expanded = match source {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(Error::InvalidInput(format!(
+                "'{source}' is not a cron schedule; expected five fields, \
+                 minute hour day-of-month month day-of-week, e.g. '0 0 * * *'"
+            )));
+        };
+        let mut weekdays = field(weekday, "day-of-week", 0, 7)?;
+        // Both 0 and 7 are Sunday.
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Cron {
+            source: source.to_string(),
+            minutes: field(minute, "minute", 0, 59)?,
+            hours: field(hour, "hour", 0, 23)?,
+            days: field(day, "day-of-month", 1, 31)?,
+            months: field(month, "month", 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Serialize for Cron {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cron {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,12 @@
+//! Minimal CSV field quoting for the exporters.
+
+use std::borrow::Cow;
+
+/// Quotes a field when it contains a delimiter, quote or line break.
+pub fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
@@ -0,0 +1,244 @@
+//! Test deposits to an exchange. Before crediting a new token an exchange
+//! asks for a small deposit to an address it provides, often with a memo
+//! identifying the account, and then for evidence: the token's facts and
+//! the finalized transaction. This sends the deposit, waits for it to be
+//! finalized and gathers that evidence in one document.
+//!
+//! Exchanges hand out either a wallet, whose associated token account
+//! receives the deposit, or a token account directly; both are accepted.
+//! The memo goes before the transfer, as accounts that require memos
+//! demand.
+
+use serde::Serialize;
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::extension;
+use crate::keypair::Signature;
+use crate::launch::now_unix;
+use crate::memo;
+use crate::metadata;
+use crate::network::Network;
+use crate::pubkey::Pubkey;
+use crate::rpc::{Commitment, RpcClient};
+use crate::signer::Signer;
+use crate::time;
+use crate::token::{self, MintAccount, TOKEN_2022_PROGRAM_ID, TokenAccount};
+use crate::tx::Instruction;
+
+/// Where the deposit lands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Destination {
+    /// The address the exchange gave.
+    pub address: Pubkey,
+    pub owner: Pubkey,
+    pub token_account: Pubkey,
+    /// Whether the token account exists yet; the deposit creates it if not.
+    pub exists: bool,
+}
+
+/// Works out which token account `address` stands for: itself, if it is a
+/// token account of `mint`, or the associated token account of the wallet
+/// it names.
+pub fn resolve(
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    info: &MintAccount,
+    address: &Pubkey,
+) -> Result<Destination> {
+    let account = rpc.get_account(address)?.value;
+    if let Some(account) = &account
+        && account.owner == info.program_id
+        && account.data.len() >= token::ACCOUNT_LEN
+    {
+        let held = TokenAccount::unpack(&account.data)?;
+        if held.mint != *mint {
+            return Err(Error::InvalidInput(format!(
+                "{address} is a token account of {}, not {mint}; ask the exchange for \
+                 this token's deposit address",
+                held.mint
+            )));
+        }
+        return Ok(Destination {
+            address: *address,
+            owner: held.owner,
+            token_account: *address,
+            exists: true,
+        });
+    }
+    let token_account = token::associated_token_address(address, mint, &info.program_id);
+    Ok(Destination {
+        address: *address,
+        owner: *address,
+        token_account,
+        exists: rpc.get_account(&token_account)?.value.is_some(),
+    })
+}
+
+/// A deposit to send, its destination already resolved.
+#[derive(Debug, Clone)]
+pub struct DepositTest {
+    pub mint: Pubkey,
+    pub info: MintAccount,
+    pub destination: Destination,
+    pub amount: TokenAmount,
+    pub memo: Option<String>,
+}
+
+impl DepositTest {
+    /// The memo first, creating the associated token account if it is
+    /// missing, then the transfer.
+    pub fn instructions(&self, sender: &Pubkey) -> Result<Vec<Instruction>> {
+        let program_id = self.info.program_id;
+        let mut ixs = Vec::new();
+        if let Some(text) = &self.memo {
+            memo::validate(text)?;
+            ixs.push(memo::memo(text, &[sender]));
+        }
+        if !self.destination.exists {
+            ixs.push(token::create_associated_token_account_idempotent(
+                sender,
+                &self.destination.owner,
+                &self.mint,
+                &program_id,
+            ));
+        }
+        ixs.push(token::transfer_checked(
+            &program_id,
+            &token::associated_token_address(sender, &self.mint, &program_id),
+            &self.mint,
+            &self.destination.token_account,
+            sender,
+            self.amount.to_u64()?,
+            self.info.mint.decimals,
+        ));
+        Ok(ixs)
+    }
+
+    /// Sends the deposit, waits until it is finalized and gathers the
+    /// evidence.
+    pub fn run(&self, rpc: &RpcClient, network: Network, sender: &dyn Signer) -> Result<Evidence> {
+        let sender_key = sender.pubkey();
+        let token_account = &self.destination.token_account;
+        let decimals = self.info.mint.decimals;
+        let balance_before = balance(rpc, token_account, decimals)?;
+        let tx = rpc.build_signed(&self.instructions(&sender_key)?, &sender_key, &[sender])?;
+        let signature = rpc.send_transaction(&tx)?;
+        rpc.confirm_transaction_at(&signature, Commitment::Finalized)?;
+        let landed = rpc
+            .get_transaction(&signature.to_string())?
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("{signature} is finalized but not served"))
+            })?;
+        let deposit = DepositFacts {
+            sender: sender_key,
+            destination: self.destination,
+            amount: self.amount,
+            memo: self.memo.clone(),
+            signature,
+            commitment: Commitment::Finalized,
+            slot: landed.slot,
+            block_time: landed.block_time.map(time::format_rfc3339),
+            fee_lamports: landed.meta.as_ref().map_or(0, |m| m.fee),
+            balance_before,
+            balance_after: balance(rpc, token_account, decimals)?,
+            explorer: network.explorer_url(&format!("tx/{signature}")),
+        };
+        Ok(Evidence {
+            network,
+            generated_at: time::format_rfc3339(now_unix()),
+            token: token_facts(rpc, network, &self.mint, &self.info)?,
+            deposit,
+        })
+    }
+}
+
+/// The token as an exchange reviews it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenFacts {
+    pub mint: Pubkey,
+    pub program: &'static str,
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    pub supply: TokenAmount,
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+    pub extensions: Vec<&'static str>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub uri: Option<String>,
+    pub metadata_mutable: Option<bool>,
+    pub explorer: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositFacts {
+    pub sender: Pubkey,
+    pub destination: Destination,
+    pub amount: TokenAmount,
+    pub memo: Option<String>,
+    pub signature: Signature,
+    pub commitment: Commitment,
+    pub slot: u64,
+    pub block_time: Option<String>,
+    pub fee_lamports: u64,
+    /// The destination's balance before and after, in tokens.
+    pub balance_before: TokenAmount,
+    pub balance_after: TokenAmount,
+    pub explorer: String,
+}
+
+/// What a listing team asks for after a test deposit.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Evidence {
+    pub network: Network,
+    pub generated_at: String,
+    pub token: TokenFacts,
+    pub deposit: DepositFacts,
+}
+
+/// The token's facts, read at the time of the deposit.
+pub fn token_facts(
+    rpc: &RpcClient,
+    network: Network,
+    mint: &Pubkey,
+    info: &MintAccount,
+) -> Result<TokenFacts> {
+    let on_chain = metadata::fetch_metadata(rpc, mint)?;
+    Ok(TokenFacts {
+        mint: *mint,
+        program: if info.program_id == TOKEN_2022_PROGRAM_ID {
+            "token-2022"
+        } else {
+            "spl-token"
+        },
+        program_id: info.program_id,
+        decimals: info.mint.decimals,
+        supply: info.mint.supply_amount(),
+        mint_authority: info.mint.mint_authority,
+        freeze_authority: info.mint.freeze_authority,
+        extensions: info
+            .extensions
+            .iter()
+            .map(|e| extension::name(e.kind))
+            .collect(),
+        name: on_chain.as_ref().map(|m| m.data.name.clone()),
+        symbol: on_chain.as_ref().map(|m| m.data.symbol.clone()),
+        uri: on_chain.as_ref().map(|m| m.data.uri.clone()),
+        metadata_mutable: on_chain.as_ref().map(|m| m.is_mutable),
+        explorer: network.explorer_url(&format!("token/{mint}")),
+    })
+}
+
+/// The balance of `token_account`, zero if it doesn't exist.
+pub fn balance(rpc: &RpcClient, token_account: &Pubkey, decimals: u8) -> Result<TokenAmount> {
+    let raw = match rpc.get_account(token_account)?.value {
+        Some(account) => TokenAccount::unpack(&account.data)?.amount,
+        None => 0,
+    };
+    Ok(TokenAmount::from_raw(raw.into(), decimals))
+}
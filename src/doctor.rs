@@ -0,0 +1,243 @@
+//! `doctor`: a checklist run before a launch, so a missing tool, a wrong
+//! endpoint, an unfunded wallet or a skewed clock shows up while it is
+//! still cheap to fix.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::config::{Config, Credentials};
+use crate::error::Result;
+use crate::launch::now_unix;
+use crate::process;
+use crate::pubkey::Pubkey;
+use crate::rpc::{LATENCY_WARNING, RpcClient};
+use crate::system;
+use crate::validator::VALIDATOR_BIN;
+
+/// Seconds the machine's clock may differ from the cluster's before
+/// blockhash and schedule timing go wrong.
+const MAX_CLOCK_SKEW_SECS: i64 = 30;
+/// Below this a launch is likely to run out of SOL partway.
+const LOW_BALANCE_LAMPORTS: u64 = 50_000_000;
+/// External programs some workflows run; none is needed for a launch.
+const OPTIONAL_TOOLS: [&str; 2] = ["solana", VALIDATOR_BIN];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Status::Pass => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, status: Status, detail: impl Into<String>) -> Self {
+        Check {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+
+    fn from_result(name: &str, result: Result<(Status, String)>) -> Self {
+        match result {
+            Ok((status, detail)) => Check::new(name, status, detail),
+            Err(e) => Check::new(name, Status::Fail, e.to_string()),
+        }
+    }
+}
+
+/// Runs every check against `config`'s network through `rpc`. When the
+/// endpoint can't be reached, the checks that need it are left out rather
+/// than each failing with the same error.
+pub fn run(config: &Config, rpc: &RpcClient) -> Vec<Check> {
+    let mut checks = vec![Check::new(
+        "solanaapp",
+        Status::Pass,
+        format!("version {}", env!("CARGO_PKG_VERSION")),
+    )];
+    checks.extend(OPTIONAL_TOOLS.iter().map(|tool| tool_check(config, tool)));
+    checks.push(Check::from_result(
+        "config directory",
+        writable_config_dir(),
+    ));
+    checks.push(Check::from_result(
+        "temp directory",
+        writable(&std::env::temp_dir()),
+    ));
+
+    let endpoint = Check::from_result("RPC endpoint", endpoint(rpc));
+    let reachable = endpoint.status != Status::Fail;
+    checks.push(endpoint);
+    if reachable {
+        checks.push(Check::from_result(
+            "genesis hash",
+            rpc.get_genesis_hash().and_then(|hash| {
+                config.network.check_genesis_hash(&hash)?;
+                Ok((Status::Pass, format!("{hash} is {}", config.network)))
+            }),
+        ));
+        // Nodes prune old blocks and some don't serve block times at all,
+        // so not being able to compare is only worth a warning.
+        checks.push(match clock(rpc) {
+            Err(e) => Check::new("clock", Status::Warn, format!("not compared: {e}")),
+            result => Check::from_result("clock", result),
+        });
+        checks.push(Check::from_result("programs", programs(rpc)));
+    }
+
+    let wallet = Credentials::load()
+        .and_then(|c| c.signer(rpc.programs()))
+        .map(|s| s.pubkey());
+    match wallet {
+        Ok(wallet) => {
+            checks.push(Check::new("DEV_KEYPAIR", Status::Pass, wallet.to_string()));
+            if reachable {
+                checks.push(Check::from_result("balance", balance(rpc, &wallet)));
+            }
+        }
+        Err(e) => checks.push(Check::new("DEV_KEYPAIR", Status::Fail, e.to_string())),
+    }
+    checks
+}
+
+fn tool_check(config: &Config, tool: &str) -> Check {
+    let program = match process::find_tool(tool, config.tool(tool)) {
+        Ok(program) => program,
+        Err(_) => {
+            return Check::new(
+                tool,
+                Status::Warn,
+                "not found; only needed by some workflows",
+            );
+        }
+    };
+    let output = Command::new(&program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout);
+            let version = version.lines().next().unwrap_or("").trim();
+            Check::new(
+                tool,
+                Status::Pass,
+                format!("{version} at {}", program.display()),
+            )
+        }
+        Ok(output) => Check::new(
+            tool,
+            Status::Warn,
+            format!(
+                "{} --version exited with {}",
+                program.display(),
+                output.status
+            ),
+        ),
+        Err(e) => Check::new(tool, Status::Warn, format!("{}: {e}", program.display())),
+    }
+}
+
+fn writable_config_dir() -> Result<(Status, String)> {
+    let dir = Config::dir()?;
+    fs::create_dir_all(&dir)?;
+    writable(&dir)
+}
+
+/// Writes and removes a scratch file in `dir`.
+fn writable(dir: &Path) -> Result<(Status, String)> {
+    let probe = dir.join(format!(".doctor-{}", std::process::id()));
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)?;
+    Ok((Status::Pass, format!("{} is writable", dir.display())))
+}
+
+fn endpoint(rpc: &RpcClient) -> Result<(Status, String)> {
+    let probe = rpc.probe()?;
+    let detail = format!(
+        "{} answers in {} ms at slot {}",
+        rpc.url(),
+        probe.latency.as_millis(),
+        probe.slot
+    );
+    let status = if probe.latency > LATENCY_WARNING {
+        Status::Warn
+    } else {
+        Status::Pass
+    };
+    Ok((status, detail))
+}
+
+fn clock(rpc: &RpcClient) -> Result<(Status, String)> {
+    let slot = rpc.get_slot()?;
+    let Some(block_time) = rpc.get_block_time(slot)? else {
+        return Ok((
+            Status::Warn,
+            format!("the cluster has no time for slot {slot}"),
+        ));
+    };
+    let skew = now_unix() - block_time;
+    let detail = format!("{skew:+}s from the cluster");
+    if skew.abs() > MAX_CLOCK_SKEW_SECS {
+        return Ok((Status::Fail, format!("{detail}; fix the system clock")));
+    }
+    Ok((Status::Pass, detail))
+}
+
+fn programs(rpc: &RpcClient) -> Result<(Status, String)> {
+    let programs = rpc.programs();
+    let named = [
+        ("token", programs.token),
+        ("token-2022", programs.token_2022),
+        ("metadata", programs.metadata),
+    ];
+    let keys: Vec<Pubkey> = named.iter().map(|(_, id)| *id).collect();
+    let accounts = rpc.get_multiple_accounts(&keys, None)?.value;
+    let missing: Vec<String> = named
+        .iter()
+        .zip(accounts)
+        .filter(|(_, account)| !account.as_ref().is_some_and(|a| a.executable))
+        .map(|((name, id), _)| format!("{name} ({id})"))
+        .collect();
+    if missing.is_empty() {
+        return Ok((
+            Status::Pass,
+            "token, token-2022 and metadata are deployed".into(),
+        ));
+    }
+    Ok((
+        Status::Fail,
+        format!("not deployed: {}", missing.join(", ")),
+    ))
+}
+
+fn balance(rpc: &RpcClient, wallet: &Pubkey) -> Result<(Status, String)> {
+    let lamports = rpc.get_balance(wallet)?;
+    let detail = format!("{} SOL", system::format_sol(lamports));
+    Ok(match lamports {
+        0 => (Status::Fail, format!("{detail}; fund the wallet first")),
+        l if l < LOW_BALANCE_LAMPORTS => {
+            (Status::Warn, format!("{detail}; may not cover a launch"))
+        }
+        _ => (Status::Pass, detail),
+    })
+}
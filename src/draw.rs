@@ -0,0 +1,217 @@
+//! Verifiable random draws for fair-launch airdrops. The seed is the hash of
+//! a block that did not exist when the draw was announced, combined with a
+//! digest of the participant list, so neither the organiser nor a
+//! participant can steer the result and anyone can reproduce it.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::pubkey::Pubkey;
+
+/// Parses a participant list: one address per line, or a CSV whose first
+/// column is the address. Blank lines, `#` comments and a header row are
+/// skipped; duplicates are dropped so entering twice gains nothing.
+pub fn parse_participants(text: &str) -> Result<Vec<Pubkey>> {
+    let mut seen = HashSet::new();
+    let mut participants = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let field = line.split(',').next().unwrap_or_default().trim();
+        match field.parse::<Pubkey>() {
+            Ok(key) => {
+                if seen.insert(key) {
+                    participants.push(key);
+                }
+            }
+            Err(_) if i == 0 => continue,
+            Err(_) => {
+                return Err(Error::InvalidInput(format!(
+                    "line {}: '{field}' is not an address",
+                    i + 1
+                )));
+            }
+        }
+    }
+    Ok(participants)
+}
+
+/// Commits to the participant set independently of the file's order.
+pub fn participants_digest(participants: &[Pubkey]) -> String {
+    let mut sorted = participants.to_vec();
+    sorted.sort();
+    let mut hasher = Sha256::new();
+    for key in &sorted {
+        hasher.update(key.as_bytes());
+    }
+    hex(&hasher.finalize())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Deterministic stream of numbers: SHA-256 of the seed and a counter.
+struct SeedStream {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl SeedStream {
+    fn new(blockhash: &str, participants_digest: &str) -> Self {
+        let seed = Sha256::new()
+            .chain_update(b"launch-solana draw")
+            .chain_update(blockhash.as_bytes())
+            .chain_update(participants_digest.as_bytes())
+            .finalize()
+            .into();
+        SeedStream { seed, counter: 0 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let block = Sha256::new()
+            .chain_update(self.seed)
+            .chain_update(self.counter.to_le_bytes())
+            .finalize();
+        self.counter += 1;
+        u64::from_le_bytes(block[..8].try_into().unwrap())
+    }
+
+    /// Uniform in `0..n`; rejection sampling keeps it unbiased.
+    fn below(&mut self, n: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % n;
+            }
+        }
+    }
+}
+
+/// Picks `winners` participants. The list is sorted first, so the result
+/// depends only on who takes part, the blockhash and the count.
+pub fn pick(participants: &[Pubkey], blockhash: &str, winners: usize) -> Result<Vec<Pubkey>> {
+    if winners == 0 || winners > participants.len() {
+        return Err(Error::InvalidInput(format!(
+            "cannot draw {winners} winners from {} participants",
+            participants.len()
+        )));
+    }
+    let mut pool = participants.to_vec();
+    pool.sort();
+    let mut stream = SeedStream::new(blockhash, &participants_digest(participants));
+    // Partial Fisher-Yates: position i receives a uniformly chosen remaining entry.
+    for i in 0..winners {
+        let j = i + stream.below((pool.len() - i) as u64) as usize;
+        pool.swap(i, j);
+    }
+    pool.truncate(winners);
+    Ok(pool)
+}
+
+/// Splits `total` evenly; the first winners drawn get the base units left over.
+pub fn split_evenly(total: TokenAmount, count: usize) -> Vec<TokenAmount> {
+    let count = count as u128;
+    let base = total.raw() / count;
+    let remainder = total.raw() % count;
+    (0..count)
+        .map(|i| TokenAmount::from_raw(base + u128::from(i < remainder), total.decimals()))
+        .collect()
+}
+
+/// Everything needed to reproduce a draw, published with its result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrawRecord {
+    /// Slot announced in advance as the seed source.
+    pub seed_slot: u64,
+    /// First produced block at or after `seed_slot`.
+    pub block_slot: u64,
+    pub blockhash: String,
+    pub participants: usize,
+    pub participants_digest: String,
+    pub total: TokenAmount,
+    pub winners: Vec<Allocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allocation {
+    pub wallet: Pubkey,
+    pub amount: TokenAmount,
+}
+
+impl DrawRecord {
+    pub fn run(
+        participants: &[Pubkey],
+        seed_slot: u64,
+        block_slot: u64,
+        blockhash: String,
+        winners: usize,
+        total: TokenAmount,
+    ) -> Result<Self> {
+        let picked = pick(participants, &blockhash, winners)?;
+        let winners = picked
+            .into_iter()
+            .zip(split_evenly(total, winners))
+            .map(|(wallet, amount)| Allocation { wallet, amount })
+            .collect();
+        Ok(DrawRecord {
+            seed_slot,
+            block_slot,
+            blockhash,
+            participants: participants.len(),
+            participants_digest: participants_digest(participants),
+            total,
+            winners,
+        })
+    }
+
+    /// Redraws from `participants` and reports every way the published
+    /// record differs from the reproduction.
+    pub fn verify(&self, participants: &[Pubkey]) -> Result<Vec<String>> {
+        let mut problems = Vec::new();
+        let digest = participants_digest(participants);
+        if digest != self.participants_digest {
+            problems.push(format!(
+                "participant list differs: digest {digest}, published {}",
+                self.participants_digest
+            ));
+        }
+        let redrawn = DrawRecord::run(
+            participants,
+            self.seed_slot,
+            self.block_slot,
+            self.blockhash.clone(),
+            self.winners.len(),
+            self.total,
+        )?;
+        for (i, (published, expected)) in self.winners.iter().zip(&redrawn.winners).enumerate() {
+            if published != expected {
+                problems.push(format!(
+                    "winner {}: published {} {}, reproduced {} {}",
+                    i + 1,
+                    published.wallet,
+                    published.amount,
+                    expected.wallet,
+                    expected.amount
+                ));
+            }
+        }
+        Ok(problems)
+    }
+
+    /// `wallet,amount` rows for an airdrop.
+    pub fn write_csv(&self, mut out: impl std::io::Write) -> Result<()> {
+        writeln!(out, "wallet,amount")?;
+        for a in &self.winners {
+            writeln!(out, "{},{}", a.wallet, a.amount)?;
+        }
+        Ok(())
+    }
+}
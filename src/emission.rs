@@ -0,0 +1,478 @@
+//! Scheduled emissions: minting a fixed amount to one wallet on a cron
+//! schedule while the mint authority is kept, as staking rewards and
+//! liquidity mining pay out, up to a hard cap on the total.
+//!
+//! An emission lives in a journal, `emission-<mint>.jsonl`: a `started`
+//! line fixing its terms, then one line per mint sent, landed or failed.
+//! A run reads the journal back to find what is owed. Times missed while
+//! nothing ran are paid in one mint at the next run, so a stopped daemon
+//! never loses an emission; the cap holds regardless, and the last mint is
+//! cut short to meet it exactly.
+//!
+//! A mint is journalled as sent before it is broadcast, so one whose
+//! outcome was lost is looked up, once its blockhash has expired, instead
+//! of being minted twice.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::amount::TokenAmount;
+use crate::cron::Cron;
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::launch::now_unix;
+use crate::manager::BLOCKHASH_LIFETIME_SECS;
+use crate::memo;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::signer::Signer;
+use crate::time::format_rfc3339;
+use crate::token;
+
+/// The terms of an emission, fixed when it starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Terms {
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    /// Wallet paid; its associated token account receives the tokens.
+    pub recipient: Pubkey,
+    /// Minted at each scheduled time.
+    pub amount: TokenAmount,
+    pub schedule: Cron,
+    /// The most the emission mints in total.
+    pub cap: TokenAmount,
+    /// Unix seconds; scheduled times before it are not owed.
+    pub starts_at: i64,
+}
+
+/// One line of the journal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EmissionEvent {
+    Started {
+        at: i64,
+        terms: Terms,
+    },
+    /// A mint paying every scheduled time up to `due` was broadcast.
+    Sent {
+        at: i64,
+        due: i64,
+        amount: TokenAmount,
+        signature: Signature,
+    },
+    Minted {
+        at: i64,
+        due: i64,
+        amount: TokenAmount,
+        signature: Signature,
+    },
+    Failed {
+        at: i64,
+        due: i64,
+        error: String,
+    },
+    /// The cap is reached; nothing more will be minted.
+    Capped {
+        at: i64,
+    },
+}
+
+impl fmt::Display for EmissionEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmissionEvent::Started { terms, .. } => write!(
+                f,
+                "emitting {} of {} to {} on '{}', capped at {}",
+                terms.amount, terms.mint, terms.recipient, terms.schedule, terms.cap
+            ),
+            EmissionEvent::Sent {
+                due,
+                amount,
+                signature,
+                ..
+            } => write!(f, "sent {amount} due {}: {signature}", format_rfc3339(*due)),
+            EmissionEvent::Minted {
+                due,
+                amount,
+                signature,
+                ..
+            } => write!(
+                f,
+                "minted {amount} due {}: {signature}",
+                format_rfc3339(*due)
+            ),
+            EmissionEvent::Failed { due, error, .. } => {
+                write!(f, "mint due {} failed: {error}", format_rfc3339(*due))
+            }
+            EmissionEvent::Capped { .. } => f.write_str("cap reached; the emission is complete"),
+        }
+    }
+}
+
+/// Where an emission stands, as its journal tells.
+#[derive(Debug, Clone)]
+pub struct EmissionState {
+    pub terms: Terms,
+    /// Landed mints in total.
+    pub minted: TokenAmount,
+    /// The latest scheduled time paid.
+    pub paid_until: Option<i64>,
+    pub pending: Option<Pending>,
+    pub capped: bool,
+    /// Mints that failed since the last one landed.
+    pub failures: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pending {
+    pub sent_at: i64,
+    pub due: i64,
+    pub amount: TokenAmount,
+    pub signature: Signature,
+}
+
+impl EmissionState {
+    fn new(terms: Terms) -> Self {
+        EmissionState {
+            minted: TokenAmount::from_raw(0, terms.amount.decimals()),
+            terms,
+            paid_until: None,
+            pending: None,
+            capped: false,
+            failures: 0,
+        }
+    }
+
+    fn apply(&mut self, event: &EmissionEvent) {
+        match *event {
+            EmissionEvent::Started { .. } => {}
+            EmissionEvent::Sent {
+                at,
+                due,
+                amount,
+                signature,
+            } => {
+                self.pending = Some(Pending {
+                    sent_at: at,
+                    due,
+                    amount,
+                    signature,
+                });
+            }
+            EmissionEvent::Minted { due, amount, .. } => {
+                self.pending = None;
+                self.minted = self.minted.checked_add(amount).unwrap_or(self.minted);
+                self.paid_until = Some(due);
+                self.failures = 0;
+            }
+            EmissionEvent::Failed { .. } => {
+                self.pending = None;
+                self.failures += 1;
+            }
+            EmissionEvent::Capped { .. } => self.capped = true,
+        }
+    }
+
+    /// What is left under the cap.
+    pub fn remaining(&self) -> TokenAmount {
+        self.terms
+            .cap
+            .checked_sub(self.minted)
+            .unwrap_or(TokenAmount::from_raw(0, self.terms.cap.decimals()))
+    }
+
+    /// The scheduled times owed by `now`, as the latest of them and how
+    /// many there are.
+    pub fn owed(&self, now: i64) -> Option<(i64, u128)> {
+        let mut after = self.paid_until.unwrap_or(self.terms.starts_at - 1);
+        let mut latest = None;
+        let mut count = 0u128;
+        while let Some(next) = self.terms.schedule.next_after(after) {
+            if next > now {
+                break;
+            }
+            latest = Some(next);
+            count += 1;
+            after = next;
+        }
+        latest.map(|due| (due, count))
+    }
+
+    /// The next scheduled time after what is paid.
+    pub fn next_due(&self) -> Option<i64> {
+        let after = self.paid_until.unwrap_or(self.terms.starts_at - 1);
+        self.terms.schedule.next_after(after)
+    }
+}
+
+/// `emission-<mint>.jsonl` in the working directory.
+pub fn default_path(mint: &Pubkey) -> PathBuf {
+    PathBuf::from(format!("emission-{mint}.jsonl"))
+}
+
+/// Reads the journal at `path` back.
+pub fn load(path: &Path) -> Result<EmissionState> {
+    let file = File::open(path).map_err(|e| {
+        Error::InvalidInput(format!(
+            "{}: {e}; start the emission with `emission start`",
+            path.display()
+        ))
+    })?;
+    let mut state: Option<EmissionState> = None;
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: EmissionEvent = serde_json::from_str(&line)
+            .map_err(|e| Error::InvalidInput(format!("{} line {}: {e}", path.display(), i + 1)))?;
+        match (&mut state, &event) {
+            (None, EmissionEvent::Started { terms, .. }) => {
+                state = Some(EmissionState::new(terms.clone()));
+            }
+            (None, _) => {
+                return Err(Error::InvalidInput(format!(
+                    "{} does not start with the emission's terms",
+                    path.display()
+                )));
+            }
+            (Some(state), event) => state.apply(event),
+        }
+    }
+    state.ok_or_else(|| Error::InvalidInput(format!("{} is empty", path.display())))
+}
+
+/// Writes a new journal at `path` with `terms`.
+pub fn start(path: &Path, terms: Terms) -> Result<EmissionState> {
+    if path.exists() {
+        return Err(Error::InvalidInput(format!(
+            "{} already exists; an emission's terms can't be changed once started",
+            path.display()
+        )));
+    }
+    let event = EmissionEvent::Started {
+        at: now_unix(),
+        terms: terms.clone(),
+    };
+    append(path, &event)?;
+    Ok(EmissionState::new(terms))
+}
+
+/// Appends `event` to the journal. Past `start`, the caller holds the
+/// journal's lock, which is advisory, so this handle needs none of its own.
+fn append(path: &Path, event: &EmissionEvent) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Runs an emission journalled at a path.
+pub struct Emitter<'a> {
+    rpc: &'a RpcClient,
+    authority: &'a dyn Signer,
+    path: PathBuf,
+    state: EmissionState,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn open(rpc: &'a RpcClient, authority: &'a dyn Signer, path: &Path) -> Result<Self> {
+        Ok(Emitter {
+            rpc,
+            authority,
+            path: path.to_path_buf(),
+            state: load(path)?,
+        })
+    }
+
+    pub fn state(&self) -> &EmissionState {
+        &self.state
+    }
+
+    fn record(&mut self, event: EmissionEvent) -> Result<EmissionEvent> {
+        append(&self.path, &event)?;
+        self.state.apply(&event);
+        Ok(event)
+    }
+
+    /// Settles a mint whose outcome is unknown and mints what is owed at
+    /// `now`. A failed mint is journalled and returned, not raised, so the
+    /// next round retries it; errors are what stops the emission for good,
+    /// such as the mint authority being gone.
+    pub fn poll(&mut self, now: i64) -> Result<Vec<EmissionEvent>> {
+        // Another run may share the journal. Holding its lock from reading
+        // the state back to journalling the outcome keeps the two from both
+        // paying the same time, or paying past the cap.
+        let journal = File::open(&self.path)?;
+        journal.lock()?;
+        let polled = load(&self.path).and_then(|state| {
+            self.state = state;
+            self.poll_locked(now)
+        });
+        journal.unlock()?;
+        polled
+    }
+
+    fn poll_locked(&mut self, now: i64) -> Result<Vec<EmissionEvent>> {
+        let mut events = Vec::new();
+        if let Some(pending) = self.state.pending {
+            match self.settle(pending, now)? {
+                Some(event) => events.push(self.record(event)?),
+                // Still able to land; decide next round.
+                None => return Ok(events),
+            }
+        }
+        if self.state.capped {
+            return Ok(events);
+        }
+        let remaining = self.state.remaining();
+        if remaining.is_zero() {
+            events.push(self.record(EmissionEvent::Capped { at: now })?);
+            return Ok(events);
+        }
+        let Some((due, count)) = self.state.owed(now) else {
+            return Ok(events);
+        };
+        let owed = self
+            .state
+            .terms
+            .amount
+            .checked_mul(count)
+            .unwrap_or(remaining);
+        let amount = if owed.raw() > remaining.raw() {
+            remaining
+        } else {
+            owed
+        };
+        events.extend(self.mint(due, amount, now)?);
+        Ok(events)
+    }
+
+    /// The outcome of a sent mint, once it is known.
+    fn settle(&self, pending: Pending, now: i64) -> Result<Option<EmissionEvent>> {
+        let status = self
+            .rpc
+            .get_signature_statuses(&[pending.signature], true)?
+            .into_iter()
+            .next()
+            .flatten();
+        Ok(match status {
+            Some(status) if status.err.is_some() => Some(EmissionEvent::Failed {
+                at: now,
+                due: pending.due,
+                error: self
+                    .rpc
+                    .transaction_failure(&pending.signature, &status.err.unwrap_or_default())
+                    .to_string(),
+            }),
+            Some(status) if status.is_confirmed() => Some(EmissionEvent::Minted {
+                at: now,
+                due: pending.due,
+                amount: pending.amount,
+                signature: pending.signature,
+            }),
+            None if now >= pending.sent_at + BLOCKHASH_LIFETIME_SECS => {
+                Some(EmissionEvent::Failed {
+                    at: now,
+                    due: pending.due,
+                    error: format!("{} expired without landing", pending.signature),
+                })
+            }
+            _ => None,
+        })
+    }
+
+    fn mint(&mut self, due: i64, amount: TokenAmount, now: i64) -> Result<Vec<EmissionEvent>> {
+        let terms = &self.state.terms;
+        let authority = self.authority.pubkey();
+        let info = token::fetch_mint(self.rpc, &terms.mint)?;
+        if info.mint.mint_authority != Some(authority) {
+            return Err(Error::InvalidInput(format!(
+                "{authority} no longer holds the mint authority of {}; the emission can't continue",
+                terms.mint
+            )));
+        }
+        let destination =
+            token::associated_token_address(&terms.recipient, &terms.mint, &terms.program_id);
+        let instructions = vec![
+            memo::memo(
+                &format!("emission due {}", format_rfc3339(due)),
+                &[&authority],
+            ),
+            token::create_associated_token_account_idempotent(
+                &authority,
+                &terms.recipient,
+                &terms.mint,
+                &terms.program_id,
+            ),
+            token::mint_to_checked(
+                &terms.program_id,
+                &terms.mint,
+                &destination,
+                &authority,
+                amount.to_u64()?,
+                info.mint.decimals,
+            ),
+        ];
+        let tx = match self
+            .rpc
+            .build_signed(&instructions, &authority, &[self.authority])
+        {
+            Ok(tx) => tx,
+            Err(e) => {
+                let event = EmissionEvent::Failed {
+                    at: now,
+                    due,
+                    error: e.to_string(),
+                };
+                return Ok(vec![self.record(event)?]);
+            }
+        };
+        let signature = tx.signature();
+        let sent = self.record(EmissionEvent::Sent {
+            at: now,
+            due,
+            amount,
+            signature,
+        })?;
+        let mut events = vec![sent];
+        let outcome = self
+            .rpc
+            .send_transaction(&tx)
+            .and_then(|_| self.rpc.confirm_transaction(&signature));
+        match outcome {
+            Ok(()) => events.push(self.record(EmissionEvent::Minted {
+                at: now_unix(),
+                due,
+                amount,
+                signature,
+            })?),
+            Err(e @ (Error::TransactionFailed { .. } | Error::SimulationFailed(_))) => {
+                events.push(self.record(EmissionEvent::Failed {
+                    at: now_unix(),
+                    due,
+                    error: e.to_string(),
+                })?)
+            }
+            // Outcome unknown; settled from its status next round.
+            Err(_) => {}
+        }
+        Ok(events)
+    }
+}
+
+/// Posts `text` to a chat webhook. Slack reads `text` and Discord
+/// `content`, so both are sent.
+pub fn alert(webhook_url: &str, text: &str) -> Result<()> {
+    ureq::post(webhook_url)
+        .send_json(json!({ "text": text, "content": text }))
+        .map_err(|e| Error::Http(format!("POST {webhook_url}: {e}")))?;
+    Ok(())
+}
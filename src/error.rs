@@ -0,0 +1,90 @@
+use std::fmt;
+
+use crate::pubkey::Pubkey;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Http(String),
+    Rpc {
+        code: i64,
+        message: String,
+    },
+    Config(String),
+    InvalidInput(String),
+    InvalidAccountData(String),
+    AccountNotFound(Pubkey),
+    TransactionFailed {
+        signature: String,
+        reason: String,
+    },
+    /// A transaction its simulation rejected, so it was never sent.
+    SimulationFailed(String),
+    Timeout(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::Json(e) => write!(f, "JSON error: {e}"),
+            Error::Http(msg) => write!(f, "HTTP error: {msg}"),
+            Error::Rpc { code, message } => write!(f, "RPC error {code}: {message}"),
+            Error::Config(msg) => write!(f, "configuration error: {msg}"),
+            Error::InvalidInput(msg) => write!(f, "invalid input: {msg}"),
+            Error::InvalidAccountData(msg) => write!(f, "invalid account data: {msg}"),
+            Error::AccountNotFound(key) => write!(f, "account {key} not found"),
+            Error::TransactionFailed { signature, reason } => {
+                write!(f, "transaction {signature} failed: {reason}")
+            }
+            Error::SimulationFailed(reason) => {
+                write!(f, "transaction rejected in simulation: {reason}")
+            }
+            Error::Timeout(msg) => write!(f, "timed out: {msg}"),
+        }
+    }
+}
+
+impl Error {
+    /// A short name for the kind of error, for counting failures by cause.
+    pub fn category(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "io",
+            Error::Json(_) => "json",
+            Error::Http(_) => "http",
+            Error::Rpc { .. } => "rpc",
+            Error::Config(_) => "config",
+            Error::InvalidInput(_) => "invalid-input",
+            Error::InvalidAccountData(_) => "invalid-account-data",
+            Error::AccountNotFound(_) => "account-not-found",
+            Error::TransactionFailed { .. } => "transaction-failed",
+            Error::SimulationFailed(_) => "simulation-failed",
+            Error::Timeout(_) => "timeout",
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
@@ -0,0 +1,131 @@
+//! Newline-delimited JSON events for external monitors. One object per line:
+//!
+//! ```text
+//! {"ts":"2024-05-01T12:00:00Z","session":"3f9a01bc","event":"step_started","step":"create-mint"}
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::keypair::Signature;
+use crate::launch::now_unix;
+use crate::plugin::Hook;
+use crate::pubkey::Pubkey;
+use crate::session::Step;
+use crate::time;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    StepStarted {
+        step: Step,
+    },
+    /// A step that failed before is being attempted again.
+    StepRetried {
+        step: Step,
+        previous_error: String,
+    },
+    /// The step's transaction was simulated before sending; its compute
+    /// unit limit is set from this.
+    Simulated {
+        step: Step,
+        units_consumed: u64,
+    },
+    TransactionSent {
+        step: Step,
+        signature: Signature,
+    },
+    Confirmed {
+        step: Step,
+        signature: Signature,
+    },
+    /// A transaction whose outcome was lost turned out to have landed.
+    Recovered {
+        step: Step,
+        signature: Signature,
+    },
+    /// The mint already has metadata, so the metadata step updates it
+    /// rather than creating it.
+    MetadataExists {
+        mint: Pubkey,
+    },
+    Failed {
+        step: Step,
+        error: String,
+    },
+    Completed {
+        mint: Pubkey,
+    },
+    /// A line a plugin wrote, as it wrote it.
+    PluginOutput {
+        hook: Hook,
+        plugin: String,
+        line: String,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    ts: String,
+    session: &'a str,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Destination for events. Shared by reference, so one log can serve several
+/// sessions running on different threads.
+pub struct EventLog {
+    out: Mutex<Box<dyn Write + Send>>,
+    to_stdout: bool,
+}
+
+impl EventLog {
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        EventLog {
+            out: Mutex::new(Box::new(out)),
+            to_stdout: false,
+        }
+    }
+
+    pub fn stdout() -> Self {
+        EventLog {
+            out: Mutex::new(Box::new(io::stdout())),
+            to_stdout: true,
+        }
+    }
+
+    /// Appends to `path`, or writes to stdout when it is `-`.
+    pub fn open(path: &Path) -> Result<Self> {
+        if path == Path::new("-") {
+            return Ok(Self::stdout());
+        }
+        let file: File = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+
+    /// Whether events share stdout with regular output.
+    pub fn is_stdout(&self) -> bool {
+        self.to_stdout
+    }
+
+    /// Writes one event line. Write errors are ignored: a monitor that went
+    /// away must not stop a launch halfway through.
+    pub fn emit(&self, session: &str, event: Event) {
+        let line = Line {
+            ts: time::format_rfc3339(now_unix()),
+            session,
+            event: &event,
+        };
+        let Ok(json) = serde_json::to_string(&line) else {
+            return;
+        };
+        if let Ok(mut out) = self.out.lock() {
+            let _ = writeln!(out, "{json}").and_then(|_| out.flush());
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Turning a failed transaction's error and program logs into a sentence a
+//! launcher can act on. The cluster reports failures as
+//! `{"InstructionError":[1,{"Custom":4}]}` plus a page of logs; this picks
+//! out the program that failed and its error, says what it means for a
+//! launch and what usually fixes it, and keeps the program's own last log
+//! line for anything it doesn't recognize.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::labels::Labels;
+use crate::programs::Programs;
+use crate::pubkey::Pubkey;
+use crate::system::SYSTEM_PROGRAM_ID;
+
+/// What went wrong, as far as the error and logs tell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    /// The instruction that failed, counting from 0.
+    pub instruction: Option<u8>,
+    /// The program that failed, from the logs.
+    pub program: Option<Pubkey>,
+    /// Its label, or its address when it has none.
+    pub program_name: Option<String>,
+    /// The program's custom error code.
+    pub code: Option<u32>,
+    /// A plain description, or the raw error when it wasn't recognized.
+    pub meaning: String,
+    pub fix: Option<&'static str>,
+    /// The failing program's last `Program log:` line, when nothing better
+    /// was found.
+    pub log: Option<String>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.meaning)?;
+        match (self.instruction, self.code) {
+            (Some(i), Some(code)) => write!(f, " (instruction {i}, error {code:#x})")?,
+            (Some(i), None) => write!(f, " (instruction {i})")?,
+            _ => {}
+        }
+        if let Some(name) = &self.program_name {
+            write!(f, "\n  program: {name}")?;
+        }
+        if let Some(log) = &self.log {
+            write!(f, "\n  program said: {log}")?;
+        }
+        if let Some(fix) = self.fix {
+            write!(f, "\n  fix: {fix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Explains `err`, a transaction error as the RPC returns it, using the
+/// transaction's `logs` when there are any, naming the failed program by
+/// its label in `labels`.
+pub fn explain(err: &Value, logs: &[String], programs: &Programs, labels: &Labels) -> Explanation {
+    let (instruction, custom) = instruction_error(err);
+    let failed = failed_program(logs);
+    let mut explanation = Explanation {
+        instruction,
+        program: failed.as_ref().map(|f| f.0),
+        program_name: failed.as_ref().map(|f| labels.name(&f.0)),
+        code: custom,
+        meaning: err.to_string(),
+        fix: None,
+        log: None,
+    };
+    let program_log = failed.and_then(|f| f.1);
+    let known = match (explanation.program, custom) {
+        (Some(program), Some(code)) if programs.is_token_program(&program) => token_error(code),
+        (Some(program), Some(code)) if program == SYSTEM_PROGRAM_ID => system_error(code),
+        (Some(program), _) if program == programs.metadata => {
+            program_log.as_deref().and_then(metadata_error)
+        }
+        _ => None,
+    }
+    .or_else(|| transaction_error(err))
+    .or_else(|| compute_exhausted(err, logs));
+    match known {
+        Some((meaning, fix)) => {
+            explanation.meaning = meaning.to_string();
+            explanation.fix = Some(fix);
+        }
+        None => explanation.log = program_log,
+    }
+    explanation
+}
+
+/// The instruction index and custom code of an `InstructionError`.
+fn instruction_error(err: &Value) -> (Option<u8>, Option<u32>) {
+    let Some([index, detail]) = err
+        .get("InstructionError")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+    else {
+        return (None, None);
+    };
+    let index = index.as_u64().and_then(|i| u8::try_from(i).ok());
+    let code = detail
+        .get("Custom")
+        .and_then(Value::as_u64)
+        .and_then(|c| u32::try_from(c).ok());
+    (index, code)
+}
+
+/// The program that failed first, which is the one that raised the error
+/// when it came from a cross-program call, with the last line it logged
+/// itself. Logs of programs it called are skipped over, since their own
+/// invoke and success lines bracket them.
+fn failed_program(logs: &[String]) -> Option<(Pubkey, Option<String>)> {
+    let (at, program) = logs.iter().enumerate().find_map(|(i, line)| {
+        let rest = line.strip_prefix("Program ")?;
+        let (id, outcome) = rest.split_once(' ')?;
+        outcome
+            .starts_with("failed")
+            .then(|| id.parse::<Pubkey>().ok())
+            .flatten()
+            .map(|id| (i, id))
+    })?;
+    let mut depth = 0usize;
+    let mut log = None;
+    for line in logs[..at].iter().rev() {
+        if line.ends_with(" success") {
+            depth += 1;
+        } else if line.contains(" invoke [") {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+        } else if depth == 0
+            && let Some(text) = line.strip_prefix("Program log: ")
+        {
+            log = Some(text.trim_start_matches("Error: ").to_string());
+            break;
+        }
+    }
+    Some((program, log))
+}
+
+type Known = (&'static str, &'static str);
+
+/// SPL Token and Token-2022 share these codes.
+fn token_error(code: u32) -> Option<Known> {
+    Some(match code {
+        0 => (
+            "the account would not hold enough SOL to stay rent exempt",
+            "fund the account with its rent-exempt minimum (`rent` shows it)",
+        ),
+        1 => (
+            "the token account holds fewer tokens than the amount",
+            "check the balance and lower the amount, remembering the mint's decimals",
+        ),
+        2 => (
+            "the account given as the mint is not a mint",
+            "check the mint address; a wallet or token account was probably passed instead",
+        ),
+        3 => (
+            "the token account belongs to a different mint",
+            "use the token account of this mint; the associated token account is derived from both",
+        ),
+        4 => (
+            "owner does not match: the signer is not the owner or authority this needs",
+            "sign with the key that holds that authority; `authorities` lists what a wallet holds",
+        ),
+        5 => (
+            "the mint authority is revoked, so the supply is fixed",
+            "nothing can mint more; launch a new mint if more supply is needed",
+        ),
+        6 => (
+            "the account is already in use",
+            "a previous attempt may have created it; resume with `session resume` instead of starting over",
+        ),
+        7 | 8 => (
+            "the number of multisig signers is wrong",
+            "sign with as many of the multisig's members as its threshold requires",
+        ),
+        9 => (
+            "the account is not initialized",
+            "create the token account first, e.g. the recipient's associated token account",
+        ),
+        11 => (
+            "the token account still holds tokens, so it cannot be closed",
+            "transfer or burn the balance first",
+        ),
+        14 => (
+            "the amount would overflow the supply",
+            "lower the amount; the supply is a 64-bit count of base units",
+        ),
+        16 => (
+            "the mint has no freeze authority",
+            "freezing is impossible once the freeze authority is revoked",
+        ),
+        17 => (
+            "the token account is frozen",
+            "thaw it with the freeze authority before moving its tokens",
+        ),
+        18 => (
+            "the decimals given do not match the mint's",
+            "pass the decimals the mint was created with",
+        ),
+        _ => return None,
+    })
+}
+
+fn system_error(code: u32) -> Option<Known> {
+    Some(match code {
+        0 => (
+            "an account already exists at that address",
+            "a previous attempt may have created it; resume with `session resume` or use a new mint keypair",
+        ),
+        1 => (
+            "the payer does not have enough SOL for this transfer or account",
+            "fund the payer and retry",
+        ),
+        _ => return None,
+    })
+}
+
+/// The metadata program only logs its errors by name.
+fn metadata_error(log: &str) -> Option<Known> {
+    let log = log.to_ascii_lowercase();
+    let known: &[(&str, Known)] = &[
+        (
+            "update authority given does not match",
+            (
+                "the signer is not the metadata's update authority",
+                "sign with the update authority; it may have been handed over or revoked",
+            ),
+        ),
+        (
+            "data is immutable",
+            (
+                "the metadata was made immutable",
+                "it can no longer be changed by anyone",
+            ),
+        ),
+        (
+            "name too long",
+            ("the name is too long", "keep the name within 32 bytes"),
+        ),
+        (
+            "symbol too long",
+            ("the symbol is too long", "keep the symbol within 10 bytes"),
+        ),
+        (
+            "uri too long",
+            ("the URI is too long", "keep the URI within 200 bytes"),
+        ),
+        (
+            "already initialized",
+            (
+                "the mint already has a metadata account",
+                "update the existing metadata instead of creating it",
+            ),
+        ),
+        (
+            "mint authority provided does not match",
+            (
+                "creating metadata needs the mint authority's signature",
+                "sign with the mint authority, or create the metadata before revoking it",
+            ),
+        ),
+    ];
+    known
+        .iter()
+        .find(|(needle, _)| log.contains(needle))
+        .map(|(_, known)| *known)
+}
+
+/// Errors of the transaction as a whole rather than one instruction.
+fn transaction_error(err: &Value) -> Option<Known> {
+    let name = err
+        .as_str()
+        .or_else(|| err.as_object()?.keys().next().map(String::as_str))?;
+    Some(match name {
+        "InsufficientFundsForFee" => (
+            "the fee payer cannot pay the fee",
+            "fund the payer with a little SOL",
+        ),
+        "AccountNotFound" => (
+            "the fee payer has never been funded",
+            "send SOL to the payer first",
+        ),
+        "InsufficientFundsForRent" => (
+            "an account would be left below its rent-exempt minimum",
+            "fund the payer with enough SOL for the accounts this creates",
+        ),
+        "BlockhashNotFound" => (
+            "the blockhash expired before the transaction landed",
+            "retry; if it keeps happening the RPC may be lagging (`health` checks it)",
+        ),
+        "AlreadyProcessed" => (
+            "this exact transaction already landed",
+            "nothing to do; look the signature up instead of resending",
+        ),
+        _ => return None,
+    })
+}
+
+fn compute_exhausted(err: &Value, logs: &[String]) -> Option<Known> {
+    let exhausted = err.to_string().contains("ComputationalBudgetExceeded")
+        || logs.iter().any(|l| l.contains("exceeded CUs meter"));
+    exhausted.then_some((
+        "the transaction ran out of compute units",
+        "raise --compute-unit-limit (shown with --expert) or split the transaction",
+    ))
+}
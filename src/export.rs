@@ -0,0 +1,264 @@
+//! Historical transaction export: pages through the signatures touching a mint
+//! and its token accounts and decodes every SPL Token mint, burn and transfer.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::amount::TokenAmount;
+use crate::csv;
+use crate::error::Result;
+use crate::holders;
+use crate::memo;
+use crate::pubkey::Pubkey;
+use crate::rpc::{ConfirmedTransaction, RpcClient, SignatureInfo, UiInstruction};
+use crate::time::format_rfc3339;
+use crate::token::{self, TokenInstruction};
+
+/// Largest page size accepted by `getSignaturesForAddress`.
+const SIGNATURE_PAGE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MovementKind {
+    Mint,
+    Burn,
+    Transfer,
+}
+
+impl MovementKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MovementKind::Mint => "mint",
+            MovementKind::Burn => "burn",
+            MovementKind::Transfer => "transfer",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenMovement {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub kind: MovementKind,
+    pub amount: TokenAmount,
+    pub source: Option<Pubkey>,
+    pub source_owner: Option<Pubkey>,
+    pub destination: Option<Pubkey>,
+    pub destination_owner: Option<Pubkey>,
+    pub authority: Option<Pubkey>,
+    /// Memos recorded with the transaction, joined with "; ".
+    pub memo: Option<String>,
+}
+
+/// Everything needed to decode a mint's history: its token accounts and their owners.
+pub struct ExportScope {
+    pub mint: Pubkey,
+    /// The token program that owns the mint; only it can move the tokens.
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    /// Token account -> owner for every account of the mint that still exists.
+    pub owners: HashMap<Pubkey, Pubkey>,
+}
+
+impl ExportScope {
+    pub fn load(rpc: &RpcClient, mint: &Pubkey) -> Result<Self> {
+        let info = token::fetch_mint(rpc, mint)?;
+        let (_, balances) = holders::fetch_balances(rpc, mint, &info.program_id)?;
+        Ok(ExportScope {
+            mint: *mint,
+            program_id: info.program_id,
+            decimals: info.mint.decimals,
+            owners: balances.into_iter().map(|b| (b.account, b.owner)).collect(),
+        })
+    }
+
+    /// The mint itself followed by every known token account.
+    pub fn addresses(&self) -> Vec<Pubkey> {
+        let mut addresses = vec![self.mint];
+        let mut accounts: Vec<Pubkey> = self.owners.keys().copied().collect();
+        accounts.sort();
+        addresses.extend(accounts);
+        addresses
+    }
+
+    /// Decodes the movements of this mint within one transaction, including
+    /// those made through CPI.
+    pub fn decode(&self, signature: &str, tx: &ConfirmedTransaction) -> Result<Vec<TokenMovement>> {
+        let keys = tx.account_keys()?;
+        let inner = tx.meta.as_ref().and_then(|m| m.inner_instructions.as_ref());
+        let memos: Vec<String> = tx
+            .transaction
+            .message
+            .instructions
+            .iter()
+            .filter(|ix| {
+                keys.get(ix.program_id_index as usize)
+                    .is_some_and(memo::is_memo_program)
+            })
+            .filter_map(|ix| bs58::decode(&ix.data).into_vec().ok())
+            .map(|data| String::from_utf8_lossy(&data).into_owned())
+            .collect();
+        let memo = (!memos.is_empty()).then(|| memos.join("; "));
+        let mut movements = Vec::new();
+        for (index, ix) in tx.transaction.message.instructions.iter().enumerate() {
+            let nested = inner
+                .into_iter()
+                .flatten()
+                .filter(|set| set.index as usize == index)
+                .flat_map(|set| set.instructions.iter());
+            for ix in std::iter::once(ix).chain(nested) {
+                if let Some(d) = self.decode_instruction(&keys, ix) {
+                    movements.push(TokenMovement {
+                        signature: signature.to_string(),
+                        slot: tx.slot,
+                        block_time: tx.block_time,
+                        kind: d.kind,
+                        amount: TokenAmount::from_raw(d.amount as u128, self.decimals),
+                        source: d.source,
+                        source_owner: d.source.and_then(|s| self.owners.get(&s).copied()),
+                        destination: d.destination,
+                        destination_owner: d.destination.and_then(|a| self.owners.get(&a).copied()),
+                        authority: d.authority,
+                        memo: memo.clone(),
+                    });
+                }
+            }
+        }
+        Ok(movements)
+    }
+
+    fn decode_instruction(&self, keys: &[Pubkey], ix: &UiInstruction) -> Option<Decoded> {
+        let program_id = keys.get(ix.program_id_index as usize)?;
+        if *program_id != self.program_id {
+            return None;
+        }
+        let data = bs58::decode(&ix.data).into_vec().ok()?;
+        let account =
+            |i: usize| -> Option<Pubkey> { keys.get(*ix.accounts.get(i)? as usize).copied() };
+
+        let (kind, amount, mint, source, destination, authority) =
+            match TokenInstruction::unpack(&data)? {
+                TokenInstruction::Transfer { amount } => {
+                    let (source, destination) = (account(0)?, account(1)?);
+                    if !self.owners.contains_key(&source) && !self.owners.contains_key(&destination)
+                    {
+                        return None;
+                    }
+                    (
+                        MovementKind::Transfer,
+                        amount,
+                        self.mint,
+                        Some(source),
+                        Some(destination),
+                        account(2),
+                    )
+                }
+                TokenInstruction::TransferChecked { amount, .. } => (
+                    MovementKind::Transfer,
+                    amount,
+                    account(1)?,
+                    account(0),
+                    account(2),
+                    account(3),
+                ),
+                TokenInstruction::MintTo { amount }
+                | TokenInstruction::MintToChecked { amount, .. } => (
+                    MovementKind::Mint,
+                    amount,
+                    account(0)?,
+                    None,
+                    account(1),
+                    account(2),
+                ),
+                TokenInstruction::Burn { amount }
+                | TokenInstruction::BurnChecked { amount, .. } => (
+                    MovementKind::Burn,
+                    amount,
+                    account(1)?,
+                    account(0),
+                    None,
+                    account(2),
+                ),
+            };
+        if mint != self.mint {
+            return None;
+        }
+        Some(Decoded {
+            kind,
+            amount,
+            source,
+            destination,
+            authority,
+        })
+    }
+}
+
+struct Decoded {
+    kind: MovementKind,
+    amount: u64,
+    source: Option<Pubkey>,
+    destination: Option<Pubkey>,
+    authority: Option<Pubkey>,
+}
+
+/// Collects the unique successful signatures touching any of `addresses`,
+/// oldest first. `limit` caps how many signatures are read per address.
+pub fn collect_signatures(
+    rpc: &RpcClient,
+    addresses: &[Pubkey],
+    limit: Option<usize>,
+) -> Result<Vec<SignatureInfo>> {
+    let mut seen = HashSet::new();
+    let mut signatures = Vec::new();
+    for address in addresses {
+        let mut before: Option<String> = None;
+        let mut remaining = limit.unwrap_or(usize::MAX);
+        while remaining > 0 {
+            let page_size = remaining.min(SIGNATURE_PAGE);
+            let page = rpc.get_signatures_for_address(address, before.as_deref(), page_size)?;
+            let exhausted = page.len() < page_size;
+            remaining -= page.len();
+            before = page.last().map(|s| s.signature.clone());
+            for info in page {
+                if info.err.is_none() && seen.insert(info.signature.clone()) {
+                    signatures.push(info);
+                }
+            }
+            if exhausted {
+                break;
+            }
+        }
+    }
+    signatures.sort_by_key(|s| s.slot);
+    Ok(signatures)
+}
+
+pub fn write_csv(movements: &[TokenMovement], mut out: impl Write) -> Result<()> {
+    let key = |k: Option<Pubkey>| k.map(|k| k.to_string()).unwrap_or_default();
+    writeln!(
+        out,
+        "signature,slot,timestamp,kind,amount,ui_amount,source,source_owner,destination,destination_owner,authority,memo"
+    )?;
+    for m in movements {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            m.signature,
+            m.slot,
+            m.block_time.map(format_rfc3339).unwrap_or_default(),
+            m.kind.as_str(),
+            m.amount.raw(),
+            m.amount,
+            key(m.source),
+            key(m.source_owner),
+            key(m.destination),
+            key(m.destination_owner),
+            key(m.authority),
+            csv::escape(m.memo.as_deref().unwrap_or_default()),
+        )?;
+    }
+    Ok(())
+}
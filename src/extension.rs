@@ -0,0 +1,565 @@
+//! Token-2022 extensions: the type-length-value area that follows the base
+//! mint or account layout, and the instructions that set extensions up.
+//!
+//! Mint extensions have to be initialized after the account is allocated
+//! and before `InitializeMint2`, so the account is created at the size of
+//! every extension it will carry.
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::layout::{Reader, Writer};
+use crate::pubkey::Pubkey;
+use crate::system::SYSTEM_PROGRAM_ID;
+use crate::token::{ACCOUNT_LEN, MINT_LEN};
+use crate::tx::{AccountMeta, Instruction};
+
+/// Extensions start after the base layout padded to an account's size and
+/// a one-byte account type.
+pub const EXTENSIONS_OFFSET: usize = ACCOUNT_LEN + 1;
+/// Type and length prefix of each extension.
+const TLV_HEADER_LEN: usize = 4;
+
+pub const IMMUTABLE_OWNER: u16 = 7;
+pub const MEMO_TRANSFER: u16 = 8;
+pub const CPI_GUARD: u16 = 11;
+pub const PERMANENT_DELEGATE: u16 = 12;
+pub const TRANSFER_HOOK: u16 = 14;
+pub const TRANSFER_HOOK_LEN: usize = 64;
+/// Every token account of a transfer-hook mint carries this one-byte flag.
+pub const TRANSFER_HOOK_ACCOUNT_LEN: usize = 1;
+pub const GROUP_POINTER: u16 = 20;
+pub const GROUP_POINTER_LEN: usize = 64;
+pub const TOKEN_GROUP: u16 = 21;
+pub const TOKEN_GROUP_LEN: usize = 80;
+pub const GROUP_MEMBER_POINTER: u16 = 22;
+pub const GROUP_MEMBER_POINTER_LEN: usize = 64;
+pub const TOKEN_GROUP_MEMBER: u16 = 23;
+pub const TOKEN_GROUP_MEMBER_LEN: usize = 72;
+
+const IX_REALLOCATE: u8 = 29;
+const IX_MEMO_TRANSFER_EXTENSION: u8 = 30;
+const IX_CPI_GUARD_EXTENSION: u8 = 34;
+const IX_TRANSFER_HOOK_EXTENSION: u8 = 36;
+const IX_GROUP_POINTER_EXTENSION: u8 = 40;
+const IX_GROUP_MEMBER_POINTER_EXTENSION: u8 = 41;
+// Every pointer-style extension instruction has the same two sub-operations,
+// as do the account toggles (enable, disable).
+const EXTENSION_INITIALIZE: u8 = 0;
+const EXTENSION_UPDATE: u8 = 1;
+const EXTENSION_ENABLE: u8 = 0;
+const EXTENSION_DISABLE: u8 = 1;
+
+/// Seed of the account a transfer hook program reads its extra accounts from.
+const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+
+/// Name of an extension type as used by the Token-2022 program.
+pub fn name(kind: u16) -> &'static str {
+    match kind {
+        1 => "transfer-fee-config",
+        2 => "transfer-fee-amount",
+        3 => "mint-close-authority",
+        4 => "confidential-transfer-mint",
+        5 => "confidential-transfer-account",
+        6 => "default-account-state",
+        7 => "immutable-owner",
+        8 => "memo-transfer",
+        9 => "non-transferable",
+        10 => "interest-bearing-config",
+        11 => "cpi-guard",
+        12 => "permanent-delegate",
+        13 => "non-transferable-account",
+        14 => "transfer-hook",
+        15 => "transfer-hook-account",
+        16 => "confidential-transfer-fee-config",
+        17 => "confidential-transfer-fee-amount",
+        18 => "metadata-pointer",
+        19 => "token-metadata",
+        20 => "group-pointer",
+        21 => "token-group",
+        22 => "group-member-pointer",
+        23 => "token-group-member",
+        _ => "unknown",
+    }
+}
+
+/// The extension type called `name`, as [`name`] spells it.
+pub fn kind_by_name(name: &str) -> Option<u16> {
+    (1..=23).find(|&kind| self::name(kind) == name)
+}
+
+/// Mint extensions of a fixed size: type, data length, and the data length
+/// of the extension it adds to every token account of the mint, if any.
+const MINT_EXTENSIONS: &[(u16, usize, Option<usize>)] = &[
+    (1, 108, Some(8)),
+    (3, 32, None),
+    (4, 65, None),
+    (6, 1, None),
+    (9, 0, Some(0)),
+    (10, 52, None),
+    (12, 32, None),
+    (
+        TRANSFER_HOOK,
+        TRANSFER_HOOK_LEN,
+        Some(TRANSFER_HOOK_ACCOUNT_LEN),
+    ),
+    (16, 129, None),
+    (18, 64, None),
+    (GROUP_POINTER, GROUP_POINTER_LEN, None),
+    (TOKEN_GROUP, TOKEN_GROUP_LEN, None),
+    (GROUP_MEMBER_POINTER, GROUP_MEMBER_POINTER_LEN, None),
+    (TOKEN_GROUP_MEMBER, TOKEN_GROUP_MEMBER_LEN, None),
+];
+
+/// Data length of a fixed-size mint extension; `None` for account
+/// extensions and for variable-size ones such as token-metadata.
+pub fn mint_extension_len(kind: u16) -> Option<usize> {
+    MINT_EXTENSIONS
+        .iter()
+        .find(|(k, ..)| *k == kind)
+        .map(|&(_, len, _)| len)
+}
+
+/// Data length of the extension a mint extension adds to token accounts.
+pub fn account_extension_len(mint_kind: u16) -> Option<usize> {
+    MINT_EXTENSIONS
+        .iter()
+        .find(|(k, ..)| *k == mint_kind)
+        .and_then(|&(.., account)| account)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extension {
+    pub kind: u16,
+    pub data: Vec<u8>,
+}
+
+/// Reads the extensions of a mint or token account. Accounts of the
+/// legacy program, or without extensions, have none.
+pub fn parse(data: &[u8]) -> Result<Vec<Extension>> {
+    let mut extensions = Vec::new();
+    let Some(mut tlv) = data.get(EXTENSIONS_OFFSET..) else {
+        return Ok(extensions);
+    };
+    while tlv.len() >= TLV_HEADER_LEN {
+        let mut r = Reader::new(tlv);
+        let kind = r.u16()?;
+        let len = r.u16()? as usize;
+        // Space reserved for extensions not written yet is zero-filled.
+        if kind == 0 {
+            break;
+        }
+        let data = r.take(len)?;
+        extensions.push(Extension {
+            kind,
+            data: data.to_vec(),
+        });
+        tlv = &tlv[TLV_HEADER_LEN + len..];
+    }
+    Ok(extensions)
+}
+
+/// Looks up one extension by type.
+pub fn find(extensions: &[Extension], kind: u16) -> Option<&[u8]> {
+    extensions
+        .iter()
+        .find(|e| e.kind == kind)
+        .map(|e| e.data.as_slice())
+}
+
+/// Size of a mint carrying extensions with these data lengths.
+pub fn mint_len(extension_lens: &[usize]) -> usize {
+    if extension_lens.is_empty() {
+        return MINT_LEN;
+    }
+    EXTENSIONS_OFFSET
+        + extension_lens
+            .iter()
+            .map(|len| TLV_HEADER_LEN + len)
+            .sum::<usize>()
+}
+
+/// Size of a token account carrying extensions with these data lengths.
+pub fn account_len(extension_lens: &[usize]) -> usize {
+    if extension_lens.is_empty() {
+        return ACCOUNT_LEN;
+    }
+    EXTENSIONS_OFFSET
+        + extension_lens
+            .iter()
+            .map(|len| TLV_HEADER_LEN + len)
+            .sum::<usize>()
+}
+
+/// Token-2022 stores optional keys as 32 bytes, all zero for none.
+fn optional_key(r: &mut Reader) -> Result<Option<Pubkey>> {
+    let key = r.pubkey()?;
+    Ok((key != Pubkey::default()).then_some(key))
+}
+
+fn write_optional_key(w: &mut Writer, key: Option<&Pubkey>) {
+    w.pubkey(key.unwrap_or(&Pubkey::default()));
+}
+
+/// The permanent delegate of a mint, which may move or burn tokens from
+/// any of its accounts.
+pub fn permanent_delegate(extensions: &[Extension]) -> Result<Option<Pubkey>> {
+    let Some(data) = find(extensions, PERMANENT_DELEGATE) else {
+        return Ok(None);
+    };
+    if data.len() < 32 {
+        return Err(Error::InvalidAccountData(
+            "permanent delegate extension is truncated".into(),
+        ));
+    }
+    optional_key(&mut Reader::new(data))
+}
+
+/// A key stored by an extension together with the authority that may change
+/// it, the layout shared by the transfer hook and the group and member
+/// pointers.
+fn unpack_pointer(data: &[u8], what: &str) -> Result<(Option<Pubkey>, Option<Pubkey>)> {
+    if data.len() < 64 {
+        return Err(Error::InvalidAccountData(format!(
+            "{what} extension is truncated"
+        )));
+    }
+    let mut r = Reader::new(data);
+    Ok((optional_key(&mut r)?, optional_key(&mut r)?))
+}
+
+/// `Initialize` of a pointer-style extension; runs before `InitializeMint2`.
+fn initialize_pointer(
+    program_id: &Pubkey,
+    instruction: u8,
+    mint: &Pubkey,
+    authority: Option<&Pubkey>,
+    address: Option<&Pubkey>,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(instruction).u8(EXTENSION_INITIALIZE);
+    write_optional_key(&mut w, authority);
+    write_optional_key(&mut w, address);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*mint, false)],
+        data: w.into_inner(),
+    }
+}
+
+/// `Update` of a pointer-style extension, signed by its authority.
+fn update_pointer(
+    program_id: &Pubkey,
+    instruction: u8,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    address: Option<&Pubkey>,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(instruction).u8(EXTENSION_UPDATE);
+    write_optional_key(&mut w, address);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// The program Token-2022 calls on every transfer of a mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferHook {
+    /// May change the program; `None` makes the hook permanent.
+    pub authority: Option<Pubkey>,
+    pub program_id: Option<Pubkey>,
+}
+
+impl TransferHook {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let (authority, program_id) = unpack_pointer(data, "transfer hook")?;
+        Ok(TransferHook {
+            authority,
+            program_id,
+        })
+    }
+}
+
+/// Address the hook program keeps the extra accounts of `mint`'s transfers at.
+pub fn extra_account_metas_address(mint: &Pubkey, hook_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[EXTRA_ACCOUNT_METAS_SEED, mint.as_bytes()], hook_program).0
+}
+
+/// Sets up the transfer hook extension; runs before `InitializeMint2`.
+pub fn initialize_transfer_hook(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    authority: Option<&Pubkey>,
+    hook_program: Option<&Pubkey>,
+) -> Instruction {
+    initialize_pointer(
+        program_id,
+        IX_TRANSFER_HOOK_EXTENSION,
+        mint,
+        authority,
+        hook_program,
+    )
+}
+
+/// Points the transfer hook at another program, or with `None` disables it.
+pub fn update_transfer_hook(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    hook_program: Option<&Pubkey>,
+) -> Instruction {
+    update_pointer(
+        program_id,
+        IX_TRANSFER_HOOK_EXTENSION,
+        mint,
+        authority,
+        hook_program,
+    )
+}
+
+/// Where a mint's group or member data lives. Launched tokens point at
+/// themselves, keeping the group data in the mint account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pointer {
+    pub authority: Option<Pubkey>,
+    pub address: Option<Pubkey>,
+}
+
+impl Pointer {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let (authority, address) = unpack_pointer(data, "pointer")?;
+        Ok(Pointer { authority, address })
+    }
+}
+
+pub fn initialize_group_pointer(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    authority: Option<&Pubkey>,
+    group: Option<&Pubkey>,
+) -> Instruction {
+    initialize_pointer(
+        program_id,
+        IX_GROUP_POINTER_EXTENSION,
+        mint,
+        authority,
+        group,
+    )
+}
+
+pub fn initialize_group_member_pointer(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    authority: Option<&Pubkey>,
+    member: Option<&Pubkey>,
+) -> Instruction {
+    initialize_pointer(
+        program_id,
+        IX_GROUP_MEMBER_POINTER_EXTENSION,
+        mint,
+        authority,
+        member,
+    )
+}
+
+/// Repoints, or with `None` clears, a mint's member pointer.
+pub fn update_group_member_pointer(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    member: Option<&Pubkey>,
+) -> Instruction {
+    update_pointer(
+        program_id,
+        IX_GROUP_MEMBER_POINTER_EXTENSION,
+        mint,
+        authority,
+        member,
+    )
+}
+
+/// A group of mints, e.g. a family of seasonal tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenGroup {
+    /// May add members and change the maximum size.
+    pub update_authority: Option<Pubkey>,
+    pub mint: Pubkey,
+    /// Members added so far; removing a member does not lower it.
+    pub size: u64,
+    pub max_size: u64,
+}
+
+impl TokenGroup {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        Ok(TokenGroup {
+            update_authority: optional_key(&mut r)?,
+            mint: r.pubkey()?,
+            size: r.u64()?,
+            max_size: r.u64()?,
+        })
+    }
+}
+
+/// Membership of a mint in a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenGroupMember {
+    pub mint: Pubkey,
+    pub group: Pubkey,
+    /// Position in the group, from 1.
+    pub member_number: u64,
+}
+
+impl TokenGroupMember {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        Ok(TokenGroupMember {
+            mint: r.pubkey()?,
+            group: r.pubkey()?,
+            member_number: r.u64()?,
+        })
+    }
+}
+
+/// Token group interface instruction discriminator: first 8 bytes of
+/// `sha256("spl_token_group_interface:<name>")`.
+fn group_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("spl_token_group_interface:{name}"));
+    hash[..8].try_into().unwrap()
+}
+
+/// Makes `mint` a group. Runs after `InitializeMint2`, signed by the mint
+/// authority; Token-2022 grows the account, so it must already hold the
+/// rent for the group data.
+pub fn initialize_group(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    update_authority: Option<&Pubkey>,
+    max_size: u64,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.bytes(&group_discriminator("initialize_token_group"));
+    write_optional_key(&mut w, update_authority);
+    w.u64(max_size);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// Adds `member` to `group`. Signed by the member's mint authority and
+/// the group's update authority; like `initialize_group` it grows the
+/// member mint.
+pub fn initialize_member(
+    program_id: &Pubkey,
+    member: &Pubkey,
+    member_mint_authority: &Pubkey,
+    group: &Pubkey,
+    group_update_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*member, false),
+            AccountMeta::new_readonly(*member, false),
+            AccountMeta::new_readonly(*member_mint_authority, true),
+            AccountMeta::new(*group, false),
+            AccountMeta::new_readonly(*group_update_authority, true),
+        ],
+        data: group_discriminator("initialize_member").to_vec(),
+    }
+}
+
+/// Whether a one-byte toggle extension such as the CPI guard is on. A token
+/// account without the extension has it off.
+pub fn is_enabled(extensions: &[Extension], kind: u16) -> bool {
+    find(extensions, kind).is_some_and(|data| data.first() == Some(&1))
+}
+
+/// Grows a Token-2022 token account to make room for `extension_types`,
+/// with `payer` covering the extra rent.
+pub fn reallocate(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    extension_types: &[u16],
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_REALLOCATE);
+    for kind in extension_types {
+        w.u16(*kind);
+    }
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+fn toggle(
+    program_id: &Pubkey,
+    instruction: u8,
+    account: &Pubkey,
+    owner: &Pubkey,
+    enable: bool,
+) -> Instruction {
+    let op = if enable {
+        EXTENSION_ENABLE
+    } else {
+        EXTENSION_DISABLE
+    };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![instruction, op],
+    }
+}
+
+/// Turns the CPI guard of a token account on or off. While it is on,
+/// programs invoked by the owner cannot move, burn, approve or close
+/// through the account.
+pub fn set_cpi_guard(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    owner: &Pubkey,
+    enable: bool,
+) -> Instruction {
+    toggle(program_id, IX_CPI_GUARD_EXTENSION, account, owner, enable)
+}
+
+/// Turns the requirement for incoming transfers to carry a memo on or off.
+pub fn set_required_memo(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    owner: &Pubkey,
+    enable: bool,
+) -> Instruction {
+    toggle(
+        program_id,
+        IX_MEMO_TRANSFER_EXTENSION,
+        account,
+        owner,
+        enable,
+    )
+}
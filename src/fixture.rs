@@ -0,0 +1,102 @@
+//! Recorded RPC traffic for deterministic replays.
+//!
+//! A fixture is a JSON Lines file with one call per line:
+//! `{"method": ..., "params": ..., "result": ...}`, or `"error": {"code",
+//! "message"}` in place of `result`. `--record` appends every call made
+//! during a real run; `--replay` answers calls from the file instead of the
+//! network. Each recorded call answers at most once, in file order, so a
+//! polled method replays the same sequence of answers it was recorded with.
+//! Hand-written entries may leave out `params` to match any arguments.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcErrorObject {
+    pub code: i64,
+    pub message: String,
+    /// Details some errors carry, such as the error and logs of a
+    /// transaction that failed preflight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedCall {
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcErrorObject>,
+}
+
+impl RecordedCall {
+    fn matches(&self, method: &str, params: &Value) -> bool {
+        self.method == method && self.params.as_ref().is_none_or(|p| p == params)
+    }
+}
+
+/// Answers RPC calls from a fixture file.
+pub struct Replay {
+    calls: Mutex<Vec<Option<RecordedCall>>>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)?;
+        let calls = raw
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(n, line)| {
+                serde_json::from_str(line)
+                    .map(Some)
+                    .map_err(|e| Error::Config(format!("{}:{}: {e}", path.display(), n + 1)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Replay {
+            calls: Mutex::new(calls),
+        })
+    }
+
+    /// Takes the first unused call matching `method` and `params`.
+    pub fn respond(&self, method: &str, params: &Value) -> Result<RecordedCall> {
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        calls
+            .iter_mut()
+            .find(|c| c.as_ref().is_some_and(|c| c.matches(method, params)))
+            .and_then(Option::take)
+            .ok_or_else(|| Error::Http(format!("{method}: no recorded response for {params}")))
+    }
+}
+
+/// Appends every RPC call to a fixture file as it completes.
+pub struct Recorder {
+    out: Mutex<BufWriter<File>>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Recorder {
+            out: Mutex::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    /// Recording is best effort: a failed write must not fail the run itself.
+    pub fn record(&self, call: &RecordedCall) {
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        if let Ok(line) = serde_json::to_string(call) {
+            let _ = writeln!(out, "{line}");
+            let _ = out.flush();
+        }
+    }
+}
@@ -0,0 +1,582 @@
+//! Freezing the accounts that receive a mint's tokens from flagged
+//! addresses.
+//!
+//! Incident response for a token whose freeze authority is still held:
+//! once an exploiter's wallets are known, [`Freezer`] freezes their token
+//! accounts, then watches the mint and those accounts and freezes every
+//! account that receives tokens from them. With `follow`, the owners of
+//! frozen accounts are flagged in turn, so tokens passed on through fresh
+//! wallets are caught at the next hop too. Exempt addresses, such as pools
+//! and exchange deposit accounts, are never frozen.
+//!
+//! Transfers are found by polling signatures, so a freeze lands some
+//! seconds after the transfer it answers. Every flag, detection and freeze
+//! is appended to a log, which is also what a restarted watch resumes from.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::export::{ExportScope, MovementKind};
+use crate::keypair::Signature;
+use crate::launch::now_unix;
+use crate::pack::Packer;
+use crate::pubkey::Pubkey;
+use crate::rpc::{MAX_MULTIPLE_ACCOUNTS, RpcClient, SignatureInfo};
+use crate::signer::Signer;
+use crate::token::{self, AccountState, MintAccount, TokenAccount};
+
+/// Signatures read per page while catching up with a watched address.
+const SIGNATURE_PAGE: usize = 100;
+
+/// What gets frozen.
+#[derive(Debug, Clone, Default)]
+pub struct FreezeRules {
+    /// Wallets or token accounts whose tokens are tainted.
+    pub flagged: Vec<Pubkey>,
+    /// Wallets or token accounts never frozen.
+    pub exempt: Vec<Pubkey>,
+    /// Transfers of fewer base units are ignored.
+    pub min_amount: u64,
+    /// Flag the owner of every account frozen for receiving tainted tokens.
+    pub follow: bool,
+    /// On a fresh log, also check this many of each watched address's
+    /// latest transactions; otherwise only those after the watch starts.
+    pub backfill: usize,
+}
+
+/// Reads addresses one per line; blank lines and `#` comments are skipped.
+pub fn read_addresses(path: &Path) -> Result<Vec<Pubkey>> {
+    let text = std::fs::read_to_string(path)?;
+    let mut addresses = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let address = line
+            .parse()
+            .map_err(|e| Error::InvalidInput(format!("{} line {}: {e}", path.display(), i + 1)))?;
+        addresses.push(address);
+    }
+    Ok(addresses)
+}
+
+/// One line of the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum FreezeEvent {
+    Started {
+        at: i64,
+        mint: Pubkey,
+        authority: Pubkey,
+    },
+    Flagged {
+        at: i64,
+        address: Pubkey,
+        reason: String,
+    },
+    /// Tainted tokens arrived in `destination`.
+    Detected {
+        at: i64,
+        signature: String,
+        source: Pubkey,
+        destination: Pubkey,
+        owner: Option<Pubkey>,
+        amount: TokenAmount,
+    },
+    Skipped {
+        at: i64,
+        account: Pubkey,
+        reason: String,
+    },
+    Frozen {
+        at: i64,
+        accounts: Vec<Pubkey>,
+        signature: Signature,
+    },
+    Failed {
+        at: i64,
+        accounts: Vec<Pubkey>,
+        error: String,
+    },
+    /// Everything touching `address` up to `signature` is handled.
+    Cursor {
+        at: i64,
+        address: Pubkey,
+        signature: String,
+    },
+}
+
+impl fmt::Display for FreezeEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |accounts: &[Pubkey]| {
+            accounts
+                .iter()
+                .map(Pubkey::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            FreezeEvent::Started {
+                mint, authority, ..
+            } => write!(f, "watching {mint} with freeze authority {authority}"),
+            FreezeEvent::Flagged {
+                address, reason, ..
+            } => write!(f, "flagged {address}: {reason}"),
+            FreezeEvent::Detected {
+                signature,
+                source,
+                destination,
+                amount,
+                ..
+            } => write!(
+                f,
+                "{amount} moved from {source} to {destination} in {signature}"
+            ),
+            FreezeEvent::Skipped {
+                account, reason, ..
+            } => write!(f, "not freezing {account}: {reason}"),
+            FreezeEvent::Frozen {
+                accounts,
+                signature,
+                ..
+            } => write!(f, "froze {} in {signature}", list(accounts)),
+            FreezeEvent::Failed {
+                accounts, error, ..
+            } => write!(f, "could not freeze {}: {error}", list(accounts)),
+            FreezeEvent::Cursor { .. } => f.write_str("caught up"),
+        }
+    }
+}
+
+/// Watches one mint and freezes what its rules match.
+pub struct Freezer<'a> {
+    rpc: &'a RpcClient,
+    mint: Pubkey,
+    account: MintAccount,
+    authority: &'a dyn Signer,
+    rules: FreezeRules,
+    /// `None` in a dry run, which sends and records nothing.
+    log: Option<File>,
+    path: PathBuf,
+    flagged: HashSet<Pubkey>,
+    frozen: HashSet<Pubkey>,
+    /// Newest signature handled, per watched address.
+    cursors: HashMap<Pubkey, String>,
+    /// Owner of each token account looked up.
+    owners: HashMap<Pubkey, Pubkey>,
+}
+
+impl<'a> Freezer<'a> {
+    /// `freeze-<mint>.jsonl` in the working directory.
+    pub fn default_path(mint: &Pubkey) -> PathBuf {
+        PathBuf::from(format!("freeze-{mint}.jsonl"))
+    }
+
+    /// Checks `authority` holds the freeze authority of `mint` and resumes
+    /// the log at `path`, if there is one. A dry run reads the log but
+    /// never writes it.
+    pub fn open(
+        rpc: &'a RpcClient,
+        mint: &Pubkey,
+        authority: &'a dyn Signer,
+        rules: FreezeRules,
+        path: &Path,
+        dry_run: bool,
+    ) -> Result<Self> {
+        let account = token::fetch_mint(rpc, mint)?;
+        let key = authority.pubkey();
+        match account.mint.freeze_authority {
+            Some(holder) if holder == key => {}
+            Some(holder) => {
+                return Err(Error::InvalidInput(format!(
+                    "the freeze authority of {mint} is {holder}, not {key}"
+                )));
+            }
+            None => {
+                return Err(Error::InvalidInput(format!(
+                    "{mint} has no freeze authority; its accounts can't be frozen"
+                )));
+            }
+        }
+        let mut freezer = Freezer {
+            rpc,
+            mint: *mint,
+            account,
+            authority,
+            rules,
+            log: None,
+            path: path.to_path_buf(),
+            flagged: HashSet::new(),
+            frozen: HashSet::new(),
+            cursors: HashMap::new(),
+            owners: HashMap::new(),
+        };
+        let started = freezer.replay()?;
+        if !dry_run {
+            freezer.log = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        }
+        if !started {
+            freezer.record(&FreezeEvent::Started {
+                at: now_unix(),
+                mint: *mint,
+                authority: key,
+            })?;
+        }
+        for address in freezer.rules.flagged.clone() {
+            freezer.flag(address, "listed")?;
+        }
+        Ok(freezer)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn flagged(&self) -> usize {
+        self.flagged.len()
+    }
+
+    /// Reads the log back; returns whether it was started for this mint.
+    fn replay(&mut self) -> Result<bool> {
+        let Ok(file) = File::open(&self.path) else {
+            return Ok(false);
+        };
+        let mut started = false;
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: FreezeEvent = serde_json::from_str(&line).map_err(|e| {
+                Error::InvalidInput(format!("{} line {}: {e}", self.path.display(), i + 1))
+            })?;
+            match event {
+                FreezeEvent::Started { mint, .. } if mint != self.mint => {
+                    return Err(Error::InvalidInput(format!(
+                        "{} is the freeze log of {mint}",
+                        self.path.display()
+                    )));
+                }
+                FreezeEvent::Started { .. } => started = true,
+                FreezeEvent::Flagged { address, .. } => {
+                    self.flagged.insert(address);
+                }
+                FreezeEvent::Frozen { accounts, .. } => self.frozen.extend(accounts),
+                FreezeEvent::Cursor {
+                    address, signature, ..
+                } => {
+                    self.cursors.insert(address, signature);
+                }
+                FreezeEvent::Detected { .. }
+                | FreezeEvent::Skipped { .. }
+                | FreezeEvent::Failed { .. } => {}
+            }
+        }
+        Ok(started)
+    }
+
+    fn record(&mut self, event: &FreezeEvent) -> Result<()> {
+        if let Some(file) = &mut self.log {
+            let mut line = serde_json::to_vec(event)?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn flag(&mut self, address: Pubkey, reason: &str) -> Result<Option<FreezeEvent>> {
+        if !self.flagged.insert(address) {
+            return Ok(None);
+        }
+        let event = FreezeEvent::Flagged {
+            at: now_unix(),
+            address,
+            reason: reason.to_string(),
+        };
+        self.record(&event)?;
+        Ok(Some(event))
+    }
+
+    fn is_exempt(&self, account: &Pubkey, owner: Option<&Pubkey>) -> bool {
+        self.rules
+            .exempt
+            .iter()
+            .any(|e| e == account || Some(e) == owner)
+    }
+
+    /// The flagged addresses' token accounts of the mint, with their state.
+    fn flagged_accounts(&mut self) -> Result<Vec<(Pubkey, TokenAccount)>> {
+        let mut found = Vec::new();
+        let mut flagged: Vec<Pubkey> = self.flagged.iter().copied().collect();
+        flagged.sort();
+        for address in flagged {
+            for keyed in self
+                .rpc
+                .get_token_accounts_by_owner(&address, &self.account.program_id)?
+            {
+                let token = TokenAccount::unpack(&keyed.account.data)?;
+                if token.mint == self.mint {
+                    found.push((keyed.pubkey, token));
+                }
+            }
+        }
+        // A flagged token account is its own entry.
+        let direct: Vec<Pubkey> = self
+            .flagged
+            .iter()
+            .filter(|a| !found.iter().any(|(k, _)| k == *a))
+            .copied()
+            .collect();
+        for (address, token) in self.fetch_token_accounts(&direct)? {
+            if let Some(token) = token {
+                found.push((address, token));
+            }
+        }
+        for (address, token) in &found {
+            self.owners.insert(*address, token.owner);
+        }
+        Ok(found)
+    }
+
+    /// Reads `addresses` as token accounts of the mint; anything else,
+    /// such as a wallet, comes back as `None`.
+    fn fetch_token_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<(Pubkey, Option<TokenAccount>)>> {
+        let mut out = Vec::new();
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = self.rpc.get_multiple_accounts(chunk, None)?.value;
+            for (address, account) in chunk.iter().zip(accounts) {
+                let token = account
+                    .filter(|a| a.owner == self.account.program_id)
+                    .and_then(|a| TokenAccount::unpack(&a.data).ok())
+                    .filter(|t| t.mint == self.mint);
+                out.push((*address, token));
+            }
+        }
+        Ok(out)
+    }
+
+    /// Signatures touching `address` since its cursor, oldest first, and
+    /// the newest one to move the cursor to.
+    fn new_signatures(&self, address: &Pubkey) -> Result<(Vec<SignatureInfo>, Option<String>)> {
+        let cursor = self.cursors.get(address);
+        let limit = if cursor.is_some() {
+            usize::MAX
+        } else {
+            self.rules.backfill
+        };
+        let mut found = Vec::new();
+        let mut newest = None;
+        let mut before: Option<String> = None;
+        loop {
+            let page =
+                self.rpc
+                    .get_signatures_for_address(address, before.as_deref(), SIGNATURE_PAGE)?;
+            let exhausted = page.len() < SIGNATURE_PAGE;
+            if newest.is_none() {
+                newest = page.first().map(|s| s.signature.clone());
+            }
+            before = page.last().map(|s| s.signature.clone());
+            for info in page {
+                if Some(&info.signature) == cursor || found.len() >= limit {
+                    found.reverse();
+                    return Ok((found, newest));
+                }
+                found.push(info);
+            }
+            if exhausted {
+                found.reverse();
+                return Ok((found, newest));
+            }
+        }
+    }
+
+    /// One pass: freezes the flagged accounts not frozen yet, then checks
+    /// every transaction since the last pass. Returns what happened.
+    pub fn poll(&mut self) -> Result<Vec<FreezeEvent>> {
+        let mut events = Vec::new();
+        let mut to_freeze = Vec::new();
+        for (address, token) in self.flagged_accounts()? {
+            if token.state != AccountState::Frozen && !self.frozen.contains(&address) {
+                to_freeze.push((address, Some(token.owner)));
+            }
+        }
+
+        let mut watched = vec![self.mint];
+        watched.extend(self.owners.keys().copied());
+        watched.extend(self.flagged.iter().copied());
+        watched.sort();
+        watched.dedup();
+        let mut seen = HashSet::new();
+        let mut signatures = Vec::new();
+        let mut cursors = Vec::new();
+        for address in &watched {
+            let (found, newest) = self.new_signatures(address)?;
+            for info in found {
+                if info.err.is_none() && seen.insert(info.signature.clone()) {
+                    signatures.push(info);
+                }
+            }
+            if let Some(newest) = newest
+                && self.cursors.get(address) != Some(&newest)
+            {
+                cursors.push((*address, newest));
+            }
+        }
+        signatures.sort_by_key(|s| s.slot);
+
+        let scope = ExportScope {
+            mint: self.mint,
+            program_id: self.account.program_id,
+            decimals: self.account.mint.decimals,
+            owners: self.owners.clone(),
+        };
+        for info in &signatures {
+            let Some(tx) = self.rpc.get_transaction(&info.signature)? else {
+                continue;
+            };
+            for movement in scope.decode(&info.signature, &tx)? {
+                let (MovementKind::Transfer, Some(source), Some(destination)) =
+                    (movement.kind, movement.source, movement.destination)
+                else {
+                    continue;
+                };
+                let tainted = self.flagged.contains(&source)
+                    || movement
+                        .source_owner
+                        .is_some_and(|o| self.flagged.contains(&o))
+                    || movement
+                        .authority
+                        .is_some_and(|a| self.flagged.contains(&a));
+                if !tainted
+                    || movement.amount.raw() < u128::from(self.rules.min_amount)
+                    || self.frozen.contains(&destination)
+                    || to_freeze.iter().any(|(a, _)| *a == destination)
+                {
+                    continue;
+                }
+                let owner = match self.owners.get(&destination) {
+                    Some(owner) => Some(*owner),
+                    None => self.fetch_token_accounts(&[destination])?[0]
+                        .1
+                        .as_ref()
+                        .map(|t| t.owner),
+                };
+                if let Some(owner) = owner {
+                    self.owners.insert(destination, owner);
+                }
+                let event = FreezeEvent::Detected {
+                    at: now_unix(),
+                    signature: info.signature.clone(),
+                    source,
+                    destination,
+                    owner,
+                    amount: movement.amount,
+                };
+                self.record(&event)?;
+                events.push(event);
+                to_freeze.push((destination, owner));
+            }
+        }
+
+        let mut accounts = Vec::new();
+        for (account, owner) in to_freeze {
+            if self.is_exempt(&account, owner.as_ref()) {
+                let event = FreezeEvent::Skipped {
+                    at: now_unix(),
+                    account,
+                    reason: "exempt".into(),
+                };
+                self.record(&event)?;
+                events.push(event);
+                continue;
+            }
+            accounts.push(account);
+            if self.rules.follow
+                && let Some(owner) = owner
+                && let Some(event) = self.flag(owner, &format!("owns {account}"))?
+            {
+                events.push(event);
+            }
+        }
+        events.extend(self.freeze(&accounts)?);
+
+        for (address, signature) in cursors {
+            self.record(&FreezeEvent::Cursor {
+                at: now_unix(),
+                address,
+                signature: signature.clone(),
+            })?;
+            self.cursors.insert(address, signature);
+        }
+        Ok(events)
+    }
+
+    /// Sends the freezes, as many per transaction as fit; a dry run only
+    /// reports what it would freeze.
+    fn freeze(&mut self, accounts: &[Pubkey]) -> Result<Vec<FreezeEvent>> {
+        if accounts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let key = self.authority.pubkey();
+        if self.log.is_none() {
+            return Ok(accounts
+                .iter()
+                .map(|account| FreezeEvent::Skipped {
+                    at: now_unix(),
+                    account: *account,
+                    reason: "dry run".into(),
+                })
+                .collect());
+        }
+        let program_id = self.account.program_id;
+        let batches = Packer::new(key).pack(accounts.iter().map(|account| {
+            (
+                *account,
+                vec![token::freeze_account(
+                    &program_id,
+                    account,
+                    &self.mint,
+                    &key,
+                )],
+            )
+        }))?;
+        let mut events = Vec::new();
+        for (accounts, ixs) in batches {
+            let sent = self
+                .rpc
+                .build_signed(&ixs, &key, &[self.authority])
+                .and_then(|tx| self.rpc.send_and_confirm(&tx));
+            // Keep going: a watch must not stop over one failed freeze.
+            let event = match sent {
+                Ok(signature) => {
+                    self.frozen.extend(accounts.iter().copied());
+                    FreezeEvent::Frozen {
+                        at: now_unix(),
+                        accounts,
+                        signature,
+                    }
+                }
+                Err(e) => FreezeEvent::Failed {
+                    at: now_unix(),
+                    accounts,
+                    error: e.to_string(),
+                },
+            };
+            self.record(&event)?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
@@ -0,0 +1,238 @@
+//! Operational wallets: SOL spread from a funding wallet across keypairs
+//! that pay fees for parallel batches, and swept back once they are done.
+//!
+//! The wallets are keypair files in one directory, `wallet-<n>.json`, so
+//! the processes using them and a later sweep find the same keys. Transfers
+//! are packed into as few transactions as fit (see [`crate::pack`]) and the
+//! transactions are sent
+//! concurrently; the funding wallet pays every fee, so a sweep leaves the
+//! operational wallets at exactly zero.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::keypair::{Keypair, Signature};
+use crate::pack::Packer;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::signer::Signer;
+use crate::system::{self, LAMPORTS_PER_SIGNATURE, SYSTEM_PROGRAM_ID};
+use crate::tx::{Hash, Instruction, Message};
+
+fn wallet_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("wallet-{index}.json"))
+}
+
+fn fresh_keypair(funder: &Pubkey, index: usize) -> Keypair {
+    // Fee payers hold little and are swept back; a one-off seed is enough.
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(b"launch-solana operational wallet")
+        .chain_update(funder.as_bytes())
+        .chain_update(index.to_le_bytes())
+        .chain_update(nanos.to_le_bytes())
+        .chain_update(std::process::id().to_le_bytes())
+        .finalize()
+        .into();
+    Keypair::from_seed(&seed)
+}
+
+/// The first `count` wallets in `dir`, writing keypairs for any missing.
+pub fn ensure_wallets(dir: &Path, count: usize, funder: &Pubkey) -> Result<Vec<Keypair>> {
+    fs::create_dir_all(dir)?;
+    (0..count)
+        .map(|index| {
+            let path = wallet_path(dir, index);
+            if path.exists() {
+                return Keypair::read_file(&path);
+            }
+            let keypair = fresh_keypair(funder, index);
+            fs::write(&path, serde_json::to_string(&keypair.to_bytes().to_vec())?)?;
+            Ok(keypair)
+        })
+        .collect()
+}
+
+/// Every `wallet-<n>.json` in `dir`, in index order.
+pub fn load_wallets(dir: &Path) -> Result<Vec<Keypair>> {
+    let mut indexed = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let index = name
+            .to_str()
+            .and_then(|n| n.strip_prefix("wallet-"))
+            .and_then(|n| n.strip_suffix(".json"))
+            .and_then(|n| n.parse::<usize>().ok());
+        if let Some(index) = index {
+            indexed.push(index);
+        }
+    }
+    indexed.sort_unstable();
+    indexed
+        .into_iter()
+        .map(|index| Keypair::read_file(&wallet_path(dir, index)))
+        .collect()
+}
+
+/// One SOL transfer between the funding wallet and an operational wallet.
+#[derive(Debug, Clone, Serialize)]
+pub struct FundTransfer {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub lamports: u64,
+}
+
+/// A transaction of transfers and how it went.
+#[derive(Debug, Clone, Serialize)]
+pub struct FundBatch {
+    pub transfers: Vec<FundTransfer>,
+    pub signature: Option<Signature>,
+    pub error: Option<String>,
+}
+
+impl FundBatch {
+    pub fn lamports(&self) -> u64 {
+        self.transfers.iter().map(|t| t.lamports).sum()
+    }
+}
+
+fn balances(rpc: &RpcClient, wallets: &[Pubkey]) -> Result<Vec<(Pubkey, u64)>> {
+    let accounts = rpc.get_multiple_accounts(wallets, None)?.value;
+    wallets
+        .iter()
+        .zip(accounts)
+        .map(|(wallet, account)| match account {
+            Some(account) if account.owner != SYSTEM_PROGRAM_ID || !account.data.is_empty() => {
+                Err(Error::InvalidInput(format!(
+                    "{wallet} is not a plain system wallet; it can't be funded or swept"
+                )))
+            }
+            account => Ok((*wallet, account.map_or(0, |a| a.lamports))),
+        })
+        .collect()
+}
+
+/// Transfers topping every wallet up to `each` lamports; wallets already
+/// holding that much are left alone, so funding again only fills gaps.
+pub fn top_up(
+    rpc: &RpcClient,
+    funder: &Pubkey,
+    wallets: &[Pubkey],
+    each: u64,
+) -> Result<Vec<FundTransfer>> {
+    let rent = rpc.get_minimum_balance_for_rent_exemption(0)?;
+    if each < rent {
+        return Err(Error::InvalidInput(format!(
+            "each wallet needs at least {} SOL to stay rent-exempt",
+            system::format_sol(rent)
+        )));
+    }
+    Ok(balances(rpc, wallets)?
+        .into_iter()
+        .filter(|(_, balance)| *balance < each)
+        .map(|(wallet, balance)| FundTransfer {
+            from: *funder,
+            to: wallet,
+            lamports: each - balance,
+        })
+        .collect())
+}
+
+/// Transfers returning every wallet's whole balance to `funder`.
+pub fn sweep_back(
+    rpc: &RpcClient,
+    funder: &Pubkey,
+    wallets: &[Pubkey],
+) -> Result<Vec<FundTransfer>> {
+    Ok(balances(rpc, wallets)?
+        .into_iter()
+        .filter(|(_, balance)| *balance > 0)
+        .map(|(wallet, balance)| FundTransfer {
+            from: wallet,
+            to: *funder,
+            lamports: balance,
+        })
+        .collect())
+}
+
+/// Fees the funding wallet pays for `transfers`, packed as [`execute`]
+/// packs them.
+pub fn fees(payer: &Pubkey, transfers: &[FundTransfer]) -> Result<u64> {
+    Ok(pack(payer, transfers)?
+        .iter()
+        .map(|(_, ixs)| {
+            let message = Message::new(ixs, payer, Hash::default());
+            u64::from(message.header.num_required_signatures) * LAMPORTS_PER_SIGNATURE
+        })
+        .sum())
+}
+
+type Packed = Vec<(Vec<FundTransfer>, Vec<Instruction>)>;
+
+fn pack(payer: &Pubkey, transfers: &[FundTransfer]) -> Result<Packed> {
+    Packer::new(*payer).pack(transfers.iter().map(|t| {
+        let ix = system::transfer(&t.from, &t.to, t.lamports);
+        (t.clone(), vec![ix])
+    }))
+}
+
+/// Packs `transfers` into transactions paid by `payer`, signs them in turn
+/// and sends them concurrently. `wallets` must hold the keypair of every
+/// wallet sending SOL. A batch that fails is reported, not retried, and
+/// doesn't stop the others.
+pub fn execute(
+    rpc: &RpcClient,
+    payer: &dyn Signer,
+    wallets: &[Keypair],
+    transfers: &[FundTransfer],
+) -> Result<Vec<FundBatch>> {
+    let payer_key = payer.pubkey();
+    let blockhash = rpc.get_latest_blockhash()?;
+    let mut signed = Vec::new();
+    for (moved, ixs) in pack(&payer_key, transfers)? {
+        let message = Message::new(&ixs, &payer_key, blockhash);
+        let mut keys: Vec<&dyn Signer> = vec![payer];
+        for key in &message.signer_keys()[1..] {
+            let wallet = wallets
+                .iter()
+                .find(|k| k.pubkey() == *key)
+                .ok_or_else(|| Error::InvalidInput(format!("no keypair for wallet {key}")))?;
+            keys.push(wallet);
+        }
+        let (tx, _) = rpc.sign_simulated(&ixs, &payer_key, blockhash, &keys)?;
+        signed.push((moved, tx));
+    }
+    Ok(thread::scope(|s| {
+        let handles: Vec<_> = signed
+            .into_iter()
+            .map(|(moved, tx)| {
+                s.spawn(move || {
+                    let result = rpc.send_and_confirm(&tx);
+                    FundBatch {
+                        transfers: moved,
+                        signature: Some(tx.signature()),
+                        error: result.err().map(|e| e.to_string()),
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle.join().unwrap_or_else(|_| FundBatch {
+                    transfers: Vec::new(),
+                    signature: None,
+                    error: Some("sender thread panicked".into()),
+                })
+            })
+            .collect()
+    }))
+}
@@ -0,0 +1,124 @@
+//! Launch transactions built for a browser wallet to sign, for users who
+//! will not export a private key. The mint keypair partially signs; the
+//! wallet adds the authority signature (wallet-adapter `signAllTransactions`)
+//! and sends the transactions in order.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+use crate::keypair::Keypair;
+use crate::pipeline::{self, LaunchAccounts};
+use crate::pubkey::Pubkey;
+use crate::session::{LaunchParams, Step, SupplyAccount};
+use crate::tx::{Hash, Instruction, Message, PACKET_DATA_SIZE, Transaction};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandoffTransaction {
+    pub steps: Vec<Step>,
+    /// Wire-format transaction with the mint signature filled in, base64.
+    pub transaction: String,
+    /// Signers the wallet still has to provide.
+    pub missing_signers: Vec<Pubkey>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Handoff {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub recent_blockhash: String,
+    pub transactions: Vec<HandoffTransaction>,
+}
+
+/// Packs the launch steps into as few transactions as fit the packet size.
+pub fn build(
+    params: &LaunchParams,
+    authority: &Pubkey,
+    mint: &Keypair,
+    recent_blockhash: Hash,
+    mint_rent: u64,
+) -> Result<Handoff> {
+    if let Some(SupplyAccount::Auxiliary { address, .. }) = params.supply_account {
+        return Err(Error::InvalidInput(format!(
+            "the supply account {address} has to sign its creation, which a wallet handoff can't do"
+        )));
+    }
+    let accounts = LaunchAccounts::new(*authority, mint.pubkey(), params);
+    let budget = params.compute_budget_instructions()?;
+    let mut groups: Vec<(Vec<Step>, Vec<Instruction>)> = Vec::new();
+    for step in params.steps() {
+        let ixs = pipeline::step_instructions(params, &accounts, step, mint_rent)?;
+        if let Some((steps, current)) = groups.last_mut() {
+            let mut candidate = current.clone();
+            candidate.extend(ixs.iter().cloned());
+            if sign(&candidate, authority, mint, recent_blockhash)?
+                .serialize()
+                .len()
+                <= PACKET_DATA_SIZE
+            {
+                steps.push(step);
+                *current = candidate;
+                continue;
+            }
+        }
+        let mut first = budget.clone();
+        first.extend(ixs);
+        groups.push((vec![step], first));
+    }
+
+    let transactions = groups
+        .into_iter()
+        .map(|(steps, ixs)| {
+            let tx = sign(&ixs, authority, mint, recent_blockhash)?;
+            let wire = tx.serialize();
+            if wire.len() > PACKET_DATA_SIZE {
+                return Err(Error::InvalidInput(format!(
+                    "{} transaction is {} bytes, over the {PACKET_DATA_SIZE}-byte limit",
+                    steps[0].as_str(),
+                    wire.len()
+                )));
+            }
+            let missing_signers = tx
+                .message
+                .signer_keys()
+                .iter()
+                .zip(&tx.signatures)
+                .filter(|(_, sig)| **sig == Default::default())
+                .map(|(key, _)| *key)
+                .collect();
+            Ok(HandoffTransaction {
+                steps,
+                transaction: BASE64.encode(wire),
+                missing_signers,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Handoff {
+        authority: *authority,
+        mint: accounts.mint,
+        token_account: accounts.token_account,
+        recent_blockhash: recent_blockhash.to_string(),
+        transactions,
+    })
+}
+
+/// Compiles the message and adds the mint signature when it is required.
+fn sign(
+    instructions: &[Instruction],
+    authority: &Pubkey,
+    mint: &Keypair,
+    recent_blockhash: Hash,
+) -> Result<Transaction> {
+    let message = Message::new(instructions, authority, recent_blockhash);
+    let needs_mint = message.signer_keys().contains(&mint.pubkey());
+    let mut tx = Transaction::new_unsigned(message);
+    if needs_mint {
+        tx.sign(&[mint])?;
+    }
+    Ok(tx)
+}
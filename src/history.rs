@@ -0,0 +1,133 @@
+//! Versioned backups of a token's metadata, kept in its launch record so a
+//! change made from this machine can be undone, one version at a time,
+//! while the update authority is still held.
+//!
+//! Commands that change metadata run the change through [`backed_up`],
+//! which takes a [`snapshot`] before sending and passes it to
+//! [`save_if_changed`] afterwards, whether the change succeeded or not: a
+//! partly applied change is still a change.
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::launch::{LaunchRecord, LaunchStore, MetadataVersion, now_unix};
+use crate::metadata::{self, DataV2};
+use crate::pubkey::Pubkey;
+use crate::rehost;
+use crate::rpc::RpcClient;
+use crate::signer::Signer;
+use crate::storage;
+
+/// Versions kept per token; the oldest are dropped beyond this.
+pub const MAX_VERSIONS: usize = 20;
+
+/// The metadata of `mint` as it is now, read live, with its off-chain JSON
+/// when that can be fetched. `None` if it has no metadata account.
+pub fn snapshot(
+    config: &Config,
+    rpc: &RpcClient,
+    mint: &Pubkey,
+) -> Result<Option<MetadataVersion>> {
+    let Some(current) = metadata::fetch_metadata(rpc, mint)? else {
+        return Ok(None);
+    };
+    let json = storage::fetch_json(&current.data.uri, config.ipfs_gateway()).ok();
+    Ok(Some(MetadataVersion {
+        replaced_at: 0,
+        data: current.data,
+        json,
+    }))
+}
+
+/// Adds `before` to the launch record of `mint` if the on-chain metadata
+/// no longer matches it. Tokens without a record get one rebuilt from the
+/// chain. Returns whether a version was added.
+pub fn save_if_changed(
+    store: &LaunchStore,
+    config: &Config,
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    before: Option<MetadataVersion>,
+) -> Result<bool> {
+    let Some(mut before) = before else {
+        return Ok(false);
+    };
+    let current = metadata::fetch_metadata(rpc, mint)?.map(|m| m.data);
+    if current.as_ref() == Some(&before.data) {
+        return Ok(false);
+    }
+    let mut record = match store.load(mint)? {
+        Some(record) => record,
+        None => LaunchRecord::from_chain(rpc, config, mint)?,
+    };
+    before.replaced_at = now_unix();
+    record.metadata_history.push(before);
+    let excess = record.metadata_history.len().saturating_sub(MAX_VERSIONS);
+    record.metadata_history.drain(..excess);
+    store.save(&record)?;
+    Ok(true)
+}
+
+/// Runs `change`, which alters the metadata of `mints`, and keeps in
+/// `store` the versions it replaced. Returns the change's own result, and
+/// the mints a version was kept for or why keeping them failed; that
+/// doesn't undo the change, which has already happened.
+pub fn backed_up<T>(
+    store: &LaunchStore,
+    config: &Config,
+    rpc: &RpcClient,
+    mints: &[Pubkey],
+    change: impl FnOnce() -> Result<T>,
+) -> Result<(Result<T>, Result<Vec<Pubkey>>)> {
+    let before = mints
+        .iter()
+        .map(|mint| snapshot(config, rpc, mint))
+        .collect::<Result<Vec<_>>>()?;
+    let result = change();
+    let mut kept = Vec::new();
+    for (mint, before) in mints.iter().zip(before) {
+        match save_if_changed(store, config, rpc, mint, before) {
+            Ok(true) => kept.push(*mint),
+            Ok(false) => {}
+            Err(e) => return Ok((result, Err(e))),
+        }
+    }
+    Ok((result, Ok(kept)))
+}
+
+/// Puts back `version`'s on-chain data, pointing at `uri` instead of its
+/// own URI when given, e.g. because the old JSON was pinned again
+/// elsewhere. `authority` must still hold the update authority and the
+/// metadata must still be mutable.
+pub fn restore(
+    rpc: &RpcClient,
+    authority: &dyn Signer,
+    mint: &Pubkey,
+    version: &MetadataVersion,
+    uri: Option<&str>,
+) -> Result<Signature> {
+    let current = metadata::fetch_metadata(rpc, mint)?
+        .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?;
+    rehost::ensure_update_authority(&current, mint, &authority.pubkey())?;
+    let data = DataV2 {
+        uri: uri.map_or_else(|| version.data.uri.clone(), str::to_string),
+        ..version.data.clone()
+    };
+    data.validate()?;
+    if data == current.data {
+        return Err(Error::InvalidInput(format!(
+            "{mint}'s metadata already matches that version"
+        )));
+    }
+    let ix = metadata::update_metadata_accounts_v2(
+        &rpc.programs().metadata,
+        mint,
+        &authority.pubkey(),
+        Some(&data),
+        None,
+        None,
+        None,
+    );
+    let tx = rpc.build_signed(&[ix], &authority.pubkey(), &[authority])?;
+    rpc.send_and_confirm(&tx)
+}
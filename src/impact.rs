@@ -0,0 +1,114 @@
+//! Price impact of trades against a planned constant-product pool
+//! (`token_reserve * quote_reserve = k`, as Raydium's standard AMM), to
+//! help choose how much liquidity to seed before listing.
+//!
+//! Everything here is an estimate in whole units with floating point;
+//! nothing is sent on-chain.
+
+use serde::Serialize;
+
+use crate::amount::BPS_DENOMINATOR;
+use crate::error::{Error, Result};
+
+/// Raydium's standard AMM fee.
+pub const DEFAULT_FEE_BPS: u32 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Pool {
+    /// Tokens deposited, in whole tokens.
+    pub token_reserve: f64,
+    /// Quote asset (e.g. SOL) deposited, in whole units.
+    pub quote_reserve: f64,
+    /// Swap fee taken from the input amount.
+    pub fee_bps: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Trade {
+    pub side: Side,
+    /// Quote paid for a buy, tokens sold for a sell.
+    pub amount_in: f64,
+    /// Tokens received for a buy, quote received for a sell.
+    pub amount_out: f64,
+    /// Quote per token actually paid or received, fee included.
+    pub average_price: f64,
+    /// Spot price once the trade has moved the pool.
+    pub price_after: f64,
+    /// Relative change of the spot price; positive for buys.
+    pub price_impact: f64,
+}
+
+impl Pool {
+    pub fn new(token_reserve: f64, quote_reserve: f64, fee_bps: u32) -> Result<Self> {
+        if !(token_reserve > 0.0 && quote_reserve > 0.0) {
+            return Err(Error::InvalidInput(
+                "pool reserves must both be positive".into(),
+            ));
+        }
+        if fee_bps >= BPS_DENOMINATOR {
+            return Err(Error::InvalidInput("fee must be below 100%".into()));
+        }
+        Ok(Pool {
+            token_reserve,
+            quote_reserve,
+            fee_bps,
+        })
+    }
+
+    /// Quote per token.
+    pub fn price(&self) -> f64 {
+        self.quote_reserve / self.token_reserve
+    }
+
+    fn after_fee(&self, amount: f64) -> f64 {
+        amount * (BPS_DENOMINATOR - self.fee_bps) as f64 / BPS_DENOMINATOR as f64
+    }
+
+    /// Spends `quote_in` on tokens.
+    pub fn buy(&self, quote_in: f64) -> Trade {
+        let k = self.token_reserve * self.quote_reserve;
+        let quote_after = self.quote_reserve + self.after_fee(quote_in);
+        let token_after = k / quote_after;
+        let tokens_out = self.token_reserve - token_after;
+        // The fee stays in the pool.
+        let price_after = (self.quote_reserve + quote_in) / token_after;
+        Trade {
+            side: Side::Buy,
+            amount_in: quote_in,
+            amount_out: tokens_out,
+            average_price: quote_in / tokens_out,
+            price_after,
+            price_impact: price_after / self.price() - 1.0,
+        }
+    }
+
+    /// Sells `tokens_in` for quote.
+    pub fn sell(&self, tokens_in: f64) -> Trade {
+        let k = self.token_reserve * self.quote_reserve;
+        let token_after = self.token_reserve + self.after_fee(tokens_in);
+        let quote_after = k / token_after;
+        let quote_out = self.quote_reserve - quote_after;
+        let price_after = quote_after / (self.token_reserve + tokens_in);
+        Trade {
+            side: Side::Sell,
+            amount_in: tokens_in,
+            amount_out: quote_out,
+            average_price: quote_out / tokens_in,
+            price_after,
+            price_impact: price_after / self.price() - 1.0,
+        }
+    }
+}
+
+/// Value of `supply` tokens at `price`: the fully diluted valuation for the
+/// total supply, the market cap for the circulating part.
+pub fn valuation(price: f64, supply: f64) -> f64 {
+    price * supply
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use ed25519_dalek::{Signer as _, SigningKey, VerifyingKey};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+use crate::pubkey::Pubkey;
+
+/// A 64-byte ed25519 signature, displayed as base58.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature([u8; 64]);
+
+impl Signature {
+    pub const fn new(bytes: [u8; 64]) -> Self {
+        Signature(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 64] {
+        &self.0
+    }
+
+    /// Checks this is `signer`'s ed25519 signature over `message`.
+    pub fn verify(&self, signer: &Pubkey, message: &[u8]) -> bool {
+        VerifyingKey::from_bytes(signer.as_bytes()).is_ok_and(|key| {
+            key.verify_strict(message, &ed25519_dalek::Signature::from_bytes(&self.0))
+                .is_ok()
+        })
+    }
+}
+
+impl Default for Signature {
+    fn default() -> Self {
+        Signature([0; 64])
+    }
+}
+
+impl FromStr for Signature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = bs58::decode(s.trim())
+            .into_vec()
+            .map_err(|e| Error::InvalidInput(format!("'{s}' is not valid base58: {e}")))?;
+        let bytes: [u8; 64] = bytes
+            .try_into()
+            .map_err(|_| Error::InvalidInput(format!("'{s}' is not a 64-byte signature")))?;
+        Ok(Signature(bytes))
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&bs58::encode(self.0).into_string())
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Signature({self})")
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// An ed25519 keypair in Solana's 64-byte (secret || public) layout.
+pub struct Keypair(SigningKey);
+
+impl Keypair {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes: &[u8; 64] = bytes.try_into().map_err(|_| {
+            Error::InvalidInput(format!("keypair must be 64 bytes, got {}", bytes.len()))
+        })?;
+        SigningKey::from_keypair_bytes(bytes)
+            .map(Keypair)
+            .map_err(|_| {
+                Error::InvalidInput("keypair public half does not match its secret".into())
+            })
+    }
+
+    /// Builds a keypair from a 32-byte ed25519 secret seed.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Keypair(SigningKey::from_bytes(seed))
+    }
+
+    /// Parses a base58 secret key as exported by Phantom and other wallets.
+    pub fn from_base58(s: &str) -> Result<Self> {
+        let bytes = bs58::decode(s.trim())
+            .into_vec()
+            .map_err(|e| Error::InvalidInput(format!("keypair is not valid base58: {e}")))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parses the JSON byte-array format written by `solana-keygen`.
+    pub fn from_json(s: &str) -> Result<Self> {
+        let bytes: Vec<u8> = serde_json::from_str(s)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn read_file(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)?;
+        Self::from_json(&raw).map_err(|e| Error::InvalidInput(format!("{}: {e}", path.display())))
+    }
+
+    /// Accepts any of the forms users paste into `DEV_KEYPAIR`: a JSON byte
+    /// array, a base58 secret key, or a path to a keypair file.
+    pub fn from_setting(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if value.starts_with('[') {
+            return Self::from_json(value);
+        }
+        let path = Path::new(value);
+        if path.is_file() {
+            return Self::read_file(path);
+        }
+        Self::from_base58(value)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        self.0.to_keypair_bytes()
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        Pubkey::new(self.0.verifying_key().to_bytes())
+    }
+
+    pub fn sign_message(&self, message: &[u8]) -> Signature {
+        Signature(self.0.sign(message).to_bytes())
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Keypair({})", self.pubkey())
+    }
+}
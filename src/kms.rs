@@ -0,0 +1,238 @@
+//! Signing with an Ed25519 key held in AWS KMS.
+//!
+//! The key never leaves KMS: the message goes out in a `Sign` request and
+//! only the signature comes back. Requests are signed with AWS Signature
+//! Version 4 using the usual `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`
+//! and optional `AWS_SESSION_TOKEN` variables.
+
+use std::env;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::launch::now_unix;
+use crate::phrase::hmac_sha256;
+use crate::pubkey::Pubkey;
+use crate::signer::Signer;
+use crate::time;
+
+const SERVICE: &str = "kms";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+const SIGNING_ALGORITHM: &str = "ED25519_SHA_512";
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo; the key follows it.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    pub fn from_env() -> Result<Self> {
+        let get = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+        Ok(AwsCredentials {
+            access_key_id: get("AWS_ACCESS_KEY_ID")
+                .ok_or_else(|| Error::Config("AWS_ACCESS_KEY_ID is not set".into()))?,
+            secret_access_key: get("AWS_SECRET_ACCESS_KEY")
+                .ok_or_else(|| Error::Config("AWS_SECRET_ACCESS_KEY is not set".into()))?,
+            session_token: get("AWS_SESSION_TOKEN"),
+        })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// `YYYYMMDD'T'HHMMSS'Z'`, the form SigV4 dates take.
+fn amz_date(unix: i64) -> String {
+    time::format_rfc3339(unix).replace(['-', ':'], "")
+}
+
+/// The parts of an HTTP request that SigV4 signs.
+pub struct Request<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    /// Lowercase names and values, `host` and `x-amz-date` among them,
+    /// sorted by name.
+    pub headers: &'a [(&'a str, &'a str)],
+    pub body: &'a [u8],
+    pub amz_date: &'a str,
+}
+
+/// The `Authorization` header for `request`, signed with SigV4.
+pub fn authorization(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    request: &Request,
+) -> String {
+    let Request {
+        method,
+        path,
+        headers,
+        body,
+        amz_date,
+    } = request;
+    let date = &amz_date[..8];
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{method}\n{path}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        hex(&Sha256::digest(body))
+    );
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = hmac_sha256(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, \
+         Signature={signature}",
+        credentials.access_key_id
+    )
+}
+
+/// An Ed25519 (`ECC_NIST_EDWARDS25519`) KMS key.
+pub struct KmsSigner {
+    key_id: String,
+    region: String,
+    credentials: AwsCredentials,
+    pubkey: Pubkey,
+}
+
+impl KmsSigner {
+    /// Connects to the key and reads its public half. `region` defaults to
+    /// `AWS_REGION`.
+    pub fn connect(
+        key_id: &str,
+        region: Option<&str>,
+        credentials: AwsCredentials,
+    ) -> Result<Self> {
+        let region = match region {
+            Some(region) => region.to_string(),
+            None => env::var("AWS_REGION")
+                .ok()
+                .or_else(|| env::var("AWS_DEFAULT_REGION").ok())
+                .ok_or_else(|| {
+                    Error::Config("no KMS region; add ?region= or set AWS_REGION".into())
+                })?,
+        };
+        let mut signer = KmsSigner {
+            key_id: key_id.to_string(),
+            region,
+            credentials,
+            pubkey: Pubkey::default(),
+        };
+        let response = signer.call("GetPublicKey", json!({ "KeyId": key_id }))?;
+        let der = response["PublicKey"]
+            .as_str()
+            .and_then(|k| BASE64.decode(k).ok())
+            .ok_or_else(|| Error::Http("KMS GetPublicKey: no public key".into()))?;
+        let key = der
+            .strip_prefix(&ED25519_SPKI_PREFIX[..])
+            .and_then(|k| <[u8; 32]>::try_from(k).ok())
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("KMS key {key_id} is not an Ed25519 key"))
+            })?;
+        signer.pubkey = Pubkey::new(key);
+        Ok(signer)
+    }
+
+    fn call(&self, action: &str, body: Value) -> Result<Value> {
+        let host = format!("kms.{}.amazonaws.com", self.region);
+        let target = format!("TrentService.{action}");
+        let body = serde_json::to_vec(&body)?;
+        let date = amz_date(now_unix());
+        let mut headers = vec![
+            ("content-type", CONTENT_TYPE),
+            ("host", host.as_str()),
+            ("x-amz-date", date.as_str()),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", token.as_str()));
+        }
+        headers.push(("x-amz-target", target.as_str()));
+        let auth = authorization(
+            &self.credentials,
+            &self.region,
+            SERVICE,
+            &Request {
+                method: "POST",
+                path: "/",
+                headers: &headers,
+                body: &body,
+                amz_date: &date,
+            },
+        );
+        let mut request = ureq::post(&format!("https://{host}/")).set("Authorization", &auth);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.set(name, value);
+        }
+        request
+            .send_bytes(&body)
+            .map_err(|e| match e {
+                ureq::Error::Status(status, response) => Error::Http(format!(
+                    "KMS {action}: {status} {}",
+                    response.into_string().unwrap_or_default()
+                )),
+                e => Error::Http(format!("KMS {action}: {e}")),
+            })?
+            .into_json()
+            .map_err(|e| Error::Http(format!("KMS {action}: {e}")))
+    }
+}
+
+impl Signer for KmsSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let response = self.call(
+            "Sign",
+            json!({
+                "KeyId": self.key_id,
+                "Message": BASE64.encode(message),
+                "MessageType": "RAW",
+                "SigningAlgorithm": SIGNING_ALGORITHM,
+            }),
+        )?;
+        let bytes: [u8; 64] = response["Signature"]
+            .as_str()
+            .and_then(|s| BASE64.decode(s).ok())
+            .and_then(|s| s.try_into().ok())
+            .ok_or_else(|| Error::Http("KMS Sign: no Ed25519 signature".into()))?;
+        let signature = Signature::new(bytes);
+        if !signature.verify(&self.pubkey, message) {
+            return Err(Error::Http(format!(
+                "KMS Sign: the signature is not {}'s",
+                self.pubkey
+            )));
+        }
+        Ok(signature)
+    }
+}
@@ -0,0 +1,146 @@
+//! Human-readable names for the program IDs and token mints that turn up in
+//! inspection and decoding output, so a holder list or a failed transaction
+//! reads "Raydium CPMM" or "USDC" rather than a bare address.
+//!
+//! The built-in table covers the programs the tool calls and the common
+//! quote mints; anything else can be named under `labels` in the config:
+//!
+//! ```json
+//! "labels": { "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin": "Serum v3" }
+//! ```
+//!
+//! A label in the config replaces a built-in one for the same address.
+
+use std::collections::HashMap;
+
+use crate::compute_budget::COMPUTE_BUDGET_PROGRAM_ID;
+use crate::memo::{MEMO_PROGRAM_ID, MEMO_V1_PROGRAM_ID};
+use crate::metadata::METADATA_PROGRAM_ID;
+use crate::pack::ADDRESS_LOOKUP_TABLE_PROGRAM_ID;
+use crate::programs::{
+    Programs, RAYDIUM_CPMM_DEVNET_PROGRAM_ID, RAYDIUM_CPMM_PROGRAM_ID, USDC_MINT, WRAPPED_SOL_MINT,
+};
+use crate::pubkey::Pubkey;
+use crate::squads::SQUADS_PROGRAM_ID;
+use crate::system::SYSTEM_PROGRAM_ID;
+use crate::token::{ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+
+const RAYDIUM_AMM_V4: Pubkey =
+    Pubkey::from_str_const("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+const RAYDIUM_CLMM: Pubkey = Pubkey::from_str_const("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+const ORCA_WHIRLPOOL: Pubkey =
+    Pubkey::from_str_const("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+const METEORA_DLMM: Pubkey = Pubkey::from_str_const("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
+const JUPITER_V6: Pubkey = Pubkey::from_str_const("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+const USDT_MINT: Pubkey = Pubkey::from_str_const("Es9vMFrzaCERmJfrF4H2FYD4KNNpxNw9vJ8fQXtrGx2o");
+
+const BUILTIN: [(Pubkey, &str); 20] = [
+    (SYSTEM_PROGRAM_ID, "System Program"),
+    (TOKEN_PROGRAM_ID, "SPL Token"),
+    (TOKEN_2022_PROGRAM_ID, "Token-2022"),
+    (ASSOCIATED_TOKEN_PROGRAM_ID, "Associated Token Account"),
+    (METADATA_PROGRAM_ID, "Token Metadata"),
+    (COMPUTE_BUDGET_PROGRAM_ID, "Compute Budget"),
+    (MEMO_PROGRAM_ID, "Memo"),
+    (MEMO_V1_PROGRAM_ID, "Memo (v1)"),
+    (ADDRESS_LOOKUP_TABLE_PROGRAM_ID, "Address Lookup Table"),
+    (SQUADS_PROGRAM_ID, "Squads v4"),
+    (RAYDIUM_CPMM_PROGRAM_ID, "Raydium CPMM"),
+    (RAYDIUM_CPMM_DEVNET_PROGRAM_ID, "Raydium CPMM (devnet)"),
+    (RAYDIUM_AMM_V4, "Raydium AMM v4"),
+    (RAYDIUM_CLMM, "Raydium CLMM"),
+    (ORCA_WHIRLPOOL, "Orca Whirlpool"),
+    (METEORA_DLMM, "Meteora DLMM"),
+    (JUPITER_V6, "Jupiter v6"),
+    (WRAPPED_SOL_MINT, "Wrapped SOL"),
+    (USDC_MINT, "USDC"),
+    (USDT_MINT, "USDT"),
+];
+
+/// The built-in label of `address`, if it has one.
+pub fn builtin(address: &Pubkey) -> Option<&'static str> {
+    BUILTIN
+        .iter()
+        .find(|(id, _)| id == address)
+        .map(|(_, label)| *label)
+}
+
+/// Resolves addresses to labels: the config's first, then programs
+/// deployed elsewhere under the name of the one they stand in for, then
+/// the built-in table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Labels {
+    custom: HashMap<Pubkey, String>,
+    deployed: Vec<(Pubkey, &'static str)>,
+}
+
+impl Labels {
+    pub fn new(custom: HashMap<Pubkey, String>) -> Self {
+        Labels {
+            custom,
+            deployed: Vec::new(),
+        }
+    }
+
+    /// Names the token and metadata programs of a cluster that deploys
+    /// them somewhere other than the canonical address.
+    pub fn with_programs(mut self, programs: &Programs) -> Self {
+        for (name, id) in programs.overrides() {
+            let canonical = match name {
+                "token" => TOKEN_PROGRAM_ID,
+                "token-2022" => TOKEN_2022_PROGRAM_ID,
+                _ => METADATA_PROGRAM_ID,
+            };
+            if let Some(label) = builtin(&canonical) {
+                self.deployed.push((id, label));
+            }
+        }
+        self
+    }
+
+    pub fn get(&self, address: &Pubkey) -> Option<&str> {
+        self.custom
+            .get(address)
+            .map(String::as_str)
+            .or_else(|| {
+                self.deployed
+                    .iter()
+                    .find(|(id, _)| id == address)
+                    .map(|(_, label)| *label)
+            })
+            .or_else(|| builtin(address))
+    }
+
+    /// The label of `address`, or the address when it has none.
+    pub fn name(&self, address: &Pubkey) -> String {
+        self.get(address)
+            .map_or_else(|| address.to_string(), str::to_string)
+    }
+
+    /// `Label (address)`, or just the address when it has no label.
+    pub fn describe(&self, address: &Pubkey) -> String {
+        match self.get(address) {
+            Some(label) => format!("{label} ({address})"),
+            None => address.to_string(),
+        }
+    }
+
+    /// Every label, built-in ones the config replaces left out, with
+    /// whether it came from the config.
+    pub fn entries(&self) -> Vec<(Pubkey, &str, bool)> {
+        let mut entries: Vec<(Pubkey, &str, bool)> = BUILTIN
+            .iter()
+            .chain(&self.deployed)
+            .filter(|(id, _)| !self.custom.contains_key(id))
+            .map(|(id, label)| (*id, *label, false))
+            .collect();
+        let mut custom: Vec<(Pubkey, &str, bool)> = self
+            .custom
+            .iter()
+            .map(|(id, label)| (*id, label.as_str(), true))
+            .collect();
+        custom.sort_by(|a, b| a.1.cmp(b.1));
+        entries.extend(custom);
+        entries
+    }
+}
@@ -0,0 +1,371 @@
+//! Launch records: what was created for each token, persisted per mint so
+//! post-launch tooling can work from the original parameters.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "native")]
+use std::fs;
+#[cfg(feature = "native")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "native")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::amount::TokenAmount;
+#[cfg(feature = "native")]
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::metadata::DataV2;
+use crate::network::Network;
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::RpcClient;
+use crate::session::Spend;
+#[cfg(feature = "native")]
+use crate::{metadata, storage, token};
+
+#[cfg(feature = "native")]
+const LAUNCHES_DIR: &str = "launches";
+
+/// Project links as published in the off-chain metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Links {
+    pub website: Option<String>,
+    pub twitter: Option<String>,
+    pub telegram: Option<String>,
+    pub discord: Option<String>,
+}
+
+impl Links {
+    /// Reads links from `extensions` or, as pump.fun-style JSON does, the top level.
+    pub fn from_json(json: &Value) -> Self {
+        let get = |key: &str| {
+            json.pointer(&format!("/extensions/{key}"))
+                .or_else(|| json.get(key))
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        Links {
+            website: get("website"),
+            twitter: get("twitter"),
+            telegram: get("telegram"),
+            discord: get("discord"),
+        }
+    }
+
+    /// Each link by its key in the JSON.
+    pub fn entries(&self) -> [(&'static str, Option<&str>); 4] {
+        [
+            ("website", self.website.as_deref()),
+            ("twitter", self.twitter.as_deref()),
+            ("telegram", self.telegram.as_deref()),
+            ("discord", self.discord.as_deref()),
+        ]
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Result<&mut Option<String>> {
+        match key {
+            "website" => Ok(&mut self.website),
+            "twitter" => Ok(&mut self.twitter),
+            "telegram" => Ok(&mut self.telegram),
+            "discord" => Ok(&mut self.discord),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown link '{key}'; expected website, twitter, telegram or discord"
+            ))),
+        }
+    }
+
+    /// What is wrong with each link: not an https:// URL, or for the
+    /// social ones, not on that network's domain.
+    pub fn problems(&self) -> Vec<String> {
+        self.entries()
+            .into_iter()
+            .filter_map(|(key, url)| Some((key, url?)))
+            .filter_map(|(key, url)| link_problem(key, url).map(|p| format!("{key}: {p}")))
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(Error::InvalidInput(problems.join("; ")))
+    }
+
+    /// Writes the links under `extensions`, removing the ones that are
+    /// unset, and leaves everything else in `json` as it is. Links also at
+    /// the top level, as pump.fun-style JSON has them, are kept in step.
+    pub fn write_json(&self, json: &mut Value) {
+        for (key, url) in self.entries() {
+            if json.get(key).is_some_and(Value::is_string) {
+                match url {
+                    Some(url) => json[key] = json!(url),
+                    None => {
+                        json.as_object_mut().map(|o| o.remove(key));
+                    }
+                }
+            }
+            match url {
+                Some(url) => {
+                    if !json["extensions"].is_object() {
+                        json["extensions"] = Value::Object(Map::new());
+                    }
+                    json["extensions"][key] = json!(url);
+                }
+                None => {
+                    if let Some(extensions) =
+                        json.get_mut("extensions").and_then(Value::as_object_mut)
+                    {
+                        extensions.remove(key);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Hosts a social link must be on, by key.
+const SOCIAL_HOSTS: [(&str, &[&str]); 3] = [
+    ("twitter", &["x.com", "twitter.com"]),
+    ("telegram", &["t.me", "telegram.me"]),
+    ("discord", &["discord.gg", "discord.com"]),
+];
+
+fn link_problem(key: &str, url: &str) -> Option<String> {
+    let Some(rest) = url.strip_prefix("https://") else {
+        return Some(format!("'{url}' is not an https:// link"));
+    };
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (host, path) = rest.split_at(end);
+    let host = host.to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let valid_host = host.contains('.')
+        && !host.starts_with('.')
+        && !host.ends_with('.')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    if !valid_host || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Some(format!("'{url}' is not a valid URL"));
+    }
+    let (_, hosts) = SOCIAL_HOSTS.iter().find(|(k, _)| *k == key)?;
+    if !hosts.contains(&host) {
+        return Some(format!("'{url}' is not on {}", hosts.join(" or ")));
+    }
+    if path.trim_matches('/').is_empty() {
+        return Some(format!(
+            "'{url}' links to the site, not an account or invite"
+        ));
+    }
+    None
+}
+
+/// The name and description in another language.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Localized {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// How aggregators list the token: its name and description in other
+/// languages, and the tags and category they index it under.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Listing {
+    /// By language tag, e.g. `es` or `zh-Hans`.
+    pub localized: BTreeMap<String, Localized>,
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+}
+
+impl Listing {
+    pub fn is_empty(&self) -> bool {
+        self.localized.is_empty() && self.tags.is_empty() && self.category.is_none()
+    }
+
+    /// Adds the listing to off-chain metadata: translations under
+    /// `localizations`, tags and category under `extensions`. The base
+    /// fields are left as they are.
+    pub fn write_json(&self, json: &mut Value) {
+        if !self.localized.is_empty() {
+            json["localizations"] = json!(self.localized);
+        }
+        if self.tags.is_empty() && self.category.is_none() {
+            return;
+        }
+        if !json["extensions"].is_object() {
+            json["extensions"] = Value::Object(Map::new());
+        }
+        if !self.tags.is_empty() {
+            json["extensions"]["tags"] = json!(self.tags);
+        }
+        if let Some(category) = &self.category {
+            json["extensions"]["category"] = json!(category);
+        }
+    }
+}
+
+/// Whether `tag` looks like a BCP 47 language tag: a two- or three-letter
+/// language, then subtags of up to eight letters or digits (`pt-BR`).
+pub fn is_language_tag(tag: &str) -> bool {
+    let mut parts = tag.split('-');
+    let language = parts.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|p| (1..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    pub mint: Pubkey,
+    pub network: Network,
+    /// Unix seconds when the record was created.
+    pub created_at: i64,
+    pub program_id: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub decimals: u8,
+    /// Supply at the time the record was written.
+    pub supply: TokenAmount,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub links: Links,
+    /// Lamports the launch cost, when every step's cost is known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spend: Option<Spend>,
+    /// Metadata as it was before each change made from this machine,
+    /// oldest first, for `metadata rollback`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metadata_history: Vec<MetadataVersion>,
+}
+
+/// The on-chain metadata and off-chain JSON of a token as they were before
+/// a change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataVersion {
+    /// Unix seconds when it was replaced.
+    pub replaced_at: i64,
+    pub data: DataV2,
+    /// The JSON `data.uri` pointed at, if it could be fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<Value>,
+}
+
+/// The system clock in Unix seconds; not in `wasm32` builds, where reading
+/// it panics, so code they compile takes the time from its caller.
+#[cfg(feature = "native")]
+pub fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "native")]
+impl LaunchRecord {
+    /// Rebuilds a record from on-chain state and off-chain metadata, for tokens
+    /// launched before records were kept or with other tools.
+    pub fn from_chain(rpc: &RpcClient, config: &Config, mint: &Pubkey) -> Result<Self> {
+        let mint_account = token::fetch_mint(rpc, mint)?;
+        let on_chain = metadata::fetch_metadata_cached(rpc, mint)?
+            .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?;
+        let json =
+            storage::fetch_json_cached(rpc.cache(), &on_chain.data.uri, config.ipfs_gateway()).ok();
+        let text = |key: &str| {
+            json.as_ref()
+                .and_then(|j| j.get(key))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        Ok(LaunchRecord {
+            mint: *mint,
+            network: config.network,
+            created_at: now_unix(),
+            program_id: mint_account.program_id,
+            name: on_chain.data.name,
+            symbol: on_chain.data.symbol,
+            uri: on_chain.data.uri,
+            decimals: mint_account.mint.decimals,
+            supply: mint_account.mint.supply_amount(),
+            description: text("description"),
+            image: text("image"),
+            links: json.as_ref().map(Links::from_json).unwrap_or_default(),
+            spend: None,
+            metadata_history: Vec::new(),
+        })
+    }
+}
+
+/// Launch records stored as one JSON file per mint.
+#[cfg(feature = "native")]
+pub struct LaunchStore {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "native")]
+impl LaunchStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(LaunchStore { dir })
+    }
+
+    pub fn open_default() -> Result<Self> {
+        Self::open(Config::dir()?.join(LAUNCHES_DIR))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, mint: &Pubkey) -> PathBuf {
+        self.dir.join(format!("{mint}.json"))
+    }
+
+    pub fn save(&self, record: &LaunchRecord) -> Result<()> {
+        fs::write(
+            self.path(&record.mint),
+            serde_json::to_string_pretty(record)?,
+        )?;
+        Ok(())
+    }
+
+    pub fn load(&self, mint: &Pubkey) -> Result<Option<LaunchRecord>> {
+        let path = self.path(mint);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let raw = fs::read_to_string(&path)?;
+        serde_json::from_str(&raw)
+            .map(Some)
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+    }
+
+    /// All stored records, newest first.
+    pub fn list(&self) -> Result<Vec<LaunchRecord>> {
+        let mut records: Vec<LaunchRecord> = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                // Unreadable files are skipped so one bad record can't hide the rest.
+                let raw = fs::read_to_string(&path)?;
+                if let Ok(record) = serde_json::from_str(&raw) {
+                    records.push(record);
+                }
+            }
+        }
+        records.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+        Ok(records)
+    }
+}
@@ -0,0 +1,164 @@
+//! Little-endian / Borsh byte readers and writers for account and instruction data.
+
+use crate::error::{Error, Result};
+use crate::pubkey::Pubkey;
+
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let slice = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| Error::InvalidAccountData("unexpected end of data".into()))?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::from_slice(self.take(32)?).unwrap())
+    }
+
+    /// SPL `COption<Pubkey>`: a 4-byte tag followed by the always-present key.
+    pub fn coption_pubkey(&mut self) -> Result<Option<Pubkey>> {
+        let tag = self.u32()?;
+        let key = self.pubkey()?;
+        Ok((tag == 1).then_some(key))
+    }
+
+    pub fn coption_u64(&mut self) -> Result<Option<u64>> {
+        let tag = self.u32()?;
+        let value = self.u64()?;
+        Ok((tag == 1).then_some(value))
+    }
+
+    /// Borsh string; Metaplex pads fixed-width fields with NULs, which are trimmed.
+    pub fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        let s = std::str::from_utf8(bytes)
+            .map_err(|_| Error::InvalidAccountData("string is not UTF-8".into()))?;
+        Ok(s.trim_end_matches('\0').to_string())
+    }
+
+    /// Borsh `Option<T>`: a 1-byte tag followed by the value only when present.
+    pub fn option<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T>) -> Result<Option<T>> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => read(self).map(Some),
+        }
+    }
+
+    pub fn vec<T>(&mut self, mut read: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let len = self.u32()? as usize;
+        (0..len).map(|_| read(self)).collect()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Writer::default()
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn u8(&mut self, v: u8) -> &mut Self {
+        self.buf.push(v);
+        self
+    }
+
+    pub fn bool(&mut self, v: bool) -> &mut Self {
+        self.u8(v as u8)
+    }
+
+    pub fn u16(&mut self, v: u16) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub fn u32(&mut self, v: u32) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub fn u64(&mut self, v: u64) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub fn pubkey(&mut self, v: &Pubkey) -> &mut Self {
+        self.buf.extend_from_slice(v.as_bytes());
+        self
+    }
+
+    /// Raw bytes with no length prefix.
+    pub fn bytes(&mut self, v: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(v);
+        self
+    }
+
+    pub fn string(&mut self, v: &str) -> &mut Self {
+        self.u32(v.len() as u32);
+        self.buf.extend_from_slice(v.as_bytes());
+        self
+    }
+
+    pub fn option<T>(&mut self, v: Option<T>, write: impl FnOnce(&mut Self, T)) -> &mut Self {
+        match v {
+            None => {
+                self.u8(0);
+            }
+            Some(v) => {
+                self.u8(1);
+                write(self, v);
+            }
+        }
+        self
+    }
+
+    pub fn vec<T>(&mut self, v: &[T], mut write: impl FnMut(&mut Self, &T)) -> &mut Self {
+        self.u32(v.len() as u32);
+        for item in v {
+            write(self, item);
+        }
+        self
+    }
+}
@@ -0,0 +1,217 @@
+//! Signing with the Solana app on a Ledger device.
+//!
+//! The device is reached through Linux's `hidraw` interface, so no USB
+//! library is needed: APDUs are split into 64-byte HID reports framed the
+//! way Ledger's transport expects. The app shows every message on the
+//! device and signs only once it is approved there.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::pubkey::Pubkey;
+use crate::signer::Signer;
+
+const LEDGER_VENDOR_ID: &str = "00002C97";
+const HID_PACKET_SIZE: usize = 64;
+const CHANNEL: u16 = 0x0101;
+const TAG_APDU: u8 = 0x05;
+
+const CLA: u8 = 0xe0;
+const INS_GET_PUBKEY: u8 = 0x05;
+const INS_SIGN_MESSAGE: u8 = 0x06;
+const P1_NON_CONFIRM: u8 = 0x00;
+const P1_CONFIRM: u8 = 0x01;
+const P2_EXTEND: u8 = 0x01;
+const P2_MORE: u8 = 0x02;
+const MAX_CHUNK: usize = 255;
+
+const SW_OK: u16 = 0x9000;
+const SW_USER_REFUSED: u16 = 0x6985;
+
+const HARDENED: u32 = 1 << 31;
+
+/// One account of the Solana app, at `m/44'/501'/account'[/change']`.
+pub struct Ledger {
+    device: PathBuf,
+    path: Vec<u32>,
+    pubkey: Pubkey,
+}
+
+/// `hidraw` nodes whose device reports Ledger's vendor id.
+fn devices() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir("/sys/class/hidraw") else {
+        return Vec::new();
+    };
+    let mut found: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            fs::read_to_string(e.path().join("device/uevent"))
+                .is_ok_and(|uevent| uevent.to_ascii_uppercase().contains(LEDGER_VENDOR_ID))
+        })
+        .map(|e| Path::new("/dev").join(e.file_name()))
+        .collect();
+    found.sort();
+    found
+}
+
+fn derivation(path: &[u32]) -> Vec<u8> {
+    let mut out = vec![path.len() as u8];
+    for index in path {
+        out.extend_from_slice(&(index | HARDENED).to_be_bytes());
+    }
+    out
+}
+
+fn write_apdu(device: &mut File, apdu: &[u8]) -> Result<()> {
+    let mut payload = (apdu.len() as u16).to_be_bytes().to_vec();
+    payload.extend_from_slice(apdu);
+    for (seq, chunk) in payload.chunks(HID_PACKET_SIZE - 5).enumerate() {
+        // hidraw takes a leading report id, always 0 for Ledger.
+        let mut report = vec![0u8];
+        report.extend_from_slice(&CHANNEL.to_be_bytes());
+        report.push(TAG_APDU);
+        report.extend_from_slice(&(seq as u16).to_be_bytes());
+        report.extend_from_slice(chunk);
+        report.resize(HID_PACKET_SIZE + 1, 0);
+        device.write_all(&report)?;
+    }
+    Ok(())
+}
+
+fn read_response(device: &mut File) -> Result<(Vec<u8>, u16)> {
+    let mut data = Vec::new();
+    let mut expected = None;
+    let mut seq = 0u16;
+    loop {
+        let mut report = [0u8; HID_PACKET_SIZE];
+        let n = device.read(&mut report)?;
+        let header_ok = n >= 5
+            && report[..2] == CHANNEL.to_be_bytes()
+            && report[2] == TAG_APDU
+            && report[3..5] == seq.to_be_bytes();
+        if !header_ok {
+            return Err(Error::InvalidInput(
+                "unexpected reply from the Ledger".into(),
+            ));
+        }
+        let mut body = &report[5..n];
+        if seq == 0 {
+            if body.len() < 2 {
+                return Err(Error::InvalidInput("short reply from the Ledger".into()));
+            }
+            expected = Some(u16::from_be_bytes([body[0], body[1]]) as usize);
+            body = &body[2..];
+        }
+        data.extend_from_slice(body);
+        let expected = expected.unwrap_or_default();
+        if data.len() >= expected {
+            data.truncate(expected);
+            break;
+        }
+        seq += 1;
+    }
+    if data.len() < 2 {
+        return Err(Error::InvalidInput(
+            "Ledger reply has no status word".into(),
+        ));
+    }
+    let status = u16::from_be_bytes([data[data.len() - 2], data[data.len() - 1]]);
+    data.truncate(data.len() - 2);
+    Ok((data, status))
+}
+
+fn exchange(device: &Path, ins: u8, p1: u8, payload: &[u8]) -> Result<Vec<u8>> {
+    let mut file = OpenOptions::new().read(true).write(true).open(device)?;
+    let chunks: Vec<&[u8]> = payload.chunks(MAX_CHUNK).collect();
+    let mut reply = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let mut p2 = 0;
+        if i > 0 {
+            p2 |= P2_EXTEND;
+        }
+        if i + 1 < chunks.len() {
+            p2 |= P2_MORE;
+        }
+        let mut apdu = vec![CLA, ins, p1, p2, chunk.len() as u8];
+        apdu.extend_from_slice(chunk);
+        write_apdu(&mut file, &apdu)?;
+        let (data, status) = read_response(&mut file)?;
+        match status {
+            SW_OK => reply = data,
+            SW_USER_REFUSED => {
+                return Err(Error::InvalidInput("refused on the Ledger".into()));
+            }
+            other => {
+                return Err(Error::InvalidInput(format!(
+                    "Ledger returned status {other:#06x}; is the Solana app open?"
+                )));
+            }
+        }
+    }
+    Ok(reply)
+}
+
+impl Ledger {
+    /// Finds a connected Ledger with the Solana app open and reads the key
+    /// at `m/44'/501'/account'[/change']`.
+    pub fn open(account: u32, change: Option<u32>) -> Result<Self> {
+        let mut path = vec![44, 501, account];
+        path.extend(change);
+        let candidates = devices();
+        if candidates.is_empty() {
+            return Err(Error::InvalidInput(if cfg!(target_os = "linux") {
+                "no Ledger found; connect it, unlock it and open the Solana app".into()
+            } else {
+                "Ledger signing goes through /dev/hidraw and is only available on Linux".into()
+            }));
+        }
+        let mut last_error = None;
+        for device in candidates {
+            match exchange(&device, INS_GET_PUBKEY, P1_NON_CONFIRM, &derivation(&path)) {
+                Ok(reply) => {
+                    let bytes: [u8; 32] = reply
+                        .get(..32)
+                        .and_then(|b| b.try_into().ok())
+                        .ok_or_else(|| {
+                            Error::InvalidInput("Ledger returned a malformed public key".into())
+                        })?;
+                    return Ok(Ledger {
+                        device,
+                        path,
+                        pubkey: Pubkey::new(bytes),
+                    });
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Error::InvalidInput("no Ledger found".into())))
+    }
+}
+
+impl Signer for Ledger {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let mut payload = vec![1];
+        payload.extend_from_slice(&derivation(&self.path));
+        payload.extend_from_slice(message);
+        eprintln!("Approve the transaction on the Ledger...");
+        let reply = exchange(&self.device, INS_SIGN_MESSAGE, P1_CONFIRM, &payload)?;
+        let bytes: [u8; 64] = reply
+            .get(..64)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| Error::InvalidInput("Ledger returned a malformed signature".into()))?;
+        let signature = Signature::new(bytes);
+        if !signature.verify(&self.pubkey, message) {
+            return Err(Error::InvalidInput(
+                "the Ledger's signature does not verify".into(),
+            ));
+        }
+        Ok(signature)
+    }
+}
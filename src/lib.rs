@@ -0,0 +1,142 @@
+//! Core library behind the LAUNCH! Solana command-line tool.
+//!
+//! Without the default `native` feature only transaction building, account
+//! decoding and validation are compiled, which builds for `wasm32` so a
+//! browser page can assemble launch transactions for a wallet to sign.
+
+#[cfg(feature = "native")]
+pub mod accounting;
+#[cfg(feature = "native")]
+pub mod airdrop;
+pub mod amount;
+pub mod anchor;
+#[cfg(feature = "native")]
+pub mod audit;
+#[cfg(feature = "native")]
+pub mod broadcast;
+#[cfg(feature = "native")]
+pub mod cache;
+#[cfg(feature = "native")]
+pub mod checklist;
+pub mod claim;
+#[cfg(feature = "native")]
+pub mod clawback;
+#[cfg(feature = "native")]
+pub mod collection;
+#[cfg(feature = "native")]
+pub mod collision;
+pub mod compute_budget;
+#[cfg(feature = "native")]
+pub mod config;
+pub mod cron;
+pub mod csv;
+#[cfg(feature = "native")]
+pub mod deposit;
+#[cfg(feature = "native")]
+pub mod doctor;
+pub mod draw;
+#[cfg(feature = "native")]
+pub mod emission;
+pub mod error;
+#[cfg(feature = "native")]
+pub mod events;
+pub mod explain;
+#[cfg(feature = "native")]
+pub mod export;
+pub mod extension;
+#[cfg(feature = "native")]
+pub mod fixture;
+#[cfg(feature = "native")]
+pub mod freezer;
+#[cfg(feature = "native")]
+pub mod fund;
+pub mod handoff;
+#[cfg(feature = "native")]
+pub mod history;
+#[cfg(feature = "native")]
+pub mod holders;
+pub mod impact;
+pub mod keypair;
+#[cfg(feature = "native")]
+pub mod kms;
+pub mod labels;
+pub mod launch;
+mod layout;
+#[cfg(feature = "native")]
+pub mod ledger;
+#[cfg(feature = "native")]
+pub mod liquidity;
+#[cfg(feature = "native")]
+pub mod logo;
+#[cfg(feature = "native")]
+pub mod manager;
+pub mod manifest;
+pub mod memo;
+pub mod metadata;
+pub mod network;
+pub mod pack;
+pub mod pay;
+pub mod phrase;
+pub mod pipeline;
+pub mod plan;
+#[cfg(feature = "native")]
+pub mod plugin;
+pub mod policy;
+#[cfg(feature = "native")]
+pub mod portfolio;
+pub mod preview;
+#[cfg(feature = "native")]
+pub mod process;
+pub mod programs;
+pub mod proposal;
+pub mod pubkey;
+pub mod qr;
+#[cfg(feature = "native")]
+pub mod recipients;
+#[cfg(feature = "native")]
+pub mod reclaim;
+#[cfg(feature = "native")]
+pub mod reconcile;
+pub mod registry;
+pub mod rehearsal;
+#[cfg(feature = "native")]
+pub mod rehost;
+#[cfg(feature = "native")]
+pub mod remote;
+#[cfg(feature = "native")]
+pub mod retire;
+#[cfg(feature = "native")]
+pub mod rotate;
+#[cfg(feature = "native")]
+pub mod rpc;
+#[cfg(feature = "native")]
+pub mod schedule;
+#[cfg(feature = "native")]
+pub mod screen;
+#[cfg(feature = "native")]
+pub mod script;
+pub mod session;
+pub mod signer;
+pub mod squads;
+#[cfg(feature = "native")]
+pub mod stats;
+#[cfg(feature = "native")]
+pub mod storage;
+pub mod swap;
+#[cfg(feature = "native")]
+pub mod sweep;
+pub mod system;
+pub mod time;
+pub mod token;
+#[cfg(feature = "native")]
+pub mod transcript;
+pub mod tx;
+#[cfg(feature = "native")]
+pub mod update;
+#[cfg(feature = "native")]
+pub mod validator;
+#[cfg(feature = "native")]
+pub mod vanity;
+pub mod yaml;
+
+pub use error::{Error, Result};
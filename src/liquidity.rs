@@ -0,0 +1,783 @@
+//! A launched token's liquidity on Raydium's constant-product AMM (CPMM):
+//! seeding it across several pools at once, and monitoring the pools'
+//! reserves, the project's share of them, the trading fees that share has
+//! earned and the impermanent loss against having held the tokens instead.
+//!
+//! Seeding splits the liquidity by percentage across the CPMM's fee tiers
+//! (its `AmmConfig`s), one pool per tier, every pool opening at the same
+//! price. The prices the rounded amounts imply are checked against each
+//! other before anything is signed, and every pool's transaction is
+//! simulated before the first is sent. Only Raydium's CPMM is covered;
+//! pools on other DEXes, such as Orca's Whirlpools, are not created here.
+//!
+//! In the CPMM fees stay in the reserves rather than being claimed, so the
+//! fees earned show as growth of `sqrt(token_reserve * quote_reserve)` per
+//! LP token. Both fees and impermanent loss are measured against a
+//! baseline: the pool as it was when first looked at, which `lp status`
+//! records. Run it right after adding liquidity for figures since the
+//! deposit.
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount::{BPS_DENOMINATOR, TokenAmount, format_bps};
+use crate::anchor::instruction_discriminator;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::layout::{Reader, Writer};
+use crate::network::Network;
+use crate::portfolio::to_f64;
+use crate::programs::{RAYDIUM_CPMM_DEVNET_PROGRAM_ID, RAYDIUM_CPMM_PROGRAM_ID, WRAPPED_SOL_MINT};
+use crate::pubkey::Pubkey;
+use crate::rpc::{AccountFilter, RpcClient};
+use crate::system::{RENT_SYSVAR_ID, SYSTEM_PROGRAM_ID};
+use crate::token::{
+    ACCOUNT_LEN, ASSOCIATED_TOKEN_PROGRAM_ID, MINT_LEN, TOKEN_PROGRAM_ID, TokenAccount,
+};
+use crate::tx::{AccountMeta, Instruction};
+
+/// Where the fee for creating a pool goes; the program checks it.
+const CREATE_POOL_FEE_RECEIVER: Pubkey =
+    Pubkey::from_str_const("DNXgeM9EiiaAbaWvwjHj9fQQLAX5ZsfHyvmYUNRAdNC8");
+const CREATE_POOL_FEE_RECEIVER_DEVNET: Pubkey =
+    Pubkey::from_str_const("G11FKBRaAkHAKuLCgLM6K6NUc9rTjPAznRCjZifrTQe2");
+
+const SEED_AMM_CONFIG: &[u8] = b"amm_config";
+const SEED_POOL: &[u8] = b"pool";
+const SEED_AUTHORITY: &[u8] = b"vault_and_lp_mint_auth_seed";
+const SEED_LP_MINT: &[u8] = b"pool_lp_mint";
+const SEED_VAULT: &[u8] = b"pool_vault";
+const SEED_OBSERVATION: &[u8] = b"observation";
+
+/// `AmmConfig` trade fees are parts per million.
+const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// Size of a CPMM `PoolState` account.
+const POOL_STATE_LEN: u64 = 637;
+/// Size of a CPMM `ObservationState` account, holding 100 price
+/// observations.
+const OBSERVATION_STATE_LEN: usize = 4075;
+/// Sizes of the accounts [`Cpmm::initialize`] opens at the creator's
+/// expense: the pool state, its LP mint, both vaults, the observation
+/// state and the creator's LP token account. Vaults of Token-2022 mints
+/// with extensions are larger.
+pub const POOL_ACCOUNT_LENS: [usize; 6] = [
+    POOL_STATE_LEN as usize,
+    MINT_LEN,
+    ACCOUNT_LEN,
+    ACCOUNT_LEN,
+    OBSERVATION_STATE_LEN,
+    ACCOUNT_LEN,
+];
+/// Offsets of the two mints in `PoolState`.
+const TOKEN_0_MINT_OFFSET: usize = 168;
+const TOKEN_1_MINT_OFFSET: usize = 200;
+
+const LIQUIDITY_DIR: &str = "liquidity";
+
+/// The parts of a CPMM `PoolState` the monitor reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpmmPool {
+    pub token_0_vault: Pubkey,
+    pub token_1_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub lp_decimals: u8,
+    pub mint_0_decimals: u8,
+    pub mint_1_decimals: u8,
+    pub lp_supply: u64,
+    /// Fees held in the vaults for the protocol and fund, which are not
+    /// part of the reserves.
+    pub withheld_0: u64,
+    pub withheld_1: u64,
+}
+
+impl CpmmPool {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        r.take(8)?;
+        let _amm_config = r.pubkey()?;
+        let _pool_creator = r.pubkey()?;
+        let token_0_vault = r.pubkey()?;
+        let token_1_vault = r.pubkey()?;
+        let lp_mint = r.pubkey()?;
+        let token_0_mint = r.pubkey()?;
+        let token_1_mint = r.pubkey()?;
+        let _token_0_program = r.pubkey()?;
+        let _token_1_program = r.pubkey()?;
+        let _observation = r.pubkey()?;
+        let _auth_bump = r.u8()?;
+        let _status = r.u8()?;
+        let lp_decimals = r.u8()?;
+        let mint_0_decimals = r.u8()?;
+        let mint_1_decimals = r.u8()?;
+        let lp_supply = r.u64()?;
+        let protocol_fees_0 = r.u64()?;
+        let protocol_fees_1 = r.u64()?;
+        let fund_fees_0 = r.u64()?;
+        let fund_fees_1 = r.u64()?;
+        Ok(CpmmPool {
+            token_0_vault,
+            token_1_vault,
+            lp_mint,
+            token_0_mint,
+            token_1_mint,
+            lp_decimals,
+            mint_0_decimals,
+            mint_1_decimals,
+            lp_supply,
+            withheld_0: protocol_fees_0.saturating_add(fund_fees_0),
+            withheld_1: protocol_fees_1.saturating_add(fund_fees_1),
+        })
+    }
+}
+
+/// A fee tier pools are created under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmmConfig {
+    pub index: u16,
+    pub disable_create_pool: bool,
+    /// Parts per million of each trade.
+    pub trade_fee_rate: u64,
+    /// Lamports charged for creating a pool.
+    pub create_pool_fee: u64,
+}
+
+impl AmmConfig {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        r.take(8)?;
+        let _bump = r.u8()?;
+        let disable_create_pool = r.bool()?;
+        let index = r.u16()?;
+        let trade_fee_rate = r.u64()?;
+        let _protocol_fee_rate = r.u64()?;
+        let _fund_fee_rate = r.u64()?;
+        let create_pool_fee = r.u64()?;
+        Ok(AmmConfig {
+            index,
+            disable_create_pool,
+            trade_fee_rate,
+            create_pool_fee,
+        })
+    }
+
+    pub fn fee_bps(&self) -> u32 {
+        (self.trade_fee_rate * u64::from(BPS_DENOMINATOR) / FEE_RATE_DENOMINATOR) as u32
+    }
+}
+
+/// The CPMM deployment of one cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cpmm {
+    pub program: Pubkey,
+    pub fee_receiver: Pubkey,
+}
+
+impl Cpmm {
+    /// Devnet has its own deployment; other clusters, local ones cloning
+    /// mainnet's included, use mainnet's.
+    pub fn for_network(network: Network) -> Self {
+        match network {
+            Network::Devnet => Cpmm {
+                program: RAYDIUM_CPMM_DEVNET_PROGRAM_ID,
+                fee_receiver: CREATE_POOL_FEE_RECEIVER_DEVNET,
+            },
+            _ => Cpmm {
+                program: RAYDIUM_CPMM_PROGRAM_ID,
+                fee_receiver: CREATE_POOL_FEE_RECEIVER,
+            },
+        }
+    }
+
+    fn pda(&self, seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &self.program).0
+    }
+
+    pub fn amm_config_address(&self, index: u16) -> Pubkey {
+        self.pda(&[SEED_AMM_CONFIG, &index.to_be_bytes()])
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        self.pda(&[SEED_AUTHORITY])
+    }
+
+    /// The pool of two mints under a config; the program orders the mints
+    /// by address.
+    pub fn pool_address(&self, amm_config: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey) -> Pubkey {
+        let (mint_0, mint_1) = ordered(mint_a, mint_b);
+        self.pda(&[
+            SEED_POOL,
+            amm_config.as_bytes(),
+            mint_0.as_bytes(),
+            mint_1.as_bytes(),
+        ])
+    }
+
+    pub fn fetch_amm_config(&self, rpc: &RpcClient, index: u16) -> Result<AmmConfig> {
+        let address = self.amm_config_address(index);
+        let account = rpc.get_account(&address)?.value.ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Raydium CPMM has no fee tier {index} on this cluster"
+            ))
+        })?;
+        AmmConfig::unpack(&account.data)
+    }
+
+    /// Creates a pool of `token` and `quote` and deposits the first
+    /// liquidity from `creator`'s associated token accounts, which receive
+    /// the LP tokens too.
+    pub fn initialize(
+        &self,
+        creator: &Pubkey,
+        index: u16,
+        token: Side,
+        quote: Side,
+    ) -> Instruction {
+        let amm_config = self.amm_config_address(index);
+        let (side_0, side_1) = if ordered(&token.mint, &quote.mint).0 == &token.mint {
+            (token, quote)
+        } else {
+            (quote, token)
+        };
+        let pool = self.pool_address(&amm_config, &side_0.mint, &side_1.mint);
+        let lp_mint = self.pda(&[SEED_LP_MINT, pool.as_bytes()]);
+        let vault = |mint: &Pubkey| self.pda(&[SEED_VAULT, pool.as_bytes(), mint.as_bytes()]);
+        let ata = |mint: &Pubkey, program: &Pubkey| {
+            crate::token::associated_token_address(creator, mint, program)
+        };
+        let mut w = Writer::new();
+        w.bytes(&instruction_discriminator("initialize"))
+            .u64(side_0.amount)
+            .u64(side_1.amount)
+            .u64(0);
+        Instruction {
+            program_id: self.program,
+            accounts: vec![
+                AccountMeta::new(*creator, true),
+                AccountMeta::new_readonly(amm_config, false),
+                AccountMeta::new_readonly(self.authority(), false),
+                AccountMeta::new(pool, false),
+                AccountMeta::new_readonly(side_0.mint, false),
+                AccountMeta::new_readonly(side_1.mint, false),
+                AccountMeta::new(lp_mint, false),
+                AccountMeta::new(ata(&side_0.mint, &side_0.program), false),
+                AccountMeta::new(ata(&side_1.mint, &side_1.program), false),
+                AccountMeta::new(ata(&lp_mint, &TOKEN_PROGRAM_ID), false),
+                AccountMeta::new(vault(&side_0.mint), false),
+                AccountMeta::new(vault(&side_1.mint), false),
+                AccountMeta::new(self.fee_receiver, false),
+                AccountMeta::new(self.pda(&[SEED_OBSERVATION, pool.as_bytes()]), false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(side_0.program, false),
+                AccountMeta::new_readonly(side_1.program, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(RENT_SYSVAR_ID, false),
+            ],
+            data: w.into_inner(),
+        }
+    }
+
+    /// [`initialize`](Self::initialize), preceded by wrapping the SOL the
+    /// deposit needs when the quote is wrapped SOL.
+    pub fn seed(&self, creator: &Pubkey, index: u16, token: Side, quote: Side) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        if quote.mint == WRAPPED_SOL_MINT {
+            let wrapped =
+                crate::token::associated_token_address(creator, &quote.mint, &quote.program);
+            instructions.extend([
+                crate::token::create_associated_token_account_idempotent(
+                    creator,
+                    creator,
+                    &quote.mint,
+                    &quote.program,
+                ),
+                crate::system::transfer(creator, &wrapped, quote.amount),
+                crate::token::sync_native(&quote.program, &wrapped),
+            ]);
+        }
+        instructions.push(self.initialize(creator, index, token, quote));
+        instructions
+    }
+
+    /// Every pool pairing `mint` with something.
+    pub fn find_pools(&self, rpc: &RpcClient, mint: &Pubkey) -> Result<Vec<(Pubkey, CpmmPool)>> {
+        let mut pools = Vec::new();
+        for offset in [TOKEN_0_MINT_OFFSET, TOKEN_1_MINT_OFFSET] {
+            let filters = [
+                AccountFilter::DataSize(POOL_STATE_LEN),
+                AccountFilter::Memcmp {
+                    offset,
+                    bytes: mint.as_bytes().to_vec(),
+                },
+            ];
+            for keyed in rpc
+                .get_program_accounts(&self.program, &filters, None)?
+                .value
+            {
+                pools.push((keyed.pubkey, CpmmPool::unpack(&keyed.account.data)?));
+            }
+        }
+        Ok(pools)
+    }
+
+    pub fn fetch_pool(&self, rpc: &RpcClient, address: &Pubkey) -> Result<CpmmPool> {
+        let account = rpc
+            .get_account(address)?
+            .value
+            .ok_or(Error::AccountNotFound(*address))?;
+        if account.owner != self.program {
+            return Err(Error::InvalidInput(format!(
+                "{address} is not a Raydium CPMM pool"
+            )));
+        }
+        CpmmPool::unpack(&account.data)
+    }
+}
+
+fn ordered<'a>(a: &'a Pubkey, b: &'a Pubkey) -> (&'a Pubkey, &'a Pubkey) {
+    if a.as_bytes() <= b.as_bytes() {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// One side of a deposit: a mint, its token program and the base units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Side {
+    pub mint: Pubkey,
+    pub program: Pubkey,
+    pub amount: u64,
+}
+
+/// A CPMM fee tier liquidity is seeded into, by `AmmConfig` index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct FeeTier {
+    pub index: u16,
+}
+
+impl fmt::Display for FeeTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("fee tier {}", self.index))
+    }
+}
+
+impl FromStr for FeeTier {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.trim()
+            .parse()
+            .map(|index| FeeTier { index })
+            .map_err(|_| Error::InvalidInput(format!("'{s}' is not a CPMM fee tier index")))
+    }
+}
+
+/// The share of the liquidity one fee tier's pool gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deployment {
+    pub fee_tier: FeeTier,
+    pub share_bps: u32,
+    pub token: TokenAmount,
+    pub quote: TokenAmount,
+}
+
+impl Deployment {
+    /// Quote per whole token the deposit opens the pool at, for display.
+    pub fn price(&self) -> f64 {
+        to_f64(self.quote) / to_f64(self.token)
+    }
+}
+
+/// Most decimal places a USD price is given with.
+const USD_PRICE_DECIMALS: u8 = 18;
+
+/// A positive USD price, kept as an exact decimal so amounts pegged to it
+/// are worked out in base units without floating point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsdPrice(TokenAmount);
+
+impl UsdPrice {
+    /// A price from a price API, which quotes them as floats; rounded to
+    /// nine decimal places.
+    pub fn from_f64(price: f64) -> Result<Self> {
+        if !price.is_finite() {
+            return Err(Error::InvalidInput(format!("{price} is not a price")));
+        }
+        format!("{price:.9}").parse()
+    }
+
+    pub fn to_f64(self) -> f64 {
+        to_f64(self.0)
+    }
+
+    /// How far `now` is from `self`, in basis points, rounded up.
+    pub fn moved_bps(self, now: UsdPrice) -> u128 {
+        let decimals = self.0.decimals().max(now.0.decimals());
+        let at = |p: UsdPrice| p.0.raw() * 10u128.pow(u32::from(decimals - p.0.decimals()));
+        let (then, now) = (at(self), at(now));
+        (then.abs_diff(now) * u128::from(BPS_DENOMINATOR)).div_ceil(then)
+    }
+}
+
+impl FromStr for UsdPrice {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let price = TokenAmount::parse(s.trim().trim_start_matches('$'), USD_PRICE_DECIMALS)?;
+        if price.is_zero() {
+            return Err(Error::InvalidInput(format!(
+                "'{s}' is not a positive price"
+            )));
+        }
+        // Fewest decimals, so the products pegging works out stay small.
+        let (mut raw, mut decimals) = (price.raw(), price.decimals());
+        while decimals > 0 && raw % 10 == 0 {
+            raw /= 10;
+            decimals -= 1;
+        }
+        Ok(UsdPrice(TokenAmount::from_raw(raw, decimals)))
+    }
+}
+
+impl fmt::Display for UsdPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The SOL that pairs with `token` tokens for a pool to open at
+/// `usd_price` a token, SOL being worth `sol_usd`.
+pub fn pegged_sol(
+    token: TokenAmount,
+    usd_price: UsdPrice,
+    sol_usd: UsdPrice,
+) -> Result<TokenAmount> {
+    at_ratio(token, usd_price, sol_usd, 9)
+}
+
+/// The tokens that pair with `sol` for a pool to open at `usd_price` a
+/// token, SOL being worth `sol_usd`.
+pub fn pegged_tokens(
+    sol: TokenAmount,
+    usd_price: UsdPrice,
+    sol_usd: UsdPrice,
+    decimals: u8,
+) -> Result<TokenAmount> {
+    at_ratio(sol, sol_usd, usd_price, decimals)
+}
+
+/// `amount * numerator / denominator` in base units of `decimals`, rounded
+/// to the nearest unit.
+fn at_ratio(
+    amount: TokenAmount,
+    numerator: UsdPrice,
+    denominator: UsdPrice,
+    decimals: u8,
+) -> Result<TokenAmount> {
+    let unusable = || {
+        Error::InvalidInput(format!(
+            "{amount} at that price pairs with no depositable amount; check the price"
+        ))
+    };
+    // raw * n / 10^(amount + n decimals) / (d / 10^d decimals) * 10^decimals,
+    // with the powers of ten on one side only.
+    let exponent = i32::from(decimals) + i32::from(denominator.0.decimals())
+        - i32::from(amount.decimals())
+        - i32::from(numerator.0.decimals());
+    let power = 10u128
+        .checked_pow(exponent.unsigned_abs())
+        .ok_or_else(unusable)?;
+    let (top, bottom) = if exponent >= 0 {
+        (power, 1)
+    } else {
+        (1, power)
+    };
+    let top = amount
+        .raw()
+        .checked_mul(numerator.0.raw())
+        .and_then(|n| n.checked_mul(top))
+        .ok_or_else(unusable)?;
+    let bottom = denominator
+        .0
+        .raw()
+        .checked_mul(bottom)
+        .ok_or_else(unusable)?;
+    let raw = top
+        .checked_add(bottom / 2)
+        .map(|n| n / bottom)
+        .ok_or_else(unusable)?;
+    if raw == 0 || raw > u128::from(u64::MAX) {
+        return Err(unusable());
+    }
+    Ok(TokenAmount::from_raw(raw, decimals))
+}
+
+/// Whether `diff` is more than `tolerance_bps` of `base`.
+fn beyond_bps(mut diff: u128, mut base: u128, tolerance_bps: u32) -> bool {
+    // Both halved until the products fit; at such sizes that loses nothing
+    // visible at basis-point precision.
+    while base.checked_mul(u128::from(BPS_DENOMINATOR)).is_none() {
+        diff >>= 1;
+        base >>= 1;
+    }
+    diff.saturating_mul(u128::from(BPS_DENOMINATOR))
+        > base.saturating_mul(u128::from(tolerance_bps))
+}
+
+/// Splits `token` and `quote` across `shares`, which must add up to 100%
+/// and name each fee tier once, and checks every pool would open within
+/// `tolerance_bps` of the overall price.
+pub fn plan_deployments(
+    token: TokenAmount,
+    quote: TokenAmount,
+    shares: &[(FeeTier, u32)],
+    tolerance_bps: u32,
+) -> Result<Vec<Deployment>> {
+    let total: u32 = shares.iter().map(|(_, bps)| bps).sum();
+    if total != BPS_DENOMINATOR {
+        return Err(Error::InvalidInput(format!(
+            "fee tier shares add up to {}, not 100%",
+            format_bps(total)
+        )));
+    }
+    for (i, (fee_tier, _)) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|(other, _)| other == fee_tier) {
+            return Err(Error::InvalidInput(format!("{fee_tier} is listed twice")));
+        }
+    }
+    let bps: Vec<u32> = shares.iter().map(|(_, bps)| *bps).collect();
+    let tokens = token.split_bps(&bps)?;
+    let quotes = quote.split_bps(&bps)?;
+    let mut deployments = Vec::new();
+    for (((fee_tier, share_bps), tier_token), tier_quote) in shares.iter().zip(tokens).zip(quotes) {
+        if tier_token.is_zero() || tier_quote.is_zero() {
+            return Err(Error::InvalidInput(format!(
+                "{fee_tier}'s share is too small to hold both sides"
+            )));
+        }
+        let deployment = Deployment {
+            fee_tier: *fee_tier,
+            share_bps: *share_bps,
+            token: tier_token,
+            quote: tier_quote,
+        };
+        // Its quote per token against the overall one, cross-multiplied:
+        // tier_quote / tier_token against quote / token.
+        let opens = tier_quote.raw() * token.raw();
+        let intended = quote.raw() * tier_token.raw();
+        if beyond_bps(opens.abs_diff(intended), intended, tolerance_bps) {
+            let target = to_f64(quote) / to_f64(token);
+            return Err(Error::InvalidInput(format!(
+                "{fee_tier} would open at {:.12}, {:.4}% off the intended {target:.12}; \
+                 rounding leaves its share too small for a consistent price",
+                deployment.price(),
+                (deployment.price() / target - 1.0).abs() * 100.0
+            )));
+        }
+        deployments.push(deployment);
+    }
+    Ok(deployments)
+}
+
+/// A pool from the launched token's side.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStatus {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub token_reserve: TokenAmount,
+    pub quote_reserve: TokenAmount,
+    pub lp_supply: TokenAmount,
+    /// LP tokens the project's wallets hold.
+    pub lp_held: TokenAmount,
+}
+
+impl PoolStatus {
+    /// Reads the reserves of `pool` and the LP tokens `wallets` hold.
+    pub fn load(
+        rpc: &RpcClient,
+        address: &Pubkey,
+        pool: &CpmmPool,
+        mint: &Pubkey,
+        wallets: &[Pubkey],
+    ) -> Result<Self> {
+        let flipped = if pool.token_0_mint == *mint {
+            false
+        } else if pool.token_1_mint == *mint {
+            true
+        } else {
+            return Err(Error::InvalidInput(format!(
+                "pool {address} does not trade {mint}"
+            )));
+        };
+        let vaults = rpc
+            .get_multiple_accounts(&[pool.token_0_vault, pool.token_1_vault], None)?
+            .value;
+        let mut reserves = [0u64; 2];
+        for (i, (vault, withheld)) in vaults
+            .iter()
+            .zip([pool.withheld_0, pool.withheld_1])
+            .enumerate()
+        {
+            let vault = vault.as_ref().ok_or_else(|| {
+                Error::InvalidAccountData(format!("pool {address} has lost a vault"))
+            })?;
+            reserves[i] = TokenAccount::unpack(&vault.data)?
+                .amount
+                .saturating_sub(withheld);
+        }
+        let mut lp_held = 0u64;
+        for wallet in wallets {
+            for keyed in rpc.get_token_accounts_by_owner(wallet, &TOKEN_PROGRAM_ID)? {
+                let account = TokenAccount::unpack(&keyed.account.data)?;
+                if account.mint == pool.lp_mint {
+                    lp_held = lp_held.saturating_add(account.amount);
+                }
+            }
+        }
+        let side = |i: usize, decimals: u8| TokenAmount::from_raw(reserves[i].into(), decimals);
+        let (token, quote) = if flipped {
+            (side(1, pool.mint_1_decimals), side(0, pool.mint_0_decimals))
+        } else {
+            (side(0, pool.mint_0_decimals), side(1, pool.mint_1_decimals))
+        };
+        Ok(PoolStatus {
+            pool: *address,
+            mint: *mint,
+            quote_mint: if flipped {
+                pool.token_0_mint
+            } else {
+                pool.token_1_mint
+            },
+            token_reserve: token,
+            quote_reserve: quote,
+            lp_supply: TokenAmount::from_raw(pool.lp_supply.into(), pool.lp_decimals),
+            lp_held: TokenAmount::from_raw(lp_held.into(), pool.lp_decimals),
+        })
+    }
+
+    /// Quote per whole token.
+    pub fn price(&self) -> f64 {
+        to_f64(self.quote_reserve) / to_f64(self.token_reserve)
+    }
+
+    /// The project's fraction of the pool.
+    pub fn share(&self) -> f64 {
+        let supply = to_f64(self.lp_supply);
+        if supply > 0.0 {
+            to_f64(self.lp_held) / supply
+        } else {
+            0.0
+        }
+    }
+
+    /// `sqrt(token_reserve * quote_reserve)` per LP token, which only
+    /// trading fees make grow; `None` while no LP tokens exist.
+    pub fn root_per_lp(&self) -> Option<f64> {
+        let supply = to_f64(self.lp_supply);
+        (supply > 0.0)
+            .then(|| (to_f64(self.token_reserve) * to_f64(self.quote_reserve)).sqrt() / supply)
+    }
+
+    /// `None` for a pool without LP tokens, which has nothing to measure
+    /// later growth against.
+    pub fn baseline(&self, recorded_at: i64) -> Option<Baseline> {
+        Some(Baseline {
+            pool: self.pool,
+            recorded_at,
+            price: self.price(),
+            root_per_lp: self.root_per_lp()?,
+        })
+    }
+
+    /// What has changed since `baseline`, or `None` if either side has no
+    /// LP tokens to compare.
+    pub fn performance(&self, baseline: &Baseline) -> Option<Performance> {
+        if !baseline.root_per_lp.is_normal() {
+            return None;
+        }
+        let growth = self.root_per_lp()? / baseline.root_per_lp;
+        let ratio = self.price() / baseline.price;
+        let (token, quote) = (
+            to_f64(self.token_reserve) * self.share(),
+            to_f64(self.quote_reserve) * self.share(),
+        );
+        let earned = 1.0 - 1.0 / growth;
+        Some(Performance {
+            fee_growth: growth - 1.0,
+            fees_token: token * earned,
+            fees_quote: quote * earned,
+            price_ratio: ratio,
+            impermanent_loss: impermanent_loss(ratio),
+        })
+    }
+}
+
+/// The pool when it was first looked at.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub pool: Pubkey,
+    /// Unix seconds.
+    pub recorded_at: i64,
+    pub price: f64,
+    pub root_per_lp: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Performance {
+    /// Relative growth of the reserves per LP token from fees.
+    pub fee_growth: f64,
+    /// The position's share of the fees, in whole units of each side.
+    pub fees_token: f64,
+    pub fees_quote: f64,
+    /// The token's price now over its price at the baseline.
+    pub price_ratio: f64,
+    /// The position's value, fees aside, against holding what it held at
+    /// the baseline; zero or negative.
+    pub impermanent_loss: f64,
+}
+
+/// The loss of a constant-product position against holding, for the price
+/// having moved by `ratio`.
+pub fn impermanent_loss(ratio: f64) -> f64 {
+    2.0 * ratio.sqrt() / (1.0 + ratio) - 1.0
+}
+
+fn baseline_path(pool: &Pubkey) -> Result<PathBuf> {
+    Ok(Config::dir()?
+        .join(LIQUIDITY_DIR)
+        .join(format!("{pool}.json")))
+}
+
+pub fn load_baseline(pool: &Pubkey) -> Result<Option<Baseline>> {
+    let path = baseline_path(pool)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path)?;
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+}
+
+pub fn save_baseline(baseline: &Baseline) -> Result<()> {
+    let path = baseline_path(&baseline.pool)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(baseline)?)?;
+    Ok(())
+}
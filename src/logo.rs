@@ -0,0 +1,179 @@
+//! Token logo preparation: decode any common format, square and resize it,
+//! strip embedded metadata and hand the result to an [`Uploader`]. Header
+//! banners for listing sites go through the same steps at 3:1.
+
+use std::io::{BufRead, Cursor, Seek};
+use std::path::Path;
+
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+
+use crate::error::{Error, Result};
+use crate::storage::Uploader;
+
+/// Wallets and aggregators render logos at up to this size.
+pub const RECOMMENDED_SIZE: u32 = 512;
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 128;
+/// Below this the logo is unusably blurry even in a token list.
+const MIN_SOURCE_SIZE: u32 = 64;
+/// Listing sites show headers at 3:1, up to this size.
+pub const HEADER_WIDTH: u32 = 1500;
+pub const HEADER_HEIGHT: u32 = 500;
+/// Below this width a header is rejected as too small to display.
+const MIN_HEADER_WIDTH: u32 = 600;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LogoOptions {
+    pub size: u32,
+    pub thumbnail: Option<u32>,
+}
+
+impl Default for LogoOptions {
+    fn default() -> Self {
+        LogoOptions {
+            size: RECOMMENDED_SIZE,
+            thumbnail: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessedLogo {
+    pub png: Vec<u8>,
+    pub thumbnail: Option<Vec<u8>>,
+    pub source_format: Option<ImageFormat>,
+    pub source_width: u32,
+    pub source_height: u32,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogoUris {
+    pub image: String,
+    pub thumbnail: Option<String>,
+}
+
+fn image_error(source: &str, e: impl std::fmt::Display) -> Error {
+    Error::InvalidInput(format!("{source}: {e}"))
+}
+
+/// Decodes `path`, applies its EXIF orientation and re-encodes it as a square
+/// PNG. Re-encoding drops EXIF and any other embedded metadata.
+pub fn process(path: &Path, options: LogoOptions) -> Result<ProcessedLogo> {
+    let source = path.display().to_string();
+    square(decode(ImageReader::open(path)?, &source)?, &source, options)
+}
+
+/// Like [`process`], for an image already in memory, e.g. downloaded from
+/// `source`.
+pub fn process_bytes(bytes: &[u8], source: &str, options: LogoOptions) -> Result<ProcessedLogo> {
+    square(
+        decode(ImageReader::new(Cursor::new(bytes)), source)?,
+        source,
+        options,
+    )
+}
+
+fn square(
+    (image, source_format): (DynamicImage, Option<ImageFormat>),
+    source: &str,
+    options: LogoOptions,
+) -> Result<ProcessedLogo> {
+    let (width, height) = (image.width(), image.height());
+    if width.min(height) < MIN_SOURCE_SIZE {
+        return Err(Error::InvalidInput(format!(
+            "{source} is {width}x{height}; logos must be at least {MIN_SOURCE_SIZE}px on each side"
+        )));
+    }
+    let mut warnings = Vec::new();
+    if width != height {
+        warnings.push(format!(
+            "image is {width}x{height}; it was center-cropped to a square"
+        ));
+    }
+    if width.min(height) < options.size {
+        warnings.push(format!(
+            "image is smaller than {0}x{0} and was upscaled; provide a larger source for a sharper logo",
+            options.size
+        ));
+    }
+
+    let png = encode_png(&image, options.size)?;
+    let thumbnail = options
+        .thumbnail
+        .map(|size| encode_png(&image, size))
+        .transpose()?;
+    Ok(ProcessedLogo {
+        png,
+        thumbnail,
+        source_format,
+        source_width: width,
+        source_height: height,
+        warnings,
+    })
+}
+
+/// Decodes an image and applies its EXIF orientation.
+fn decode<R: BufRead + Seek>(
+    reader: ImageReader<R>,
+    source: &str,
+) -> Result<(DynamicImage, Option<ImageFormat>)> {
+    let reader = reader.with_guessed_format()?;
+    let source_format = reader.format();
+    let mut decoder = reader.into_decoder().map_err(|e| image_error(source, e))?;
+    let orientation = decoder.orientation().map_err(|e| image_error(source, e))?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| image_error(source, e))?;
+    image.apply_orientation(orientation);
+    Ok((image, source_format))
+}
+
+/// Re-encodes `path` as a [`HEADER_WIDTH`] by [`HEADER_HEIGHT`] PNG,
+/// center-cropped to 3:1. Returns the PNG and warnings about the source.
+pub fn process_header(path: &Path) -> Result<(Vec<u8>, Vec<String>)> {
+    let (image, _) = decode(ImageReader::open(path)?, &path.display().to_string())?;
+    let (width, height) = (image.width(), image.height());
+    if width < MIN_HEADER_WIDTH || height < MIN_HEADER_WIDTH / 3 {
+        return Err(Error::InvalidInput(format!(
+            "{} is {width}x{height}; headers must be at least {MIN_HEADER_WIDTH}x{}",
+            path.display(),
+            MIN_HEADER_WIDTH / 3
+        )));
+    }
+    let mut warnings = Vec::new();
+    if width != height * 3 {
+        warnings.push(format!(
+            "header is {width}x{height}; it was center-cropped to 3:1"
+        ));
+    }
+    if width < HEADER_WIDTH {
+        warnings.push(format!(
+            "header is narrower than {HEADER_WIDTH}px and was upscaled"
+        ));
+    }
+    let png = encode_png_at(&image, HEADER_WIDTH, HEADER_HEIGHT)?;
+    Ok((png, warnings))
+}
+
+fn encode_png(image: &DynamicImage, size: u32) -> Result<Vec<u8>> {
+    encode_png_at(image, size, size)
+}
+
+fn encode_png_at(image: &DynamicImage, width: u32, height: u32) -> Result<Vec<u8>> {
+    let resized = image.resize_to_fill(width, height, FilterType::Lanczos3);
+    let mut out = Cursor::new(Vec::new());
+    resized
+        .write_to(&mut out, ImageFormat::Png)
+        .map_err(|e| Error::InvalidInput(format!("could not encode PNG: {e}")))?;
+    Ok(out.into_inner())
+}
+
+/// Uploads the processed logo (and thumbnail, if any) under `name`.
+pub fn upload(uploader: &dyn Uploader, name: &str, logo: &ProcessedLogo) -> Result<LogoUris> {
+    let image = uploader.upload_file(&format!("{name}.png"), "image/png", &logo.png)?;
+    let thumbnail = logo
+        .thumbnail
+        .as_ref()
+        .map(|png| uploader.upload_file(&format!("{name}-thumb.png"), "image/png", png))
+        .transpose()?;
+    Ok(LogoUris { image, thumbnail })
+}
@@ -1,3 +1,7771 @@
-fn main() {
-    println!("Hello, world!");
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use sha2::{Digest, Sha256};
+
+use solanaapp::accounting;
+use solanaapp::airdrop::{self, Airdrop, AirdropReport, EngineOptions, Journal};
+use solanaapp::amount::{self, Allocation, TokenAmount};
+use solanaapp::audit::{self, AuditLog};
+use solanaapp::broadcast::{BroadcastReport, Broadcaster};
+use solanaapp::cache::RpcCache;
+use solanaapp::checklist;
+use solanaapp::claim::{self, ClaimLedger, ClaimServer, VoucherSet};
+use solanaapp::clawback::{self, Clawback};
+use solanaapp::collection::{self, Membership, Parent};
+use solanaapp::collision;
+use solanaapp::config::{Config, Credentials, Network, Timeouts, WatchWallet};
+use solanaapp::deposit::{self, DepositTest};
+use solanaapp::doctor::{self, Status};
+use solanaapp::draw::{self, DrawRecord};
+use solanaapp::emission::{self, EmissionEvent, EmissionState, Emitter};
+use solanaapp::events::{Event, EventLog};
+use solanaapp::export::{self, ExportScope};
+use solanaapp::extension::{self, Extension, Pointer, TokenGroup, TokenGroupMember, TransferHook};
+use solanaapp::fixture::{Recorder, Replay};
+use solanaapp::freezer::{self, FreezeRules, Freezer};
+use solanaapp::fund::{self, FundBatch};
+use solanaapp::handoff;
+use solanaapp::history;
+use solanaapp::holders;
+use solanaapp::impact::{self, Pool, Trade};
+use solanaapp::keypair::{Keypair, Signature};
+use solanaapp::labels::Labels;
+use solanaapp::launch::{
+    self, LaunchRecord, LaunchStore, Links, Listing, Localized, MetadataVersion,
+};
+use solanaapp::liquidity::{self, Cpmm, FeeTier, PoolStatus, UsdPrice};
+use solanaapp::logo::{self, LogoOptions};
+use solanaapp::manager::SolanaTokenManager;
+use solanaapp::manifest::Manifest;
+use solanaapp::memo;
+use solanaapp::metadata::{self, DataV2};
+use solanaapp::pack;
+use solanaapp::pay::{self, TransferRequest};
+use solanaapp::phrase;
+use solanaapp::plan::{self, ChainState, Difference, Plan, TokenSpec};
+use solanaapp::plugin::{self, Hook};
+use solanaapp::portfolio::{self, Portfolio};
+use solanaapp::preview::{self, Finding, Severity};
+use solanaapp::process;
+use solanaapp::programs;
+use solanaapp::proposal::{self, MetadataUpdate, Proposal};
+use solanaapp::pubkey::Pubkey;
+use solanaapp::qr::QrCode;
+use solanaapp::reclaim;
+use solanaapp::reconcile::{self, Anomaly};
+use solanaapp::registry;
+use solanaapp::rehearsal;
+use solanaapp::rehost::{self, RehostOptions};
+use solanaapp::retire::{self, Retirement};
+use solanaapp::rotate;
+use solanaapp::rpc::{
+    Commitment, LATENCY_WARNING, MAX_MULTIPLE_ACCOUNTS, RpcClient, SLOT_LAG_WARNING,
+};
+use solanaapp::schedule::{self, Due, ScheduleStatus, ScheduleStore, ScheduledRevocation};
+use solanaapp::screen::{self, Screen};
+use solanaapp::script::{self, Outcome, Script};
+use solanaapp::session::{
+    GroupRole, LaunchParams, LaunchSession, SessionStore, Spend, Step, SupplyAccount,
+};
+use solanaapp::signer::{self, Signer};
+use solanaapp::squads::{self, Member, ProposalStatus};
+use solanaapp::stats::{Stats, StatsFile};
+use solanaapp::storage::Uploader;
+use solanaapp::swap;
+use solanaapp::sweep::{self, SweepMove, SweepPlan};
+use solanaapp::system;
+use solanaapp::time;
+use solanaapp::token::{self, TokenAccount};
+use solanaapp::transcript::{Prompter, Transcript};
+use solanaapp::tx::Instruction;
+use solanaapp::update;
+use solanaapp::validator::{TestValidator, VALIDATOR_BIN, ValidatorOptions};
+use solanaapp::vanity::{self, Backend, Pattern, Position, Search};
+use solanaapp::{Error, Result};
+
+const LOCAL_AIRDROP_LAMPORTS: u64 = 100 * system::LAMPORTS_PER_SOL;
+/// Recipients whose screening warnings are printed before the rest are counted.
+const MAX_LISTED_WARNINGS: usize = 20;
+/// Launch options hidden and refused outside expert mode.
+const EXPERT_LAUNCH_ARGS: &[&str] = &[
+    "supply_owner",
+    "supply_account",
+    "compute_unit_limit",
+    "compute_unit_price",
+];
+
+#[derive(Parser)]
+#[command(name = "launch-solana", version, about = "LAUNCH! Solana toolkit")]
+struct Cli {
+    /// Cluster to operate on (mainnet, devnet, testnet, localnet), or an SVM
+    /// chain (eclipse, eclipse-testnet, soon, soon-testnet).
+    #[arg(long, global = true)]
+    network: Option<Network>,
+
+    /// Custom RPC endpoint; overrides the network default.
+    #[arg(long, global = true)]
+    url: Option<String>,
+
+    /// Start a throwaway solana-test-validator (with the metadata program
+    /// cloned from mainnet), fund DEV_KEYPAIR from it, and stop it on exit.
+    #[arg(long, global = true, conflicts_with_all = ["network", "url"])]
+    local: bool,
+
+    /// Append every RPC call and its response to this fixture file.
+    #[arg(long, global = true, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Answer RPC calls from a recorded fixture instead of the network.
+    #[arg(long, global = true, value_name = "PATH", conflicts_with_all = ["local", "record"])]
+    replay: Option<PathBuf>,
+
+    /// Reuse account scans (holders, reconcile, export) and token metadata
+    /// (preview, registry) fetched within this many seconds; 0 always fetches.
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = 300)]
+    cache_ttl: u64,
+
+    /// Fetch everything live for this run, as `--cache-ttl 0` does.
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Submit launch transactions through this endpoint (e.g. a staked
+    /// sender) instead of the RPC; overrides `sender_url` in the config file.
+    #[arg(long, global = true, value_name = "URL")]
+    sender: Option<String>,
+
+    /// Also send launch transactions to this RPC endpoint; repeat for several.
+    /// Adds to `broadcast_urls` from the config file.
+    #[arg(long = "broadcast", global = true, value_name = "URL")]
+    broadcast: Vec<String>,
+
+    /// Write NDJSON progress events to this file, or to stdout with `-`.
+    #[arg(long, global = true, value_name = "PATH")]
+    events: Option<PathBuf>,
+
+    /// How settled reads and confirmations must be (processed, confirmed,
+    /// finalized); overrides `commitment` in the config file.
+    #[arg(long, global = true, value_name = "LEVEL")]
+    commitment: Option<Commitment>,
+
+    /// Commitment to wait for after revoking or handing over an authority
+    /// (default finalized); overrides `critical_commitment` in the config file.
+    #[arg(long, global = true, value_name = "LEVEL")]
+    critical_commitment: Option<Commitment>,
+
+    /// SPL Token program to call, for clusters that deploy it (or a
+    /// compatible program) elsewhere; overrides `programs` in the config file.
+    #[arg(long, global = true, value_name = "PROGRAM_ID")]
+    token_program: Option<Pubkey>,
+
+    /// Token-2022 program to call; overrides `programs` in the config file.
+    #[arg(long, global = true, value_name = "PROGRAM_ID")]
+    token_2022_program: Option<Pubkey>,
+
+    /// Token Metadata program to call; overrides `programs` in the config file.
+    #[arg(long, global = true, value_name = "PROGRAM_ID")]
+    metadata_program: Option<Pubkey>,
+
+    /// Seconds one RPC request may take before it is abandoned (default
+    /// 60); overrides `timeouts.rpc` in the config file.
+    #[arg(long, global = true, value_name = "SECONDS")]
+    rpc_timeout: Option<u64>,
+
+    /// Seconds a plugin may run before it is stopped (default 60);
+    /// overrides `timeouts.plugin` in the config file.
+    #[arg(long, global = true, value_name = "SECONDS")]
+    plugin_timeout: Option<u64>,
+
+    /// Seconds `--local` waits for solana-test-validator to answer (default
+    /// 90); overrides `timeouts.validator` in the config file.
+    #[arg(long, global = true, value_name = "SECONDS")]
+    validator_timeout: Option<u64>,
+
+    /// Expose every launch parameter for this run, as `"expert": true` in
+    /// the config file does permanently.
+    #[arg(long, global = true)]
+    expert: bool,
+
+    /// Write the command, every question asked and its answer, and how the
+    /// run ended to this JSON file; see `transcript replay`.
+    #[arg(long, global = true, value_name = "PATH")]
+    transcript: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a token: mint, supply, metadata. Runs as a resumable launch session.
+    Launch(Box<LaunchArgs>),
+    /// List, inspect, switch between and resume launch sessions.
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
+    },
+    /// Show or run again a transcript written with --transcript.
+    Transcript {
+        #[command(subcommand)]
+        action: TranscriptAction,
+    },
+    /// Compare the sum of all token account balances against the mint supply.
+    Reconcile {
+        mint: Pubkey,
+        /// Write the per-account ledger to this CSV file.
+        #[arg(long)]
+        ledger: Option<PathBuf>,
+        /// Print the full report as JSON instead of a summary.
+        #[arg(long)]
+        json: bool,
+    },
+    /// List a token's holders, aggregated per owner, largest first.
+    Holders {
+        mint: Pubkey,
+        /// Write every holder to this CSV file.
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Number of holders to print.
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+        /// Print the full snapshot as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Summarize wallets: SOL, token holdings with USD value, rent in empty
+    /// accounts and tokens launched from them.
+    /// Watch-only wallets: cold wallets inspected by address, without
+    /// their keypair ever being present.
+    Wallet {
+        #[command(subcommand)]
+        action: WalletAction,
+    },
+    /// Names shown for program IDs and mints in inspection output:
+    /// built-in ones for common programs and quote mints, plus your own.
+    Labels {
+        #[command(subcommand)]
+        action: LabelsAction,
+    },
+    /// Find every mint a wallet is still mint, freeze or metadata update
+    /// authority of.
+    Authorities {
+        /// Address or watched wallet label; defaults to DEV_KEYPAIR.
+        wallet: Option<String>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// List every token any wallet holds, marking the ones launched from
+    /// here and the ones it holds an authority of: due diligence on
+    /// partners and market makers.
+    WalletScan {
+        /// Address or watched wallet label.
+        wallet: String,
+        /// Also search the token and metadata programs for mints the wallet
+        /// controls without holding any; public RPCs often refuse this.
+        #[arg(long)]
+        deep: bool,
+        #[arg(long)]
+        json: bool,
+    },
+    Portfolio {
+        /// Wallet to include besides DEV_KEYPAIR and the watched wallets;
+        /// repeat for several.
+        #[arg(long = "wallet")]
+        wallets: Vec<Pubkey>,
+        /// Print the portfolios as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Send tokens from DEV_KEYPAIR's account, creating the recipient's account if needed.
+    Transfer {
+        mint: Pubkey,
+        /// Recipient wallet.
+        #[arg(long)]
+        to: Pubkey,
+        /// Amount in whole tokens, e.g. 1_000.5.
+        #[arg(long)]
+        amount: String,
+        /// Record this text (e.g. "team allocation", an invoice ID) with the transfer.
+        #[arg(long)]
+        memo: Option<String>,
+        /// Send even if the recipient looks wrong, without asking.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Move every authority, token and lamport out of a possibly
+    /// compromised wallet into a new one, then check nothing is left.
+    Rotate(RotateArgs),
+    /// Move a token's balances from several wallets into a treasury account.
+    /// DEV_KEYPAIR pays the fees and is swept too.
+    Sweep {
+        mint: Pubkey,
+        /// Treasury wallet; its associated token account receives everything.
+        #[arg(long)]
+        to: Pubkey,
+        /// Keypair file of another wallet to sweep; repeat for several.
+        #[arg(long = "from", value_name = "KEYPAIR")]
+        from: Vec<PathBuf>,
+        /// Record this text with every sweep transaction.
+        #[arg(long)]
+        memo: Option<String>,
+        /// Sweep every wallet without asking.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Spread SOL from DEV_KEYPAIR across operational wallets that pay fees
+    /// for parallel batches, and sweep what is left back.
+    Fund {
+        #[command(subcommand)]
+        action: FundAction,
+    },
+    /// Export the mint, burn and transfer history of a token as CSV.
+    ExportTxs {
+        mint: Pubkey,
+        /// Output file; prints to stdout when omitted.
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Maximum number of signatures to read per address.
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Export the project's side of a token's history for bookkeeping: each
+    /// mint, airdrop, transfer and fee, valued in USD when it happened.
+    ExportAccounting(ExportAccountingArgs),
+    /// Re-upload a token's metadata JSON to IPFS and point the on-chain URI at it.
+    Rehost {
+        mint: Pubkey,
+        /// Use a local copy of the metadata JSON instead of fetching the current URI.
+        #[arg(long)]
+        from_file: Option<PathBuf>,
+        /// Also re-host the logo referenced by the JSON's `image` field.
+        #[arg(long)]
+        rehost_image: bool,
+        /// Only verify that the migration is possible.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Draw an address or link as a QR code in the terminal, for phone
+    /// wallets to scan.
+    Qr {
+        /// Address, URL or any other text up to 213 bytes.
+        text: String,
+    },
+    /// Make a Solana Pay link, with its QR code, asking for a payment in
+    /// the token: for a till, an invoice or a pay button.
+    PayLink(PayLinkArgs),
+    /// Send a small test deposit to an exchange and write the evidence its
+    /// listing team asks for.
+    DepositTest(DepositTestArgs),
+    /// Convert a logo to a square PNG without metadata, optionally uploading it.
+    Logo {
+        path: PathBuf,
+        /// Output edge length in pixels.
+        #[arg(long, default_value_t = logo::RECOMMENDED_SIZE)]
+        size: u32,
+        /// Also produce a thumbnail of this edge length.
+        #[arg(long, num_args = 0..=1, default_missing_value = "128")]
+        thumbnail: Option<u32>,
+        /// Directory to write the processed files to.
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+        /// Upload the processed files to IPFS and print their URIs.
+        #[arg(long)]
+        upload: bool,
+    },
+    /// Show how wallets will display a token's metadata and flag common mistakes.
+    Preview {
+        /// Existing token to preview; fields below override its values.
+        mint: Option<Pubkey>,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        symbol: Option<String>,
+        #[arg(long)]
+        uri: Option<String>,
+        /// Off-chain JSON file; fetched from the URI when omitted.
+        #[arg(long)]
+        json: Option<PathBuf>,
+    },
+    /// Generate token registry and listing site submissions (Jupiter,
+    /// CoinGecko, DEX Screener, Birdeye) from the launch record.
+    Registry {
+        mint: Pubkey,
+        #[arg(long, value_enum, default_value_t = RegistryTarget::All)]
+        target: RegistryTarget,
+        /// Write the payloads into this directory instead of printing them;
+        /// needed for the image files.
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Logo for the listing bundles; by default the metadata's image.
+        #[arg(long)]
+        icon: Option<PathBuf>,
+        /// Header banner for DEX Screener, cropped to 3:1.
+        #[arg(long)]
+        header: Option<PathBuf>,
+    },
+    /// Squads v4 multisig: create a vault, hand authorities to it, and
+    /// propose, approve and execute authority operations through it.
+    Squads {
+        #[command(subcommand)]
+        action: SquadsAction,
+    },
+    /// Pre-sign an authority revocation now and broadcast it at a set time or slot.
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleAction,
+    },
+    /// Mint a set amount to a wallet on a cron schedule, up to a cap, for
+    /// staking rewards and liquidity mining.
+    Emission {
+        #[command(subcommand)]
+        action: EmissionAction,
+    },
+    /// Liquidity pools of a launched token and the project's position in
+    /// them.
+    Lp {
+        #[command(subcommand)]
+        action: LpAction,
+    },
+    /// Metadata updates for an update authority held by another key,
+    /// written to a proposal file for its holder to sign.
+    Proposal {
+        #[command(subcommand)]
+        action: ProposalAction,
+    },
+    /// Show the key commands sign with: DEV_SIGNER if set, else DEV_KEYPAIR.
+    Signer {
+        /// Check this specifier instead, e.g. usb://ledger or kms://<key id>.
+        spec: Option<String>,
+    },
+    /// Review a metadata update proposal file and add DEV_KEYPAIR's signature.
+    SignProposal {
+        path: PathBuf,
+        /// Sign without asking for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Check that the audit log of signed transactions is intact.
+    VerifyAuditLog {
+        /// Log to check; defaults to audit.jsonl in the config directory.
+        path: Option<PathBuf>,
+    },
+    /// Inspect or change the transfer hook program of a Token-2022 mint.
+    TransferHook {
+        #[command(subcommand)]
+        action: TransferHookAction,
+    },
+    /// Show Token-2022 token groups and add or remove their members.
+    Group {
+        #[command(subcommand)]
+        action: GroupAction,
+    },
+    /// Protections on DEV_KEYPAIR's own Token-2022 token accounts: CPI guard
+    /// and required memos on incoming transfers.
+    Account {
+        #[command(subcommand)]
+        action: AccountAction,
+    },
+    /// Estimate the price impact of sample trades and the implied valuation
+    /// for a planned constant-product pool, before adding liquidity.
+    Impact(ImpactArgs),
+    /// Show the mint addresses MINT_PASSPHRASE derives, or write one's keypair file.
+    MintKey {
+        #[arg(default_value_t = 0)]
+        index: u32,
+        /// How many consecutive indexes to show.
+        #[arg(long, default_value_t = 1, conflicts_with = "out")]
+        count: u32,
+        /// Write the keypair at INDEX to this file, in solana-keygen's format.
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Rent-exempt minimums for the accounts a launch creates, at the
+    /// cluster's current rent.
+    Rent {
+        /// Price a Token-2022 mint, e.g. to compare extension choices.
+        #[arg(long)]
+        token_2022: bool,
+        /// Mint extension to include (e.g. transfer-hook, metadata-pointer);
+        /// repeat for several.
+        #[arg(long = "extension", value_name = "NAME", requires = "token_2022")]
+        extensions: Vec<String>,
+        /// Also price token accounts for this many holders.
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        holders: u64,
+    },
+    /// Show the endpoint's current slot, how long it takes to answer and how
+    /// far it trails a reference endpoint, to catch a degraded RPC before a
+    /// time-sensitive step.
+    Health {
+        /// Endpoint to compare slots with; defaults to the network's public
+        /// RPC when another one is in use, except on localnet.
+        #[arg(long, value_name = "URL")]
+        reference: Option<String>,
+        /// Keep the line refreshed until interrupted.
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between refreshes with --watch.
+        #[arg(long, value_name = "SECS", default_value_t = 5)]
+        interval: u64,
+    },
+    /// Check everything a launch depends on: installed tools, the endpoint
+    /// and its genesis hash, DEV_KEYPAIR and its balance, writable
+    /// directories, the clock and the deployed programs.
+    Doctor,
+    /// Show the usage statistics kept on this machine when `"stats": true`
+    /// is set in the config file.
+    Stats {
+        /// Print them as JSON.
+        #[arg(long)]
+        json: bool,
+        /// Delete them and start counting afresh.
+        #[arg(long, conflicts_with = "json")]
+        reset: bool,
+    },
+    /// Replace this binary with the latest release's build for this
+    /// platform, once its checksums, signed together with its version,
+    /// verify. Needs `update_repo` and `update_key` in the config file.
+    SelfUpdate {
+        /// Only report whether a newer release exists.
+        #[arg(long)]
+        check: bool,
+        /// Skip the confirmation prompt.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Estimate how long grinding a vanity mint address would take on this machine.
+    Vanity {
+        /// Text the address should start with; repeat to accept any of several.
+        #[arg(long, value_name = "TEXT")]
+        prefix: Vec<String>,
+        /// Text the address should end with; repeat to accept any of several.
+        #[arg(long, value_name = "TEXT")]
+        suffix: Vec<String>,
+        #[arg(long)]
+        ignore_case: bool,
+        /// Threads to measure with; defaults to one per core.
+        #[arg(long, value_name = "N")]
+        threads: Option<usize>,
+        /// How long to measure the key rate for.
+        #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+        seconds: u64,
+    },
+    /// Declarative launches: a YAML plan of the token's mint, supply,
+    /// metadata, allocations and authorities, applied by sending only
+    /// what is missing.
+    Plan {
+        #[command(subcommand)]
+        action: PlanAction,
+    },
+    /// Buy a little of a token through Jupiter once its pool exists, to
+    /// prove it is tradable; the proof is saved with its launch session.
+    TestBuy {
+        mint: Pubkey,
+        /// SOL to spend.
+        #[arg(long, default_value = "0.01")]
+        sol: String,
+        /// Most the price may move against the quote, in basis points.
+        #[arg(long, default_value_t = 100)]
+        slippage_bps: u16,
+    },
+    /// Group tokens under a Metaplex collection NFT, so explorers show
+    /// them as one family.
+    Collection {
+        #[command(subcommand)]
+        action: CollectionAction,
+    },
+    /// Show or undo metadata changes made from this machine.
+    Metadata {
+        #[command(subcommand)]
+        action: MetadataAction,
+    },
+    /// Change parts of the off-chain metadata without touching the rest.
+    Update {
+        #[command(subcommand)]
+        action: UpdateAction,
+    },
+    /// Run a launch script against a mint, signed by DEV_KEYPAIR: lines
+    /// like `if supply < 1000000 then mint 250000` or `revoke freeze`.
+    RunScript {
+        /// The script file.
+        path: PathBuf,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Show what would be sent without sending it.
+        #[arg(long)]
+        dry_run: bool,
+        /// Refuse any `mint` that would take the supply past this many
+        /// whole tokens.
+        #[arg(long, value_name = "AMOUNT")]
+        max_supply: Option<String>,
+    },
+    /// Post-launch plugins: executables in the config directory's
+    /// `plugins` folder, run after the mint, the metadata and the launch.
+    Plugins {
+        #[command(subcommand)]
+        action: PluginAction,
+    },
+    /// Whitelist claims: sign per-wallet vouchers and run the claim server
+    /// that pays out signed claims up to each wallet's cap.
+    Claim {
+        #[command(subcommand)]
+        action: ClaimAction,
+    },
+    /// Draw airdrop winners from a participant list, seeded by a future
+    /// block's hash so anyone can reproduce the result.
+    Draw {
+        #[command(subcommand)]
+        action: DrawAction,
+    },
+    /// Send a token to a `wallet,amount` list, many transactions at a time
+    /// through the fee payers of `fund split` and durable nonces. Resumes
+    /// from its journal when run again.
+    Airdrop(AirdropArgs),
+    /// Freeze the token accounts of flagged addresses and of everyone they
+    /// send the token to, while the freeze authority is still held. Every
+    /// action is appended to a log the next run resumes from.
+    FreezeWatch(FreezeWatchArgs),
+    /// Take tokens back from holders through the mint's permanent delegate
+    /// and send them to a treasury, recording the reason on chain and in a
+    /// log.
+    Clawback(ClawbackArgs),
+    /// Wind a token down: burn what DEV_KEYPAIR holds, close its token
+    /// accounts, point the metadata at a retirement notice and write a
+    /// report.
+    Retire(RetireArgs),
+    /// Close accounts earlier runs left behind on this network, getting
+    /// their rent back: empty token accounts of launched mints, nonce
+    /// accounts of finished scheduled revocations and airdrops, and
+    /// deactivated lookup tables.
+    Reclaim(ReclaimArgs),
+    /// Split a total supply across an allocation table in exact base units.
+    Allocate {
+        /// Total supply in whole tokens, e.g. 1_000_000_000.
+        #[arg(long)]
+        supply: String,
+        #[arg(long, default_value_t = 9)]
+        decimals: u8,
+        /// Allocation as label=percent; repeat for each row, e.g. --share team=15%.
+        #[arg(long = "share", required = true)]
+        shares: Vec<String>,
+    },
+}
+
+impl Command {
+    /// Whether the command talks to the cluster, and so needs the endpoint
+    /// checked against the selected network first.
+    fn uses_rpc(&self) -> bool {
+        match self {
+            Command::Logo { .. }
+            | Command::Qr { .. }
+            | Command::Transcript { .. }
+            | Command::Allocate { .. }
+            | Command::Impact(_)
+            | Command::Vanity { .. }
+            | Command::MintKey { .. }
+            | Command::Signer { .. }
+            | Command::VerifyAuditLog { .. }
+            | Command::SelfUpdate { .. }
+            | Command::Doctor
+            | Command::Stats { .. }
+            | Command::Labels { .. } => false,
+            Command::Preview { mint, .. } => mint.is_some(),
+            Command::Session { action } => matches!(
+                action,
+                SessionAction::Resume { .. } | SessionAction::Replay { .. }
+            ),
+            Command::Schedule { action } => {
+                !matches!(action, ScheduleAction::List | ScheduleAction::Show { .. })
+            }
+            Command::Proposal { action } => !matches!(action, ProposalAction::Show { .. }),
+            Command::Wallet { action } => matches!(action, WalletAction::Show { .. }),
+            Command::Plan { action } => !matches!(action, PlanAction::Validate { .. }),
+            Command::Plugins { .. } => false,
+            Command::Claim { action } => !matches!(action, ClaimAction::Status { .. }),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Args)]
+struct ExportAccountingArgs {
+    mint: Pubkey,
+    /// Another wallet of the project, as address or watched wallet label;
+    /// repeat for several. DEV_KEYPAIR's wallet is always included.
+    #[arg(long = "wallet")]
+    wallets: Vec<String>,
+    #[arg(long, value_enum, default_value = "generic")]
+    format: AccountingFormat,
+    /// Output file; prints to stdout when omitted.
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Maximum number of signatures to read per address.
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Leave the USD columns empty instead of looking prices up.
+    #[arg(long)]
+    no_prices: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AccountingFormat {
+    /// One row per entry with its purpose, amount and USD value.
+    Generic,
+    /// Koinly's universal import format.
+    Koinly,
+}
+
+#[derive(Args)]
+struct PayLinkArgs {
+    mint: Pubkey,
+    /// Wallet paid: address or watched wallet label; defaults to
+    /// DEV_KEYPAIR.
+    #[arg(long)]
+    to: Option<String>,
+    /// Amount in whole tokens; left out, the payer enters it.
+    #[arg(long)]
+    amount: Option<String>,
+    /// Merchant name the wallet shows.
+    #[arg(long)]
+    label: Option<String>,
+    /// What the payment is for, shown by the wallet.
+    #[arg(long)]
+    message: Option<String>,
+    /// Text recorded on-chain with the payment, e.g. an order ID.
+    #[arg(long)]
+    memo: Option<String>,
+    /// Add a unique reference key, to find this payment by later.
+    #[arg(long)]
+    reference: bool,
+}
+
+#[derive(Args)]
+struct DepositTestArgs {
+    mint: Pubkey,
+    /// Deposit address the exchange gave: a wallet or a token account.
+    #[arg(long)]
+    to: Pubkey,
+    /// Amount in whole tokens.
+    #[arg(long, default_value = "1")]
+    amount: String,
+    /// Memo or tag the exchange asked for, identifying the account.
+    #[arg(long)]
+    memo: Option<String>,
+    /// Where to write the evidence; defaults to deposit-<MINT>.json.
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Skip the confirmation prompt.
+    #[arg(long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct RotateArgs {
+    /// Wallet to move everything to.
+    #[arg(long)]
+    to: Pubkey,
+    /// Keypair file of the old wallet; defaults to DEV_KEYPAIR.
+    #[arg(long)]
+    from: Option<PathBuf>,
+    /// Keypair file paying the fees, for an old wallet already drained of SOL.
+    #[arg(long)]
+    payer: Option<PathBuf>,
+    /// Also move the authorities of this mint; repeat for several.
+    #[arg(long = "mint")]
+    mints: Vec<Pubkey>,
+    #[arg(long, default_value = "rotation-report.json")]
+    report: PathBuf,
+    /// Skip the confirmation prompt.
+    #[arg(long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct AirdropArgs {
+    mint: Pubkey,
+    /// CSV of `wallet,amount` rows, amounts in whole tokens, e.g. from `draw`.
+    recipients: PathBuf,
+    /// Directory of fee-payer keypairs, as `fund split` creates it.
+    #[arg(long, value_name = "DIR")]
+    payers: PathBuf,
+    /// Transactions in flight per fee payer, each through its own durable
+    /// nonce account.
+    #[arg(long, default_value_t = 8)]
+    nonces_per_payer: usize,
+    /// Transactions each fee payer sends per second at most.
+    #[arg(long, default_value_t = 4.0)]
+    rate: f64,
+    /// Recipients per transaction at most; by default as many as fit.
+    #[arg(long)]
+    per_tx: Option<usize>,
+    /// Address lookup table holding recipients' wallets and token
+    /// accounts, so more fit per transaction; repeat for several.
+    #[arg(long = "lookup-table", value_name = "ADDRESS")]
+    lookup_tables: Vec<Pubkey>,
+    /// Journal of sent transactions; defaults to <RECIPIENTS>.airdrop.jsonl.
+    #[arg(long)]
+    journal: Option<PathBuf>,
+    /// Leave the nonce accounts open for another airdrop instead of
+    /// returning their rent to the fee payers.
+    #[arg(long)]
+    keep_nonces: bool,
+    /// Skip the confirmation prompt.
+    #[arg(long)]
+    yes: bool,
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct FreezeWatchArgs {
+    mint: Pubkey,
+    /// Exploiter wallet or token account; repeat for several.
+    #[arg(long = "flag", value_name = "ADDRESS")]
+    flagged: Vec<Pubkey>,
+    /// File of flagged addresses, one per line.
+    #[arg(long)]
+    flag_file: Option<PathBuf>,
+    /// Wallet or token account never to freeze, such as a pool; repeat
+    /// for several.
+    #[arg(long = "exempt", value_name = "ADDRESS")]
+    exempt: Vec<Pubkey>,
+    /// Ignore transfers of fewer tokens than this.
+    #[arg(long)]
+    min_amount: Option<String>,
+    /// Also flag the owner of every account frozen, to catch the next hop.
+    #[arg(long)]
+    follow: bool,
+    /// Keep running and check again every --interval seconds.
+    #[arg(long)]
+    daemon: bool,
+    #[arg(long, default_value_t = 10)]
+    interval: u64,
+    /// On a fresh log, also check this many past transactions of each
+    /// watched address.
+    #[arg(long, default_value_t = 0)]
+    backfill: usize,
+    /// Report what would be frozen without sending or logging anything.
+    #[arg(long)]
+    dry_run: bool,
+    /// Log of actions; defaults to freeze-<MINT>.jsonl.
+    #[arg(long)]
+    log: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ClawbackArgs {
+    mint: Pubkey,
+    /// Token account or wallet to take from; a wallet stands for all its
+    /// accounts of the mint. Repeat for several.
+    #[arg(long = "holder", value_name = "ADDRESS", required = true)]
+    holders: Vec<Pubkey>,
+    /// Why the tokens are taken; sent as a memo and logged.
+    #[arg(long)]
+    reason: String,
+    /// Wallet receiving the tokens; defaults to the permanent delegate.
+    #[arg(long)]
+    to: Option<Pubkey>,
+    /// List the affected holders without sending anything.
+    #[arg(long)]
+    dry_run: bool,
+    /// Log of clawbacks; defaults to clawback-<MINT>.jsonl.
+    #[arg(long)]
+    log: Option<PathBuf>,
+    /// Skip the confirmation prompt.
+    #[arg(long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct RetireArgs {
+    mint: Pubkey,
+    /// Metadata URI to point the token at. Without it a notice built from
+    /// --reason is uploaded to IPFS.
+    #[arg(long, conflicts_with = "keep_metadata")]
+    uri: Option<String>,
+    /// Why the token is retired, for the uploaded notice.
+    #[arg(long)]
+    reason: Option<String>,
+    /// Leave the metadata as it is.
+    #[arg(long)]
+    keep_metadata: bool,
+    /// Also revoke the mint authority, so no more can ever be minted.
+    #[arg(long)]
+    revoke_mint: bool,
+    /// Show what would be done without sending anything.
+    #[arg(long)]
+    dry_run: bool,
+    /// Where to write the report; defaults to retire-<MINT>.json.
+    #[arg(long)]
+    report: Option<PathBuf>,
+    /// Skip the confirmation prompt.
+    #[arg(long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct ReclaimArgs {
+    /// Directory of airdrop fee-payer keypairs, whose nonce accounts are
+    /// closed too.
+    #[arg(long, value_name = "DIR")]
+    payers: Option<PathBuf>,
+    /// Nonce accounts each fee payer opened, as given to `airdrop`.
+    #[arg(long, default_value_t = 8)]
+    nonces_per_payer: usize,
+    /// Address lookup table to close once deactivated; repeat for several.
+    /// Launches don't record the tables they use.
+    #[arg(long = "lookup-table", value_name = "ADDRESS")]
+    lookup_tables: Vec<Pubkey>,
+    /// Show what would be closed without sending anything.
+    #[arg(long)]
+    dry_run: bool,
+    /// Skip the confirmation prompt.
+    #[arg(long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct ImpactArgs {
+    /// Tokens to deposit in the pool.
+    #[arg(long)]
+    tokens: String,
+    /// SOL to deposit in the pool.
+    #[arg(long)]
+    sol: String,
+    /// Total token supply, for the fully diluted valuation.
+    #[arg(long)]
+    supply: String,
+    #[arg(long, default_value_t = impact::DEFAULT_FEE_BPS)]
+    fee_bps: u32,
+    /// Buy size in SOL; repeat for several.
+    #[arg(long = "buy", default_values = ["0.1", "1", "5", "10"])]
+    buys: Vec<String>,
+    /// Sell size in tokens, or as a percentage of the pool's tokens; repeat for several.
+    #[arg(long = "sell", default_values = ["0.1%", "1%", "5%", "10%"])]
+    sells: Vec<String>,
+    /// Circulating supply for the market cap, in tokens or as a
+    /// percentage of the supply; repeat for several.
+    #[arg(long = "circulating", default_values = ["10%", "25%", "50%", "100%"])]
+    circulating: Vec<String>,
+    /// SOL price in USD, to show values in dollars as well.
+    #[arg(long)]
+    sol_usd: Option<f64>,
+}
+
+#[derive(Args)]
+struct LaunchArgs {
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    symbol: String,
+    /// Total supply in whole tokens, e.g. 1_000_000_000.
+    #[arg(long)]
+    supply: String,
+    /// Decimal places; 9 unless --defaults gives others.
+    #[arg(long)]
+    decimals: Option<u8>,
+    /// Take the decimals, token program, authority policy, links and
+    /// compute budget not given here from an earlier launch session, or
+    /// `last` for the latest on this network. The values taken are printed
+    /// in brackets; turn a flag back off with e.g. --revoke-mint=false.
+    #[arg(long, value_name = "SESSION")]
+    defaults: Option<String>,
+    /// Existing metadata JSON URI. Without it the JSON is built from the
+    /// options below and uploaded to IPFS.
+    #[arg(long)]
+    uri: Option<String>,
+    #[arg(long)]
+    description: Option<String>,
+    /// Logo URI, e.g. as printed by `logo --upload`.
+    #[arg(long)]
+    image: Option<String>,
+    #[arg(long)]
+    website: Option<String>,
+    #[arg(long)]
+    twitter: Option<String>,
+    #[arg(long)]
+    telegram: Option<String>,
+    #[arg(long)]
+    discord: Option<String>,
+    /// Name in another language, as LANG=NAME (e.g. es=Ejemplo); repeat
+    /// for several.
+    #[arg(long = "localized-name", value_name = "LANG=NAME", value_parser = parse_localized)]
+    localized_names: Vec<(String, String)>,
+    /// Description in another language, as LANG=TEXT; repeat for several.
+    #[arg(long = "localized-description", value_name = "LANG=TEXT", value_parser = parse_localized)]
+    localized_descriptions: Vec<(String, String)>,
+    /// Tag aggregators index the token under, e.g. meme; repeat for several.
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+    /// Category aggregators list the token in, e.g. meme or defi.
+    #[arg(long)]
+    category: Option<String>,
+    /// Create the mint under Token-2022 instead of the original token program.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    token_2022: Option<bool>,
+    /// Token-2022 transfer hook program to call on every transfer.
+    #[arg(long, value_name = "PROGRAM", requires = "token_2022")]
+    transfer_hook: Option<Pubkey>,
+    /// Make the token a Token-2022 group that other launches can join.
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "token_2022",
+        conflicts_with = "member_of"
+    )]
+    group_max_size: Option<u64>,
+    /// Add the token to this Token-2022 group; DEV_KEYPAIR must be its update authority.
+    #[arg(long, value_name = "GROUP", requires = "token_2022")]
+    member_of: Option<Pubkey>,
+    /// Record this text (e.g. "initial supply") with the supply mint transaction.
+    #[arg(long)]
+    memo: Option<String>,
+    /// Mint the supply into this wallet's associated token account instead
+    /// of DEV_KEYPAIR's.
+    #[arg(long, value_name = "WALLET")]
+    supply_owner: Option<Pubkey>,
+    /// Mint the supply into a new plain (non-associated) token account at
+    /// this keypair's address, owned by --supply-owner or DEV_KEYPAIR.
+    #[arg(long, value_name = "KEYPAIR", conflicts_with = "handoff")]
+    supply_account: Option<PathBuf>,
+    /// Compute units each launch transaction requests.
+    #[arg(long, value_name = "UNITS")]
+    compute_unit_limit: Option<u32>,
+    /// Priority fee per compute unit, in micro-lamports.
+    #[arg(long, value_name = "MICRO_LAMPORTS")]
+    compute_unit_price: Option<u64>,
+    /// Keep a freeze authority on the mint.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    freeze_authority: Option<bool>,
+    /// Revoke the mint authority once the supply is minted.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    revoke_mint: Option<bool>,
+    /// Make the metadata immutable.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    immutable: Option<bool>,
+    /// Don't sign with DEV_KEYPAIR: print unsigned transactions for this
+    /// wallet to sign in the browser (wallet-adapter signAllTransactions).
+    #[arg(long, value_name = "WALLET")]
+    handoff: Option<Pubkey>,
+    /// Launch even if an identical launch is unfinished or finished in the last day.
+    #[arg(long)]
+    allow_duplicate: bool,
+    /// Grind for a mint address starting with this text; repeat to accept any of several.
+    #[arg(long, value_name = "TEXT", conflicts_with = "handoff")]
+    vanity_prefix: Vec<String>,
+    /// Grind for a mint address ending with this text; repeat to accept any of several.
+    #[arg(long, value_name = "TEXT", conflicts_with = "handoff")]
+    vanity_suffix: Vec<String>,
+    /// Match the vanity text in any letter case, which is much faster.
+    #[arg(long)]
+    vanity_ignore_case: bool,
+    /// Threads to grind on; defaults to one per core.
+    #[arg(long, value_name = "N")]
+    vanity_threads: Option<usize>,
+    /// Derive the mint key from MINT_PASSPHRASE and this index, so a
+    /// rehearsal and the real launch can share it; see `mint-key`.
+    #[arg(long, value_name = "N", conflicts_with_all = ["vanity_prefix", "vanity_suffix"])]
+    mint_index: Option<u32>,
+}
+
+#[derive(Subcommand)]
+enum TranscriptAction {
+    /// Print the recorded command, questions, answers and outcome.
+    Show { path: PathBuf },
+    /// Run the recorded command again, answering its questions from the
+    /// transcript. Edit the answers in the file to take another path.
+    Replay {
+        path: PathBuf,
+        /// Offer each recorded answer as the default instead of using it.
+        #[arg(long)]
+        edit: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionAction {
+    /// List sessions, most recently updated first.
+    List,
+    /// Show a session's parameters and progress (default: the active session).
+    Show { id: Option<String> },
+    /// Make another session the active one.
+    Switch { id: String },
+    /// Continue a session from its first unfinished step (default: the active session).
+    Resume {
+        id: Option<String>,
+        /// Keypair of the session's auxiliary supply account, if it has one
+        /// that is not created yet.
+        #[arg(long, value_name = "KEYPAIR")]
+        supply_account: Option<PathBuf>,
+    },
+    /// Launch again, on the selected network, exactly what a finished
+    /// rehearsal (e.g. on devnet) launched, and report anything that came
+    /// out differently.
+    Replay {
+        /// The finished rehearsal session.
+        id: String,
+        /// Don't ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SquadsAction {
+    /// Create a multisig paid for by DEV_KEYPAIR.
+    Create {
+        /// Member with full permissions; repeat for each member.
+        #[arg(long = "member", required = true)]
+        members: Vec<Pubkey>,
+        #[arg(long)]
+        threshold: u16,
+        /// Seconds between approval and execution.
+        #[arg(long, default_value_t = 0)]
+        time_lock: u32,
+    },
+    /// Move a mint's mint, freeze and metadata update authorities from DEV_KEYPAIR to the vault.
+    Handover {
+        multisig: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long, default_value_t = 0)]
+        vault_index: u8,
+    },
+    /// Propose an authority operation as a vault transaction instead of sending it.
+    Propose {
+        multisig: Pubkey,
+        #[arg(long, default_value_t = 0)]
+        vault_index: u8,
+        #[arg(long)]
+        memo: Option<String>,
+        #[command(subcommand)]
+        operation: ProposeOperation,
+    },
+    /// Approve a proposal as DEV_KEYPAIR.
+    Approve { multisig: Pubkey, index: u64 },
+    /// Execute an approved proposal as DEV_KEYPAIR.
+    Execute { multisig: Pubkey, index: u64 },
+    /// Show the multisig, or one proposal; with --wait, poll until it is approved or closed.
+    Status {
+        multisig: Pubkey,
+        index: Option<u64>,
+        #[arg(long, requires = "index")]
+        wait: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScheduleAction {
+    /// Sign a revocation of DEV_KEYPAIR's authority against a new durable nonce.
+    Revoke {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Revoke the mint authority (the default when neither is given).
+        #[arg(long)]
+        mint_authority: bool,
+        #[arg(long)]
+        freeze_authority: bool,
+        /// When to revoke, as RFC 3339 (2025-07-01T12:00:00Z) or a date.
+        #[arg(long, required_unless_present = "at_slot", conflicts_with = "at_slot")]
+        at: Option<String>,
+        /// Revoke once the cluster reaches this slot.
+        #[arg(long)]
+        at_slot: Option<u64>,
+    },
+    /// List scheduled revocations, soonest first.
+    List,
+    /// Show a scheduled revocation and its signed transaction.
+    Show { id: String },
+    /// Broadcast every revocation that is due on the selected network.
+    Run {
+        /// Keep running and check again every --interval seconds.
+        #[arg(long)]
+        daemon: bool,
+        #[arg(long, default_value_t = 30)]
+        interval: u64,
+    },
+    /// Advance the nonce so a pending revocation can never be broadcast.
+    Cancel { id: String },
+}
+
+#[derive(Subcommand)]
+enum EmissionAction {
+    /// Fix an emission's terms in a new journal; DEV_KEYPAIR must hold the
+    /// mint authority.
+    Start {
+        mint: Pubkey,
+        /// Wallet paid: address or watched wallet label.
+        #[arg(long)]
+        to: String,
+        /// Whole tokens minted at each scheduled time.
+        #[arg(long)]
+        amount: String,
+        /// Cron schedule in UTC, e.g. '0 0 * * *' for daily at midnight.
+        #[arg(long)]
+        schedule: String,
+        /// Most whole tokens the emission mints in total.
+        #[arg(long)]
+        cap: String,
+        /// First time that counts, as RFC 3339 or a date; now by default.
+        #[arg(long)]
+        from: Option<String>,
+        /// Journal file; defaults to emission-<MINT>.jsonl.
+        #[arg(long)]
+        journal: Option<PathBuf>,
+    },
+    /// Mint what is owed, signed by DEV_KEYPAIR.
+    Run {
+        mint: Pubkey,
+        #[arg(long)]
+        journal: Option<PathBuf>,
+        /// Keep running and check again every --interval seconds.
+        #[arg(long)]
+        daemon: bool,
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+        /// Post failures to this Slack or Discord webhook.
+        #[arg(long)]
+        alert_webhook: Option<String>,
+    },
+    /// Show what an emission has minted and what is due next.
+    Status {
+        mint: Pubkey,
+        #[arg(long)]
+        journal: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LpAction {
+    /// Show each pool's reserves, the project's share, the fees it earned
+    /// and its impermanent loss.
+    Status {
+        mint: Pubkey,
+        /// Only this pool; by default every Raydium CPMM pool of the mint.
+        #[arg(long)]
+        pool: Option<Pubkey>,
+        /// Wallet holding LP tokens besides DEV_KEYPAIR and the watched
+        /// wallets; repeat for several.
+        #[arg(long = "wallet")]
+        wallets: Vec<Pubkey>,
+        /// Measure fees and impermanent loss from now on.
+        #[arg(long)]
+        reset_baseline: bool,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Seed the first liquidity across Raydium CPMM pools of several fee
+    /// tiers in one step, each opening at the same price.
+    Deploy {
+        mint: Pubkey,
+        /// Whole tokens to deposit in total.
+        #[arg(long)]
+        token_amount: Option<String>,
+        /// Whole units of the quote to deposit in total.
+        #[arg(long)]
+        quote_amount: Option<String>,
+        /// Launch price in USD a token; the other side of the deposit is
+        /// worked out from SOL's current price, so give only one of
+        /// --token-amount and --quote-amount. The quote must be SOL.
+        #[arg(long)]
+        usd_price: Option<UsdPrice>,
+        /// With --usd-price, how far SOL may move between working out the
+        /// amounts and sending them, as a percentage.
+        #[arg(long, default_value = "1", value_parser = amount::parse_percent_bps)]
+        max_sol_move: u32,
+        /// What the token trades against; wrapped SOL by default, wrapped
+        /// from DEV_KEYPAIR's SOL.
+        #[arg(long)]
+        quote_mint: Option<Pubkey>,
+        /// A Raydium CPMM fee tier, by index, and its pool's share of the
+        /// liquidity, e.g. 0=70; repeat for several. Other DEXes are not
+        /// supported.
+        #[arg(long = "fee-tier", value_name = "INDEX=PERCENT", value_parser = parse_fee_tier_share, required = true)]
+        fee_tiers: Vec<(FeeTier, u32)>,
+        /// How far any pool's opening price may be from the intended one,
+        /// as a percentage.
+        #[arg(long, default_value = "0.1", value_parser = amount::parse_percent_bps)]
+        tolerance: u32,
+        /// Go ahead although this launch checklist item fails; repeat for
+        /// several.
+        #[arg(long = "override", value_name = "ITEM")]
+        overrides: Vec<checklist::Item>,
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Run the launch checklist liquidity waits for.
+    Checklist {
+        mint: Pubkey,
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProposalAction {
+    /// Propose a metadata update, paid for and nonce-held by DEV_KEYPAIR.
+    Create {
+        #[arg(long)]
+        mint: Pubkey,
+        /// The key that must sign the update; defaults to the current one.
+        #[arg(long)]
+        update_authority: Option<Pubkey>,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        symbol: Option<String>,
+        #[arg(long)]
+        uri: Option<String>,
+        #[arg(long)]
+        new_update_authority: Option<Pubkey>,
+        #[arg(long)]
+        immutable: bool,
+        /// Where to write the proposal file.
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Decode a proposal file and list the signatures it still needs.
+    Show { path: PathBuf },
+    /// Broadcast a proposal once every signature is in.
+    Submit { path: PathBuf },
+    /// Advance the proposal's nonce so it can never be broadcast.
+    Cancel { path: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum TransferHookAction {
+    /// Show the hook program, its authority and the extra-account-metas account.
+    Show { mint: Pubkey },
+    /// Point the hook at another program, or disable it, as DEV_KEYPAIR.
+    Update {
+        mint: Pubkey,
+        #[arg(long, required_unless_present = "clear", conflicts_with = "clear")]
+        program: Option<Pubkey>,
+        /// Stop calling any program on transfer.
+        #[arg(long)]
+        clear: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GroupAction {
+    /// Show a group's size and authority, or which group a member belongs to.
+    Show { mint: Pubkey },
+    /// Add a mint created with a member pointer to a group, or restore a
+    /// removed member's pointer.
+    AddMember {
+        group: Pubkey,
+        #[arg(long)]
+        member: Pubkey,
+    },
+    /// Clear a member's pointer so it no longer presents as part of the
+    /// group. The membership record and the group size are permanent.
+    RemoveMember {
+        group: Pubkey,
+        #[arg(long)]
+        member: Pubkey,
+    },
+}
+
+/// A token account given directly, or as DEV_KEYPAIR's associated account for a mint.
+#[derive(Args)]
+struct AccountTarget {
+    /// Token account address.
+    #[arg(long, required_unless_present = "mint", conflicts_with = "mint")]
+    account: Option<Pubkey>,
+    /// Use DEV_KEYPAIR's associated token account for this mint.
+    #[arg(long)]
+    mint: Option<Pubkey>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Toggle {
+    On,
+    Off,
+}
+
+#[derive(Subcommand)]
+enum AccountAction {
+    /// Show the balance and which protections are enabled.
+    Show {
+        #[command(flatten)]
+        target: AccountTarget,
+    },
+    /// Stop programs the owner signs for from moving, burning, approving or
+    /// closing through the account.
+    CpiGuard {
+        #[command(flatten)]
+        target: AccountTarget,
+        #[arg(value_enum)]
+        state: Toggle,
+    },
+    /// Reject incoming transfers that don't carry a memo.
+    RequireMemo {
+        #[command(flatten)]
+        target: AccountTarget,
+        #[arg(value_enum)]
+        state: Toggle,
+    },
+}
+
+#[derive(Subcommand)]
+enum WalletAction {
+    /// Add a watch-only wallet.
+    Watch {
+        address: Pubkey,
+        #[arg(long)]
+        label: String,
+    },
+    /// Stop watching a wallet, by label or address.
+    Unwatch {
+        wallet: String,
+    },
+    List,
+    /// Balances, token accounts and authorities of a wallet, by label or address.
+    Show {
+        wallet: String,
+        /// Also check the authorities of this mint; repeat for several.
+        #[arg(long = "mint")]
+        mints: Vec<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LabelsAction {
+    /// Label an address, replacing any label it already has.
+    Add {
+        address: Pubkey,
+        label: String,
+    },
+    /// Drop a label of your own, by address or label.
+    Remove {
+        label: String,
+    },
+    List,
+}
+
+#[derive(Subcommand)]
+enum FundAction {
+    /// Top up COUNT wallets in DIR to a balance, creating wallet-<n>.json
+    /// keypairs for any that don't exist yet.
+    Split {
+        dir: PathBuf,
+        #[arg(long)]
+        count: usize,
+        /// Balance each wallet should hold, in SOL.
+        #[arg(long, value_name = "SOL")]
+        each: String,
+        /// Fund without asking for confirmation.
+        #[arg(long)]
+        yes: bool,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Return the SOL of every wallet in DIR to DEV_KEYPAIR.
+    Sweep {
+        dir: PathBuf,
+        /// Sweep without asking for confirmation.
+        #[arg(long)]
+        yes: bool,
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum PlanAction {
+    /// Check a plan file without touching the chain.
+    Validate { path: PathBuf },
+    /// Report how a mint differs from a plan, e.g. to check a launched
+    /// token still matches its published spec. Exits with an error if it
+    /// doesn't.
+    Diff {
+        mint: Pubkey,
+        path: PathBuf,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Launch the plan's token if it doesn't exist yet, then send what is
+    /// missing for the chain to match the plan.
+    Apply {
+        path: PathBuf,
+        /// Show the differences without sending anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Apply every plan of a manifest of related tokens in order, with
+    /// the same wallet, and report their mints together.
+    ApplyManifest {
+        path: PathBuf,
+        /// Show the differences without sending anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CollectionAction {
+    /// Show which collection each token is in and whether it is verified.
+    Show {
+        #[arg(required = true)]
+        mints: Vec<Pubkey>,
+    },
+    /// Name the collection in each token's metadata, and verify the tokens
+    /// too when DEV_KEYPAIR is the collection's update authority.
+    Set {
+        /// Mint of the collection's parent NFT.
+        #[arg(long)]
+        collection: Pubkey,
+        #[arg(required = true)]
+        mints: Vec<Pubkey>,
+    },
+    /// Verify tokens that already name the collection; DEV_KEYPAIR must be
+    /// its update authority.
+    Verify {
+        /// Mint of the collection's parent NFT.
+        #[arg(long)]
+        collection: Pubkey,
+        #[arg(required = true)]
+        mints: Vec<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+enum UpdateAction {
+    /// Set or remove the website, twitter, telegram and discord links,
+    /// re-uploading the JSON with only those changed. DEV_KEYPAIR must be
+    /// the update authority.
+    Socials {
+        mint: Pubkey,
+        #[arg(long)]
+        website: Option<String>,
+        #[arg(long)]
+        twitter: Option<String>,
+        #[arg(long)]
+        telegram: Option<String>,
+        #[arg(long)]
+        discord: Option<String>,
+        /// Drop a link: website, twitter, telegram or discord; repeat for
+        /// several.
+        #[arg(long = "remove", value_name = "LINK")]
+        remove: Vec<String>,
+        /// Don't ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MetadataAction {
+    /// List the earlier versions kept in the launch record, oldest first.
+    History { mint: Pubkey },
+    /// Put back the version before the latest change; run again to go
+    /// further back. DEV_KEYPAIR must still be the update authority.
+    Rollback {
+        mint: Pubkey,
+        /// Pin the saved off-chain JSON again and point at the new copy,
+        /// for when the old URI no longer serves it.
+        #[arg(long)]
+        reupload: bool,
+        /// Don't ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum PluginAction {
+    /// Show the plugin directory and the plugins found in it.
+    List,
+    /// Run every plugin for a hook against a session, to try them out.
+    Run {
+        /// after-mint, after-metadata or after-launch.
+        hook: String,
+        /// Session to pass (default: the active session).
+        session: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ClaimAction {
+    /// Sign a voucher for every wallet on a `wallet,cap` whitelist.
+    Vouchers {
+        mint: Pubkey,
+        /// CSV of wallet,cap rows, caps in whole tokens.
+        #[arg(long)]
+        whitelist: PathBuf,
+        #[arg(long, default_value = "vouchers.json")]
+        out: PathBuf,
+    },
+    /// Serve the vouchers and pay out claims from the configured wallet.
+    Serve {
+        #[arg(long, default_value = "vouchers.json")]
+        vouchers: PathBuf,
+        /// Where payouts are recorded; kept across restarts.
+        #[arg(long, default_value = "claims.json")]
+        ledger: PathBuf,
+        #[arg(long, default_value = "127.0.0.1:8787")]
+        listen: String,
+        /// Also draw the portal's URL as a QR code.
+        #[arg(long)]
+        qr: bool,
+    },
+    /// Show what each whitelisted wallet has claimed.
+    Status {
+        #[arg(long, default_value = "vouchers.json")]
+        vouchers: PathBuf,
+        #[arg(long, default_value = "claims.json")]
+        ledger: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum DrawAction {
+    /// Print the participant digest and a future seed slot to publish
+    /// before the draw.
+    Announce {
+        #[arg(long)]
+        participants: PathBuf,
+        /// How far ahead the seed slot is; a slot is about 400 ms.
+        #[arg(long, default_value_t = 1500)]
+        delay_slots: u64,
+    },
+    /// Draw the winners once the seed slot is finalized.
+    Run {
+        #[arg(long)]
+        participants: PathBuf,
+        #[arg(long)]
+        seed_slot: u64,
+        #[arg(long)]
+        winners: usize,
+        /// Tokens split evenly among the winners, in whole tokens.
+        #[arg(long)]
+        total: String,
+        #[arg(long, default_value_t = 9)]
+        decimals: u8,
+        /// Where to write the draw record to publish.
+        #[arg(long)]
+        out: PathBuf,
+        /// Also write the allocations as wallet,amount CSV.
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Reproduce a published draw and check it against the chain.
+    Verify {
+        record: PathBuf,
+        #[arg(long)]
+        participants: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProposeOperation {
+    /// Mint tokens to an owner's associated token account (the vault pays rent).
+    MintTo {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Wallet receiving the tokens.
+        #[arg(long)]
+        to: Pubkey,
+        /// Amount in whole tokens.
+        #[arg(long)]
+        amount: String,
+        /// Refuse if the supply would pass this many whole tokens.
+        #[arg(long, value_name = "AMOUNT")]
+        max_supply: Option<String>,
+    },
+    /// Permanently revoke the mint authority.
+    RevokeMint {
+        #[arg(long)]
+        mint: Pubkey,
+    },
+    /// Permanently revoke the freeze authority.
+    RevokeFreeze {
+        #[arg(long)]
+        mint: Pubkey,
+    },
+    /// Change metadata fields or make the metadata immutable.
+    UpdateMetadata {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        symbol: Option<String>,
+        #[arg(long)]
+        uri: Option<String>,
+        #[arg(long)]
+        immutable: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RegistryTarget {
+    Jupiter,
+    Coingecko,
+    Dexscreener,
+    Birdeye,
+    All,
+}
+
+impl RegistryTarget {
+    fn includes(self, target: RegistryTarget) -> bool {
+        self == RegistryTarget::All || self == target
+    }
+}
+
+fn main() -> ExitCode {
+    // Decided before parsing, since it changes what `launch --help` shows.
+    let expert =
+        std::env::args().any(|a| a == "--expert") || Config::load().is_ok_and(|c| c.expert);
+    let mut command = Cli::command();
+    if !expert {
+        command = command.mut_subcommand("launch", |launch| {
+            EXPERT_LAUNCH_ARGS
+                .iter()
+                .fold(launch, |c, id| c.mut_arg(*id, |a| a.hide(true)))
+                .after_help(
+                    "More options (compute budget, supply account) are shown with --expert.",
+                )
+        });
+    }
+    let matches = command.get_matches();
+    let cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => e.exit(),
+    };
+    if let Some(path) = &cli.transcript
+        && !matches!(cli.command, Command::Transcript { .. })
+    {
+        let transcript = Transcript::new(std::env::args().skip(1));
+        match Prompter::default().record(path.clone(), transcript) {
+            Ok(prompter) => PROMPTER.set(prompter),
+            Err(e) => {
+                eprintln!("error: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let purpose = purpose(&matches);
+    let result = run(cli, purpose);
+    let finished =
+        PROMPTER.with_borrow_mut(|p| p.finish(result.as_ref().err().map(Error::to_string)));
+    match result.and(finished) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// The subcommand path, e.g. `proposal submit`, which tags audit log entries.
+fn purpose(matches: &clap::ArgMatches) -> String {
+    std::iter::successors(matches.subcommand(), |(_, m)| m.subcommand())
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn cmd_transcript(transcript_path: Option<&PathBuf>, action: TranscriptAction) -> Result<()> {
+    match action {
+        TranscriptAction::Show { path } => {
+            let transcript = Transcript::load(&path)?;
+            println!("Command:  solanaapp {}", transcript.args.join(" "));
+            println!("Started:  {}", time::format_rfc3339(transcript.started_at));
+            for prompt in &transcript.prompts {
+                println!("  {}  -> {}", prompt.question, prompt.answer);
+            }
+            match &transcript.finish {
+                Some(finish) => println!(
+                    "Finished: {}, {}",
+                    time::format_rfc3339(finish.at),
+                    finish.error.as_deref().unwrap_or("successfully")
+                ),
+                None => println!("Finished: never; the run stopped early"),
+            }
+            Ok(())
+        }
+        TranscriptAction::Replay { path, edit } => {
+            let transcript = Transcript::load(&path)?;
+            let argv = std::iter::once("solanaapp".to_string()).chain(transcript.args.clone());
+            let matches = Cli::command()
+                .try_get_matches_from(argv)
+                .map_err(|e| Error::InvalidInput(format!("{}: {e}", path.display())))?;
+            let cli = Cli::from_arg_matches(&matches)
+                .map_err(|e| Error::InvalidInput(format!("{}: {e}", path.display())))?;
+            if matches!(cli.command, Command::Transcript { .. }) {
+                return Err(Error::InvalidInput(format!(
+                    "{} records another transcript command",
+                    path.display()
+                )));
+            }
+            let mut prompter = Prompter::default();
+            if let Some(out) = transcript_path {
+                prompter =
+                    prompter.record(out.clone(), Transcript::new(transcript.args.clone()))?;
+            }
+            PROMPTER.set(prompter.replay(&transcript, edit));
+            eprintln!("replaying: solanaapp {}", transcript.args.join(" "));
+            run(cli, purpose(&matches))
+        }
+    }
+}
+
+fn run(cli: Cli, purpose: String) -> Result<()> {
+    if let Command::Transcript { action } = cli.command {
+        return cmd_transcript(cli.transcript.as_ref(), action);
+    }
+    let mut config = Config::load()?;
+    if let Some(network) = cli.network {
+        config.network = network;
+        config.rpc_url = None;
+    }
+    if let Some(url) = cli.url {
+        config.rpc_url = Some(url);
+    }
+    config.expert |= cli.expert;
+    if let Some(commitment) = cli.commitment {
+        config.commitment = commitment;
+    }
+    if let Some(commitment) = cli.critical_commitment {
+        config.critical_commitment = Some(commitment);
+    }
+    let timeouts = &mut config.timeouts;
+    timeouts.rpc = cli.rpc_timeout.or(timeouts.rpc);
+    timeouts.plugin = cli.plugin_timeout.or(timeouts.plugin);
+    timeouts.validator = cli.validator_timeout.or(timeouts.validator);
+    TIMEOUTS.set(config.timeouts);
+    COLLECT_STATS.set(config.stats);
+    // Held until the command finishes; dropping it stops the validator.
+    let _validator = if cli.local {
+        Some(start_local_validator(&mut config)?)
+    } else {
+        None
+    };
+    let mut rpc = match &cli.replay {
+        Some(path) => RpcClient::replay(Replay::load(path)?),
+        None => RpcClient::new(config.rpc_url()),
+    }
+    .with_timeout(config.timeouts.rpc())
+    .with_commitment(config.commitment)
+    .with_critical_commitment(config.critical_commitment());
+    let mut programs = config.programs();
+    if let Some(id) = cli.token_program {
+        programs.token = id;
+    }
+    if let Some(id) = cli.token_2022_program {
+        programs.token_2022 = id;
+    }
+    if let Some(id) = cli.metadata_program {
+        programs.metadata = id;
+    }
+    rpc = rpc
+        .with_programs(programs)
+        .with_labels(Labels::new(config.labels.clone()).with_programs(&programs));
+    if cli.cache_ttl > 0 && !cli.no_cache && cli.replay.is_none() {
+        rpc = rpc.with_cache(RpcCache::open_default(Duration::from_secs(cli.cache_ttl))?);
+    }
+    if let Some(path) = &cli.record {
+        rpc = rpc.with_recorder(Recorder::create(path)?);
+    }
+    if cli.replay.is_none() && cli.command.uses_rpc() {
+        rpc = rpc.with_audit(Arc::new(AuditLog::open(
+            &AuditLog::default_path()?,
+            purpose,
+        )?));
+    }
+    // Fixtures are recorded against whatever cluster the recorder used.
+    if cli.replay.is_none() && cli.command.uses_rpc() {
+        config
+            .network
+            .check_genesis_hash(&rpc.get_genesis_hash()?)?;
+        programs::check_deployed(&rpc, &programs, config.network)?;
+    }
+    let events = cli.events.as_deref().map(EventLog::open).transpose()?;
+    config.broadcast_urls.extend(cli.broadcast);
+    if let Some(url) = cli.sender {
+        config.sender_url = Some(url);
+    }
+    // A replayed run must not reach real endpoints.
+    let broadcaster = (cli.replay.is_none()
+        && (config.sender_url.is_some() || !config.broadcast_urls.is_empty()))
+    .then(|| {
+        let broadcaster = Broadcaster::new(&config.broadcast_urls, config.timeouts.rpc());
+        match &config.sender_url {
+            Some(url) => broadcaster.with_sender(url.clone(), config.sender_policy),
+            None => broadcaster,
+        }
+    });
+
+    match cli.command {
+        Command::Launch(args) => {
+            cmd_launch(&config, &rpc, events.as_ref(), broadcaster.as_ref(), *args)
+        }
+        Command::Session { action } => {
+            cmd_session(&config, &rpc, events.as_ref(), broadcaster.as_ref(), action)
+        }
+        // Runs before any of the setup above; see the top of `run`.
+        Command::Transcript { .. } => unreachable!("transcript commands return early"),
+        Command::Reconcile { mint, ledger, json } => cmd_reconcile(&rpc, &mint, ledger, json),
+        Command::Holders {
+            mint,
+            out,
+            top,
+            json,
+        } => cmd_holders(&rpc, &mint, out, top, json),
+        Command::Wallet { action } => cmd_wallet(&config, &rpc, action),
+        Command::Labels { action } => cmd_labels(&rpc, action),
+        Command::Authorities { wallet, json } => cmd_authorities(&config, &rpc, wallet, json),
+        Command::Rotate(args) => cmd_rotate(&config, &rpc, args),
+        Command::WalletScan { wallet, deep, json } => {
+            cmd_wallet_scan(&config, &rpc, &wallet, deep, json)
+        }
+        Command::Portfolio { wallets, json } => cmd_portfolio(&config, &rpc, wallets, json),
+        Command::Transfer {
+            mint,
+            to,
+            amount,
+            memo,
+            yes,
+        } => cmd_transfer(&config, &rpc, &mint, &to, &amount, memo.as_deref(), yes),
+        Command::Sweep {
+            mint,
+            to,
+            from,
+            memo,
+            yes,
+        } => cmd_sweep(&rpc, &mint, &to, &from, memo.as_deref(), yes),
+        Command::Fund { action } => cmd_fund(&rpc, action),
+        Command::ExportTxs { mint, out, limit } => cmd_export_txs(&rpc, &mint, out, limit),
+        Command::ExportAccounting(args) => cmd_export_accounting(&config, &rpc, args),
+        Command::Rehost {
+            mint,
+            from_file,
+            rehost_image,
+            dry_run,
+        } => cmd_rehost(&config, &rpc, &mint, from_file, rehost_image, dry_run),
+        Command::Qr { text } => {
+            print!("{}", QrCode::encode(&text)?.to_terminal());
+            println!("{text}");
+            Ok(())
+        }
+        Command::PayLink(args) => cmd_pay_link(&config, &rpc, args),
+        Command::DepositTest(args) => cmd_deposit_test(&config, &rpc, args),
+        Command::Logo {
+            path,
+            size,
+            thumbnail,
+            out,
+            upload,
+        } => cmd_logo(
+            &config,
+            &path,
+            LogoOptions { size, thumbnail },
+            &out,
+            upload,
+        ),
+        Command::Preview {
+            mint,
+            name,
+            symbol,
+            uri,
+            json,
+        } => cmd_preview(&config, &rpc, mint, name, symbol, uri, json),
+        Command::Registry {
+            mint,
+            target,
+            out,
+            icon,
+            header,
+        } => cmd_registry(
+            &config,
+            &rpc,
+            &mint,
+            target,
+            out,
+            ListingAssets { icon, header },
+        ),
+        Command::Squads { action } => cmd_squads(&config, &rpc, action),
+        Command::Schedule { action } => cmd_schedule(&config, &rpc, action),
+        Command::Emission { action } => cmd_emission(&config, &rpc, action),
+        Command::Lp { action } => cmd_lp(&config, &rpc, action),
+        Command::Proposal { action } => cmd_proposal(&config, &rpc, action),
+        Command::Signer { spec } => cmd_signer(&rpc, spec.as_deref()),
+        Command::SignProposal { path, yes } => cmd_sign_proposal(&config, &rpc, &path, yes),
+        Command::VerifyAuditLog { path } => cmd_verify_audit_log(path),
+        Command::TransferHook { action } => cmd_transfer_hook(&rpc, action),
+        Command::Group { action } => cmd_group(&rpc, action),
+        Command::Account { action } => cmd_account(&rpc, action),
+        Command::Allocate {
+            supply,
+            decimals,
+            shares,
+        } => cmd_allocate(&supply, decimals, &shares),
+        Command::Impact(args) => cmd_impact(&args),
+        Command::MintKey { index, count, out } => cmd_mint_key(index, count, out),
+        Command::Rent {
+            token_2022,
+            extensions,
+            holders,
+        } => cmd_rent(&config, &rpc, token_2022, &extensions, holders),
+        Command::Health {
+            reference,
+            watch,
+            interval,
+        } => cmd_health(&config, &rpc, reference, watch, interval),
+        Command::Doctor => cmd_doctor(&config, &rpc),
+        Command::SelfUpdate { check, yes } => cmd_self_update(&config, check, yes),
+        Command::Stats { json, reset } => cmd_stats(&config, json, reset),
+        Command::Vanity {
+            prefix,
+            suffix,
+            ignore_case,
+            threads,
+            seconds,
+        } => cmd_vanity(&prefix, &suffix, ignore_case, threads, seconds),
+        Command::Plan { action } => {
+            cmd_plan(&config, &rpc, events.as_ref(), broadcaster.as_ref(), action)
+        }
+        Command::TestBuy {
+            mint,
+            sol,
+            slippage_bps,
+        } => cmd_test_buy(&config, &rpc, &mint, &sol, slippage_bps),
+        Command::Collection { action } => cmd_collection(&config, &rpc, action),
+        Command::Metadata { action } => cmd_metadata(&config, &rpc, action),
+        Command::Update { action } => cmd_update(&config, &rpc, action),
+        Command::RunScript {
+            path,
+            mint,
+            dry_run,
+            max_supply,
+        } => cmd_run_script(&config, &rpc, &path, &mint, dry_run, max_supply.as_deref()),
+        Command::Plugins { action } => cmd_plugins(action),
+        Command::Claim { action } => cmd_claim(&rpc, action),
+        Command::Draw { action } => cmd_draw(&rpc, action),
+        Command::Airdrop(args) => cmd_airdrop(&rpc, args),
+        Command::FreezeWatch(args) => cmd_freeze_watch(&rpc, args),
+        Command::Clawback(args) => cmd_clawback(&rpc, args),
+        Command::Retire(args) => cmd_retire(&config, &rpc, args),
+        Command::Reclaim(args) => cmd_reclaim(&config, &rpc, args),
+    }
+}
+
+fn start_local_validator(config: &mut Config) -> Result<TestValidator> {
+    eprintln!("starting solana-test-validator...");
+    let validator = TestValidator::start(&ValidatorOptions {
+        startup_timeout: config.timeouts.validator(),
+        program: Some(process::find_tool(
+            VALIDATOR_BIN,
+            config.tool(VALIDATOR_BIN),
+        )?),
+        ..ValidatorOptions::default()
+    })?;
+    config.network = Network::Localnet;
+    config.rpc_url = Some(validator.url().to_string());
+    // Commands that only read the chain work without a keypair.
+    if let Ok(payer) = Credentials::load().and_then(|c| c.keypair()) {
+        validator.airdrop(&payer.pubkey(), LOCAL_AIRDROP_LAMPORTS)?;
+        eprintln!(
+            "funded {} with {} SOL on {}",
+            payer.pubkey(),
+            system::format_sol(LOCAL_AIRDROP_LAMPORTS),
+            validator.url()
+        );
+    }
+    Ok(validator)
+}
+
+fn cmd_reconcile(
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    ledger: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    let report = reconcile::reconcile(rpc, mint)?;
+    if let Some(path) = ledger {
+        report.write_ledger_csv(BufWriter::new(File::create(&path)?))?;
+        eprintln!("ledger written to {}", path.display());
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let d = report.decimals;
+    println!("Mint:             {} ({})", report.mint, report.program_id);
+    println!(
+        "Reported supply:  {} (slot {})",
+        report.reported_supply, report.supply_slot
+    );
+    println!(
+        "Accounts total:   {} across {} accounts (slot {})",
+        report.accounts_total,
+        report.entries.len(),
+        report.accounts_slot
+    );
+    if report.is_balanced() {
+        println!("Status:           balanced");
+    } else {
+        let diff = report.difference();
+        let sign = if diff < 0 { "-" } else { "+" };
+        println!(
+            "Status:           MISMATCH ({sign}{})",
+            TokenAmount::from_raw(diff.unsigned_abs(), d)
+        );
+    }
+    if report.supply_changed_during_scan {
+        println!(
+            "Warning:          supply changed during the scan; re-run for a consistent snapshot"
+        );
+    }
+    println!(
+        "Frozen:           {} accounts holding {}",
+        report.count(Anomaly::Frozen),
+        report.frozen_total()
+    );
+    println!(
+        "Frozen & empty:   {} (must be thawed before closing)",
+        report.count(Anomaly::FrozenEmpty)
+    );
+    println!("Empty/closable:   {}", report.count(Anomaly::Empty));
+    println!("Delegated:        {}", report.count(Anomaly::Delegated));
+    println!(
+        "Foreign closer:   {}",
+        report.count(Anomaly::ForeignCloseAuthority)
+    );
+    Ok(())
+}
+
+fn cmd_holders(
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    out: Option<PathBuf>,
+    top: usize,
+    json: bool,
+) -> Result<()> {
+    let snapshot = holders::snapshot(rpc, mint)?;
+    if let Some(path) = out {
+        snapshot.write_csv(BufWriter::new(File::create(&path)?))?;
+        eprintln!(
+            "{} holders written to {}",
+            snapshot.holders.len(),
+            path.display()
+        );
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&snapshot)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} holders of {} (supply {}, slot {})",
+        snapshot.holders.len(),
+        snapshot.mint,
+        snapshot.supply,
+        snapshot.slot
+    );
+    for holder in snapshot.holders.iter().take(top) {
+        let program = match holder.owner_program {
+            Some(program) => format!("  (program {})", rpc.labels().name(&program)),
+            None => String::new(),
+        };
+        println!(
+            "{:<44}  {:>24}  {:>7}{program}",
+            holder.owner,
+            holder.amount,
+            amount::format_bps(holder.share_bps)
+        );
+    }
+    Ok(())
+}
+
+fn cmd_portfolio(config: &Config, rpc: &RpcClient, extra: Vec<Pubkey>, json: bool) -> Result<()> {
+    let mut wallets = Vec::new();
+    if let Ok(keypair) = Credentials::load().and_then(|c| c.keypair()) {
+        wallets.push(keypair.pubkey());
+    }
+    for wallet in extra
+        .into_iter()
+        .chain(config.watch_wallets.iter().map(|w| w.address))
+    {
+        if !wallets.contains(&wallet) {
+            wallets.push(wallet);
+        }
+    }
+    if wallets.is_empty() {
+        return Err(Error::InvalidInput(
+            "no wallet: set DEV_KEYPAIR or pass --wallet".into(),
+        ));
+    }
+
+    let mut portfolios = wallets
+        .iter()
+        .map(|wallet| Portfolio::load(rpc, wallet))
+        .collect::<Result<Vec<_>>>()?;
+    // Only mainnet tokens have a market price.
+    if config.network == Network::Mainnet {
+        let mints: Vec<Pubkey> = portfolios.iter().flat_map(Portfolio::mints).collect();
+        match portfolio::fetch_usd_prices(config.price_api_url(), &mints) {
+            Ok(prices) => portfolios.iter_mut().for_each(|p| p.apply_prices(&prices)),
+            Err(e) => eprintln!("warning: no prices: {e}"),
+        }
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&portfolios)?);
+        return Ok(());
+    }
+
+    let usd = |value: Option<f64>| value.map_or("-".to_string(), |v| format!("${v:.2}"));
+    let sessions = SessionStore::open_default()?.list()?;
+    for (i, p) in portfolios.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match config.watch_wallets.iter().find(|w| w.address == p.wallet) {
+            Some(w) => println!("Wallet {} ({}, watch-only)", p.wallet, w.label),
+            None => println!("Wallet {}", p.wallet),
+        }
+        println!(
+            "  {:<44}  {:>24}  {:>14}",
+            "SOL",
+            system::format_sol(p.lamports),
+            usd(p.sol_usd_value())
+        );
+        for h in &p.holdings {
+            println!(
+                "  {:<44}  {:>24}  {:>14}",
+                h.mint,
+                h.amount,
+                usd(h.usd_value())
+            );
+        }
+        if config.network == Network::Mainnet {
+            println!(
+                "  Total: ${:.2} (unpriced tokens count as 0)",
+                p.usd_total()
+            );
+        }
+        if !p.empty_accounts.is_empty() {
+            println!(
+                "  {} empty token account(s) lock {} SOL of rent",
+                p.empty_accounts.len(),
+                system::format_sol(p.empty_rent)
+            );
+        }
+        let launched: Vec<&LaunchSession> = sessions
+            .iter()
+            .filter(|s| s.authority == p.wallet && s.network == config.network && s.is_complete())
+            .collect();
+        if !launched.is_empty() {
+            println!("  Launched:");
+            for s in launched {
+                let mint = s.mint.map(|m| m.to_string()).unwrap_or_default();
+                println!("    {:<10}  {mint}", s.params.symbol);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_wallet(config: &Config, rpc: &RpcClient, action: WalletAction) -> Result<()> {
+    match action {
+        WalletAction::Watch { address, label } => {
+            // Saved from a fresh load so --network/--url overrides aren't persisted.
+            let mut stored = Config::load()?;
+            if let Some(existing) = stored
+                .watch_wallets
+                .iter()
+                .find(|w| w.label == label || w.address == address)
+            {
+                return Err(Error::InvalidInput(format!(
+                    "already watching {} as '{}'",
+                    existing.address, existing.label
+                )));
+            }
+            if let Ok(keypair) = Credentials::load().and_then(|c| c.keypair())
+                && keypair.pubkey() == address
+            {
+                eprintln!("note: {address} is DEV_KEYPAIR, which is always included");
+            }
+            stored.watch_wallets.push(WatchWallet { label, address });
+            stored.save()?;
+            println!("Watching {address}");
+        }
+        WalletAction::Unwatch { wallet } => {
+            let mut stored = Config::load()?;
+            let Some(found) = stored.watch_wallet(&wallet).cloned() else {
+                return Err(Error::InvalidInput(format!("not watching '{wallet}'")));
+            };
+            stored.watch_wallets.retain(|w| *w != found);
+            stored.save()?;
+            println!("Stopped watching {} ({})", found.address, found.label);
+        }
+        WalletAction::List => {
+            if config.watch_wallets.is_empty() {
+                println!("no watch-only wallets; add one with `wallet watch ADDRESS --label NAME`");
+            }
+            for w in &config.watch_wallets {
+                println!("{:<20}  {}", w.label, w.address);
+            }
+        }
+        WalletAction::Show { wallet, mints } => {
+            let (address, label) = match config.watch_wallet(&wallet) {
+                Some(w) => (w.address, Some(w.label.as_str())),
+                None => (wallet.parse::<Pubkey>()?, None),
+            };
+            show_wallet(config, rpc, &address, label, mints)?;
+        }
+    }
+    Ok(())
+}
+
+fn cmd_authorities(
+    config: &Config,
+    rpc: &RpcClient,
+    wallet: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let wallet = match wallet {
+        Some(w) => match config.watch_wallet(&w) {
+            Some(watched) => watched.address,
+            None => w.parse()?,
+        },
+        None => Credentials::load()?.keypair()?.pubkey(),
+    };
+    eprintln!("scanning for mints {wallet} holds authority over...");
+    let audit = portfolio::audit_authorities(rpc, &wallet)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&audit)?);
+    } else if audit.roles.is_empty() {
+        println!("{wallet} holds no mint, freeze or metadata update authority");
+    } else {
+        println!("{:<44}  AUTHORITY", "MINT");
+        for r in &audit.roles {
+            println!("{:<44}  {}", r.mint, r.names().join(", "));
+        }
+        println!(
+            "{wallet} holds authority over {} mint(s); revoke or rotate what is no longer needed",
+            audit.roles.len()
+        );
+    }
+    for f in &audit.failed {
+        eprintln!(
+            "warning: {} scan failed, results may be incomplete: {}",
+            f.scan, f.error
+        );
+    }
+    Ok(())
+}
+
+fn cmd_wallet_scan(
+    config: &Config,
+    rpc: &RpcClient,
+    wallet: &str,
+    deep: bool,
+    json: bool,
+) -> Result<()> {
+    let wallet = match config.watch_wallet(wallet) {
+        Some(watched) => watched.address,
+        None => wallet.parse()?,
+    };
+    let mut launched: HashSet<Pubkey> = LaunchStore::open_default()?
+        .list()?
+        .iter()
+        .filter(|r| r.network == config.network)
+        .map(|r| r.mint)
+        .collect();
+    launched.extend(
+        SessionStore::open_default()?
+            .list()?
+            .iter()
+            .filter(|s| s.network == config.network)
+            .filter_map(|s| s.mint),
+    );
+    let scan = portfolio::scan_wallet(rpc, &wallet, &launched)?;
+    let audit = if deep {
+        eprintln!("scanning for mints {wallet} holds authority over...");
+        let mut audit = portfolio::audit_authorities(rpc, &wallet)?;
+        audit
+            .roles
+            .retain(|r| !scan.tokens.iter().any(|t| t.holding.mint == r.mint));
+        Some(audit)
+    } else {
+        None
+    };
+    if json {
+        let report = serde_json::json!({
+            "scan": scan,
+            "controlled_not_held": audit.as_ref().map(|a| &a.roles),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("Wallet {wallet}");
+        println!("  SOL: {}", system::format_sol(scan.lamports));
+        if scan.tokens.is_empty() {
+            println!("  holds no tokens");
+        }
+        for t in &scan.tokens {
+            let mut flags = Vec::new();
+            if t.launched_here {
+                flags.push("launched here".to_string());
+            }
+            if !t.authorities.is_empty() {
+                flags.push(format!("{} authority", t.authorities.join(", ")));
+            }
+            println!(
+                "  {:<44}  {:<10}  {:>24}  {}",
+                t.holding.mint,
+                t.symbol
+                    .as_deref()
+                    .or_else(|| rpc.labels().get(&t.holding.mint))
+                    .unwrap_or("?"),
+                t.holding.amount,
+                flags.join("; ")
+            );
+        }
+        if scan.empty_accounts > 0 {
+            println!("  and {} empty token account(s)", scan.empty_accounts);
+        }
+        if let Some(audit) = &audit
+            && !audit.roles.is_empty()
+        {
+            println!("  Controls without holding:");
+            for r in &audit.roles {
+                let mark = if launched.contains(&r.mint) {
+                    "  (launched here)"
+                } else {
+                    ""
+                };
+                println!("    {:<44}  {}{mark}", r.mint, r.names().join(", "));
+            }
+        }
+    }
+    for f in audit.iter().flat_map(|a| &a.failed) {
+        eprintln!(
+            "warning: {} scan failed, results may be incomplete: {}",
+            f.scan, f.error
+        );
+    }
+    Ok(())
+}
+
+/// Authorities can only be checked on known mints: those launched from
+/// `wallet` here plus `extra`.
+fn known_mints(config: &Config, wallet: &Pubkey, mut extra: Vec<Pubkey>) -> Result<Vec<Pubkey>> {
+    extra.extend(
+        SessionStore::open_default()?
+            .list()?
+            .iter()
+            .filter(|s| s.authority == *wallet && s.network == config.network)
+            .filter_map(|s| s.mint),
+    );
+    extra.sort();
+    extra.dedup();
+    Ok(extra)
+}
+
+fn cmd_rotate(config: &Config, rpc: &RpcClient, args: RotateArgs) -> Result<()> {
+    let RotateArgs {
+        to,
+        from,
+        payer,
+        mints,
+        report: report_path,
+        yes,
+    } = args;
+    let old = match from {
+        Some(path) => Keypair::read_file(&path)?,
+        None => Credentials::load()?.keypair()?,
+    };
+    let payer = payer.map(|path| Keypair::read_file(&path)).transpose()?;
+    let old_key = old.pubkey();
+    if to == old_key {
+        return Err(Error::InvalidInput(
+            "the new wallet is the old wallet".into(),
+        ));
+    }
+    let mints = known_mints(config, &old_key, mints)?;
+    let holdings = rotate::Holdings::load(rpc, &old_key, &mints)?;
+    if holdings.is_empty() {
+        println!("{old_key} holds nothing to move");
+        return Ok(());
+    }
+
+    println!("Rotating {old_key} -> {to}");
+    for r in &holdings.authorities {
+        println!("  authority  {:<44}  {}", r.mint, r.names().join(", "));
+    }
+    for t in &holdings.tokens {
+        let note = if t.transfer_hook && !t.amount.is_zero() {
+            "  (transfer hook: cannot be moved here)"
+        } else {
+            ""
+        };
+        println!("  tokens     {:<44}  {:>24}{note}", t.mint, t.amount);
+    }
+    println!("  SOL        {}", system::format_sol(holdings.lamports));
+    if !yes && !confirm(&format!("Move all of this to {to}?"))? {
+        return Err(Error::InvalidInput("rotation cancelled".into()));
+    }
+
+    let handed_over: Vec<Pubkey> = holdings
+        .authorities
+        .iter()
+        .filter(|r| r.metadata_update_authority)
+        .map(|r| r.mint)
+        .collect();
+    let report = with_metadata_backups(config, rpc, &handed_over, || {
+        rotate::rotate(
+            rpc,
+            &old,
+            &to,
+            payer.as_ref().unwrap_or(&old),
+            &holdings,
+            &mints,
+        )
+    })?;
+    std::fs::write(&report_path, serde_json::to_string_pretty(&report)?)?;
+    for step in &report.steps {
+        match (&step.signature, &step.error) {
+            (Some(sig), _) => println!("  ok      {}  {sig}", step.description),
+            (None, Some(e)) => println!("  FAILED  {}: {e}", step.description),
+            (None, None) => {}
+        }
+    }
+    eprintln!("report written to {}", report_path.display());
+    if !report.is_clean() {
+        let r = &report.remaining;
+        return Err(Error::InvalidInput(format!(
+            "{old_key} still holds {} SOL, {} token account(s) and {} authority role(s)",
+            system::format_sol(r.lamports),
+            r.tokens.len(),
+            r.authorities.len()
+        )));
+    }
+    println!("Rotation complete: {old_key} holds nothing");
+    Ok(())
+}
+
+fn cmd_labels(rpc: &RpcClient, action: LabelsAction) -> Result<()> {
+    match action {
+        LabelsAction::Add { address, label } => {
+            let label = label.trim().to_string();
+            if label.is_empty() {
+                return Err(Error::InvalidInput("the label is empty".into()));
+            }
+            // Saved from a fresh load so --network/--url overrides aren't persisted.
+            let mut stored = Config::load()?;
+            if let Some(old) = stored.labels.insert(address, label.clone()) {
+                println!("Changed the label of {address} from '{old}' to '{label}'");
+            } else {
+                println!("Labelled {address} '{label}'");
+            }
+            stored.save()?;
+        }
+        LabelsAction::Remove { label } => {
+            let mut stored = Config::load()?;
+            let found = stored
+                .labels
+                .iter()
+                .find(|(address, name)| **name == label || address.to_string() == label)
+                .map(|(address, _)| *address);
+            let Some(address) = found else {
+                return Err(Error::InvalidInput(format!(
+                    "no label of your own matches '{label}'; built-in labels can only be replaced"
+                )));
+            };
+            let name = stored.labels.remove(&address).unwrap_or_default();
+            stored.save()?;
+            println!("Removed '{name}' from {address}");
+        }
+        LabelsAction::List => {
+            for (address, label, custom) in rpc.labels().entries() {
+                let origin = if custom { "config" } else { "built-in" };
+                println!("{label:<28}  {:<44}  {origin}", address.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn show_wallet(
+    config: &Config,
+    rpc: &RpcClient,
+    wallet: &Pubkey,
+    label: Option<&str>,
+    mut mints: Vec<Pubkey>,
+) -> Result<()> {
+    match label {
+        Some(label) => println!("Wallet {wallet} ({label}, watch-only)"),
+        None => println!("Wallet {wallet}"),
+    }
+    match rpc.get_account(wallet)?.value {
+        None => println!("  no account on {}: never funded", config.network),
+        Some(account) if account.owner != system::SYSTEM_PROGRAM_ID => println!(
+            "  owned by {}, not the system program: this is not a plain wallet",
+            account.owner
+        ),
+        Some(_) => {}
+    }
+    let portfolio = Portfolio::load(rpc, wallet)?;
+    println!("  SOL: {}", system::format_sol(portfolio.lamports));
+    if !portfolio.holdings.is_empty() {
+        println!("  Token accounts:");
+        for h in &portfolio.holdings {
+            println!(
+                "    {:<44}  {:<44}  {:>24}",
+                h.account,
+                rpc.labels().name(&h.mint),
+                h.amount
+            );
+        }
+    }
+    if !portfolio.empty_accounts.is_empty() {
+        println!(
+            "  {} empty token account(s) lock {} SOL of rent",
+            portfolio.empty_accounts.len(),
+            system::format_sol(portfolio.empty_rent)
+        );
+    }
+
+    mints.extend(portfolio.holdings.iter().map(|h| h.mint));
+    let mints = known_mints(config, wallet, mints)?;
+    let roles = portfolio::authority_roles(rpc, wallet, &mints)?;
+    if roles.is_empty() {
+        println!(
+            "  Holds no authority over the {} mint(s) checked",
+            mints.len()
+        );
+    } else {
+        println!("  Authorities:");
+        for r in &roles {
+            println!("    {:<44}  {}", r.mint, r.names().join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn cmd_transfer(
+    config: &Config,
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    to: &Pubkey,
+    amount: &str,
+    memo: Option<&str>,
+    yes: bool,
+) -> Result<()> {
+    if let Some(text) = memo {
+        memo::validate(text)?;
+    }
+    let owner = Credentials::load()?.signer(rpc.programs())?;
+    let key = owner.pubkey();
+    let info = token::fetch_mint(rpc, mint)?;
+    if extension::find(&info.extensions, extension::TRANSFER_HOOK).is_some() {
+        return Err(Error::InvalidInput(format!(
+            "{mint} has a transfer hook; transfers need the hook's extra accounts, \
+             which this command does not resolve"
+        )));
+    }
+    let decimals = info.mint.decimals;
+    let amount = TokenAmount::parse(amount, decimals)?;
+    let program_id = info.program_id;
+    let mut history = screen::History::load(&screen::History::default_path()?)?;
+    let screen = Screen::new(rpc.programs(), mint, &program_id, &key)
+        .with_known(config.watch_wallets.iter().map(|w| w.address))
+        .with_known(history.addresses());
+    if !print_screen_warnings(&screen.check_accounts(rpc, std::slice::from_ref(to))?)
+        && !yes
+        && !confirm(&format!("Send {amount} to {to} anyway?"))?
+    {
+        return Err(Error::InvalidInput("transfer cancelled".into()));
+    }
+    let source = token::associated_token_address(&key, mint, &program_id);
+    let destination = token::associated_token_address(to, mint, &program_id);
+    let mut ixs = vec![
+        token::create_associated_token_account_idempotent(&key, to, mint, &program_id),
+        token::transfer_checked(
+            &program_id,
+            &source,
+            mint,
+            &destination,
+            &key,
+            amount.to_u64()?,
+            decimals,
+        ),
+    ];
+    if let Some(text) = memo {
+        ixs.push(memo::memo(text, &[&key]));
+    }
+    let signature = send_signed(rpc, &ixs, &owner)?;
+    history.record(to)?;
+    println!("Sent {amount} to {to} ({destination})");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+/// Prints each flagged recipient's warnings, at most [`MAX_LISTED_WARNINGS`]
+/// of them; returns whether there were none.
+fn print_screen_warnings(flagged: &[(Pubkey, Vec<screen::Warning>)]) -> bool {
+    for (recipient, warnings) in flagged.iter().take(MAX_LISTED_WARNINGS) {
+        for warning in warnings {
+            eprintln!("warning: {recipient} {warning}");
+        }
+    }
+    if flagged.len() > MAX_LISTED_WARNINGS {
+        eprintln!(
+            "warning: {} more recipient(s) look wrong",
+            flagged.len() - MAX_LISTED_WARNINGS
+        );
+    }
+    flagged.is_empty()
+}
+
+fn cmd_sweep(
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    treasury: &Pubkey,
+    from: &[PathBuf],
+    memo: Option<&str>,
+    yes: bool,
+) -> Result<()> {
+    if let Some(text) = memo {
+        memo::validate(text)?;
+    }
+    let payer = Credentials::load()?.keypair()?;
+    let others = from
+        .iter()
+        .map(|path| Keypair::read_file(path))
+        .collect::<Result<Vec<_>>>()?;
+    let mut signers = vec![&payer];
+    for keypair in &others {
+        if !signers.iter().any(|k| k.pubkey() == keypair.pubkey()) {
+            signers.push(keypair);
+        }
+    }
+    let wallets: Vec<Pubkey> = signers.iter().map(|k| k.pubkey()).collect();
+    let mut plan = SweepPlan::build(rpc, mint, &wallets, treasury)?;
+    if plan.moves.is_empty() {
+        println!("nothing to sweep: no wallet holds {mint} outside the treasury");
+        return Ok(());
+    }
+
+    let questions: Vec<(Pubkey, String)> = wallets
+        .iter()
+        .filter_map(|wallet| {
+            let moves: Vec<&SweepMove> =
+                plan.moves.iter().filter(|m| m.wallet == *wallet).collect();
+            if moves.is_empty() {
+                return None;
+            }
+            let raw: u128 = moves.iter().map(|m| m.amount.raw()).sum();
+            let amount = TokenAmount::from_raw(raw, plan.decimals);
+            let question = format!("Sweep {amount} from {wallet} ({} account(s))?", moves.len());
+            Some((*wallet, question))
+        })
+        .collect();
+    let mut answers = if yes {
+        vec![true; questions.len()]
+    } else {
+        Vec::new()
+    };
+    if answers.is_empty() && questions.len() > 1 {
+        eprintln!("answer back to change the previous answer");
+    }
+    while let Some((_, question)) = questions.get(answers.len()) {
+        match reply(question)? {
+            Reply::Yes => answers.push(true),
+            Reply::No => answers.push(false),
+            Reply::Back if answers.pop().is_none() => {
+                eprintln!("there is no earlier question to go back to");
+            }
+            Reply::Back => {}
+        }
+    }
+    let confirmed: Vec<Pubkey> = questions
+        .iter()
+        .zip(&answers)
+        .filter(|(_, sweep)| **sweep)
+        .map(|((wallet, _), _)| *wallet)
+        .collect();
+    plan.retain_wallets(&confirmed);
+    if plan.moves.is_empty() {
+        return Err(Error::InvalidInput("sweep cancelled".into()));
+    }
+
+    let batches = sweep::execute(rpc, &payer, &signers, &plan, memo)?;
+    println!(
+        "Swept {} {mint} into {} ({})",
+        plan.total(),
+        plan.treasury,
+        plan.treasury_account
+    );
+    for batch in &batches {
+        for m in &batch.moves {
+            println!("  {:<44}  {:>24}  {}", m.account, m.amount, batch.signature);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_fund(rpc: &RpcClient, action: FundAction) -> Result<()> {
+    let funder = Credentials::load()?.signer(rpc.programs())?;
+    let funder_key = funder.pubkey();
+    let (wallets, transfers, yes, json) = match action {
+        FundAction::Split {
+            dir,
+            count,
+            each,
+            yes,
+            json,
+        } => {
+            if count == 0 {
+                return Err(Error::InvalidInput("--count must be at least 1".into()));
+            }
+            let each = TokenAmount::parse(&each, 9)?.to_u64()?;
+            let wallets = fund::ensure_wallets(&dir, count, &funder_key)?;
+            let keys: Vec<Pubkey> = wallets.iter().map(Keypair::pubkey).collect();
+            let transfers = fund::top_up(rpc, &funder_key, &keys, each)?;
+            let needed: u64 = transfers.iter().map(|t| t.lamports).sum::<u64>()
+                + fund::fees(&funder_key, &transfers)?;
+            let balance = rpc.get_balance(&funder_key)?;
+            if balance < needed {
+                return Err(Error::InvalidInput(format!(
+                    "{funder_key} holds {} SOL; funding needs {} SOL with fees",
+                    system::format_sol(balance),
+                    system::format_sol(needed)
+                )));
+            }
+            (wallets, transfers, yes, json)
+        }
+        FundAction::Sweep { dir, yes, json } => {
+            let wallets = fund::load_wallets(&dir)?;
+            if wallets.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "no wallet-<n>.json keypairs in {}",
+                    dir.display()
+                )));
+            }
+            let keys: Vec<Pubkey> = wallets.iter().map(Keypair::pubkey).collect();
+            let transfers = fund::sweep_back(rpc, &funder_key, &keys)?;
+            (wallets, transfers, yes, json)
+        }
+    };
+    if transfers.is_empty() {
+        println!("nothing to transfer: every wallet is already where it should be");
+        return Ok(());
+    }
+    let total: u64 = transfers.iter().map(|t| t.lamports).sum();
+    let question = format!(
+        "Move {} SOL in {} transfer(s), fees {} SOL paid by {funder_key}?",
+        system::format_sol(total),
+        transfers.len(),
+        system::format_sol(fund::fees(&funder_key, &transfers)?)
+    );
+    if !yes && !confirm(&question)? {
+        return Err(Error::InvalidInput("funding cancelled".into()));
+    }
+    let batches = fund::execute(rpc, funder.as_ref(), &wallets, &transfers)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&batches)?);
+    } else {
+        print_fund_batches(&batches);
+    }
+    let failed = batches.iter().filter(|b| b.error.is_some()).count();
+    if failed > 0 {
+        return Err(Error::InvalidInput(format!(
+            "{failed} of {} transaction(s) failed; run the command again to retry them",
+            batches.len()
+        )));
+    }
+    Ok(())
+}
+
+fn print_fund_batches(batches: &[FundBatch]) {
+    for batch in batches {
+        let outcome = match (&batch.error, &batch.signature) {
+            (Some(error), _) => format!("FAILED: {error}"),
+            (None, Some(signature)) => signature.to_string(),
+            (None, None) => String::new(),
+        };
+        println!(
+            "{} transfer(s), {} SOL  {outcome}",
+            batch.transfers.len(),
+            system::format_sol(batch.lamports())
+        );
+        for t in &batch.transfers {
+            println!(
+                "  {:<44} -> {:<44} {:>14} SOL",
+                t.from.to_string(),
+                t.to.to_string(),
+                system::format_sol(t.lamports)
+            );
+        }
+    }
+    let moved: u64 = batches
+        .iter()
+        .filter(|b| b.error.is_none())
+        .map(FundBatch::lamports)
+        .sum();
+    println!("Moved {} SOL", system::format_sol(moved));
+}
+
+fn cmd_clawback(rpc: &RpcClient, args: ClawbackArgs) -> Result<()> {
+    memo::validate(&args.reason)?;
+    let delegate = Credentials::load()?.signer(rpc.programs())?;
+    let key = delegate.pubkey();
+    let treasury = args.to.unwrap_or(key);
+    let plan = Clawback::prepare(rpc, &args.mint, &key, &treasury, &args.holders)?;
+
+    println!("Clawing back {} to {treasury}", args.mint);
+    println!("Reason: {}", args.reason);
+    for h in &plan.holdings {
+        let note = match &h.skip {
+            Some(reason) => format!("  (skipped: {reason})"),
+            None if h.frozen => "  (thawed and frozen again)".to_string(),
+            None => String::new(),
+        };
+        println!(
+            "  {:<44}  {:<44}  {:>24}{note}",
+            h.account, h.owner, h.amount
+        );
+    }
+    let count = plan.taken().count();
+    println!("Total: {} from {count} account(s)", plan.total());
+    if args.dry_run || count == 0 {
+        return Ok(());
+    }
+    if !args.yes && !confirm(&format!("Move {} to {treasury}?", plan.total()))? {
+        return Err(Error::InvalidInput("clawback cancelled".into()));
+    }
+
+    let log = args
+        .log
+        .unwrap_or_else(|| clawback::default_log_path(&args.mint));
+    let entries = plan.execute(rpc, &*delegate, &args.reason, &log)?;
+    let mut failed = 0;
+    for entry in &entries {
+        match (&entry.signature, &entry.error) {
+            (Some(sig), _) => println!("  ok      {}  {}  {sig}", entry.account, entry.amount),
+            (None, Some(e)) => {
+                failed += 1;
+                println!("  FAILED  {}: {e}", entry.account);
+            }
+            (None, None) => {}
+        }
+    }
+    eprintln!("logged to {}", log.display());
+    if failed > 0 {
+        return Err(Error::InvalidInput(format!(
+            "{failed} of {} account(s) were not clawed back",
+            entries.len()
+        )));
+    }
+    Ok(())
+}
+
+fn cmd_reclaim(config: &Config, rpc: &RpcClient, args: ReclaimArgs) -> Result<()> {
+    let signer = Credentials::load()?.signer(rpc.programs())?;
+    let owner = signer.pubkey();
+    let sessions: Vec<LaunchSession> = SessionStore::open_default()?
+        .list()?
+        .into_iter()
+        .filter(|s| s.network == config.network)
+        .collect();
+    let schedules: Vec<ScheduledRevocation> = ScheduleStore::open_default()?
+        .list()?
+        .into_iter()
+        .filter(|s| s.network == config.network)
+        .collect();
+    let mut nonces = reclaim::finished_schedule_nonces(&schedules);
+    let payers = match &args.payers {
+        Some(dir) => fund::load_wallets(dir)?,
+        None => Vec::new(),
+    };
+    nonces.extend(reclaim::airdrop_nonces(&payers, args.nonces_per_payer));
+    let tables: Vec<reclaim::Candidate> = args
+        .lookup_tables
+        .iter()
+        .map(|address| reclaim::Candidate {
+            address: *address,
+            source: "given on the command line".into(),
+        })
+        .collect();
+
+    eprintln!(
+        "Scanning {} launch session(s), {} nonce account(s) and {} lookup table(s) on {}...",
+        sessions.len(),
+        nonces.len(),
+        tables.len(),
+        config.network
+    );
+    let scan = reclaim::scan(rpc, &owner, &sessions, &nonces, &tables)?;
+    for (address, why) in &scan.waiting {
+        println!("  left  {address}: {why}");
+    }
+    for c in &scan.closable {
+        println!(
+            "  close  {}  {:<20} {:>14} SOL  ({})",
+            c.address,
+            c.kind.as_str(),
+            system::format_sol(c.lamports),
+            c.source
+        );
+    }
+    if scan.closable.is_empty() {
+        println!("Nothing to reclaim");
+        return Ok(());
+    }
+    println!(
+        "{} account(s) hold {} SOL of reclaimable rent, returned to {owner}",
+        scan.closable.len(),
+        system::format_sol(scan.lamports())
+    );
+    if args.dry_run {
+        return Ok(());
+    }
+    if !args.yes && !confirm("Close these accounts?")? {
+        return Err(Error::InvalidInput("reclaim cancelled".into()));
+    }
+
+    let others: Vec<&dyn Signer> = payers.iter().map(|k| k as &dyn Signer).collect();
+    let outcomes = reclaim::close(rpc, &*signer, &others, &scan.closable)?;
+    let mut reclaimed = 0;
+    let mut failed = 0;
+    for batch in &outcomes {
+        for c in &batch.accounts {
+            match (&batch.signature, &batch.error) {
+                (Some(sig), _) => {
+                    reclaimed += c.lamports;
+                    println!("  closed  {}  {sig}", c.address);
+                }
+                (None, Some(e)) => {
+                    failed += 1;
+                    println!("  FAILED  {}: {e}", c.address);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+    println!("Reclaimed {} SOL", system::format_sol(reclaimed));
+    if failed > 0 {
+        return Err(Error::InvalidInput(format!(
+            "{failed} account(s) could not be closed; run reclaim again to retry"
+        )));
+    }
+    Ok(())
+}
+
+fn cmd_retire(config: &Config, rpc: &RpcClient, args: RetireArgs) -> Result<()> {
+    let signer = Credentials::load()?.signer(rpc.programs())?;
+    let plan = Retirement::prepare(rpc, &args.mint, &signer.pubkey())?;
+
+    println!("Retiring {} on {}", args.mint, config.network);
+    for h in &plan.holdings {
+        let note = match &h.skip {
+            Some(reason) => format!("  (left open: {reason})"),
+            None if h.frozen => "  (thawed first)".to_string(),
+            None => String::new(),
+        };
+        println!("  burn {:>24} and close {}{note}", h.amount, h.account);
+    }
+    let reclaimed: u64 = plan.retired().map(|h| h.lamports).sum();
+    println!(
+        "Burn {} of the supply of {}, leaving {} with other holders; reclaim {} SOL",
+        plan.burned(),
+        plan.supply,
+        plan.remaining(),
+        system::format_sol(reclaimed)
+    );
+    match (&plan.metadata, &plan.metadata_skip) {
+        (_, Some(reason)) => println!("Metadata: left as it is ({reason})"),
+        _ if args.keep_metadata => println!("Metadata: left as it is"),
+        _ => println!(
+            "Metadata: URI set to {}",
+            args.uri
+                .as_deref()
+                .unwrap_or("an uploaded retirement notice")
+        ),
+    }
+    if args.revoke_mint {
+        if plan.holds_mint_authority {
+            println!("Mint authority: revoked, permanently");
+        } else {
+            println!("Mint authority: not held by {}", plan.authority);
+        }
+    }
+    if args.dry_run {
+        return Ok(());
+    }
+    if !args.yes && !confirm(&format!("Retire {}? This can't be undone.", args.mint))? {
+        return Err(Error::InvalidInput("retirement cancelled".into()));
+    }
+
+    let uri = match (&plan.metadata, args.keep_metadata, args.uri) {
+        (Some(current), false, None) => {
+            let name = &current.data.name;
+            let symbol = &current.data.symbol;
+            let mut description =
+                format!("{name} ({symbol}) has been retired and is no longer supported.");
+            if let Some(reason) = &args.reason {
+                description.push(' ');
+                description.push_str(reason);
+            }
+            let notice = serde_json::json!({
+                "name": name,
+                "symbol": symbol,
+                "description": description,
+            });
+            let uploader = Credentials::load()?.pinata(config)?;
+            let uri = uploader.upload_json(&format!("{name} retirement notice"), &notice)?;
+            eprintln!("retirement notice uploaded to {uri}");
+            Some(uri)
+        }
+        (Some(_), false, Some(uri)) => Some(uri),
+        _ => None,
+    };
+    let execute = || plan.execute(rpc, &*signer, uri.as_deref(), args.revoke_mint);
+    let report = match &uri {
+        Some(_) => with_metadata_backup(config, rpc, &args.mint, execute)?,
+        None => execute()?,
+    };
+    for account in &report.accounts {
+        match (&account.signature, &account.error) {
+            (Some(sig), _) => println!("  closed  {}  {sig}", account.account),
+            (None, Some(e)) => println!("  FAILED  {}: {e}", account.account),
+            (None, None) => {}
+        }
+    }
+    if let Some(sig) = &report.metadata_signature {
+        println!("  metadata  {sig}");
+    }
+    if let Some(sig) = &report.mint_authority_revoked {
+        println!("  mint authority revoked  {sig}");
+    }
+    for note in &report.notes {
+        eprintln!("note: {note}");
+    }
+    println!(
+        "Burned {}, reclaimed {} SOL; supply now {}",
+        report.burned(plan.decimals),
+        system::format_sol(report.reclaimed_lamports()),
+        report.supply_after
+    );
+    let path = args
+        .report
+        .unwrap_or_else(|| retire::default_report_path(&args.mint));
+    std::fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+    eprintln!("report written to {}", path.display());
+    if report.failed() {
+        return Err(Error::InvalidInput(
+            "some accounts were not closed; run retire again to finish".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn cmd_freeze_watch(rpc: &RpcClient, args: FreezeWatchArgs) -> Result<()> {
+    let mut flagged = args.flagged;
+    if let Some(path) = &args.flag_file {
+        flagged.extend(freezer::read_addresses(path)?);
+    }
+    if flagged.is_empty() {
+        return Err(Error::InvalidInput(
+            "nothing to watch; flag an address with --flag or --flag-file".into(),
+        ));
+    }
+    let min_amount = match &args.min_amount {
+        Some(amount) => {
+            let decimals = token::fetch_mint(rpc, &args.mint)?.mint.decimals;
+            TokenAmount::parse(amount, decimals)?.to_u64()?
+        }
+        None => 0,
+    };
+    let rules = FreezeRules {
+        flagged,
+        exempt: args.exempt,
+        min_amount,
+        follow: args.follow,
+        backfill: args.backfill,
+    };
+    let authority = Credentials::load()?.signer(rpc.programs())?;
+    let path = args
+        .log
+        .unwrap_or_else(|| Freezer::default_path(&args.mint));
+    let mut watch = Freezer::open(rpc, &args.mint, &*authority, rules, &path, args.dry_run)?;
+    eprintln!(
+        "watching {} flagged addresses; logging to {}",
+        watch.flagged(),
+        watch.path().display()
+    );
+    loop {
+        match watch.poll() {
+            Ok(events) => {
+                for event in events {
+                    println!("{event}");
+                }
+            }
+            // Keep going: a daemon must not stop over one failed round.
+            Err(e) if args.daemon => eprintln!("{e}"),
+            Err(e) => return Err(e),
+        }
+        if !args.daemon {
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_secs(args.interval));
+    }
+}
+
+fn cmd_airdrop(rpc: &RpcClient, args: AirdropArgs) -> Result<()> {
+    let authority = Credentials::load()?.signer(rpc.programs())?;
+    eprintln!("checking {}...", args.recipients.display());
+    let plan = Airdrop::prepare(rpc, &args.mint, &authority.pubkey(), &args.recipients)?;
+    let payers = fund::load_wallets(&args.payers)?;
+    if payers.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "no fee payers in {}; create them with `fund split`",
+            args.payers.display()
+        )));
+    }
+    let lookup_tables = pack::fetch_lookup_tables(rpc, &args.lookup_tables)?;
+    let journal_path = args
+        .journal
+        .unwrap_or_else(|| Journal::default_path(&args.recipients));
+    let mut journal = Journal::open(&journal_path, &plan)?;
+    let remaining = plan.recipients.len().saturating_sub(journal.finished());
+    if remaining == 0 {
+        println!(
+            "every recipient was handled; see {}",
+            journal_path.display()
+        );
+        return Ok(());
+    }
+
+    // Worst case: every recipient needs a new token account.
+    let nonce_rent = rpc.get_minimum_balance_for_rent_exemption(system::NONCE_ACCOUNT_LEN)?;
+    let account_rent = rpc.get_minimum_balance_for_rent_exemption(token::ACCOUNT_LEN)?;
+    let per_payer = remaining.div_ceil(payers.len()) as u64;
+    let needed = args.nonces_per_payer as u64 * nonce_rent
+        + per_payer * (account_rent + 2 * system::LAMPORTS_PER_SIGNATURE);
+    let keys: Vec<Pubkey> = payers.iter().map(Keypair::pubkey).collect();
+    let balances = rpc.get_multiple_accounts(&keys, None)?.value;
+    for (key, account) in keys.iter().zip(&balances) {
+        let balance = account.as_ref().map_or(0, |a| a.lamports);
+        if balance < needed {
+            return Err(Error::InvalidInput(format!(
+                "fee payer {key} holds {} SOL and may need {} SOL; top the payers up with \
+                 `fund split {} --count {} --each {}`",
+                system::format_sol(balance),
+                system::format_sol(needed),
+                args.payers.display(),
+                payers.len(),
+                system::format_sol(needed)
+            )));
+        }
+    }
+
+    let history = screen::History::load(&screen::History::default_path()?)?;
+    let screen = Screen::new(
+        rpc.programs(),
+        &args.mint,
+        &plan.program_id,
+        &plan.authority,
+    )
+    .with_known(history.addresses());
+    let mut flagged = Vec::new();
+    for chunk in plan.recipients.chunks(0, MAX_MULTIPLE_ACCOUNTS)? {
+        let wallets: Vec<Pubkey> = chunk?.iter().map(|r| r.wallet).collect();
+        flagged.extend(screen.check_accounts(rpc, &wallets)?);
+    }
+    print_screen_warnings(&flagged);
+
+    let question = format!(
+        "Send {} {} to {remaining} wallet(s) ({} already handled), {} fee payer(s) × {} nonce(s)?",
+        plan.total(),
+        args.mint,
+        plan.recipients.len() - remaining,
+        payers.len(),
+        args.nonces_per_payer
+    );
+    if !args.yes && !confirm(&question)? {
+        return Err(Error::InvalidInput("airdrop cancelled".into()));
+    }
+    let created = airdrop::open_nonces(rpc, &payers, args.nonces_per_payer)?;
+    if created > 0 {
+        eprintln!("created {created} nonce account(s)");
+    }
+    let options = EngineOptions {
+        nonces_per_payer: args.nonces_per_payer,
+        rate_per_payer: args.rate,
+        max_per_tx: args.per_tx,
+        lookup_tables,
+    };
+    let report = airdrop::run(
+        rpc,
+        &plan,
+        authority.as_ref(),
+        &payers,
+        options,
+        &mut journal,
+    )?;
+    if !args.keep_nonces {
+        let reclaimed = airdrop::close_nonces(rpc, &payers, args.nonces_per_payer)?;
+        eprintln!(
+            "closed the nonce accounts, returning {} SOL to the fee payers",
+            system::format_sol(reclaimed)
+        );
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_airdrop_report(&report, journal.path());
+    }
+    if !report.failed.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "{} recipient(s) were not paid; run the command again to retry them",
+            report.failed.len()
+        )));
+    }
+    Ok(())
+}
+
+fn print_airdrop_report(report: &AirdropReport, journal: &std::path::Path) {
+    println!(
+        "Paid {} of {} recipient(s) in {} transaction(s), {:.1}s",
+        report.confirmed, report.recipients, report.transactions, report.elapsed_secs
+    );
+    if report.already_done > 0 {
+        println!("{} were handled by an earlier run", report.already_done);
+    }
+    for failed in &report.failed {
+        println!("  FAILED {}  {}", failed.wallet, failed.error);
+    }
+    println!("Journal: {}", journal.display());
+}
+
+fn cmd_export_txs(
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    out: Option<PathBuf>,
+    limit: Option<usize>,
+) -> Result<()> {
+    let scope = ExportScope::load(rpc, mint)?;
+    let addresses = scope.addresses();
+    eprintln!("collecting signatures for {} addresses...", addresses.len());
+    let signatures = export::collect_signatures(rpc, &addresses, limit)?;
+    eprintln!("decoding {} transactions...", signatures.len());
+
+    let mut movements = Vec::new();
+    for (i, info) in signatures.iter().enumerate() {
+        if let Some(tx) = rpc.get_transaction(&info.signature)? {
+            movements.extend(scope.decode(&info.signature, &tx)?);
+        }
+        if (i + 1) % 100 == 0 {
+            eprintln!("  {}/{}", i + 1, signatures.len());
+        }
+    }
+
+    let writer: Box<dyn Write> = match &out {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    export::write_csv(&movements, writer)?;
+    if let Some(path) = out {
+        eprintln!(
+            "{} movements written to {}",
+            movements.len(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+fn cmd_export_accounting(
+    config: &Config,
+    rpc: &RpcClient,
+    args: ExportAccountingArgs,
+) -> Result<()> {
+    let mut wallets = HashSet::from([Credentials::load()?.keypair()?.pubkey()]);
+    for w in &args.wallets {
+        wallets.insert(match config.watch_wallet(w) {
+            Some(watched) => watched.address,
+            None => w.parse()?,
+        });
+    }
+    let mint = args.mint;
+    let scope = ExportScope::load(rpc, &mint)?;
+    let symbol = metadata::fetch_metadata(rpc, &mint)?
+        .map(|m| m.data.symbol)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| mint.to_string());
+    let addresses = scope.addresses();
+    eprintln!("collecting signatures for {} addresses...", addresses.len());
+    let signatures = export::collect_signatures(rpc, &addresses, args.limit)?;
+    eprintln!("decoding {} transactions...", signatures.len());
+    let mut movements = Vec::new();
+    let mut transactions = BTreeMap::new();
+    for (i, info) in signatures.iter().enumerate() {
+        if let Some(tx) = rpc.get_transaction(&info.signature)? {
+            movements.extend(scope.decode(&info.signature, &tx)?);
+            transactions.insert(info.signature.clone(), tx);
+        }
+        if (i + 1) % 100 == 0 {
+            eprintln!("  {}/{}", i + 1, signatures.len());
+        }
+    }
+    let mut entries = accounting::entries(&symbol, &wallets, &movements, &transactions)?;
+
+    let times: Vec<i64> = entries.iter().filter_map(|e| e.block_time).collect();
+    if let (false, Some(&from), Some(&to)) =
+        (args.no_prices, times.iter().min(), times.iter().max())
+    {
+        if config.network == Network::Mainnet {
+            let api = config.price_history_api_url();
+            // A little either side, so the first and last entries have a
+            // price quoted near them.
+            let (from, to) = (from - 3600, to + 3600);
+            let sol = accounting::sol_price_history(api, from, to)?;
+            let token = accounting::token_price_history(api, &mint, from, to)?;
+            if token.is_none() {
+                eprintln!(
+                    "note: {api} has no price history for {symbol}; its USD values are left empty"
+                );
+            }
+            accounting::apply_prices(&mut entries, &sol, token.as_ref());
+        } else {
+            eprintln!(
+                "note: {} tokens and SOL have no market price; USD values are left empty",
+                config.network
+            );
+        }
+    }
+
+    let writer: Box<dyn Write> = match &args.out {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    match args.format {
+        AccountingFormat::Generic => accounting::write_csv(&entries, writer)?,
+        AccountingFormat::Koinly => accounting::write_koinly_csv(&entries, writer)?,
+    }
+    if let Some(path) = args.out {
+        eprintln!("{} entries written to {}", entries.len(), path.display());
+    }
+    Ok(())
+}
+
+fn cmd_rehost(
+    config: &Config,
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    from_file: Option<PathBuf>,
+    rehost_image: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let credentials = Credentials::load()?;
+    let authority = credentials.keypair()?;
+    let uploader = credentials.pinata(config)?;
+    let local_json = match from_file {
+        Some(path) => Some(serde_json::from_str(&std::fs::read_to_string(path)?)?),
+        None => None,
+    };
+    let options = RehostOptions {
+        local_json,
+        rehost_image,
+        dry_run,
+        ipfs_gateway: config.ipfs_gateway().to_string(),
+    };
+    let outcome = if dry_run {
+        rehost::rehost(rpc, &uploader, &authority, mint, options)?
+    } else {
+        with_metadata_backup(config, rpc, mint, || {
+            rehost::rehost(rpc, &uploader, &authority, mint, options)
+        })?
+    };
+    print_findings(&outcome.findings);
+
+    println!("Current URI:  {}", outcome.old_uri);
+    if let Some(image) = &outcome.old_image {
+        println!("Current logo: {image}");
+    }
+    if dry_run {
+        println!(
+            "Dry run: metadata is mutable and {} holds update authority",
+            authority.pubkey()
+        );
+        return Ok(());
+    }
+    if let Some(image) = &outcome.new_image {
+        println!("New logo:     {image}");
+    }
+    if let Some(uri) = &outcome.new_uri {
+        println!("New URI:      {uri}");
+    }
+    if let Some(signature) = &outcome.signature {
+        println!("Signature:    {signature}");
+    }
+    Ok(())
+}
+
+fn cmd_pay_link(config: &Config, rpc: &RpcClient, args: PayLinkArgs) -> Result<()> {
+    let recipient = match args.to {
+        Some(w) => match config.watch_wallet(&w) {
+            Some(watched) => watched.address,
+            None => w.parse()?,
+        },
+        None => Credentials::load()?.keypair()?.pubkey(),
+    };
+    let mint = args.mint;
+    let info = token::fetch_mint(rpc, &mint)?;
+    if extension::find(&info.extensions, extension::TRANSFER_HOOK).is_some() {
+        eprintln!(
+            "warning: {mint} has a transfer hook; wallets that don't resolve its \
+             extra accounts can't pay this link"
+        );
+    }
+    let mut request = TransferRequest::new(recipient);
+    request.spl_token = Some(mint);
+    request.amount = args
+        .amount
+        .map(|a| TokenAmount::parse(&a, info.mint.decimals))
+        .transpose()?;
+    request.label = args.label;
+    request.message = args.message;
+    request.memo = args.memo;
+    if args.reference {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        request
+            .references
+            .push(pay::unique_reference(&recipient, &mint, nanos));
+    }
+    request.validate()?;
+    let url = request.to_url();
+    match QrCode::encode(&url) {
+        Ok(qr) => print!("{}", qr.to_terminal()),
+        Err(_) => eprintln!(
+            "the link is too long for a terminal QR code; shorten the label or message, \
+             or encode it with another tool"
+        ),
+    }
+    println!("{url}");
+    for reference in &request.references {
+        println!("Reference: {reference} (the payment's transaction lists it as an account)");
+    }
+    Ok(())
+}
+
+fn cmd_deposit_test(config: &Config, rpc: &RpcClient, args: DepositTestArgs) -> Result<()> {
+    let sender = Credentials::load()?.signer(rpc.programs())?;
+    let mint = args.mint;
+    let info = token::fetch_mint(rpc, &mint)?;
+    let amount = TokenAmount::parse(&args.amount, info.mint.decimals)?;
+    if amount.is_zero() {
+        return Err(Error::InvalidInput(
+            "a test deposit must move some tokens".into(),
+        ));
+    }
+    let destination = deposit::resolve(rpc, &mint, &info, &args.to)?;
+    let test = DepositTest {
+        mint,
+        info,
+        destination,
+        amount,
+        memo: args.memo,
+    };
+    println!("Deposit {amount} of {mint} from {}", sender.pubkey());
+    println!("  to:            {}", destination.address);
+    if destination.token_account != destination.address {
+        println!("  token account: {}", destination.token_account);
+    }
+    if !destination.exists {
+        println!("  (the token account doesn't exist yet; the deposit creates it)");
+    }
+    if let Some(text) = &test.memo {
+        println!("  memo:          {text}");
+    }
+    if !args.yes && !confirm("Send the deposit?")? {
+        return Err(Error::InvalidInput("deposit cancelled".into()));
+    }
+    println!("waiting for the deposit to be finalized...");
+    let evidence = test.run(rpc, config.network, sender.as_ref())?;
+    let path = args
+        .out
+        .unwrap_or_else(|| PathBuf::from(format!("deposit-{mint}.json")));
+    std::fs::write(&path, serde_json::to_string_pretty(&evidence)?)?;
+    let facts = &evidence.deposit;
+    println!("Finalized in slot {}: {}", facts.slot, facts.signature);
+    println!(
+        "Destination balance: {} -> {}",
+        facts.balance_before, facts.balance_after
+    );
+    println!("Explorer: {}", facts.explorer);
+    println!("Evidence written to {}", path.display());
+    Ok(())
+}
+
+fn cmd_logo(
+    config: &Config,
+    path: &std::path::Path,
+    options: LogoOptions,
+    out: &std::path::Path,
+    upload: bool,
+) -> Result<()> {
+    let processed = logo::process(path, options)?;
+    for warning in &processed.warnings {
+        eprintln!("warning: {warning}");
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "logo".into());
+
+    std::fs::create_dir_all(out)?;
+    let image_path = out.join(format!("{stem}-{}.png", options.size));
+    std::fs::write(&image_path, &processed.png)?;
+    println!("Logo:      {}", image_path.display());
+    if let (Some(thumb), Some(size)) = (&processed.thumbnail, options.thumbnail) {
+        let thumb_path = out.join(format!("{stem}-{size}.png"));
+        std::fs::write(&thumb_path, thumb)?;
+        println!("Thumbnail: {}", thumb_path.display());
+    }
+
+    if upload {
+        let uploader = Credentials::load()?.pinata(config)?;
+        let uris = logo::upload(&uploader, &stem, &processed)?;
+        println!("Image URI: {}", uris.image);
+        if let Some(thumb) = uris.thumbnail {
+            println!("Thumb URI: {thumb}");
+        }
+    }
+    Ok(())
+}
+
+fn print_findings(findings: &[Finding]) {
+    for finding in findings {
+        let level = match finding.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        eprintln!("{level}: {}: {}", finding.field, finding.message);
+    }
+}
+
+/// Findings for `data`'s name and symbol: the configured warn and deny
+/// terms, and collisions with tokens on the configured token list. The
+/// list is only advisory, so one that can't be fetched is noted and
+/// skipped.
+fn name_findings(
+    config: &Config,
+    rpc: &RpcClient,
+    data: &DataV2,
+    own: Option<&Pubkey>,
+) -> Vec<Finding> {
+    let mut findings = preview::check_terms(data, &config.warn_terms(), &config.deny_terms);
+    let Some(url) = config.token_list_url() else {
+        return findings;
+    };
+    match collision::fetch(rpc.cache(), url) {
+        Ok(tokens) => findings.extend(collision::check(
+            data,
+            &tokens,
+            own.map(Pubkey::to_string).as_deref(),
+        )),
+        Err(e) => eprintln!("warning: could not check the name and symbol against {url}: {e}"),
+    }
+    findings
+}
+
+fn cmd_preview(
+    config: &Config,
+    rpc: &RpcClient,
+    mint: Option<Pubkey>,
+    name: Option<String>,
+    symbol: Option<String>,
+    uri: Option<String>,
+    json: Option<PathBuf>,
+) -> Result<()> {
+    let mut data = match mint {
+        Some(mint) => {
+            metadata::fetch_metadata_cached(rpc, &mint)?
+                .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?
+                .data
+        }
+        None => DataV2::default(),
+    };
+    if let Some(name) = name {
+        data.name = name;
+    }
+    if let Some(symbol) = symbol {
+        data.symbol = symbol;
+    }
+    if let Some(uri) = uri {
+        data.uri = uri;
+    }
+
+    let off_chain = match json {
+        Some(path) => Some(serde_json::from_str(&std::fs::read_to_string(path)?)?),
+        None if !data.uri.is_empty() => {
+            match solanaapp::storage::fetch_json_cached(
+                rpc.cache(),
+                &data.uri,
+                config.ipfs_gateway(),
+            ) {
+                Ok(json) => Some(json),
+                Err(e) => {
+                    eprintln!("warning: could not fetch off-chain JSON: {e}");
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    print!("{}", preview::render(&data, off_chain.as_ref()));
+    let mut findings = preview::validate(&data, off_chain.as_ref());
+    findings.extend(name_findings(config, rpc, &data, mint.as_ref()));
+    print_findings(&findings);
+    if preview::has_errors(&findings) {
+        return Err(Error::InvalidInput(
+            "metadata has problems that must be fixed before submitting".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Loads the stored launch record for `mint`, rebuilding it from chain state
+/// when the token was not launched from this machine.
+fn launch_record(config: &Config, rpc: &RpcClient, mint: &Pubkey) -> Result<LaunchRecord> {
+    if let Some(record) = LaunchStore::open_default()?.load(mint)? {
+        return Ok(record);
+    }
+    eprintln!("no launch record for {mint}; reconstructing from chain");
+    LaunchRecord::from_chain(rpc, config, mint)
+}
+
+/// Local images for the listing bundles.
+struct ListingAssets {
+    icon: Option<PathBuf>,
+    header: Option<PathBuf>,
+}
+
+fn cmd_registry(
+    config: &Config,
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    target: RegistryTarget,
+    out: Option<PathBuf>,
+    assets: ListingAssets,
+) -> Result<()> {
+    let record = launch_record(config, rpc, mint)?;
+    let state = token::fetch_mint(rpc, mint)?.mint;
+    let checks = registry::checks(&record, &state);
+
+    println!("Listing requirements:");
+    for check in &checks {
+        let mark = if check.passed { "ok  " } else { "MISS" };
+        if check.detail.is_empty() {
+            println!("  [{mark}] {}", check.requirement);
+        } else {
+            println!("  [{mark}] {} ({})", check.requirement, check.detail);
+        }
+    }
+
+    if let Some(dir) = &out {
+        std::fs::create_dir_all(dir)?;
+    }
+    if target.includes(RegistryTarget::Jupiter) {
+        let jupiter = registry::jupiter(&record, &checks);
+        match &out {
+            Some(dir) => {
+                std::fs::write(
+                    dir.join("jupiter-validated-tokens.csv"),
+                    format!("{}\n", jupiter.csv_row),
+                )?;
+                std::fs::write(
+                    dir.join("jupiter-pr.md"),
+                    format!("# {}\n\n{}", jupiter.pr_title, jupiter.pr_body),
+                )?;
+            }
+            None => {
+                println!("\n== Jupiter token list ==");
+                println!("CSV row:  {}", jupiter.csv_row);
+                println!("PR title: {}\n", jupiter.pr_title);
+                print!("{}", jupiter.pr_body);
+            }
+        }
+    }
+    if target.includes(RegistryTarget::Coingecko) {
+        let form = serde_json::to_string_pretty(&registry::coingecko(&record, &state))?;
+        match &out {
+            Some(dir) => std::fs::write(dir.join("coingecko.json"), form)?,
+            None => println!("\n== CoinGecko listing form ==\n{form}"),
+        }
+    }
+    let bundles =
+        target.includes(RegistryTarget::Dexscreener) || target.includes(RegistryTarget::Birdeye);
+    let images = match (&out, bundles) {
+        (Some(_), true) => listing_images(config, &record, &assets)?,
+        _ => {
+            if assets.icon.is_some() || assets.header.is_some() {
+                eprintln!("warning: images are only written with --out");
+            }
+            ListingImages::default()
+        }
+    };
+    if target.includes(RegistryTarget::Dexscreener) {
+        let payload = registry::dexscreener(&record, images.header.is_some());
+        if images.header.is_none() {
+            eprintln!("warning: no --header; DEX Screener's enhanced info shows a header banner");
+        }
+        write_listing_bundle(
+            out.as_deref(),
+            "dexscreener",
+            &payload,
+            images.icon.as_deref(),
+            images.header.as_deref(),
+        )?;
+    }
+    if target.includes(RegistryTarget::Birdeye) {
+        let payload = registry::birdeye(&record, &state);
+        write_listing_bundle(
+            out.as_deref(),
+            "birdeye",
+            &payload,
+            images.icon.as_deref(),
+            None,
+        )?;
+    }
+    if bundles {
+        // Neither site takes submissions through an API; both are orders
+        // placed on their websites with these files attached.
+        eprintln!(
+            "DEX Screener and Birdeye take these through their websites, not an API; \
+             attach the files from each bundle there"
+        );
+    }
+    if let Some(dir) = out {
+        println!("payloads written to {}", dir.display());
+    }
+    Ok(())
+}
+
+/// The bundles' images as PNGs.
+#[derive(Default)]
+struct ListingImages {
+    icon: Option<Vec<u8>>,
+    header: Option<Vec<u8>>,
+}
+
+/// The icon, from `--icon` or else downloaded from the metadata's image,
+/// and the header.
+fn listing_images(
+    config: &Config,
+    record: &LaunchRecord,
+    assets: &ListingAssets,
+) -> Result<ListingImages> {
+    let icon = match (&assets.icon, &record.image) {
+        (Some(path), _) => {
+            let logo = logo::process(path, LogoOptions::default())?;
+            for warning in &logo.warnings {
+                eprintln!("warning: icon: {warning}");
+            }
+            Some(logo.png)
+        }
+        (None, Some(image)) => {
+            let (bytes, _) = solanaapp::storage::fetch(image, config.ipfs_gateway())?;
+            let logo = logo::process_bytes(&bytes, image, LogoOptions::default())?;
+            for warning in &logo.warnings {
+                eprintln!("warning: icon: {warning}");
+            }
+            Some(logo.png)
+        }
+        (None, None) => {
+            eprintln!("warning: no icon: the metadata has no image and no --icon was given");
+            None
+        }
+    };
+    let header = match &assets.header {
+        Some(path) => {
+            let (png, warnings) = logo::process_header(path)?;
+            for warning in &warnings {
+                eprintln!("warning: header: {warning}");
+            }
+            Some(png)
+        }
+        None => None,
+    };
+    Ok(ListingImages { icon, header })
+}
+
+/// Writes `payload` and the images into `out/name`, or prints the payload.
+fn write_listing_bundle(
+    out: Option<&Path>,
+    name: &str,
+    payload: &serde_json::Value,
+    icon: Option<&[u8]>,
+    header: Option<&[u8]>,
+) -> Result<()> {
+    let json = serde_json::to_string_pretty(payload)?;
+    let Some(out) = out else {
+        println!("\n== {name} ==\n{json}");
+        return Ok(());
+    };
+    let dir = out.join(name);
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("token-info.json"), json)?;
+    if let Some(icon) = icon {
+        std::fs::write(dir.join(registry::ICON_FILE), icon)?;
+    }
+    if let Some(header) = header {
+        std::fs::write(dir.join(registry::HEADER_FILE), header)?;
+    }
+    Ok(())
+}
+
+fn cmd_allocate(supply: &str, decimals: u8, shares: &[String]) -> Result<()> {
+    if decimals > amount::MAX_DECIMALS {
+        return Err(Error::InvalidInput(format!(
+            "decimals must be at most {}",
+            amount::MAX_DECIMALS
+        )));
+    }
+    let supply = TokenAmount::parse(supply, decimals)?;
+    supply.to_u64()?;
+    let table = shares
+        .iter()
+        .map(|s| Allocation::parse(s))
+        .collect::<Result<Vec<_>>>()?;
+    let rows = amount::allocate(supply, &table)?;
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    for (label, part) in &rows {
+        let share = table
+            .iter()
+            .find(|a| &a.label == label)
+            .map(|a| amount::format_bps(a.share_bps))
+            .unwrap_or_default();
+        println!(
+            "{label:<width$}  {share:>7}  {part:>30}  {:>24}",
+            part.raw()
+        );
+    }
+    println!(
+        "{:<width$}  {:>7}  {supply:>30}  {:>24}",
+        "total",
+        "100%",
+        supply.raw()
+    );
+    Ok(())
+}
+
+/// A whole-unit quantity such as `1_000_000` or `0.5`.
+fn parse_units(input: &str) -> Result<f64> {
+    let amount = TokenAmount::parse(input, amount::MAX_DECIMALS)?;
+    Ok(amount.raw() as f64 / 10f64.powi(amount::MAX_DECIMALS as i32))
+}
+
+/// A quantity given directly, or as a percentage (`5%`) of `whole`.
+fn parse_share(input: &str, whole: f64) -> Result<f64> {
+    if input.trim().ends_with('%') {
+        let bps = amount::parse_percent_bps(input)?;
+        Ok(whole * bps as f64 / amount::BPS_DENOMINATOR as f64)
+    } else {
+        parse_units(input)
+    }
+}
+
+/// Four significant digits, without switching to exponent notation.
+fn significant(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return format!("{value}");
+    }
+    let decimals = (3 - value.abs().log10().floor() as i32).max(0) as usize;
+    format!("{value:.decimals$}")
+}
+
+fn cmd_impact(args: &ImpactArgs) -> Result<()> {
+    let sol_usd = args.sol_usd;
+    let pool = Pool::new(
+        parse_units(&args.tokens)?,
+        parse_units(&args.sol)?,
+        args.fee_bps,
+    )?;
+    let supply = parse_units(&args.supply)?;
+    if pool.token_reserve > supply {
+        return Err(Error::InvalidInput(
+            "the pool can't hold more tokens than the supply".into(),
+        ));
+    }
+    let sol_value = |v: f64| match sol_usd {
+        Some(usd) => format!("{} SOL (${})", significant(v), significant(v * usd)),
+        None => format!("{} SOL", significant(v)),
+    };
+    let price = pool.price();
+    println!(
+        "Pool: {} tokens / {} SOL, fee {}",
+        significant(pool.token_reserve),
+        significant(pool.quote_reserve),
+        amount::format_bps(args.fee_bps)
+    );
+    println!("Price: {} per token", sol_value(price));
+    println!("FDV:   {}", sol_value(impact::valuation(price, supply)));
+    println!("Market cap by circulating supply:");
+    for c in &args.circulating {
+        let tokens = parse_share(c, supply)?;
+        println!(
+            "  {:>16} tokens  {}",
+            significant(tokens),
+            sol_value(impact::valuation(price, tokens))
+        );
+    }
+
+    println!("Buys:");
+    println!(
+        "  {:>12}  {:>16}  {:>14}  {:>14}  {:>9}",
+        "SOL in", "tokens out", "avg price", "price after", "impact"
+    );
+    for buy in &args.buys {
+        print_trade(&pool.buy(parse_units(buy)?));
+    }
+    println!("Sells:");
+    println!(
+        "  {:>12}  {:>16}  {:>14}  {:>14}  {:>9}",
+        "tokens in", "SOL out", "avg price", "price after", "impact"
+    );
+    for sell in &args.sells {
+        print_trade(&pool.sell(parse_share(sell, pool.token_reserve)?));
+    }
+    Ok(())
+}
+
+fn print_trade(trade: &Trade) {
+    println!(
+        "  {:>12}  {:>16}  {:>14}  {:>14}  {:>8.2}%",
+        significant(trade.amount_in),
+        significant(trade.amount_out),
+        significant(trade.average_price),
+        significant(trade.price_after),
+        trade.price_impact * 100.0
+    );
+}
+
+fn read_plan(path: &std::path::Path) -> Result<Plan> {
+    let plan = Plan::parse(&std::fs::read_to_string(path)?).map_err(|e| match e {
+        Error::InvalidInput(message) => {
+            Error::InvalidInput(format!("{}: {message}", path.display()))
+        }
+        e => e,
+    })?;
+    plan.validate()?;
+    Ok(plan)
+}
+
+fn print_differences(differences: &[Difference]) {
+    if differences.is_empty() {
+        println!("chain matches the plan");
+        return;
+    }
+    println!("(~ fixable by `plan apply`, ! needs someone to decide)");
+    for difference in differences {
+        let marker = if difference.fix.is_some() { "~" } else { "!" };
+        println!("{marker} {}", difference.description);
+    }
+}
+
+/// The plan's mint: the one it names, else the one an earlier launch of
+/// the same parameters created, finishing that launch first if needed.
+fn plan_mint(
+    config: &Config,
+    rpc: &RpcClient,
+    events: Option<&EventLog>,
+    broadcaster: Option<&Broadcaster>,
+    plan: &Plan,
+    payer: &Keypair,
+    dry_run: bool,
+) -> Result<Option<Pubkey>> {
+    if let Some(mint) = plan.token.mint {
+        return Ok(Some(mint));
+    }
+    let mut params = plan.launch_params(rpc.programs())?;
+    let store = SessionStore::open_default()?;
+    let key = params.idempotency_key(config.network, &payer.pubkey());
+    let existing = store
+        .list()?
+        .into_iter()
+        .find(|s| s.idempotency_key() == key);
+    if let Some(session) = &existing
+        && session.is_complete()
+    {
+        return Ok(session.mint);
+    }
+    if dry_run {
+        match &existing {
+            Some(session) => println!("+ finish launch session {}", session.id),
+            None => println!("+ launch {} ({})", params.name, params.symbol),
+        }
+        return Ok(None);
+    }
+    let mut session = match existing {
+        Some(session) => {
+            eprintln!("resuming launch session {}", session.id);
+            session
+        }
+        None => {
+            if params.uri.is_empty() {
+                let uploader = Credentials::load()?.pinata(config)?;
+                params.uri = uploader.upload_json(
+                    &format!("{} metadata", params.name),
+                    &params.metadata_json(),
+                )?;
+                eprintln!("metadata uploaded to {}", params.uri);
+            }
+            let session = LaunchSession::new(
+                store.new_id(),
+                config.network,
+                payer.pubkey(),
+                params,
+                solanaapp::launch::now_unix(),
+            );
+            store.save(&session)?;
+            store.set_active(&session.id)?;
+            eprintln!("launch session {} started", session.id);
+            session
+        }
+    };
+    run_session(config, rpc, payer, None, events, broadcaster, &mut session)?;
+    if let Some(mint) = session.mint {
+        eprintln!("add `mint: {mint}` under `token:` in the plan to pin it");
+    }
+    Ok(session.mint)
+}
+
+fn cmd_plan(
+    config: &Config,
+    rpc: &RpcClient,
+    events: Option<&EventLog>,
+    broadcaster: Option<&Broadcaster>,
+    action: PlanAction,
+) -> Result<()> {
+    match action {
+        PlanAction::Validate { path } => {
+            let plan = read_plan(&path)?;
+            let params = plan.launch_params(rpc.programs())?;
+            let data = DataV2 {
+                name: params.name.clone(),
+                symbol: params.symbol.clone(),
+                uri: plan
+                    .metadata
+                    .uri
+                    .clone()
+                    .unwrap_or_else(|| "ipfs://pending".into()),
+                ..DataV2::default()
+            };
+            let json = plan.metadata.uri.is_none().then(|| params.metadata_json());
+            let mut findings = preview::validate(&data, json.as_ref());
+            findings.extend(name_findings(config, rpc, &data, None));
+            print_findings(&findings);
+            if preview::has_errors(&findings) {
+                return Err(Error::InvalidInput("plan metadata has problems".into()));
+            }
+            println!(
+                "{}: {} ({}), supply {}, {} allocation(s)",
+                path.display(),
+                plan.token.name,
+                plan.token.symbol,
+                plan.supply()?,
+                plan.allocations.len()
+            );
+        }
+        PlanAction::Diff { mint, path, json } => {
+            let plan = read_plan(&path)?;
+            // Fixes are judged against DEV_KEYPAIR when one is configured;
+            // without it every difference reads as needing someone else.
+            let authority = Credentials::load()
+                .and_then(|c| c.keypair())
+                .map(|k| k.pubkey())
+                .unwrap_or_default();
+            let mut differences = Vec::new();
+            if let Some(pinned) = plan.token.mint.filter(|pinned| *pinned != mint) {
+                differences.push(Difference {
+                    description: format!("plan pins mint {pinned}"),
+                    fix: None,
+                });
+            }
+            let state = ChainState::fetch(rpc, &mint, &plan)?;
+            differences.extend(plan::diff(&plan, &state, &authority)?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&differences)?);
+            } else {
+                println!("Mint {mint}");
+                print_differences(&differences);
+            }
+            if !differences.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "{mint} differs from {} in {} way(s)",
+                    path.display(),
+                    differences.len()
+                )));
+            }
+        }
+        PlanAction::Apply { path, dry_run, yes } => {
+            let plan = read_plan(&path)?;
+            check_plan_network(config, plan.network)?;
+            let payer = Credentials::load()?.keypair()?;
+            let mode = ApplyMode { dry_run, yes };
+            apply_plan(config, rpc, events, broadcaster, &plan, &payer, mode)?;
+        }
+        PlanAction::ApplyManifest { path, dry_run, yes } => {
+            let manifest = Manifest::parse(&std::fs::read_to_string(&path)?)
+                .and_then(|m| m.validate().map(|()| m))
+                .map_err(|e| match e {
+                    Error::InvalidInput(message) => {
+                        Error::InvalidInput(format!("{}: {message}", path.display()))
+                    }
+                    e => e,
+                })?;
+            check_plan_network(config, manifest.network)?;
+            let payer = Credentials::load()?.keypair()?;
+            let mode = ApplyMode { dry_run, yes };
+            let mut launched: Vec<(String, &TokenSpec, Option<Pubkey>)> = Vec::new();
+            let plans = manifest.plans();
+            let mut outcome = Ok(());
+            for (label, plan) in &plans {
+                println!("== {label}: {} ({})", plan.token.name, plan.token.symbol);
+                match apply_plan(config, rpc, events, broadcaster, plan, &payer, mode) {
+                    Ok(mint) => launched.push((label.clone(), &plan.token, mint)),
+                    Err(e) => {
+                        outcome = Err(e);
+                        break;
+                    }
+                }
+            }
+            println!();
+            println!(
+                "{}: {} of {} token(s) applied on {}, paid by {}",
+                path.display(),
+                launched.len(),
+                plans.len(),
+                config.network,
+                payer.pubkey()
+            );
+            for (label, token, mint) in &launched {
+                let mint = mint.map_or("(not launched yet)".to_string(), |m| m.to_string());
+                println!("  {label:<12} {:<10} {mint}  {}", token.symbol, token.name);
+            }
+            for (label, _) in plans.iter().skip(launched.len()) {
+                println!("  {label:<12} not reached");
+            }
+            outcome?;
+        }
+    }
+    Ok(())
+}
+
+fn check_plan_network(config: &Config, network: Option<Network>) -> Result<()> {
+    match network {
+        Some(network) if network != config.network => Err(Error::InvalidInput(format!(
+            "plan is for {network}, but {} is selected",
+            config.network
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ApplyMode {
+    /// Show the differences without sending anything.
+    dry_run: bool,
+    /// Send fixes without asking.
+    yes: bool,
+}
+
+/// Launches the plan's token if it doesn't exist yet and sends the fixes
+/// it is missing. Returns the mint, or `None` for a dry run that would
+/// launch it.
+fn apply_plan(
+    config: &Config,
+    rpc: &RpcClient,
+    events: Option<&EventLog>,
+    broadcaster: Option<&Broadcaster>,
+    plan: &Plan,
+    payer: &Keypair,
+    mode: ApplyMode,
+) -> Result<Option<Pubkey>> {
+    let authority = payer.pubkey();
+    let Some(mint) = plan_mint(config, rpc, events, broadcaster, plan, payer, mode.dry_run)? else {
+        return Ok(None);
+    };
+    let state = ChainState::fetch(rpc, &mint, plan)?;
+    let differences = plan::diff(plan, &state, &authority)?;
+    println!("Mint {mint}");
+    print_differences(&differences);
+    if differences.iter().any(|d| d.fix.is_none()) {
+        return Err(Error::InvalidInput(
+            "differences marked ! can't be fixed by a transaction; \
+             update the plan or the token by hand first"
+                .into(),
+        ));
+    }
+    if differences.is_empty() || mode.dry_run {
+        return Ok(Some(mint));
+    }
+    if !mode.yes && !confirm(&format!("Send {} fix(es)?", differences.len()))? {
+        return Err(Error::InvalidInput("aborted".into()));
+    }
+    let send_fixes = || {
+        for fix in differences.iter().filter_map(|d| d.fix.as_ref()) {
+            let ixs = fix.instructions(&state, &authority)?;
+            let signature = if fix.is_critical() {
+                send_signed_critical(rpc, &ixs, payer)?
+            } else {
+                send_signed(rpc, &ixs, payer)?
+            };
+            println!("{fix}: {signature}");
+        }
+        Ok(())
+    };
+    let updates_metadata = differences
+        .iter()
+        .any(|d| matches!(d.fix, Some(plan::Fix::UpdateMetadata { .. })));
+    if updates_metadata {
+        with_metadata_backup(config, rpc, &mint, send_fixes)?;
+    } else {
+        send_fixes()?;
+    }
+    Ok(Some(mint))
+}
+
+fn cmd_test_buy(
+    config: &Config,
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    sol: &str,
+    slippage_bps: u16,
+) -> Result<()> {
+    let lamports = TokenAmount::parse(sol, 9)?.to_u64()?;
+    if lamports == 0 {
+        return Err(Error::InvalidInput("--sol must be more than zero".into()));
+    }
+    let buyer = Credentials::load()?.keypair()?;
+    eprintln!(
+        "buying {mint} for {} SOL through {}...",
+        system::format_sol(lamports),
+        config.swap_api_url()
+    );
+    let proof = swap::test_buy(
+        rpc,
+        config.swap_api_url(),
+        &buyer,
+        mint,
+        lamports,
+        slippage_bps,
+    )?;
+    let decimals = token::fetch_mint(rpc, mint)?.mint.decimals;
+    let show = |raw: u64| TokenAmount::from_raw(raw as u128, decimals);
+    println!("Bought {} {mint}", show(proof.received));
+    println!(
+        "Quoted {}, minimum {} at {} bps slippage, price impact {}%",
+        show(proof.quoted_out),
+        show(proof.minimum_out),
+        proof.slippage_bps,
+        proof.price_impact_pct
+    );
+    if !proof.route.is_empty() {
+        println!("Route: {}", proof.route.join(" -> "));
+    }
+    println!("Signature: {}", proof.signature);
+    let store = SessionStore::open_default()?;
+    if let Some(mut session) = store
+        .list()?
+        .into_iter()
+        .find(|s| s.mint == Some(*mint) && s.network == config.network)
+    {
+        session.test_buy = Some(proof);
+        store.save(&session)?;
+        eprintln!("proof saved to launch session {}", session.id);
+    }
+    Ok(())
+}
+
+fn cmd_collection(config: &Config, rpc: &RpcClient, action: CollectionAction) -> Result<()> {
+    let program = rpc.programs().metadata;
+    let fetch = |mint: &Pubkey| {
+        metadata::fetch_metadata(rpc, mint)?
+            .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))
+    };
+    match action {
+        CollectionAction::Show { mints } => {
+            let mut groups: Vec<(Pubkey, Vec<String>)> = Vec::new();
+            for mint in &mints {
+                let current = fetch(mint)?;
+                let symbol = &current.data.symbol;
+                let (key, status) = match Membership::of(&current) {
+                    Membership::None => {
+                        println!("{mint} {symbol}: in no collection");
+                        continue;
+                    }
+                    Membership::Verified(key) => (key, "verified"),
+                    Membership::Unverified(key) => (key, "unverified"),
+                };
+                println!("{mint} {symbol}: {status} member of {key}");
+                let entry = format!("{symbol} {mint} ({status})");
+                match groups.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, members)) => members.push(entry),
+                    None => groups.push((key, vec![entry])),
+                }
+            }
+            for (key, members) in groups {
+                let parent = match Parent::fetch(rpc, &key) {
+                    Ok(parent) => format!(
+                        "{} ({}), verified by {}",
+                        parent.metadata.data.name,
+                        parent.mint,
+                        parent.update_authority()
+                    ),
+                    Err(e) => format!("{key}: {e}"),
+                };
+                println!();
+                println!("collection {parent}");
+                for member in members {
+                    println!("  {member}");
+                }
+            }
+        }
+        CollectionAction::Set { collection, mints } => {
+            let parent = Parent::fetch(rpc, &collection)?;
+            let signer = Credentials::load()?.signer(rpc.programs())?;
+            let authority = signer.pubkey();
+            for mint in &mints {
+                let current = fetch(mint)?;
+                let ixs =
+                    collection::set_instructions(&program, mint, &current, &parent, &authority)?;
+                if ixs.is_empty() {
+                    println!("{mint}: already a verified member");
+                    continue;
+                }
+                let signature =
+                    with_metadata_backup(config, rpc, mint, || send_signed(rpc, &ixs, &signer))?;
+                println!("{mint}: {signature}");
+            }
+            if parent.update_authority() != authority {
+                eprintln!(
+                    "the tokens name the collection but are unverified; its update authority {} \
+                     must run `collection verify --collection {collection}` on them",
+                    parent.update_authority()
+                );
+            }
+        }
+        CollectionAction::Verify { collection, mints } => {
+            let parent = Parent::fetch(rpc, &collection)?;
+            let signer = Credentials::load()?.signer(rpc.programs())?;
+            for mint in &mints {
+                let current = fetch(mint)?;
+                let ix = collection::verify_instruction(
+                    &program,
+                    mint,
+                    &current,
+                    &parent,
+                    &signer.pubkey(),
+                )?;
+                let signature = send_signed(rpc, &[ix], &signer)?;
+                println!("{mint}: {signature}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_run_script(
+    config: &Config,
+    rpc: &RpcClient,
+    path: &std::path::Path,
+    mint: &Pubkey,
+    dry_run: bool,
+    max_supply: Option<&str>,
+) -> Result<()> {
+    let source = std::fs::read_to_string(path)?;
+    let script = Script::parse(&source)?;
+    let signer = Credentials::load()?.signer(rpc.programs())?;
+    let run = || {
+        script::run(
+            rpc,
+            &script,
+            mint,
+            &signer,
+            dry_run,
+            max_supply,
+            |outcome| match outcome {
+                Outcome::Skipped { line } => eprintln!("line {line}: condition false, skipped"),
+                Outcome::Printed { text, .. } => println!("{text}"),
+                Outcome::Sent {
+                    line,
+                    action,
+                    signature,
+                } => println!("line {line}: {action}: {signature}"),
+                Outcome::Planned { line, action } => println!("line {line}: would {action}"),
+                Outcome::Stopped { line } => eprintln!("line {line}: stopped"),
+            },
+        )
+    };
+    if dry_run || !script.changes_metadata() {
+        return run();
+    }
+    with_metadata_backup(config, rpc, mint, run)
+}
+
+/// Runs `change`, which alters `mint`'s metadata, keeping the version it
+/// replaces in the launch record for `metadata rollback`. Failing to keep
+/// it is only a warning; the change itself already happened.
+fn with_metadata_backup<T>(
+    config: &Config,
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    change: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    with_metadata_backups(config, rpc, std::slice::from_ref(mint), change)
+}
+
+/// [`with_metadata_backup`] for a change to several tokens at once.
+fn with_metadata_backups<T>(
+    config: &Config,
+    rpc: &RpcClient,
+    mints: &[Pubkey],
+    change: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    if mints.is_empty() {
+        return change();
+    }
+    let store = LaunchStore::open_default()?;
+    let (result, kept) = history::backed_up(&store, config, rpc, mints, change)?;
+    match kept {
+        Ok(kept) => {
+            for mint in kept {
+                eprintln!("previous metadata kept; `metadata rollback {mint}` restores it");
+            }
+        }
+        Err(e) => eprintln!("warning: previous metadata not kept for rollback: {e}"),
+    }
+    result
+}
+
+/// The tokens whose metadata accounts are among `keys`, e.g. those a
+/// transaction writes.
+fn metadata_mints(rpc: &RpcClient, keys: &[Pubkey]) -> Result<Vec<Pubkey>> {
+    let program = rpc.programs().metadata;
+    let accounts = rpc.get_multiple_accounts(keys, None)?.value;
+    Ok(accounts
+        .into_iter()
+        .flatten()
+        .filter(|account| account.owner == program)
+        .filter_map(|account| metadata::Metadata::unpack(&account.data).ok())
+        .map(|metadata| metadata.mint)
+        .collect())
+}
+
+fn print_metadata_version(index: usize, version: &MetadataVersion) {
+    println!(
+        "{index:>3}  replaced {}  {} ({})  {}{}",
+        time::format_rfc3339(version.replaced_at),
+        version.data.name,
+        version.data.symbol,
+        version.data.uri,
+        if version.json.is_some() {
+            ""
+        } else {
+            "  [no JSON saved]"
+        }
+    );
+}
+
+fn cmd_metadata(config: &Config, rpc: &RpcClient, action: MetadataAction) -> Result<()> {
+    let store = LaunchStore::open_default()?;
+    match action {
+        MetadataAction::History { mint } => {
+            let history = store
+                .load(&mint)?
+                .map(|r| r.metadata_history)
+                .unwrap_or_default();
+            if history.is_empty() {
+                println!("no earlier metadata versions kept for {mint}");
+            }
+            for (i, version) in history.iter().enumerate() {
+                print_metadata_version(i + 1, version);
+            }
+        }
+        MetadataAction::Rollback {
+            mint,
+            reupload,
+            yes,
+        } => {
+            let none_kept =
+                || Error::InvalidInput(format!("no earlier metadata versions kept for {mint}"));
+            let mut record = store.load(&mint)?.ok_or_else(none_kept)?;
+            let Some(version) = record.metadata_history.last().cloned() else {
+                return Err(none_kept());
+            };
+            let uri = match (&version.json, reupload) {
+                (Some(json), true) => {
+                    let uploader = Credentials::load()?.pinata(config)?;
+                    let uri =
+                        uploader.upload_json(&format!("{} metadata", version.data.name), json)?;
+                    eprintln!("saved JSON pinned again at {uri}");
+                    Some(uri)
+                }
+                (None, true) => {
+                    return Err(Error::InvalidInput(
+                        "no JSON was saved with that version; roll back without --reupload".into(),
+                    ));
+                }
+                (_, false) => {
+                    if version.json.is_some()
+                        && solanaapp::storage::fetch_json(&version.data.uri, config.ipfs_gateway())
+                            .is_err()
+                    {
+                        eprintln!(
+                            "warning: {} no longer serves the saved JSON; use --reupload to pin it again",
+                            version.data.uri
+                        );
+                    }
+                    None
+                }
+            };
+            println!("Restoring:");
+            print_metadata_version(record.metadata_history.len(), &version);
+            if !yes && !confirm("Send the update?")? {
+                return Err(Error::InvalidInput("aborted".into()));
+            }
+            let authority = Credentials::load()?.signer(rpc.programs())?;
+            let signature = history::restore(rpc, &*authority, &mint, &version, uri.as_deref())?;
+            record.metadata_history.pop();
+            store.save(&record)?;
+            println!("Signature: {signature}");
+            println!("{} earlier version(s) left", record.metadata_history.len());
+        }
+    }
+    Ok(())
+}
+
+fn cmd_update(config: &Config, rpc: &RpcClient, action: UpdateAction) -> Result<()> {
+    let UpdateAction::Socials {
+        mint,
+        website,
+        twitter,
+        telegram,
+        discord,
+        remove,
+        yes,
+    } = action;
+    let current = metadata::fetch_metadata(rpc, &mint)?
+        .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?;
+    let json = solanaapp::storage::fetch_json(&current.data.uri, config.ipfs_gateway())?;
+    let before = Links::from_json(&json);
+    let mut links = before.clone();
+    for (key, url) in [
+        ("website", website),
+        ("twitter", twitter),
+        ("telegram", telegram),
+        ("discord", discord),
+    ] {
+        if let Some(url) = url {
+            *links.get_mut(key)? = Some(url);
+        }
+    }
+    for key in &remove {
+        *links.get_mut(key)? = None;
+    }
+    links.validate()?;
+    if links == before {
+        return Err(Error::InvalidInput(
+            "nothing to change; pass a link to set or --remove one".into(),
+        ));
+    }
+    for ((key, old), (_, new)) in before.entries().into_iter().zip(links.entries()) {
+        if old != new {
+            println!(
+                "  {key:<9} {} -> {}",
+                old.unwrap_or("(none)"),
+                new.unwrap_or("(none)")
+            );
+        }
+    }
+    if !yes && !confirm("Upload the new JSON and point the metadata at it?")? {
+        return Err(Error::InvalidInput("aborted".into()));
+    }
+    let credentials = Credentials::load()?;
+    let authority = credentials.keypair()?;
+    let uploader = credentials.pinata(config)?;
+    let (uri, signature) = with_metadata_backup(config, rpc, &mint, || {
+        rehost::rewrite(
+            rpc,
+            &uploader,
+            &authority,
+            &mint,
+            config.ipfs_gateway(),
+            |json| links.write_json(json),
+        )
+    })?;
+    let store = LaunchStore::open_default()?;
+    if let Some(mut record) = store.load(&mint)? {
+        record.links = links;
+        store.save(&record)?;
+    }
+    println!("New URI:   {uri}");
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn cmd_plugins(action: PluginAction) -> Result<()> {
+    let dir = plugin::plugin_dir()?;
+    match action {
+        PluginAction::List => {
+            let plugins = plugin::discover(&dir)?;
+            println!("Plugin directory: {}", dir.display());
+            if plugins.is_empty() {
+                println!("no plugins; add executables there to run them after launch steps");
+            }
+            for path in &plugins {
+                println!("  {}", path.display());
+            }
+        }
+        PluginAction::Run { hook, session } => {
+            let hook = Hook::parse(&hook)?;
+            let session = session_or_active(&SessionStore::open_default()?, session)?;
+            if !fire_plugins(hook, &session, None) {
+                return Err(Error::InvalidInput(format!(
+                    "a plugin failed at {}",
+                    hook.as_str()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the plugins for `hook`, reporting each; returns whether all succeeded.
+fn fire_plugins(hook: Hook, session: &LaunchSession, events: Option<&EventLog>) -> bool {
+    let name = |plugin: &Path| {
+        plugin.file_name().map_or_else(
+            || plugin.display().to_string(),
+            |n| n.to_string_lossy().into(),
+        )
+    };
+    let on_line = |plugin: &Path, line: &str| {
+        let plugin = name(plugin);
+        eprintln!("[{plugin}] {line}");
+        if let Some(events) = events {
+            let line = line.to_string();
+            events.emit(&session.id, Event::PluginOutput { hook, plugin, line });
+        }
+    };
+    let runs = match plugin::plugin_dir().and_then(|dir| {
+        let timeout = TIMEOUTS.get().plugin();
+        plugin::run_all(&dir, hook, session.network, session, timeout, &on_line)
+    }) {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!("warning: plugins not run at {}: {e}", hook.as_str());
+            return false;
+        }
+    };
+    let mut ok = true;
+    for run in &runs {
+        let name = name(&run.plugin);
+        if run.succeeded() {
+            eprintln!("plugin {name} ran at {}", hook.as_str());
+        } else {
+            ok = false;
+            let reason = match (&run.error, run.status) {
+                (Some(e), _) => e.clone(),
+                (None, Some(code)) => format!("exit code {code}"),
+                (None, None) => "killed by a signal".into(),
+            };
+            eprintln!(
+                "warning: plugin {name} failed at {}: {reason}",
+                hook.as_str()
+            );
+        }
+    }
+    ok
+}
+
+fn read_vouchers(path: &std::path::Path) -> Result<VoucherSet> {
+    let raw = std::fs::read_to_string(path)?;
+    let set: VoucherSet = serde_json::from_str(&raw)
+        .map_err(|e| Error::InvalidInput(format!("{}: {e}", path.display())))?;
+    set.verify()?;
+    Ok(set)
+}
+
+fn cmd_claim(rpc: &RpcClient, action: ClaimAction) -> Result<()> {
+    match action {
+        ClaimAction::Vouchers {
+            mint,
+            whitelist,
+            out,
+        } => {
+            let authority = Credentials::load()?.keypair()?;
+            let decimals = token::fetch_mint(rpc, &mint)?.mint.decimals;
+            let entries = claim::parse_whitelist(&std::fs::read_to_string(&whitelist)?, decimals)?;
+            if entries.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "{} lists no wallets",
+                    whitelist.display()
+                )));
+            }
+            let set = VoucherSet::issue(&authority, mint, decimals, &entries);
+            std::fs::write(&out, serde_json::to_string_pretty(&set)?)?;
+            let total: u128 = entries.iter().map(|(_, cap)| cap.raw()).sum();
+            println!(
+                "Signed {} vouchers for {} {mint} in total, written to {}",
+                set.vouchers.len(),
+                TokenAmount::from_raw(total, decimals),
+                out.display()
+            );
+        }
+        ClaimAction::Serve {
+            vouchers,
+            ledger,
+            listen,
+            qr,
+        } => {
+            let operator = Credentials::load()?.signer(rpc.programs())?;
+            let set = read_vouchers(&vouchers)?;
+            let mint = set.mint;
+            let mut server = ClaimServer::new(rpc, &operator, set, ledger)?;
+            let listener = std::net::TcpListener::bind(&listen)?;
+            eprintln!(
+                "serving claims of {mint} on http://{listen}, paying from {}",
+                operator.pubkey()
+            );
+            if qr {
+                eprint!(
+                    "{}",
+                    QrCode::encode(&format!("http://{listen}"))?.to_terminal()
+                );
+            }
+            server.serve(listener)?;
+        }
+        ClaimAction::Status { vouchers, ledger } => {
+            let set = read_vouchers(&vouchers)?;
+            let ledger = ClaimLedger::load_or_new(&ledger, set.mint)?;
+            println!("{:<44}  {:>24}  {:>24}", "WALLET", "CAP", "CLAIMED");
+            let mut claimed_total = 0;
+            for v in &set.vouchers {
+                let claimed = ledger.claimed(&v.wallet, set.decimals);
+                claimed_total += claimed.raw();
+                println!("{:<44}  {:>24}  {:>24}", v.wallet, v.cap, claimed);
+            }
+            println!(
+                "Claimed {} by {} of {} wallets",
+                TokenAmount::from_raw(claimed_total, set.decimals),
+                set.vouchers
+                    .iter()
+                    .filter(|v| !ledger.claimed(&v.wallet, set.decimals).is_zero())
+                    .count(),
+                set.vouchers.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn read_participants(path: &std::path::Path) -> Result<Vec<Pubkey>> {
+    let participants = draw::parse_participants(&std::fs::read_to_string(path)?)?;
+    if participants.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "{} lists no participants",
+            path.display()
+        )));
+    }
+    Ok(participants)
+}
+
+/// First produced block at or after `slot`; a slot can be skipped by its leader.
+fn seed_block(rpc: &RpcClient, slot: u64) -> Result<(u64, String)> {
+    let block_slot = rpc
+        .get_blocks(slot, slot + 100)?
+        .first()
+        .copied()
+        .ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "slot {slot} is not finalized yet (current slot {})",
+                rpc.get_slot().map_or("unknown".into(), |s| s.to_string())
+            ))
+        })?;
+    Ok((block_slot, rpc.get_block_hash(block_slot)?))
+}
+
+fn cmd_draw(rpc: &RpcClient, action: DrawAction) -> Result<()> {
+    match action {
+        DrawAction::Announce {
+            participants,
+            delay_slots,
+        } => {
+            let participants = read_participants(&participants)?;
+            let seed_slot = rpc.get_slot()? + delay_slots;
+            println!("Participants: {}", participants.len());
+            println!("Digest:       {}", draw::participants_digest(&participants));
+            println!("Seed slot:    {seed_slot}");
+            eprintln!(
+                "publish these before slot {seed_slot}, then run `draw run --seed-slot {seed_slot}`"
+            );
+        }
+        DrawAction::Run {
+            participants,
+            seed_slot,
+            winners,
+            total,
+            decimals,
+            out,
+            csv,
+        } => {
+            let participants = read_participants(&participants)?;
+            let total = TokenAmount::parse(&total, decimals)?;
+            let (block_slot, blockhash) = seed_block(rpc, seed_slot)?;
+            let record = DrawRecord::run(
+                &participants,
+                seed_slot,
+                block_slot,
+                blockhash,
+                winners,
+                total,
+            )?;
+            std::fs::write(&out, serde_json::to_string_pretty(&record)?)?;
+            if let Some(path) = csv {
+                record.write_csv(BufWriter::new(File::create(path)?))?;
+            }
+            println!(
+                "Drew {} of {} participants with the hash of block {} ({})",
+                record.winners.len(),
+                record.participants,
+                record.block_slot,
+                record.blockhash
+            );
+            for a in &record.winners {
+                println!("  {:<44}  {:>24}", a.wallet, a.amount);
+            }
+            eprintln!("record written to {}", out.display());
+        }
+        DrawAction::Verify {
+            record,
+            participants,
+        } => {
+            let raw = std::fs::read_to_string(&record)?;
+            let record: DrawRecord = serde_json::from_str(&raw)
+                .map_err(|e| Error::InvalidInput(format!("{}: {e}", record.display())))?;
+            let participants = read_participants(&participants)?;
+            let mut problems = record.verify(&participants)?;
+            let (block_slot, blockhash) = seed_block(rpc, record.seed_slot)?;
+            if (block_slot, blockhash.as_str()) != (record.block_slot, record.blockhash.as_str()) {
+                problems.push(format!(
+                    "seed block is {block_slot} ({blockhash}), record says {} ({})",
+                    record.block_slot, record.blockhash
+                ));
+            }
+            if problems.is_empty() {
+                println!(
+                    "Draw verified: {} winners reproduced from block {}",
+                    record.winners.len(),
+                    record.block_slot
+                );
+            } else {
+                for problem in &problems {
+                    println!("MISMATCH: {problem}");
+                }
+                return Err(Error::InvalidInput("the draw does not reproduce".into()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads `LANG=TEXT`.
+fn parse_localized(input: &str) -> Result<(String, String)> {
+    let (language, text) = input
+        .split_once('=')
+        .ok_or_else(|| Error::InvalidInput(format!("'{input}' is not LANG=TEXT")))?;
+    if !launch::is_language_tag(language) {
+        return Err(Error::InvalidInput(format!(
+            "'{language}' is not a language tag such as es or pt-BR"
+        )));
+    }
+    Ok((language.to_string(), text.to_string()))
+}
+
+fn listing(
+    names: Vec<(String, String)>,
+    descriptions: Vec<(String, String)>,
+    tags: Vec<String>,
+    category: Option<String>,
+) -> Listing {
+    let mut localized: BTreeMap<String, Localized> = BTreeMap::new();
+    for (language, name) in names {
+        localized.entry(language).or_default().name = Some(name);
+    }
+    for (language, description) in descriptions {
+        localized.entry(language).or_default().description = Some(description);
+    }
+    Listing {
+        localized,
+        tags,
+        category,
+    }
+}
+
+/// Launch options not given on the command line, taken from an earlier
+/// session by `launch --defaults`.
+struct LaunchDefaults {
+    session: Option<LaunchSession>,
+    taken: Vec<(&'static str, String)>,
+}
+
+impl LaunchDefaults {
+    /// Session `id` of `network`, or its latest session for `last`.
+    fn load(network: Network, id: Option<&str>) -> Result<Self> {
+        let session = match id {
+            None => None,
+            Some("last") => Some(
+                SessionStore::open_default()?
+                    .list()?
+                    .into_iter()
+                    .find(|s| s.network == network)
+                    .ok_or_else(|| {
+                        Error::InvalidInput(format!("there is no earlier launch on {network}"))
+                    })?,
+            ),
+            Some(id) => {
+                let session = SessionStore::open_default()?.load(id)?;
+                if session.network != network {
+                    return Err(Error::InvalidInput(format!(
+                        "session {id} launched on {}, not {network}",
+                        session.network
+                    )));
+                }
+                Some(session)
+            }
+        };
+        Ok(LaunchDefaults {
+            session,
+            taken: Vec::new(),
+        })
+    }
+
+    /// `given`, or else what `earlier` reads from the session, noted under
+    /// `flag`.
+    fn pick<T: std::fmt::Display>(
+        &mut self,
+        flag: &'static str,
+        given: Option<T>,
+        earlier: impl FnOnce(&LaunchParams) -> Option<T>,
+    ) -> Option<T> {
+        if given.is_some() {
+            return given;
+        }
+        let value = earlier(&self.session.as_ref()?.params)?;
+        self.taken.push((flag, value.to_string()));
+        Some(value)
+    }
+
+    fn print(&self) {
+        if let Some(session) = &self.session
+            && !self.taken.is_empty()
+        {
+            eprintln!("defaults from session {}:", session.id);
+            for (flag, value) in &self.taken {
+                eprintln!("  {flag} [{value}]");
+            }
+        }
+    }
+}
+
+fn cmd_launch(
+    config: &Config,
+    rpc: &RpcClient,
+    events: Option<&EventLog>,
+    broadcaster: Option<&Broadcaster>,
+    args: LaunchArgs,
+) -> Result<()> {
+    let mut defaults = LaunchDefaults::load(config.network, args.defaults.as_deref())?;
+    let decimals = defaults
+        .pick("--decimals", args.decimals, |p| Some(p.supply.decimals()))
+        .unwrap_or(9);
+    if decimals > amount::MAX_DECIMALS {
+        return Err(Error::InvalidInput(format!(
+            "decimals must be at most {}",
+            amount::MAX_DECIMALS
+        )));
+    }
+    if !config.expert {
+        let used = [
+            ("--supply-owner", args.supply_owner.is_some()),
+            ("--supply-account", args.supply_account.is_some()),
+            ("--compute-unit-limit", args.compute_unit_limit.is_some()),
+            ("--compute-unit-price", args.compute_unit_price.is_some()),
+        ];
+        if let Some((flag, _)) = used.iter().find(|(_, set)| *set) {
+            return Err(Error::InvalidInput(format!(
+                "{flag} is an expert option; pass --expert or set \"expert\": true in {}",
+                Config::path()?.display()
+            )));
+        }
+    }
+    let programs = rpc.programs();
+    let token_2022 = defaults.pick("--token-2022", args.token_2022, |p| {
+        Some(p.token_program == programs.token_2022)
+    });
+    let freeze_authority = defaults.pick("--freeze-authority", args.freeze_authority, |p| {
+        Some(p.freeze_authority)
+    });
+    let revoke_mint = defaults.pick("--revoke-mint", args.revoke_mint, |p| {
+        Some(p.revoke_mint_authority)
+    });
+    let immutable = defaults.pick("--immutable", args.immutable, |p| Some(!p.is_mutable));
+    let links = Links {
+        website: defaults.pick("--website", args.website, |p| p.links.website.clone()),
+        twitter: defaults.pick("--twitter", args.twitter, |p| p.links.twitter.clone()),
+        telegram: defaults.pick("--telegram", args.telegram, |p| p.links.telegram.clone()),
+        discord: defaults.pick("--discord", args.discord, |p| p.links.discord.clone()),
+    };
+    // Expert options are only carried over for expert users.
+    let (compute_unit_limit, compute_unit_price) = if config.expert {
+        (
+            defaults.pick("--compute-unit-limit", args.compute_unit_limit, |p| {
+                p.compute_unit_limit
+            }),
+            defaults.pick("--compute-unit-price", args.compute_unit_price, |p| {
+                p.compute_unit_price
+            }),
+        )
+    } else {
+        (args.compute_unit_limit, args.compute_unit_price)
+    };
+    defaults.print();
+    let supply = TokenAmount::parse(&args.supply, decimals)?;
+    for advice in amount::check_decimals(supply)? {
+        eprintln!("note: {advice}");
+    }
+    let vanity = if args.vanity_prefix.is_empty() && args.vanity_suffix.is_empty() {
+        None
+    } else {
+        Some(vanity_search(
+            &args.vanity_prefix,
+            &args.vanity_suffix,
+            args.vanity_ignore_case,
+        )?)
+    };
+    let credentials = Credentials::load()?;
+    let derived_mint = args
+        .mint_index
+        .map(|index| phrase::mint_keypair(credentials.mint_passphrase()?, index))
+        .transpose()?;
+    if let (Some(mint), Some(index)) = (&derived_mint, args.mint_index)
+        && rpc.get_account(&mint.pubkey())?.value.is_some()
+    {
+        return Err(Error::InvalidInput(format!(
+            "mint {} (passphrase index {index}) already exists on {}; pick another --mint-index",
+            mint.pubkey(),
+            config.network
+        )));
+    }
+    let payer = match args.handoff {
+        Some(_) => None,
+        None => Some(credentials.keypair()?),
+    };
+    let mut params = LaunchParams {
+        name: args.name,
+        symbol: args.symbol,
+        uri: args.uri.unwrap_or_default(),
+        supply,
+        token_program: programs.token_program(token_2022.unwrap_or(false)),
+        freeze_authority: freeze_authority.unwrap_or(false),
+        revoke_mint_authority: revoke_mint.unwrap_or(false),
+        is_mutable: !immutable.unwrap_or(false),
+        description: args.description,
+        image: args.image,
+        links,
+        listing: listing(
+            args.localized_names,
+            args.localized_descriptions,
+            args.tags,
+            args.category,
+        ),
+        transfer_hook: args.transfer_hook,
+        group: match (args.group_max_size, args.member_of) {
+            (Some(max_size), _) => Some(GroupRole::Group { max_size }),
+            (None, Some(group)) => Some(GroupRole::Member { group }),
+            (None, None) => None,
+        },
+        memo: args.memo,
+        supply_account: None,
+        compute_unit_limit,
+        compute_unit_price,
+        metadata_program: programs.metadata_override(),
+    };
+    if let Some(text) = &params.memo {
+        memo::validate(text)?;
+    }
+    params.compute_budget_instructions()?;
+
+    let json = params.metadata_json();
+    let data = DataV2 {
+        name: params.name.clone(),
+        symbol: params.symbol.clone(),
+        uri: if params.uri.is_empty() {
+            "ipfs://pending".into()
+        } else {
+            params.uri.clone()
+        },
+        ..DataV2::default()
+    };
+    let mut findings = preview::validate(&data, Some(&json));
+    findings.extend(name_findings(config, rpc, &data, None));
+    print_findings(&findings);
+    if preview::has_errors(&findings) {
+        return Err(Error::InvalidInput(
+            "metadata has problems that must be fixed before launching".into(),
+        ));
+    }
+    if let Some(hook) = &params.transfer_hook {
+        check_hook_program(rpc, hook)?;
+    }
+    if let Some(GroupRole::Member { group }) = &params.group {
+        let authority = match &payer {
+            Some(payer) => payer.pubkey(),
+            None => args.handoff.unwrap_or_default(),
+        };
+        check_group_open(rpc, group, &authority)?;
+    }
+    let supply_signer = args
+        .supply_account
+        .as_deref()
+        .map(Keypair::read_file)
+        .transpose()?;
+    params.supply_account = match (&supply_signer, args.supply_owner) {
+        (Some(keypair), owner) => Some(SupplyAccount::Auxiliary {
+            address: keypair.pubkey(),
+            owner: owner
+                .or(payer.as_ref().map(Keypair::pubkey))
+                .unwrap_or_default(),
+        }),
+        (None, Some(owner)) => Some(SupplyAccount::Associated { owner }),
+        (None, None) => None,
+    };
+    let store = SessionStore::open_default()?;
+    if let Some(payer) = &payer
+        && !args.allow_duplicate
+    {
+        let key = params.idempotency_key(config.network, &payer.pubkey());
+        let duplicates = store.duplicates(&key, solanaapp::launch::now_unix())?;
+        if let Some(existing) = duplicates.first() {
+            return Err(Error::InvalidInput(
+                match existing.mint.filter(|_| existing.is_complete()) {
+                    Some(mint) => format!(
+                        "an identical launch (session {}) already created {mint} at {}; \
+                     pass --allow-duplicate to launch another",
+                        existing.id,
+                        time::format_rfc3339(existing.updated_at)
+                    ),
+                    None => format!(
+                        "an identical launch (session {}) is unfinished; run `session resume {}` \
+                     instead of starting over, or pass --allow-duplicate",
+                        existing.id, existing.id
+                    ),
+                },
+            ));
+        }
+    }
+    if params.uri.is_empty() {
+        let uploader = credentials.pinata(config)?;
+        params.uri = uploader.upload_json(&format!("{} metadata", params.name), &json)?;
+        eprintln!("metadata uploaded to {}", params.uri);
+    }
+
+    let Some(payer) = payer else {
+        let wallet = args.handoff.unwrap_or_default();
+        return print_handoff(rpc, &params, &wallet, derived_mint);
+    };
+    let mut session = LaunchSession::new(
+        store.new_id(),
+        config.network,
+        payer.pubkey(),
+        params,
+        solanaapp::launch::now_unix(),
+    );
+    session.mint_index = args.mint_index;
+    if let Some(search) = &vanity {
+        let seed = SolanaTokenManager::new(rpc, &payer).mint_seed(&session);
+        let found = grind_vanity(search, &seed, args.vanity_threads);
+        eprintln!(
+            "mint address {} ({}) after {} keys in {}",
+            found.address,
+            search.patterns()[found.pattern],
+            found.progress.attempts,
+            format_seconds(found.progress.elapsed.as_secs_f64())
+        );
+        session.mint_salt = Some(found.salt);
+    }
+    store.save(&session)?;
+    store.set_active(&session.id)?;
+    eprintln!("launch session {} started", session.id);
+    run_session(
+        config,
+        rpc,
+        &payer,
+        supply_signer.as_ref(),
+        events,
+        broadcaster,
+        &mut session,
+    )
+}
+
+fn cmd_health(
+    config: &Config,
+    rpc: &RpcClient,
+    reference: Option<String>,
+    watch: bool,
+    interval: u64,
+) -> Result<()> {
+    let reference = reference
+        .or_else(|| {
+            // A local cluster has no public counterpart to compare with.
+            let public = config.network.default_rpc_url();
+            (config.network != Network::Localnet && rpc.url() != public).then(|| public.to_string())
+        })
+        .map(|url| {
+            RpcClient::new(url)
+                .with_timeout(config.timeouts.rpc())
+                .with_commitment(rpc.commitment())
+        });
+    println!("{} at {}", config.network, rpc.url());
+    if let Some(reference) = &reference {
+        println!("reference {}", reference.url());
+    }
+    if !watch {
+        println!("{}", health_line(rpc, reference.as_ref())?);
+        return Ok(());
+    }
+    let interval = Duration::from_secs(interval.max(1));
+    loop {
+        match health_line(rpc, reference.as_ref()) {
+            Ok(line) => println!("{line}"),
+            Err(e) => println!("unreachable: {e}"),
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Slot, latency and lag behind `reference`, probed at the same time so
+/// the lag isn't skewed by the time between them.
+fn health_line(rpc: &RpcClient, reference: Option<&RpcClient>) -> Result<String> {
+    let (probe, against) = std::thread::scope(|s| {
+        let against = reference.map(|r| s.spawn(|| r.probe()));
+        let probe = rpc.probe();
+        let against = against.map(|h| {
+            h.join()
+                .unwrap_or_else(|_| Err(Error::Http("probe thread panicked".into())))
+        });
+        (probe, against)
+    });
+    let probe = probe?;
+    let mut line = format!(
+        "slot {}  latency {} ms",
+        probe.slot,
+        probe.latency.as_millis()
+    );
+    let mut problems = Vec::new();
+    if probe.latency > LATENCY_WARNING {
+        problems.push("slow to answer".to_string());
+    }
+    match against {
+        None => {}
+        Some(Ok(other)) => {
+            let lag = other.slot.saturating_sub(probe.slot);
+            line.push_str(&format!("  {lag} slot(s) behind reference"));
+            if lag > SLOT_LAG_WARNING {
+                problems.push(format!("more than {SLOT_LAG_WARNING} slots behind"));
+            }
+        }
+        Some(Err(e)) => line.push_str(&format!("  reference unreachable ({e})")),
+    }
+    if !problems.is_empty() {
+        line.push_str(&format!("  DEGRADED: {}", problems.join(", ")));
+    }
+    Ok(line)
+}
+
+fn cmd_doctor(config: &Config, rpc: &RpcClient) -> Result<()> {
+    println!("{} at {}", config.network, rpc.url());
+    let checks = doctor::run(config, rpc);
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for check in &checks {
+        println!(
+            "[{:<4}] {:<width$}  {}",
+            check.status, check.name, check.detail
+        );
+    }
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        return Err(Error::Config(format!(
+            "{failed} check(s) failed; fix them before launching"
+        )));
+    }
+    println!("ready to launch");
+    Ok(())
+}
+
+fn cmd_stats(config: &Config, json: bool, reset: bool) -> Result<()> {
+    let file = StatsFile::open_default()?;
+    if reset {
+        file.reset()?;
+        println!("usage statistics cleared");
+        return Ok(());
+    }
+    let stats = file.load()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    if !config.stats {
+        eprintln!(
+            "note: statistics are off; set \"stats\": true in the config file to collect them"
+        );
+    }
+    if stats.since == 0 {
+        println!("no statistics recorded yet");
+        return Ok(());
+    }
+    println!("Since {}", time::format_rfc3339(stats.since));
+    println!(
+        "Launch runs: {} ({} completed, {} failed)",
+        stats.launches(),
+        stats.launches_completed,
+        stats.launches_failed
+    );
+    for (network, count) in &stats.networks {
+        println!("  {network:<16} {count}");
+    }
+    if !stats.failures.is_empty() {
+        println!("Failures:");
+        for (category, count) in &stats.failures {
+            println!("  {category:<24} {count}");
+        }
+    }
+    if !stats.steps.is_empty() {
+        println!("Average step time:");
+        for (step, times) in &stats.steps {
+            println!(
+                "  {step:<24} {:>8.1}s over {} run(s)",
+                times.average().as_secs_f64(),
+                times.count
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_self_update(config: &Config, check: bool, yes: bool) -> Result<()> {
+    let (Some(repo), Some(key)) = (&config.update_repo, &config.update_key) else {
+        return Err(Error::Config(
+            "self-update needs update_repo (OWNER/NAME) and update_key, the key that \
+             signs its releases, in the config file"
+                .into(),
+        ));
+    };
+    let release = update::latest_release(repo)?;
+    let current = update::CURRENT_VERSION;
+    if !update::is_newer(release.version(), current)? {
+        println!(
+            "{current} is up to date (latest release {})",
+            release.tag_name
+        );
+        return Ok(());
+    }
+    println!(
+        "{} is available from {repo}; this is {current}",
+        release.tag_name
+    );
+    if check {
+        return Ok(());
+    }
+    let exe = std::env::current_exe()?;
+    if !yes
+        && !confirm(&format!(
+            "Replace {} with {}?",
+            exe.display(),
+            release.tag_name
+        ))?
+    {
+        return Err(Error::InvalidInput("update cancelled".into()));
+    }
+    let binary = update::fetch_verified(&release, key)?;
+    eprintln!(
+        "signature by {key} for {} and checksum verified",
+        release.version()
+    );
+    if let Some(old) = update::replace(&exe, &binary)? {
+        eprintln!("previous binary kept as {}", old.display());
+    }
+    println!("Updated to {}", release.tag_name);
+    Ok(())
+}
+
+fn cmd_rent(
+    config: &Config,
+    rpc: &RpcClient,
+    token_2022: bool,
+    extensions: &[String],
+    holders: u64,
+) -> Result<()> {
+    let mut kinds = Vec::new();
+    for name in extensions {
+        let kind = extension::kind_by_name(name)
+            .filter(|&kind| extension::mint_extension_len(kind).is_some())
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("'{name}' is not a fixed-size mint extension"))
+            })?;
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    let mint_lens: Vec<usize> = kinds
+        .iter()
+        .filter_map(|&kind| extension::mint_extension_len(kind))
+        .collect();
+    let mut account_lens: Vec<usize> = kinds
+        .iter()
+        .filter_map(|&kind| extension::account_extension_len(kind))
+        .collect();
+    if token_2022 {
+        // The associated token account program always makes Token-2022
+        // accounts immutable-owner.
+        account_lens.push(0);
+    }
+    let mint_len = extension::mint_len(&mint_lens);
+    let account_len = extension::account_len(&account_lens);
+    let mint_rent = rpc.get_minimum_balance_for_rent_exemption(mint_len)?;
+    let account_rent = rpc.get_minimum_balance_for_rent_exemption(account_len)?;
+    let metadata_rent = rpc.get_minimum_balance_for_rent_exemption(metadata::MAX_METADATA_LEN)?;
+
+    println!("Rent-exempt minimums on {}:", config.network);
+    if !kinds.is_empty() {
+        let names: Vec<&str> = kinds.iter().map(|&kind| extension::name(kind)).collect();
+        println!("  (mint extensions: {})", names.join(", "));
+    }
+    let mint_label = if token_2022 {
+        "Mint (Token-2022)"
+    } else {
+        "Mint"
+    };
+    for (label, len, lamports) in [
+        (mint_label, mint_len, mint_rent),
+        ("Token account", account_len, account_rent),
+        (
+            "Metadata account",
+            metadata::MAX_METADATA_LEN,
+            metadata_rent,
+        ),
+    ] {
+        println!(
+            "  {label:<40} {len:>5} bytes  {:>12} SOL",
+            system::format_sol(lamports)
+        );
+    }
+    let launch = mint_rent + account_rent + metadata_rent;
+    println!(
+        "  {:<40} {:>11}  {:>12} SOL",
+        "Launch (mint, supply account, metadata)",
+        "",
+        system::format_sol(launch)
+    );
+    if holders > 0 {
+        let accounts = account_rent.saturating_mul(holders);
+        println!(
+            "  {:<40} {:>11}  {:>12} SOL",
+            format!("{holders} holder token accounts"),
+            "",
+            system::format_sol(accounts)
+        );
+        println!(
+            "  {:<40} {:>11}  {:>12} SOL",
+            "Total",
+            "",
+            system::format_sol(launch.saturating_add(accounts))
+        );
+    }
+    println!("Transaction fees are extra.");
+    Ok(())
+}
+
+fn cmd_mint_key(index: u32, count: u32, out: Option<PathBuf>) -> Result<()> {
+    let credentials = Credentials::load()?;
+    let passphrase = credentials.mint_passphrase()?;
+    if let Some(path) = out {
+        let keypair = phrase::mint_keypair(passphrase, index)?;
+        std::fs::write(&path, serde_json::to_string(&keypair.to_bytes().to_vec())?)?;
+        println!("{}", keypair.pubkey());
+        eprintln!("mint keypair {index} written to {}", path.display());
+        return Ok(());
+    }
+    let last = index
+        .checked_add(count.saturating_sub(1))
+        .ok_or_else(|| Error::InvalidInput("--count runs past the last index".into()))?;
+    for i in index..=last {
+        println!("{i:>4}  {}", phrase::mint_keypair(passphrase, i)?.pubkey());
+    }
+    Ok(())
+}
+
+fn vanity_search(prefixes: &[String], suffixes: &[String], ignore_case: bool) -> Result<Search> {
+    let prefixes = prefixes
+        .iter()
+        .map(|text| Pattern::new(text, Position::Prefix, ignore_case));
+    let suffixes = suffixes
+        .iter()
+        .map(|text| Pattern::new(text, Position::Suffix, ignore_case));
+    Search::new(prefixes.chain(suffixes).collect::<Result<_>>()?)
+}
+
+/// Rough human duration, e.g. `40s`, `3.5h`, `12 days`.
+fn format_seconds(seconds: f64) -> String {
+    match seconds {
+        s if s < 90.0 => format!("{s:.0}s"),
+        s if s < 90.0 * 60.0 => format!("{:.0}m", s / 60.0),
+        s if s < 48.0 * 3600.0 => format!("{:.1}h", s / 3600.0),
+        s if s < 730.0 * 86_400.0 => format!("{:.0} days", s / 86_400.0),
+        s => format!("{:.0} years", s / (365.0 * 86_400.0)),
+    }
+}
+
+/// Grinds until `search` matches, reporting the rate on stderr.
+fn grind_vanity(search: &Search, seed: &[u8; 32], threads: Option<usize>) -> vanity::Found {
+    let workers = threads.unwrap_or_else(vanity::default_workers);
+    let expected = search.difficulty();
+    eprintln!(
+        "grinding for a mint address {} on {workers} threads (about {expected:.0} keys expected)",
+        search
+            .patterns()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" or ")
+    );
+    let found = vanity::grind(&vanity::Cpu, seed, search, workers, |progress| {
+        let rate = progress.rate();
+        let remaining = (expected - progress.attempts as f64).max(0.0) / rate.max(1.0);
+        eprintln!(
+            "  {} keys, {rate:.0} keys/s, about {} to go",
+            progress.attempts,
+            format_seconds(remaining)
+        );
+        true
+    });
+    found.expect("grinding only stops once a key matches")
+}
+
+fn cmd_vanity(
+    prefixes: &[String],
+    suffixes: &[String],
+    ignore_case: bool,
+    threads: Option<usize>,
+    seconds: u64,
+) -> Result<()> {
+    let search = vanity_search(prefixes, suffixes, ignore_case)?;
+    let workers = threads.unwrap_or_else(vanity::default_workers);
+    let backend = vanity::Cpu;
+    eprintln!(
+        "measuring the {} backend on {workers} threads for {seconds}s...",
+        backend.name()
+    );
+    let started = std::time::Instant::now();
+    let deadline = started + Duration::from_secs(seconds);
+    let mut attempts = 0;
+    // Easy patterns match within the window, so keep searching from fresh
+    // seeds until it ends. Matches are thrown away; only the count matters.
+    for round in 0u64.. {
+        let mut seed = [0; 32];
+        seed[..8].copy_from_slice(&round.to_le_bytes());
+        let mut tried = 0;
+        let found = vanity::grind(&backend, &seed, &search, workers, |progress| {
+            tried = progress.attempts;
+            std::time::Instant::now() < deadline
+        });
+        attempts += found.map_or(tried, |f| f.progress.attempts);
+        if std::time::Instant::now() >= deadline {
+            break;
+        }
+    }
+    let rate = attempts as f64 / started.elapsed().as_secs_f64();
+    println!("{rate:.0} keys/s");
+    for pattern in search.patterns() {
+        let expected = pattern.difficulty();
+        println!(
+            "{pattern}: about {expected:.0} keys, {}",
+            format_seconds(expected / rate.max(1.0))
+        );
+    }
+    if search.patterns().len() > 1 {
+        println!(
+            "any of them: about {}",
+            format_seconds(search.difficulty() / rate.max(1.0))
+        );
+    }
+    Ok(())
+}
+
+fn print_handoff(
+    rpc: &RpcClient,
+    params: &LaunchParams,
+    wallet: &Pubkey,
+    mint: Option<Keypair>,
+) -> Result<()> {
+    let mint = mint.unwrap_or_else(|| one_off_mint(wallet));
+    let rent = rpc.get_minimum_balance_for_rent_exemption(params.mint_len()?)?;
+    let handoff = handoff::build(params, wallet, &mint, rpc.get_latest_blockhash()?, rent)?;
+    println!("{}", serde_json::to_string_pretty(&handoff)?);
+    eprintln!(
+        "sign and send the {} transaction(s) in order before the blockhash expires (about a minute)",
+        handoff.transactions.len()
+    );
+    Ok(())
+}
+
+fn one_off_mint(wallet: &Pubkey) -> Keypair {
+    // The mint key only has to stay secret until the mint exists; it holds
+    // no authority afterwards, so a one-off seed is enough.
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(b"launch-solana handoff mint")
+        .chain_update(wallet.as_bytes())
+        .chain_update(nanos.to_le_bytes())
+        .chain_update(std::process::id().to_le_bytes())
+        .finalize()
+        .into();
+    Keypair::from_seed(&seed)
+}
+
+/// Runs the remaining steps of a session, saving it after each one.
+fn run_session(
+    config: &Config,
+    rpc: &RpcClient,
+    payer: &Keypair,
+    supply_signer: Option<&Keypair>,
+    events: Option<&EventLog>,
+    broadcaster: Option<&Broadcaster>,
+    session: &mut LaunchSession,
+) -> Result<()> {
+    let store = SessionStore::open_default()?;
+    let credentials = match session.mint_index {
+        Some(_) => Some(Credentials::load()?),
+        None => None,
+    };
+    let mut manager = SolanaTokenManager::new(rpc, payer).with_store(&store);
+    if let Some(credentials) = &credentials {
+        manager = manager.with_mint_passphrase(credentials.mint_passphrase()?);
+    }
+    if let Some(keypair) = supply_signer {
+        manager = manager.with_supply_signer(keypair);
+    }
+    if let Some(events) = events {
+        manager = manager.with_events(events);
+    }
+    if let Some(broadcaster) = broadcaster {
+        manager = manager.with_broadcaster(broadcaster);
+    }
+    while let Some(step) = session.next_step() {
+        eprintln!("[{}] {}...", session.id, step.as_str());
+        if let Some(pending) = session.pending.filter(|p| p.step == step) {
+            eprintln!(
+                "[{}] checking whether {} from the interrupted attempt landed...",
+                session.id, pending.signature
+            );
+        }
+        let started = std::time::Instant::now();
+        let result = match (step, session.mint) {
+            // Metadata the mint already has is updated, not created.
+            (Step::CreateMetadata, Some(mint)) => {
+                with_metadata_backup(config, rpc, &mint, || manager.execute(session, step))
+            }
+            _ => manager.execute(session, step),
+        };
+        let took = started.elapsed();
+        store.save(session)?;
+        if let Some(broadcaster) = broadcaster {
+            print_broadcasts(&broadcaster.take_reports());
+        }
+        let signature = match result {
+            Ok(signature) => signature,
+            Err(e) => {
+                record_stats(|s| s.launch_failed(session.network, &e));
+                eprintln!(
+                    "session {} stopped at {}; run `session resume {}` to retry",
+                    session.id,
+                    step.as_str(),
+                    session.id
+                );
+                return Err(e);
+            }
+        };
+        eprintln!("[{}] {} confirmed: {signature}", session.id, step.as_str());
+        record_stats(|s| s.step_done(step, took));
+        let step_spend = session.completed.last().and_then(|r| r.spend);
+        let (total, unknown) = session.spend();
+        eprintln!(
+            "[{}] cost {}, launch so far {}",
+            session.id,
+            step_spend.map_or("unknown".into(), |s| describe_spend(&s)),
+            describe_total_spend(&total, unknown)
+        );
+        if let Some(hook) = Hook::after_step(step) {
+            fire_plugins(hook, session, events);
+        }
+    }
+
+    let mut record = session.record()?;
+    let launches = LaunchStore::open_default()?;
+    // Keep the versions the metadata step replaced.
+    if let Some(earlier) = launches.load(&record.mint)? {
+        record.metadata_history = earlier.metadata_history;
+    }
+    launches.save(&record)?;
+    record_stats(|s| s.launch_completed(session.network));
+    match rehearsal::observe(rpc, session) {
+        Ok(outcome) => {
+            session.outcome = Some(outcome);
+            store.save(session)?;
+        }
+        Err(e) => eprintln!("warning: could not read back the launch result: {e}"),
+    }
+    if let (Some(id), Some(outcome)) = (&session.rehearsal, &session.outcome) {
+        let rehearsal = store.load(id)?;
+        if let Some(rehearsed) = &rehearsal.outcome {
+            let divergences =
+                rehearsal::compare(rehearsed, &rehearsal.authority, outcome, &session.authority);
+            if divergences.is_empty() {
+                eprintln!("matches rehearsal {id}");
+            }
+            for d in &divergences {
+                eprintln!(
+                    "DIVERGES from rehearsal {id}: {}: rehearsed {}, got {}",
+                    d.field, d.rehearsed, d.replayed
+                );
+            }
+        }
+    }
+    if let Some(hook) = &session.params.transfer_hook
+        && !has_extra_account_metas(rpc, &record.mint, hook)?
+    {
+        eprintln!(
+            "warning: {hook} has no extra-account-metas account for {} yet ({}); \
+             transfers fail until the hook program initializes it",
+            record.mint,
+            extension::extra_account_metas_address(&record.mint, hook)
+        );
+    }
+    fire_plugins(Hook::AfterLaunch, session, events);
+    if events.is_some_and(EventLog::is_stdout) {
+        return Ok(());
+    }
+    println!("Mint:    {}", record.mint);
+    if let Some(account) = session.token_account {
+        println!("Account: {account}");
+    }
+    println!("Supply:  {} {}", record.supply, record.symbol);
+    let (total, unknown) = session.spend();
+    println!("Cost:    {}", describe_total_spend(&total, unknown));
+    println!(
+        "Explorer: {}",
+        session
+            .network
+            .explorer_url(&format!("token/{}", record.mint))
+    );
+    Ok(())
+}
+
+fn describe_spend(spend: &Spend) -> String {
+    format!(
+        "{} SOL (fee {}, rent {})",
+        system::format_sol(spend.total()),
+        system::format_sol(spend.fee),
+        system::format_sol(spend.rent)
+    )
+}
+
+fn describe_total_spend(total: &Spend, unknown: usize) -> String {
+    match unknown {
+        0 => describe_spend(total),
+        n => format!("{} plus {n} step(s) of unknown cost", describe_spend(total)),
+    }
+}
+
+fn print_broadcasts(reports: &[BroadcastReport]) {
+    for report in reports {
+        let mut summary = format!(
+            "  sent to {} of {} endpoints",
+            report.accepted(),
+            report.endpoints.len()
+        );
+        if report.resends > 0 {
+            summary.push_str(&format!(", resent {} times", report.resends));
+        }
+        if let Some(url) = &report.first_confirmed_by {
+            summary.push_str(&format!(", first confirmed by {url}"));
+        }
+        eprintln!("{summary}");
+        for endpoint in &report.endpoints {
+            match &endpoint.error {
+                None => eprintln!("    {} ok ({} ms)", endpoint.url, endpoint.latency_ms),
+                Some(error) => eprintln!("    {} failed: {error}", endpoint.url),
+            }
+        }
+    }
+}
+
+fn session_or_active(store: &SessionStore, id: Option<String>) -> Result<LaunchSession> {
+    let id = match id {
+        Some(id) => id,
+        None => store
+            .active()?
+            .ok_or_else(|| Error::InvalidInput("no active session; pass a session ID".into()))?,
+    };
+    store.load(&id)
+}
+
+fn session_status(session: &LaunchSession) -> String {
+    match session.next_step() {
+        None => "complete".into(),
+        Some(step) if session.last_error.is_some() => format!("failed at {}", step.as_str()),
+        Some(step) => format!("pending {}", step.as_str()),
+    }
+}
+
+fn cmd_session(
+    config: &Config,
+    rpc: &RpcClient,
+    events: Option<&EventLog>,
+    broadcaster: Option<&Broadcaster>,
+    action: SessionAction,
+) -> Result<()> {
+    let store = SessionStore::open_default()?;
+    match action {
+        SessionAction::List => {
+            let active = store.active()?;
+            for session in store.list()? {
+                let marker = if active.as_deref() == Some(session.id.as_str()) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!(
+                    "{marker} {}  {:<8}  {:<10}  {}  {}",
+                    session.id,
+                    session.network,
+                    session.params.symbol,
+                    time::format_rfc3339(session.updated_at),
+                    session_status(&session)
+                );
+            }
+        }
+        SessionAction::Show { id } => {
+            let session = session_or_active(&store, id)?;
+            println!("Session:   {}", session.id);
+            println!("Key:       {}", session.idempotency_key());
+            println!("Network:   {}", session.network);
+            println!("Authority: {}", session.authority);
+            println!(
+                "Token:     {} ({}), supply {}",
+                session.params.name, session.params.symbol, session.params.supply
+            );
+            if let Some(mint) = session.mint {
+                println!("Mint:      {mint}");
+            }
+            if let Some(account) = session.token_account {
+                println!("Account:   {account}");
+            }
+            println!("Status:    {}", session_status(&session));
+            for step in session.steps() {
+                match session.completed.iter().find(|r| r.step == step) {
+                    Some(record) => println!(
+                        "  [x] {:<22} {}  {}",
+                        step.as_str(),
+                        record.signature,
+                        record
+                            .spend
+                            .map_or("cost unknown".into(), |s| describe_spend(&s))
+                    ),
+                    None => println!("  [ ] {}", step.as_str()),
+                }
+            }
+            if !session.completed.is_empty() {
+                let (total, unknown) = session.spend();
+                println!("Cost:      {}", describe_total_spend(&total, unknown));
+            }
+            if let Some(buy) = &session.test_buy {
+                println!(
+                    "Test buy:  {} at {}",
+                    buy.signature,
+                    time::format_rfc3339(buy.at)
+                );
+            }
+            if let Some(error) = &session.last_error {
+                println!("Last error: {error}");
+            }
+            if let Some(pending) = session.pending {
+                println!(
+                    "Unconfirmed {} transaction {} sent at {}",
+                    pending.step.as_str(),
+                    pending.signature,
+                    time::format_rfc3339(pending.sent_at)
+                );
+            }
+        }
+        SessionAction::Switch { id } => {
+            store.set_active(&id)?;
+            println!("active session is now {id}");
+        }
+        SessionAction::Resume { id, supply_account } => {
+            let mut session = session_or_active(&store, id)?;
+            if session.network != config.network {
+                return Err(Error::InvalidInput(format!(
+                    "session {} runs on {}; pass --network {}",
+                    session.id, session.network, session.network
+                )));
+            }
+            let payer = Credentials::load()?.keypair()?;
+            let supply_signer = supply_account
+                .as_deref()
+                .map(Keypair::read_file)
+                .transpose()?;
+            run_session(
+                config,
+                rpc,
+                &payer,
+                supply_signer.as_ref(),
+                events,
+                broadcaster,
+                &mut session,
+            )?;
+        }
+        SessionAction::Replay { id, yes } => {
+            let rehearsal = store.load(&id)?;
+            if rehearsal.network == config.network {
+                return Err(Error::InvalidInput(format!(
+                    "session {id} already ran on {}; pass the --network to replay it on",
+                    rehearsal.network
+                )));
+            }
+            if rehearsal.network.is_mainnet() {
+                return Err(Error::InvalidInput(format!(
+                    "session {id} ran on {}; rehearse on a test network or localnet",
+                    rehearsal.network
+                )));
+            }
+            if !rehearsal.is_complete() || rehearsal.outcome.is_none() {
+                // Resuming a finished session only reads its result back.
+                return Err(Error::InvalidInput(format!(
+                    "session {id} has no recorded result; run `session resume {id} --network {}` first",
+                    rehearsal.network
+                )));
+            }
+            let payer = Credentials::load()?.keypair()?;
+            let params = rehearsal.params.clone();
+            println!(
+                "Replaying rehearsal {id} ({}) on {}:",
+                rehearsal.network, config.network
+            );
+            println!(
+                "  {} ({}), supply {}, {}",
+                params.name,
+                params.symbol,
+                params.supply,
+                if params.token_program == rpc.programs().token_2022 {
+                    "Token-2022"
+                } else {
+                    "SPL Token"
+                }
+            );
+            println!("  Metadata: {}", params.uri);
+            if let Some(mint) = rehearsal.mint {
+                println!(
+                    "  Rehearsed mint: {}",
+                    rehearsal.network.explorer_url(&format!("token/{mint}"))
+                );
+            }
+            if let Some(index) = rehearsal.mint_index {
+                println!("  Mint key: passphrase index {index}, the rehearsal's address");
+            }
+            let steps: Vec<&str> = params.steps().iter().map(|s| s.as_str()).collect();
+            println!("  Steps: {}", steps.join(", "));
+            if payer.pubkey() != rehearsal.authority {
+                println!(
+                    "  Authority: {} (rehearsed with {})",
+                    payer.pubkey(),
+                    rehearsal.authority
+                );
+            }
+            if !yes && !confirm(&format!("Launch on {}?", config.network))? {
+                return Err(Error::InvalidInput("replay cancelled".into()));
+            }
+            let mut session = LaunchSession::new(
+                store.new_id(),
+                config.network,
+                payer.pubkey(),
+                params,
+                solanaapp::launch::now_unix(),
+            );
+            session.rehearsal = Some(id);
+            session.mint_index = rehearsal.mint_index;
+            store.save(&session)?;
+            store.set_active(&session.id)?;
+            eprintln!("launch session {} started", session.id);
+            run_session(config, rpc, &payer, None, events, broadcaster, &mut session)?;
+        }
+    }
+    Ok(())
+}
+
+thread_local! {
+    /// Answers the run's questions, and records them with --transcript.
+    static PROMPTER: RefCell<Prompter> = RefCell::new(Prompter::default());
+    /// The run's timeouts, for steps too deep to be handed the config.
+    static TIMEOUTS: Cell<Timeouts> = Cell::new(Timeouts::default());
+    /// Whether the config file opted in to local usage statistics.
+    static COLLECT_STATS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Updates the local usage statistics when they are being collected. A
+/// failure to write them is only reported: they must never stop a launch.
+fn record_stats(change: impl FnOnce(&mut Stats)) {
+    if !COLLECT_STATS.get() {
+        return;
+    }
+    if let Err(e) = StatsFile::open_default().and_then(|file| file.update(change)) {
+        eprintln!("warning: usage statistics not updated: {e}");
+    }
+}
+
+/// An answer to one question of a flow.
+enum Reply {
+    Yes,
+    No,
+    /// Revisit the flow's previous question.
+    Back,
+}
+
+/// Asks a yes/no question on the terminal, or answers it from the
+/// transcript being replayed, until it gets an answer: "y" or "yes", "n",
+/// "no" or nothing, "back", or "cancel", which stops the command there.
+/// Whatever the command saved so far, such as a launch session, is kept.
+fn reply(question: &str) -> Result<Reply> {
+    loop {
+        let answer = PROMPTER.with_borrow_mut(|p| p.answer(question, ask))?;
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => return Ok(Reply::Yes),
+            "" | "n" | "no" => return Ok(Reply::No),
+            "back" => return Ok(Reply::Back),
+            "cancel" => {
+                return Err(Error::InvalidInput(format!("cancelled at \"{question}\"")));
+            }
+            other => eprintln!("\"{other}\" is not an answer; type y, n, back or cancel"),
+        }
+    }
+}
+
+/// [`reply`] to a question with none before it.
+fn confirm(question: &str) -> Result<bool> {
+    loop {
+        match reply(question)? {
+            Reply::Yes => return Ok(true),
+            Reply::No => return Ok(false),
+            Reply::Back => eprintln!("there is no earlier question to go back to"),
+        }
+    }
+}
+
+/// Reads an answer from the terminal; an empty one keeps `recorded`.
+fn ask(question: &str, recorded: Option<&str>) -> Result<String> {
+    match recorded {
+        Some(answer) => eprint!("{question} [y/N, recorded: {answer}] "),
+        None => eprint!("{question} [y/N] "),
+    }
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(match recorded {
+        Some(recorded) if answer.is_empty() => recorded.to_string(),
+        _ => answer.to_string(),
+    })
+}
+
+fn cmd_squads(config: &Config, rpc: &RpcClient, action: SquadsAction) -> Result<()> {
+    match action {
+        SquadsAction::Create {
+            members,
+            threshold,
+            time_lock,
+        } => {
+            let creator = Credentials::load()?.keypair()?;
+            let members: Vec<Member> = members
+                .into_iter()
+                .map(|key| Member {
+                    key,
+                    permissions: squads::PERMISSION_ALL,
+                })
+                .collect();
+            // The create key only seeds the multisig address.
+            let seed: [u8; 32] = Sha256::new()
+                .chain_update(b"launch-solana squads create key")
+                .chain_update(creator.to_bytes())
+                .chain_update(solanaapp::launch::now_unix().to_le_bytes())
+                .finalize()
+                .into();
+            let create_key = Keypair::from_seed(&seed);
+            let (multisig, signature) = squads::create_multisig(
+                rpc,
+                &creator,
+                &create_key,
+                &members,
+                threshold,
+                time_lock,
+            )?;
+            println!("Multisig: {multisig}");
+            println!("Vault 0:  {}", squads::vault_address(&multisig, 0));
+            println!("Signature: {signature}");
+        }
+        SquadsAction::Handover {
+            multisig,
+            mint,
+            vault_index,
+        } => {
+            let authority = Credentials::load()?.signer(rpc.programs())?;
+            let key = authority.pubkey();
+            squads::fetch_multisig(rpc, &multisig)?;
+            let vault = squads::vault_address(&multisig, vault_index);
+            let mint_account = token::fetch_mint(rpc, &mint)?;
+            let program_id = mint_account.program_id;
+            let mut ixs = Vec::new();
+            let mut moved = Vec::new();
+            if mint_account.mint.mint_authority == Some(key) {
+                ixs.push(token::set_authority(
+                    &program_id,
+                    &mint,
+                    &key,
+                    token::AuthorityType::MintTokens,
+                    Some(&vault),
+                ));
+                moved.push("mint");
+            }
+            if mint_account.mint.freeze_authority == Some(key) {
+                ixs.push(token::set_authority(
+                    &program_id,
+                    &mint,
+                    &key,
+                    token::AuthorityType::FreezeAccount,
+                    Some(&vault),
+                ));
+                moved.push("freeze");
+            }
+            if let Some(on_chain) = metadata::fetch_metadata(rpc, &mint)?
+                && on_chain.is_mutable
+                && on_chain.update_authority == key
+            {
+                ixs.push(metadata::update_metadata_accounts_v2(
+                    &rpc.programs().metadata,
+                    &mint,
+                    &key,
+                    None,
+                    Some(&vault),
+                    None,
+                    None,
+                ));
+                moved.push("metadata update");
+            }
+            if ixs.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "{key} holds no authority over {mint}"
+                )));
+            }
+            let tx = rpc.build_signed(&ixs, &key, &[&authority])?;
+            let signature =
+                with_metadata_backup(config, rpc, &mint, || rpc.send_and_confirm_critical(&tx))?;
+            println!("Moved {} authority to vault {vault}", moved.join(", "));
+            println!("Signature: {signature}");
+        }
+        SquadsAction::Propose {
+            multisig,
+            vault_index,
+            memo,
+            operation,
+        } => {
+            let member = Credentials::load()?.keypair()?;
+            let vault = squads::vault_address(&multisig, vault_index);
+            let ixs = proposal_instructions(rpc, &vault, operation)?;
+            let (index, signature) =
+                squads::propose(rpc, &member, &multisig, vault_index, &ixs, memo.as_deref())?;
+            println!("Proposal {index} created on {multisig}");
+            println!("Signature: {signature}");
+        }
+        SquadsAction::Approve { multisig, index } => {
+            let member = Credentials::load()?.keypair()?;
+            let signature = squads::approve(rpc, &member, &multisig, index)?;
+            println!("Approved proposal {index}: {signature}");
+        }
+        SquadsAction::Execute { multisig, index } => {
+            let member = Credentials::load()?.keypair()?;
+            let transaction = squads::fetch_vault_transaction(rpc, &multisig, index)?;
+            let mints = metadata_mints(rpc, &transaction.writable_keys())?;
+            let signature = with_metadata_backups(config, rpc, &mints, || {
+                squads::execute(rpc, &member, &multisig, index)
+            })?;
+            println!("Executed proposal {index}: {signature}");
+        }
+        SquadsAction::Status {
+            multisig,
+            index: None,
+            ..
+        } => {
+            let state = squads::fetch_multisig(rpc, &multisig)?;
+            println!("Multisig:   {multisig}");
+            println!("Vault 0:    {}", squads::vault_address(&multisig, 0));
+            println!("Threshold:  {} of {}", state.threshold, state.members.len());
+            println!("Time lock:  {}s", state.time_lock);
+            println!("Latest transaction: {}", state.transaction_index);
+            for member in &state.members {
+                println!("  {} (permissions {:03b})", member.key, member.permissions);
+            }
+        }
+        SquadsAction::Status {
+            multisig,
+            index: Some(index),
+            wait,
+        } => {
+            let threshold = squads::fetch_multisig(rpc, &multisig)?.threshold;
+            let mut last = None;
+            loop {
+                let proposal = squads::fetch_proposal(rpc, &multisig, index)?;
+                if last != Some(proposal.status) {
+                    println!(
+                        "Proposal {index}: {} ({} of {threshold} approvals, {} rejections)",
+                        proposal.status.as_str(),
+                        proposal.approved.len(),
+                        proposal.rejected.len()
+                    );
+                    last = Some(proposal.status);
+                }
+                let settled = proposal.status.is_final()
+                    || matches!(proposal.status, ProposalStatus::Approved { .. });
+                if !wait || settled {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_secs(5));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fails unless `program` is a deployed, executable program.
+fn check_hook_program(rpc: &RpcClient, program: &Pubkey) -> Result<()> {
+    match rpc.get_account(program)?.value {
+        Some(account) if account.executable => Ok(()),
+        Some(_) => Err(Error::InvalidInput(format!(
+            "transfer hook {program} is not an executable program"
+        ))),
+        None => Err(Error::AccountNotFound(*program)),
+    }
+}
+
+/// Whether the hook program has set up its extra-account-metas account for
+/// `mint`; without it every transfer of the mint fails.
+fn has_extra_account_metas(rpc: &RpcClient, mint: &Pubkey, program: &Pubkey) -> Result<bool> {
+    let address = extension::extra_account_metas_address(mint, program);
+    Ok(rpc
+        .get_account(&address)?
+        .value
+        .is_some_and(|account| account.owner == *program))
+}
+
+fn cmd_transfer_hook(rpc: &RpcClient, action: TransferHookAction) -> Result<()> {
+    match action {
+        TransferHookAction::Show { mint } => {
+            let account = token::fetch_mint(rpc, &mint)?;
+            let Some(data) = extension::find(&account.extensions, extension::TRANSFER_HOOK) else {
+                println!("{mint} has no transfer hook");
+                return Ok(());
+            };
+            let hook = TransferHook::unpack(data)?;
+            let describe = |key: Option<Pubkey>| key.map_or("none".to_string(), |k| k.to_string());
+            let program = hook
+                .program_id
+                .map_or("none".to_string(), |k| rpc.labels().describe(&k));
+            println!("Program:   {program}");
+            println!("Authority: {}", describe(hook.authority));
+            if let Some(program) = hook.program_id {
+                let address = extension::extra_account_metas_address(&mint, &program);
+                let state = if has_extra_account_metas(rpc, &mint, &program)? {
+                    "initialized"
+                } else {
+                    "MISSING: transfers will fail"
+                };
+                println!("Extra account metas: {address} ({state})");
+            }
+        }
+        TransferHookAction::Update {
+            mint,
+            program,
+            clear: _,
+        } => {
+            let authority = Credentials::load()?.signer(rpc.programs())?;
+            let account = token::fetch_mint(rpc, &mint)?;
+            let data = extension::find(&account.extensions, extension::TRANSFER_HOOK).ok_or_else(
+                || {
+                    Error::InvalidInput(format!(
+                        "{mint} was created without the transfer hook extension"
+                    ))
+                },
+            )?;
+            let hook = TransferHook::unpack(data)?;
+            if hook.authority != Some(authority.pubkey()) {
+                return Err(Error::InvalidInput(format!(
+                    "transfer hook authority of {mint} is not held by {}",
+                    authority.pubkey()
+                )));
+            }
+            if let Some(program) = &program {
+                check_hook_program(rpc, program)?;
+                if !has_extra_account_metas(rpc, &mint, program)? {
+                    return Err(Error::InvalidInput(format!(
+                        "{program} has no extra-account-metas account for {mint} at {}; \
+                         initialize it first or every transfer will fail",
+                        extension::extra_account_metas_address(&mint, program)
+                    )));
+                }
+            }
+            let ix = extension::update_transfer_hook(
+                &account.program_id,
+                &mint,
+                &authority.pubkey(),
+                program.as_ref(),
+            );
+            let signature = send_signed(rpc, &[ix], &authority)?;
+            match program {
+                Some(program) => println!("Transfer hook of {mint} set to {program}"),
+                None => println!("Transfer hook of {mint} cleared"),
+            }
+            println!("Signature: {signature}");
+        }
+    }
+    Ok(())
+}
+
+fn send_signed(rpc: &RpcClient, ixs: &[Instruction], signer: &dyn Signer) -> Result<Signature> {
+    let tx = rpc.build_signed(ixs, &signer.pubkey(), &[signer])?;
+    rpc.send_and_confirm(&tx)
+}
+
+/// Like [`send_signed`], for transactions that can't be undone.
+fn send_signed_critical(
+    rpc: &RpcClient,
+    ixs: &[Instruction],
+    signer: &dyn Signer,
+) -> Result<Signature> {
+    let tx = rpc.build_signed(ixs, &signer.pubkey(), &[signer])?;
+    rpc.send_and_confirm_critical(&tx)
+}
+
+fn fetch_group(rpc: &RpcClient, group: &Pubkey) -> Result<TokenGroup> {
+    let account = token::fetch_mint(rpc, group)?;
+    let data = extension::find(&account.extensions, extension::TOKEN_GROUP)
+        .ok_or_else(|| Error::InvalidInput(format!("{group} is not a token group")))?;
+    TokenGroup::unpack(data)
+}
+
+/// Fails unless `authority` can add another member to `group`.
+fn check_group_open(rpc: &RpcClient, group: &Pubkey, authority: &Pubkey) -> Result<()> {
+    let info = fetch_group(rpc, group)?;
+    if info.update_authority != Some(*authority) {
+        return Err(Error::InvalidInput(format!(
+            "update authority of group {group} is not held by {authority}"
+        )));
+    }
+    if info.size >= info.max_size {
+        return Err(Error::InvalidInput(format!(
+            "group {group} is full ({} of {} members)",
+            info.size, info.max_size
+        )));
+    }
+    Ok(())
+}
+
+fn cmd_group(rpc: &RpcClient, action: GroupAction) -> Result<()> {
+    match action {
+        GroupAction::Show { mint } => {
+            let account = token::fetch_mint(rpc, &mint)?;
+            let describe = |key: Option<Pubkey>| key.map_or("none".to_string(), |k| k.to_string());
+            let exts = &account.extensions;
+            if let Some(data) = extension::find(exts, extension::TOKEN_GROUP) {
+                let group = TokenGroup::unpack(data)?;
+                println!("Group:     {mint}");
+                println!("Members:   {} of {}", group.size, group.max_size);
+                println!("Authority: {}", describe(group.update_authority));
+            }
+            if let Some(data) = extension::find(exts, extension::TOKEN_GROUP_MEMBER) {
+                let member = TokenGroupMember::unpack(data)?;
+                let pointer = extension::find(exts, extension::GROUP_MEMBER_POINTER)
+                    .map(Pointer::unpack)
+                    .transpose()?;
+                let removed = pointer.is_some_and(|p| p.address.is_none());
+                println!(
+                    "Member #{} of {}{}",
+                    member.member_number,
+                    member.group,
+                    if removed { " (removed)" } else { "" }
+                );
+            } else if extension::find(exts, extension::GROUP_MEMBER_POINTER).is_some() {
+                println!("{mint} has a member pointer but has not joined a group");
+            }
+            if !exts.iter().any(|e| {
+                e.kind == extension::TOKEN_GROUP
+                    || e.kind == extension::TOKEN_GROUP_MEMBER
+                    || e.kind == extension::GROUP_MEMBER_POINTER
+            }) {
+                println!("{mint} is neither a token group nor a member of one");
+            }
+        }
+        GroupAction::AddMember { group, member } => {
+            let authority = Credentials::load()?.signer(rpc.programs())?;
+            let key = authority.pubkey();
+            let account = token::fetch_mint(rpc, &member)?;
+            let exts = &account.extensions;
+            let pointer = extension::find(exts, extension::GROUP_MEMBER_POINTER)
+                .map(Pointer::unpack)
+                .transpose()?
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "{member} was created without a group member pointer"
+                    ))
+                })?;
+            let ixs = match extension::find(exts, extension::TOKEN_GROUP_MEMBER) {
+                Some(data) => {
+                    let record = TokenGroupMember::unpack(data)?;
+                    if record.group != group {
+                        return Err(Error::InvalidInput(format!(
+                            "{member} is permanently a member of {}",
+                            record.group
+                        )));
+                    }
+                    if pointer.address.is_some() {
+                        return Err(Error::InvalidInput(format!(
+                            "{member} is already a member of {group}"
+                        )));
+                    }
+                    if pointer.authority != Some(key) {
+                        return Err(Error::InvalidInput(format!(
+                            "member pointer authority of {member} is not held by {key}"
+                        )));
+                    }
+                    vec![extension::update_group_member_pointer(
+                        &account.program_id,
+                        &member,
+                        &key,
+                        Some(&member),
+                    )]
+                }
+                None => {
+                    check_group_open(rpc, &group, &key)?;
+                    if account.mint.mint_authority != Some(key) {
+                        return Err(Error::InvalidInput(format!(
+                            "mint authority of {member} is not held by {key}"
+                        )));
+                    }
+                    // Token-2022 grows the member mint for the membership
+                    // record; the rent for it has to be there already.
+                    let current = rpc
+                        .get_account(&member)?
+                        .value
+                        .ok_or(Error::AccountNotFound(member))?;
+                    let grown = current.data.len() + 4 + extension::TOKEN_GROUP_MEMBER_LEN;
+                    let rent = rpc.get_minimum_balance_for_rent_exemption(grown)?;
+                    let mut ixs = Vec::new();
+                    if rent > current.lamports {
+                        ixs.push(system::transfer(&key, &member, rent - current.lamports));
+                    }
+                    ixs.push(extension::initialize_member(
+                        &account.program_id,
+                        &member,
+                        &key,
+                        &group,
+                        &key,
+                    ));
+                    ixs
+                }
+            };
+            let signature = send_signed(rpc, &ixs, &authority)?;
+            println!("Added {member} to group {group}");
+            println!("Signature: {signature}");
+        }
+        GroupAction::RemoveMember { group, member } => {
+            let authority = Credentials::load()?.signer(rpc.programs())?;
+            let key = authority.pubkey();
+            let account = token::fetch_mint(rpc, &member)?;
+            let exts = &account.extensions;
+            let record = extension::find(exts, extension::TOKEN_GROUP_MEMBER)
+                .map(TokenGroupMember::unpack)
+                .transpose()?
+                .filter(|record| record.group == group)
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!("{member} is not a member of {group}"))
+                })?;
+            let pointer = extension::find(exts, extension::GROUP_MEMBER_POINTER)
+                .map(Pointer::unpack)
+                .transpose()?
+                .filter(|pointer| pointer.address.is_some())
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!("{member} was already removed from {group}"))
+                })?;
+            if pointer.authority != Some(key) {
+                return Err(Error::InvalidInput(format!(
+                    "member pointer authority of {member} is not held by {key}"
+                )));
+            }
+            let ix =
+                extension::update_group_member_pointer(&account.program_id, &member, &key, None);
+            let signature = send_signed(rpc, &[ix], &authority)?;
+            println!(
+                "Removed {member} (member #{}) from group {group}",
+                record.member_number
+            );
+            println!("Signature: {signature}");
+            eprintln!(
+                "note: the membership record stays on-chain and the group size is not reduced"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Reads DEV_KEYPAIR's Token-2022 token account named by `target`.
+fn fetch_own_account(
+    rpc: &RpcClient,
+    target: &AccountTarget,
+    owner: &Pubkey,
+) -> Result<(Pubkey, TokenAccount, Vec<Extension>)> {
+    let address = match (target.account, target.mint) {
+        (Some(account), _) => account,
+        (None, Some(mint)) => {
+            token::associated_token_address(owner, &mint, &rpc.programs().token_2022)
+        }
+        (None, None) => unreachable!("clap requires --account or --mint"),
+    };
+    let account = rpc
+        .get_account(&address)?
+        .value
+        .ok_or(Error::AccountNotFound(address))?;
+    if account.owner != rpc.programs().token_2022 {
+        return Err(Error::InvalidInput(format!(
+            "{address} is not a Token-2022 account; CPI guard and required memos are \
+             Token-2022 extensions"
+        )));
+    }
+    let token = TokenAccount::unpack(&account.data)?;
+    if token.owner != *owner {
+        return Err(Error::InvalidInput(format!(
+            "{address} is owned by {}, not {owner}",
+            token.owner
+        )));
+    }
+    Ok((address, token, extension::parse(&account.data)?))
+}
+
+fn cmd_account(rpc: &RpcClient, action: AccountAction) -> Result<()> {
+    let owner = Credentials::load()?.signer(rpc.programs())?;
+    let (target, kind, state) = match action {
+        AccountAction::Show { target } => {
+            let (address, token, exts) = fetch_own_account(rpc, &target, &owner.pubkey())?;
+            let on_off = |kind| {
+                if extension::is_enabled(&exts, kind) {
+                    "on"
+                } else {
+                    "off"
+                }
+            };
+            println!("Account:      {address}");
+            println!("Mint:         {}", token.mint);
+            println!("Balance:      {} (base units)", token.amount);
+            println!("CPI guard:    {}", on_off(extension::CPI_GUARD));
+            println!("Require memo: {}", on_off(extension::MEMO_TRANSFER));
+            return Ok(());
+        }
+        AccountAction::CpiGuard { target, state } => (target, extension::CPI_GUARD, state),
+        AccountAction::RequireMemo { target, state } => (target, extension::MEMO_TRANSFER, state),
+    };
+    let key = owner.pubkey();
+    let (address, _, exts) = fetch_own_account(rpc, &target, &key)?;
+    let enable = matches!(state, Toggle::On);
+    let label = extension::name(kind);
+    if extension::is_enabled(&exts, kind) == enable {
+        println!(
+            "{label} is already {} on {address}",
+            if enable { "on" } else { "off" }
+        );
+        return Ok(());
+    }
+    let program_id = rpc.programs().token_2022;
+    let mut ixs = Vec::new();
+    // Accounts are created without room for these extensions.
+    if extension::find(&exts, kind).is_none() {
+        ixs.push(extension::reallocate(
+            &program_id,
+            &address,
+            &key,
+            &key,
+            &[kind],
+        ));
+    }
+    ixs.push(if kind == extension::CPI_GUARD {
+        extension::set_cpi_guard(&program_id, &address, &key, enable)
+    } else {
+        extension::set_required_memo(&program_id, &address, &key, enable)
+    });
+    let signature = send_signed(rpc, &ixs, &owner)?;
+    println!(
+        "{label} {} on {address}",
+        if enable { "enabled" } else { "disabled" }
+    );
+    println!("Signature: {signature}");
+    Ok(())
+}
+
+fn describe_due(due: Due) -> String {
+    match due {
+        Due::At(at) => time::format_rfc3339(at),
+        Due::Slot(slot) => format!("slot {slot}"),
+    }
+}
+
+fn describe_status(status: &ScheduleStatus) -> String {
+    match status {
+        ScheduleStatus::Pending => "pending".into(),
+        ScheduleStatus::Executed { signature } => format!("executed {signature}"),
+        ScheduleStatus::Cancelled { signature } => format!("cancelled {signature}"),
+    }
+}
+
+fn revoked_names(item: &ScheduledRevocation) -> String {
+    item.revoke
+        .iter()
+        .map(|kind| kind.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn cmd_emission(config: &Config, rpc: &RpcClient, action: EmissionAction) -> Result<()> {
+    match action {
+        EmissionAction::Start {
+            mint,
+            to,
+            amount,
+            schedule,
+            cap,
+            from,
+            journal,
+        } => {
+            let authority = Credentials::load()?.signer(rpc.programs())?.pubkey();
+            let info = token::fetch_mint(rpc, &mint)?;
+            if info.mint.mint_authority != Some(authority) {
+                return Err(Error::InvalidInput(format!(
+                    "{authority} doesn't hold the mint authority of {mint}; an emission needs it \
+                     for as long as it runs"
+                )));
+            }
+            let recipient = match config.watch_wallet(&to) {
+                Some(watched) => watched.address,
+                None => to.parse()?,
+            };
+            let decimals = info.mint.decimals;
+            let amount = TokenAmount::parse(&amount, decimals)?;
+            let cap = TokenAmount::parse(&cap, decimals)?;
+            if amount.is_zero() || cap.raw() < amount.raw() {
+                return Err(Error::InvalidInput(
+                    "--amount must be above zero and no more than --cap".into(),
+                ));
+            }
+            let starts_at = match from {
+                Some(from) => time::parse_rfc3339(&from).ok_or_else(|| {
+                    Error::InvalidInput(format!("invalid --from '{from}', expected RFC 3339"))
+                })?,
+                None => solanaapp::launch::now_unix(),
+            };
+            let terms = emission::Terms {
+                mint,
+                program_id: info.program_id,
+                recipient,
+                amount,
+                schedule: schedule.parse()?,
+                cap,
+                starts_at,
+            };
+            let path = journal.unwrap_or_else(|| emission::default_path(&mint));
+            let state = emission::start(&path, terms)?;
+            println!("Emission journal: {}", path.display());
+            print_emission(&state);
+        }
+        EmissionAction::Run {
+            mint,
+            journal,
+            daemon,
+            interval,
+            alert_webhook,
+        } => {
+            let authority = Credentials::load()?.signer(rpc.programs())?;
+            let path = journal.unwrap_or_else(|| emission::default_path(&mint));
+            let mut emitter = Emitter::open(rpc, &*authority, &path)?;
+            let alert = |text: &str| {
+                eprintln!("{text}");
+                if let Some(url) = &alert_webhook
+                    && let Err(e) = emission::alert(url, &format!("emission of {mint}: {text}"))
+                {
+                    eprintln!("could not send alert: {e}");
+                }
+            };
+            // The same error every round is alerted once.
+            let mut last_error: Option<String> = None;
+            loop {
+                match emitter.poll(solanaapp::launch::now_unix()) {
+                    Ok(events) => {
+                        last_error = None;
+                        for event in events {
+                            match event {
+                                EmissionEvent::Failed { .. } | EmissionEvent::Capped { .. } => {
+                                    alert(&event.to_string())
+                                }
+                                _ => println!("{event}"),
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let text = e.to_string();
+                        if last_error.as_deref() != Some(text.as_str()) {
+                            alert(&text);
+                        }
+                        if !daemon {
+                            return Err(e);
+                        }
+                        last_error = Some(text);
+                    }
+                }
+                if !daemon || emitter.state().capped {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+            }
+        }
+        EmissionAction::Status { mint, journal } => {
+            let path = journal.unwrap_or_else(|| emission::default_path(&mint));
+            print_emission(&emission::load(&path)?);
+        }
+    }
+    Ok(())
+}
+
+fn print_emission(state: &EmissionState) {
+    let terms = &state.terms;
+    println!("Mint:       {}", terms.mint);
+    println!("Recipient:  {}", terms.recipient);
+    println!(
+        "Amount:     {} per '{}' (UTC)",
+        terms.amount, terms.schedule
+    );
+    println!("Minted:     {} of {} cap", state.minted, terms.cap);
+    if let Some(paid) = state.paid_until {
+        println!("Paid until: {}", time::format_rfc3339(paid));
+    }
+    if let Some(pending) = &state.pending {
+        println!(
+            "Pending:    {} due {}: {} (outcome not yet known)",
+            pending.amount,
+            time::format_rfc3339(pending.due),
+            pending.signature
+        );
+    }
+    if state.failures > 0 {
+        println!("Failures:   {} since the last mint landed", state.failures);
+    }
+    match (state.capped, state.next_due()) {
+        (true, _) => println!("Next:       none; the cap is reached"),
+        (false, Some(next)) => println!("Next:       {}", time::format_rfc3339(next)),
+        (false, None) => println!("Next:       never; the schedule matches no date"),
+    }
+}
+
+/// Reads `INDEX=PERCENT`.
+fn parse_fee_tier_share(input: &str) -> Result<(FeeTier, u32)> {
+    let (fee_tier, share) = input
+        .split_once('=')
+        .ok_or_else(|| Error::InvalidInput(format!("'{input}' is not INDEX=PERCENT")))?;
+    Ok((fee_tier.parse()?, amount::parse_percent_bps(share)?))
+}
+
+fn cmd_lp(config: &Config, rpc: &RpcClient, action: LpAction) -> Result<()> {
+    let cpmm = Cpmm::for_network(config.network);
+    let (mint, pool, extra, reset_baseline, json) = match action {
+        LpAction::Status {
+            mint,
+            pool,
+            wallets,
+            reset_baseline,
+            json,
+        } => (mint, pool, wallets, reset_baseline, json),
+        LpAction::Deploy {
+            mint,
+            token_amount,
+            quote_amount,
+            usd_price,
+            max_sol_move,
+            quote_mint,
+            fee_tiers,
+            tolerance,
+            overrides,
+            yes,
+        } => {
+            let quote_mint = quote_mint.unwrap_or(programs::WRAPPED_SOL_MINT);
+            return cmd_lp_deploy(
+                config,
+                rpc,
+                &cpmm,
+                &LiquidityDeployment {
+                    mint,
+                    token_amount,
+                    quote_mint,
+                    quote_amount,
+                    usd_price,
+                    max_sol_move,
+                    fee_tiers,
+                    tolerance,
+                    overrides,
+                    yes,
+                },
+            );
+        }
+        LpAction::Checklist { mint, json } => {
+            let info = token::fetch_mint(rpc, &mint)?;
+            let checks = config
+                .launch_checklist
+                .run(config, rpc, &mint, &info.mint)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&checks)?);
+            } else {
+                print_checklist(&checks, &[]);
+            }
+            return checklist::enforce(&checks, &[]);
+        }
+    };
+    let mut wallets = Vec::new();
+    if let Ok(keypair) = Credentials::load().and_then(|c| c.keypair()) {
+        wallets.push(keypair.pubkey());
+    }
+    for wallet in extra
+        .into_iter()
+        .chain(config.watch_wallets.iter().map(|w| w.address))
+    {
+        if !wallets.contains(&wallet) {
+            wallets.push(wallet);
+        }
+    }
+    let pools = match pool {
+        Some(address) => vec![(address, cpmm.fetch_pool(rpc, &address)?)],
+        None => cpmm.find_pools(rpc, &mint)?,
+    };
+    if pools.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "no Raydium CPMM pool trades {mint}"
+        )));
+    }
+    let now = solanaapp::launch::now_unix();
+    let mut report = Vec::new();
+    for (address, pool) in &pools {
+        let status = PoolStatus::load(rpc, address, pool, &mint, &wallets)?;
+        let baseline = match liquidity::load_baseline(address)? {
+            Some(baseline) if !reset_baseline => Some(baseline),
+            _ => match status.baseline(now) {
+                Some(baseline) => {
+                    liquidity::save_baseline(&baseline)?;
+                    Some(baseline)
+                }
+                None => None,
+            },
+        };
+        let performance = baseline
+            .as_ref()
+            .and_then(|baseline| status.performance(baseline));
+        report.push(serde_json::json!({
+            "status": status,
+            "baseline": baseline,
+            "performance": performance,
+        }));
+        if json {
+            continue;
+        }
+        let quote = if status.quote_mint == programs::WRAPPED_SOL_MINT {
+            "SOL".to_string()
+        } else {
+            status.quote_mint.to_string()
+        };
+        println!("Pool {address} (Raydium CPMM)");
+        println!(
+            "  reserves:  {} tokens + {} {quote}, {:.9} {quote} per token",
+            status.token_reserve,
+            status.quote_reserve,
+            status.price()
+        );
+        println!(
+            "  position:  {} of {} LP ({:.2}%)",
+            status.lp_held,
+            status.lp_supply,
+            status.share() * 100.0
+        );
+        let (Some(baseline), Some(performance)) = (baseline, performance) else {
+            println!("  fees:      nothing to measure while the pool has no LP supply");
+            continue;
+        };
+        println!(
+            "  since:     {}",
+            time::format_rfc3339(baseline.recorded_at)
+        );
+        println!(
+            "  fees:      +{:.4}% of the reserves, about {:.4} tokens + {:.9} {quote} of them yours",
+            performance.fee_growth * 100.0,
+            performance.fees_token,
+            performance.fees_quote
+        );
+        println!(
+            "  IL:        {:.2}% against holding, price {:.4}x",
+            performance.impermanent_loss * 100.0,
+            performance.price_ratio
+        );
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+struct LiquidityDeployment {
+    mint: Pubkey,
+    token_amount: Option<String>,
+    quote_mint: Pubkey,
+    quote_amount: Option<String>,
+    usd_price: Option<UsdPrice>,
+    max_sol_move: u32,
+    fee_tiers: Vec<(FeeTier, u32)>,
+    tolerance: u32,
+    overrides: Vec<checklist::Item>,
+    yes: bool,
+}
+
+fn print_checklist(checks: &[checklist::Check], overrides: &[checklist::Item]) {
+    println!("Launch checklist:");
+    for check in checks {
+        let mark = match (check.passed, overrides.contains(&check.item)) {
+            (true, _) => "ok",
+            (false, true) => "overridden",
+            (false, false) => "FAIL",
+        };
+        println!("  {:<10}  {:<20}  {}", mark, check.item, check.detail);
+    }
+}
+
+/// SOL's USD price, as the price API quotes wrapped SOL against USDC.
+fn sol_usd_price(config: &Config) -> Result<UsdPrice> {
+    let price = portfolio::fetch_usd_prices(config.price_api_url(), &[programs::WRAPPED_SOL_MINT])?
+        .get(&programs::WRAPPED_SOL_MINT)
+        .copied()
+        .ok_or_else(|| Error::Http(format!("{} has no price for SOL", config.price_api_url())))?;
+    UsdPrice::from_f64(price)
+}
+
+fn cmd_lp_deploy(
+    config: &Config,
+    rpc: &RpcClient,
+    cpmm: &Cpmm,
+    args: &LiquidityDeployment,
+) -> Result<()> {
+    let signer = Credentials::load()?.signer(rpc.programs())?;
+    let creator = signer.pubkey();
+    let token_info = token::fetch_mint(rpc, &args.mint)?;
+    let checks = config
+        .launch_checklist
+        .run(config, rpc, &args.mint, &token_info.mint)?;
+    print_checklist(&checks, &args.overrides);
+    checklist::enforce(&checks, &args.overrides)?;
+    let quote_info = token::fetch_mint(rpc, &args.quote_mint)?;
+    let token_decimals = token_info.mint.decimals;
+    let parse_token = |s: &str| TokenAmount::parse(s, token_decimals);
+    let parse_quote = |s: &str| TokenAmount::parse(s, quote_info.mint.decimals);
+    let (token_total, quote_total, locked_sol_usd) = match args.usd_price {
+        Some(usd_price) => {
+            if args.quote_mint != programs::WRAPPED_SOL_MINT {
+                return Err(Error::InvalidInput(
+                    "--usd-price pegs against SOL; leave out --quote-mint".into(),
+                ));
+            }
+            let sol_usd = sol_usd_price(config)?;
+            let (token, sol) = match (&args.token_amount, &args.quote_amount) {
+                (Some(token), None) => {
+                    let token = parse_token(token)?;
+                    (token, liquidity::pegged_sol(token, usd_price, sol_usd)?)
+                }
+                (None, Some(sol)) => {
+                    let sol = parse_quote(sol)?;
+                    let token = liquidity::pegged_tokens(sol, usd_price, sol_usd, token_decimals)?;
+                    (token, sol)
+                }
+                _ => {
+                    return Err(Error::InvalidInput(
+                        "with --usd-price give one of --token-amount and --quote-amount".into(),
+                    ));
+                }
+            };
+            println!(
+                "SOL at ${:.4}: {token} tokens + {sol} SOL open at ${usd_price} a token",
+                sol_usd.to_f64()
+            );
+            (token, sol, Some(sol_usd))
+        }
+        None => match (&args.token_amount, &args.quote_amount) {
+            (Some(token), Some(quote)) => (parse_token(token)?, parse_quote(quote)?, None),
+            _ => {
+                return Err(Error::InvalidInput(
+                    "give --token-amount and --quote-amount, or one of them and --usd-price".into(),
+                ));
+            }
+        },
+    };
+    let deployments =
+        liquidity::plan_deployments(token_total, quote_total, &args.fee_tiers, args.tolerance)?;
+
+    let token_account =
+        token::associated_token_address(&creator, &args.mint, &token_info.program_id);
+    let token_held = deposit::balance(rpc, &token_account, token_info.mint.decimals)?;
+    if token_held.raw() < token_total.raw() {
+        return Err(Error::InvalidInput(format!(
+            "{creator} holds {token_held} tokens, not the {token_total} to deposit"
+        )));
+    }
+    let wraps_sol = args.quote_mint == programs::WRAPPED_SOL_MINT;
+    if !wraps_sol {
+        let quote_account =
+            token::associated_token_address(&creator, &args.quote_mint, &quote_info.program_id);
+        let quote_held = deposit::balance(rpc, &quote_account, quote_info.mint.decimals)?;
+        if quote_held.raw() < quote_total.raw() {
+            return Err(Error::InvalidInput(format!(
+                "{creator} holds {quote_held} of {}, not the {quote_total} to deposit",
+                args.quote_mint
+            )));
+        }
+    }
+
+    let quote = if wraps_sol {
+        "SOL".to_string()
+    } else {
+        args.quote_mint.to_string()
+    };
+    println!(
+        "Opening at {:.12} {quote} per token:",
+        quote_total.raw() as f64 / token_total.raw() as f64
+            * 10f64.powi(i32::from(token_info.mint.decimals) - i32::from(quote_info.mint.decimals))
+    );
+    let mut planned = Vec::new();
+    let mut pool_fees = 0u64;
+    for deployment in &deployments {
+        let index = deployment.fee_tier.index;
+        let amm_config = cpmm.fetch_amm_config(rpc, index)?;
+        if amm_config.disable_create_pool {
+            return Err(Error::InvalidInput(format!(
+                "{} does not accept new pools",
+                deployment.fee_tier
+            )));
+        }
+        let pool = cpmm.pool_address(
+            &cpmm.amm_config_address(index),
+            &args.mint,
+            &args.quote_mint,
+        );
+        if rpc.get_account(&pool)?.value.is_some() {
+            return Err(Error::InvalidInput(format!(
+                "{} already has a pool for this pair: {pool}",
+                deployment.fee_tier
+            )));
+        }
+        pool_fees = pool_fees.saturating_add(amm_config.create_pool_fee);
+        println!(
+            "  {:<16} {:>7}  {} tokens + {} {quote}, {:.12}, trade fee {}, pool {pool}",
+            deployment.fee_tier.to_string(),
+            amount::format_bps(deployment.share_bps),
+            deployment.token,
+            deployment.quote,
+            deployment.price(),
+            amount::format_bps(amm_config.fee_bps()),
+        );
+        let ixs = cpmm.seed(
+            &creator,
+            index,
+            liquidity::Side {
+                mint: args.mint,
+                program: token_info.program_id,
+                amount: deployment.token.to_u64()?,
+            },
+            liquidity::Side {
+                mint: args.quote_mint,
+                program: quote_info.program_id,
+                amount: deployment.quote.to_u64()?,
+            },
+        );
+        planned.push((deployment.fee_tier, ixs));
+    }
+    if pool_fees > 0 {
+        println!(
+            "Creating the pools costs {} SOL on top",
+            TokenAmount::from_raw(pool_fees.into(), 9)
+        );
+    }
+    if wraps_sol {
+        // The deposit, the creation fees and every account the pools open
+        // all come out of SOL.
+        let pool_rent = liquidity::POOL_ACCOUNT_LENS
+            .iter()
+            .map(|&len| rpc.get_minimum_balance_for_rent_exemption(len))
+            .sum::<Result<u64>>()?;
+        let wrapped_rent = rpc.get_minimum_balance_for_rent_exemption(token::ACCOUNT_LEN)?;
+        let count = planned.len() as u64;
+        let needed = quote_total.to_u64()?
+            + pool_fees
+            + wrapped_rent
+            + count * (pool_rent + system::LAMPORTS_PER_SIGNATURE);
+        let balance = rpc.get_balance(&creator)?;
+        if balance < needed {
+            return Err(Error::InvalidInput(format!(
+                "{creator} holds {} SOL and needs about {} SOL: {quote_total} to wrap, \
+                 and the rest for pool fees, rent and transaction fees",
+                system::format_sol(balance),
+                system::format_sol(needed)
+            )));
+        }
+    }
+    if !args.yes && !confirm(&format!("Create {} pool(s)?", planned.len()))? {
+        return Ok(());
+    }
+    // The amounts stay as confirmed; a move in SOL since only changes the
+    // USD price they open at.
+    if let Some(locked) = locked_sol_usd {
+        let now = sol_usd_price(config)?;
+        let moved = locked.moved_bps(now);
+        if moved > u128::from(args.max_sol_move) {
+            let (locked, now) = (locked.to_f64(), now.to_f64());
+            eprintln!(
+                "warning: SOL moved {:.2}% since the amounts were worked out (${locked:.4} -> ${now:.4}); \
+                 the pools would open at ${:.8} a token",
+                (now / locked - 1.0).abs() * 100.0,
+                args.usd_price.map_or(0.0, UsdPrice::to_f64) * now / locked
+            );
+            if args.yes {
+                return Err(Error::InvalidInput(
+                    "SOL moved past --max-sol-move; run again to reprice".into(),
+                ));
+            }
+            if !confirm("Deploy at the confirmed amounts anyway?")? {
+                return Ok(());
+            }
+        }
+    }
+    // Signed only once confirmed, on a fresh blockhash, and every one
+    // simulated before the first is sent, so a pool that would fail stops
+    // the deployment before any is created.
+    let transactions = planned
+        .into_iter()
+        .map(|(fee_tier, ixs)| {
+            Ok((
+                fee_tier,
+                rpc.build_signed(&ixs, &creator, &[signer.as_ref()])?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for (i, (fee_tier, tx)) in transactions.iter().enumerate() {
+        match rpc.send_and_confirm(tx) {
+            Ok(signature) => println!("{fee_tier}: created, {signature}"),
+            Err(e) => {
+                let pending: Vec<String> = transactions[i + 1..]
+                    .iter()
+                    .map(|(fee_tier, _)| fee_tier.to_string())
+                    .collect();
+                if !pending.is_empty() {
+                    eprintln!("Not sent: {}", pending.join(", "));
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_schedule(config: &Config, rpc: &RpcClient, action: ScheduleAction) -> Result<()> {
+    let store = ScheduleStore::open_default()?;
+    match action {
+        ScheduleAction::Revoke {
+            mint,
+            mint_authority,
+            freeze_authority,
+            at,
+            at_slot,
+        } => {
+            let due = match (at, at_slot) {
+                (Some(at), _) => Due::At(time::parse_rfc3339(&at).ok_or_else(|| {
+                    Error::InvalidInput(format!("'{at}' is not an RFC 3339 date or time"))
+                })?),
+                (None, Some(slot)) => Due::Slot(slot),
+                (None, None) => unreachable!("clap requires --at or --at-slot"),
+            };
+            let mut revoke = Vec::new();
+            if mint_authority || !freeze_authority {
+                revoke.push(token::AuthorityType::MintTokens);
+            }
+            if freeze_authority {
+                revoke.push(token::AuthorityType::FreezeAccount);
+            }
+            let authority = Credentials::load()?.keypair()?;
+            eprintln!("Creating durable nonce account...");
+            let item = schedule::schedule(
+                rpc,
+                &authority,
+                store.new_id(),
+                config.network,
+                &mint,
+                revoke,
+                due,
+            )?;
+            store.save(&item)?;
+            println!(
+                "Scheduled {}: revoke {} authority of {mint}",
+                item.id,
+                revoked_names(&item)
+            );
+            println!("Due:       {}", describe_due(item.due));
+            println!("Nonce:     {}", item.nonce_account);
+            println!("Signature: {}", item.signature);
+            println!("Transaction (base64, publish as the commitment):");
+            println!("{}", item.transaction);
+            eprintln!(
+                "Keep `schedule run --daemon` running to broadcast it. Anyone holding the \
+                 transaction can broadcast it early; only `schedule cancel` withdraws it."
+            );
+        }
+        ScheduleAction::List => {
+            for item in store.list()? {
+                println!(
+                    "{}  {:<8}  {}  {:<12}  {:<25}  {}",
+                    item.id,
+                    item.network,
+                    item.mint,
+                    revoked_names(&item),
+                    describe_due(item.due),
+                    describe_status(&item.status)
+                );
+            }
+        }
+        ScheduleAction::Show { id } => {
+            let item = store.load(&id)?;
+            println!("Schedule:  {}", item.id);
+            println!("Network:   {}", item.network);
+            println!("Mint:      {}", item.mint);
+            println!("Authority: {}", item.authority);
+            println!("Revoke:    {}", revoked_names(&item));
+            println!("Due:       {}", describe_due(item.due));
+            println!("Nonce:     {}", item.nonce_account);
+            println!("Signature: {}", item.signature);
+            println!("Status:    {}", describe_status(&item.status));
+            println!("Transaction:");
+            println!("{}", item.transaction);
+        }
+        ScheduleAction::Run { daemon, interval } => loop {
+            let pending: Vec<_> = store
+                .list()?
+                .into_iter()
+                .filter(|i| i.network == config.network && i.status == ScheduleStatus::Pending)
+                .collect();
+            if !pending.is_empty() {
+                let now = solanaapp::launch::now_unix();
+                let slot = rpc.get_slot()?;
+                for mut item in pending.into_iter().filter(|i| i.is_due(now, slot)) {
+                    match schedule::execute(rpc, &mut item) {
+                        Ok(signature) => {
+                            store.save(&item)?;
+                            println!(
+                                "{}: revoked {} authority of {}: {signature}",
+                                item.id,
+                                revoked_names(&item),
+                                item.mint
+                            );
+                        }
+                        // Keep going: a daemon must not stop over one failed broadcast.
+                        Err(e) => eprintln!("{}: {e}", item.id),
+                    }
+                }
+            }
+            if !daemon {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_secs(interval));
+        },
+        ScheduleAction::Cancel { id } => {
+            let mut item = store.load(&id)?;
+            let authority = Credentials::load()?.keypair()?;
+            let signature = schedule::cancel(rpc, &authority, &mut item)?;
+            store.save(&item)?;
+            println!("Cancelled {id}; nonce advanced in {signature}");
+        }
+    }
+    Ok(())
+}
+
+fn print_proposal_signatures(item: &Proposal) -> Result<()> {
+    for key in item.signers()? {
+        let state = if item.signatures.contains_key(&key) {
+            "signed"
+        } else {
+            "missing"
+        };
+        println!("  {key}  {state}");
+    }
+    Ok(())
+}
+
+fn load_proposal(config: &Config, path: &std::path::Path) -> Result<Proposal> {
+    let item = Proposal::read_file(path)?;
+    if item.network != config.network {
+        return Err(Error::InvalidInput(format!(
+            "{} is a {} proposal; pass --network {}",
+            path.display(),
+            item.network,
+            item.network
+        )));
+    }
+    item.verify_signatures()?;
+    Ok(item)
+}
+
+fn cmd_proposal(config: &Config, rpc: &RpcClient, action: ProposalAction) -> Result<()> {
+    match action {
+        ProposalAction::Create {
+            mint,
+            update_authority,
+            name,
+            symbol,
+            uri,
+            new_update_authority,
+            immutable,
+            out,
+        } => {
+            let current = metadata::fetch_metadata(rpc, &mint)?
+                .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?;
+            let changed = name.is_some() || symbol.is_some() || uri.is_some();
+            if !changed && new_update_authority.is_none() && !immutable {
+                return Err(Error::InvalidInput(
+                    "nothing to update; pass --name, --symbol, --uri, --new-update-authority \
+                     or --immutable"
+                        .into(),
+                ));
+            }
+            let mut data = current.data;
+            if let Some(name) = name {
+                data.name = name;
+            }
+            if let Some(symbol) = symbol {
+                data.symbol = symbol;
+            }
+            if let Some(uri) = uri {
+                data.uri = uri;
+            }
+            let update = MetadataUpdate {
+                program_id: rpc.programs().metadata,
+                update_authority: update_authority.unwrap_or(current.update_authority),
+                args: metadata::UpdateMetadataArgs {
+                    data: changed.then_some(data),
+                    new_update_authority,
+                    primary_sale_happened: None,
+                    is_mutable: immutable.then_some(false),
+                },
+            };
+            let proposer = Credentials::load()?.keypair()?;
+            eprintln!("Creating durable nonce account...");
+            let item = proposal::create(rpc, &proposer, config.network, &mint, update)?;
+            item.write_file(&out)?;
+            println!("Proposal written to {}", out.display());
+            for line in &item.summary {
+                println!("  {line}");
+            }
+            println!("Nonce:     {}", item.nonce_account);
+            println!("Signatures:");
+            print_proposal_signatures(&item)?;
+            eprintln!(
+                "Send the file to the holder of {} to run `sign-proposal`, then \
+                 `proposal submit` it.",
+                item.update_authority
+            );
+        }
+        ProposalAction::Show { path } => {
+            let item = load_proposal(config, &path)?;
+            let update = item.update()?;
+            println!("Network:          {}", item.network);
+            println!("Mint:             {}", item.mint);
+            println!("Proposer:         {}", item.proposer);
+            println!("Update authority: {}", item.update_authority);
+            println!("Nonce:            {}", item.nonce_account);
+            println!(
+                "Created:          {}",
+                time::format_rfc3339(item.created_at)
+            );
+            println!("Summary (from the file):");
+            for line in &item.summary {
+                println!("  {line}");
+            }
+            println!("Decoded from the message:");
+            for line in proposal::describe(&update, None) {
+                println!("  {line}");
+            }
+            println!("Signatures:");
+            print_proposal_signatures(&item)?;
+        }
+        ProposalAction::Submit { path } => {
+            let item = load_proposal(config, &path)?;
+            item.update()?;
+            let signature =
+                with_metadata_backup(config, rpc, &item.mint, || proposal::submit(rpc, &item))?;
+            println!("Metadata of {} updated: {signature}", item.mint);
+        }
+        ProposalAction::Cancel { path } => {
+            let item = load_proposal(config, &path)?;
+            let proposer = Credentials::load()?.keypair()?;
+            let signature = proposal::cancel(rpc, &proposer, &item)?;
+            println!("Withdrew {}; nonce advanced in {signature}", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn cmd_signer(rpc: &RpcClient, spec: Option<&str>) -> Result<()> {
+    let credentials = Credentials::load()?;
+    let spec = spec.or(credentials.dev_signer.as_deref());
+    let signer = match spec {
+        Some(spec) => signer::open(&spec.parse()?, credentials.signer_token.clone())?,
+        None => credentials.signer(rpc.programs())?,
+    };
+    println!("Signer: {}", spec.unwrap_or("DEV_KEYPAIR"));
+    println!("Key:    {}", signer.pubkey());
+    if let Some(path) = &credentials.signing_policy {
+        println!("Policy: {path}");
+    }
+    Ok(())
+}
+
+fn cmd_verify_audit_log(path: Option<PathBuf>) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => AuditLog::default_path()?,
+    };
+    if !path.exists() {
+        return Err(Error::Config(format!(
+            "no audit log at {}; nothing has been submitted yet",
+            path.display()
+        )));
+    }
+    let verified = audit::verify(&path)?;
+    println!(
+        "{}: {} entries, chain intact",
+        path.display(),
+        verified.entries
+    );
+    println!("Head hash: {}", verified.head);
+    Ok(())
+}
+
+/// Shows what a proposal really does, decoded from its message and compared
+/// with the live metadata, before adding DEV_KEYPAIR's signature.
+fn cmd_sign_proposal(
+    config: &Config,
+    rpc: &RpcClient,
+    path: &std::path::Path,
+    yes: bool,
+) -> Result<()> {
+    let mut item = load_proposal(config, path)?;
+    let update = item.update()?;
+    let current = metadata::fetch_metadata(rpc, &item.mint)?;
+    let summary = proposal::describe(&update, current.as_ref());
+    println!("Mint:             {}", item.mint);
+    println!("Proposer:         {}", item.proposer);
+    println!("Update authority: {}", item.update_authority);
+    println!("Changes:");
+    for line in &summary {
+        println!("  {line}");
+    }
+    if summary != item.summary {
+        eprintln!(
+            "warning: the file's summary does not match its message against the current \
+             metadata; review the changes above, not the file's summary"
+        );
+    }
+    let signer = Credentials::load()?.signer(rpc.programs())?;
+    if item.signatures.contains_key(&signer.pubkey()) {
+        println!("{} has already signed", signer.pubkey());
+        return Ok(());
+    }
+    if !yes && !confirm(&format!("Sign as {}?", signer.pubkey()))? {
+        return Err(Error::InvalidInput("not signed".into()));
+    }
+    let signature = item.sign(&signer)?;
+    if let Some(audit) = rpc.audit() {
+        audit.record_message(&item.message_bytes()?, &[signer.pubkey()], Some(signature))?;
+    }
+    item.write_file(path)?;
+    println!("Signed {} as {}", path.display(), signer.pubkey());
+    let missing = item.missing()?;
+    if missing.is_empty() {
+        eprintln!("Every signature is in; run `proposal submit` to broadcast it.");
+    } else {
+        println!("Signatures:");
+        print_proposal_signatures(&item)?;
+    }
+    Ok(())
+}
+
+/// The instructions a proposal asks the vault to sign.
+fn proposal_instructions(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    operation: ProposeOperation,
+) -> Result<Vec<Instruction>> {
+    let expect_authority = |what: &str, current: Option<Pubkey>, mint: &Pubkey| {
+        if current != Some(*vault) {
+            return Err(Error::InvalidInput(format!(
+                "{what} authority of {mint} is {}, not the vault {vault}",
+                current.map_or("revoked".to_string(), |k| k.to_string())
+            )));
+        }
+        Ok(())
+    };
+    match operation {
+        ProposeOperation::MintTo {
+            mint,
+            to,
+            amount,
+            max_supply,
+        } => {
+            let account = token::fetch_mint(rpc, &mint)?;
+            expect_authority("mint", account.mint.mint_authority, &mint)?;
+            let amount = TokenAmount::parse(&amount, account.mint.decimals)?;
+            let cap = max_supply
+                .map(|cap| TokenAmount::parse(&cap, account.mint.decimals))
+                .transpose()?;
+            let raw = account.mint.mintable(amount, cap)?;
+            let destination = token::associated_token_address(&to, &mint, &account.program_id);
+            Ok(vec![
+                token::create_associated_token_account_idempotent(
+                    vault,
+                    &to,
+                    &mint,
+                    &account.program_id,
+                ),
+                token::mint_to_checked(
+                    &account.program_id,
+                    &mint,
+                    &destination,
+                    vault,
+                    raw,
+                    account.mint.decimals,
+                ),
+            ])
+        }
+        ProposeOperation::RevokeMint { mint } => {
+            let account = token::fetch_mint(rpc, &mint)?;
+            expect_authority("mint", account.mint.mint_authority, &mint)?;
+            Ok(vec![token::set_authority(
+                &account.program_id,
+                &mint,
+                vault,
+                token::AuthorityType::MintTokens,
+                None,
+            )])
+        }
+        ProposeOperation::RevokeFreeze { mint } => {
+            let account = token::fetch_mint(rpc, &mint)?;
+            expect_authority("freeze", account.mint.freeze_authority, &mint)?;
+            Ok(vec![token::set_authority(
+                &account.program_id,
+                &mint,
+                vault,
+                token::AuthorityType::FreezeAccount,
+                None,
+            )])
+        }
+        ProposeOperation::UpdateMetadata {
+            mint,
+            name,
+            symbol,
+            uri,
+            immutable,
+        } => {
+            let current = metadata::fetch_metadata(rpc, &mint)?
+                .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?;
+            rehost::ensure_update_authority(&current, &mint, vault)?;
+            let changed = name.is_some() || symbol.is_some() || uri.is_some();
+            let mut data = current.data;
+            if let Some(name) = name {
+                data.name = name;
+            }
+            if let Some(symbol) = symbol {
+                data.symbol = symbol;
+            }
+            if let Some(uri) = uri {
+                data.uri = uri;
+            }
+            data.validate()?;
+            if !changed && !immutable {
+                return Err(Error::InvalidInput(
+                    "nothing to update; pass --name, --symbol, --uri or --immutable".into(),
+                ));
+            }
+            Ok(vec![metadata::update_metadata_accounts_v2(
+                &rpc.programs().metadata,
+                &mint,
+                vault,
+                changed.then_some(&data),
+                None,
+                None,
+                immutable.then_some(false),
+            )])
+        }
+    }
 }
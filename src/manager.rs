@@ -0,0 +1,395 @@
+//! Runs the launch pipeline. `SolanaTokenManager` holds only the RPC client
+//! and the paying wallet; everything about a particular launch lives in its
+//! `LaunchSession`, so one manager can drive any number of sessions.
+
+use std::thread;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use crate::broadcast::Broadcaster;
+use crate::error::{Error, Result};
+use crate::events::{Event, EventLog};
+use crate::keypair::{Keypair, Signature};
+use crate::launch::now_unix;
+use crate::metadata::{self, Metadata};
+use crate::phrase;
+use crate::pipeline::{self, LaunchAccounts};
+use crate::rpc::RpcClient;
+use crate::session::{LaunchSession, PendingTransaction, SessionStore, Spend, Step, SupplyAccount};
+use crate::tx::Instruction;
+use crate::vanity;
+
+/// How long a sent transaction can still land: its blockhash expires after
+/// 150 blocks, about a minute, plus margin for slow slots.
+pub(crate) const BLOCKHASH_LIFETIME_SECS: i64 = 90;
+/// Recent payer signatures searched for a transaction of unknown outcome.
+const RECOVERY_SEARCH_LIMIT: usize = 100;
+/// A confirmed transaction can take a moment to be served by `getTransaction`.
+const SPEND_LOOKUP_ATTEMPTS: usize = 5;
+const SPEND_LOOKUP_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct SolanaTokenManager<'a> {
+    rpc: &'a RpcClient,
+    payer: &'a Keypair,
+    events: Option<&'a EventLog>,
+    broadcaster: Option<&'a Broadcaster>,
+    store: Option<&'a SessionStore>,
+    supply_signer: Option<&'a Keypair>,
+    mint_passphrase: Option<&'a str>,
+}
+
+impl<'a> SolanaTokenManager<'a> {
+    pub fn new(rpc: &'a RpcClient, payer: &'a Keypair) -> Self {
+        SolanaTokenManager {
+            rpc,
+            payer,
+            events: None,
+            broadcaster: None,
+            store: None,
+            supply_signer: None,
+            mint_passphrase: None,
+        }
+    }
+
+    /// Reports step progress and transaction signatures to `events`.
+    pub fn with_events(mut self, events: &'a EventLog) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Sends every transaction through `broadcaster` as well as the RPC.
+    pub fn with_broadcaster(mut self, broadcaster: &'a Broadcaster) -> Self {
+        self.broadcaster = Some(broadcaster);
+        self
+    }
+
+    /// Signs the creation of an auxiliary supply account.
+    pub fn with_supply_signer(mut self, keypair: &'a Keypair) -> Self {
+        self.supply_signer = Some(keypair);
+        self
+    }
+
+    /// Derives the mint key of sessions with a `mint_index` from `passphrase`.
+    pub fn with_mint_passphrase(mut self, passphrase: &'a str) -> Self {
+        self.mint_passphrase = Some(passphrase);
+        self
+    }
+
+    /// Saves the session as soon as a transaction is sent, so its signature
+    /// survives a crash while waiting for confirmation.
+    pub fn with_store(mut self, store: &'a SessionStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    fn emit(&self, session: &LaunchSession, event: Event) {
+        if let Some(events) = self.events {
+            events.emit(&session.id, event);
+        }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        self.rpc
+    }
+
+    /// The mint keypair of a session, derived from the payer's secret and the
+    /// session ID, or from the project passphrase for sessions with a
+    /// `mint_index`, so an interrupted launch can be resumed without storing
+    /// keys.
+    pub fn mint_keypair(&self, session: &LaunchSession) -> Result<Keypair> {
+        let keypair = match session.mint_index {
+            Some(index) => {
+                let passphrase = self.mint_passphrase.ok_or_else(|| {
+                    Error::Config(format!(
+                        "session {} derives its mint from MINT_PASSPHRASE, which is not set",
+                        session.id
+                    ))
+                })?;
+                phrase::mint_keypair(passphrase, index)?
+            }
+            None => {
+                let seed = self.mint_seed(session);
+                match session.mint_salt {
+                    Some(salt) => vanity::derive(&seed, salt),
+                    None => Keypair::from_seed(&seed),
+                }
+            }
+        };
+        if let Some(mint) = session.mint
+            && mint != keypair.pubkey()
+        {
+            return Err(Error::InvalidInput(format!(
+                "session {} created mint {mint}, but its mint key now derives to {}; \
+                 is MINT_PASSPHRASE the one used to start it?",
+                session.id,
+                keypair.pubkey()
+            )));
+        }
+        Ok(keypair)
+    }
+
+    /// The secret seed a session's mint key comes from; vanity searches
+    /// start from it.
+    pub fn mint_seed(&self, session: &LaunchSession) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(b"launch-solana mint")
+            .chain_update(self.payer.to_bytes())
+            .chain_update(session.id.as_bytes())
+            .finalize()
+            .into()
+    }
+
+    fn check_session(&self, session: &LaunchSession) -> Result<()> {
+        if session.authority != self.payer.pubkey() {
+            return Err(Error::InvalidInput(format!(
+                "session {} belongs to wallet {}, not {}",
+                session.id,
+                session.authority,
+                self.payer.pubkey()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Runs one step and records it in the session. The caller persists the
+    /// session afterwards, whether the step succeeded or not.
+    pub fn execute(&self, session: &mut LaunchSession, step: Step) -> Result<Signature> {
+        self.check_session(session)?;
+        match &session.last_error {
+            Some(error) => self.emit(
+                session,
+                Event::StepRetried {
+                    step,
+                    previous_error: error.clone(),
+                },
+            ),
+            None => self.emit(session, Event::StepStarted { step }),
+        }
+        let result = match self.recover(session, step) {
+            Ok(Some(signature)) => Ok(signature),
+            Ok(None) => self.run_step(session, step),
+            Err(e) => Err(e),
+        };
+        match &result {
+            Ok(signature) => {
+                let spend = self.spend(signature);
+                session.complete(step, *signature, spend, now_unix());
+                self.emit(
+                    session,
+                    Event::Confirmed {
+                        step,
+                        signature: *signature,
+                    },
+                );
+                if let (true, Some(mint)) = (session.is_complete(), session.mint) {
+                    self.emit(session, Event::Completed { mint });
+                }
+            }
+            Err(e) => {
+                // A failed transaction cannot land later; anything else
+                // (a timeout, a dropped connection) leaves it in doubt.
+                if matches!(e, Error::TransactionFailed { .. }) {
+                    session.pending = None;
+                }
+                session.fail(e, now_unix());
+                self.emit(
+                    session,
+                    Event::Failed {
+                        step,
+                        error: e.to_string(),
+                    },
+                );
+            }
+        }
+        result
+    }
+
+    /// Settles a transaction of this step whose outcome was lost, so a retry
+    /// cannot repeat a step that already happened. Waits out its blockhash
+    /// first, then looks for it among the payer's recent signatures.
+    fn recover(&self, session: &mut LaunchSession, step: Step) -> Result<Option<Signature>> {
+        let Some(pending) = session.pending.filter(|p| p.step == step) else {
+            return Ok(None);
+        };
+        let wait = pending.sent_at + BLOCKHASH_LIFETIME_SECS - now_unix();
+        if wait > 0 {
+            thread::sleep(Duration::from_secs(wait as u64));
+        }
+        let wanted = pending.signature.to_string();
+        let recent = self.rpc.get_signatures_for_address(
+            &self.payer.pubkey(),
+            None,
+            RECOVERY_SEARCH_LIMIT,
+        )?;
+        session.pending = None;
+        match recent.iter().find(|info| info.signature == wanted) {
+            Some(info) if info.err.is_none() => {
+                if step == Step::CreateTokenAccount {
+                    let accounts = LaunchAccounts::new(
+                        self.payer.pubkey(),
+                        self.mint_keypair(session)?.pubkey(),
+                        &session.params,
+                    );
+                    session.token_account = Some(accounts.token_account);
+                }
+                self.emit(
+                    session,
+                    Event::Recovered {
+                        step,
+                        signature: pending.signature,
+                    },
+                );
+                Ok(Some(pending.signature))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn run_step(&self, session: &mut LaunchSession, step: Step) -> Result<Signature> {
+        let mint_keypair = self.mint_keypair(session)?;
+        let accounts =
+            LaunchAccounts::new(self.payer.pubkey(), mint_keypair.pubkey(), &session.params);
+        if step != Step::CreateMint && session.mint.is_none() {
+            return Err(Error::InvalidInput(format!(
+                "session {} has no mint; run the earlier steps first",
+                session.id
+            )));
+        }
+        let mint_rent = match step {
+            Step::CreateMint => self
+                .rpc
+                .get_minimum_balance_for_rent_exemption(session.params.mint_len()?)?,
+            _ => 0,
+        };
+        let mut instructions = session.params.compute_budget_instructions()?;
+        if step == Step::CreateMetadata {
+            // Creating metadata that already exists fails with an opaque
+            // program error, so an existing account is updated instead.
+            let address =
+                metadata::metadata_address(&session.params.metadata_program(), &accounts.mint);
+            let existing = match self.rpc.get_account(&address)?.value {
+                Some(account) => Some(Metadata::unpack(&account.data)?),
+                None => None,
+            };
+            if existing.is_some() {
+                self.emit(
+                    session,
+                    Event::MetadataExists {
+                        mint: accounts.mint,
+                    },
+                );
+            }
+            instructions.extend(pipeline::metadata_instructions(
+                &session.params,
+                &accounts,
+                existing.as_ref(),
+            )?);
+        } else {
+            instructions.extend(pipeline::step_instructions(
+                &session.params,
+                &accounts,
+                step,
+                mint_rent,
+            )?);
+        }
+
+        match step {
+            Step::CreateMint => {
+                session.mint = Some(accounts.mint);
+                self.send(session, step, &instructions, &[self.payer, &mint_keypair])
+            }
+            Step::CreateTokenAccount => {
+                let mut signers = vec![self.payer];
+                if let Some(SupplyAccount::Auxiliary { address, .. }) =
+                    session.params.supply_account
+                {
+                    let keypair = self
+                        .supply_signer
+                        .filter(|k| k.pubkey() == address)
+                        .ok_or_else(|| {
+                            Error::InvalidInput(format!(
+                                "the supply account {address} has to sign its creation; \
+                                 pass its keypair"
+                            ))
+                        })?;
+                    signers.push(keypair);
+                }
+                let signature = self.send(session, step, &instructions, &signers)?;
+                session.token_account = Some(accounts.token_account);
+                Ok(signature)
+            }
+            _ => self.send(session, step, &instructions, &[self.payer]),
+        }
+    }
+
+    /// What a landed transaction cost the payer, read back from the
+    /// cluster. Best effort: `None` if it can't be read yet.
+    fn spend(&self, signature: &Signature) -> Option<Spend> {
+        for attempt in 0..SPEND_LOOKUP_ATTEMPTS {
+            if attempt > 0 {
+                thread::sleep(SPEND_LOOKUP_INTERVAL);
+            }
+            match self.rpc.get_transaction(&signature.to_string()) {
+                Ok(Some(tx)) => {
+                    let meta = tx.meta?;
+                    let before = *meta.pre_balances.first()?;
+                    let after = *meta.post_balances.first()?;
+                    return Some(Spend {
+                        fee: meta.fee,
+                        rent: before.saturating_sub(after).saturating_sub(meta.fee),
+                    });
+                }
+                // Not served at the history commitment yet.
+                Ok(None) => continue,
+                Err(_) => return None,
+            }
+        }
+        None
+    }
+
+    fn send(
+        &self,
+        session: &mut LaunchSession,
+        step: Step,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Signature> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let (tx, units_consumed) =
+            self.rpc
+                .sign_simulated(instructions, &self.payer.pubkey(), blockhash, signers)?;
+        if let Some(units_consumed) = units_consumed {
+            self.emit(
+                session,
+                Event::Simulated {
+                    step,
+                    units_consumed,
+                },
+            );
+        }
+        let signature = match self.broadcaster {
+            Some(broadcaster) => broadcaster.send(self.rpc, &tx)?,
+            None => self.rpc.send_transaction(&tx)?,
+        };
+        self.emit(session, Event::TransactionSent { step, signature });
+        session.pending = Some(PendingTransaction {
+            step,
+            signature,
+            sent_at: now_unix(),
+        });
+        if let Some(store) = self.store {
+            store.save(session)?;
+        }
+        // Revoking can't be undone, so it waits for the critical commitment.
+        let commitment = match step {
+            Step::RevokeMintAuthority => self.rpc.critical_commitment(),
+            _ => self.rpc.commitment(),
+        };
+        match self.broadcaster {
+            Some(broadcaster) => broadcaster.confirm(self.rpc, &signature, commitment)?,
+            None => self.rpc.confirm_transaction_at(&signature, commitment)?,
+        }
+        Ok(signature)
+    }
+}
@@ -0,0 +1,113 @@
+//! Launch manifests: several related tokens, say a token, its rewards
+//! token and an LP incentive token, launched in one run by
+//! `plan apply-manifest` with one wallet on one network.
+//!
+//! ```yaml
+//! network: devnet
+//! tokens:
+//!   - label: main
+//!     token:
+//!       name: Example
+//!       symbol: EXM
+//!       supply: 1_000_000_000
+//!   - label: rewards
+//!     token:
+//!       name: Example Rewards
+//!       symbol: EXMR
+//!       supply: 100_000_000
+//!     authorities:
+//!       mint: keep
+//! ```
+//!
+//! Each entry takes the `token`, `metadata`, `allocations` and
+//! `authorities` sections of a [`Plan`]; the label names it in the report.
+//! Entries are launched in the order written.
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::network::Network;
+use crate::plan::{AllocationSpec, AuthorityPolicy, MetadataSpec, Plan, TokenSpec};
+use crate::yaml;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestToken {
+    /// Short name for the token within the manifest, e.g. `rewards`.
+    pub label: String,
+    pub token: TokenSpec,
+    #[serde(default)]
+    pub metadata: MetadataSpec,
+    #[serde(default)]
+    pub allocations: Vec<AllocationSpec>,
+    #[serde(default)]
+    pub authorities: AuthorityPolicy,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// The cluster every token is for; applying it elsewhere is refused.
+    #[serde(default)]
+    pub network: Option<Network>,
+    pub tokens: Vec<ManifestToken>,
+}
+
+impl Manifest {
+    pub fn parse(source: &str) -> Result<Self> {
+        serde_json::from_value(yaml::parse(source)?).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+
+    /// Each token as a plan of its own, with its label.
+    pub fn plans(&self) -> Vec<(String, Plan)> {
+        self.tokens
+            .iter()
+            .map(|t| {
+                let plan = Plan {
+                    network: self.network,
+                    token: t.token.clone(),
+                    metadata: t.metadata.clone(),
+                    allocations: t.allocations.clone(),
+                    authorities: t.authorities.clone(),
+                };
+                (t.label.clone(), plan)
+            })
+            .collect()
+    }
+
+    /// Checks every plan, and that labels and symbols are not repeated:
+    /// tokens launched together are told apart by them.
+    pub fn validate(&self) -> Result<()> {
+        if self.tokens.is_empty() {
+            return Err(Error::InvalidInput("the manifest lists no tokens".into()));
+        }
+        for (i, token) in self.tokens.iter().enumerate() {
+            if token.label.trim().is_empty() {
+                return Err(Error::InvalidInput(format!("token {}: empty label", i + 1)));
+            }
+            let earlier = &self.tokens[..i];
+            if earlier.iter().any(|t| t.label == token.label) {
+                return Err(Error::InvalidInput(format!(
+                    "label '{}' is used twice",
+                    token.label
+                )));
+            }
+            if let Some(other) = earlier
+                .iter()
+                .find(|t| t.token.symbol.eq_ignore_ascii_case(&token.token.symbol))
+            {
+                return Err(Error::InvalidInput(format!(
+                    "{} and {} both have the symbol {}",
+                    other.label, token.label, token.token.symbol
+                )));
+            }
+        }
+        for (label, plan) in self.plans() {
+            plan.validate().map_err(|e| match e {
+                Error::InvalidInput(message) => Error::InvalidInput(format!("{label}: {message}")),
+                e => e,
+            })?;
+        }
+        Ok(())
+    }
+}
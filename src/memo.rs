@@ -0,0 +1,47 @@
+//! SPL Memo program: free-form text recorded with a transaction, e.g. an
+//! invoice ID or "team allocation" on a transfer, so the purpose of a token
+//! movement can be read back from its history.
+
+use crate::error::{Error, Result};
+use crate::pubkey::Pubkey;
+use crate::tx::{AccountMeta, Instruction};
+
+pub const MEMO_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+/// The first memo program; still found in older history.
+pub const MEMO_V1_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+
+/// Longest memo accepted. Leaves room in the transaction for the transfer
+/// or mint it is attached to.
+pub const MAX_MEMO_LEN: usize = 256;
+
+pub fn is_memo_program(program_id: &Pubkey) -> bool {
+    *program_id == MEMO_PROGRAM_ID || *program_id == MEMO_V1_PROGRAM_ID
+}
+
+pub fn validate(text: &str) -> Result<()> {
+    if text.trim().is_empty() {
+        return Err(Error::InvalidInput("memo is empty".into()));
+    }
+    if text.len() > MAX_MEMO_LEN {
+        return Err(Error::InvalidInput(format!(
+            "memo is {} bytes, at most {MAX_MEMO_LEN} fit alongside a transfer",
+            text.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Records `text` with the transaction. The program checks that every
+/// listed signer signed, which ties the memo to them.
+pub fn memo(text: &str, signers: &[&Pubkey]) -> Instruction {
+    Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: signers
+            .iter()
+            .map(|key| AccountMeta::new_readonly(**key, true))
+            .collect(),
+        data: text.as_bytes().to_vec(),
+    }
+}
@@ -0,0 +1,437 @@
+//! Metaplex Token Metadata accounts and instructions. Builders take the
+//! program's address, as forks may deploy it elsewhere (see `programs`).
+
+#[cfg(feature = "native")]
+use base64::Engine;
+#[cfg(feature = "native")]
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native")]
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::layout::{Reader, Writer};
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::RpcClient;
+use crate::system::SYSTEM_PROGRAM_ID;
+use crate::tx::{AccountMeta, Instruction};
+
+pub const METADATA_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+pub const MAX_URI_LENGTH: usize = 200;
+/// Size the metadata program allocates for a fungible token's metadata
+/// account, whatever its name, symbol and URI.
+pub const MAX_METADATA_LEN: usize = 679;
+
+pub(crate) const KEY_METADATA_V1: u8 = 4;
+const IX_UPDATE_METADATA_ACCOUNT_V2: u8 = 15;
+const IX_VERIFY_COLLECTION: u8 = 18;
+const IX_VERIFY_SIZED_COLLECTION_ITEM: u8 = 30;
+const IX_CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+pub fn metadata_address(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", program_id.as_bytes(), mint.as_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// The master edition account of an NFT mint; collection parents have one.
+pub fn master_edition_address(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            program_id.as_bytes(),
+            mint.as_bytes(),
+            b"edition",
+        ],
+        program_id,
+    )
+    .0
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+fn read_creator(r: &mut Reader) -> Result<Creator> {
+    Ok(Creator {
+        address: r.pubkey()?,
+        verified: r.bool()?,
+        share: r.u8()?,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Collection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+/// Set on a collection parent's metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CollectionDetails {
+    /// A sized collection counting its verified items.
+    V1 {
+        size: u64,
+    },
+    V2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Uses {
+    pub use_method: u8,
+    pub remaining: u64,
+    pub total: u64,
+}
+
+/// The mutable portion of a metadata account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataV2 {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub collection: Option<Collection>,
+    pub uses: Option<Uses>,
+}
+
+impl DataV2 {
+    fn write(&self, w: &mut Writer) {
+        w.string(&self.name)
+            .string(&self.symbol)
+            .string(&self.uri)
+            .u16(self.seller_fee_basis_points)
+            .option(self.creators.as_ref(), |w, creators| {
+                w.vec(creators, |w, c| {
+                    w.pubkey(&c.address).bool(c.verified).u8(c.share);
+                });
+            })
+            .option(self.collection.as_ref(), |w, c| {
+                w.bool(c.verified).pubkey(&c.key);
+            })
+            .option(self.uses.as_ref(), |w, u| {
+                w.u8(u.use_method).u64(u.remaining).u64(u.total);
+            });
+    }
+
+    fn read(r: &mut Reader) -> Result<Self> {
+        Ok(DataV2 {
+            name: r.string()?,
+            symbol: r.string()?,
+            uri: r.string()?,
+            seller_fee_basis_points: r.u16()?,
+            creators: r.option(|r| r.vec(read_creator))?,
+            collection: r.option(|r| {
+                Ok(Collection {
+                    verified: r.bool()?,
+                    key: r.pubkey()?,
+                })
+            })?,
+            uses: r.option(|r| {
+                Ok(Uses {
+                    use_method: r.u8()?,
+                    remaining: r.u64()?,
+                    total: r.u64()?,
+                })
+            })?,
+        })
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let check = |field: &str, value: &str, max: usize| {
+            if value.len() > max {
+                Err(Error::InvalidInput(format!(
+                    "{field} is {} bytes, the maximum is {max}",
+                    value.len()
+                )))
+            } else {
+                Ok(())
+            }
+        };
+        check("name", &self.name, MAX_NAME_LENGTH)?;
+        check("symbol", &self.symbol, MAX_SYMBOL_LENGTH)?;
+        check("uri", &self.uri, MAX_URI_LENGTH)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Metadata {
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub data: DataV2,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub collection_details: Option<CollectionDetails>,
+}
+
+impl Metadata {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        if r.u8()? != KEY_METADATA_V1 {
+            return Err(Error::InvalidAccountData("not a metadata account".into()));
+        }
+        let update_authority = r.pubkey()?;
+        let mint = r.pubkey()?;
+        let name = r.string()?;
+        let symbol = r.string()?;
+        let uri = r.string()?;
+        let seller_fee_basis_points = r.u16()?;
+        let creators = r.option(|r| r.vec(read_creator))?;
+        let primary_sale_happened = r.bool()?;
+        let is_mutable = r.bool()?;
+        // Older accounts may end here; everything after is optional.
+        let _edition_nonce = r.option(|r| r.u8()).unwrap_or(None);
+        let _token_standard = r.option(|r| r.u8()).unwrap_or(None);
+        let collection = r
+            .option(|r| {
+                Ok(Collection {
+                    verified: r.bool()?,
+                    key: r.pubkey()?,
+                })
+            })
+            .unwrap_or(None);
+        let uses = r
+            .option(|r| {
+                Ok(Uses {
+                    use_method: r.u8()?,
+                    remaining: r.u64()?,
+                    total: r.u64()?,
+                })
+            })
+            .unwrap_or(None);
+        let collection_details = r
+            .option(|r| match r.u8()? {
+                0 => Ok(CollectionDetails::V1 { size: r.u64()? }),
+                1 => {
+                    r.u64()?;
+                    Ok(CollectionDetails::V2)
+                }
+                other => Err(Error::InvalidAccountData(format!(
+                    "unknown collection details version {other}"
+                ))),
+            })
+            .unwrap_or(None);
+
+        Ok(Metadata {
+            update_authority,
+            mint,
+            data: DataV2 {
+                name,
+                symbol,
+                uri,
+                seller_fee_basis_points,
+                creators,
+                collection,
+                uses,
+            },
+            primary_sale_happened,
+            is_mutable,
+            collection_details,
+        })
+    }
+}
+
+/// Reads the metadata account of `mint`, if one has been created.
+#[cfg(feature = "native")]
+pub fn fetch_metadata(rpc: &RpcClient, mint: &Pubkey) -> Result<Option<Metadata>> {
+    let address = metadata_address(&rpc.programs().metadata, mint);
+    match rpc.get_account(&address)?.value {
+        Some(account) => Metadata::unpack(&account.data).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Like [`fetch_metadata`], but reuses a read made within the client's
+/// cache TTL. Only for showing or auditing metadata; anything about to
+/// change it should read it live.
+#[cfg(feature = "native")]
+pub fn fetch_metadata_cached(rpc: &RpcClient, mint: &Pubkey) -> Result<Option<Metadata>> {
+    let Some(cache) = rpc.cache() else {
+        return fetch_metadata(rpc, mint);
+    };
+    let program = rpc.programs().metadata;
+    let (program_key, mint_key) = (program.to_string(), mint.to_string());
+    let key = [
+        rpc.url(),
+        "metadata",
+        program_key.as_str(),
+        mint_key.as_str(),
+    ];
+    let data =
+        match cache.get_entry(&key) {
+            Some(Value::String(encoded)) => Some(BASE64.decode(encoded).map_err(|e| {
+                Error::InvalidAccountData(format!("cached metadata for {mint}: {e}"))
+            })?),
+            Some(_) => None,
+            None => {
+                let data = rpc
+                    .get_account(&metadata_address(&program, mint))?
+                    .value
+                    .map(|a| a.data);
+                let entry = data
+                    .as_ref()
+                    .map_or(Value::Null, |d| Value::String(BASE64.encode(d)));
+                cache.put_entry(&key, &entry);
+                data
+            }
+        };
+    data.map(|d| Metadata::unpack(&d)).transpose()
+}
+
+/// The arguments of an `UpdateMetadataAccountV2` instruction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateMetadataArgs {
+    pub data: Option<DataV2>,
+    pub new_update_authority: Option<Pubkey>,
+    pub primary_sale_happened: Option<bool>,
+    pub is_mutable: Option<bool>,
+}
+
+impl UpdateMetadataArgs {
+    /// Decodes the data of an `UpdateMetadataAccountV2` instruction.
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        if r.u8()? != IX_UPDATE_METADATA_ACCOUNT_V2 {
+            return Err(Error::InvalidInput(
+                "not an UpdateMetadataAccountV2 instruction".into(),
+            ));
+        }
+        Ok(UpdateMetadataArgs {
+            data: r.option(DataV2::read)?,
+            new_update_authority: r.option(|r| r.pubkey())?,
+            primary_sale_happened: r.option(|r| r.bool())?,
+            is_mutable: r.option(|r| r.bool())?,
+        })
+    }
+
+    pub fn instruction(
+        &self,
+        program_id: &Pubkey,
+        mint: &Pubkey,
+        update_authority: &Pubkey,
+    ) -> Instruction {
+        update_metadata_accounts_v2(
+            program_id,
+            mint,
+            update_authority,
+            self.data.as_ref(),
+            self.new_update_authority.as_ref(),
+            self.primary_sale_happened,
+            self.is_mutable,
+        )
+    }
+}
+
+/// Builds an `UpdateMetadataAccountV2` instruction. Fields left as `None`
+/// keep their current on-chain value.
+pub fn update_metadata_accounts_v2(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    update_authority: &Pubkey,
+    data: Option<&DataV2>,
+    new_update_authority: Option<&Pubkey>,
+    primary_sale_happened: Option<bool>,
+    is_mutable: Option<bool>,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_UPDATE_METADATA_ACCOUNT_V2)
+        .option(data, |w, d| d.write(w))
+        .option(new_update_authority, |w, k| {
+            w.pubkey(k);
+        })
+        .option(primary_sale_happened, |w, v| {
+            w.bool(v);
+        })
+        .option(is_mutable, |w, v| {
+            w.bool(v);
+        });
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(metadata_address(program_id, mint), false),
+            AccountMeta::new_readonly(*update_authority, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// Builds a `CreateMetadataAccountV3` instruction for a fungible mint.
+pub fn create_metadata_accounts_v3(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    payer: &Pubkey,
+    update_authority: &Pubkey,
+    data: &DataV2,
+    is_mutable: bool,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_CREATE_METADATA_ACCOUNT_V3);
+    data.write(&mut w);
+    // No collection details: this is not a collection parent.
+    w.bool(is_mutable).option(None::<()>, |_, _| {});
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(metadata_address(program_id, mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*update_authority, update_authority == payer),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// Builds a `VerifyCollection` instruction marking `mint` a verified
+/// member of the collection whose parent NFT is `collection_mint`, or
+/// `VerifySizedCollectionItem` for a `sized` collection, whose count it
+/// also updates. `collection_authority` is the parent's update authority.
+pub fn verify_collection(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    collection_mint: &Pubkey,
+    collection_authority: &Pubkey,
+    payer: &Pubkey,
+    sized: bool,
+) -> Instruction {
+    let (ix, collection) = if sized {
+        (
+            IX_VERIFY_SIZED_COLLECTION_ITEM,
+            AccountMeta::new(metadata_address(program_id, collection_mint), false),
+        )
+    } else {
+        (
+            IX_VERIFY_COLLECTION,
+            AccountMeta::new_readonly(metadata_address(program_id, collection_mint), false),
+        )
+    };
+    let authority = if sized {
+        AccountMeta::new_readonly(*collection_authority, true)
+    } else {
+        AccountMeta::new(*collection_authority, true)
+    };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(metadata_address(program_id, mint), false),
+            authority,
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*collection_mint, false),
+            collection,
+            AccountMeta::new_readonly(master_edition_address(program_id, collection_mint), false),
+        ],
+        data: vec![ix],
+    }
+}
@@ -0,0 +1,138 @@
+//! Solana clusters, and other chains running the Solana VM, with their
+//! public endpoints. The SVM chains deploy the token and metadata programs
+//! at the Solana addresses; a chain that doesn't names them under
+//! `programs` in the config file.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    #[default]
+    Devnet,
+    Testnet,
+    Localnet,
+    /// Eclipse mainnet.
+    Eclipse,
+    #[serde(rename = "eclipse-testnet")]
+    EclipseTestnet,
+    /// SOON mainnet.
+    Soon,
+    #[serde(rename = "soon-testnet")]
+    SoonTestnet,
+}
+
+impl Network {
+    pub fn default_rpc_url(self) -> &'static str {
+        match self {
+            Network::Mainnet => "https://api.mainnet-beta.solana.com",
+            Network::Devnet => "https://api.devnet.solana.com",
+            Network::Testnet => "https://api.testnet.solana.com",
+            Network::Localnet => "http://127.0.0.1:8899",
+            Network::Eclipse => "https://mainnetbeta-rpc.eclipse.xyz",
+            Network::EclipseTestnet => "https://testnet.dev2.eclipsenetwork.xyz",
+            Network::Soon => "https://rpc.mainnet.soo.network/rpc",
+            Network::SoonTestnet => "https://rpc.testnet.soo.network/rpc",
+        }
+    }
+
+    /// Whether tokens on this network hold real value, so it is no place
+    /// to rehearse.
+    pub fn is_mainnet(self) -> bool {
+        matches!(self, Network::Mainnet | Network::Eclipse | Network::Soon)
+    }
+
+    /// Genesis hash of the public cluster; `None` for a local validator,
+    /// whose genesis is new every time it starts from scratch, and for the
+    /// SVM chains, which are only checked not to be a Solana cluster.
+    pub fn genesis_hash(self) -> Option<&'static str> {
+        match self {
+            Network::Mainnet => Some("5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d"),
+            Network::Devnet => Some("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG"),
+            Network::Testnet => Some("4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY"),
+            Network::Localnet
+            | Network::Eclipse
+            | Network::EclipseTestnet
+            | Network::Soon
+            | Network::SoonTestnet => None,
+        }
+    }
+
+    /// Fails unless `genesis_hash`, as reported by an RPC endpoint, belongs to
+    /// this cluster, so a mislabelled custom URL can't send a "devnet" launch
+    /// to mainnet.
+    pub fn check_genesis_hash(self, genesis_hash: &str) -> Result<()> {
+        let actual = [Network::Mainnet, Network::Devnet, Network::Testnet]
+            .into_iter()
+            .find(|n| n.genesis_hash() == Some(genesis_hash));
+        match (self.genesis_hash(), actual) {
+            (Some(expected), _) if expected == genesis_hash => Ok(()),
+            (None, None) => Ok(()),
+            (_, Some(actual)) => Err(Error::Config(format!(
+                "the RPC endpoint serves {actual}, not {self}; fix --url or rpc_url, or pass --network {actual}"
+            ))),
+            (Some(_), None) => Err(Error::Config(format!(
+                "the RPC endpoint serves an unknown cluster (genesis hash {genesis_hash}), not {self}; \
+                 use --network localnet for a private cluster"
+            ))),
+        }
+    }
+
+    /// Explorer link for `path` (e.g. `token/<mint>` or `tx/<signature>`):
+    /// Solscan, or the chain's own Solscan-style explorer.
+    pub fn explorer_url(self, path: &str) -> String {
+        let base = format!("https://solscan.io/{path}");
+        match self {
+            Network::Eclipse => format!("https://eclipsescan.xyz/{path}"),
+            Network::EclipseTestnet => format!("https://eclipsescan.xyz/{path}?cluster=testnet"),
+            Network::Soon => format!("https://explorer.soo.network/{path}"),
+            Network::SoonTestnet => format!("https://explorer.testnet.soo.network/{path}"),
+            Network::Mainnet => base,
+            Network::Devnet => format!("{base}?cluster=devnet"),
+            Network::Testnet => format!("{base}?cluster=testnet"),
+            Network::Localnet => format!(
+                "{base}?cluster=custom&customUrl={}",
+                Network::Localnet.default_rpc_url()
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Network::Mainnet => "mainnet",
+            Network::Devnet => "devnet",
+            Network::Testnet => "testnet",
+            Network::Localnet => "localnet",
+            Network::Eclipse => "eclipse",
+            Network::EclipseTestnet => "eclipse-testnet",
+            Network::Soon => "soon",
+            Network::SoonTestnet => "soon-testnet",
+        })
+    }
+}
+
+impl FromStr for Network {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "mainnet" | "mainnet-beta" => Ok(Network::Mainnet),
+            "devnet" => Ok(Network::Devnet),
+            "testnet" => Ok(Network::Testnet),
+            "localnet" | "localhost" => Ok(Network::Localnet),
+            "eclipse" | "eclipse-mainnet" => Ok(Network::Eclipse),
+            "eclipse-testnet" => Ok(Network::EclipseTestnet),
+            "soon" | "soon-mainnet" => Ok(Network::Soon),
+            "soon-testnet" => Ok(Network::SoonTestnet),
+            other => Err(Error::InvalidInput(format!("unknown network '{other}'"))),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Packing instructions into as few transactions as the runtime accepts.
+//!
+//! A transaction is limited three ways: its serialized size, the accounts it
+//! locks and the compute units it may use. Batches of transfers, token
+//! account creations or freezes are packed greedily against all three
+//! rather than at a fixed count per transaction, so small instructions fill
+//! a transaction and heavy ones don't overrun it.
+//!
+//! With address lookup tables, accounts found in a table cost one byte
+//! instead of 32 and the packer builds version 0 messages; the account lock
+//! limit then usually binds before the size does. Tables must already exist
+//! and hold the addresses; nothing here creates or extends them.
+//!
+//! Compute is estimated per instruction from the program it calls, erring
+//! high. No compute unit limit is packed, so a low estimate can't fail a
+//! transaction; sending sets one from a simulation, where it still fits.
+
+use crate::compute_budget::{COMPUTE_BUDGET_PROGRAM_ID, MAX_COMPUTE_UNIT_LIMIT};
+use crate::error::{Error, Result};
+use crate::memo::{MEMO_PROGRAM_ID, MEMO_V1_PROGRAM_ID};
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::RpcClient;
+use crate::system::SYSTEM_PROGRAM_ID;
+use crate::token::{ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use crate::tx::{
+    AccountMeta, Hash, Instruction, LookupTable, Message, MessageV0, PACKET_DATA_SIZE,
+    VersionedMessage,
+};
+
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("AddressLookupTab1e1111111111111111111111111");
+
+/// Slots a deactivated table stays usable for, as long as its deactivation
+/// slot is in the SlotHashes sysvar; only then can it be closed.
+pub const LOOKUP_TABLE_COOLDOWN_SLOTS: u64 = 512;
+const IX_CLOSE_LOOKUP_TABLE: u32 = 4;
+
+/// Accounts one transaction may lock, listed or loaded from tables.
+pub const MAX_TX_ACCOUNT_LOCKS: usize = 64;
+
+/// Table state before the addresses: type, deactivation slot, last extended
+/// slot and its start index, optional authority and padding.
+const LOOKUP_TABLE_META_LEN: usize = 56;
+const LOOKUP_TABLE_TYPE: u32 = 1;
+
+/// Units the runtime allows an instruction when no limit is requested.
+const DEFAULT_INSTRUCTION_UNITS: u32 = 200_000;
+
+/// Reads an address lookup table account.
+pub fn unpack_lookup_table(key: &Pubkey, data: &[u8]) -> Result<LookupTable> {
+    let bad = |why: &str| Error::InvalidAccountData(format!("{key}: {why}"));
+    if data.len() < LOOKUP_TABLE_META_LEN
+        || u32::from_le_bytes(data[..4].try_into().unwrap()) != LOOKUP_TABLE_TYPE
+    {
+        return Err(bad("not an address lookup table"));
+    }
+    let deactivation_slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    if deactivation_slot != u64::MAX {
+        return Err(bad("the lookup table is deactivated"));
+    }
+    let addresses = &data[LOOKUP_TABLE_META_LEN..];
+    if !addresses.len().is_multiple_of(32) {
+        return Err(bad("lookup table addresses are truncated"));
+    }
+    Ok(LookupTable {
+        key: *key,
+        addresses: addresses
+            .chunks_exact(32)
+            .map(|a| Pubkey::new(a.try_into().unwrap()))
+            .collect(),
+    })
+}
+
+/// A lookup table's deactivation slot, if it is deactivated, and its
+/// authority, if it has one left.
+pub fn lookup_table_status(key: &Pubkey, data: &[u8]) -> Result<(Option<u64>, Option<Pubkey>)> {
+    if data.len() < LOOKUP_TABLE_META_LEN
+        || u32::from_le_bytes(data[..4].try_into().unwrap()) != LOOKUP_TABLE_TYPE
+    {
+        return Err(Error::InvalidAccountData(format!(
+            "{key}: not an address lookup table"
+        )));
+    }
+    let deactivation_slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let authority = (data[21] == 1).then(|| Pubkey::new(data[22..54].try_into().unwrap()));
+    Ok((
+        (deactivation_slot != u64::MAX).then_some(deactivation_slot),
+        authority,
+    ))
+}
+
+/// Closes a deactivated lookup table once its cooldown is over, sending its
+/// rent to `recipient`.
+pub fn close_lookup_table(table: &Pubkey, authority: &Pubkey, recipient: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+        ],
+        data: IX_CLOSE_LOOKUP_TABLE.to_le_bytes().to_vec(),
+    }
+}
+
+#[cfg(feature = "native")]
+pub fn fetch_lookup_tables(rpc: &RpcClient, keys: &[Pubkey]) -> Result<Vec<LookupTable>> {
+    let accounts = rpc.get_multiple_accounts(keys, None)?.value;
+    keys.iter()
+        .zip(accounts)
+        .map(|(key, account)| {
+            let account = account.ok_or(Error::AccountNotFound(*key))?;
+            if account.owner != ADDRESS_LOOKUP_TABLE_PROGRAM_ID {
+                return Err(Error::InvalidInput(format!(
+                    "{key} is owned by {}, not the address lookup table program",
+                    account.owner
+                )));
+            }
+            unpack_lookup_table(key, &account.data)
+        })
+        .collect()
+}
+
+/// Compute units `ix` uses at most, roughly, judged by its program.
+pub fn estimate_units(ix: &Instruction) -> u32 {
+    match ix.program_id {
+        COMPUTE_BUDGET_PROGRAM_ID => 150,
+        SYSTEM_PROGRAM_ID => 500,
+        TOKEN_PROGRAM_ID => 6_500,
+        // Extensions such as transfer fees add work to most instructions.
+        TOKEN_2022_PROGRAM_ID => 20_000,
+        // Creating the account calls the token program several times.
+        ASSOCIATED_TOKEN_PROGRAM_ID => 40_000,
+        MEMO_PROGRAM_ID | MEMO_V1_PROGRAM_ID => 30_000,
+        _ => DEFAULT_INSTRUCTION_UNITS,
+    }
+}
+
+/// Packs instructions into transactions paid by one fee payer.
+#[derive(Debug, Clone)]
+pub struct Packer<'a> {
+    payer: Pubkey,
+    prefix: Vec<Instruction>,
+    tables: &'a [LookupTable],
+    max_items: Option<usize>,
+}
+
+impl<'a> Packer<'a> {
+    pub fn new(payer: Pubkey) -> Self {
+        Packer {
+            payer,
+            prefix: Vec::new(),
+            tables: &[],
+            max_items: None,
+        }
+    }
+
+    /// Instructions every transaction starts with, such as advancing a
+    /// durable nonce.
+    pub fn with_prefix(mut self, prefix: Vec<Instruction>) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Tables to load accounts from; any makes the messages version 0.
+    pub fn with_lookup_tables(mut self, tables: &'a [LookupTable]) -> Self {
+        self.tables = tables;
+        self
+    }
+
+    /// Caps the items per transaction below what would fit.
+    pub fn with_max_items(mut self, max_items: Option<usize>) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    pub fn message(
+        &self,
+        instructions: &[Instruction],
+        recent_blockhash: Hash,
+    ) -> VersionedMessage {
+        if self.tables.is_empty() {
+            VersionedMessage::Legacy(Message::new(instructions, &self.payer, recent_blockhash))
+        } else {
+            VersionedMessage::V0(MessageV0::new(
+                instructions,
+                &self.payer,
+                recent_blockhash,
+                self.tables,
+            ))
+        }
+    }
+
+    /// Why a transaction of `instructions` would be refused, if it would.
+    pub fn exceeds(&self, instructions: &[Instruction]) -> Option<String> {
+        let message = self.message(instructions, Hash::default());
+        let signatures = message.signer_keys().len();
+        // One byte counts the signatures while there are fewer than 128.
+        let size = 1 + signatures * 64 + message.serialize().len();
+        if size > PACKET_DATA_SIZE {
+            return Some(format!(
+                "{size} bytes, over the {PACKET_DATA_SIZE}-byte limit"
+            ));
+        }
+        let accounts = message.account_count();
+        if accounts > MAX_TX_ACCOUNT_LOCKS {
+            return Some(format!(
+                "{accounts} accounts, over the {MAX_TX_ACCOUNT_LOCKS}-account limit"
+            ));
+        }
+        let units: u64 = instructions
+            .iter()
+            .map(|ix| u64::from(estimate_units(ix)))
+            .sum();
+        if units > u64::from(MAX_COMPUTE_UNIT_LIMIT) {
+            return Some(format!(
+                "about {units} compute units, over the {MAX_COMPUTE_UNIT_LIMIT}-unit limit"
+            ));
+        }
+        None
+    }
+
+    /// An empty transaction to add items to.
+    pub fn batch<T>(&self) -> Batch<'_, 'a, T> {
+        Batch {
+            packer: self,
+            items: Vec::new(),
+            instructions: self.prefix.clone(),
+        }
+    }
+
+    /// Packs `items`, each with the instructions that must share its
+    /// transaction, in order into as few transactions as fit.
+    pub fn pack<T>(
+        &self,
+        items: impl IntoIterator<Item = (T, Vec<Instruction>)>,
+    ) -> Result<Vec<(Vec<T>, Vec<Instruction>)>> {
+        let mut packed = Vec::new();
+        let mut batch = self.batch();
+        for (item, group) in items {
+            if let Err((item, group)) = batch.push(item, group)? {
+                packed.push(std::mem::replace(&mut batch, self.batch()).into_parts());
+                if batch.push(item, group)?.is_err() {
+                    return Err(Error::InvalidInput(
+                        "at least one item per transaction is needed".into(),
+                    ));
+                }
+            }
+        }
+        if !batch.is_empty() {
+            packed.push(batch.into_parts());
+        }
+        Ok(packed)
+    }
+}
+
+/// One transaction being filled.
+#[derive(Debug, Clone)]
+pub struct Batch<'p, 'a, T> {
+    packer: &'p Packer<'a>,
+    items: Vec<T>,
+    instructions: Vec<Instruction>,
+}
+
+/// An item a batch had no room for, handed back.
+pub type Refused<T> = (T, Vec<Instruction>);
+
+impl<T> Batch<'_, '_, T> {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Adds `item` if its instructions fit, or hands it back. An item that
+    /// can't fit even in an empty transaction is an error.
+    pub fn push(
+        &mut self,
+        item: T,
+        group: Vec<Instruction>,
+    ) -> Result<std::result::Result<(), Refused<T>>> {
+        if self
+            .packer
+            .max_items
+            .is_some_and(|max| self.items.len() >= max)
+        {
+            return Ok(Err((item, group)));
+        }
+        let before = self.instructions.len();
+        self.instructions.extend(group);
+        match self.packer.exceeds(&self.instructions) {
+            None => {
+                self.items.push(item);
+                Ok(Ok(()))
+            }
+            Some(why) if self.items.is_empty() => Err(Error::InvalidInput(format!(
+                "an item's instructions don't fit in one transaction: {why}"
+            ))),
+            Some(_) => {
+                let group = self.instructions.split_off(before);
+                Ok(Err((item, group)))
+            }
+        }
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn into_parts(self) -> (Vec<T>, Vec<Instruction>) {
+        (self.items, self.instructions)
+    }
+}
@@ -0,0 +1,123 @@
+//! Solana Pay transfer requests: `solana:` links a wallet opens as a
+//! prefilled payment, e.g.
+//! `solana:<recipient>?amount=12.5&spl-token=<mint>&label=Coffee%20Shop`.
+//! A merchant shows one as a QR code at the till or puts it behind a pay
+//! button; the wallet fills in the recipient, token and amount and asks
+//! the customer to approve.
+//!
+//! A reference key makes a payment findable: it rides along as a read-only
+//! account of the transfer, so `getSignaturesForAddress` on the reference
+//! returns the customer's transaction.
+
+use sha2::{Digest, Sha256};
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::memo;
+use crate::pubkey::Pubkey;
+
+pub const SCHEME: &str = "solana";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferRequest {
+    /// The wallet paid; its associated token account receives the tokens.
+    pub recipient: Pubkey,
+    /// Left out to let the customer enter an amount.
+    pub amount: Option<TokenAmount>,
+    /// The token paid in; `None` requests SOL.
+    pub spl_token: Option<Pubkey>,
+    pub references: Vec<Pubkey>,
+    /// The merchant, shown by the wallet.
+    pub label: Option<String>,
+    /// What the payment is for, shown by the wallet.
+    pub message: Option<String>,
+    /// Recorded on-chain with the payment, e.g. an order ID.
+    pub memo: Option<String>,
+}
+
+impl TransferRequest {
+    pub fn new(recipient: Pubkey) -> Self {
+        TransferRequest {
+            recipient,
+            amount: None,
+            spl_token: None,
+            references: Vec::new(),
+            label: None,
+            message: None,
+            memo: None,
+        }
+    }
+
+    /// Rejects what wallets would refuse: a zero amount, or a memo too long
+    /// to fit beside the transfer.
+    pub fn validate(&self) -> Result<()> {
+        if self.amount.is_some_and(TokenAmount::is_zero) {
+            return Err(Error::InvalidInput(
+                "a payment amount must be above zero; leave it out to let the payer choose".into(),
+            ));
+        }
+        if let Some(text) = &self.memo {
+            memo::validate(text)?;
+        }
+        Ok(())
+    }
+
+    /// The `solana:` URL, fields in the order the specification lists them.
+    pub fn to_url(&self) -> String {
+        let mut params: Vec<(&str, String)> = Vec::new();
+        if let Some(amount) = self.amount {
+            params.push(("amount", amount.to_string()));
+        }
+        if let Some(mint) = self.spl_token {
+            params.push(("spl-token", mint.to_string()));
+        }
+        for reference in &self.references {
+            params.push(("reference", reference.to_string()));
+        }
+        for (name, value) in [
+            ("label", &self.label),
+            ("message", &self.message),
+            ("memo", &self.memo),
+        ] {
+            if let Some(value) = value {
+                params.push((name, encode(value)));
+            }
+        }
+        let query: Vec<String> = params
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect();
+        if query.is_empty() {
+            format!("{SCHEME}:{}", self.recipient)
+        } else {
+            format!("{SCHEME}:{}?{}", self.recipient, query.join("&"))
+        }
+    }
+}
+
+/// A reference key no other payment uses, derived from the request and the
+/// time rather than kept: nothing ever signs with it.
+pub fn unique_reference(recipient: &Pubkey, mint: &Pubkey, nanos: u128) -> Pubkey {
+    let digest: [u8; 32] = Sha256::new()
+        .chain_update(b"launch-solana pay reference")
+        .chain_update(recipient.as_bytes())
+        .chain_update(mint.as_bytes())
+        .chain_update(nanos.to_le_bytes())
+        .finalize()
+        .into();
+    Pubkey::new(digest)
+}
+
+/// Percent-encodes everything but the characters URLs leave as they are.
+fn encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
@@ -0,0 +1,90 @@
+//! Mint keys derived from a project passphrase, so a team can regenerate
+//! the same mint for a rehearsal and for the real launch, and back its key
+//! up as a phrase instead of a keypair file.
+//!
+//! The derivation can be reproduced with any PBKDF2 implementation:
+//!
+//! ```text
+//! seed = PBKDF2-HMAC-SHA256(
+//!     password   = the passphrase as UTF-8, exactly as given,
+//!     salt       = "launch-solana mint" followed by the index as 4 big-endian bytes,
+//!     iterations = 100000,
+//!     length     = 32 bytes)
+//! mint = the ed25519 keypair whose secret seed is `seed`
+//! ```
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::keypair::Keypair;
+
+pub const ITERATIONS: u32 = 100_000;
+const SALT_PREFIX: &[u8] = b"launch-solana mint";
+/// Shorter phrases are refused; the mint key is only as strong as the phrase.
+pub const MIN_PASSPHRASE_LEN: usize = 12;
+
+const BLOCK_LEN: usize = 64;
+
+/// HMAC-SHA256 with the key already absorbed into the inner and outer states.
+struct Hmac {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl Hmac {
+    fn new(key: &[u8]) -> Self {
+        let mut block = [0u8; BLOCK_LEN];
+        if key.len() > BLOCK_LEN {
+            block[..32].copy_from_slice(&Sha256::digest(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let pad = |byte: u8| -> Sha256 {
+            let padded: Vec<u8> = block.iter().map(|b| b ^ byte).collect();
+            Sha256::new().chain_update(padded)
+        };
+        Hmac {
+            inner: pad(0x36),
+            outer: pad(0x5c),
+        }
+    }
+
+    fn mac(&self, message: &[u8]) -> [u8; 32] {
+        let inner = self.inner.clone().chain_update(message).finalize();
+        self.outer.clone().chain_update(inner).finalize().into()
+    }
+}
+
+/// HMAC-SHA256 of `message` under `key`.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    Hmac::new(key).mac(message)
+}
+
+/// PBKDF2-HMAC-SHA256 producing one 32-byte block.
+pub fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let hmac = Hmac::new(password);
+    let mut block = salt.to_vec();
+    block.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac.mac(&block);
+    let mut out = u;
+    for _ in 1..iterations {
+        u = hmac.mac(&u);
+        for (o, x) in out.iter_mut().zip(u) {
+            *o ^= x;
+        }
+    }
+    out
+}
+
+/// The mint keypair at `index` for `passphrase`.
+pub fn mint_keypair(passphrase: &str, index: u32) -> Result<Keypair> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(Error::InvalidInput(format!(
+            "the mint passphrase must be at least {MIN_PASSPHRASE_LEN} characters"
+        )));
+    }
+    let mut salt = SALT_PREFIX.to_vec();
+    salt.extend_from_slice(&index.to_be_bytes());
+    let seed = pbkdf2_sha256(passphrase.as_bytes(), &salt, ITERATIONS);
+    Ok(Keypair::from_seed(&seed))
+}
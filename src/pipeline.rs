@@ -0,0 +1,235 @@
+//! Instructions for each launch step. Pure functions of the launch
+//! parameters and addresses, shared by the RPC-driven manager and by
+//! handoff transactions built for a browser wallet.
+
+use crate::error::{Error, Result};
+use crate::extension;
+use crate::memo;
+use crate::metadata::{self, DataV2, Metadata};
+use crate::pubkey::Pubkey;
+use crate::session::{GroupRole, LaunchParams, Step, SupplyAccount};
+use crate::system;
+use crate::token::{self, AuthorityType};
+use crate::tx::Instruction;
+
+/// Rent-exempt minimum for a legacy mint account, for use when no RPC
+/// endpoint is available to ask.
+pub const MINT_RENT_LAMPORTS: u64 = system::rent_exempt_minimum(token::MINT_LEN);
+
+/// The accounts a launch creates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchAccounts {
+    /// Pays for the launch and holds the mint and update authorities.
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    /// Receives the supply.
+    pub token_account: Pubkey,
+}
+
+impl LaunchAccounts {
+    pub fn new(authority: Pubkey, mint: Pubkey, params: &LaunchParams) -> Self {
+        LaunchAccounts {
+            authority,
+            mint,
+            token_account: params.supply_token_account(&authority, &mint),
+        }
+    }
+}
+
+/// Instructions for one step. `mint_rent` is only used by `CreateMint`.
+pub fn step_instructions(
+    params: &LaunchParams,
+    accounts: &LaunchAccounts,
+    step: Step,
+    mint_rent: u64,
+) -> Result<Vec<Instruction>> {
+    let program_id = params.token_program;
+    let LaunchAccounts {
+        authority,
+        mint,
+        token_account,
+    } = *accounts;
+
+    Ok(match step {
+        Step::CreateMint => {
+            let mut ixs = vec![system::create_account(
+                &authority,
+                &mint,
+                mint_rent,
+                params.mint_space()? as u64,
+                &program_id,
+            )];
+            if let Some(hook) = &params.transfer_hook {
+                ixs.push(extension::initialize_transfer_hook(
+                    &program_id,
+                    &mint,
+                    Some(&authority),
+                    Some(hook),
+                ));
+            }
+            match params.group {
+                Some(GroupRole::Group { .. }) => ixs.push(extension::initialize_group_pointer(
+                    &program_id,
+                    &mint,
+                    Some(&authority),
+                    Some(&mint),
+                )),
+                Some(GroupRole::Member { .. }) => {
+                    ixs.push(extension::initialize_group_member_pointer(
+                        &program_id,
+                        &mint,
+                        Some(&authority),
+                        Some(&mint),
+                    ))
+                }
+                None => {}
+            }
+            ixs.push(token::initialize_mint2(
+                &program_id,
+                &mint,
+                params.decimals(),
+                &authority,
+                params.freeze_authority.then_some(&authority),
+            ));
+            match params.group {
+                Some(GroupRole::Group { max_size }) => ixs.push(extension::initialize_group(
+                    &program_id,
+                    &mint,
+                    &authority,
+                    Some(&authority),
+                    max_size,
+                )),
+                Some(GroupRole::Member { group }) => ixs.push(extension::initialize_member(
+                    &program_id,
+                    &mint,
+                    &authority,
+                    &group,
+                    &authority,
+                )),
+                None => {}
+            }
+            ixs
+        }
+        Step::CreateTokenAccount => match params.supply_account {
+            None => vec![token::create_associated_token_account_idempotent(
+                &authority,
+                &authority,
+                &mint,
+                &program_id,
+            )],
+            Some(SupplyAccount::Associated { owner }) => {
+                vec![token::create_associated_token_account_idempotent(
+                    &authority,
+                    &owner,
+                    &mint,
+                    &program_id,
+                )]
+            }
+            Some(SupplyAccount::Auxiliary { address, owner }) => {
+                let len = params.token_account_len();
+                vec![
+                    system::create_account(
+                        &authority,
+                        &address,
+                        system::rent_exempt_minimum(len),
+                        len as u64,
+                        &program_id,
+                    ),
+                    token::initialize_account3(&program_id, &address, &mint, &owner),
+                ]
+            }
+        },
+        Step::MintSupply => {
+            let mut ixs = vec![token::mint_to_checked(
+                &program_id,
+                &mint,
+                &token_account,
+                &authority,
+                params.supply.to_u64()?,
+                params.decimals(),
+            )];
+            if let Some(text) = &params.memo {
+                memo::validate(text)?;
+                ixs.push(memo::memo(text, &[&authority]));
+            }
+            ixs
+        }
+        Step::CreateMetadata => metadata_instructions(params, accounts, None)?,
+        Step::RevokeMintAuthority => vec![token::set_authority(
+            &program_id,
+            &mint,
+            &authority,
+            AuthorityType::MintTokens,
+            None,
+        )],
+    })
+}
+
+/// Instructions that give the mint the launch's metadata: a create when
+/// `existing`, the mint's metadata account as read from the cluster, is
+/// `None`, else an update of it, which the authority must be able to make.
+pub fn metadata_instructions(
+    params: &LaunchParams,
+    accounts: &LaunchAccounts,
+    existing: Option<&Metadata>,
+) -> Result<Vec<Instruction>> {
+    let LaunchAccounts {
+        authority, mint, ..
+    } = *accounts;
+    let data = DataV2 {
+        name: params.name.clone(),
+        symbol: params.symbol.clone(),
+        uri: params.uri.clone(),
+        ..DataV2::default()
+    };
+    data.validate()?;
+    let Some(existing) = existing else {
+        return Ok(vec![metadata::create_metadata_accounts_v3(
+            &params.metadata_program(),
+            &mint,
+            &authority,
+            &authority,
+            &authority,
+            &data,
+            params.is_mutable,
+        )]);
+    };
+    if existing.update_authority != authority {
+        return Err(Error::InvalidInput(format!(
+            "{mint} already has metadata, and its update authority is {}, not {authority}",
+            existing.update_authority
+        )));
+    }
+    if !existing.is_mutable {
+        return Err(Error::InvalidInput(format!(
+            "{mint} already has metadata (\"{}\", {}) and it is immutable",
+            existing.data.name, existing.data.symbol
+        )));
+    }
+    Ok(vec![metadata::update_metadata_accounts_v2(
+        &params.metadata_program(),
+        &mint,
+        &authority,
+        Some(&data),
+        None,
+        None,
+        (!params.is_mutable).then_some(false),
+    )])
+}
+
+/// Instructions for every step of a launch, in order.
+pub fn launch_instructions(
+    params: &LaunchParams,
+    accounts: &LaunchAccounts,
+    steps: &[Step],
+    mint_rent: u64,
+) -> Result<Vec<Instruction>> {
+    if steps.is_empty() {
+        return Err(Error::InvalidInput("no launch steps to build".into()));
+    }
+    let mut instructions = Vec::new();
+    for step in steps {
+        instructions.extend(step_instructions(params, accounts, *step, mint_rent)?);
+    }
+    Ok(instructions)
+}
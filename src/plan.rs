@@ -0,0 +1,609 @@
+//! Launch plans: a YAML file describing what a token should look like
+//! (mint, supply, metadata, allocations and which authorities survive),
+//! compared against the chain to find what is still missing.
+//!
+//! ```yaml
+//! token:
+//!   name: Example
+//!   symbol: EXM
+//!   decimals: 6
+//!   supply: 1_000_000_000
+//!   program: token-2022      # or token (the default)
+//!   mint: <address>          # filled in once launched
+//! metadata:
+//!   uri: https://example.com/exm.json   # or description/image/links to upload,
+//!   localized:                          # with translations, tags and category
+//!     es:
+//!       name: Ejemplo
+//!   tags:
+//!     - meme
+//!   category: meme
+//! allocations:
+//!   - wallet: <address>
+//!     amount: 150_000_000
+//!     label: team
+//! authorities:
+//!   mint: revoke             # keep | revoke (default keep)
+//!   freeze: revoke           # keep | revoke (default revoke)
+//!   metadata: immutable      # mutable | immutable (default mutable)
+//! ```
+//!
+//! Some differences can be fixed by sending a transaction ([`Fix`]);
+//! others, like the wrong decimals or an authority that is already gone,
+//! can't, and are reported for a person to decide on.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::amount::{self, TokenAmount};
+use crate::error::{Error, Result};
+use crate::launch::{Links, Listing, Localized};
+use crate::metadata::{self, DataV2, Metadata};
+use crate::network::Network;
+use crate::programs::Programs;
+use crate::pubkey::Pubkey;
+use crate::session::LaunchParams;
+use crate::token::{self, AuthorityType, MintAccount};
+use crate::tx::Instruction;
+use crate::yaml;
+#[cfg(feature = "native")]
+use crate::{extension, rpc::RpcClient, token::TokenAccount};
+
+/// Amounts may be written as YAML numbers or strings like `1_000_000`.
+fn amount_text<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Text {
+        Number(u64),
+        Text(String),
+    }
+    Ok(match Text::deserialize(deserializer)? {
+        Text::Number(n) => n.to_string(),
+        Text::Text(s) => s,
+    })
+}
+
+fn default_decimals() -> u8 {
+    9
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenProgram {
+    #[default]
+    Token,
+    #[serde(rename = "token-2022")]
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn id(self, programs: &Programs) -> Pubkey {
+        programs.token_program(self == TokenProgram::Token2022)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenSpec {
+    pub name: String,
+    pub symbol: String,
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+    /// Whole tokens.
+    #[serde(deserialize_with = "amount_text")]
+    pub supply: String,
+    #[serde(default)]
+    pub program: TokenProgram,
+    /// The launched mint; until it is set, `plan apply` launches one.
+    #[serde(default)]
+    pub mint: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetadataSpec {
+    /// Without it the JSON is built from the fields below and uploaded.
+    pub uri: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub website: Option<String>,
+    pub twitter: Option<String>,
+    pub telegram: Option<String>,
+    pub discord: Option<String>,
+    /// Name and description by language tag.
+    #[serde(default)]
+    pub localized: BTreeMap<String, Localized>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AllocationSpec {
+    pub wallet: Pubkey,
+    /// Whole tokens the wallet should hold at least.
+    #[serde(deserialize_with = "amount_text")]
+    pub amount: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Keep {
+    Keep,
+    Revoke,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mutability {
+    #[default]
+    Mutable,
+    Immutable,
+}
+
+fn keep() -> Keep {
+    Keep::Keep
+}
+
+fn revoke() -> Keep {
+    Keep::Revoke
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorityPolicy {
+    #[serde(default = "keep")]
+    pub mint: Keep,
+    #[serde(default = "revoke")]
+    pub freeze: Keep,
+    #[serde(default)]
+    pub metadata: Mutability,
+}
+
+impl Default for AuthorityPolicy {
+    fn default() -> Self {
+        AuthorityPolicy {
+            mint: Keep::Keep,
+            freeze: Keep::Revoke,
+            metadata: Mutability::Mutable,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Plan {
+    /// The cluster the plan is for; applying it elsewhere is refused.
+    #[serde(default)]
+    pub network: Option<Network>,
+    pub token: TokenSpec,
+    #[serde(default)]
+    pub metadata: MetadataSpec,
+    #[serde(default)]
+    pub allocations: Vec<AllocationSpec>,
+    #[serde(default)]
+    pub authorities: AuthorityPolicy,
+}
+
+impl Plan {
+    pub fn parse(source: &str) -> Result<Self> {
+        serde_json::from_value(yaml::parse(source)?).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+
+    pub fn supply(&self) -> Result<TokenAmount> {
+        if self.token.decimals > amount::MAX_DECIMALS {
+            return Err(Error::InvalidInput(format!(
+                "plan: decimals must be at most {}",
+                amount::MAX_DECIMALS
+            )));
+        }
+        let supply = TokenAmount::parse(&self.token.supply, self.token.decimals)?;
+        supply.to_u64()?;
+        Ok(supply)
+    }
+
+    /// Checks everything that can be checked without the chain: amounts,
+    /// metadata field lengths and that the allocations fit in the supply.
+    pub fn validate(&self) -> Result<()> {
+        let supply = self.supply()?;
+        self.on_chain_data(&self.metadata.uri.clone().unwrap_or_default())
+            .validate()?;
+        let mut allocated = TokenAmount::zero(self.token.decimals);
+        for (i, allocation) in self.allocations.iter().enumerate() {
+            let amount = TokenAmount::parse(&allocation.amount, self.token.decimals)
+                .map_err(|e| Error::InvalidInput(format!("allocation {}: {e}", i + 1)))?;
+            allocated = allocated
+                .checked_add(amount)
+                .ok_or_else(|| Error::InvalidInput("allocations overflow".into()))?;
+        }
+        if allocated > supply {
+            return Err(Error::InvalidInput(format!(
+                "allocations add up to {allocated}, more than the supply of {supply}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn on_chain_data(&self, uri: &str) -> DataV2 {
+        DataV2 {
+            name: self.token.name.clone(),
+            symbol: self.token.symbol.clone(),
+            uri: uri.to_string(),
+            ..DataV2::default()
+        }
+    }
+
+    /// The launch that creates the plan's mint under `programs`; the URI is
+    /// left empty when the plan has none, for the caller to upload the JSON.
+    pub fn launch_params(&self, programs: &Programs) -> Result<LaunchParams> {
+        Ok(LaunchParams {
+            name: self.token.name.clone(),
+            symbol: self.token.symbol.clone(),
+            uri: self.metadata.uri.clone().unwrap_or_default(),
+            supply: self.supply()?,
+            token_program: self.token.program.id(programs),
+            freeze_authority: self.authorities.freeze == Keep::Keep,
+            revoke_mint_authority: self.authorities.mint == Keep::Revoke,
+            is_mutable: self.authorities.metadata == Mutability::Mutable,
+            description: self.metadata.description.clone(),
+            image: self.metadata.image.clone(),
+            links: Links {
+                website: self.metadata.website.clone(),
+                twitter: self.metadata.twitter.clone(),
+                telegram: self.metadata.telegram.clone(),
+                discord: self.metadata.discord.clone(),
+            },
+            listing: Listing {
+                localized: self.metadata.localized.clone(),
+                tags: self.metadata.tags.clone(),
+                category: self.metadata.category.clone(),
+            },
+            transfer_hook: None,
+            group: None,
+            memo: None,
+            supply_account: None,
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            metadata_program: programs.metadata_override(),
+        })
+    }
+}
+
+/// The part of the chain a plan is compared against.
+#[derive(Debug, Clone)]
+pub struct ChainState {
+    pub mint: Pubkey,
+    pub account: MintAccount,
+    pub metadata: Option<Metadata>,
+    /// Where the cluster deploys the programs involved.
+    pub programs: Programs,
+    /// Each allocation wallet's associated account balance, in plan order.
+    pub balances: Vec<u64>,
+    /// Moving tokens needs the hook's extra accounts, which are not resolved.
+    pub transfer_hook: bool,
+}
+
+#[cfg(feature = "native")]
+impl ChainState {
+    pub fn fetch(rpc: &RpcClient, mint: &Pubkey, plan: &Plan) -> Result<Self> {
+        let account = token::fetch_mint(rpc, mint)?;
+        let mut balances = Vec::new();
+        for allocation in &plan.allocations {
+            let ata =
+                token::associated_token_address(&allocation.wallet, mint, &account.program_id);
+            balances.push(match rpc.get_account(&ata)?.value {
+                Some(a) => TokenAccount::unpack(&a.data)?.amount,
+                None => 0,
+            });
+        }
+        Ok(ChainState {
+            mint: *mint,
+            transfer_hook: extension::find(&account.extensions, extension::TRANSFER_HOOK).is_some(),
+            metadata: metadata::fetch_metadata(rpc, mint)?,
+            programs: *rpc.programs(),
+            account,
+            balances,
+        })
+    }
+}
+
+/// A transaction that brings the chain closer to the plan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Fix {
+    MintSupply { amount: TokenAmount },
+    UpdateMetadata { data: DataV2 },
+    Allocate { wallet: Pubkey, amount: TokenAmount },
+    Revoke { authority: AuthorityType },
+    MakeImmutable,
+}
+
+impl Fix {
+    /// Instructions for `authority`, which must hold the authority the fix
+    /// uses; allocations are sent from its associated account.
+    pub fn instructions(&self, state: &ChainState, authority: &Pubkey) -> Result<Vec<Instruction>> {
+        let mint = &state.mint;
+        let program_id = state.account.program_id;
+        let decimals = state.account.mint.decimals;
+        Ok(match self {
+            Fix::MintSupply { amount } => {
+                let destination = token::associated_token_address(authority, mint, &program_id);
+                vec![
+                    token::create_associated_token_account_idempotent(
+                        authority,
+                        authority,
+                        mint,
+                        &program_id,
+                    ),
+                    token::mint_to_checked(
+                        &program_id,
+                        mint,
+                        &destination,
+                        authority,
+                        state.account.mint.mintable(*amount, None)?,
+                        decimals,
+                    ),
+                ]
+            }
+            Fix::UpdateMetadata { data } => vec![metadata::update_metadata_accounts_v2(
+                &state.programs.metadata,
+                mint,
+                authority,
+                Some(data),
+                None,
+                None,
+                None,
+            )],
+            Fix::Allocate { wallet, amount } => {
+                let source = token::associated_token_address(authority, mint, &program_id);
+                let destination = token::associated_token_address(wallet, mint, &program_id);
+                vec![
+                    token::create_associated_token_account_idempotent(
+                        authority,
+                        wallet,
+                        mint,
+                        &program_id,
+                    ),
+                    token::transfer_checked(
+                        &program_id,
+                        &source,
+                        mint,
+                        &destination,
+                        authority,
+                        amount.to_u64()?,
+                        decimals,
+                    ),
+                ]
+            }
+            Fix::Revoke { authority: kind } => vec![token::set_authority(
+                &program_id,
+                mint,
+                authority,
+                *kind,
+                None,
+            )],
+            Fix::MakeImmutable => vec![metadata::update_metadata_accounts_v2(
+                &state.programs.metadata,
+                mint,
+                authority,
+                None,
+                None,
+                None,
+                Some(false),
+            )],
+        })
+    }
+}
+
+impl Fix {
+    /// Whether it can't be undone, so is worth waiting to see finalized.
+    pub fn is_critical(&self) -> bool {
+        matches!(self, Fix::Revoke { .. } | Fix::MakeImmutable)
+    }
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fix::MintSupply { amount } => write!(f, "mint {amount}"),
+            Fix::UpdateMetadata { .. } => write!(f, "update metadata"),
+            Fix::Allocate { wallet, amount } => write!(f, "send {amount} to {wallet}"),
+            Fix::Revoke { authority: kind } => write!(f, "revoke {} authority", kind.as_str()),
+            Fix::MakeImmutable => write!(f, "make metadata immutable"),
+        }
+    }
+}
+
+/// One way the chain differs from the plan.
+#[derive(Debug, Clone, Serialize)]
+pub struct Difference {
+    pub description: String,
+    /// `None` when no transaction can fix it.
+    pub fix: Option<Fix>,
+}
+
+impl Difference {
+    fn fixable(description: String, fix: Fix) -> Self {
+        Difference {
+            description,
+            fix: Some(fix),
+        }
+    }
+
+    fn unfixable(description: String) -> Self {
+        Difference {
+            description,
+            fix: None,
+        }
+    }
+}
+
+fn describe(key: Option<Pubkey>) -> String {
+    key.map_or("revoked".to_string(), |k| k.to_string())
+}
+
+/// Compares `state` with `plan`, as seen by `authority`, the wallet that
+/// would sign the fixes. Fixes come in the order they must be sent: supply
+/// before the mint authority is revoked, metadata before it is locked.
+pub fn diff(plan: &Plan, state: &ChainState, authority: &Pubkey) -> Result<Vec<Difference>> {
+    let mint = &state.account.mint;
+    let mut out = Vec::new();
+    let mut fixes = Vec::new();
+
+    let program_id = plan.token.program.id(&state.programs);
+    if state.account.program_id != program_id {
+        out.push(Difference::unfixable(format!(
+            "mint is owned by {}, plan wants {program_id}",
+            state.account.program_id,
+        )));
+    }
+    if mint.decimals != plan.token.decimals {
+        // Nothing else can be compared in the plan's units.
+        out.push(Difference::unfixable(format!(
+            "mint has {} decimals, plan wants {}",
+            mint.decimals, plan.token.decimals
+        )));
+        return Ok(out);
+    }
+    let decimals = mint.decimals;
+    let amount = |raw: u64| TokenAmount::from_raw(raw as u128, decimals);
+
+    let supply = plan.supply()?;
+    let current = amount(mint.supply);
+    if current > supply {
+        out.push(Difference::unfixable(format!(
+            "supply is {current}, more than the planned {supply}"
+        )));
+    } else if let Some(missing) = supply.checked_sub(current).filter(|m| !m.is_zero()) {
+        let description = format!("supply is {current}, plan wants {supply}");
+        fixes.push(if mint.mint_authority == Some(*authority) {
+            Difference::fixable(description, Fix::MintSupply { amount: missing })
+        } else {
+            Difference::unfixable(format!(
+                "{description}; mint authority is {}",
+                describe(mint.mint_authority)
+            ))
+        });
+    }
+
+    let can_update =
+        |metadata: &Metadata| metadata.is_mutable && metadata.update_authority == *authority;
+    match &state.metadata {
+        None => out.push(Difference::unfixable("mint has no metadata account".into())),
+        Some(current) => {
+            let wanted = plan.metadata.uri.as_deref().unwrap_or(&current.data.uri);
+            let mut mismatched = Vec::new();
+            for (field, have, want) in [
+                ("name", &current.data.name, &plan.token.name),
+                ("symbol", &current.data.symbol, &plan.token.symbol),
+                ("uri", &current.data.uri, &wanted.to_string()),
+            ] {
+                if have != want {
+                    mismatched.push(format!("{field} is {have:?}, plan wants {want:?}"));
+                }
+            }
+            if !mismatched.is_empty() {
+                let description = mismatched.join("; ");
+                fixes.push(if can_update(current) {
+                    let mut data = current.data.clone();
+                    data.name = plan.token.name.clone();
+                    data.symbol = plan.token.symbol.clone();
+                    data.uri = wanted.to_string();
+                    Difference::fixable(description, Fix::UpdateMetadata { data })
+                } else {
+                    Difference::unfixable(format!(
+                        "{description}; metadata is {}",
+                        if current.is_mutable {
+                            format!("updatable only by {}", current.update_authority)
+                        } else {
+                            "immutable".to_string()
+                        }
+                    ))
+                });
+            }
+        }
+    }
+
+    for (allocation, &balance) in plan.allocations.iter().zip(&state.balances) {
+        let wanted = TokenAmount::parse(&allocation.amount, decimals)?;
+        let have = amount(balance);
+        let Some(missing) = wanted.checked_sub(have).filter(|m| !m.is_zero()) else {
+            continue;
+        };
+        let name = match &allocation.label {
+            Some(label) => format!("{} ({label})", allocation.wallet),
+            None => allocation.wallet.to_string(),
+        };
+        let description = format!("{name} holds {have}, plan wants {wanted}");
+        fixes.push(if state.transfer_hook {
+            Difference::unfixable(format!(
+                "{description}; the mint has a transfer hook, whose extra accounts are not resolved"
+            ))
+        } else {
+            Difference::fixable(
+                description,
+                Fix::Allocate {
+                    wallet: allocation.wallet,
+                    amount: missing,
+                },
+            )
+        });
+    }
+
+    for (what, current, policy, kind) in [
+        (
+            "mint",
+            mint.mint_authority,
+            plan.authorities.mint,
+            AuthorityType::MintTokens,
+        ),
+        (
+            "freeze",
+            mint.freeze_authority,
+            plan.authorities.freeze,
+            AuthorityType::FreezeAccount,
+        ),
+    ] {
+        match (policy, current) {
+            (Keep::Revoke, Some(holder)) if holder == *authority => {
+                fixes.push(Difference::fixable(
+                    format!("{what} authority is still active"),
+                    Fix::Revoke { authority: kind },
+                ))
+            }
+            (Keep::Revoke, Some(holder)) => out.push(Difference::unfixable(format!(
+                "{what} authority is still active, held by {holder}"
+            ))),
+            (Keep::Keep, None) => out.push(Difference::unfixable(format!(
+                "{what} authority is revoked, plan keeps it"
+            ))),
+            _ => {}
+        }
+    }
+
+    if let Some(current) = &state.metadata {
+        match (plan.authorities.metadata, current.is_mutable) {
+            (Mutability::Immutable, true) if can_update(current) => fixes.push(
+                Difference::fixable("metadata is still mutable".into(), Fix::MakeImmutable),
+            ),
+            (Mutability::Immutable, true) => out.push(Difference::unfixable(format!(
+                "metadata is still mutable, updatable only by {}",
+                current.update_authority
+            ))),
+            (Mutability::Mutable, false) => out.push(Difference::unfixable(
+                "metadata is immutable, plan keeps it mutable".into(),
+            )),
+            _ => {}
+        }
+    }
+
+    out.extend(fixes);
+    Ok(out)
+}
@@ -0,0 +1,229 @@
+//! Post-launch plugins: executables in the `plugins` directory next to the
+//! config file, run at fixed points of a launch so a team can attach its
+//! own steps (announcing the token, registering it with an internal API)
+//! without changing this tool.
+//!
+//! A plugin is called as `PLUGIN <hook>` with a JSON [`HookPayload`] on
+//! stdin. Hooks a plugin doesn't handle should exit 0. A failing plugin is
+//! reported but never fails the launch, which is already on-chain by then.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::network::Network;
+use crate::process;
+use crate::session::{LaunchSession, Step};
+
+const PLUGIN_DIR: &str = "plugins";
+/// How long a plugin may run before it is stopped, unless configured.
+pub const PLUGIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Hook {
+    /// The mint account exists.
+    AfterMint,
+    /// The metadata account exists.
+    AfterMetadata,
+    /// Every step of the launch is done.
+    AfterLaunch,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 3] = [Hook::AfterMint, Hook::AfterMetadata, Hook::AfterLaunch];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Hook::AfterMint => "after-mint",
+            Hook::AfterMetadata => "after-metadata",
+            Hook::AfterLaunch => "after-launch",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        Hook::ALL
+            .into_iter()
+            .find(|h| h.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Hook::ALL.iter().map(|h| h.as_str()).collect();
+                Error::InvalidInput(format!(
+                    "unknown hook '{s}', expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// The hook that fires once `step` is confirmed.
+    pub fn after_step(step: Step) -> Option<Self> {
+        match step {
+            Step::CreateMint => Some(Hook::AfterMint),
+            Step::CreateMetadata => Some(Hook::AfterMetadata),
+            _ => None,
+        }
+    }
+}
+
+/// What a plugin reads from stdin.
+#[derive(Debug, Serialize)]
+pub struct HookPayload<'a> {
+    pub hook: Hook,
+    pub network: Network,
+    pub session: &'a LaunchSession,
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginRun {
+    pub plugin: PathBuf,
+    /// Exit code, or `None` when it was stopped or could not start.
+    pub status: Option<i32>,
+    pub output: String,
+    pub error: Option<String>,
+}
+
+impl PluginRun {
+    pub fn succeeded(&self) -> bool {
+        self.status == Some(0) && self.error.is_none()
+    }
+}
+
+pub fn plugin_dir() -> Result<PathBuf> {
+    Ok(Config::dir()?.join(PLUGIN_DIR))
+}
+
+/// Executables in `dir`, in name order, which is the order they run in.
+/// A missing directory just means no plugins.
+pub fn discover(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut plugins = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if process::is_executable(&path) {
+            plugins.push(path);
+        }
+    }
+    plugins.sort();
+    Ok(plugins)
+}
+
+/// Reads `pipe` to the end, handing each line to `on_line` as it comes;
+/// returns everything read.
+fn read_lines(pipe: Box<dyn Read + Send>, on_line: &(dyn Fn(&str) + Sync)) -> String {
+    let mut reader = BufReader::new(pipe);
+    let mut text = String::new();
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+        let decoded = String::from_utf8_lossy(&line);
+        on_line(decoded.trim_end_matches(['\r', '\n']));
+        text.push_str(&decoded);
+        line.clear();
+    }
+    text
+}
+
+/// Runs one plugin to completion or until `timeout` passes, when it is
+/// stopped. Each line it writes to stdout or stderr goes to `on_line` as
+/// soon as it is written, and all of it is collected in the result.
+pub fn run(
+    plugin: &Path,
+    payload: &HookPayload,
+    timeout: Duration,
+    on_line: &(dyn Fn(&str) + Sync),
+) -> PluginRun {
+    let mut result = PluginRun {
+        plugin: plugin.to_path_buf(),
+        status: None,
+        output: String::new(),
+        error: None,
+    };
+    let input = match serde_json::to_vec(payload) {
+        Ok(input) => input,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    let child = process::own_group(&mut Command::new(plugin))
+        .arg(payload.hook.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            result.error = Some(format!("could not start: {e}"));
+            return result;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // A plugin that ignores its input may close stdin early; that's fine.
+        let _ = stdin.write_all(&input);
+    }
+    // Read the pipes on their own threads so a chatty plugin can't block
+    // on a full pipe while we wait for it to exit, passing each line on as
+    // it arrives.
+    let pipes = [
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    ];
+    thread::scope(|s| {
+        let readers: Vec<_> = pipes
+            .into_iter()
+            .flatten()
+            .map(|pipe| s.spawn(|| read_lines(pipe, on_line)))
+            .collect();
+        match process::wait_timeout(&mut child, timeout) {
+            Ok(Some(status)) => result.status = status.code(),
+            Ok(None) => {
+                process::terminate(&mut child);
+                result.error = Some(format!(
+                    "timed out after {}s and was stopped",
+                    timeout.as_secs()
+                ));
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        for reader in readers {
+            result.output.push_str(&reader.join().unwrap_or_default());
+        }
+    });
+    result
+}
+
+/// Runs every plugin in `dir` for `hook`, in order, each for at most
+/// `timeout`, passing their output lines to `on_line` with the plugin
+/// that wrote them.
+pub fn run_all(
+    dir: &Path,
+    hook: Hook,
+    network: Network,
+    session: &LaunchSession,
+    timeout: Duration,
+    on_line: &(dyn Fn(&Path, &str) + Sync),
+) -> Result<Vec<PluginRun>> {
+    let payload = HookPayload {
+        hook,
+        network,
+        session,
+    };
+    Ok(discover(dir)?
+        .iter()
+        .map(|plugin| run(plugin, &payload, timeout, &|line| on_line(plugin, line)))
+        .collect())
+}
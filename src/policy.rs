@@ -0,0 +1,419 @@
+//! Limits on what unattended signing may sign.
+//!
+//! A policy file lists the programs, and optionally the instructions of
+//! each, that a transaction may contain, with caps on the tokens and SOL it
+//! may move. [`PolicySigner`] checks every message against it before the
+//! wrapped signer sees it, so a bug or a compromised automation host cannot
+//! get anything else signed:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "program": "compute-budget" },
+//!     { "program": "associated-token" },
+//!     { "program": "token", "instructions": ["mint_to", "mint_to_checked"],
+//!       "max_amount": 1000000000000 },
+//!     { "program": "system", "instructions": ["transfer"], "max_lamports": 50000000 }
+//!   ]
+//! }
+//! ```
+//!
+//! Every instruction must match a rule: the same program, given by name
+//! (`system`, `token`, `token-2022`, `associated-token`, `compute-budget`,
+//! `memo`, `metadata`) or address, and one of its `instructions` when they
+//! are listed. The token and metadata names stand for the programs the
+//! network is configured with. Caps are per transaction, in base units and
+//! lamports, summed over the instructions a rule matches; `max_amount`
+//! applies to the token programs and `max_lamports` to the system program.
+//! An instruction a capped rule matches whose amount can't be read, such as
+//! a confidential transfer or one the policy doesn't know, is refused.
+//! Versioned messages cannot be inspected here and are refused.
+
+use serde::Deserialize;
+
+use crate::compute_budget::COMPUTE_BUDGET_PROGRAM_ID;
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::labels::Labels;
+use crate::memo::MEMO_PROGRAM_ID;
+use crate::programs::Programs;
+use crate::pubkey::Pubkey;
+use crate::signer::Signer;
+use crate::system::SYSTEM_PROGRAM_ID;
+use crate::token::ASSOCIATED_TOKEN_PROGRAM_ID;
+use crate::tx::Message;
+
+/// The programs rules may name, the token and metadata ones wherever the
+/// network deploys them.
+fn programs(deployed: &Programs) -> [(&'static str, Pubkey); 7] {
+    [
+        ("system", SYSTEM_PROGRAM_ID),
+        ("token", deployed.token),
+        ("token-2022", deployed.token_2022),
+        ("associated-token", ASSOCIATED_TOKEN_PROGRAM_ID),
+        ("compute-budget", COMPUTE_BUDGET_PROGRAM_ID),
+        ("memo", MEMO_PROGRAM_ID),
+        ("metadata", deployed.metadata),
+    ]
+}
+
+const SYSTEM_INSTRUCTIONS: [&str; 13] = [
+    "create_account",
+    "assign",
+    "transfer",
+    "create_account_with_seed",
+    "advance_nonce_account",
+    "withdraw_nonce_account",
+    "initialize_nonce_account",
+    "authorize_nonce_account",
+    "allocate",
+    "allocate_with_seed",
+    "assign_with_seed",
+    "transfer_with_seed",
+    "upgrade_nonce_account",
+];
+
+const TOKEN_INSTRUCTIONS: [&str; 42] = [
+    "initialize_mint",
+    "initialize_account",
+    "initialize_multisig",
+    "transfer",
+    "approve",
+    "revoke",
+    "set_authority",
+    "mint_to",
+    "burn",
+    "close_account",
+    "freeze_account",
+    "thaw_account",
+    "transfer_checked",
+    "approve_checked",
+    "mint_to_checked",
+    "burn_checked",
+    "initialize_account2",
+    "sync_native",
+    "initialize_account3",
+    "initialize_multisig2",
+    "initialize_mint2",
+    "get_account_data_size",
+    "initialize_immutable_owner",
+    "amount_to_ui_amount",
+    "ui_amount_to_amount",
+    "initialize_mint_close_authority",
+    "transfer_fee_extension",
+    "confidential_transfer_extension",
+    "default_account_state_extension",
+    "reallocate",
+    "memo_transfer_extension",
+    "create_native_mint",
+    "initialize_non_transferable_mint",
+    "interest_bearing_mint_extension",
+    "cpi_guard_extension",
+    "initialize_permanent_delegate",
+    "transfer_hook_extension",
+    "confidential_transfer_fee_extension",
+    "withdraw_excess_lamports",
+    "metadata_pointer_extension",
+    "group_pointer_extension",
+    "group_member_pointer_extension",
+];
+
+/// Token instructions whose data carries a u64 amount after the tag.
+const TOKEN_AMOUNT_INSTRUCTIONS: [u8; 8] = [3, 4, 7, 8, 12, 13, 14, 15];
+/// Token-2022 extensions that move tokens by amounts kept encrypted.
+const TOKEN_CONFIDENTIAL_INSTRUCTIONS: [u8; 2] = [27, 37];
+const TOKEN_TRANSFER_FEE_EXTENSION: u8 = 26;
+/// System instructions whose data carries lamports right after the tag.
+const SYSTEM_LAMPORT_INSTRUCTIONS: [u32; 4] = [0, 2, 5, 11];
+const SYSTEM_CREATE_ACCOUNT_WITH_SEED: u32 = 3;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// A program name or address.
+    pub program: String,
+    /// Instruction names; any instruction of the program when absent.
+    #[serde(default)]
+    pub instructions: Option<Vec<String>>,
+    /// Cap on the token amount moved, minted, burned or approved.
+    #[serde(default)]
+    pub max_amount: Option<u64>,
+    /// Cap on the lamports transferred or funded.
+    #[serde(default)]
+    pub max_lamports: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+    /// Where the network deploys the token and metadata programs.
+    #[serde(skip)]
+    deployed: Programs,
+}
+
+/// How much of something an instruction moves, as far as the policy can
+/// tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Moved {
+    Nothing,
+    Exactly(u64),
+    /// It may move some, but the amount isn't in a form the policy reads.
+    Unknown,
+}
+
+impl Moved {
+    fn read(amount: Option<u64>) -> Self {
+        amount.map_or(Moved::Unknown, Moved::Exactly)
+    }
+}
+
+/// An instruction as the policy sees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub program: Pubkey,
+    pub name: String,
+    /// Tokens moved, minted, burned or approved.
+    pub amount: Moved,
+    /// Lamports transferred or funded.
+    pub lamports: Moved,
+}
+
+fn u64_at(data: &[u8], at: usize) -> Option<u64> {
+    data.get(at..at + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// The lamports of `create_account_with_seed`, after the base address and
+/// the length-prefixed seed.
+fn create_with_seed_lamports(data: &[u8]) -> Option<u64> {
+    let seed_len = usize::try_from(u64_at(data, 36)?).ok()?;
+    u64_at(data, 44usize.checked_add(seed_len)?)
+}
+
+/// Names an instruction and pulls out the amounts the policy caps.
+pub fn decode(deployed: &Programs, program: &Pubkey, data: &[u8]) -> Decoded {
+    let unnamed =
+        |tag: Option<u32>| tag.map_or("unknown".to_string(), |t| format!("instruction_{t}"));
+    let mut decoded = Decoded {
+        program: *program,
+        name: "unknown".into(),
+        amount: Moved::Nothing,
+        lamports: Moved::Nothing,
+    };
+    if *program == SYSTEM_PROGRAM_ID {
+        let tag = data
+            .get(..4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+        decoded.name = tag
+            .and_then(|t| SYSTEM_INSTRUCTIONS.get(t as usize))
+            .map_or_else(|| unnamed(tag), |n| n.to_string());
+        decoded.lamports = match tag {
+            Some(t) if SYSTEM_LAMPORT_INSTRUCTIONS.contains(&t) => Moved::read(u64_at(data, 4)),
+            Some(SYSTEM_CREATE_ACCOUNT_WITH_SEED) => Moved::read(create_with_seed_lamports(data)),
+            Some(t) if (t as usize) < SYSTEM_INSTRUCTIONS.len() => Moved::Nothing,
+            _ => Moved::Unknown,
+        };
+    } else if deployed.is_token_program(program) {
+        let tag = data.first().copied();
+        decoded.name = tag
+            .and_then(|t| TOKEN_INSTRUCTIONS.get(t as usize))
+            .map_or_else(|| unnamed(tag.map(u32::from)), |n| n.to_string());
+        decoded.amount = match tag {
+            Some(t) if TOKEN_AMOUNT_INSTRUCTIONS.contains(&t) => Moved::read(u64_at(data, 1)),
+            // transfer_checked_with_fee carries its amount after the
+            // extension's own tag; withdrawing withheld fees moves all of them.
+            Some(TOKEN_TRANSFER_FEE_EXTENSION) => match data.get(1) {
+                Some(1) => Moved::read(u64_at(data, 2)),
+                Some(0 | 4 | 5) => Moved::Nothing,
+                _ => Moved::Unknown,
+            },
+            Some(t) if TOKEN_CONFIDENTIAL_INSTRUCTIONS.contains(&t) => Moved::Unknown,
+            Some(t) if (t as usize) < TOKEN_INSTRUCTIONS.len() => Moved::Nothing,
+            _ => Moved::Unknown,
+        };
+    } else if *program == ASSOCIATED_TOKEN_PROGRAM_ID {
+        decoded.name = match data.first() {
+            None | Some(0) => "create",
+            Some(1) => "create_idempotent",
+            Some(2) => "recover_nested",
+            Some(_) => "unknown",
+        }
+        .into();
+    } else if *program == COMPUTE_BUDGET_PROGRAM_ID {
+        decoded.name = match data.first() {
+            Some(1) => "request_heap_frame",
+            Some(2) => "set_compute_unit_limit",
+            Some(3) => "set_compute_unit_price",
+            Some(4) => "set_loaded_accounts_data_size_limit",
+            _ => "unknown",
+        }
+        .into();
+    } else if *program == MEMO_PROGRAM_ID {
+        decoded.name = "memo".into();
+    } else if *program == deployed.metadata {
+        decoded.name = match data.first() {
+            Some(15) => "update_metadata_account_v2".into(),
+            Some(33) => "create_metadata_account_v3".into(),
+            tag => unnamed(tag.map(|t| u32::from(*t))),
+        };
+    }
+    decoded
+}
+
+impl Rule {
+    fn program(&self, deployed: &Programs) -> Result<Pubkey> {
+        match programs(deployed)
+            .iter()
+            .find(|(name, _)| *name == self.program)
+        {
+            Some((_, id)) => Ok(*id),
+            None => self.program.parse().map_err(|_| {
+                Error::Config(format!(
+                    "policy: '{}' is neither a known program name nor an address",
+                    self.program
+                ))
+            }),
+        }
+    }
+
+    fn matches(&self, deployed: &Programs, ix: &Decoded) -> Result<bool> {
+        Ok(self.program(deployed)? == ix.program
+            && self
+                .instructions
+                .as_ref()
+                .is_none_or(|names| names.contains(&ix.name)))
+    }
+}
+
+impl Policy {
+    /// Parses a policy for a network whose programs are `deployed`.
+    pub fn from_json(json: &str, deployed: Programs) -> Result<Self> {
+        let mut policy: Policy =
+            serde_json::from_str(json).map_err(|e| Error::Config(format!("policy: {e}")))?;
+        policy.deployed = deployed;
+        for rule in &policy.rules {
+            let program = rule.program(&deployed)?;
+            if rule.max_amount.is_some() && !deployed.is_token_program(&program) {
+                return Err(Error::Config(format!(
+                    "policy: max_amount on '{}' can't be enforced; only the token programs' amounts are read",
+                    rule.program
+                )));
+            }
+            if rule.max_lamports.is_some() && program != SYSTEM_PROGRAM_ID {
+                return Err(Error::Config(format!(
+                    "policy: max_lamports on '{}' can't be enforced; only the system program's lamports are read",
+                    rule.program
+                )));
+            }
+        }
+        Ok(policy)
+    }
+
+    #[cfg(feature = "native")]
+    pub fn read_file(path: &std::path::Path, deployed: Programs) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?, deployed)
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+    }
+
+    /// The program's name in rules, else its label and address.
+    fn program_name(&self, program: &Pubkey) -> String {
+        match programs(&self.deployed)
+            .iter()
+            .find(|(_, id)| id == program)
+        {
+            Some((name, _)) => name.to_string(),
+            None => Labels::default().describe(program),
+        }
+    }
+
+    /// Checks a serialized message; the error names the first instruction
+    /// or cap the policy does not allow.
+    pub fn check(&self, message: &[u8]) -> Result<()> {
+        let message = Message::deserialize(message).map_err(|e| {
+            Error::InvalidInput(format!("signing policy: cannot inspect the message: {e}"))
+        })?;
+        let mut amounts = vec![0u64; self.rules.len()];
+        let mut lamports = vec![0u64; self.rules.len()];
+        for (i, ix) in message.instructions.iter().enumerate() {
+            let program = message.account_keys[ix.program_id_index as usize];
+            let decoded = decode(&self.deployed, &program, &ix.data);
+            let mut matched = None;
+            for (r, rule) in self.rules.iter().enumerate() {
+                if rule.matches(&self.deployed, &decoded)? {
+                    matched = Some(r);
+                    break;
+                }
+            }
+            let Some(r) = matched else {
+                return Err(Error::InvalidInput(format!(
+                    "signing policy: instruction {} ({} {}) is not allowed",
+                    i + 1,
+                    self.program_name(&program),
+                    decoded.name
+                )));
+            };
+            let rule = &self.rules[r];
+            for (moved, capped, total) in [
+                (decoded.amount, rule.max_amount.is_some(), &mut amounts[r]),
+                (
+                    decoded.lamports,
+                    rule.max_lamports.is_some(),
+                    &mut lamports[r],
+                ),
+            ] {
+                match moved {
+                    Moved::Nothing => {}
+                    Moved::Exactly(n) => *total = total.saturating_add(n),
+                    Moved::Unknown if capped => {
+                        return Err(Error::InvalidInput(format!(
+                            "signing policy: instruction {} ({} {}) moves an amount that can't be \
+                             read, so the cap on {} can't be enforced",
+                            i + 1,
+                            self.program_name(&program),
+                            decoded.name,
+                            rule.program
+                        )));
+                    }
+                    Moved::Unknown => {}
+                }
+            }
+        }
+        for (r, rule) in self.rules.iter().enumerate() {
+            if let Some(max) = rule.max_amount
+                && amounts[r] > max
+            {
+                return Err(Error::InvalidInput(format!(
+                    "signing policy: {} base units through {} in one transaction, the cap is {max}",
+                    amounts[r], rule.program
+                )));
+            }
+            if let Some(max) = rule.max_lamports
+                && lamports[r] > max
+            {
+                return Err(Error::InvalidInput(format!(
+                    "signing policy: {} lamports via {} in one transaction, the cap is {max}",
+                    lamports[r], rule.program
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A signer that signs only what `policy` allows.
+pub struct PolicySigner<S> {
+    pub inner: S,
+    pub policy: Policy,
+}
+
+impl<S: Signer> Signer for PolicySigner<S> {
+    fn pubkey(&self) -> Pubkey {
+        self.inner.pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature> {
+        self.policy.check(message)?;
+        self.inner.try_sign_message(message)
+    }
+}
@@ -0,0 +1,445 @@
+//! Wallet overview: SOL, every token holding with its USD value, and the
+//! rent sitting in empty token accounts that closing them would return.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::metadata::{self, Metadata};
+use crate::programs::WRAPPED_SOL_MINT;
+use crate::pubkey::Pubkey;
+use crate::rpc::{AccountFilter, DataSlice, MAX_MULTIPLE_ACCOUNTS, RpcClient};
+use crate::system::LAMPORTS_PER_SOL;
+use crate::token::{MINT_LEN, Mint, TokenAccount};
+
+/// Jupiter's price endpoint; takes up to this many mints per request.
+pub const DEFAULT_PRICE_API: &str = "https://api.jup.ag/price/v2";
+const PRICE_BATCH: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Holding {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub amount: TokenAmount,
+    pub usd_price: Option<f64>,
+}
+
+impl Holding {
+    pub fn usd_value(&self) -> Option<f64> {
+        Some(to_f64(self.amount) * self.usd_price?)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Portfolio {
+    pub wallet: Pubkey,
+    pub lamports: u64,
+    pub sol_usd_price: Option<f64>,
+    /// Non-empty holdings, most valuable first once priced.
+    pub holdings: Vec<Holding>,
+    /// Token accounts holding nothing.
+    pub empty_accounts: Vec<Pubkey>,
+    /// Lamports locked as rent in `empty_accounts`.
+    pub empty_rent: u64,
+}
+
+pub(crate) fn to_f64(amount: TokenAmount) -> f64 {
+    amount.raw() as f64 / 10f64.powi(amount.decimals() as i32)
+}
+
+impl Portfolio {
+    /// Balances of `wallet`, without prices.
+    pub fn load(rpc: &RpcClient, wallet: &Pubkey) -> Result<Self> {
+        let lamports = rpc.get_balance(wallet)?;
+        let mut accounts = Vec::new();
+        let programs = rpc.programs();
+        for program_id in [programs.token, programs.token_2022] {
+            for keyed in rpc.get_token_accounts_by_owner(wallet, &program_id)? {
+                let token = TokenAccount::unpack(&keyed.account.data)?;
+                accounts.push((keyed.pubkey, program_id, keyed.account.lamports, token));
+            }
+        }
+
+        let mut mints: Vec<Pubkey> = accounts.iter().map(|(.., t)| t.mint).collect();
+        mints.sort();
+        mints.dedup();
+        let slice = DataSlice {
+            offset: 0,
+            length: MINT_LEN,
+        };
+        let decimals: HashMap<Pubkey, u8> = mints
+            .iter()
+            .zip(rpc.get_multiple_accounts(&mints, Some(slice))?.value)
+            .filter_map(|(mint, account)| {
+                Some((*mint, Mint::unpack(&account?.data).ok()?.decimals))
+            })
+            .collect();
+
+        let mut holdings = Vec::new();
+        let mut empty_accounts = Vec::new();
+        let mut empty_rent = 0;
+        for (account, program_id, rent, token) in accounts {
+            if token.amount == 0 {
+                empty_accounts.push(account);
+                empty_rent += rent;
+                continue;
+            }
+            let decimals = decimals.get(&token.mint).copied().ok_or_else(|| {
+                Error::InvalidAccountData(format!("mint {} of {account} is missing", token.mint))
+            })?;
+            holdings.push(Holding {
+                account,
+                mint: token.mint,
+                program_id,
+                amount: TokenAmount::from_raw(token.amount as u128, decimals),
+                usd_price: None,
+            });
+        }
+        Ok(Portfolio {
+            wallet: *wallet,
+            lamports,
+            sol_usd_price: None,
+            holdings,
+            empty_accounts,
+            empty_rent,
+        })
+    }
+
+    /// Every mint a price is wanted for, SOL included.
+    pub fn mints(&self) -> Vec<Pubkey> {
+        let mut mints = vec![WRAPPED_SOL_MINT];
+        mints.extend(self.holdings.iter().map(|h| h.mint));
+        mints
+    }
+
+    pub fn apply_prices(&mut self, prices: &HashMap<Pubkey, f64>) {
+        self.sol_usd_price = prices.get(&WRAPPED_SOL_MINT).copied();
+        for holding in &mut self.holdings {
+            holding.usd_price = prices.get(&holding.mint).copied();
+        }
+        self.holdings.sort_by(|a, b| {
+            b.usd_value()
+                .unwrap_or(0.0)
+                .total_cmp(&a.usd_value().unwrap_or(0.0))
+        });
+    }
+
+    pub fn sol_usd_value(&self) -> Option<f64> {
+        Some(self.lamports as f64 / LAMPORTS_PER_SOL as f64 * self.sol_usd_price?)
+    }
+
+    /// SOL plus every priced holding; unpriced holdings count as zero.
+    pub fn usd_total(&self) -> f64 {
+        self.sol_usd_value().unwrap_or(0.0)
+            + self
+                .holdings
+                .iter()
+                .filter_map(Holding::usd_value)
+                .sum::<f64>()
+    }
+}
+
+/// Authorities a wallet holds over one mint.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorityRoles {
+    pub mint: Pubkey,
+    pub mint_authority: bool,
+    pub freeze_authority: bool,
+    pub metadata_update_authority: bool,
+}
+
+impl AuthorityRoles {
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.mint_authority, "mint"),
+            (self.freeze_authority, "freeze"),
+            (self.metadata_update_authority, "metadata update"),
+        ]
+        .into_iter()
+        .filter_map(|(held, name)| held.then_some(name))
+        .collect()
+    }
+}
+
+/// Which authorities of `mints` `wallet` holds. Mints without any are left
+/// out; nothing indexes authorities, so only the given mints are checked.
+pub fn authority_roles(
+    rpc: &RpcClient,
+    wallet: &Pubkey,
+    mints: &[Pubkey],
+) -> Result<Vec<AuthorityRoles>> {
+    let mut roles = Vec::new();
+    for chunk in mints.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let metadata_addresses: Vec<Pubkey> = chunk
+            .iter()
+            .map(|mint| metadata::metadata_address(&rpc.programs().metadata, mint))
+            .collect();
+        let mint_accounts = rpc.get_multiple_accounts(chunk, None)?.value;
+        let metadata_accounts = rpc.get_multiple_accounts(&metadata_addresses, None)?.value;
+        for ((mint, account), metadata) in chunk.iter().zip(mint_accounts).zip(metadata_accounts) {
+            let Some(state) = account.and_then(|a| Mint::unpack(&a.data).ok()) else {
+                continue;
+            };
+            let entry = AuthorityRoles {
+                mint: *mint,
+                mint_authority: state.mint_authority == Some(*wallet),
+                freeze_authority: state.freeze_authority == Some(*wallet),
+                metadata_update_authority: metadata
+                    .and_then(|a| Metadata::unpack(&a.data).ok())
+                    .is_some_and(|m| m.update_authority == *wallet),
+            };
+            if !entry.names().is_empty() {
+                roles.push(entry);
+            }
+        }
+    }
+    Ok(roles)
+}
+
+/// One token a scanned wallet holds.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScannedToken {
+    pub holding: Holding,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    /// Launched from this machine.
+    pub launched_here: bool,
+    /// Authorities the wallet holds over the mint.
+    pub authorities: Vec<&'static str>,
+}
+
+/// What a wallet that isn't ours holds, for due diligence on partners and
+/// market makers.
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletScan {
+    pub wallet: Pubkey,
+    pub lamports: u64,
+    pub tokens: Vec<ScannedToken>,
+    pub empty_accounts: usize,
+}
+
+/// Reads every holding of `wallet` with the token's name and symbol, and
+/// marks the mints in `launched` and those `wallet` holds an authority of.
+pub fn scan_wallet(
+    rpc: &RpcClient,
+    wallet: &Pubkey,
+    launched: &HashSet<Pubkey>,
+) -> Result<WalletScan> {
+    let portfolio = Portfolio::load(rpc, wallet)?;
+    let mut mints: Vec<Pubkey> = portfolio.holdings.iter().map(|h| h.mint).collect();
+    mints.sort();
+    mints.dedup();
+    let roles: HashMap<Pubkey, AuthorityRoles> = authority_roles(rpc, wallet, &mints)?
+        .into_iter()
+        .map(|r| (r.mint, r))
+        .collect();
+    let mut names = HashMap::new();
+    for chunk in mints.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let addresses: Vec<Pubkey> = chunk
+            .iter()
+            .map(|mint| metadata::metadata_address(&rpc.programs().metadata, mint))
+            .collect();
+        for (mint, account) in chunk
+            .iter()
+            .zip(rpc.get_multiple_accounts(&addresses, None)?.value)
+        {
+            if let Some(m) = account.and_then(|a| Metadata::unpack(&a.data).ok()) {
+                names.insert(*mint, (m.data.name, m.data.symbol));
+            }
+        }
+    }
+    let tokens = portfolio
+        .holdings
+        .into_iter()
+        .map(|holding| {
+            let (name, symbol) = names.get(&holding.mint).cloned().unzip();
+            ScannedToken {
+                name,
+                symbol,
+                launched_here: launched.contains(&holding.mint),
+                authorities: roles
+                    .get(&holding.mint)
+                    .map(AuthorityRoles::names)
+                    .unwrap_or_default(),
+                holding,
+            }
+        })
+        .collect();
+    Ok(WalletScan {
+        wallet: *wallet,
+        lamports: portfolio.lamports,
+        tokens,
+        empty_accounts: portfolio.empty_accounts.len(),
+    })
+}
+
+/// A mint's freeze authority `COption` starts after the mint authority
+/// (36 bytes), supply (8), decimals and the initialized flag.
+const FREEZE_AUTHORITY_OFFSET: usize = 46;
+/// A metadata account's update authority follows its one-byte key, and
+/// the mint follows that.
+const METADATA_UPDATE_AUTHORITY_OFFSET: usize = 1;
+const METADATA_MINT_OFFSET: usize = 33;
+
+/// A scan the RPC refused; public endpoints often disable
+/// `getProgramAccounts` on the busiest programs.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedScan {
+    pub scan: &'static str,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorityAudit {
+    pub wallet: Pubkey,
+    /// Mints `wallet` holds any authority over, by address.
+    pub roles: Vec<AuthorityRoles>,
+    pub failed: Vec<FailedScan>,
+}
+
+fn role_entry(roles: &mut BTreeMap<Pubkey, AuthorityRoles>, mint: Pubkey) -> &mut AuthorityRoles {
+    roles.entry(mint).or_insert(AuthorityRoles {
+        mint,
+        mint_authority: false,
+        freeze_authority: false,
+        metadata_update_authority: false,
+    })
+}
+
+/// Finds every mint `wallet` is still mint, freeze or metadata update
+/// authority of, by searching the token programs and the metadata program
+/// for accounts with its key in the authority field.
+pub fn audit_authorities(rpc: &RpcClient, wallet: &Pubkey) -> Result<AuthorityAudit> {
+    // A set `COption<Pubkey>` is the tag 1 as a u32, then the key.
+    let mut coption = vec![1, 0, 0, 0];
+    coption.extend_from_slice(wallet.as_bytes());
+    let mut roles = BTreeMap::new();
+    let mut failed = Vec::new();
+
+    let programs = rpc.programs();
+    let mint_scans = [
+        ("token mint authority", programs.token, 0),
+        (
+            "token freeze authority",
+            programs.token,
+            FREEZE_AUTHORITY_OFFSET,
+        ),
+        ("token-2022 mint authority", programs.token_2022, 0),
+        (
+            "token-2022 freeze authority",
+            programs.token_2022,
+            FREEZE_AUTHORITY_OFFSET,
+        ),
+    ];
+    for (scan, program_id, offset) in mint_scans {
+        let mut filters = vec![AccountFilter::Memcmp {
+            offset,
+            bytes: coption.clone(),
+        }];
+        // Token-2022 mints carry extensions, so only legacy mints have a fixed size.
+        if program_id == programs.token {
+            filters.push(AccountFilter::DataSize(MINT_LEN as u64));
+        }
+        let slice = DataSlice {
+            offset: 0,
+            length: MINT_LEN,
+        };
+        match rpc.get_program_accounts(&program_id, &filters, Some(slice)) {
+            Ok(response) => {
+                for keyed in response.value {
+                    // The filters can't tell a mint from a token account
+                    // that happens to match; the decoded fields can.
+                    let Ok(mint) = Mint::unpack(&keyed.account.data) else {
+                        continue;
+                    };
+                    let is_mint = mint.mint_authority == Some(*wallet);
+                    let is_freeze = mint.freeze_authority == Some(*wallet);
+                    if is_mint || is_freeze {
+                        let r = role_entry(&mut roles, keyed.pubkey);
+                        r.mint_authority |= is_mint;
+                        r.freeze_authority |= is_freeze;
+                    }
+                }
+            }
+            Err(e) => failed.push(FailedScan {
+                scan,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    let filters = [
+        AccountFilter::Memcmp {
+            offset: 0,
+            bytes: vec![metadata::KEY_METADATA_V1],
+        },
+        AccountFilter::Memcmp {
+            offset: METADATA_UPDATE_AUTHORITY_OFFSET,
+            bytes: wallet.to_bytes().to_vec(),
+        },
+    ];
+    let slice = DataSlice {
+        offset: 0,
+        length: METADATA_MINT_OFFSET + 32,
+    };
+    match rpc.get_program_accounts(&rpc.programs().metadata, &filters, Some(slice)) {
+        Ok(response) => {
+            for keyed in response.value {
+                if let Some(mint) = keyed
+                    .account
+                    .data
+                    .get(METADATA_MINT_OFFSET..)
+                    .and_then(Pubkey::from_slice)
+                {
+                    role_entry(&mut roles, mint).metadata_update_authority = true;
+                }
+            }
+        }
+        Err(e) => failed.push(FailedScan {
+            scan: "metadata update authority",
+            error: e.to_string(),
+        }),
+    }
+
+    Ok(AuthorityAudit {
+        wallet: *wallet,
+        roles: roles.into_values().collect(),
+        failed,
+    })
+}
+
+#[derive(Deserialize)]
+struct PriceResponse {
+    data: HashMap<String, Option<PriceEntry>>,
+}
+
+#[derive(Deserialize)]
+struct PriceEntry {
+    price: String,
+}
+
+/// USD prices from a Jupiter-compatible price API. Mints it has no price
+/// for are left out.
+pub fn fetch_usd_prices(api_url: &str, mints: &[Pubkey]) -> Result<HashMap<Pubkey, f64>> {
+    let mut prices = HashMap::new();
+    for chunk in mints.chunks(PRICE_BATCH) {
+        let ids: Vec<String> = chunk.iter().map(Pubkey::to_string).collect();
+        let response: PriceResponse = ureq::get(api_url)
+            .query("ids", &ids.join(","))
+            .call()
+            .map_err(|e| Error::Http(format!("GET {api_url}: {e}")))?
+            .into_json()
+            .map_err(|e| Error::Http(format!("GET {api_url}: unexpected response: {e}")))?;
+        for (id, entry) in response.data {
+            if let (Ok(mint), Some(Ok(price))) =
+                (id.parse::<Pubkey>(), entry.map(|e| e.price.parse::<f64>()))
+            {
+                prices.insert(mint, price);
+            }
+        }
+    }
+    Ok(prices)
+}
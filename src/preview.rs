@@ -0,0 +1,383 @@
+//! Wallet-style metadata preview and pre-submission validation.
+//!
+//! The display widths below are approximations of how Phantom and Solflare
+//! truncate names in their token lists; they change between wallet releases,
+//! so findings are advisory unless they would break the metadata outright.
+
+use std::fmt::Write as _;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::launch::{Links, is_language_tag};
+use crate::metadata::{self, DataV2};
+
+/// Characters of the name Phantom shows in the token list before an ellipsis.
+pub const PHANTOM_NAME_WIDTH: usize = 20;
+/// Characters of the name Solflare shows in the token list before an ellipsis.
+pub const SOLFLARE_NAME_WIDTH: usize = 16;
+/// Tickers longer than this are truncated by most wallets and aggregators.
+pub const DISPLAY_SYMBOL_WIDTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl Finding {
+    pub(crate) fn warning(field: &'static str, message: impl Into<String>) -> Self {
+        Finding {
+            severity: Severity::Warning,
+            field,
+            message: message.into(),
+        }
+    }
+
+    fn error(field: &'static str, message: impl Into<String>) -> Self {
+        Finding {
+            severity: Severity::Error,
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+pub fn has_errors(findings: &[Finding]) -> bool {
+    findings.iter().any(|f| f.severity == Severity::Error)
+}
+
+/// Emoji and the joiners/selectors used to build them.
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0xFE0F | 0x200D
+    )
+}
+
+/// Truncates to `width` characters with an ellipsis, the way wallet lists do.
+pub fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        let kept: String = s.chars().take(width.saturating_sub(1)).collect();
+        format!("{kept}…")
+    }
+}
+
+fn check_text(field: &'static str, value: &str, findings: &mut Vec<Finding>) {
+    if value.trim().is_empty() {
+        findings.push(Finding::error(field, format!("{field} is empty")));
+        return;
+    }
+    if value.trim() != value {
+        findings.push(Finding::warning(
+            field,
+            format!("{field} has leading or trailing whitespace"),
+        ));
+    }
+    if value.chars().any(char::is_control) {
+        findings.push(Finding::error(
+            field,
+            format!("{field} contains control characters"),
+        ));
+    }
+    if value.chars().any(is_emoji) {
+        findings.push(Finding::warning(
+            field,
+            format!("{field} contains emoji, which some wallets render as blank boxes and explorers strip"),
+        ));
+    }
+}
+
+fn is_fetchable(uri: &str) -> bool {
+    uri.starts_with("https://") || uri.starts_with("ipfs://") || uri.starts_with("ar://")
+}
+
+/// Terms wallets and aggregators flag as likely impersonation when a name
+/// or symbol contains them: stablecoins, wrapped assets, exchanges and
+/// wallets.
+pub const DEFAULT_WARN_TERMS: &[&str] = &[
+    "USDC", "USDT", "PYUSD", "Tether", "Circle", "Wrapped", "Official", "Airdrop", "Binance",
+    "Coinbase", "Kraken", "OKX", "Bybit", "Jupiter", "Phantom", "Solflare",
+];
+
+fn words(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Findings for a name or symbol containing one of `warn` or `deny`, as
+/// whole words and ignoring case, so "USDC" flags "USDC Pro" but not
+/// "USDCAT". Denied terms are errors and stop a launch.
+pub fn check_terms(data: &DataV2, warn: &[String], deny: &[String]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (field, value) in [("name", &data.name), ("symbol", &data.symbol)] {
+        let have = words(value);
+        let contains = |term: &String| {
+            let term = words(term);
+            !term.is_empty() && have.windows(term.len()).any(|w| w == term.as_slice())
+        };
+        for term in deny.iter().filter(|t| contains(t)) {
+            findings.push(Finding::error(
+                field,
+                format!("{field} contains \"{term}\", which is on the deny list"),
+            ));
+        }
+        for term in warn.iter().filter(|t| contains(t) && !deny.contains(t)) {
+            findings.push(Finding::warning(
+                field,
+                format!(
+                    "{field} contains \"{term}\"; wallets and aggregators may flag it as impersonation"
+                ),
+            ));
+        }
+    }
+    findings
+}
+
+/// Validates on-chain fields and, when available, the off-chain JSON.
+pub fn validate(data: &DataV2, json: Option<&Value>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if let Err(e) = data.validate() {
+        findings.push(Finding::error("data", e.to_string()));
+    }
+
+    check_text("name", &data.name, &mut findings);
+    if data.name.chars().count() > PHANTOM_NAME_WIDTH {
+        findings.push(Finding::warning(
+            "name",
+            format!(
+                "wallets will truncate the name to \"{}\"",
+                truncate(&data.name, PHANTOM_NAME_WIDTH)
+            ),
+        ));
+    }
+
+    check_text("symbol", &data.symbol, &mut findings);
+    if data.symbol.chars().any(char::is_whitespace) {
+        findings.push(Finding::error(
+            "symbol",
+            "symbol contains whitespace; tickers must be a single word",
+        ));
+    }
+    if data.symbol.starts_with('$') {
+        findings.push(Finding::warning(
+            "symbol",
+            "symbol starts with '$'; wallets add their own prefix in some views",
+        ));
+    }
+    if data.symbol.chars().any(|c| c.is_lowercase()) {
+        findings.push(Finding::warning(
+            "symbol",
+            format!(
+                "symbol has lowercase letters; most lists show tickers as \"{}\"",
+                data.symbol.to_uppercase()
+            ),
+        ));
+    }
+    if data.symbol.chars().count() > DISPLAY_SYMBOL_WIDTH {
+        findings.push(Finding::warning(
+            "symbol",
+            format!("symbols longer than {DISPLAY_SYMBOL_WIDTH} characters are truncated in most wallets"),
+        ));
+    }
+
+    if data.uri.is_empty() {
+        findings.push(Finding::error(
+            "uri",
+            "metadata URI is empty; wallets will show no logo",
+        ));
+    } else if !is_fetchable(&data.uri) {
+        findings.push(Finding::warning(
+            "uri",
+            "metadata URI is not https://, ipfs:// or ar://; some wallets refuse to load it",
+        ));
+    }
+
+    if let Some(json) = json {
+        validate_json(data, json, &mut findings);
+    }
+    findings
+}
+
+fn validate_json(data: &DataV2, json: &Value, findings: &mut Vec<Finding>) {
+    if !json.is_object() {
+        findings.push(Finding::error(
+            "json",
+            "off-chain metadata is not a JSON object",
+        ));
+        return;
+    }
+    match json.get("image").and_then(Value::as_str) {
+        None => findings.push(Finding::error(
+            "image",
+            "off-chain JSON has no `image` field; wallets will show a placeholder logo",
+        )),
+        Some(image) if !is_fetchable(image) => findings.push(Finding::warning(
+            "image",
+            "image link is not https://, ipfs:// or ar://",
+        )),
+        Some(_) => {}
+    }
+    let pairs = [
+        ("name", "json.name", &data.name),
+        ("symbol", "json.symbol", &data.symbol),
+    ];
+    for (key, label, on_chain) in pairs {
+        match json.get(key).and_then(Value::as_str) {
+            None => findings.push(Finding::warning(
+                label,
+                format!("off-chain JSON has no `{key}`; some aggregators read it from there"),
+            )),
+            Some(off_chain) if off_chain != on_chain => findings.push(Finding::warning(
+                label,
+                format!("off-chain {key} \"{off_chain}\" differs from on-chain \"{on_chain}\""),
+            )),
+            Some(_) => {}
+        }
+    }
+    if json
+        .get("description")
+        .and_then(Value::as_str)
+        .is_none_or(|d| d.trim().is_empty())
+    {
+        findings.push(Finding::warning(
+            "description",
+            "off-chain JSON has no description",
+        ));
+    }
+    for problem in Links::from_json(json).problems() {
+        findings.push(Finding::error("links", problem));
+    }
+    validate_localizations(json, findings);
+    validate_classification(json, findings);
+}
+
+/// Translations may only stand beside the base name and description, which
+/// wallets that ignore them still show.
+fn validate_localizations(json: &Value, findings: &mut Vec<Finding>) {
+    let Some(localizations) = json.get("localizations") else {
+        return;
+    };
+    let Some(localizations) = localizations.as_object() else {
+        findings.push(Finding::error(
+            "localizations",
+            "`localizations` is not an object keyed by language",
+        ));
+        return;
+    };
+    for (language, entry) in localizations {
+        if !is_language_tag(language) {
+            findings.push(Finding::error(
+                "localizations",
+                format!("'{language}' is not a language tag such as es or pt-BR"),
+            ));
+        }
+        let Some(entry) = entry.as_object() else {
+            findings.push(Finding::error(
+                "localizations",
+                format!("the {language} localization is not an object"),
+            ));
+            continue;
+        };
+        for (key, value) in entry {
+            if key != "name" && key != "description" {
+                findings.push(Finding::error(
+                    "localizations",
+                    format!("the {language} localization sets `{key}`; only name and description are translated"),
+                ));
+                continue;
+            }
+            match value.as_str() {
+                Some(text) if !text.trim().is_empty() && !text.chars().any(char::is_control) => {}
+                _ => findings.push(Finding::error(
+                    "localizations",
+                    format!("the {language} {key} is empty, not text, or has control characters"),
+                )),
+            }
+            if json.get(key).and_then(Value::as_str).is_none() {
+                findings.push(Finding::error(
+                    "localizations",
+                    format!("the {language} {key} has no base `{key}` to fall back on"),
+                ));
+            }
+        }
+    }
+}
+
+fn validate_classification(json: &Value, findings: &mut Vec<Finding>) {
+    if let Some(tags) = json.pointer("/extensions/tags") {
+        match tags.as_array() {
+            Some(tags)
+                if tags
+                    .iter()
+                    .all(|t| t.as_str().is_some_and(|t| !t.trim().is_empty())) =>
+            {
+                let mut seen: Vec<&str> = tags.iter().filter_map(Value::as_str).collect();
+                seen.sort_unstable();
+                seen.dedup();
+                if seen.len() < tags.len() {
+                    findings.push(Finding::warning("tags", "a tag is listed twice"));
+                }
+            }
+            _ => findings.push(Finding::error(
+                "tags",
+                "`extensions.tags` must be a list of non-empty text",
+            )),
+        }
+    }
+    if let Some(category) = json.pointer("/extensions/category")
+        && category.as_str().is_none_or(|c| c.trim().is_empty())
+    {
+        findings.push(Finding::error(
+            "category",
+            "`extensions.category` must be non-empty text",
+        ));
+    }
+}
+
+/// Renders a plain-text approximation of the wallet token-list rows.
+pub fn render(data: &DataV2, json: Option<&Value>) -> String {
+    let image = json
+        .and_then(|j| j.get("image"))
+        .and_then(Value::as_str)
+        .unwrap_or("(none - placeholder shown)");
+    let symbol = data.symbol.trim();
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Phantom   [logo] {:<w$}  0 {symbol}",
+        truncate(data.name.trim(), PHANTOM_NAME_WIDTH),
+        w = PHANTOM_NAME_WIDTH
+    );
+    let _ = writeln!(
+        out,
+        "Solflare  [logo] {:<w$}  0 {symbol}",
+        truncate(data.name.trim(), SOLFLARE_NAME_WIDTH),
+        w = PHANTOM_NAME_WIDTH
+    );
+    let _ = writeln!(out, "Logo      {image}");
+    let _ = writeln!(out, "URI       {}", data.uri);
+    let _ = writeln!(
+        out,
+        "Limits    name {}/{}  symbol {}/{}  uri {}/{} bytes",
+        data.name.len(),
+        metadata::MAX_NAME_LENGTH,
+        data.symbol.len(),
+        metadata::MAX_SYMBOL_LENGTH,
+        data.uri.len(),
+        metadata::MAX_URI_LENGTH
+    );
+    out
+}
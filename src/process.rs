@@ -0,0 +1,129 @@
+//! Finding external programs (plugins, `solana-test-validator`), waiting
+//! on them with a deadline, and stopping them cleanly when it passes.
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+/// Where the Solana installer puts its tools, under the home directory.
+const SOLANA_INSTALL_BIN: &str = ".local/share/solana/install/active_release/bin";
+/// Where `cargo install` puts programs, plugins built in Rust among them.
+const CARGO_BIN: &str = ".cargo/bin";
+const POLL: Duration = Duration::from_millis(100);
+/// How long a program asked to stop has to exit before it is killed.
+const GRACE: Duration = Duration::from_secs(2);
+
+#[cfg(unix)]
+pub(crate) fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The environment variable that gives `tool`'s path, e.g.
+/// `SOLANA_TEST_VALIDATOR_PATH` for `solana-test-validator`.
+pub fn path_variable(tool: &str) -> String {
+    format!("{}_PATH", tool.to_ascii_uppercase().replace('-', "_"))
+}
+
+/// Finds the program `tool`: at the path in its [`path_variable`], else at
+/// `configured`, else on `PATH`, else where the Solana installer or cargo
+/// put it, so a shell that never sourced their `PATH` changes still works.
+/// A path given explicitly must exist; there is no falling back from it.
+pub fn find_tool(tool: &str, configured: Option<&Path>) -> Result<PathBuf> {
+    let variable = path_variable(tool);
+    let explicit = env::var_os(&variable)
+        .filter(|v| !v.is_empty())
+        .map(|v| (PathBuf::from(v), variable.as_str()))
+        .or_else(|| configured.map(|p| (p.to_path_buf(), "the config file")));
+    if let Some((path, from)) = explicit {
+        if !is_executable(&path) {
+            return Err(Error::Config(format!(
+                "{tool} is set to {} in {from}, which is not an executable file",
+                path.display()
+            )));
+        }
+        return Ok(path);
+    }
+    let name = format!("{tool}{}", env::consts::EXE_SUFFIX);
+    let on_path = env::var_os("PATH")
+        .map(|p| env::split_paths(&p).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let usual = dirs::home_dir()
+        .map(|home| vec![home.join(SOLANA_INSTALL_BIN), home.join(CARGO_BIN)])
+        .unwrap_or_default();
+    on_path
+        .into_iter()
+        .chain(usual)
+        .map(|dir| dir.join(&name))
+        .find(|path| is_executable(path))
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "{tool} not found on PATH or in ~/{SOLANA_INSTALL_BIN}; install it, or set \
+                 {variable} or \"tools\": {{\"{tool}\": PATH}} in the config file"
+            ))
+        })
+}
+
+/// Waits for `child` to exit, for at most `timeout`; `None` if it is still
+/// running then.
+pub fn wait_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if started.elapsed() >= timeout {
+            return Ok(None);
+        }
+        thread::sleep(POLL);
+    }
+}
+
+/// Starts `command` in a process group of its own, so [`terminate`] stops
+/// whatever it runs too rather than leaving it holding our pipes. The
+/// group doesn't get the terminal's Ctrl-C, so only for short-lived
+/// programs that are always waited on.
+pub fn own_group(command: &mut Command) -> &mut Command {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    command
+}
+
+/// Sends `signal` through kill(1), as std can only send SIGKILL: to the
+/// process group `child` leads if it was started with [`own_group`], else
+/// to the child alone.
+fn signal(child: &Child, signal: &str) -> bool {
+    let send = |target: String| {
+        Command::new("kill")
+            .args([signal, "--", &target])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+    send(format!("-{}", child.id())) || send(child.id().to_string())
+}
+
+/// Stops `child`: asks it to exit with SIGTERM so it can clean up, and
+/// kills it if it hasn't within a short grace period. Elsewhere than Unix
+/// it is killed straight away.
+pub fn terminate(child: &mut Child) {
+    if cfg!(unix) {
+        if signal(child, "-TERM") && matches!(wait_timeout(child, GRACE), Ok(Some(_))) {
+            return;
+        }
+        signal(child, "-KILL");
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
@@ -0,0 +1,118 @@
+//! Addresses of the programs the tool calls that a cluster may deploy
+//! somewhere other than the canonical address. Public clusters use the
+//! canonical deployments; a private cluster or fork running a compatible
+//! program elsewhere names it per network in the config file:
+//!
+//! ```json
+//! "programs": { "localnet": { "token": "Tok11111…", "metadata": "Meta1111…" } }
+//! ```
+//!
+//! The addresses of the other programs and mints the tool names, which
+//! are never overridden, are defined here once as well.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "native")]
+use crate::error::{Error, Result};
+use crate::metadata::METADATA_PROGRAM_ID;
+#[cfg(feature = "native")]
+use crate::network::Network;
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::RpcClient;
+use crate::token::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+
+pub const RAYDIUM_CPMM_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP8C");
+pub const RAYDIUM_CPMM_DEVNET_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW");
+/// Wrapped SOL: what a buy spends, and what price APIs quote SOL under.
+pub const WRAPPED_SOL_MINT: Pubkey =
+    Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+pub const USDC_MINT: Pubkey =
+    Pubkey::from_str_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Programs {
+    /// SPL Token, or a program with the same accounts and instructions.
+    pub token: Pubkey,
+    /// Token-2022, or a program with its extensions.
+    pub token_2022: Pubkey,
+    /// Token Metadata, or a program with the same accounts and instructions.
+    pub metadata: Pubkey,
+}
+
+impl Default for Programs {
+    fn default() -> Self {
+        Programs {
+            token: TOKEN_PROGRAM_ID,
+            token_2022: TOKEN_2022_PROGRAM_ID,
+            metadata: METADATA_PROGRAM_ID,
+        }
+    }
+}
+
+impl Programs {
+    /// Each program that differs from its canonical address, by name.
+    pub fn overrides(&self) -> Vec<(&'static str, Pubkey)> {
+        let canonical = Programs::default();
+        [
+            ("token", self.token, canonical.token),
+            ("token-2022", self.token_2022, canonical.token_2022),
+            ("metadata", self.metadata, canonical.metadata),
+        ]
+        .into_iter()
+        .filter(|(_, id, canonical)| id != canonical)
+        .map(|(name, id, _)| (name, id))
+        .collect()
+    }
+
+    /// The metadata program, if not the canonical one; launches record it
+    /// so a resumed session calls the same program.
+    pub fn metadata_override(&self) -> Option<Pubkey> {
+        (self.metadata != METADATA_PROGRAM_ID).then_some(self.metadata)
+    }
+
+    /// Either token program.
+    pub fn is_token_program(&self, program_id: &Pubkey) -> bool {
+        *program_id == self.token || *program_id == self.token_2022
+    }
+
+    /// The token program new mints are created under.
+    pub fn token_program(&self, token_2022: bool) -> Pubkey {
+        if token_2022 {
+            self.token_2022
+        } else {
+            self.token
+        }
+    }
+}
+
+/// Checks each overridden program is deployed on the cluster `rpc` points
+/// at, so a mistyped address fails before anything is sent to it.
+#[cfg(feature = "native")]
+pub fn check_deployed(rpc: &RpcClient, programs: &Programs, network: Network) -> Result<()> {
+    let overrides = programs.overrides();
+    if overrides.is_empty() {
+        return Ok(());
+    }
+    let keys: Vec<Pubkey> = overrides.iter().map(|(_, id)| *id).collect();
+    let accounts = rpc.get_multiple_accounts(&keys, None)?.value;
+    for ((name, id), account) in overrides.iter().zip(accounts) {
+        match account {
+            Some(account) if account.executable => {}
+            Some(_) => {
+                return Err(Error::Config(format!(
+                    "the {name} program configured for {network}, {id}, is not executable"
+                )));
+            }
+            None => {
+                return Err(Error::Config(format!(
+                    "the {name} program configured for {network}, {id}, is not deployed"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,431 @@
+//! Metadata updates proposed by one key and signed by others later.
+//!
+//! When the update authority of a token's metadata is held apart from the
+//! wallet that runs the launch, the update is built once, written to a
+//! proposal file and passed around for signatures. The file carries the
+//! unsigned message, a summary of the change for reviewers and the
+//! signatures collected so far. The message uses a durable nonce instead of
+//! a recent blockhash, so it stays valid for as long as signing takes; the
+//! proposer withdraws it by advancing the nonce.
+//!
+//! Reviewers should not trust the summary: [`Proposal::update`] decodes the
+//! message itself and refuses anything but a nonce advance followed by one
+//! metadata update, and [`describe`] rebuilds the summary from it.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "native")]
+use std::fs;
+#[cfg(feature = "native")]
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native")]
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+#[cfg(feature = "native")]
+use crate::keypair::Keypair;
+use crate::keypair::Signature;
+#[cfg(feature = "native")]
+use crate::launch::now_unix;
+use crate::metadata::{self, Collection, Creator, Metadata, UpdateMetadataArgs, Uses};
+use crate::network::Network;
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::RpcClient;
+use crate::signer::Signer;
+use crate::system;
+#[cfg(feature = "native")]
+use crate::system::NonceState;
+use crate::tx::{Hash, Message, Transaction};
+
+pub const PROPOSAL_VERSION: u32 = 1;
+
+/// A proposal file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proposal {
+    pub version: u32,
+    pub network: Network,
+    pub mint: Pubkey,
+    /// Pays the fee and holds the nonce.
+    pub proposer: Pubkey,
+    pub update_authority: Pubkey,
+    pub nonce_account: Pubkey,
+    /// What the proposer says the update does, one line per change.
+    pub summary: Vec<String>,
+    /// The unsigned legacy message, base64.
+    pub message: String,
+    /// Signatures collected so far, by signer.
+    #[serde(default)]
+    pub signatures: BTreeMap<Pubkey, Signature>,
+    /// Unix seconds.
+    pub created_at: i64,
+    /// Metadata program the update calls, when not the canonical one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_program: Option<Pubkey>,
+}
+
+/// The update a proposal's message makes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataUpdate {
+    pub program_id: Pubkey,
+    pub update_authority: Pubkey,
+    pub args: UpdateMetadataArgs,
+}
+
+/// The message a proposal signs: advance the nonce, then update metadata.
+pub fn build_message(
+    proposer: &Pubkey,
+    nonce_account: &Pubkey,
+    nonce: Hash,
+    mint: &Pubkey,
+    update: &MetadataUpdate,
+) -> Message {
+    let ixs = [
+        system::advance_nonce_account(nonce_account, proposer),
+        update
+            .args
+            .instruction(&update.program_id, mint, &update.update_authority),
+    ];
+    Message::new(&ixs, proposer, nonce)
+}
+
+impl Proposal {
+    pub fn message_bytes(&self) -> Result<Vec<u8>> {
+        BASE64
+            .decode(&self.message)
+            .map_err(|e| Error::InvalidInput(format!("proposal message is not base64: {e}")))
+    }
+
+    pub fn decode_message(&self) -> Result<Message> {
+        Message::deserialize(&self.message_bytes()?)
+    }
+
+    /// Decodes the update from the message, and checks the message does
+    /// nothing else: rebuilt from the decoded update it must come out
+    /// byte for byte the same.
+    pub fn update(&self) -> Result<MetadataUpdate> {
+        let bytes = self.message_bytes()?;
+        let message = Message::deserialize(&bytes)?;
+        let unexpected = || {
+            Error::InvalidInput(
+                "proposal message is not a single metadata update against its nonce".into(),
+            )
+        };
+        let [_, update_ix] = message.instructions.as_slice() else {
+            return Err(unexpected());
+        };
+        let key = |index: u8| message.account_keys[index as usize];
+        let program_id = key(update_ix.program_id_index);
+        if program_id
+            != self
+                .metadata_program
+                .unwrap_or(metadata::METADATA_PROGRAM_ID)
+        {
+            return Err(unexpected());
+        }
+        let update_authority = update_ix
+            .accounts
+            .get(1)
+            .map(|&i| key(i))
+            .ok_or_else(unexpected)?;
+        let update = MetadataUpdate {
+            program_id,
+            update_authority,
+            args: UpdateMetadataArgs::unpack(&update_ix.data)?,
+        };
+        let rebuilt = build_message(
+            &self.proposer,
+            &self.nonce_account,
+            message.recent_blockhash,
+            &self.mint,
+            &update,
+        );
+        if rebuilt.serialize() != bytes || update_authority != self.update_authority {
+            return Err(unexpected());
+        }
+        Ok(update)
+    }
+
+    /// The keys whose signatures the transaction needs.
+    pub fn signers(&self) -> Result<Vec<Pubkey>> {
+        Ok(self.decode_message()?.signer_keys().to_vec())
+    }
+
+    pub fn missing(&self) -> Result<Vec<Pubkey>> {
+        Ok(self
+            .signers()?
+            .into_iter()
+            .filter(|k| !self.signatures.contains_key(k))
+            .collect())
+    }
+
+    /// Adds `signer`'s signature, which must be one the message needs.
+    pub fn sign<S: Signer + ?Sized>(&mut self, signer: &S) -> Result<Signature> {
+        let key = signer.pubkey();
+        if !self.signers()?.contains(&key) {
+            return Err(Error::InvalidInput(format!(
+                "{key} is not a signer of this proposal"
+            )));
+        }
+        let signature = signer.try_sign_message(&self.message_bytes()?)?;
+        self.signatures.insert(key, signature);
+        Ok(signature)
+    }
+
+    /// Checks every collected signature against the message.
+    pub fn verify_signatures(&self) -> Result<()> {
+        let message = self.message_bytes()?;
+        let signers = self.signers()?;
+        for (key, signature) in &self.signatures {
+            if !signers.contains(key) || !signature.verify(key, &message) {
+                return Err(Error::InvalidInput(format!(
+                    "the proposal's signature for {key} is not valid"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The signed transaction, once every signature is in.
+    pub fn transaction(&self) -> Result<Transaction> {
+        self.verify_signatures()?;
+        let missing = self.missing()?;
+        if !missing.is_empty() {
+            let keys: Vec<String> = missing.iter().map(Pubkey::to_string).collect();
+            return Err(Error::InvalidInput(format!(
+                "the proposal still needs signatures from {}",
+                keys.join(", ")
+            )));
+        }
+        let message = self.decode_message()?;
+        let mut tx = Transaction::new_unsigned(message);
+        for (slot, key) in tx.signatures.iter_mut().zip(self.signers()?) {
+            *slot = self.signatures[&key];
+        }
+        Ok(tx)
+    }
+
+    #[cfg(feature = "native")]
+    pub fn read_file(path: &Path) -> Result<Self> {
+        let proposal: Proposal = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if proposal.version != PROPOSAL_VERSION {
+            return Err(Error::InvalidInput(format!(
+                "{} is a version {} proposal; this tool reads version {PROPOSAL_VERSION}",
+                path.display(),
+                proposal.version
+            )));
+        }
+        Ok(proposal)
+    }
+
+    #[cfg(feature = "native")]
+    pub fn write_file(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+}
+
+fn describe_creators(creators: &Option<Vec<Creator>>) -> String {
+    match creators {
+        None => "none".into(),
+        Some(creators) => creators
+            .iter()
+            .map(|c| {
+                let verified = if c.verified { ", verified" } else { "" };
+                format!("{} ({}%{verified})", c.address, c.share)
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+fn describe_collection(collection: &Option<Collection>) -> String {
+    match collection {
+        None => "none".into(),
+        Some(c) if c.verified => format!("{} (verified)", c.key),
+        Some(c) => c.key.to_string(),
+    }
+}
+
+fn describe_uses(uses: &Option<Uses>) -> String {
+    match uses {
+        None => "none".into(),
+        Some(u) => format!(
+            "method {}, {} of {} remaining",
+            u.use_method, u.remaining, u.total
+        ),
+    }
+}
+
+/// One line per change `update` makes to `current`, or per field it sets
+/// when the current metadata is not known.
+pub fn describe(update: &MetadataUpdate, current: Option<&Metadata>) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut change = |field: &str, before: Option<String>, after: String| match before {
+        Some(before) if before == after => {}
+        Some(before) => lines.push(format!("{field}: {before} -> {after}")),
+        None => lines.push(format!("{field}: {after}")),
+    };
+    let args = &update.args;
+    if let Some(data) = &args.data {
+        let old = current.map(|m| &m.data);
+        change(
+            "name",
+            old.map(|d| format!("{:?}", d.name)),
+            format!("{:?}", data.name),
+        );
+        change(
+            "symbol",
+            old.map(|d| format!("{:?}", d.symbol)),
+            format!("{:?}", data.symbol),
+        );
+        change(
+            "uri",
+            old.map(|d| format!("{:?}", d.uri)),
+            format!("{:?}", data.uri),
+        );
+        change(
+            "seller fee",
+            old.map(|d| format!("{} bps", d.seller_fee_basis_points)),
+            format!("{} bps", data.seller_fee_basis_points),
+        );
+        change(
+            "creators",
+            old.map(|d| describe_creators(&d.creators)),
+            describe_creators(&data.creators),
+        );
+        change(
+            "collection",
+            old.map(|d| describe_collection(&d.collection)),
+            describe_collection(&data.collection),
+        );
+        change(
+            "uses",
+            old.map(|d| describe_uses(&d.uses)),
+            describe_uses(&data.uses),
+        );
+    }
+    if let Some(key) = args.new_update_authority {
+        change(
+            "update authority",
+            Some(update.update_authority.to_string()),
+            key.to_string(),
+        );
+    }
+    if let Some(value) = args.primary_sale_happened {
+        change(
+            "primary sale happened",
+            current.map(|m| m.primary_sale_happened.to_string()),
+            value.to_string(),
+        );
+    }
+    if let Some(value) = args.is_mutable {
+        change(
+            "mutable",
+            current.map(|m| m.is_mutable.to_string()),
+            value.to_string(),
+        );
+    }
+    if lines.is_empty() {
+        lines.push("no changes".into());
+    }
+    lines
+}
+
+/// Derived like a scheduled revocation's nonce, so nothing secret is stored.
+#[cfg(feature = "native")]
+fn nonce_keypair(proposer: &Keypair, mint: &Pubkey, created_at: i64) -> Keypair {
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(b"launch-solana proposal nonce")
+        .chain_update(proposer.to_bytes())
+        .chain_update(mint.as_bytes())
+        .chain_update(created_at.to_le_bytes())
+        .finalize()
+        .into();
+    Keypair::from_seed(&seed)
+}
+
+/// Creates the nonce account and builds the proposal, signed by the
+/// proposer. Fails if `update_authority` cannot make the update.
+#[cfg(feature = "native")]
+pub fn create(
+    rpc: &RpcClient,
+    proposer: &Keypair,
+    network: Network,
+    mint: &Pubkey,
+    update: MetadataUpdate,
+) -> Result<Proposal> {
+    let current = metadata::fetch_metadata(rpc, mint)?
+        .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?;
+    if current.update_authority != update.update_authority {
+        return Err(Error::InvalidInput(format!(
+            "the update authority of {mint} is {}, not {}",
+            current.update_authority, update.update_authority
+        )));
+    }
+    if !current.is_mutable {
+        return Err(Error::InvalidInput(format!(
+            "the metadata of {mint} is immutable"
+        )));
+    }
+    if let Some(data) = &update.args.data {
+        data.validate()?;
+    }
+
+    let key = proposer.pubkey();
+    let created_at = now_unix();
+    let nonce = nonce_keypair(proposer, mint, created_at);
+    let rent = rpc.get_minimum_balance_for_rent_exemption(system::NONCE_ACCOUNT_LEN)?;
+    let ixs = system::create_nonce_account(&key, &nonce.pubkey(), &key, rent);
+    let tx = rpc.build_signed(&ixs, &key, &[proposer, &nonce])?;
+    rpc.send_and_confirm(&tx)?;
+    let account = rpc
+        .get_account(&nonce.pubkey())?
+        .value
+        .ok_or(Error::AccountNotFound(nonce.pubkey()))?;
+    let state = NonceState::unpack(&account.data)?;
+
+    let message = build_message(&key, &nonce.pubkey(), state.nonce, mint, &update);
+    let mut proposal = Proposal {
+        version: PROPOSAL_VERSION,
+        network,
+        mint: *mint,
+        proposer: key,
+        update_authority: update.update_authority,
+        nonce_account: nonce.pubkey(),
+        summary: describe(&update, Some(&current)),
+        message: BASE64.encode(message.serialize()),
+        signatures: BTreeMap::new(),
+        created_at,
+        metadata_program: (update.program_id != metadata::METADATA_PROGRAM_ID)
+            .then_some(update.program_id),
+    };
+    proposal.sign(proposer)?;
+    Ok(proposal)
+}
+
+/// Broadcasts a fully signed proposal.
+#[cfg(feature = "native")]
+pub fn submit(rpc: &RpcClient, proposal: &Proposal) -> Result<Signature> {
+    let tx = proposal.transaction()?;
+    let signature = rpc.send_raw_transaction(&tx.serialize())?;
+    rpc.confirm_transaction_at(&signature, rpc.critical_commitment())?;
+    Ok(signature)
+}
+
+/// Advances the nonce so the proposal can never land, whoever signs it.
+#[cfg(feature = "native")]
+pub fn cancel(rpc: &RpcClient, proposer: &Keypair, proposal: &Proposal) -> Result<Signature> {
+    if proposal.proposer != proposer.pubkey() {
+        return Err(Error::InvalidInput(format!(
+            "only the proposer {} can withdraw this proposal",
+            proposal.proposer
+        )));
+    }
+    let ix = system::advance_nonce_account(&proposal.nonce_account, &proposal.proposer);
+    let tx = rpc.build_signed(&[ix], &proposal.proposer, &[proposer])?;
+    rpc.send_and_confirm(&tx)
+}
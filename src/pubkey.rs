@@ -0,0 +1,102 @@
+use std::fmt;
+use std::str::FromStr;
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+/// A 32-byte Solana account address, displayed as base58.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pubkey([u8; 32]);
+
+impl Pubkey {
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        Pubkey(bytes)
+    }
+
+    /// Decodes a base58 address at compile time; panics on malformed input.
+    pub const fn from_str_const(s: &str) -> Self {
+        Pubkey(bs58::decode(s.as_bytes()).into_array_const_unwrap())
+    }
+
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(Pubkey)
+    }
+
+    /// Whether the address is a valid ed25519 point, i.e. could have a private key.
+    pub fn is_on_curve(&self) -> bool {
+        CompressedEdwardsY(self.0).decompress().is_some()
+    }
+
+    /// Derives a program address from `seeds`, or `None` if the hash lands on the curve.
+    pub fn create_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Option<Pubkey> {
+        let mut hasher = Sha256::new();
+        for seed in seeds {
+            hasher.update(seed);
+        }
+        hasher.update(program_id.0);
+        hasher.update(b"ProgramDerivedAddress");
+        let address = Pubkey(hasher.finalize().into());
+        (!address.is_on_curve()).then_some(address)
+    }
+
+    /// Finds the canonical program-derived address and its bump seed.
+    pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+        for bump in (0..=u8::MAX).rev() {
+            let mut with_bump = seeds.to_vec();
+            let bump_seed = [bump];
+            with_bump.push(&bump_seed);
+            if let Some(address) = Self::create_program_address(&with_bump, program_id) {
+                return (address, bump);
+            }
+        }
+        unreachable!("no off-curve program address for the given seeds")
+    }
+}
+
+impl FromStr for Pubkey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(s.trim())
+            .into_vec()
+            .map_err(|e| Error::InvalidInput(format!("'{s}' is not valid base58: {e}")))?;
+        Pubkey::from_slice(&bytes)
+            .ok_or_else(|| Error::InvalidInput(format!("'{s}' is not a 32-byte address")))
+    }
+}
+
+impl fmt::Display for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&bs58::encode(self.0).into_string())
+    }
+}
+
+impl fmt::Debug for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pubkey({self})")
+    }
+}
+
+impl Serialize for Pubkey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pubkey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
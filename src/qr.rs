@@ -0,0 +1,442 @@
+//! QR codes of addresses and links, drawn in the terminal.
+//!
+//! Encodes bytes as a QR code of versions 1 to 10 at error correction
+//! level M: up to 213 bytes, plenty for an address or a claim portal URL.
+//! [`QrCode::to_terminal`] draws two rows of modules per line of text with
+//! half blocks, light modules as blocks, which scans from a dark terminal.
+
+use crate::error::{Error, Result};
+
+/// Codewords per version at level M: error correction per block, then the
+/// data codewords of each short block and the number of short and long
+/// blocks; a long block has one data codeword more.
+const BLOCKS: [(usize, usize, usize, usize); 10] = [
+    (10, 16, 1, 0),
+    (16, 28, 1, 0),
+    (26, 44, 1, 0),
+    (18, 32, 2, 0),
+    (24, 43, 2, 0),
+    (16, 27, 4, 0),
+    (18, 31, 4, 0),
+    (22, 38, 2, 2),
+    (22, 36, 3, 2),
+    (26, 43, 4, 1),
+];
+/// Centres of the alignment patterns along each axis, per version.
+const ALIGNMENT: [&[usize]; 10] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+];
+/// Level M's two format bits.
+const LEVEL_M: u32 = 0b00;
+/// Light modules around the code.
+const QUIET_ZONE: usize = 2;
+
+/// A QR code as a square of dark (`true`) and light modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+struct Grid {
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Grid {
+    fn set(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+}
+
+impl QrCode {
+    /// Encodes `text` in byte mode in the smallest version it fits.
+    pub fn encode(text: &str) -> Result<Self> {
+        let data = text.as_bytes();
+        let version = (1..=BLOCKS.len())
+            .find(|&v| data.len() <= capacity(v))
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "{} bytes don't fit in a QR code; at most {} do",
+                    data.len(),
+                    capacity(BLOCKS.len())
+                ))
+            })?;
+        let codewords = interleave(version, &data_codewords(version, data));
+
+        let size = 17 + 4 * version;
+        let mut grid = Grid {
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        };
+        draw_function_patterns(&mut grid, version);
+        draw_codewords(&mut grid, &codewords);
+
+        let mut best: Option<(u32, Vec<bool>)> = None;
+        for mask in 0..8 {
+            let mut candidate = Grid {
+                size,
+                modules: grid.modules.clone(),
+                function: grid.function.clone(),
+            };
+            apply_mask(&mut candidate, mask);
+            draw_format_bits(&mut candidate, mask);
+            let score = penalty(&candidate);
+            if best.as_ref().is_none_or(|(s, _)| score < *s) {
+                best = Some((score, candidate.modules));
+            }
+        }
+        let modules = best.map(|(_, m)| m).unwrap_or_default();
+        Ok(QrCode { size, modules })
+    }
+
+    /// Modules along each side.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at column `x`, row `y` is dark.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    /// The code as lines of half blocks, light modules drawn.
+    pub fn to_terminal(&self) -> String {
+        let quiet = QUIET_ZONE as isize;
+        let side = self.size as isize + quiet;
+        let light = |x: isize, y: isize| x < 0 || y < 0 || !self.get(x as usize, y as usize);
+        let mut out = String::new();
+        let mut y = -quiet;
+        while y < side {
+            for x in -quiet..side {
+                out.push(match (light(x, y), light(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            out.push('\n');
+            y += 2;
+        }
+        out
+    }
+}
+
+/// Bits in the character count of byte mode.
+fn count_bits(version: usize) -> usize {
+    if version < 10 { 8 } else { 16 }
+}
+
+fn data_len(version: usize) -> usize {
+    let (_, short, short_blocks, long_blocks) = BLOCKS[version - 1];
+    short * short_blocks + (short + 1) * long_blocks
+}
+
+/// Bytes that fit in `version`.
+fn capacity(version: usize) -> usize {
+    (data_len(version) * 8 - 4 - count_bits(version)) / 8
+}
+
+/// Mode, count, data, terminator and padding, as codewords.
+fn data_codewords(version: usize, data: &[u8]) -> Vec<u8> {
+    let mut bits: Vec<bool> = Vec::new();
+    let mut push = |value: usize, len: usize| {
+        for i in (0..len).rev() {
+            bits.push((value >> i) & 1 == 1);
+        }
+    };
+    push(0b0100, 4);
+    push(data.len(), count_bits(version));
+    for &byte in data {
+        push(byte.into(), 8);
+    }
+    let total = data_len(version) * 8;
+    let terminator = (total - bits.len()).min(4);
+    bits.extend(std::iter::repeat_n(false, terminator));
+    bits.resize(bits.len().div_ceil(8) * 8, false);
+
+    let mut codewords: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, &b| (acc << 1) | u8::from(b)))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() == data_len(version) {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+/// Splits the data into blocks, adds each block's error correction and
+/// interleaves them.
+fn interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let (ec_len, short, short_blocks, long_blocks) = BLOCKS[version - 1];
+    let divisor = rs_divisor(ec_len);
+    let mut blocks = Vec::new();
+    let mut rest = data;
+    for i in 0..short_blocks + long_blocks {
+        let len = if i < short_blocks { short } else { short + 1 };
+        let (block, tail) = rest.split_at(len);
+        rest = tail;
+        blocks.push((block.to_vec(), rs_remainder(block, &divisor)));
+    }
+    let mut out = Vec::new();
+    for i in 0..=short {
+        for (block, _) in &blocks {
+            if let Some(&b) = block.get(i) {
+                out.push(b);
+            }
+        }
+    }
+    for i in 0..ec_len {
+        for (_, ec) in &blocks {
+            out.push(ec[i]);
+        }
+    }
+    out
+}
+
+/// Product in GF(256) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= u16::from((y >> i) & 1) * u16::from(x);
+    }
+    z as u8
+}
+
+/// Generator polynomial of `degree`, leading coefficient dropped.
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_mul(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_mul(root, 0x02);
+    }
+    result
+}
+
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &b in data {
+        let factor = b ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_mul(d, factor);
+        }
+    }
+    result
+}
+
+fn draw_function_patterns(grid: &mut Grid, version: usize) {
+    let size = grid.size;
+    for i in 0..size {
+        grid.set(6, i, i % 2 == 0);
+        grid.set(i, 6, i % 2 == 0);
+    }
+    for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+        for dy in -4isize..=4 {
+            for dx in -4isize..=4 {
+                let (x, y) = (cx as isize + dx, cy as isize + dy);
+                if (0..size as isize).contains(&x) && (0..size as isize).contains(&y) {
+                    let distance = dx.abs().max(dy.abs());
+                    grid.set(x as usize, y as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+    let centres = ALIGNMENT[version - 1];
+    let last = centres.len().saturating_sub(1);
+    for (i, &cx) in centres.iter().enumerate() {
+        for (j, &cy) in centres.iter().enumerate() {
+            // The finder patterns sit where these three would.
+            if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) {
+                continue;
+            }
+            for dy in -2isize..=2 {
+                for dx in -2isize..=2 {
+                    let distance = dx.abs().max(dy.abs());
+                    grid.set(
+                        (cx as isize + dx) as usize,
+                        (cy as isize + dy) as usize,
+                        distance != 1,
+                    );
+                }
+            }
+        }
+    }
+    // Reserve the format areas; the real bits are drawn per mask.
+    draw_format_bits(grid, 0);
+    if version >= 7 {
+        let mut rem = version as u32;
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+        }
+        let bits = ((version as u32) << 12) | rem;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 == 1;
+            let (a, b) = (size - 11 + i % 3, i / 3);
+            grid.set(a, b, dark);
+            grid.set(b, a, dark);
+        }
+    }
+}
+
+fn draw_format_bits(grid: &mut Grid, mask: u32) {
+    let size = grid.size;
+    let data = (LEVEL_M << 3) | mask;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    let bits = ((data << 10) | rem) ^ 0x5412;
+    let bit = |i: usize| (bits >> i) & 1 == 1;
+    for i in 0..=5 {
+        grid.set(8, i, bit(i));
+    }
+    grid.set(8, 7, bit(6));
+    grid.set(8, 8, bit(7));
+    grid.set(7, 8, bit(8));
+    for i in 9..15 {
+        grid.set(14 - i, 8, bit(i));
+    }
+    for i in 0..8 {
+        grid.set(size - 1 - i, 8, bit(i));
+    }
+    for i in 8..15 {
+        grid.set(8, size - 15 + i, bit(i));
+    }
+    grid.set(8, size - 8, true);
+}
+
+/// Fills the data modules in the two-column zigzag, from the bottom right.
+fn draw_codewords(grid: &mut Grid, codewords: &[u8]) {
+    let size = grid.size;
+    let total = codewords.len() * 8;
+    let mut i = 0;
+    let mut right = size - 1;
+    loop {
+        if right == 6 {
+            right = 5;
+        }
+        let upward = (right + 1) & 2 == 0;
+        for vert in 0..size {
+            let y = if upward { size - 1 - vert } else { vert };
+            for x in [right, right - 1] {
+                if !grid.function[y * size + x] && i < total {
+                    grid.modules[y * size + x] = (codewords[i >> 3] >> (7 - (i & 7))) & 1 == 1;
+                    i += 1;
+                }
+            }
+        }
+        if right < 2 {
+            break;
+        }
+        right -= 2;
+    }
+}
+
+fn apply_mask(grid: &mut Grid, mask: u32) {
+    let size = grid.size;
+    for y in 0..size {
+        for x in 0..size {
+            let invert = match mask {
+                0 => (x + y) % 2 == 0,
+                1 => y % 2 == 0,
+                2 => x % 3 == 0,
+                3 => (x + y) % 3 == 0,
+                4 => (x / 3 + y / 2) % 2 == 0,
+                5 => x * y % 2 + x * y % 3 == 0,
+                6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+            };
+            if invert && !grid.function[y * size + x] {
+                grid.modules[y * size + x] ^= true;
+            }
+        }
+    }
+}
+
+/// The standard's penalty score; the mask with the lowest is used.
+fn penalty(grid: &Grid) -> u32 {
+    let size = grid.size;
+    let mut score = 0;
+    let finder = [true, false, true, true, true, false, true];
+    for horizontal in [true, false] {
+        for a in 0..size {
+            let line: Vec<bool> = (0..size)
+                .map(|b| {
+                    if horizontal {
+                        grid.get(b, a)
+                    } else {
+                        grid.get(a, b)
+                    }
+                })
+                .collect();
+            let mut run = 1;
+            for b in 1..=size {
+                if b < size && line[b] == line[b - 1] {
+                    run += 1;
+                    continue;
+                }
+                if run >= 5 {
+                    score += 3 + (run - 5) as u32;
+                }
+                run = 1;
+            }
+            for start in 0..size.saturating_sub(finder.len() - 1) {
+                if line[start..start + finder.len()] != finder {
+                    continue;
+                }
+                let light = |from: isize, to: isize| {
+                    (from..to).all(|i| i < 0 || i >= size as isize || !line[i as usize])
+                };
+                let s = start as isize;
+                let e = s + finder.len() as isize;
+                if light(s - 4, s) || light(e, e + 4) {
+                    score += 40;
+                }
+            }
+        }
+    }
+    for y in 0..size - 1 {
+        for x in 0..size - 1 {
+            let c = grid.get(x, y);
+            if c == grid.get(x + 1, y) && c == grid.get(x, y + 1) && c == grid.get(x + 1, y + 1) {
+                score += 3;
+            }
+        }
+    }
+    let dark = grid.modules.iter().filter(|&&m| m).count();
+    let total = size * size;
+    let k = (dark * 20)
+        .abs_diff(total * 10)
+        .div_ceil(total)
+        .saturating_sub(1);
+    score + k as u32 * 10
+}
@@ -0,0 +1,177 @@
+//! Recipient lists read from `wallet,amount` CSVs of any length.
+//!
+//! Nothing holds a whole list in memory. [`RecipientList::scan`] streams the
+//! file once, validating every row and totalling the amounts, and spreads
+//! the wallets over bucket files on disk next to it; each bucket is then
+//! checked for duplicates on its own, so memory peaks at one bucket, about
+//! a sixty-fourth of the list. Senders read the list back in chunks.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::amount::TokenAmount;
+use crate::claim;
+use crate::error::{Error, Result};
+use crate::pubkey::Pubkey;
+
+const BUCKETS: usize = 64;
+/// Bytes per index entry: the wallet, then its line number.
+const ENTRY_LEN: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Recipient {
+    pub wallet: Pubkey,
+    pub amount: TokenAmount,
+}
+
+/// A validated recipients CSV on disk.
+#[derive(Debug, Clone)]
+pub struct RecipientList {
+    path: PathBuf,
+    decimals: u8,
+    len: usize,
+    total: u128,
+    digest: String,
+}
+
+fn index_dir(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".index");
+    PathBuf::from(name)
+}
+
+impl RecipientList {
+    /// Validates every row of the CSV at `path`, amounts in whole tokens,
+    /// and checks no wallet is listed twice. The duplicate index lives in
+    /// `<path>.index/` while this runs and is removed after.
+    pub fn scan(path: &Path, decimals: u8) -> Result<Self> {
+        let dir = index_dir(path);
+        let scanned = scan_into(path, decimals, &dir);
+        let _ = fs::remove_dir_all(&dir);
+        scanned
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn total(&self) -> TokenAmount {
+        TokenAmount::from_raw(self.total, self.decimals)
+    }
+
+    /// SHA-256 of the file as scanned, hex; tells whether it changed since.
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    /// The recipients after the first `skip`, `size` at a time.
+    pub fn chunks(&self, skip: usize, size: usize) -> Result<Chunks> {
+        Ok(Chunks {
+            lines: BufReader::new(File::open(&self.path)?).lines(),
+            number: 0,
+            decimals: self.decimals,
+            skip,
+            size: size.max(1),
+        })
+    }
+}
+
+fn scan_into(path: &Path, decimals: u8, dir: &Path) -> Result<RecipientList> {
+    fs::create_dir_all(dir)?;
+    let bucket_path = |b: usize| dir.join(format!("{b:02}"));
+    let mut buckets = (0..BUCKETS)
+        .map(|b| Ok(BufWriter::new(File::create(bucket_path(b))?)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut hasher = Sha256::new();
+    let mut len = 0;
+    let mut total: u128 = 0;
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+        let Some((wallet, amount)) = claim::parse_whitelist_line(i + 1, &line, decimals)? else {
+            continue;
+        };
+        total = total
+            .checked_add(amount.raw())
+            .ok_or_else(|| Error::InvalidInput(format!("line {}: total overflows", i + 1)))?;
+        len += 1;
+        let bucket = &mut buckets[usize::from(wallet.as_bytes()[0]) % BUCKETS];
+        bucket.write_all(wallet.as_bytes())?;
+        bucket.write_all(&(i as u64 + 1).to_le_bytes())?;
+    }
+    for mut bucket in buckets {
+        bucket.flush()?;
+    }
+
+    for b in 0..BUCKETS {
+        let entries = fs::read(bucket_path(b))?;
+        let mut seen: HashMap<Pubkey, u64> = HashMap::with_capacity(entries.len() / ENTRY_LEN);
+        for entry in entries.chunks_exact(ENTRY_LEN) {
+            let wallet = Pubkey::new(entry[..32].try_into().unwrap());
+            let line = u64::from_le_bytes(entry[32..].try_into().unwrap());
+            if let Some(first) = seen.insert(wallet, line) {
+                return Err(Error::InvalidInput(format!(
+                    "line {line}: {wallet} is listed twice, first on line {first}"
+                )));
+            }
+        }
+    }
+
+    Ok(RecipientList {
+        path: path.to_path_buf(),
+        decimals,
+        len,
+        total,
+        digest: hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect(),
+    })
+}
+
+/// Recipients read back from a [`RecipientList`], a chunk at a time.
+pub struct Chunks {
+    lines: Lines<BufReader<File>>,
+    number: usize,
+    decimals: u8,
+    skip: usize,
+    size: usize,
+}
+
+impl Iterator for Chunks {
+    type Item = Result<Vec<Recipient>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = Vec::new();
+        while chunk.len() < self.size {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Some(Err(e.into())),
+                None => break,
+            };
+            self.number += 1;
+            match claim::parse_whitelist_line(self.number, &line, self.decimals) {
+                Ok(Some(_)) if self.skip > 0 => self.skip -= 1,
+                Ok(Some((wallet, amount))) => chunk.push(Recipient { wallet, amount }),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        (!chunk.is_empty()).then_some(Ok(chunk))
+    }
+}
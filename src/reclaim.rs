@@ -0,0 +1,281 @@
+//! Getting back the rent locked in accounts that earlier runs left behind:
+//! empty token accounts of launched mints, durable nonce accounts of
+//! finished scheduled revocations and airdrops, and deactivated address
+//! lookup tables.
+//!
+//! [`scan`] only reports what it can close, with who must sign and where
+//! the account came from; [`close`] sends the closes, returning the rent to
+//! the wallet that pays their fees.
+
+use std::collections::BTreeSet;
+
+use crate::airdrop;
+use crate::error::Result;
+use crate::keypair::{Keypair, Signature};
+use crate::pack::{self, LOOKUP_TABLE_COOLDOWN_SLOTS, Packer};
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::schedule::{ScheduleStatus, ScheduledRevocation};
+use crate::session::LaunchSession;
+use crate::signer::Signer;
+use crate::system::{self, NonceState};
+use crate::token::{self, TokenAccount};
+use crate::tx::Instruction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    TokenAccount { program_id: Pubkey },
+    Nonce,
+    LookupTable,
+}
+
+impl Kind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::TokenAccount { .. } => "empty token account",
+            Kind::Nonce => "nonce account",
+            Kind::LookupTable => "lookup table",
+        }
+    }
+}
+
+/// An account that can be closed now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Closable {
+    pub address: Pubkey,
+    pub kind: Kind,
+    pub lamports: u64,
+    /// Who signs the close.
+    pub authority: Pubkey,
+    /// Where the account came from, e.g. "launch session 1a2b3c4d".
+    pub source: String,
+}
+
+impl Closable {
+    fn instruction(&self, recipient: &Pubkey) -> Instruction {
+        match self.kind {
+            Kind::TokenAccount { program_id } => {
+                token::close_account(&program_id, &self.address, recipient, &self.authority)
+            }
+            Kind::Nonce => system::withdraw_nonce_account(
+                &self.address,
+                &self.authority,
+                recipient,
+                self.lamports,
+            ),
+            Kind::LookupTable => {
+                pack::close_lookup_table(&self.address, &self.authority, recipient)
+            }
+        }
+    }
+}
+
+/// A nonce or lookup table to check, and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub address: Pubkey,
+    pub source: String,
+}
+
+/// What [`scan`] found.
+#[derive(Debug, Clone, Default)]
+pub struct Scan {
+    pub closable: Vec<Closable>,
+    /// Accounts found but left open, and why.
+    pub waiting: Vec<(Pubkey, String)>,
+}
+
+impl Scan {
+    pub fn lamports(&self) -> u64 {
+        self.closable.iter().map(|c| c.lamports).sum()
+    }
+}
+
+/// The nonce accounts of scheduled revocations that are no longer pending.
+pub fn finished_schedule_nonces(items: &[ScheduledRevocation]) -> Vec<Candidate> {
+    items
+        .iter()
+        .filter(|item| item.status != ScheduleStatus::Pending)
+        .map(|item| Candidate {
+            address: item.nonce_account,
+            source: format!("scheduled revocation {}", item.id),
+        })
+        .collect()
+}
+
+/// The durable nonce accounts `airdrop` opens for each of `payers`.
+pub fn airdrop_nonces(payers: &[Keypair], per_payer: usize) -> Vec<Candidate> {
+    payers
+        .iter()
+        .flat_map(|payer| {
+            (0..per_payer).map(move |i| Candidate {
+                address: airdrop::nonce_keypair(payer, i).pubkey(),
+                source: format!("airdrop fee payer {}", payer.pubkey()),
+            })
+        })
+        .collect()
+}
+
+/// Looks for closable accounts: `owner`'s empty token accounts of the
+/// mints `sessions` launched, and whichever of `nonces` and
+/// `lookup_tables` still exist and can be closed.
+pub fn scan(
+    rpc: &RpcClient,
+    owner: &Pubkey,
+    sessions: &[LaunchSession],
+    nonces: &[Candidate],
+    lookup_tables: &[Candidate],
+) -> Result<Scan> {
+    let mut scan = Scan::default();
+    let mints: BTreeSet<Pubkey> = sessions.iter().filter_map(|s| s.mint).collect();
+    if !mints.is_empty() {
+        let programs = rpc.programs();
+        for program_id in [programs.token, programs.token_2022] {
+            for keyed in rpc.get_token_accounts_by_owner(owner, &program_id)? {
+                if keyed.account.owner != program_id {
+                    continue;
+                }
+                let account = TokenAccount::unpack(&keyed.account.data)?;
+                if !mints.contains(&account.mint) || account.amount != 0 {
+                    continue;
+                }
+                if account.close_authority.is_some_and(|a| a != *owner) {
+                    scan.waiting.push((
+                        keyed.pubkey,
+                        format!(
+                            "its close authority is {}",
+                            account.close_authority.unwrap()
+                        ),
+                    ));
+                    continue;
+                }
+                let session = sessions
+                    .iter()
+                    .find(|s| s.mint == Some(account.mint))
+                    .map_or(String::new(), |s| s.id.clone());
+                scan.closable.push(Closable {
+                    address: keyed.pubkey,
+                    kind: Kind::TokenAccount { program_id },
+                    lamports: keyed.account.lamports,
+                    authority: *owner,
+                    source: format!("launch session {session}"),
+                });
+            }
+        }
+    }
+
+    let addresses: Vec<Pubkey> = nonces.iter().map(|c| c.address).collect();
+    let accounts = rpc.get_multiple_accounts(&addresses, None)?.value;
+    for (candidate, account) in nonces.iter().zip(accounts) {
+        let Some(account) = account else { continue };
+        if account.owner != system::SYSTEM_PROGRAM_ID {
+            continue;
+        }
+        match NonceState::unpack(&account.data) {
+            Ok(state) => scan.closable.push(Closable {
+                address: candidate.address,
+                kind: Kind::Nonce,
+                lamports: account.lamports,
+                authority: state.authority,
+                source: candidate.source.clone(),
+            }),
+            Err(e) => scan.waiting.push((candidate.address, e.to_string())),
+        }
+    }
+
+    if !lookup_tables.is_empty() {
+        let slot = rpc.get_slot()?;
+        let addresses: Vec<Pubkey> = lookup_tables.iter().map(|c| c.address).collect();
+        let accounts = rpc.get_multiple_accounts(&addresses, None)?.value;
+        for (candidate, account) in lookup_tables.iter().zip(accounts) {
+            let Some(account) = account else { continue };
+            let (deactivated, authority) =
+                pack::lookup_table_status(&candidate.address, &account.data)?;
+            let waiting = match (deactivated, authority) {
+                (_, None) => Some("it is frozen and can never be closed".to_string()),
+                (None, _) => Some("it is still active; deactivate it first".to_string()),
+                (Some(at), _) if slot <= at + LOOKUP_TABLE_COOLDOWN_SLOTS => Some(format!(
+                    "it was deactivated at slot {at}; it can be closed after slot {}",
+                    at + LOOKUP_TABLE_COOLDOWN_SLOTS
+                )),
+                _ => None,
+            };
+            match (waiting, authority) {
+                (Some(why), _) => scan.waiting.push((candidate.address, why)),
+                (None, Some(authority)) => scan.closable.push(Closable {
+                    address: candidate.address,
+                    kind: Kind::LookupTable,
+                    lamports: account.lamports,
+                    authority,
+                    source: candidate.source.clone(),
+                }),
+                (None, None) => {}
+            }
+        }
+    }
+    Ok(scan)
+}
+
+/// One transaction [`close`] sent and what came of it.
+#[derive(Debug, Clone)]
+pub struct Batch<'a> {
+    pub accounts: Vec<&'a Closable>,
+    pub signature: Option<Signature>,
+    pub error: Option<String>,
+}
+
+/// Closes `closable`, paid by `payer`, which also receives the rent; every
+/// close needs its authority, `payer` or one of `signers`, and those whose
+/// authority is neither fail. A failed transaction doesn't stop the rest.
+pub fn close<'a>(
+    rpc: &RpcClient,
+    payer: &dyn Signer,
+    signers: &[&dyn Signer],
+    closable: &'a [Closable],
+) -> Result<Vec<Batch<'a>>> {
+    let payer_key = payer.pubkey();
+    let find = |key: &Pubkey| -> Option<&dyn Signer> {
+        if *key == payer_key {
+            return Some(payer);
+        }
+        signers.iter().find(|s| s.pubkey() == *key).copied()
+    };
+    let mut authorities: Vec<Pubkey> = closable.iter().map(|c| c.authority).collect();
+    authorities.sort();
+    authorities.dedup();
+    let mut outcomes = Vec::new();
+    for authority in authorities {
+        let items = closable
+            .iter()
+            .filter(|c| c.authority == authority)
+            .map(|c| (c, vec![c.instruction(&payer_key)]));
+        let Some(signer) = find(&authority) else {
+            outcomes.push(Batch {
+                accounts: items.map(|(c, _)| c).collect(),
+                signature: None,
+                error: Some(format!("no keypair for {authority} to sign with")),
+            });
+            continue;
+        };
+        for (batch, ixs) in Packer::new(payer_key).pack(items)? {
+            let keys: &[&dyn Signer] = if authority == payer_key {
+                &[payer]
+            } else {
+                &[payer, signer]
+            };
+            let sent = rpc
+                .build_signed(&ixs, &payer_key, keys)
+                .and_then(|tx| rpc.send_and_confirm(&tx));
+            let (signature, error) = match sent {
+                Ok(signature) => (Some(signature), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            outcomes.push(Batch {
+                accounts: batch,
+                signature,
+                error,
+            });
+        }
+    }
+    Ok(outcomes)
+}
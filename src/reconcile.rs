@@ -0,0 +1,178 @@
+//! Supply reconciliation: sums every token account of a mint and compares the
+//! total against the supply reported by the mint account.
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::amount::TokenAmount;
+use crate::error::Result;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::token::{self, AccountState, TokenAccount};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anomaly {
+    /// The account is frozen and cannot move its balance.
+    Frozen,
+    /// Zero balance; the account can be closed to reclaim rent.
+    Empty,
+    /// A delegate may move part of the balance.
+    Delegated,
+    /// Someone other than the owner can close the account.
+    ForeignCloseAuthority,
+    /// An empty account that cannot be closed until it is thawed.
+    FrozenEmpty,
+}
+
+impl Anomaly {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Anomaly::Frozen => "frozen",
+            Anomaly::Empty => "empty",
+            Anomaly::Delegated => "delegated",
+            Anomaly::ForeignCloseAuthority => "foreign-close-authority",
+            Anomaly::FrozenEmpty => "frozen-empty",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LedgerEntry {
+    pub account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: TokenAmount,
+    pub state: AccountState,
+    pub delegate: Option<Pubkey>,
+    pub delegated_amount: TokenAmount,
+    pub close_authority: Option<Pubkey>,
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl LedgerEntry {
+    fn new(account: Pubkey, token: TokenAccount, decimals: u8) -> Self {
+        let mut anomalies = Vec::new();
+        match (token.state, token.amount) {
+            (AccountState::Frozen, 0) => anomalies.push(Anomaly::FrozenEmpty),
+            (AccountState::Frozen, _) => anomalies.push(Anomaly::Frozen),
+            (_, 0) => anomalies.push(Anomaly::Empty),
+            _ => {}
+        }
+        if token.delegate.is_some() && token.delegated_amount > 0 {
+            anomalies.push(Anomaly::Delegated);
+        }
+        if token.close_authority.is_some_and(|a| a != token.owner) {
+            anomalies.push(Anomaly::ForeignCloseAuthority);
+        }
+        LedgerEntry {
+            account,
+            owner: token.owner,
+            amount: TokenAmount::from_raw(token.amount as u128, decimals),
+            state: token.state,
+            delegate: token.delegate,
+            delegated_amount: TokenAmount::from_raw(token.delegated_amount as u128, decimals),
+            close_authority: token.close_authority,
+            anomalies,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Reconciliation {
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+    pub reported_supply: TokenAmount,
+    pub supply_slot: u64,
+    pub accounts_slot: u64,
+    /// Set when the supply changed while the accounts were being scanned.
+    pub supply_changed_during_scan: bool,
+    pub accounts_total: TokenAmount,
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl Reconciliation {
+    /// Sum of account balances minus reported supply, in base units.
+    pub fn difference(&self) -> i128 {
+        self.accounts_total.raw() as i128 - self.reported_supply.raw() as i128
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.difference() == 0
+    }
+
+    pub fn count(&self, anomaly: Anomaly) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.anomalies.contains(&anomaly))
+            .count()
+    }
+
+    pub fn frozen_total(&self) -> TokenAmount {
+        let raw = self
+            .entries
+            .iter()
+            .filter(|e| e.state == AccountState::Frozen)
+            .map(|e| e.amount.raw())
+            .sum();
+        TokenAmount::from_raw(raw, self.decimals)
+    }
+
+    /// Writes the per-account ledger as CSV.
+    pub fn write_ledger_csv(&self, mut out: impl Write) -> Result<()> {
+        writeln!(
+            out,
+            "account,owner,amount,ui_amount,state,delegate,delegated_amount,close_authority,anomalies"
+        )?;
+        for e in &self.entries {
+            let anomalies: Vec<&str> = e.anomalies.iter().map(|a| a.as_str()).collect();
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{}",
+                e.account,
+                e.owner,
+                e.amount.raw(),
+                e.amount,
+                e.state.as_str(),
+                e.delegate.map(|d| d.to_string()).unwrap_or_default(),
+                e.delegated_amount.raw(),
+                e.close_authority.map(|c| c.to_string()).unwrap_or_default(),
+                anomalies.join(";"),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Scans every token account of `mint` and reconciles balances against supply.
+pub fn reconcile(rpc: &RpcClient, mint: &Pubkey) -> Result<Reconciliation> {
+    let before = token::fetch_mint(rpc, mint)?;
+    let (accounts_slot, accounts) = token::fetch_token_accounts(rpc, mint, &before.program_id)?;
+
+    let mut entries: Vec<LedgerEntry> = accounts
+        .into_iter()
+        .map(|(address, account)| LedgerEntry::new(address, account, before.mint.decimals))
+        .collect();
+    entries.sort_by(|a, b| b.amount.cmp(&a.amount).then(a.account.cmp(&b.account)));
+
+    let after = token::fetch_mint(rpc, mint)?;
+    Ok(Reconciliation {
+        mint: *mint,
+        program_id: before.program_id,
+        decimals: before.mint.decimals,
+        mint_authority: before.mint.mint_authority,
+        freeze_authority: before.mint.freeze_authority,
+        reported_supply: before.mint.supply_amount(),
+        supply_slot: before.slot,
+        accounts_slot,
+        supply_changed_during_scan: after.mint.supply != before.mint.supply,
+        accounts_total: TokenAmount::from_raw(
+            entries.iter().map(|e| e.amount.raw()).sum(),
+            before.mint.decimals,
+        ),
+        entries,
+    })
+}
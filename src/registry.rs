@@ -0,0 +1,206 @@
+//! Submission payloads for token registries and listing sites, generated
+//! from a launch record plus the mint's current on-chain state.
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::csv;
+use crate::launch::LaunchRecord;
+use crate::metadata::DataV2;
+use crate::preview;
+use crate::token::{self, Mint};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub requirement: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(requirement: &'static str, passed: bool, detail: impl Into<String>) -> Self {
+        Check {
+            requirement,
+            passed,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Requirements registries commonly enforce before accepting a token.
+pub fn checks(record: &LaunchRecord, mint: &Mint) -> Vec<Check> {
+    let data = DataV2 {
+        name: record.name.clone(),
+        symbol: record.symbol.clone(),
+        uri: record.uri.clone(),
+        ..DataV2::default()
+    };
+    let display_errors: Vec<String> = preview::validate(&data, None)
+        .into_iter()
+        .filter(|f| f.severity == preview::Severity::Error)
+        .map(|f| f.message)
+        .collect();
+    vec![
+        Check::new(
+            "valid name and symbol",
+            display_errors.is_empty(),
+            display_errors.join("; "),
+        ),
+        Check::new(
+            "logo image",
+            record.image.is_some(),
+            record
+                .image
+                .clone()
+                .unwrap_or_else(|| "no image in metadata".into()),
+        ),
+        Check::new(
+            "freeze authority revoked",
+            mint.freeze_authority.is_none(),
+            mint.freeze_authority
+                .map(|a| format!("still held by {a}"))
+                .unwrap_or_default(),
+        ),
+        Check::new(
+            "mint authority revoked",
+            mint.mint_authority.is_none(),
+            mint.mint_authority
+                .map(|a| format!("still held by {a}; supply is not fixed"))
+                .unwrap_or_default(),
+        ),
+        Check::new(
+            "description",
+            record
+                .description
+                .as_deref()
+                .is_some_and(|d| !d.trim().is_empty()),
+            "",
+        ),
+        Check::new("website", record.links.website.is_some(), ""),
+        Check::new("twitter / X account", record.links.twitter.is_some(), ""),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JupiterSubmission {
+    /// Row for `validated-tokens.csv` in the token-list repository.
+    pub csv_row: String,
+    pub pr_title: String,
+    pub pr_body: String,
+}
+
+pub fn jupiter(record: &LaunchRecord, checks: &[Check]) -> JupiterSubmission {
+    let csv_row = format!(
+        "{},{},{},{},{},false",
+        csv::escape(&record.name),
+        csv::escape(&record.symbol),
+        record.mint,
+        record.decimals,
+        csv::escape(record.image.as_deref().unwrap_or_default()),
+    );
+    let mut pr_body = format!(
+        "## Token\n\n- Name: {}\n- Symbol: {}\n- Mint: `{}`\n- Decimals: {}\n- Explorer: {}\n",
+        record.name,
+        record.symbol,
+        record.mint,
+        record.decimals,
+        record
+            .network
+            .explorer_url(&format!("token/{}", record.mint)),
+    );
+    if let Some(website) = &record.links.website {
+        pr_body.push_str(&format!("- Website: {website}\n"));
+    }
+    if let Some(twitter) = &record.links.twitter {
+        pr_body.push_str(&format!("- Twitter: {twitter}\n"));
+    }
+    pr_body.push_str("\n## Checklist\n\n");
+    for check in checks {
+        let mark = if check.passed { "x" } else { " " };
+        pr_body.push_str(&format!("- [{mark}] {}\n", check.requirement));
+    }
+    JupiterSubmission {
+        csv_row,
+        pr_title: format!("Add {} ({})", record.symbol, record.mint),
+        pr_body,
+    }
+}
+
+/// Field values for the CoinGecko "new coin listing" request form.
+pub fn coingecko(record: &LaunchRecord, mint: &Mint) -> Value {
+    let supply = mint.supply_amount().to_string();
+    let standard = if record.program_id == token::TOKEN_2022_PROGRAM_ID {
+        "SPL Token-2022"
+    } else {
+        "SPL Token"
+    };
+    json!({
+        "project_name": record.name,
+        "ticker_symbol": record.symbol,
+        "blockchain": "Solana",
+        "token_standard": standard,
+        "contract_address": record.mint,
+        "decimals": record.decimals,
+        "total_supply": supply,
+        // A fixed supply only exists once nobody can mint more.
+        "max_supply": mint.mint_authority.is_none().then_some(supply),
+        "logo_url": record.image,
+        "description": record.description,
+        "website": record.links.website,
+        "twitter": record.links.twitter,
+        "telegram": record.links.telegram,
+        "discord": record.links.discord,
+        "explorer": record.network.explorer_url(&format!("token/{}", record.mint)),
+    })
+}
+
+/// File names of the images in a listing bundle, next to its JSON.
+pub const ICON_FILE: &str = "icon.png";
+pub const HEADER_FILE: &str = "header.png";
+
+/// The links in the `{type, url}` form listing sites take, website first.
+fn typed_links(record: &LaunchRecord) -> Vec<Value> {
+    record
+        .links
+        .entries()
+        .into_iter()
+        .filter_map(|(kind, url)| Some(json!({ "type": kind, "url": url? })))
+        .collect()
+}
+
+/// DEX Screener's Enhanced Token Info order: description, links, and the
+/// icon and header images, which are uploaded as files alongside.
+pub fn dexscreener(record: &LaunchRecord, header: bool) -> Value {
+    json!({
+        "chainId": "solana",
+        "tokenAddress": record.mint,
+        "name": record.name,
+        "symbol": record.symbol,
+        "description": record.description,
+        "links": typed_links(record),
+        "icon": ICON_FILE,
+        "header": header.then_some(HEADER_FILE),
+    })
+}
+
+/// Birdeye's token info update request.
+pub fn birdeye(record: &LaunchRecord, mint: &Mint) -> Value {
+    json!({
+        "chain": "solana",
+        "address": record.mint,
+        "name": record.name,
+        "symbol": record.symbol,
+        "decimals": record.decimals,
+        "logoURI": record.image,
+        "logo": ICON_FILE,
+        "description": record.description,
+        "extensions": {
+            "website": record.links.website,
+            "twitter": record.links.twitter,
+            "telegram": record.links.telegram,
+            "discord": record.links.discord,
+        },
+        "mintAuthorityRevoked": mint.mint_authority.is_none(),
+        "freezeAuthorityRevoked": mint.freeze_authority.is_none(),
+    })
+}
@@ -0,0 +1,171 @@
+//! Rehearse-then-replay launches. A finished launch on a test cluster is
+//! captured as an [`Outcome`]; replaying its parameters on another cluster
+//! captures the same, and [`compare`] lists every field that came out
+//! differently.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "native")]
+use crate::error::{Error, Result};
+#[cfg(feature = "native")]
+use crate::extension;
+#[cfg(feature = "native")]
+use crate::metadata;
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::RpcClient;
+#[cfg(feature = "native")]
+use crate::session::LaunchSession;
+#[cfg(feature = "native")]
+use crate::token::{self, TokenAccount};
+
+/// What a finished launch left on-chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub program_id: Pubkey,
+    pub mint_len: usize,
+    pub decimals: u8,
+    /// Base units.
+    pub supply: u64,
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+    /// Token-2022 extension names, in account order.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub uri: Option<String>,
+    pub update_authority: Option<Pubkey>,
+    pub is_mutable: Option<bool>,
+    /// Base units held by the launch authority's token account.
+    pub authority_balance: u64,
+}
+
+/// Reads the outcome of a complete session.
+#[cfg(feature = "native")]
+pub fn observe(rpc: &RpcClient, session: &LaunchSession) -> Result<Outcome> {
+    let (Some(mint), Some(account)) = (session.mint, session.token_account) else {
+        return Err(Error::InvalidInput(format!(
+            "session {} has not created its mint yet",
+            session.id
+        )));
+    };
+    let raw = rpc
+        .get_account(&mint)?
+        .value
+        .ok_or(Error::AccountNotFound(mint))?;
+    let info = token::Mint::unpack(&raw.data)?;
+    let balance = match rpc.get_account(&account)?.value {
+        Some(a) => TokenAccount::unpack(&a.data)?.amount,
+        None => 0,
+    };
+    let meta = metadata::fetch_metadata(rpc, &mint)?;
+    // Older metadata accounts pad strings with NULs.
+    let text = |s: &str| s.trim_end_matches('\0').to_string();
+    Ok(Outcome {
+        program_id: raw.owner,
+        mint_len: raw.data.len(),
+        decimals: info.decimals,
+        supply: info.supply,
+        mint_authority: info.mint_authority,
+        freeze_authority: info.freeze_authority,
+        extensions: extension::parse(&raw.data)?
+            .iter()
+            .map(|e| extension::name(e.kind).to_string())
+            .collect(),
+        name: meta.as_ref().map(|m| text(&m.data.name)),
+        symbol: meta.as_ref().map(|m| text(&m.data.symbol)),
+        uri: meta.as_ref().map(|m| text(&m.data.uri)),
+        update_authority: meta.as_ref().map(|m| m.update_authority),
+        is_mutable: meta.as_ref().map(|m| m.is_mutable),
+        authority_balance: balance,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    pub field: &'static str,
+    pub rehearsed: String,
+    pub replayed: String,
+}
+
+/// Fields of `replayed` that differ from `rehearsed`. The two launches are
+/// signed by different wallets, so the rehearsal authority is read as the
+/// replay authority wherever it appears.
+pub fn compare(
+    rehearsed: &Outcome,
+    rehearsal_authority: &Pubkey,
+    replayed: &Outcome,
+    authority: &Pubkey,
+) -> Vec<Divergence> {
+    let key = |k: Option<Pubkey>| match k {
+        Some(k) if k == *rehearsal_authority => Some(*authority),
+        other => other,
+    };
+    let show = |k: Option<Pubkey>| k.map_or("none".to_string(), |k| k.to_string());
+    let opt = |s: &Option<String>| s.clone().unwrap_or_else(|| "none".into());
+    let mut divergences = Vec::new();
+    let mut check = |field: &'static str, rehearsed: String, replayed: String| {
+        if rehearsed != replayed {
+            divergences.push(Divergence {
+                field,
+                rehearsed,
+                replayed,
+            });
+        }
+    };
+    check(
+        "program",
+        rehearsed.program_id.to_string(),
+        replayed.program_id.to_string(),
+    );
+    check(
+        "account size",
+        rehearsed.mint_len.to_string(),
+        replayed.mint_len.to_string(),
+    );
+    check(
+        "decimals",
+        rehearsed.decimals.to_string(),
+        replayed.decimals.to_string(),
+    );
+    check(
+        "supply",
+        rehearsed.supply.to_string(),
+        replayed.supply.to_string(),
+    );
+    check(
+        "mint authority",
+        show(key(rehearsed.mint_authority)),
+        show(replayed.mint_authority),
+    );
+    check(
+        "freeze authority",
+        show(key(rehearsed.freeze_authority)),
+        show(replayed.freeze_authority),
+    );
+    check(
+        "extensions",
+        rehearsed.extensions.join(", "),
+        replayed.extensions.join(", "),
+    );
+    check("name", opt(&rehearsed.name), opt(&replayed.name));
+    check("symbol", opt(&rehearsed.symbol), opt(&replayed.symbol));
+    check("uri", opt(&rehearsed.uri), opt(&replayed.uri));
+    check(
+        "update authority",
+        show(key(rehearsed.update_authority)),
+        show(replayed.update_authority),
+    );
+    check(
+        "mutable",
+        format!("{:?}", rehearsed.is_mutable),
+        format!("{:?}", replayed.is_mutable),
+    );
+    check(
+        "authority balance",
+        rehearsed.authority_balance.to_string(),
+        replayed.authority_balance.to_string(),
+    );
+    divergences
+}
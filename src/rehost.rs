@@ -0,0 +1,168 @@
+//! Moves a token's off-chain metadata to new storage and points the on-chain
+//! URI at it in a single update, either as it is or with some of its fields
+//! rewritten.
+
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::keypair::{Keypair, Signature};
+use crate::metadata::{self, DataV2, Metadata};
+use crate::preview::{self, Finding};
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::storage::{self, Uploader};
+
+pub struct RehostOptions {
+    /// Use this JSON instead of downloading the current URI (for dead hosts).
+    pub local_json: Option<Value>,
+    /// Also copy the `image` file to the new storage and rewrite its link.
+    pub rehost_image: bool,
+    /// Upload nothing and send nothing; only check that the migration is possible.
+    pub dry_run: bool,
+    pub ipfs_gateway: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RehostOutcome {
+    pub old_uri: String,
+    pub new_uri: Option<String>,
+    pub old_image: Option<String>,
+    pub new_image: Option<String>,
+    pub signature: Option<Signature>,
+    /// Display problems found in the metadata being migrated.
+    pub findings: Vec<Finding>,
+}
+
+/// Fails unless `authority` can still change the metadata of `mint`.
+pub fn ensure_update_authority(
+    metadata: &Metadata,
+    mint: &Pubkey,
+    authority: &Pubkey,
+) -> Result<()> {
+    if !metadata.is_mutable {
+        return Err(Error::InvalidInput(format!(
+            "metadata of {mint} is immutable and can no longer be changed"
+        )));
+    }
+    if metadata.update_authority != *authority {
+        return Err(Error::InvalidInput(format!(
+            "update authority of {mint} is {}, not the configured wallet {authority}",
+            metadata.update_authority
+        )));
+    }
+    Ok(())
+}
+
+pub fn rehost(
+    rpc: &RpcClient,
+    uploader: &dyn Uploader,
+    authority: &Keypair,
+    mint: &Pubkey,
+    options: RehostOptions,
+) -> Result<RehostOutcome> {
+    let current = metadata::fetch_metadata(rpc, mint)?
+        .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?;
+    ensure_update_authority(&current, mint, &authority.pubkey())?;
+
+    let mut json = match options.local_json {
+        Some(json) => json,
+        None => storage::fetch_json(&current.data.uri, &options.ipfs_gateway)?,
+    };
+    let old_image = json
+        .get("image")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let mut outcome = RehostOutcome {
+        old_uri: current.data.uri.clone(),
+        new_uri: None,
+        old_image: old_image.clone(),
+        new_image: None,
+        signature: None,
+        findings: preview::validate(&current.data, Some(&json)),
+    };
+    if options.dry_run {
+        return Ok(outcome);
+    }
+
+    if options.rehost_image {
+        let image = old_image
+            .ok_or_else(|| Error::InvalidInput("metadata JSON has no image to re-host".into()))?;
+        let (bytes, content_type) = storage::fetch(&image, &options.ipfs_gateway)?;
+        let file_name = format!("{}-logo", current.data.symbol.to_lowercase());
+        let new_image = uploader.upload_file(&file_name, &content_type, &bytes)?;
+        replace_image_links(&mut json, &image, &new_image);
+        outcome.new_image = Some(new_image);
+    }
+
+    let (new_uri, signature) = repoint(rpc, uploader, authority, mint, current, &json)?;
+    outcome.signature = Some(signature);
+    outcome.new_uri = Some(new_uri);
+    Ok(outcome)
+}
+
+/// Fetches the current off-chain JSON, lets `edit` change it, uploads the
+/// result and points the on-chain URI at it. Returns the new URI.
+pub fn rewrite(
+    rpc: &RpcClient,
+    uploader: &dyn Uploader,
+    authority: &Keypair,
+    mint: &Pubkey,
+    ipfs_gateway: &str,
+    edit: impl FnOnce(&mut Value),
+) -> Result<(String, Signature)> {
+    let current = metadata::fetch_metadata(rpc, mint)?
+        .ok_or_else(|| Error::InvalidInput(format!("{mint} has no metadata account")))?;
+    ensure_update_authority(&current, mint, &authority.pubkey())?;
+    let mut json = storage::fetch_json(&current.data.uri, ipfs_gateway)?;
+    let before = json.clone();
+    edit(&mut json);
+    if json == before {
+        return Err(Error::InvalidInput(format!(
+            "{mint}'s metadata JSON already says that"
+        )));
+    }
+    repoint(rpc, uploader, authority, mint, current, &json)
+}
+
+/// Uploads `json` and points `current`'s URI at it.
+fn repoint(
+    rpc: &RpcClient,
+    uploader: &dyn Uploader,
+    authority: &Keypair,
+    mint: &Pubkey,
+    current: Metadata,
+    json: &Value,
+) -> Result<(String, Signature)> {
+    let new_uri = uploader.upload_json(&format!("{} metadata", current.data.name), json)?;
+    let data = DataV2 {
+        uri: new_uri.clone(),
+        ..current.data
+    };
+    data.validate()?;
+    let ix = metadata::update_metadata_accounts_v2(
+        &rpc.programs().metadata,
+        mint,
+        &authority.pubkey(),
+        Some(&data),
+        None,
+        None,
+        None,
+    );
+    let tx = rpc.build_signed(&[ix], &authority.pubkey(), &[authority])?;
+    Ok((new_uri, rpc.send_and_confirm(&tx)?))
+}
+
+/// Points `image` and any matching `properties.files[].uri` at the new location.
+fn replace_image_links(json: &mut Value, old: &str, new: &str) {
+    json["image"] = Value::String(new.to_string());
+    if let Some(files) = json
+        .pointer_mut("/properties/files")
+        .and_then(Value::as_array_mut)
+    {
+        for file in files {
+            if file.get("uri").and_then(Value::as_str) == Some(old) {
+                file["uri"] = Value::String(new.to_string());
+            }
+        }
+    }
+}
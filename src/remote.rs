@@ -0,0 +1,126 @@
+//! Client for a remote signing service, so a key kept in an HSM or a
+//! signing service never has to be on the machine running the tool.
+//!
+//! The protocol is two calls on one URL:
+//!
+//! ```text
+//! GET  <url>                                       -> {"pubkey": "<base58>"}
+//! POST <url> {"pubkey": "<base58>", "message": "<base64>"} -> {"signature": "<base58>"}
+//! ```
+//!
+//! Only serialized transaction messages are sent. Every signature that
+//! comes back is checked against the key before it is used, so a
+//! misbehaving service can make signing fail but cannot slip in a
+//! signature for something else. A bearer token, if the service needs one,
+//! comes from `SIGNER_TOKEN`.
+
+use std::thread;
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Value, json};
+
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::pubkey::Pubkey;
+use crate::signer::Signer;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Services may wait for a person to approve, so replies can be slow.
+const READ_TIMEOUT: Duration = Duration::from_secs(300);
+const ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub struct RemoteSigner {
+    agent: ureq::Agent,
+    url: String,
+    token: Option<String>,
+    pubkey: Pubkey,
+}
+
+impl RemoteSigner {
+    /// Asks the service at `url` which key it signs for.
+    pub fn connect(url: &str, token: Option<String>) -> Result<Self> {
+        let mut signer = RemoteSigner {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(CONNECT_TIMEOUT)
+                .timeout_read(READ_TIMEOUT)
+                .build(),
+            url: url.to_string(),
+            token,
+            pubkey: Pubkey::default(),
+        };
+        let response = signer.request("GET", None)?;
+        signer.pubkey = response["pubkey"]
+            .as_str()
+            .ok_or_else(|| Error::Http(format!("GET {url}: no pubkey in the response")))?
+            .parse()?;
+        Ok(signer)
+    }
+
+    /// Sends a request, retrying connection failures and server errors.
+    /// Ed25519 signatures are deterministic, so a repeated signing request
+    /// yields the same signature.
+    fn request(&self, method: &str, body: Option<&Value>) -> Result<Value> {
+        let what = format!("{method} {}", self.url);
+        let mut attempt = 1;
+        loop {
+            let mut request = self.agent.request(method, &self.url);
+            if let Some(token) = &self.token {
+                request = request.set("Authorization", &format!("Bearer {token}"));
+            }
+            let result = match body {
+                Some(body) => request.send_json(body),
+                None => request.call(),
+            };
+            let retry = match result {
+                Ok(response) => {
+                    return response
+                        .into_json()
+                        .map_err(|e| Error::Http(format!("{what}: {e}")));
+                }
+                Err(ureq::Error::Status(status, response)) if status < 500 => {
+                    let text = response.into_string().unwrap_or_default();
+                    return Err(Error::Http(match status {
+                        401 | 403 => {
+                            format!("{what}: the signing service refused ({status}) {text}")
+                        }
+                        _ => format!("{what}: {status} {text}"),
+                    }));
+                }
+                Err(e) => e,
+            };
+            if attempt == ATTEMPTS {
+                return Err(Error::Http(format!("{what}: {retry}")));
+            }
+            attempt += 1;
+            thread::sleep(RETRY_DELAY);
+        }
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let body = json!({
+            "pubkey": self.pubkey.to_string(),
+            "message": BASE64.encode(message),
+        });
+        let response = self.request("POST", Some(&body))?;
+        let signature: Signature = response["signature"]
+            .as_str()
+            .ok_or_else(|| Error::Http(format!("POST {}: no signature in the response", self.url)))?
+            .parse()?;
+        if !signature.verify(&self.pubkey, message) {
+            return Err(Error::Http(format!(
+                "POST {}: the signature is not {}'s",
+                self.url, self.pubkey
+            )));
+        }
+        Ok(signature)
+    }
+}
@@ -0,0 +1,327 @@
+//! Retiring a token: burning what the authority wallet still holds,
+//! closing its emptied token accounts to get their rent back, pointing the
+//! metadata at a URI that says the token is retired and, if asked,
+//! revoking the mint authority so no more can ever exist.
+//!
+//! [`Retirement::prepare`] reads everything first so the caller can show
+//! what will happen; [`Retirement::execute`] carries on past a failed
+//! transaction and records every outcome in the [`RetirementReport`].
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::launch::now_unix;
+use crate::metadata::{self, Metadata};
+use crate::pack::Packer;
+use crate::pubkey::Pubkey;
+use crate::rehost;
+use crate::rpc::RpcClient;
+use crate::signer::Signer;
+use crate::token::{self, AccountState, AuthorityType, TokenAccount};
+use crate::tx::Instruction;
+
+/// One of the authority's token accounts of the mint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holding {
+    pub account: Pubkey,
+    pub amount: TokenAmount,
+    pub lamports: u64,
+    pub frozen: bool,
+    /// Why the account is left alone, if it is.
+    pub skip: Option<String>,
+}
+
+/// Everything a retirement does, worked out before anything is sent.
+#[derive(Debug, Clone)]
+pub struct Retirement {
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    pub authority: Pubkey,
+    pub supply: TokenAmount,
+    pub holdings: Vec<Holding>,
+    /// The metadata to repoint; `None` when there is none or it can't be
+    /// changed, with the reason in `metadata_skip`.
+    pub metadata: Option<Metadata>,
+    pub metadata_skip: Option<String>,
+    /// Whether the authority holds the mint authority.
+    pub holds_mint_authority: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetiredAccount {
+    pub account: Pubkey,
+    pub burned: TokenAmount,
+    /// Rent returned by closing the account.
+    pub lamports: u64,
+    pub signature: Option<Signature>,
+    pub error: Option<String>,
+}
+
+/// What a retirement did, saved as JSON for the record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetirementReport {
+    pub at: i64,
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub supply_before: TokenAmount,
+    pub supply_after: TokenAmount,
+    pub accounts: Vec<RetiredAccount>,
+    pub metadata_uri: Option<String>,
+    pub metadata_signature: Option<Signature>,
+    pub mint_authority_revoked: Option<Signature>,
+    /// What was skipped or failed, in words.
+    pub notes: Vec<String>,
+}
+
+impl RetirementReport {
+    /// Tokens burned by the transactions that landed.
+    pub fn burned(&self, decimals: u8) -> TokenAmount {
+        let raw = self
+            .accounts
+            .iter()
+            .filter(|a| a.signature.is_some())
+            .map(|a| a.burned.raw())
+            .sum();
+        TokenAmount::from_raw(raw, decimals)
+    }
+
+    pub fn reclaimed_lamports(&self) -> u64 {
+        self.accounts
+            .iter()
+            .filter(|a| a.signature.is_some())
+            .map(|a| a.lamports)
+            .sum()
+    }
+
+    pub fn failed(&self) -> bool {
+        self.accounts.iter().any(|a| a.error.is_some())
+    }
+}
+
+impl Retirement {
+    pub fn prepare(rpc: &RpcClient, mint: &Pubkey, authority: &Pubkey) -> Result<Self> {
+        let info = token::fetch_mint(rpc, mint)?;
+        let decimals = info.mint.decimals;
+        let can_thaw = info.mint.freeze_authority == Some(*authority);
+        let mut holdings = Vec::new();
+        for keyed in rpc.get_token_accounts_by_owner(authority, &info.program_id)? {
+            let account = TokenAccount::unpack(&keyed.account.data)?;
+            if account.mint != *mint {
+                continue;
+            }
+            let frozen = account.state == AccountState::Frozen;
+            holdings.push(Holding {
+                account: keyed.pubkey,
+                amount: TokenAmount::from_raw(account.amount.into(), decimals),
+                lamports: keyed.account.lamports,
+                frozen,
+                skip: (frozen && !can_thaw)
+                    .then(|| format!("frozen, and {authority} can't thaw it")),
+            });
+        }
+        holdings.sort_by_key(|h| h.account);
+
+        let (metadata, metadata_skip) = match metadata::fetch_metadata(rpc, mint)? {
+            None => (None, Some("the token has no metadata account".to_string())),
+            Some(current) => match rehost::ensure_update_authority(&current, mint, authority) {
+                Ok(()) => (Some(current), None),
+                Err(e) => (None, Some(e.to_string())),
+            },
+        };
+        Ok(Retirement {
+            mint: *mint,
+            program_id: info.program_id,
+            decimals,
+            authority: *authority,
+            supply: info.mint.supply_amount(),
+            holdings,
+            metadata,
+            metadata_skip,
+            holds_mint_authority: info.mint.mint_authority == Some(*authority),
+        })
+    }
+
+    /// The accounts that will be emptied and closed.
+    pub fn retired(&self) -> impl Iterator<Item = &Holding> {
+        self.holdings.iter().filter(|h| h.skip.is_none())
+    }
+
+    pub fn burned(&self) -> TokenAmount {
+        let raw = self.retired().map(|h| h.amount.raw()).sum();
+        TokenAmount::from_raw(raw, self.decimals)
+    }
+
+    /// The supply left with other holders once the burns land.
+    pub fn remaining(&self) -> TokenAmount {
+        self.supply
+            .checked_sub(self.burned())
+            .unwrap_or(TokenAmount::zero(self.decimals))
+    }
+
+    fn instructions(&self, holding: &Holding) -> Result<Vec<Instruction>> {
+        let p = &self.program_id;
+        let mut ixs = Vec::new();
+        if holding.frozen {
+            ixs.push(token::thaw_account(
+                p,
+                &holding.account,
+                &self.mint,
+                &self.authority,
+            ));
+        }
+        if !holding.amount.is_zero() {
+            ixs.push(token::burn_checked(
+                p,
+                &holding.account,
+                &self.mint,
+                &self.authority,
+                holding.amount.to_u64()?,
+                self.decimals,
+            ));
+        }
+        ixs.push(token::close_account(
+            p,
+            &holding.account,
+            &self.authority,
+            &self.authority,
+        ));
+        Ok(ixs)
+    }
+
+    /// Burns and closes the accounts, as many per transaction as fit, then
+    /// points the metadata at `uri` and, with `revoke_mint`, revokes the
+    /// mint authority. Stops before the metadata and the revocation if any
+    /// account failed, so a retry starts from a consistent state.
+    pub fn execute(
+        &self,
+        rpc: &RpcClient,
+        signer: &dyn Signer,
+        uri: Option<&str>,
+        revoke_mint: bool,
+    ) -> Result<RetirementReport> {
+        if signer.pubkey() != self.authority {
+            return Err(Error::InvalidInput(format!(
+                "the retirement was prepared for {}, not {}",
+                self.authority,
+                signer.pubkey()
+            )));
+        }
+        let send = |ixs: &[Instruction], critical: bool| -> Result<Signature> {
+            let tx = rpc.build_signed(ixs, &self.authority, &[signer])?;
+            if critical {
+                rpc.send_and_confirm_critical(&tx)
+            } else {
+                rpc.send_and_confirm(&tx)
+            }
+        };
+        let mut report = RetirementReport {
+            at: now_unix(),
+            mint: self.mint,
+            authority: self.authority,
+            supply_before: self.supply,
+            supply_after: self.supply,
+            accounts: Vec::new(),
+            metadata_uri: None,
+            metadata_signature: None,
+            mint_authority_revoked: None,
+            notes: Vec::new(),
+        };
+        for holding in self.holdings.iter() {
+            if let Some(reason) = &holding.skip {
+                report
+                    .notes
+                    .push(format!("{} left open: {reason}", holding.account));
+            }
+        }
+
+        let items = self
+            .retired()
+            .map(|h| Ok((h, self.instructions(h)?)))
+            .collect::<Result<Vec<_>>>()?;
+        for (holdings, ixs) in Packer::new(self.authority).pack(items)? {
+            let (signature, error) = match send(&ixs, false) {
+                Ok(signature) => (Some(signature), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            for holding in holdings {
+                report.accounts.push(RetiredAccount {
+                    account: holding.account,
+                    burned: holding.amount,
+                    lamports: holding.lamports,
+                    signature,
+                    error: error.clone(),
+                });
+            }
+        }
+        report.supply_after = token::fetch_mint(rpc, &self.mint)?.mint.supply_amount();
+        if report.failed() {
+            report
+                .notes
+                .push("some accounts failed; the metadata and mint authority were left".into());
+            return Ok(report);
+        }
+
+        match (&self.metadata, uri) {
+            (Some(current), Some(uri)) => {
+                let mut data = current.data.clone();
+                data.uri = uri.to_string();
+                data.validate()?;
+                let ix = metadata::update_metadata_accounts_v2(
+                    &rpc.programs().metadata,
+                    &self.mint,
+                    &self.authority,
+                    Some(&data),
+                    None,
+                    None,
+                    None,
+                );
+                match send(&[ix], false) {
+                    Ok(signature) => {
+                        report.metadata_uri = Some(uri.to_string());
+                        report.metadata_signature = Some(signature);
+                    }
+                    Err(e) => report.notes.push(format!("metadata not updated: {e}")),
+                }
+            }
+            (Some(_), None) => report.notes.push("metadata left as it was".into()),
+            (None, _) => report.notes.push(format!(
+                "metadata not updated: {}",
+                self.metadata_skip.as_deref().unwrap_or("no metadata")
+            )),
+        }
+
+        if revoke_mint {
+            if self.holds_mint_authority {
+                let ix = token::set_authority(
+                    &self.program_id,
+                    &self.mint,
+                    &self.authority,
+                    AuthorityType::MintTokens,
+                    None,
+                );
+                match send(&[ix], true) {
+                    Ok(signature) => report.mint_authority_revoked = Some(signature),
+                    Err(e) => report
+                        .notes
+                        .push(format!("mint authority not revoked: {e}")),
+                }
+            } else {
+                report.notes.push(format!(
+                    "{} doesn't hold the mint authority",
+                    self.authority
+                ));
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// `retire-<mint>.json` in the working directory.
+pub fn default_report_path(mint: &Pubkey) -> PathBuf {
+    PathBuf::from(format!("retire-{mint}.json"))
+}
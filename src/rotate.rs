@@ -0,0 +1,249 @@
+//! Moving everything out of a wallet whose key may be compromised. The
+//! authorities go first, since they are what an attacker can do the most
+//! damage with, then every token balance, then the SOL; finally the old
+//! wallet is scanned again to confirm nothing was left behind.
+
+use serde::Serialize;
+
+use crate::amount::TokenAmount;
+use crate::error::Result;
+use crate::extension;
+use crate::keypair::{Keypair, Signature};
+use crate::launch::now_unix;
+use crate::metadata;
+use crate::portfolio::{self, AuthorityRoles};
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::system::{self, LAMPORTS_PER_SIGNATURE};
+use crate::token::{self, AuthorityType, TokenAccount};
+use crate::tx::Instruction;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenBalance {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub amount: TokenAmount,
+    /// Moving it needs the hook's extra accounts, which are not resolved.
+    pub transfer_hook: bool,
+}
+
+/// What the old wallet still holds.
+#[derive(Debug, Clone, Serialize)]
+pub struct Holdings {
+    pub lamports: u64,
+    pub tokens: Vec<TokenBalance>,
+    pub authorities: Vec<AuthorityRoles>,
+}
+
+impl Holdings {
+    /// Scans `wallet`'s token accounts and its authorities over `mints`
+    /// and the mints it holds.
+    pub fn load(rpc: &RpcClient, wallet: &Pubkey, mints: &[Pubkey]) -> Result<Self> {
+        let mut tokens = Vec::new();
+        let programs = rpc.programs();
+        for program_id in [programs.token, programs.token_2022] {
+            for keyed in rpc.get_token_accounts_by_owner(wallet, &program_id)? {
+                let account = TokenAccount::unpack(&keyed.account.data)?;
+                let mint = token::fetch_mint(rpc, &account.mint)?;
+                tokens.push(TokenBalance {
+                    account: keyed.pubkey,
+                    mint: account.mint,
+                    program_id,
+                    amount: TokenAmount::from_raw(account.amount as u128, mint.mint.decimals),
+                    transfer_hook: extension::find(&mint.extensions, extension::TRANSFER_HOOK)
+                        .is_some(),
+                });
+            }
+        }
+        let mut checked: Vec<Pubkey> = mints.to_vec();
+        checked.extend(tokens.iter().map(|t| t.mint));
+        checked.sort();
+        checked.dedup();
+        Ok(Holdings {
+            lamports: rpc.get_balance(wallet)?,
+            authorities: portfolio::authority_roles(rpc, wallet, &checked)?,
+            tokens,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lamports == 0 && self.tokens.is_empty() && self.authorities.is_empty()
+    }
+}
+
+/// One transaction of the rotation and how it went.
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationStep {
+    pub description: String,
+    pub signature: Option<Signature>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationReport {
+    pub old_wallet: Pubkey,
+    pub new_wallet: Pubkey,
+    /// Unix seconds.
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub steps: Vec<RotationStep>,
+    /// What the old wallet still held afterwards.
+    pub remaining: Holdings,
+}
+
+impl RotationReport {
+    pub fn is_clean(&self) -> bool {
+        self.remaining.is_empty()
+    }
+}
+
+fn authority_instructions(
+    rpc: &RpcClient,
+    roles: &AuthorityRoles,
+    old: &Pubkey,
+    new: &Pubkey,
+) -> Result<Vec<Instruction>> {
+    let program_id = token::fetch_mint(rpc, &roles.mint)?.program_id;
+    let mut ixs = Vec::new();
+    for (held, kind) in [
+        (roles.mint_authority, AuthorityType::MintTokens),
+        (roles.freeze_authority, AuthorityType::FreezeAccount),
+    ] {
+        if held {
+            ixs.push(token::set_authority(
+                &program_id,
+                &roles.mint,
+                old,
+                kind,
+                Some(new),
+            ));
+        }
+    }
+    if roles.metadata_update_authority {
+        ixs.push(metadata::update_metadata_accounts_v2(
+            &rpc.programs().metadata,
+            &roles.mint,
+            old,
+            None,
+            Some(new),
+            None,
+            None,
+        ));
+    }
+    Ok(ixs)
+}
+
+/// Moves the authorities, tokens and SOL of `old` to `new`, `payer` covering
+/// fees and the new token accounts' rent; it may be `old` itself. Each
+/// transaction's failure is recorded rather than stopping the rotation, so
+/// one stuck account can't keep the rest in the compromised wallet.
+pub fn rotate(
+    rpc: &RpcClient,
+    old: &Keypair,
+    new: &Pubkey,
+    payer: &Keypair,
+    holdings: &Holdings,
+    mints: &[Pubkey],
+) -> Result<RotationReport> {
+    let started_at = now_unix();
+    let old_key = old.pubkey();
+    let payer_key = payer.pubkey();
+    let mut steps = Vec::new();
+    // Authority moves wait for the critical commitment; they can't be undone.
+    let send = |description: String, ixs: Result<Vec<Instruction>>, critical: bool| {
+        let result = ixs.and_then(|ixs| {
+            let signers: &[&Keypair] = if payer_key == old_key {
+                &[old]
+            } else {
+                &[payer, old]
+            };
+            let tx = rpc.build_signed(&ixs, &payer_key, signers)?;
+            if critical {
+                rpc.send_and_confirm_critical(&tx)
+            } else {
+                rpc.send_and_confirm(&tx)
+            }
+        });
+        RotationStep {
+            description,
+            signature: result.as_ref().ok().copied(),
+            error: result.err().map(|e| e.to_string()),
+        }
+    };
+
+    for roles in &holdings.authorities {
+        steps.push(send(
+            format!("{} authority of {}", roles.names().join(", "), roles.mint),
+            authority_instructions(rpc, roles, &old_key, new),
+            true,
+        ));
+    }
+
+    for t in &holdings.tokens {
+        if t.transfer_hook && !t.amount.is_zero() {
+            steps.push(RotationStep {
+                description: format!("{} of {}", t.amount, t.mint),
+                signature: None,
+                error: Some("mint has a transfer hook; move it with the hook's own tooling".into()),
+            });
+            continue;
+        }
+        let mut ixs = Vec::new();
+        if !t.amount.is_zero() {
+            let destination = token::associated_token_address(new, &t.mint, &t.program_id);
+            ixs.push(token::create_associated_token_account_idempotent(
+                &payer_key,
+                new,
+                &t.mint,
+                &t.program_id,
+            ));
+            ixs.push(token::transfer_checked(
+                &t.program_id,
+                &t.account,
+                &t.mint,
+                &destination,
+                &old_key,
+                t.amount.to_u64()?,
+                t.amount.decimals(),
+            ));
+        }
+        // Closing returns the account's rent to the new wallet too.
+        ixs.push(token::close_account(
+            &t.program_id,
+            &t.account,
+            new,
+            &old_key,
+        ));
+        let description = if t.amount.is_zero() {
+            format!("close empty account {}", t.account)
+        } else {
+            format!("{} of {}", t.amount, t.mint)
+        };
+        steps.push(send(description, Ok(ixs), false));
+    }
+
+    // Last, so the fees above could still be paid from it.
+    let lamports = rpc.get_balance(&old_key)?;
+    let sendable = if payer_key == old_key {
+        lamports.saturating_sub(LAMPORTS_PER_SIGNATURE)
+    } else {
+        lamports
+    };
+    if sendable > 0 {
+        steps.push(send(
+            format!("{} SOL", system::format_sol(sendable)),
+            Ok(vec![system::transfer(&old_key, new, sendable)]),
+            false,
+        ));
+    }
+
+    Ok(RotationReport {
+        old_wallet: old_key,
+        new_wallet: *new,
+        started_at,
+        finished_at: now_unix(),
+        steps,
+        remaining: Holdings::load(rpc, &old_key, mints)?,
+    })
+}
@@ -0,0 +1,1004 @@
+use std::error::Error as _;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::audit::AuditLog;
+use crate::cache::RpcCache;
+use crate::compute_budget;
+use crate::error::{Error, Result};
+use crate::explain::explain;
+use crate::fixture::{RecordedCall, Recorder, Replay, RpcErrorObject};
+use crate::keypair::Signature;
+use crate::labels::Labels;
+use crate::programs::Programs;
+use crate::pubkey::Pubkey;
+use crate::signer::Signer;
+use crate::tx::{
+    Hash, Instruction, Message, PACKET_DATA_SIZE, Transaction, VersionedTransaction,
+    inspect_wire_transaction,
+};
+
+/// How long to wait for a sent transaction before giving up; a blockhash is
+/// valid for roughly this long.
+pub(crate) const CONFIRM_TIMEOUT: Duration = Duration::from_secs(90);
+pub(crate) const CONFIRM_POLL: Duration = Duration::from_millis(500);
+/// How long one request may take before it is abandoned, unless
+/// configured.
+pub const RPC_TIMEOUT: Duration = Duration::from_secs(60);
+/// Slots an endpoint may trail a reference by, about ten seconds, before
+/// it counts as lagging.
+pub const SLOT_LAG_WARNING: u64 = 25;
+/// Round trip above which an endpoint counts as slow.
+pub const LATENCY_WARNING: Duration = Duration::from_secs(1);
+/// Most accounts `getMultipleAccounts` returns per request.
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+/// Most signatures `getSignatureStatuses` takes per request.
+pub const MAX_SIGNATURE_STATUSES: usize = 256;
+/// Lookups of a failed transaction's logs before explaining it without
+/// them; a transaction seen failing at `processed` is not served at once.
+const FAILURE_LOG_ATTEMPTS: usize = 3;
+
+/// How settled the state a call reads, or a transaction it waits for, must
+/// be: seen by the leader, voted on by a supermajority, or rooted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
+}
+
+impl fmt::Display for Commitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Commitment {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "processed" => Ok(Commitment::Processed),
+            "confirmed" => Ok(Commitment::Confirmed),
+            "finalized" => Ok(Commitment::Finalized),
+            other => Err(Error::InvalidInput(format!(
+                "unknown commitment '{other}', expected processed, confirmed or finalized"
+            ))),
+        }
+    }
+}
+
+/// An on-chain account as returned by the RPC with base64 data decoded.
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+    pub executable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyedAccount {
+    pub pubkey: Pubkey,
+    pub account: Account,
+}
+
+/// `sendTransaction` options beyond the defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SendOptions {
+    pub skip_preflight: bool,
+    /// How often the node itself re-forwards the transaction; `Some(0)`
+    /// leaves rebroadcasting to the caller.
+    pub max_retries: Option<u32>,
+}
+
+/// Limits returned account data to `length` bytes from `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataSlice {
+    pub offset: usize,
+    pub length: usize,
+}
+
+/// An RPC result together with the slot it was evaluated at.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Response<T> {
+    pub context: Context,
+    pub value: T,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Context {
+    pub slot: u64,
+}
+
+pub enum AccountFilter {
+    DataSize(u64),
+    Memcmp { offset: usize, bytes: Vec<u8> },
+}
+
+impl AccountFilter {
+    fn to_json(&self) -> Value {
+        match self {
+            AccountFilter::DataSize(size) => json!({ "dataSize": size }),
+            AccountFilter::Memcmp { offset, bytes } => json!({
+                "memcmp": {
+                    "offset": offset,
+                    "bytes": bs58::encode(bytes).into_string(),
+                    "encoding": "base58",
+                }
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub slot: u64,
+    pub err: Option<Value>,
+    pub memo: Option<String>,
+    pub block_time: Option<i64>,
+}
+
+/// A confirmed transaction in `json` encoding.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmedTransaction {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub transaction: UiTransaction,
+    pub meta: Option<TransactionMeta>,
+}
+
+impl ConfirmedTransaction {
+    /// Static account keys followed by any keys loaded from lookup tables, in
+    /// the order instruction account indexes refer to them.
+    pub fn account_keys(&self) -> Result<Vec<Pubkey>> {
+        let mut keys = self.transaction.message.account_keys.clone();
+        if let Some(loaded) = self.meta.as_ref().and_then(|m| m.loaded_addresses.as_ref()) {
+            keys.extend(loaded.writable.iter().cloned());
+            keys.extend(loaded.readonly.iter().cloned());
+        }
+        keys.iter().map(|k| k.parse()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UiTransaction {
+    pub signatures: Vec<String>,
+    pub message: UiMessage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiMessage {
+    pub account_keys: Vec<String>,
+    pub instructions: Vec<UiInstruction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    /// Base58-encoded instruction data.
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionMeta {
+    pub err: Option<Value>,
+    pub fee: u64,
+    /// Lamport balances of the account keys before and after.
+    #[serde(default)]
+    pub pre_balances: Vec<u64>,
+    #[serde(default)]
+    pub post_balances: Vec<u64>,
+    #[serde(default)]
+    pub inner_instructions: Option<Vec<InnerInstructions>>,
+    #[serde(default)]
+    pub log_messages: Option<Vec<String>>,
+    #[serde(default)]
+    pub loaded_addresses: Option<LoadedAddresses>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InnerInstructions {
+    pub index: u8,
+    pub instructions: Vec<UiInstruction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>,
+}
+
+/// What `simulateTransaction` reports.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Simulation {
+    pub err: Option<Value>,
+    #[serde(default)]
+    pub logs: Option<Vec<String>>,
+    pub units_consumed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureStatus {
+    pub slot: u64,
+    pub confirmations: Option<u64>,
+    pub err: Option<Value>,
+    pub confirmation_status: Option<String>,
+}
+
+impl SignatureStatus {
+    pub fn is_confirmed(&self) -> bool {
+        self.reached(Commitment::Confirmed)
+    }
+
+    pub fn reached(&self, commitment: Commitment) -> bool {
+        self.confirmation_status
+            .as_deref()
+            .and_then(|status| status.parse::<Commitment>().ok())
+            .is_some_and(|status| status >= commitment)
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcEnvelope {
+    result: Option<Value>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UiAccount {
+    lamports: u64,
+    owner: String,
+    data: (String, String),
+    executable: bool,
+}
+
+impl UiAccount {
+    fn decode(self) -> Result<Account> {
+        let (data, encoding) = self.data;
+        if encoding != "base64" {
+            return Err(Error::InvalidAccountData(format!(
+                "unexpected account encoding '{encoding}'"
+            )));
+        }
+        let data = BASE64
+            .decode(data)
+            .map_err(|e| Error::InvalidAccountData(format!("bad base64: {e}")))?;
+        Ok(Account {
+            lamports: self.lamports,
+            owner: self.owner.parse()?,
+            data,
+            executable: self.executable,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct UiKeyedAccount {
+    pubkey: String,
+    account: UiAccount,
+}
+
+enum Transport {
+    Http(ureq::Agent),
+    Replay(Replay),
+}
+
+fn agent(timeout: Duration) -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(timeout).build()
+}
+
+/// Whether a transport error came from a request running out of time.
+fn timed_out(source: Option<&(dyn std::error::Error + 'static)>) -> bool {
+    source
+        .and_then(|e| e.downcast_ref::<io::Error>())
+        .is_some_and(|e| e.kind() == io::ErrorKind::TimedOut)
+}
+
+/// An endpoint's current slot and how long it took to answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Probe {
+    pub slot: u64,
+    pub latency: Duration,
+}
+
+/// Minimal blocking JSON-RPC client for a Solana endpoint.
+pub struct RpcClient {
+    url: String,
+    transport: Transport,
+    recorder: Option<Recorder>,
+    audit: Option<Arc<AuditLog>>,
+    cache: Option<RpcCache>,
+    next_id: AtomicU64,
+    timeout: Duration,
+    commitment: Commitment,
+    critical_commitment: Commitment,
+    programs: Programs,
+    labels: Labels,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        RpcClient {
+            url: url.into(),
+            transport: Transport::Http(agent(RPC_TIMEOUT)),
+            recorder: None,
+            audit: None,
+            cache: None,
+            next_id: AtomicU64::new(1),
+            timeout: RPC_TIMEOUT,
+            commitment: Commitment::Confirmed,
+            critical_commitment: Commitment::Finalized,
+            programs: Programs::default(),
+            labels: Labels::default(),
+        }
+    }
+
+    /// A client answering every call from a recorded fixture.
+    pub fn replay(replay: Replay) -> Self {
+        RpcClient {
+            url: "replay".into(),
+            transport: Transport::Replay(replay),
+            recorder: None,
+            audit: None,
+            cache: None,
+            next_id: AtomicU64::new(1),
+            timeout: RPC_TIMEOUT,
+            commitment: Commitment::Confirmed,
+            critical_commitment: Commitment::Finalized,
+            programs: Programs::default(),
+            labels: Labels::default(),
+        }
+    }
+
+    /// Appends every call and its answer to `recorder`.
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Abandons any request that takes longer than `timeout`. Has no
+    /// effect on a client replaying a fixture.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        if let Transport::Http(_) = self.transport {
+            self.transport = Transport::Http(agent(timeout));
+        }
+        self.timeout = timeout;
+        self
+    }
+
+    /// Logs every transaction submitted through this client to `audit`.
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    pub fn audit(&self) -> Option<&Arc<AuditLog>> {
+        self.audit.as_ref()
+    }
+
+    /// Serves repeated account scans from `cache`, and lets inspection
+    /// commands reuse metadata through it.
+    pub fn with_cache(mut self, cache: RpcCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Commitment for reads and for waiting on ordinary transactions.
+    pub fn with_commitment(mut self, commitment: Commitment) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    /// Commitment to wait for on transactions that can't be undone, such
+    /// as revoking an authority.
+    pub fn with_critical_commitment(mut self, commitment: Commitment) -> Self {
+        self.critical_commitment = commitment;
+        self
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        self.commitment
+    }
+
+    pub fn critical_commitment(&self) -> Commitment {
+        self.critical_commitment
+    }
+
+    /// Where the cluster deploys the programs the tool calls.
+    pub fn with_programs(mut self, programs: Programs) -> Self {
+        self.programs = programs;
+        self
+    }
+
+    pub fn programs(&self) -> &Programs {
+        &self.programs
+    }
+
+    /// Names programs and mints in the errors the client explains.
+    pub fn with_labels(mut self, labels: Labels) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    /// Transaction history is only served from confirmed blocks on.
+    fn history_commitment(&self) -> Commitment {
+        self.commitment.max(Commitment::Confirmed)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn timeout_error(&self, method: &str) -> Error {
+        Error::Timeout(format!(
+            "{method}: no answer from {} within {}s",
+            self.url,
+            self.timeout.as_secs()
+        ))
+    }
+
+    pub fn cache(&self) -> Option<&RpcCache> {
+        self.cache.as_ref()
+    }
+
+    /// Performs a raw JSON-RPC call and deserializes its `result`.
+    pub fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let cache = self
+            .cache
+            .as_ref()
+            .filter(|_| RpcCache::is_cacheable(method));
+        if let Some(result) = cache.and_then(|c| c.get(&self.url, method, &params)) {
+            return serde_json::from_value(result)
+                .map_err(|e| Error::Http(format!("{method}: unexpected cached shape: {e}")));
+        }
+        let envelope = self.exchange(method, &params)?;
+        if let Some(err) = envelope.error {
+            return Err(Error::Rpc {
+                code: err.code,
+                message: err.message,
+            });
+        }
+        let result = envelope.result.unwrap_or(Value::Null);
+        if let Some(cache) = cache {
+            cache.put(&self.url, method, &params, &result);
+        }
+        // A `null` result is meaningful for lookups returning `Option`.
+        serde_json::from_value(result)
+            .map_err(|e| Error::Http(format!("{method}: unexpected result shape: {e}")))
+    }
+
+    /// Sends one request and returns the answer as is, recording it.
+    fn exchange(&self, method: &str, params: &Value) -> Result<RpcEnvelope> {
+        let envelope = match &self.transport {
+            Transport::Http(agent) => {
+                let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                let body =
+                    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+                let response = agent
+                    .post(&self.url)
+                    .send_json(body)
+                    .map_err(|e| match &e {
+                        ureq::Error::Transport(t) if timed_out(t.source()) => {
+                            self.timeout_error(method)
+                        }
+                        _ => Error::Http(format!("{method}: {e}")),
+                    })?;
+                response
+                    .into_json::<RpcEnvelope>()
+                    .map_err(|e| match e.kind() {
+                        io::ErrorKind::TimedOut => self.timeout_error(method),
+                        _ => Error::Http(format!("{method}: unreadable response: {e}")),
+                    })?
+            }
+            Transport::Replay(replay) => {
+                let call = replay.respond(method, params)?;
+                RpcEnvelope {
+                    result: call.result,
+                    error: call.error,
+                }
+            }
+        };
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&RecordedCall {
+                method: method.to_string(),
+                params: Some(params.clone()),
+                result: envelope.result.clone(),
+                error: envelope.error.clone(),
+            });
+        }
+        Ok(envelope)
+    }
+
+    pub fn get_account(&self, pubkey: &Pubkey) -> Result<Response<Option<Account>>> {
+        let response: Response<Option<UiAccount>> = self.call(
+            "getAccountInfo",
+            json!([pubkey.to_string(), { "encoding": "base64", "commitment": self.commitment }]),
+        )?;
+        Ok(Response {
+            context: response.context,
+            value: response.value.map(UiAccount::decode).transpose()?,
+        })
+    }
+
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[AccountFilter],
+        data_slice: Option<DataSlice>,
+    ) -> Result<Response<Vec<KeyedAccount>>> {
+        let filters: Vec<Value> = filters.iter().map(AccountFilter::to_json).collect();
+        let mut config = json!({
+            "encoding": "base64",
+            "commitment": self.commitment,
+            "withContext": true,
+            "filters": filters,
+        });
+        if let Some(slice) = data_slice {
+            config["dataSlice"] = json!({ "offset": slice.offset, "length": slice.length });
+        }
+        let response: Response<Vec<UiKeyedAccount>> = self.call(
+            "getProgramAccounts",
+            json!([program_id.to_string(), config]),
+        )?;
+        let accounts = response
+            .value
+            .into_iter()
+            .map(|keyed| {
+                Ok(KeyedAccount {
+                    pubkey: keyed.pubkey.parse()?,
+                    account: keyed.account.decode()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Response {
+            context: response.context,
+            value: accounts,
+        })
+    }
+
+    /// Every token account of `owner` under one token program.
+    pub fn get_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<Vec<KeyedAccount>> {
+        let response: Response<Vec<UiKeyedAccount>> = self.call(
+            "getTokenAccountsByOwner",
+            json!([
+                owner.to_string(),
+                { "programId": program_id.to_string() },
+                { "encoding": "base64", "commitment": self.commitment },
+            ]),
+        )?;
+        response
+            .value
+            .into_iter()
+            .map(|keyed| {
+                Ok(KeyedAccount {
+                    pubkey: keyed.pubkey.parse()?,
+                    account: keyed.account.decode()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Reads any number of accounts, [`MAX_MULTIPLE_ACCOUNTS`] per request.
+    /// The context is that of the oldest chunk.
+    pub fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+        data_slice: Option<DataSlice>,
+    ) -> Result<Response<Vec<Option<Account>>>> {
+        let mut slot = u64::MAX;
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let keys: Vec<String> = chunk.iter().map(Pubkey::to_string).collect();
+            let mut config = json!({ "encoding": "base64", "commitment": self.commitment });
+            if let Some(slice) = data_slice {
+                config["dataSlice"] = json!({ "offset": slice.offset, "length": slice.length });
+            }
+            let response: Response<Vec<Option<UiAccount>>> =
+                self.call("getMultipleAccounts", json!([keys, config]))?;
+            slot = slot.min(response.context.slot);
+            for account in response.value {
+                accounts.push(account.map(UiAccount::decode).transpose()?);
+            }
+        }
+        Ok(Response {
+            context: Context {
+                slot: if pubkeys.is_empty() { 0 } else { slot },
+            },
+            value: accounts,
+        })
+    }
+
+    /// Returns up to `limit` signatures involving `address`, newest first,
+    /// starting before `before` when given.
+    pub fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SignatureInfo>> {
+        let mut config = json!({ "limit": limit, "commitment": self.history_commitment() });
+        if let Some(before) = before {
+            config["before"] = json!(before);
+        }
+        self.call(
+            "getSignaturesForAddress",
+            json!([address.to_string(), config]),
+        )
+    }
+
+    pub fn get_transaction(&self, signature: &str) -> Result<Option<ConfirmedTransaction>> {
+        self.call(
+            "getTransaction",
+            json!([signature, {
+                "encoding": "json",
+                "commitment": self.history_commitment(),
+                "maxSupportedTransactionVersion": 0,
+            }]),
+        )
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash> {
+        #[derive(Deserialize)]
+        struct Blockhash {
+            blockhash: String,
+        }
+        let response: Response<Blockhash> = self.call(
+            "getLatestBlockhash",
+            json!([{ "commitment": self.commitment }]),
+        )?;
+        response.value.blockhash.parse()
+    }
+
+    /// Produced blocks between two slots, inclusive; skipped slots are left out.
+    pub fn get_blocks(&self, start_slot: u64, end_slot: u64) -> Result<Vec<u64>> {
+        self.call(
+            "getBlocks",
+            json!([start_slot, end_slot, { "commitment": "finalized" }]),
+        )
+    }
+
+    /// Hash of the finalized block at `slot`.
+    pub fn get_block_hash(&self, slot: u64) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Block {
+            blockhash: String,
+        }
+        let block: Block = self.call(
+            "getBlock",
+            json!([slot, {
+                "commitment": "finalized",
+                "transactionDetails": "none",
+                "rewards": false,
+                "maxSupportedTransactionVersion": 0,
+            }]),
+        )?;
+        Ok(block.blockhash)
+    }
+
+    pub fn get_genesis_hash(&self) -> Result<String> {
+        self.call("getGenesisHash", json!([]))
+    }
+
+    pub fn get_slot(&self) -> Result<u64> {
+        self.call("getSlot", json!([{ "commitment": self.commitment }]))
+    }
+
+    /// Times a `getSlot` round trip.
+    pub fn probe(&self) -> Result<Probe> {
+        let started = Instant::now();
+        let slot = self.get_slot()?;
+        Ok(Probe {
+            slot,
+            latency: started.elapsed(),
+        })
+    }
+
+    pub fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
+        self.call(
+            "getMinimumBalanceForRentExemption",
+            json!([data_len, { "commitment": self.commitment }]),
+        )
+    }
+
+    pub fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        let response: Response<u64> = self.call(
+            "getBalance",
+            json!([pubkey.to_string(), { "commitment": self.commitment }]),
+        )?;
+        Ok(response.value)
+    }
+
+    /// When `slot` was produced, in Unix seconds, as its leader reported;
+    /// `None` when the node doesn't know.
+    pub fn get_block_time(&self, slot: u64) -> Result<Option<i64>> {
+        self.call("getBlockTime", json!([slot]))
+    }
+
+    /// Asks the cluster faucet for lamports; only devnet, testnet and local
+    /// validators have one.
+    pub fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> Result<Signature> {
+        let signature: String = self.call(
+            "requestAirdrop",
+            json!([pubkey.to_string(), lamports, { "commitment": self.commitment }]),
+        )?;
+        signature.parse()
+    }
+
+    /// Runs `tx` against current state without sending it. Signatures are
+    /// not checked, so it may be unsigned.
+    pub fn simulate_transaction(&self, tx: &Transaction) -> Result<Simulation> {
+        self.simulate_wire(&tx.serialize())
+    }
+
+    /// [`simulate_transaction`](Self::simulate_transaction) for a message
+    /// that may load accounts from lookup tables.
+    pub fn simulate_versioned_transaction(&self, tx: &VersionedTransaction) -> Result<Simulation> {
+        self.simulate_wire(&tx.serialize())
+    }
+
+    fn simulate_wire(&self, wire: &[u8]) -> Result<Simulation> {
+        let response: Response<Simulation> = self.call(
+            "simulateTransaction",
+            json!([BASE64.encode(wire), {
+                "encoding": "base64",
+                "sigVerify": false,
+                "commitment": self.commitment,
+            }]),
+        )?;
+        Ok(response.value)
+    }
+
+    /// Signs `instructions` on a fresh blockhash; see
+    /// [`sign_simulated`](Self::sign_simulated).
+    pub fn build_signed<S: Signer + ?Sized>(
+        &self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        signers: &[&S],
+    ) -> Result<Transaction> {
+        let blockhash = self.get_latest_blockhash()?;
+        Ok(self
+            .sign_simulated(instructions, payer, blockhash, signers)?
+            .0)
+    }
+
+    /// Simulates `instructions` unsigned, then signs them with the compute
+    /// unit limit set to what the simulation consumed plus a margin, so the
+    /// fee is priced on what the transaction uses rather than the default
+    /// 200,000 units per instruction. A limit the instructions already set
+    /// is kept as chosen, and none is added where it would not fit. Returns
+    /// the units consumed with the transaction; one the simulation rejects
+    /// fails here, before anyone is asked to sign it.
+    pub fn sign_simulated<S: Signer + ?Sized>(
+        &self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        blockhash: Hash,
+        signers: &[&S],
+    ) -> Result<(Transaction, Option<u64>)> {
+        let unsigned =
+            |ixs: &[Instruction]| Transaction::new_unsigned(Message::new(ixs, payer, blockhash));
+        let with_limit = compute_budget::with_compute_unit_limit(
+            instructions,
+            compute_budget::MAX_COMPUTE_UNIT_LIMIT,
+        );
+        // A batch packed up to the size limit has no room for one more
+        // instruction; it keeps the default allowance.
+        let measure = !compute_budget::has_compute_unit_limit(instructions)
+            && unsigned(&with_limit).serialize().len() <= PACKET_DATA_SIZE;
+        let probe = if measure { &with_limit } else { instructions };
+        let simulation = self.simulate_transaction(&unsigned(probe))?;
+        if let Some(err) = simulation.err {
+            let logs = simulation.logs.unwrap_or_default();
+            return Err(Error::SimulationFailed(
+                explain(&err, &logs, &self.programs, &self.labels).to_string(),
+            ));
+        }
+        let mut tx = match simulation.units_consumed {
+            Some(units) if measure => unsigned(&compute_budget::with_compute_unit_limit(
+                instructions,
+                compute_budget::measured_limit(units),
+            )),
+            _ => unsigned(instructions),
+        };
+        tx.sign(signers)?;
+        Ok((tx, simulation.units_consumed))
+    }
+
+    /// Submits a signed transaction after preflight simulation.
+    pub fn send_transaction(&self, tx: &Transaction) -> Result<Signature> {
+        self.send_raw_transaction(&tx.serialize())
+    }
+
+    /// Submits an already serialized transaction, such as one signed earlier
+    /// against a durable nonce.
+    pub fn send_raw_transaction(&self, wire: &[u8]) -> Result<Signature> {
+        self.send_raw_transaction_with(wire, SendOptions::default())
+    }
+
+    pub fn send_raw_transaction_with(
+        &self,
+        wire: &[u8],
+        options: SendOptions,
+    ) -> Result<Signature> {
+        // Logged before it leaves, so nothing is submitted unrecorded.
+        if let Some(audit) = &self.audit {
+            audit.record_transaction(wire)?;
+        }
+        let encoded = BASE64.encode(wire);
+        let mut config = json!({ "encoding": "base64", "preflightCommitment": self.commitment });
+        if options.skip_preflight {
+            config["skipPreflight"] = json!(true);
+        }
+        if let Some(max_retries) = options.max_retries {
+            config["maxRetries"] = json!(max_retries);
+        }
+        // Not through `call`, which would drop the preflight logs.
+        let envelope = self.exchange("sendTransaction", &json!([encoded, config]))?;
+        if let Some(err) = envelope.error {
+            return Err(self.preflight_error(wire, err));
+        }
+        let signature: String = serde_json::from_value(envelope.result.unwrap_or(Value::Null))
+            .map_err(|e| Error::Http(format!("sendTransaction: unexpected result shape: {e}")))?;
+        signature.parse()
+    }
+
+    /// A transaction rejected by preflight simulation, explained from the
+    /// simulated logs; any other error as the node reported it.
+    fn preflight_error(&self, wire: &[u8], error: RpcErrorObject) -> Error {
+        let data = error.data.as_ref();
+        let failure = data.and_then(|d| d.get("err")).filter(|e| !e.is_null());
+        match (failure, inspect_wire_transaction(wire)) {
+            (Some(err), Ok((signature, ..))) => {
+                let logs: Vec<String> = data
+                    .and_then(|d| d.get("logs"))
+                    .and_then(|l| serde_json::from_value(l.clone()).ok())
+                    .unwrap_or_default();
+                Error::TransactionFailed {
+                    signature: signature.to_string(),
+                    reason: format!(
+                        "rejected in simulation: {}",
+                        explain(err, &logs, &self.programs, &self.labels)
+                    ),
+                }
+            }
+            _ => Error::Rpc {
+                code: error.code,
+                message: error.message,
+            },
+        }
+    }
+
+    /// The error for a transaction that landed and failed, explained from
+    /// its logs when the node serves them yet.
+    pub fn transaction_failure(&self, signature: &Signature, err: &Value) -> Error {
+        let mut logs = Vec::new();
+        for attempt in 0..FAILURE_LOG_ATTEMPTS {
+            match self.get_transaction(&signature.to_string()) {
+                Ok(Some(tx)) => {
+                    logs = tx.meta.and_then(|m| m.log_messages).unwrap_or_default();
+                    break;
+                }
+                Ok(None) if attempt + 1 < FAILURE_LOG_ATTEMPTS => thread::sleep(CONFIRM_POLL),
+                _ => break,
+            }
+        }
+        Error::TransactionFailed {
+            signature: signature.to_string(),
+            reason: explain(err, &logs, &self.programs, &self.labels).to_string(),
+        }
+    }
+
+    pub fn get_signature_status(&self, signature: &Signature) -> Result<Option<SignatureStatus>> {
+        let response: Response<Vec<Option<SignatureStatus>>> = self.call(
+            "getSignatureStatuses",
+            json!([[signature.to_string()], { "searchTransactionHistory": false }]),
+        )?;
+        Ok(response.value.into_iter().next().flatten())
+    }
+
+    /// Statuses of many signatures, [`MAX_SIGNATURE_STATUSES`] per request.
+    /// `search_history` also finds transactions older than the status cache.
+    pub fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+        search_history: bool,
+    ) -> Result<Vec<Option<SignatureStatus>>> {
+        let mut statuses = Vec::with_capacity(signatures.len());
+        for chunk in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+            let keys: Vec<String> = chunk.iter().map(Signature::to_string).collect();
+            let response: Response<Vec<Option<SignatureStatus>>> = self.call(
+                "getSignatureStatuses",
+                json!([keys, { "searchTransactionHistory": search_history }]),
+            )?;
+            statuses.extend(response.value);
+        }
+        Ok(statuses)
+    }
+
+    /// Sends a transaction and polls until it is confirmed or fails.
+    pub fn send_and_confirm(&self, tx: &Transaction) -> Result<Signature> {
+        let signature = self.send_transaction(tx)?;
+        self.confirm_transaction(&signature)?;
+        Ok(signature)
+    }
+
+    /// Like [`send_and_confirm`](Self::send_and_confirm), waiting for the
+    /// critical commitment.
+    pub fn send_and_confirm_critical(&self, tx: &Transaction) -> Result<Signature> {
+        let signature = self.send_transaction(tx)?;
+        self.confirm_transaction_at(&signature, self.critical_commitment)?;
+        Ok(signature)
+    }
+
+    /// Polls until a sent transaction is confirmed or fails.
+    pub fn confirm_transaction(&self, signature: &Signature) -> Result<()> {
+        self.confirm_transaction_at(signature, self.commitment)
+    }
+
+    /// Polls until a sent transaction reaches `commitment` or fails.
+    pub fn confirm_transaction_at(
+        &self,
+        signature: &Signature,
+        commitment: Commitment,
+    ) -> Result<()> {
+        let started = Instant::now();
+        while started.elapsed() < CONFIRM_TIMEOUT {
+            if let Some(status) = self.get_signature_status(signature)? {
+                if let Some(err) = status.err {
+                    return Err(self.transaction_failure(signature, &err));
+                }
+                if status.reached(commitment) {
+                    return Ok(());
+                }
+            }
+            thread::sleep(CONFIRM_POLL);
+        }
+        Err(Error::Timeout(format!(
+            "transaction {signature} was not {commitment} within {}s",
+            CONFIRM_TIMEOUT.as_secs()
+        )))
+    }
+}
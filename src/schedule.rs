@@ -0,0 +1,277 @@
+//! Authority revocations committed to ahead of time.
+//!
+//! The revocation is signed when it is scheduled, against a durable nonce
+//! instead of a recent blockhash, so the transaction stays valid until the
+//! nonce is advanced. It is stored locally and broadcast by `execute` once
+//! its time or slot arrives. Publishing the transaction lets anyone check,
+//! and if needed submit, the commitment; the only way back out is `cancel`,
+//! which advances the nonce on-chain for everyone to see.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::keypair::{Keypair, Signature};
+use crate::launch::now_unix;
+use crate::network::Network;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::session::unique_id;
+use crate::system::{self, NonceState};
+use crate::token::{self, AuthorityType};
+use crate::tx::{Instruction, Message, Transaction};
+
+const SCHEDULE_DIR: &str = "schedule";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Due {
+    /// Unix seconds.
+    At(i64),
+    Slot(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ScheduleStatus {
+    Pending,
+    Executed { signature: Signature },
+    Cancelled { signature: Signature },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledRevocation {
+    pub id: String,
+    pub network: Network,
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub authority: Pubkey,
+    pub revoke: Vec<AuthorityType>,
+    pub due: Due,
+    pub nonce_account: Pubkey,
+    /// The signed revocation, base64 wire format.
+    pub transaction: String,
+    pub signature: Signature,
+    /// Unix seconds.
+    pub created_at: i64,
+    pub status: ScheduleStatus,
+}
+
+impl ScheduledRevocation {
+    pub fn is_due(&self, now: i64, slot: u64) -> bool {
+        self.status == ScheduleStatus::Pending
+            && match self.due {
+                Due::At(at) => now >= at,
+                Due::Slot(at) => slot >= at,
+            }
+    }
+}
+
+/// Derived like a launch's mint key, so nothing secret is stored on disk.
+fn nonce_keypair(authority: &Keypair, id: &str) -> Keypair {
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(b"launch-solana revocation nonce")
+        .chain_update(authority.to_bytes())
+        .chain_update(id.as_bytes())
+        .finalize()
+        .into();
+    Keypair::from_seed(&seed)
+}
+
+fn send(rpc: &RpcClient, ixs: &[Instruction], signers: &[&Keypair]) -> Result<Signature> {
+    let tx = rpc.build_signed(ixs, &signers[0].pubkey(), signers)?;
+    rpc.send_and_confirm(&tx)
+}
+
+fn fetch_nonce(rpc: &RpcClient, address: &Pubkey) -> Result<NonceState> {
+    let account = rpc
+        .get_account(address)?
+        .value
+        .ok_or(Error::AccountNotFound(*address))?;
+    NonceState::unpack(&account.data)
+}
+
+/// Creates the nonce account and signs the revocation against it.
+pub fn schedule(
+    rpc: &RpcClient,
+    authority: &Keypair,
+    id: String,
+    network: Network,
+    mint: &Pubkey,
+    revoke: Vec<AuthorityType>,
+    due: Due,
+) -> Result<ScheduledRevocation> {
+    if revoke.is_empty() {
+        return Err(Error::InvalidInput("nothing to revoke".into()));
+    }
+    let key = authority.pubkey();
+    let mint_account = token::fetch_mint(rpc, mint)?;
+    for kind in &revoke {
+        let current = match kind {
+            AuthorityType::MintTokens => mint_account.mint.mint_authority,
+            AuthorityType::FreezeAccount => mint_account.mint.freeze_authority,
+            other => {
+                return Err(Error::InvalidInput(format!(
+                    "{} is not a mint authority",
+                    other.as_str()
+                )));
+            }
+        };
+        if current != Some(key) {
+            return Err(Error::InvalidInput(format!(
+                "{} authority of {mint} is not held by {key}",
+                kind.as_str()
+            )));
+        }
+    }
+
+    let nonce = nonce_keypair(authority, &id);
+    let rent = rpc.get_minimum_balance_for_rent_exemption(system::NONCE_ACCOUNT_LEN)?;
+    send(
+        rpc,
+        &system::create_nonce_account(&key, &nonce.pubkey(), &key, rent),
+        &[authority, &nonce],
+    )?;
+    let state = fetch_nonce(rpc, &nonce.pubkey())?;
+
+    let mut ixs = vec![system::advance_nonce_account(&nonce.pubkey(), &key)];
+    ixs.extend(
+        revoke
+            .iter()
+            .map(|kind| token::set_authority(&mint_account.program_id, mint, &key, *kind, None)),
+    );
+    let message = Message::new(&ixs, &key, state.nonce);
+    let mut tx = Transaction::new_unsigned(message);
+    tx.sign(&[authority])?;
+
+    Ok(ScheduledRevocation {
+        id,
+        network,
+        mint: *mint,
+        program_id: mint_account.program_id,
+        authority: key,
+        revoke,
+        due,
+        nonce_account: nonce.pubkey(),
+        transaction: BASE64.encode(tx.serialize()),
+        signature: tx.signature(),
+        created_at: now_unix(),
+        status: ScheduleStatus::Pending,
+    })
+}
+
+/// Broadcasts a due revocation. Fails if the nonce was advanced since it
+/// was signed, which means it was cancelled or already submitted.
+pub fn execute(rpc: &RpcClient, item: &mut ScheduledRevocation) -> Result<Signature> {
+    let wire = BASE64
+        .decode(&item.transaction)
+        .map_err(|e| Error::Config(format!("schedule {}: bad transaction: {e}", item.id)))?;
+    if let Some(status) = rpc.get_signature_status(&item.signature)?
+        && status.err.is_none()
+    {
+        rpc.confirm_transaction_at(&item.signature, rpc.critical_commitment())?;
+        item.status = ScheduleStatus::Executed {
+            signature: item.signature,
+        };
+        return Ok(item.signature);
+    }
+    let signature = rpc.send_raw_transaction(&wire)?;
+    rpc.confirm_transaction_at(&signature, rpc.critical_commitment())?;
+    item.status = ScheduleStatus::Executed { signature };
+    Ok(signature)
+}
+
+/// Advances the nonce so the signed revocation can never land.
+pub fn cancel(
+    rpc: &RpcClient,
+    authority: &Keypair,
+    item: &mut ScheduledRevocation,
+) -> Result<Signature> {
+    if item.status != ScheduleStatus::Pending {
+        return Err(Error::InvalidInput(format!(
+            "schedule {} is no longer pending",
+            item.id
+        )));
+    }
+    if item.authority != authority.pubkey() {
+        return Err(Error::InvalidInput(format!(
+            "schedule {} was signed by {}",
+            item.id, item.authority
+        )));
+    }
+    let ix = system::advance_nonce_account(&item.nonce_account, &authority.pubkey());
+    let signature = send(rpc, &[ix], &[authority])?;
+    item.status = ScheduleStatus::Cancelled { signature };
+    Ok(signature)
+}
+
+/// Scheduled revocations stored as one JSON file per ID.
+pub struct ScheduleStore {
+    dir: PathBuf,
+}
+
+impl ScheduleStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(ScheduleStore { dir })
+    }
+
+    pub fn open_default() -> Result<Self> {
+        Self::open(Config::dir()?.join(SCHEDULE_DIR))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn new_id(&self) -> String {
+        unique_id(&self.dir)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    pub fn save(&self, item: &ScheduledRevocation) -> Result<()> {
+        fs::write(self.path(&item.id), serde_json::to_string_pretty(item)?)?;
+        Ok(())
+    }
+
+    pub fn load(&self, id: &str) -> Result<ScheduledRevocation> {
+        let path = self.path(id);
+        if !path.exists() {
+            return Err(Error::InvalidInput(format!(
+                "no scheduled revocation '{id}'"
+            )));
+        }
+        let raw = fs::read_to_string(&path)?;
+        serde_json::from_str(&raw).map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+    }
+
+    /// All scheduled revocations, soonest first.
+    pub fn list(&self) -> Result<Vec<ScheduledRevocation>> {
+        let mut items: Vec<ScheduledRevocation> = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                // Unreadable files are skipped so one bad entry can't hide the rest.
+                let raw = fs::read_to_string(&path)?;
+                if let Ok(item) = serde_json::from_str(&raw) {
+                    items.push(item);
+                }
+            }
+        }
+        items.sort_by_key(|i: &ScheduledRevocation| match i.due {
+            Due::At(at) => (0, at as u64),
+            Due::Slot(slot) => (1, slot),
+        });
+        Ok(items)
+    }
+}
@@ -0,0 +1,254 @@
+//! Screening recipient addresses for the mistakes that lose tokens.
+//!
+//! Tokens sent to a program, a mint or a token account rather than a
+//! wallet are usually gone for good: the associated account created for
+//! such an address belongs to no one who can sign. Address poisoning and
+//! clipboard hijacking swap in an address that shares the first and last
+//! characters of one used before, which is all most people check. The
+//! [`Screen`] flags all of these; the caller decides whether to go on.
+//!
+//! Addresses used before come from the [`History`] of transfer recipients
+//! kept in the config directory, plus whatever the caller adds.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::compute_budget::COMPUTE_BUDGET_PROGRAM_ID;
+use crate::config::Config;
+use crate::error::Result;
+use crate::memo::{MEMO_PROGRAM_ID, MEMO_V1_PROGRAM_ID};
+use crate::pack::ADDRESS_LOOKUP_TABLE_PROGRAM_ID;
+use crate::programs::Programs;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::squads::SQUADS_PROGRAM_ID;
+use crate::system::{RECENT_BLOCKHASHES_SYSVAR_ID, RENT_SYSVAR_ID, SYSTEM_PROGRAM_ID};
+use crate::token::{self, ASSOCIATED_TOKEN_PROGRAM_ID, Mint, TokenAccount};
+
+const HISTORY_FILE: &str = "recent-recipients.json";
+/// Addresses the history keeps, newest first.
+const HISTORY_LEN: usize = 256;
+/// Characters at each end people compare when checking an address.
+const AFFIX_LEN: usize = 4;
+/// Edits within which two different addresses count as a typo of each other.
+const MAX_TYPO_EDITS: usize = 3;
+
+/// Something wrong with a recipient.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// A program or sysvar, as "the System program".
+    Program(String),
+    /// The mint being sent, or another mint.
+    Mint { same: bool },
+    /// A token account, which has an owner of its own.
+    TokenAccount { owner: Pubkey },
+    /// The sender's own account of the mint.
+    SourceAccount,
+    /// Differs from an address used before by a few characters, or matches
+    /// its ends only.
+    LookAlike(Pubkey),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Program(name) => write!(f, "is {name}, not a wallet"),
+            Warning::Mint { same: true } => f.write_str("is the token's own mint"),
+            Warning::Mint { same: false } => f.write_str("is a token mint, not a wallet"),
+            Warning::TokenAccount { owner } => {
+                write!(f, "is a token account; its owner is the wallet {owner}")
+            }
+            Warning::SourceAccount => f.write_str("is the sender's own token account"),
+            Warning::LookAlike(known) => write!(
+                f,
+                "looks like {known}, used before, but is a different address"
+            ),
+        }
+    }
+}
+
+/// Levenshtein distance between two strings, by character.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != *cb))
+                .min(above + 1)
+                .min(row[j] + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Whether two different addresses are easy to mistake for each other:
+/// a few edits apart, or with the same characters at both ends.
+pub fn looks_alike(a: &Pubkey, b: &Pubkey) -> bool {
+    if a == b {
+        return false;
+    }
+    let (a, b) = (a.to_string(), b.to_string());
+    let ends = |s: &str| {
+        (
+            s[..AFFIX_LEN].to_string(),
+            s[s.len() - AFFIX_LEN..].to_string(),
+        )
+    };
+    ends(&a) == ends(&b) || edit_distance(&a, &b) <= MAX_TYPO_EDITS
+}
+
+/// The programs and sysvars no one sends tokens to on purpose.
+fn known_programs(programs: &Programs) -> Vec<(Pubkey, &'static str)> {
+    vec![
+        (SYSTEM_PROGRAM_ID, "the System program"),
+        (programs.token, "the Token program"),
+        (programs.token_2022, "the Token-2022 program"),
+        (
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            "the Associated Token Account program",
+        ),
+        (programs.metadata, "the Token Metadata program"),
+        (MEMO_PROGRAM_ID, "the Memo program"),
+        (MEMO_V1_PROGRAM_ID, "the Memo program (v1)"),
+        (COMPUTE_BUDGET_PROGRAM_ID, "the Compute Budget program"),
+        (
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            "the Address Lookup Table program",
+        ),
+        (SQUADS_PROGRAM_ID, "the Squads program"),
+        (RENT_SYSVAR_ID, "the Rent sysvar"),
+        (RECENT_BLOCKHASHES_SYSVAR_ID, "the RecentBlockhashes sysvar"),
+    ]
+}
+
+/// Checks recipients of one mint sent from one wallet.
+#[derive(Debug, Clone)]
+pub struct Screen {
+    mint: Pubkey,
+    source: Pubkey,
+    programs: Vec<(Pubkey, &'static str)>,
+    known: Vec<Pubkey>,
+}
+
+impl Screen {
+    pub fn new(programs: &Programs, mint: &Pubkey, program_id: &Pubkey, sender: &Pubkey) -> Self {
+        Screen {
+            mint: *mint,
+            source: token::associated_token_address(sender, mint, program_id),
+            programs: known_programs(programs),
+            known: vec![*sender],
+        }
+    }
+
+    /// Adds addresses used before, to compare recipients with.
+    pub fn with_known(mut self, known: impl IntoIterator<Item = Pubkey>) -> Self {
+        for key in known {
+            if !self.known.contains(&key) {
+                self.known.push(key);
+            }
+        }
+        self
+    }
+
+    /// What can be told from the address alone.
+    pub fn check(&self, recipient: &Pubkey) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        if let Some((_, name)) = self.programs.iter().find(|(id, _)| id == recipient) {
+            warnings.push(Warning::Program(name.to_string()));
+        }
+        if *recipient == self.mint {
+            warnings.push(Warning::Mint { same: true });
+        }
+        if *recipient == self.source {
+            warnings.push(Warning::SourceAccount);
+        }
+        if let Some(known) = self.known.iter().find(|k| looks_alike(k, recipient)) {
+            warnings.push(Warning::LookAlike(*known));
+        }
+        warnings
+    }
+
+    /// [`Screen::check`] for each of `recipients`, plus what their accounts
+    /// show: executable programs, mints and token accounts. Only recipients
+    /// with warnings are returned.
+    pub fn check_accounts(
+        &self,
+        rpc: &RpcClient,
+        recipients: &[Pubkey],
+    ) -> Result<Vec<(Pubkey, Vec<Warning>)>> {
+        let accounts = rpc.get_multiple_accounts(recipients, None)?.value;
+        let mut flagged = Vec::new();
+        for (recipient, account) in recipients.iter().zip(accounts) {
+            let mut warnings = self.check(recipient);
+            if let Some(account) = account {
+                if account.executable {
+                    if warnings.is_empty() {
+                        warnings.push(Warning::Program("a program".into()));
+                    }
+                } else if rpc.programs().is_token_program(&account.owner) {
+                    if Mint::unpack(&account.data).is_ok() {
+                        if *recipient != self.mint {
+                            warnings.push(Warning::Mint { same: false });
+                        }
+                    } else if let Ok(token) = TokenAccount::unpack(&account.data)
+                        && *recipient != self.source
+                    {
+                        warnings.push(Warning::TokenAccount { owner: token.owner });
+                    }
+                }
+            }
+            if !warnings.is_empty() {
+                flagged.push((*recipient, warnings));
+            }
+        }
+        Ok(flagged)
+    }
+}
+
+/// Recent transfer recipients, newest first.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    path: PathBuf,
+    addresses: VecDeque<Pubkey>,
+}
+
+impl History {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(Config::dir()?.join(HISTORY_FILE))
+    }
+
+    /// Reads the history at `path`; a missing file is an empty history.
+    pub fn load(path: &Path) -> Result<Self> {
+        let addresses = match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(History {
+            path: path.to_path_buf(),
+            addresses,
+        })
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = Pubkey> + '_ {
+        self.addresses.iter().copied()
+    }
+
+    /// Moves `address` to the front and saves.
+    pub fn record(&mut self, address: &Pubkey) -> Result<()> {
+        self.addresses.retain(|a| a != address);
+        self.addresses.push_front(*address);
+        self.addresses.truncate(HISTORY_LEN);
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.addresses)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,744 @@
+//! Launch scripts: small line-based programs run against one mint by
+//! `run-script`, for follow-up logic that would otherwise mean a chain of
+//! hand-typed commands. For example:
+//!
+//! ```text
+//! # top the supply up to a million, then lock everything
+//! if supply < 1000000 then mint 250000
+//! set uri "https://example.com/token.json"
+//! if mint-authority and supply >= 1000000 then revoke mint; revoke freeze
+//! print "supply is now" supply
+//! ```
+//!
+//! Each line is a statement, optionally guarded by `if CONDITION then`;
+//! several statements on one line are separated by `;`. Everything after
+//! `#` is a comment. Statements:
+//!
+//! - `mint AMOUNT [to WALLET]`: mint whole tokens, by default to the
+//!   signer's associated token account
+//! - `set name|symbol|uri "TEXT"`: change one metadata field
+//! - `immutable`: make the metadata immutable
+//! - `revoke mint|freeze`: permanently revoke an authority
+//! - `print ...`: print quoted text and values
+//! - `stop`: end the script; `fail "TEXT"`: end it with an error
+//!
+//! Conditions compare `supply`, `balance` (the signer's, in whole tokens)
+//! or `decimals` with `< <= > >= == !=`, or test `mint-authority`,
+//! `freeze-authority` (held by the signer), `metadata` (exists) and
+//! `mutable`; combine them with `and`, `or` and `not`. The mint is read
+//! again after every transaction, so later lines see earlier changes.
+
+use std::fmt;
+
+use crate::amount::{MAX_DECIMALS, TokenAmount};
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::metadata::{self, Metadata};
+use crate::pubkey::Pubkey;
+use crate::rehost;
+use crate::rpc::RpcClient;
+use crate::signer::Signer;
+use crate::token::{self, AuthorityType, MintAccount, TokenAccount};
+use crate::tx::Instruction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Supply,
+    Balance,
+    Decimals,
+}
+
+impl Quantity {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "supply" => Some(Quantity::Supply),
+            "balance" => Some(Quantity::Balance),
+            "decimals" => Some(Quantity::Decimals),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    MintAuthority,
+    FreezeAuthority,
+    Metadata,
+    Mutable,
+}
+
+impl Flag {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "mint-authority" => Some(Flag::MintAuthority),
+            "freeze-authority" => Some(Flag::FreezeAuthority),
+            "metadata" => Some(Flag::Metadata),
+            "mutable" => Some(Flag::Mutable),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "<" => Some(Comparison::Lt),
+            "<=" => Some(Comparison::Le),
+            ">" => Some(Comparison::Gt),
+            ">=" => Some(Comparison::Ge),
+            "==" => Some(Comparison::Eq),
+            "!=" => Some(Comparison::Ne),
+            _ => None,
+        }
+    }
+
+    fn holds<T: Ord>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// The number stays text until the mint's decimals are known.
+    Compare(Quantity, Comparison, String),
+    Flag(Flag),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataField {
+    Name,
+    Symbol,
+    Uri,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrintItem {
+    Text(String),
+    Value(Quantity),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Mint { amount: String, to: Option<Pubkey> },
+    Set { field: MetadataField, value: String },
+    Immutable,
+    Revoke(AuthorityType),
+    Print(Vec<PrintItem>),
+    Stop,
+    Fail(String),
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Mint { amount, to: None } => write!(f, "mint {amount}"),
+            Action::Mint {
+                amount,
+                to: Some(to),
+            } => write!(f, "mint {amount} to {to}"),
+            Action::Set { field, value } => {
+                let field = match field {
+                    MetadataField::Name => "name",
+                    MetadataField::Symbol => "symbol",
+                    MetadataField::Uri => "uri",
+                };
+                write!(f, "set {field} {value:?}")
+            }
+            Action::Immutable => write!(f, "immutable"),
+            Action::Revoke(kind) => write!(f, "revoke {}", kind.as_str()),
+            Action::Print(_) => write!(f, "print"),
+            Action::Stop => write!(f, "stop"),
+            Action::Fail(message) => write!(f, "fail {message:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    /// 1-based, for messages.
+    pub line: usize,
+    pub condition: Option<Condition>,
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Script {
+    pub statements: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Semicolon,
+}
+
+fn syntax(line: usize, message: impl fmt::Display) -> Error {
+    Error::InvalidInput(format!("script line {line}: {message}"))
+}
+
+fn tokenize(line: usize, source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '#' => break,
+            ';' => {
+                chars.next();
+                tokens.push(Token::Semicolon);
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => text.push(escaped),
+                            None => return Err(syntax(line, "unterminated string")),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err(syntax(line, "unterminated string")),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, ';' | '"' | '#') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    line: usize,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_word(&self) -> Option<&str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Word(w)) => Some(w),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn word(&mut self, what: &str) -> Result<String> {
+        match self.next() {
+            Some(Token::Word(w)) => Ok(w),
+            _ => Err(syntax(self.line, format!("expected {what}"))),
+        }
+    }
+
+    fn text(&mut self, what: &str) -> Result<String> {
+        match self.next() {
+            Some(Token::Text(t)) => Ok(t),
+            _ => Err(syntax(
+                self.line,
+                format!("expected {what} in double quotes"),
+            )),
+        }
+    }
+
+    fn eat(&mut self, keyword: &str) -> bool {
+        let found = self.peek_word() == Some(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn condition(&mut self) -> Result<Condition> {
+        let mut any = vec![self.all()?];
+        while self.eat("or") {
+            any.push(self.all()?);
+        }
+        Ok(if any.len() == 1 {
+            any.remove(0)
+        } else {
+            Condition::Or(any)
+        })
+    }
+
+    fn all(&mut self) -> Result<Condition> {
+        let mut all = vec![self.term()?];
+        while self.eat("and") {
+            all.push(self.term()?);
+        }
+        Ok(if all.len() == 1 {
+            all.remove(0)
+        } else {
+            Condition::And(all)
+        })
+    }
+
+    fn term(&mut self) -> Result<Condition> {
+        if self.eat("not") {
+            return Ok(Condition::Not(Box::new(self.term()?)));
+        }
+        let word = self.word("a condition")?;
+        if let Some(flag) = Flag::parse(&word) {
+            return Ok(Condition::Flag(flag));
+        }
+        let quantity = Quantity::parse(&word).ok_or_else(|| {
+            syntax(
+                self.line,
+                format!(
+                    "unknown value '{word}'; expected supply, balance, decimals, \
+                         mint-authority, freeze-authority, metadata or mutable"
+                ),
+            )
+        })?;
+        let op = self.word("a comparison")?;
+        let op = Comparison::parse(&op)
+            .ok_or_else(|| syntax(self.line, format!("unknown comparison '{op}'")))?;
+        let number = self.word("a number")?;
+        // Checked here so a typo fails before anything is sent.
+        TokenAmount::parse(&number, MAX_DECIMALS)
+            .map_err(|_| syntax(self.line, format!("'{number}' is not a number")))?;
+        Ok(Condition::Compare(quantity, op, number))
+    }
+
+    fn action(&mut self) -> Result<Action> {
+        let keyword = self.word("a statement")?;
+        let action = match keyword.as_str() {
+            "mint" => {
+                let amount = self.word("an amount")?;
+                TokenAmount::parse(&amount, MAX_DECIMALS)
+                    .map_err(|_| syntax(self.line, format!("'{amount}' is not an amount")))?;
+                let to = if self.eat("to") {
+                    let wallet = self.word("a wallet")?;
+                    Some(
+                        wallet
+                            .parse()
+                            .map_err(|e| syntax(self.line, format!("{wallet}: {e}")))?,
+                    )
+                } else {
+                    None
+                };
+                Action::Mint { amount, to }
+            }
+            "set" => {
+                let field = match self.word("name, symbol or uri")?.as_str() {
+                    "name" => MetadataField::Name,
+                    "symbol" => MetadataField::Symbol,
+                    "uri" => MetadataField::Uri,
+                    other => {
+                        return Err(syntax(
+                            self.line,
+                            format!("cannot set '{other}'; expected name, symbol or uri"),
+                        ));
+                    }
+                };
+                Action::Set {
+                    field,
+                    value: self.text("the new value")?,
+                }
+            }
+            "immutable" => Action::Immutable,
+            "revoke" => match self.word("mint or freeze")?.as_str() {
+                "mint" => Action::Revoke(AuthorityType::MintTokens),
+                "freeze" => Action::Revoke(AuthorityType::FreezeAccount),
+                other => {
+                    return Err(syntax(
+                        self.line,
+                        format!("cannot revoke '{other}'; expected mint or freeze"),
+                    ));
+                }
+            },
+            "print" => {
+                let mut items = Vec::new();
+                while !self.at_end() && self.tokens[self.pos] != Token::Semicolon {
+                    items.push(match self.next() {
+                        Some(Token::Text(t)) => PrintItem::Text(t),
+                        Some(Token::Word(w)) => PrintItem::Value(
+                            Quantity::parse(&w)
+                                .ok_or_else(|| syntax(self.line, format!("cannot print '{w}'")))?,
+                        ),
+                        _ => unreachable!("checked above"),
+                    });
+                }
+                Action::Print(items)
+            }
+            "stop" => Action::Stop,
+            "fail" => Action::Fail(self.text("a message")?),
+            other => return Err(syntax(self.line, format!("unknown statement '{other}'"))),
+        };
+        Ok(action)
+    }
+
+    fn statement(&mut self) -> Result<Statement> {
+        let condition = if self.eat("if") {
+            let condition = self.condition()?;
+            if !self.eat("then") {
+                return Err(syntax(self.line, "expected 'then' after the condition"));
+            }
+            Some(condition)
+        } else {
+            None
+        };
+        let mut actions = vec![self.action()?];
+        while !self.at_end() {
+            if self.next() != Some(Token::Semicolon) {
+                return Err(syntax(self.line, "unexpected text after the statement"));
+            }
+            if !self.at_end() {
+                actions.push(self.action()?);
+            }
+        }
+        Ok(Statement {
+            line: self.line,
+            condition,
+            actions,
+        })
+    }
+}
+
+impl Script {
+    /// Whether any line changes the token's metadata.
+    pub fn changes_metadata(&self) -> bool {
+        self.statements
+            .iter()
+            .flat_map(|s| &s.actions)
+            .any(|a| matches!(a, Action::Set { .. }))
+    }
+
+    /// Parses the whole script up front, so a mistake on the last line is
+    /// reported before the first one sends anything.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut statements = Vec::new();
+        for (i, text) in source.lines().enumerate() {
+            let tokens = tokenize(i + 1, text)?;
+            if tokens.is_empty() {
+                continue;
+            }
+            let mut parser = Parser {
+                line: i + 1,
+                tokens,
+                pos: 0,
+            };
+            statements.push(parser.statement()?);
+        }
+        Ok(Script { statements })
+    }
+}
+
+/// What conditions are evaluated against.
+struct State {
+    mint: MintAccount,
+    metadata: Option<Metadata>,
+    metadata_program: Pubkey,
+    balance: u64,
+}
+
+impl State {
+    fn load(rpc: &RpcClient, mint: &Pubkey, signer: &Pubkey) -> Result<Self> {
+        let account = token::fetch_mint(rpc, mint)?;
+        let ata = token::associated_token_address(signer, mint, &account.program_id);
+        let balance = match rpc.get_account(&ata)?.value {
+            Some(a) => TokenAccount::unpack(&a.data)?.amount,
+            None => 0,
+        };
+        Ok(State {
+            metadata: metadata::fetch_metadata(rpc, mint)?,
+            metadata_program: rpc.programs().metadata,
+            mint: account,
+            balance,
+        })
+    }
+
+    fn amount(&self, raw: u64) -> TokenAmount {
+        TokenAmount::from_raw(raw as u128, self.mint.mint.decimals)
+    }
+
+    fn value(&self, quantity: Quantity) -> String {
+        match quantity {
+            Quantity::Supply => self.amount(self.mint.mint.supply).to_string(),
+            Quantity::Balance => self.amount(self.balance).to_string(),
+            Quantity::Decimals => self.mint.mint.decimals.to_string(),
+        }
+    }
+
+    fn eval(&self, condition: &Condition, signer: &Pubkey, line: usize) -> Result<bool> {
+        Ok(match condition {
+            Condition::Compare(Quantity::Decimals, op, number) => {
+                let number: u8 = number
+                    .parse()
+                    .map_err(|_| syntax(line, format!("'{number}' is not a decimals count")))?;
+                op.holds(self.mint.mint.decimals, number)
+            }
+            Condition::Compare(quantity, op, number) => {
+                let raw = match quantity {
+                    Quantity::Supply => self.mint.mint.supply,
+                    _ => self.balance,
+                };
+                let number = TokenAmount::parse(number, self.mint.mint.decimals)
+                    .map_err(|e| syntax(line, e))?;
+                op.holds(self.amount(raw), number)
+            }
+            Condition::Flag(Flag::MintAuthority) => self.mint.mint.mint_authority == Some(*signer),
+            Condition::Flag(Flag::FreezeAuthority) => {
+                self.mint.mint.freeze_authority == Some(*signer)
+            }
+            Condition::Flag(Flag::Metadata) => self.metadata.is_some(),
+            Condition::Flag(Flag::Mutable) => self.metadata.as_ref().is_some_and(|m| m.is_mutable),
+            Condition::Not(inner) => !self.eval(inner, signer, line)?,
+            Condition::And(all) => {
+                for c in all {
+                    if !self.eval(c, signer, line)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            Condition::Or(any) => {
+                for c in any {
+                    if self.eval(c, signer, line)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+        })
+    }
+
+    fn instructions(
+        &self,
+        action: &Action,
+        mint: &Pubkey,
+        signer: &Pubkey,
+        line: usize,
+        max_supply: Option<TokenAmount>,
+    ) -> Result<Vec<Instruction>> {
+        let program_id = self.mint.program_id;
+        let decimals = self.mint.mint.decimals;
+        let holds = |what: &str, current: Option<Pubkey>| {
+            if current != Some(*signer) {
+                return Err(syntax(
+                    line,
+                    format!(
+                        "{what} authority of {mint} is {}, not {signer}",
+                        current.map_or("revoked".to_string(), |k| k.to_string())
+                    ),
+                ));
+            }
+            Ok(())
+        };
+        let metadata = || {
+            let current = self
+                .metadata
+                .as_ref()
+                .ok_or_else(|| syntax(line, format!("{mint} has no metadata account")))?;
+            rehost::ensure_update_authority(current, mint, signer)?;
+            Ok::<_, Error>(current)
+        };
+        Ok(match action {
+            Action::Mint { amount, to } => {
+                holds("mint", self.mint.mint.mint_authority)?;
+                let owner = to.unwrap_or(*signer);
+                let amount = TokenAmount::parse(amount, decimals).map_err(|e| syntax(line, e))?;
+                let destination = token::associated_token_address(&owner, mint, &program_id);
+                vec![
+                    token::create_associated_token_account_idempotent(
+                        signer,
+                        &owner,
+                        mint,
+                        &program_id,
+                    ),
+                    token::mint_to_checked(
+                        &program_id,
+                        mint,
+                        &destination,
+                        signer,
+                        self.mint.mint.mintable(amount, max_supply)?,
+                        decimals,
+                    ),
+                ]
+            }
+            Action::Set { field, value } => {
+                let mut data = metadata()?.data.clone();
+                match field {
+                    MetadataField::Name => data.name = value.clone(),
+                    MetadataField::Symbol => data.symbol = value.clone(),
+                    MetadataField::Uri => data.uri = value.clone(),
+                }
+                data.validate()?;
+                vec![metadata::update_metadata_accounts_v2(
+                    &self.metadata_program,
+                    mint,
+                    signer,
+                    Some(&data),
+                    None,
+                    None,
+                    None,
+                )]
+            }
+            Action::Immutable => {
+                metadata()?;
+                vec![metadata::update_metadata_accounts_v2(
+                    &self.metadata_program,
+                    mint,
+                    signer,
+                    None,
+                    None,
+                    None,
+                    Some(false),
+                )]
+            }
+            Action::Revoke(kind) => {
+                let current = match kind {
+                    AuthorityType::MintTokens => self.mint.mint.mint_authority,
+                    _ => self.mint.mint.freeze_authority,
+                };
+                holds(kind.as_str(), current)?;
+                vec![token::set_authority(&program_id, mint, signer, *kind, None)]
+            }
+            Action::Print(_) | Action::Stop | Action::Fail(_) => Vec::new(),
+        })
+    }
+}
+
+/// What one statement did.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// Its condition was false.
+    Skipped {
+        line: usize,
+    },
+    Printed {
+        line: usize,
+        text: String,
+    },
+    Sent {
+        line: usize,
+        action: String,
+        signature: Signature,
+    },
+    /// Dry run: what would have been sent.
+    Planned {
+        line: usize,
+        action: String,
+    },
+    Stopped {
+        line: usize,
+    },
+}
+
+/// Runs `script` against `mint`, signing with `signer`. With `dry_run`
+/// nothing is sent and every condition sees the mint as it was at the
+/// start. Stops at the first failing transaction; the outcomes so far are
+/// passed to `report` as they happen, so they are not lost with the error.
+/// A `mint` that would take the supply past `max_supply` (whole tokens)
+/// fails before it is sent.
+pub fn run(
+    rpc: &RpcClient,
+    script: &Script,
+    mint: &Pubkey,
+    signer: &dyn Signer,
+    dry_run: bool,
+    max_supply: Option<&str>,
+    mut report: impl FnMut(&Outcome),
+) -> Result<()> {
+    let key = signer.pubkey();
+    let mut state = State::load(rpc, mint, &key)?;
+    let max_supply = max_supply
+        .map(|cap| TokenAmount::parse(cap, state.mint.mint.decimals))
+        .transpose()?;
+    for statement in &script.statements {
+        let line = statement.line;
+        if let Some(condition) = &statement.condition
+            && !state.eval(condition, &key, line)?
+        {
+            report(&Outcome::Skipped { line });
+            continue;
+        }
+        for action in &statement.actions {
+            match action {
+                Action::Print(items) => {
+                    let text: Vec<String> = items
+                        .iter()
+                        .map(|item| match item {
+                            PrintItem::Text(t) => t.clone(),
+                            PrintItem::Value(q) => state.value(*q),
+                        })
+                        .collect();
+                    report(&Outcome::Printed {
+                        line,
+                        text: text.join(" "),
+                    });
+                }
+                Action::Stop => {
+                    report(&Outcome::Stopped { line });
+                    return Ok(());
+                }
+                Action::Fail(message) => return Err(syntax(line, message)),
+                _ => {
+                    let ixs = state.instructions(action, mint, &key, line, max_supply)?;
+                    if dry_run {
+                        report(&Outcome::Planned {
+                            line,
+                            action: action.to_string(),
+                        });
+                        continue;
+                    }
+                    let tx = rpc.build_signed(&ixs, &key, &[signer])?;
+                    let signature = if matches!(action, Action::Revoke(_) | Action::Immutable) {
+                        rpc.send_and_confirm_critical(&tx)?
+                    } else {
+                        rpc.send_and_confirm(&tx)?
+                    };
+                    report(&Outcome::Sent {
+                        line,
+                        action: action.to_string(),
+                        signature,
+                    });
+                    state = State::load(rpc, mint, &key)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,593 @@
+//! Launch sessions: the state of one token launch in progress, persisted per
+//! session ID so several launches can run side by side and any of them can be
+//! resumed after a failure.
+
+#[cfg(feature = "native")]
+use std::fs;
+use std::ops::Add;
+#[cfg(feature = "native")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "native")]
+use std::process;
+#[cfg(feature = "native")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::amount::TokenAmount;
+use crate::compute_budget;
+#[cfg(feature = "native")]
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::extension;
+use crate::keypair::Signature;
+use crate::launch::{LaunchRecord, Links, Listing};
+use crate::metadata::METADATA_PROGRAM_ID;
+use crate::network::Network;
+use crate::pubkey::Pubkey;
+use crate::rehearsal::Outcome;
+use crate::swap::TestBuy;
+use crate::token::{self, TOKEN_PROGRAM_ID};
+use crate::tx::Instruction;
+
+#[cfg(feature = "native")]
+const SESSIONS_DIR: &str = "sessions";
+#[cfg(feature = "native")]
+const ACTIVE_FILE: &str = "active";
+/// How long a finished launch blocks an identical one.
+pub const DUPLICATE_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// What to create. Fixed when the session starts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchParams {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub supply: TokenAmount,
+    pub token_program: Pubkey,
+    /// Keep a freeze authority on the mint. Most listings flag tokens that do.
+    #[serde(default)]
+    pub freeze_authority: bool,
+    #[serde(default)]
+    pub revoke_mint_authority: bool,
+    #[serde(default = "default_true")]
+    pub is_mutable: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub links: Links,
+    #[serde(default, skip_serializing_if = "Listing::is_empty")]
+    pub listing: Listing,
+    /// Token-2022 only: program called on every transfer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer_hook: Option<Pubkey>,
+    /// Token-2022 only: make the mint a group, or a member of one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<GroupRole>,
+    /// Recorded with the transaction that mints the supply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Where the supply is minted; the authority's associated token
+    /// account when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supply_account: Option<SupplyAccount>,
+    /// Compute units requested by each launch transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute_unit_limit: Option<u32>,
+    /// Priority fee of each launch transaction, in micro-lamports per unit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute_unit_price: Option<u64>,
+    /// Metadata program, when the cluster deploys it somewhere other than
+    /// the canonical address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_program: Option<Pubkey>,
+}
+
+/// A token account other than the authority's own to mint the supply into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SupplyAccount {
+    /// The associated token account of another wallet.
+    Associated { owner: Pubkey },
+    /// A plain token account at the address of a keypair the launch is
+    /// given, as some market makers require; it signs its own creation.
+    Auxiliary { address: Pubkey, owner: Pubkey },
+}
+
+/// The launched mint's place in a Token-2022 token group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum GroupRole {
+    /// A new group that can hold up to `max_size` members.
+    Group { max_size: u64 },
+    /// A member of an existing group; the launch authority must be the
+    /// group's update authority.
+    Member { group: Pubkey },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl LaunchParams {
+    pub fn decimals(&self) -> u8 {
+        self.supply.decimals()
+    }
+
+    pub fn metadata_program(&self) -> Pubkey {
+        self.metadata_program.unwrap_or(METADATA_PROGRAM_ID)
+    }
+
+    /// Data lengths of the extensions the mint is created with, and of those
+    /// Token-2022 adds by growing the account after `InitializeMint2`.
+    fn extension_lens(&self) -> Result<(Vec<usize>, Vec<usize>)> {
+        let mut fixed = Vec::new();
+        let mut grown = Vec::new();
+        if self.transfer_hook.is_some() {
+            fixed.push(extension::TRANSFER_HOOK_LEN);
+        }
+        match self.group {
+            Some(GroupRole::Group { .. }) => {
+                fixed.push(extension::GROUP_POINTER_LEN);
+                grown.push(extension::TOKEN_GROUP_LEN);
+            }
+            Some(GroupRole::Member { .. }) => {
+                fixed.push(extension::GROUP_MEMBER_POINTER_LEN);
+                grown.push(extension::TOKEN_GROUP_MEMBER_LEN);
+            }
+            None => {}
+        }
+        // A cluster may deploy Token-2022 elsewhere, so only the legacy
+        // program is known not to have extensions.
+        if !fixed.is_empty() && self.token_program == TOKEN_PROGRAM_ID {
+            return Err(Error::InvalidInput(
+                "mint extensions such as a transfer hook or token group need Token-2022".into(),
+            ));
+        }
+        Ok((fixed, grown))
+    }
+
+    /// Size the mint account is allocated with.
+    pub fn mint_space(&self) -> Result<usize> {
+        Ok(extension::mint_len(&self.extension_lens()?.0))
+    }
+
+    /// Size of the finished mint account, which its rent has to cover.
+    pub fn mint_len(&self) -> Result<usize> {
+        let (mut lens, grown) = self.extension_lens()?;
+        lens.extend(grown);
+        Ok(extension::mint_len(&lens))
+    }
+
+    /// Size of a token account of the launched mint.
+    pub fn token_account_len(&self) -> usize {
+        if self.transfer_hook.is_some() {
+            extension::account_len(&[extension::TRANSFER_HOOK_ACCOUNT_LEN])
+        } else {
+            extension::account_len(&[])
+        }
+    }
+
+    /// The account the supply is minted into.
+    pub fn supply_token_account(&self, authority: &Pubkey, mint: &Pubkey) -> Pubkey {
+        match self.supply_account {
+            None => token::associated_token_address(authority, mint, &self.token_program),
+            Some(SupplyAccount::Associated { owner }) => {
+                token::associated_token_address(&owner, mint, &self.token_program)
+            }
+            Some(SupplyAccount::Auxiliary { address, .. }) => address,
+        }
+    }
+
+    /// Compute budget instructions to put first in every launch transaction.
+    pub fn compute_budget_instructions(&self) -> Result<Vec<Instruction>> {
+        let mut ixs = Vec::new();
+        if let Some(units) = self.compute_unit_limit {
+            if units == 0 || units > compute_budget::MAX_COMPUTE_UNIT_LIMIT {
+                return Err(Error::InvalidInput(format!(
+                    "compute unit limit must be between 1 and {}",
+                    compute_budget::MAX_COMPUTE_UNIT_LIMIT
+                )));
+            }
+            ixs.push(compute_budget::set_compute_unit_limit(units));
+        }
+        if let Some(price) = self.compute_unit_price {
+            ixs.push(compute_budget::set_compute_unit_price(price));
+        }
+        Ok(ixs)
+    }
+
+    /// Every step a launch with these parameters runs, in order.
+    pub fn steps(&self) -> Vec<Step> {
+        let mut steps = vec![
+            Step::CreateMint,
+            Step::CreateTokenAccount,
+            Step::MintSupply,
+            Step::CreateMetadata,
+        ];
+        if self.revoke_mint_authority {
+            steps.push(Step::RevokeMintAuthority);
+        }
+        steps
+    }
+
+    /// Identifies a launch by what it creates and who creates it, so a re-run
+    /// after an ambiguous failure is recognised as the same launch. The URI is
+    /// left out: it is derived from the other fields when uploaded, and a
+    /// second upload of the same JSON may come back under another address.
+    pub fn idempotency_key(&self, network: Network, authority: &Pubkey) -> String {
+        let mut params = serde_json::to_value(self).unwrap_or_default();
+        if let Some(fields) = params.as_object_mut() {
+            fields.remove("uri");
+            // Fees don't change what is launched.
+            fields.remove("compute_unit_limit");
+            fields.remove("compute_unit_price");
+        }
+        // serde_json maps are sorted by key, so this is canonical.
+        let digest = Sha256::new()
+            .chain_update(network.to_string().as_bytes())
+            .chain_update(authority.as_bytes())
+            .chain_update(params.to_string().as_bytes())
+            .finalize();
+        digest[..16].iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Off-chain metadata JSON in the layout wallets and explorers read.
+    pub fn metadata_json(&self) -> Value {
+        let mut json = json!({ "name": self.name, "symbol": self.symbol });
+        if let Some(description) = &self.description {
+            json["description"] = json!(description);
+        }
+        if let Some(image) = &self.image {
+            json["image"] = json!(image);
+        }
+        self.links.write_json(&mut json);
+        self.listing.write_json(&mut json);
+        json
+    }
+}
+
+/// One on-chain step of the launch pipeline, in execution order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    CreateMint,
+    CreateTokenAccount,
+    MintSupply,
+    CreateMetadata,
+    RevokeMintAuthority,
+}
+
+impl Step {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Step::CreateMint => "create-mint",
+            Step::CreateTokenAccount => "create-token-account",
+            Step::MintSupply => "mint-supply",
+            Step::CreateMetadata => "create-metadata",
+            Step::RevokeMintAuthority => "revoke-mint-authority",
+        }
+    }
+}
+
+/// Lamports the payer spent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Spend {
+    pub fee: u64,
+    /// Everything else the payer's balance lost: rent for the accounts
+    /// created, and any fee a program charged.
+    pub rent: u64,
+}
+
+impl Spend {
+    pub fn total(&self) -> u64 {
+        self.fee.saturating_add(self.rent)
+    }
+}
+
+impl Add for Spend {
+    type Output = Spend;
+
+    fn add(self, other: Spend) -> Spend {
+        Spend {
+            fee: self.fee.saturating_add(other.fee),
+            rent: self.rent.saturating_add(other.rent),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub step: Step,
+    pub signature: Signature,
+    /// Unix seconds.
+    pub completed_at: i64,
+    /// Unknown when the transaction could not be read back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spend: Option<Spend>,
+}
+
+/// A transaction that was sent but whose outcome is not known yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingTransaction {
+    pub step: Step,
+    pub signature: Signature,
+    /// Unix seconds.
+    pub sent_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchSession {
+    pub id: String,
+    pub network: Network,
+    /// Unix seconds.
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Wallet paying for the launch and holding the mint and update authorities.
+    pub authority: Pubkey,
+    pub params: LaunchParams,
+    /// Set before the mint is created so an interrupted launch still knows its address.
+    pub mint: Option<Pubkey>,
+    pub token_account: Option<Pubkey>,
+    #[serde(default)]
+    pub completed: Vec<StepRecord>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// See [`LaunchParams::idempotency_key`]. Empty for sessions saved by
+    /// older versions; use [`LaunchSession::idempotency_key`].
+    #[serde(default)]
+    pub idempotency_key: String,
+    /// Set between sending a step's transaction and learning its outcome.
+    /// Still set after a failure means the transaction may have landed.
+    #[serde(default)]
+    pub pending: Option<PendingTransaction>,
+    /// What the finished launch left on-chain, read back after the last step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<Outcome>,
+    /// ID of the rehearsal session this launch replays.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rehearsal: Option<String>,
+    /// Proof that the token could be bought once it had a pool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_buy: Option<TestBuy>,
+    /// Vanity search result mixed into the derived mint key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint_salt: Option<u64>,
+    /// Index of a mint key derived from `MINT_PASSPHRASE` (see `phrase`);
+    /// the passphrase itself is never stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint_index: Option<u32>,
+}
+
+impl LaunchSession {
+    /// A session started at `now`, in Unix seconds. Here and in the other
+    /// methods the caller reads the clock, which `wasm32` builds can't.
+    pub fn new(
+        id: String,
+        network: Network,
+        authority: Pubkey,
+        params: LaunchParams,
+        now: i64,
+    ) -> Self {
+        LaunchSession {
+            id,
+            network,
+            created_at: now,
+            updated_at: now,
+            authority,
+            mint: None,
+            token_account: None,
+            completed: Vec::new(),
+            last_error: None,
+            pending: None,
+            outcome: None,
+            rehearsal: None,
+            test_buy: None,
+            mint_salt: None,
+            mint_index: None,
+            idempotency_key: params.idempotency_key(network, &authority),
+            params,
+        }
+    }
+
+    pub fn idempotency_key(&self) -> String {
+        if self.idempotency_key.is_empty() {
+            self.params.idempotency_key(self.network, &self.authority)
+        } else {
+            self.idempotency_key.clone()
+        }
+    }
+
+    /// Every step this launch runs, in order.
+    pub fn steps(&self) -> Vec<Step> {
+        self.params.steps()
+    }
+
+    pub fn is_done(&self, step: Step) -> bool {
+        self.completed.iter().any(|r| r.step == step)
+    }
+
+    pub fn next_step(&self) -> Option<Step> {
+        self.steps().into_iter().find(|s| !self.is_done(*s))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.next_step().is_none()
+    }
+
+    pub fn complete(&mut self, step: Step, signature: Signature, spend: Option<Spend>, now: i64) {
+        self.completed.push(StepRecord {
+            step,
+            signature,
+            completed_at: now,
+            spend,
+        });
+        self.updated_at = now;
+        self.last_error = None;
+        self.pending = None;
+    }
+
+    /// What the completed steps cost, and how many of them cost an unknown amount.
+    pub fn spend(&self) -> (Spend, usize) {
+        self.completed.iter().fold(
+            (Spend::default(), 0),
+            |(total, unknown), record| match record.spend {
+                Some(spend) => (total + spend, unknown),
+                None => (total, unknown + 1),
+            },
+        )
+    }
+
+    pub fn fail(&mut self, error: &Error, now: i64) {
+        self.updated_at = now;
+        self.last_error = Some(error.to_string());
+    }
+
+    /// The launch record for a finished session.
+    pub fn record(&self) -> Result<LaunchRecord> {
+        let mint = self
+            .mint
+            .filter(|_| self.is_complete())
+            .ok_or_else(|| Error::InvalidInput(format!("session {} is not complete", self.id)))?;
+        Ok(LaunchRecord {
+            mint,
+            network: self.network,
+            created_at: self.created_at,
+            program_id: self.params.token_program,
+            name: self.params.name.clone(),
+            symbol: self.params.symbol.clone(),
+            uri: self.params.uri.clone(),
+            decimals: self.params.decimals(),
+            supply: self.params.supply,
+            description: self.params.description.clone(),
+            image: self.params.image.clone(),
+            links: self.params.links.clone(),
+            spend: match self.spend() {
+                (spend, 0) => Some(spend),
+                _ => None,
+            },
+            metadata_history: Vec::new(),
+        })
+    }
+}
+
+/// Sessions stored as one JSON file per ID. Each session is only ever
+/// written by the process driving it, and writes replace the file atomically,
+/// so concurrent launches never see each other's partial state.
+#[cfg(feature = "native")]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "native")]
+impl SessionStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(SessionStore { dir })
+    }
+
+    pub fn open_default() -> Result<Self> {
+        Self::open(Config::dir()?.join(SESSIONS_DIR))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    /// A short ID not used by any stored session.
+    pub fn new_id(&self) -> String {
+        unique_id(&self.dir)
+    }
+
+    pub fn save(&self, session: &LaunchSession) -> Result<()> {
+        let path = self.path(&session.id);
+        let tmp = path.with_extension(format!("json.{}.tmp", process::id()));
+        fs::write(&tmp, serde_json::to_string_pretty(session)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn load(&self, id: &str) -> Result<LaunchSession> {
+        let path = self.path(id);
+        if !path.exists() {
+            return Err(Error::InvalidInput(format!("no launch session '{id}'")));
+        }
+        let raw = fs::read_to_string(&path)?;
+        serde_json::from_str(&raw).map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+    }
+
+    /// All stored sessions, most recently updated first.
+    pub fn list(&self) -> Result<Vec<LaunchSession>> {
+        let mut sessions: Vec<LaunchSession> = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                // Unreadable files are skipped so one bad session can't hide the rest.
+                let raw = fs::read_to_string(&path)?;
+                if let Ok(session) = serde_json::from_str(&raw) {
+                    sessions.push(session);
+                }
+            }
+        }
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(sessions)
+    }
+
+    /// Sessions for the same launch as `key` that are unfinished, or that
+    /// finished within [`DUPLICATE_WINDOW_SECS`] of `now`.
+    pub fn duplicates(&self, key: &str, now: i64) -> Result<Vec<LaunchSession>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|s| s.idempotency_key() == key)
+            .filter(|s| !s.is_complete() || now - s.updated_at < DUPLICATE_WINDOW_SECS)
+            .collect())
+    }
+
+    /// The ID the CLI uses when none is given.
+    pub fn active(&self) -> Result<Option<String>> {
+        let path = self.dir.join(ACTIVE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let id = fs::read_to_string(path)?.trim().to_string();
+        Ok((!id.is_empty()).then_some(id))
+    }
+
+    pub fn set_active(&self, id: &str) -> Result<()> {
+        self.load(id)?;
+        fs::write(self.dir.join(ACTIVE_FILE), id)?;
+        Ok(())
+    }
+}
+
+/// A short random-looking ID with no `<id>.json` file in `dir` yet.
+#[cfg(feature = "native")]
+pub(crate) fn unique_id(dir: &Path) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut counter = 0u32;
+    loop {
+        let digest = Sha256::new()
+            .chain_update(nanos.to_le_bytes())
+            .chain_update(process::id().to_le_bytes())
+            .chain_update(counter.to_le_bytes())
+            .finalize();
+        let id: String = digest[..4].iter().map(|b| format!("{b:02x}")).collect();
+        if !dir.join(format!("{id}.json")).exists() {
+            return id;
+        }
+        counter += 1;
+    }
+}
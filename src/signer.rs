@@ -0,0 +1,195 @@
+//! Anything that can sign a transaction message for a key.
+//!
+//! Transactions are signed through [`Signer`], so the key behind a command
+//! can be a local keypair, a Ledger, a Squads vault or a remote signing
+//! service. Which one is chosen with a specifier:
+//!
+//! ```text
+//! file:///path/to/keypair.json     a solana-keygen keypair file
+//! usb://ledger                     the first account of a Ledger's Solana app
+//! usb://ledger?key=1/0             m/44'/501'/1'/0'
+//! squads://<multisig>?vault=0      a Squads v4 vault (see SquadsVault)
+//! https://signer.example/key/ops   a remote signing service (see `remote`)
+//! kms://<key id or ARN>?region=..  an AWS KMS Ed25519 key (see `kms`)
+//! ```
+
+#[cfg(feature = "native")]
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::keypair::{Keypair, Signature};
+#[cfg(feature = "native")]
+use crate::kms::{AwsCredentials, KmsSigner};
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::remote::RemoteSigner;
+use crate::squads;
+
+pub trait Signer {
+    fn pubkey(&self) -> Pubkey;
+
+    /// Signs a serialized message. May ask a person or a service, and so
+    /// may fail or be refused.
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature>;
+}
+
+impl Signer for Keypair {
+    fn pubkey(&self) -> Pubkey {
+        Keypair::pubkey(self)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature> {
+        Ok(self.sign_message(message))
+    }
+}
+
+impl<S: Signer + ?Sized> Signer for &S {
+    fn pubkey(&self) -> Pubkey {
+        (**self).pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature> {
+        (**self).try_sign_message(message)
+    }
+}
+
+impl<S: Signer + ?Sized> Signer for Box<S> {
+    fn pubkey(&self) -> Pubkey {
+        (**self).pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature> {
+        (**self).try_sign_message(message)
+    }
+}
+
+/// A parsed signer specifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerSpec {
+    File(String),
+    /// `m/44'/501'/account'` with an optional `change'`.
+    Ledger {
+        account: u32,
+        change: Option<u32>,
+    },
+    Squads {
+        multisig: Pubkey,
+        vault_index: u8,
+    },
+    Remote(String),
+    Kms {
+        key_id: String,
+        region: Option<String>,
+    },
+}
+
+impl FromStr for SignerSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bad = |why: &str| Error::InvalidInput(format!("signer '{s}': {why}"));
+        let (scheme, rest) = s
+            .split_once("://")
+            .ok_or_else(|| bad("expected file://, usb://ledger, squads://, https:// or kms://"))?;
+        let (target, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let param = |name: &str| {
+            query
+                .split('&')
+                .filter_map(|kv| kv.split_once('='))
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v)
+        };
+        match scheme {
+            "file" if !rest.is_empty() => Ok(SignerSpec::File(rest.to_string())),
+            "usb" if target == "ledger" => {
+                let Some(key) = param("key") else {
+                    return Ok(SignerSpec::Ledger {
+                        account: 0,
+                        change: None,
+                    });
+                };
+                let index = |part: &str| {
+                    part.trim_end_matches('\'')
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|i| *i < 1 << 31)
+                        .ok_or_else(|| bad("key must be ACCOUNT or ACCOUNT/CHANGE"))
+                };
+                match key.split_once('/') {
+                    Some((account, change)) => Ok(SignerSpec::Ledger {
+                        account: index(account)?,
+                        change: Some(index(change)?),
+                    }),
+                    None => Ok(SignerSpec::Ledger {
+                        account: index(key)?,
+                        change: None,
+                    }),
+                }
+            }
+            "squads" => Ok(SignerSpec::Squads {
+                multisig: target
+                    .parse()
+                    .map_err(|_| bad("expected squads://<multisig address>"))?,
+                vault_index: param("vault")
+                    .map(|v| v.parse().map_err(|_| bad("vault must be 0-255")))
+                    .transpose()?
+                    .unwrap_or(0),
+            }),
+            "https" | "http" if !target.is_empty() => Ok(SignerSpec::Remote(s.to_string())),
+            "kms" if !target.is_empty() => Ok(SignerSpec::Kms {
+                key_id: target.to_string(),
+                region: param("region").map(str::to_string),
+            }),
+            _ => Err(bad("unknown signer")),
+        }
+    }
+}
+
+/// A Squads v4 vault. A vault is a program address and has no key to sign
+/// with: it signs by executing a vault transaction its members approved,
+/// which `squads propose` sets up. It stands in for the vault where only
+/// its address is needed, and refuses to sign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SquadsVault {
+    pub multisig: Pubkey,
+    pub vault_index: u8,
+}
+
+impl Signer for SquadsVault {
+    fn pubkey(&self) -> Pubkey {
+        squads::vault_address(&self.multisig, self.vault_index)
+    }
+
+    fn try_sign_message(&self, _message: &[u8]) -> Result<Signature> {
+        Err(Error::InvalidInput(format!(
+            "{} is a Squads vault and signs only through an approved vault transaction; \
+             use `squads propose` for this operation",
+            self.pubkey()
+        )))
+    }
+}
+
+/// Opens the signer a specifier names. `token` is sent to remote signers.
+#[cfg(feature = "native")]
+pub fn open(spec: &SignerSpec, token: Option<String>) -> Result<Box<dyn Signer>> {
+    Ok(match spec {
+        SignerSpec::File(path) => Box::new(Keypair::read_file(&PathBuf::from(path))?),
+        SignerSpec::Ledger { account, change } => {
+            Box::new(crate::ledger::Ledger::open(*account, *change)?)
+        }
+        SignerSpec::Squads {
+            multisig,
+            vault_index,
+        } => Box::new(SquadsVault {
+            multisig: *multisig,
+            vault_index: *vault_index,
+        }),
+        SignerSpec::Remote(url) => Box::new(RemoteSigner::connect(url, token)?),
+        SignerSpec::Kms { key_id, region } => Box::new(KmsSigner::connect(
+            key_id,
+            region.as_deref(),
+            AwsCredentials::from_env()?,
+        )?),
+    })
+}
@@ -0,0 +1,607 @@
+//! Squads v4 multisig: vault creation, vault-transaction proposals, votes and
+//! execution. Authority-requiring instructions are wrapped in a vault
+//! transaction signed by the vault PDA instead of being sent directly.
+
+use serde::Serialize;
+
+use crate::anchor::{account_discriminator, instruction_discriminator};
+use crate::error::{Error, Result};
+#[cfg(feature = "native")]
+use crate::keypair::{Keypair, Signature};
+use crate::layout::{Reader, Writer};
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::RpcClient;
+use crate::system::SYSTEM_PROGRAM_ID;
+use crate::tx::{AccountMeta, Hash, Instruction, Message};
+
+pub const SQUADS_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+
+const SEED_PREFIX: &[u8] = b"multisig";
+const SEED_PROGRAM_CONFIG: &[u8] = b"program_config";
+const SEED_MULTISIG: &[u8] = b"multisig";
+const SEED_VAULT: &[u8] = b"vault";
+const SEED_TRANSACTION: &[u8] = b"transaction";
+const SEED_PROPOSAL: &[u8] = b"proposal";
+
+/// Member permission bits.
+pub const PERMISSION_INITIATE: u8 = 1;
+pub const PERMISSION_VOTE: u8 = 2;
+pub const PERMISSION_EXECUTE: u8 = 4;
+pub const PERMISSION_ALL: u8 = PERMISSION_INITIATE | PERMISSION_VOTE | PERMISSION_EXECUTE;
+
+fn check_discriminator(r: &mut Reader, name: &str) -> Result<()> {
+    if r.take(8)? != account_discriminator(name) {
+        return Err(Error::InvalidAccountData(format!(
+            "not a Squads {name} account"
+        )));
+    }
+    Ok(())
+}
+
+pub fn program_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[SEED_PREFIX, SEED_PROGRAM_CONFIG], &SQUADS_PROGRAM_ID).0
+}
+
+pub fn multisig_address(create_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, SEED_MULTISIG, create_key.as_bytes()],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn vault_address(multisig: &Pubkey, vault_index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_bytes(), SEED_VAULT, &[vault_index]],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn transaction_address(multisig: &Pubkey, index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SEED_PREFIX,
+            multisig.as_bytes(),
+            SEED_TRANSACTION,
+            &index.to_le_bytes(),
+        ],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn proposal_address(multisig: &Pubkey, index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SEED_PREFIX,
+            multisig.as_bytes(),
+            SEED_TRANSACTION,
+            &index.to_le_bytes(),
+            SEED_PROPOSAL,
+        ],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Member {
+    pub key: Pubkey,
+    pub permissions: u8,
+}
+
+impl Member {
+    pub fn can(&self, permission: u8) -> bool {
+        self.permissions & permission == permission
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Multisig {
+    pub create_key: Pubkey,
+    pub config_authority: Pubkey,
+    pub threshold: u16,
+    /// Seconds between approval and when execution is allowed.
+    pub time_lock: u32,
+    pub transaction_index: u64,
+    pub stale_transaction_index: u64,
+    pub rent_collector: Option<Pubkey>,
+    pub members: Vec<Member>,
+}
+
+impl Multisig {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        check_discriminator(&mut r, "Multisig")?;
+        let create_key = r.pubkey()?;
+        let config_authority = r.pubkey()?;
+        let threshold = r.u16()?;
+        let time_lock = r.u32()?;
+        let transaction_index = r.u64()?;
+        let stale_transaction_index = r.u64()?;
+        let rent_collector = r.option(|r| r.pubkey())?;
+        let _bump = r.u8()?;
+        let members = r.vec(|r| {
+            Ok(Member {
+                key: r.pubkey()?,
+                permissions: r.u8()?,
+            })
+        })?;
+        Ok(Multisig {
+            create_key,
+            config_authority,
+            threshold,
+            time_lock,
+            transaction_index,
+            stale_transaction_index,
+            rent_collector,
+            members,
+        })
+    }
+
+    pub fn member(&self, key: &Pubkey) -> Option<&Member> {
+        self.members.iter().find(|m| m.key == *key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum ProposalStatus {
+    Draft { timestamp: i64 },
+    Active { timestamp: i64 },
+    Rejected { timestamp: i64 },
+    Approved { timestamp: i64 },
+    Executing,
+    Executed { timestamp: i64 },
+    Cancelled { timestamp: i64 },
+}
+
+impl ProposalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProposalStatus::Draft { .. } => "draft",
+            ProposalStatus::Active { .. } => "active",
+            ProposalStatus::Rejected { .. } => "rejected",
+            ProposalStatus::Approved { .. } => "approved",
+            ProposalStatus::Executing => "executing",
+            ProposalStatus::Executed { .. } => "executed",
+            ProposalStatus::Cancelled { .. } => "cancelled",
+        }
+    }
+
+    /// No further votes or execution can change the outcome.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            ProposalStatus::Rejected { .. }
+                | ProposalStatus::Executed { .. }
+                | ProposalStatus::Cancelled { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Proposal {
+    pub multisig: Pubkey,
+    pub transaction_index: u64,
+    pub status: ProposalStatus,
+    pub approved: Vec<Pubkey>,
+    pub rejected: Vec<Pubkey>,
+    pub cancelled: Vec<Pubkey>,
+}
+
+impl Proposal {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        check_discriminator(&mut r, "Proposal")?;
+        let multisig = r.pubkey()?;
+        let transaction_index = r.u64()?;
+        let status = match r.u8()? {
+            0 => ProposalStatus::Draft {
+                timestamp: r.i64()?,
+            },
+            1 => ProposalStatus::Active {
+                timestamp: r.i64()?,
+            },
+            2 => ProposalStatus::Rejected {
+                timestamp: r.i64()?,
+            },
+            3 => ProposalStatus::Approved {
+                timestamp: r.i64()?,
+            },
+            4 => ProposalStatus::Executing,
+            5 => ProposalStatus::Executed {
+                timestamp: r.i64()?,
+            },
+            6 => ProposalStatus::Cancelled {
+                timestamp: r.i64()?,
+            },
+            other => {
+                return Err(Error::InvalidAccountData(format!(
+                    "unknown proposal status {other}"
+                )));
+            }
+        };
+        let _bump = r.u8()?;
+        Ok(Proposal {
+            multisig,
+            transaction_index,
+            status,
+            approved: r.vec(|r| r.pubkey())?,
+            rejected: r.vec(|r| r.pubkey())?,
+            cancelled: r.vec(|r| r.pubkey())?,
+        })
+    }
+}
+
+/// The inner message of a vault transaction as stored on-chain.
+#[derive(Debug, Clone)]
+pub struct VaultTransaction {
+    pub multisig: Pubkey,
+    pub creator: Pubkey,
+    pub index: u64,
+    pub vault_index: u8,
+    pub num_signers: u8,
+    pub num_writable_signers: u8,
+    pub num_writable_non_signers: u8,
+    pub account_keys: Vec<Pubkey>,
+}
+
+impl VaultTransaction {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        check_discriminator(&mut r, "VaultTransaction")?;
+        let multisig = r.pubkey()?;
+        let creator = r.pubkey()?;
+        let index = r.u64()?;
+        let _bump = r.u8()?;
+        let vault_index = r.u8()?;
+        let _vault_bump = r.u8()?;
+        let _ephemeral_signer_bumps = r.vec(|r| r.u8())?;
+        Ok(VaultTransaction {
+            multisig,
+            creator,
+            index,
+            vault_index,
+            num_signers: r.u8()?,
+            num_writable_signers: r.u8()?,
+            num_writable_non_signers: r.u8()?,
+            account_keys: r.vec(|r| r.pubkey())?,
+        })
+    }
+
+    /// The accounts the inner message writes.
+    pub fn writable_keys(&self) -> Vec<Pubkey> {
+        (0..self.account_keys.len())
+            .filter(|&i| self.is_writable(i))
+            .map(|i| self.account_keys[i])
+            .collect()
+    }
+
+    fn is_writable(&self, index: usize) -> bool {
+        let signers = self.num_signers as usize;
+        if index < signers {
+            index < self.num_writable_signers as usize
+        } else {
+            index - signers < self.num_writable_non_signers as usize
+        }
+    }
+}
+
+/// Serializes instructions in Squads' compact `TransactionMessage` format,
+/// with the vault as fee payer and signer.
+pub fn transaction_message(vault: &Pubkey, instructions: &[Instruction]) -> Result<Vec<u8>> {
+    let message = Message::new(instructions, vault, Hash::default());
+    let header = message.header;
+    let num_keys = message.account_keys.len();
+    if num_keys > u8::MAX as usize {
+        return Err(Error::InvalidInput(
+            "vault transaction references too many accounts".into(),
+        ));
+    }
+    let mut w = Writer::new();
+    w.u8(header.num_required_signatures)
+        .u8(header.num_required_signatures - header.num_readonly_signed_accounts)
+        .u8((num_keys - header.num_required_signatures as usize) as u8
+            - header.num_readonly_unsigned_accounts)
+        .u8(num_keys as u8);
+    for key in &message.account_keys {
+        w.pubkey(key);
+    }
+    w.u8(message.instructions.len() as u8);
+    for ix in &message.instructions {
+        w.u8(ix.program_id_index)
+            .u8(ix.accounts.len() as u8)
+            .bytes(&ix.accounts)
+            .u16(ix.data.len() as u16)
+            .bytes(&ix.data);
+    }
+    // No address lookup tables.
+    w.u8(0);
+    Ok(w.into_inner())
+}
+
+pub fn multisig_create_v2(
+    creator: &Pubkey,
+    create_key: &Pubkey,
+    treasury: &Pubkey,
+    members: &[Member],
+    threshold: u16,
+    time_lock: u32,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.bytes(&instruction_discriminator("multisig_create_v2"))
+        .option(None::<&Pubkey>, |w, k| {
+            w.pubkey(k);
+        })
+        .u16(threshold)
+        .vec(members, |w, m| {
+            w.pubkey(&m.key).u8(m.permissions);
+        })
+        .u32(time_lock)
+        .option(None::<&Pubkey>, |w, k| {
+            w.pubkey(k);
+        })
+        .option(None::<&str>, |w, s| {
+            w.string(s);
+        });
+    Instruction {
+        program_id: SQUADS_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(program_config_address(), false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new(multisig_address(create_key), false),
+            AccountMeta::new_readonly(*create_key, true),
+            AccountMeta::new(*creator, true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+pub fn vault_transaction_create(
+    multisig: &Pubkey,
+    index: u64,
+    creator: &Pubkey,
+    vault_index: u8,
+    message: &[u8],
+    memo: Option<&str>,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.bytes(&instruction_discriminator("vault_transaction_create"))
+        .u8(vault_index)
+        .u8(0)
+        .vec(message, |w, b| {
+            w.u8(*b);
+        })
+        .option(memo, |w, s| {
+            w.string(s);
+        });
+    Instruction {
+        program_id: SQUADS_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(transaction_address(multisig, index), false),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(*creator, true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+pub fn proposal_create(multisig: &Pubkey, index: u64, creator: &Pubkey) -> Instruction {
+    let mut w = Writer::new();
+    w.bytes(&instruction_discriminator("proposal_create"))
+        .u64(index)
+        .bool(false);
+    Instruction {
+        program_id: SQUADS_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(proposal_address(multisig, index), false),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(*creator, true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+pub fn proposal_approve(multisig: &Pubkey, index: u64, member: &Pubkey) -> Instruction {
+    let mut w = Writer::new();
+    w.bytes(&instruction_discriminator("proposal_approve"))
+        .option(None::<&str>, |w, s| {
+            w.string(s);
+        });
+    Instruction {
+        program_id: SQUADS_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(*member, true),
+            AccountMeta::new(proposal_address(multisig, index), false),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// Executes an approved vault transaction. The inner message's accounts are
+/// passed as remaining accounts; the vault signs through the program.
+pub fn vault_transaction_execute(
+    multisig: &Pubkey,
+    transaction: &VaultTransaction,
+    member: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*multisig, false),
+        AccountMeta::new(proposal_address(multisig, transaction.index), false),
+        AccountMeta::new_readonly(transaction_address(multisig, transaction.index), false),
+        AccountMeta::new_readonly(*member, true),
+    ];
+    accounts.extend(
+        transaction
+            .account_keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| AccountMeta {
+                pubkey: *key,
+                is_signer: false,
+                is_writable: transaction.is_writable(i),
+            }),
+    );
+    Instruction {
+        program_id: SQUADS_PROGRAM_ID,
+        accounts,
+        data: instruction_discriminator("vault_transaction_execute").to_vec(),
+    }
+}
+
+#[cfg(feature = "native")]
+fn fetch<T>(rpc: &RpcClient, address: &Pubkey, unpack: fn(&[u8]) -> Result<T>) -> Result<T> {
+    let account = rpc
+        .get_account(address)?
+        .value
+        .ok_or(Error::AccountNotFound(*address))?;
+    if account.owner != SQUADS_PROGRAM_ID {
+        return Err(Error::InvalidInput(format!(
+            "{address} is not owned by the Squads v4 program"
+        )));
+    }
+    unpack(&account.data)
+}
+
+#[cfg(feature = "native")]
+pub fn fetch_multisig(rpc: &RpcClient, multisig: &Pubkey) -> Result<Multisig> {
+    fetch(rpc, multisig, Multisig::unpack)
+}
+
+#[cfg(feature = "native")]
+pub fn fetch_proposal(rpc: &RpcClient, multisig: &Pubkey, index: u64) -> Result<Proposal> {
+    fetch(rpc, &proposal_address(multisig, index), Proposal::unpack)
+}
+
+#[cfg(feature = "native")]
+pub fn fetch_vault_transaction(
+    rpc: &RpcClient,
+    multisig: &Pubkey,
+    index: u64,
+) -> Result<VaultTransaction> {
+    fetch(
+        rpc,
+        &transaction_address(multisig, index),
+        VaultTransaction::unpack,
+    )
+}
+
+/// Reads the protocol treasury that receives the multisig creation fee.
+#[cfg(feature = "native")]
+pub fn fetch_treasury(rpc: &RpcClient) -> Result<Pubkey> {
+    fetch(rpc, &program_config_address(), |data| {
+        let mut r = Reader::new(data);
+        check_discriminator(&mut r, "ProgramConfig")?;
+        let _authority = r.pubkey()?;
+        let _creation_fee = r.u64()?;
+        r.pubkey()
+    })
+}
+
+#[cfg(feature = "native")]
+fn send(rpc: &RpcClient, instructions: &[Instruction], signers: &[&Keypair]) -> Result<Signature> {
+    let tx = rpc.build_signed(instructions, &signers[0].pubkey(), signers)?;
+    rpc.send_and_confirm(&tx)
+}
+
+/// Creates a multisig. `create_key` is a throwaway key that seeds the address.
+#[cfg(feature = "native")]
+pub fn create_multisig(
+    rpc: &RpcClient,
+    creator: &Keypair,
+    create_key: &Keypair,
+    members: &[Member],
+    threshold: u16,
+    time_lock: u32,
+) -> Result<(Pubkey, Signature)> {
+    if threshold == 0 || threshold as usize > members.len() {
+        return Err(Error::InvalidInput(format!(
+            "threshold must be between 1 and the number of members ({})",
+            members.len()
+        )));
+    }
+    let ix = multisig_create_v2(
+        &creator.pubkey(),
+        &create_key.pubkey(),
+        &fetch_treasury(rpc)?,
+        members,
+        threshold,
+        time_lock,
+    );
+    let signature = send(rpc, &[ix], &[creator, create_key])?;
+    Ok((multisig_address(&create_key.pubkey()), signature))
+}
+
+/// Wraps `instructions` in a vault transaction and opens a proposal for it,
+/// approving it right away when the proposer may vote.
+#[cfg(feature = "native")]
+pub fn propose(
+    rpc: &RpcClient,
+    member: &Keypair,
+    multisig: &Pubkey,
+    vault_index: u8,
+    instructions: &[Instruction],
+    memo: Option<&str>,
+) -> Result<(u64, Signature)> {
+    let state = fetch_multisig(rpc, multisig)?;
+    let key = member.pubkey();
+    let permissions = state
+        .member(&key)
+        .ok_or_else(|| Error::InvalidInput(format!("{key} is not a member of {multisig}")))?;
+    if !permissions.can(PERMISSION_INITIATE) {
+        return Err(Error::InvalidInput(format!(
+            "{key} may not initiate transactions on {multisig}"
+        )));
+    }
+    let index = state.transaction_index + 1;
+    let message = transaction_message(&vault_address(multisig, vault_index), instructions)?;
+    let mut ixs = vec![
+        vault_transaction_create(multisig, index, &key, vault_index, &message, memo),
+        proposal_create(multisig, index, &key),
+    ];
+    if permissions.can(PERMISSION_VOTE) {
+        ixs.push(proposal_approve(multisig, index, &key));
+    }
+    let signature = send(rpc, &ixs, &[member])?;
+    Ok((index, signature))
+}
+
+#[cfg(feature = "native")]
+pub fn approve(
+    rpc: &RpcClient,
+    member: &Keypair,
+    multisig: &Pubkey,
+    index: u64,
+) -> Result<Signature> {
+    let ix = proposal_approve(multisig, index, &member.pubkey());
+    send(rpc, &[ix], &[member])
+}
+
+#[cfg(feature = "native")]
+pub fn execute(
+    rpc: &RpcClient,
+    member: &Keypair,
+    multisig: &Pubkey,
+    index: u64,
+) -> Result<Signature> {
+    let proposal = fetch_proposal(rpc, multisig, index)?;
+    if !matches!(proposal.status, ProposalStatus::Approved { .. }) {
+        return Err(Error::InvalidInput(format!(
+            "proposal {index} is {}, not approved",
+            proposal.status.as_str()
+        )));
+    }
+    let transaction = fetch_vault_transaction(rpc, multisig, index)?;
+    let ix = vault_transaction_execute(multisig, &transaction, &member.pubkey());
+    send(rpc, &[ix], &[member])
+}
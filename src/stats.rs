@@ -0,0 +1,130 @@
+//! Usage statistics kept only on this machine, for teams running many
+//! launches: how many finished or failed and on which networks, what the
+//! failures were, and how long each step takes. Nothing is collected
+//! unless `"stats": true` is set in the config file, and nothing is ever
+//! sent anywhere; `stats` shows the file and `stats --reset` clears it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::launch::now_unix;
+use crate::network::Network;
+use crate::session::Step;
+
+const STATS_FILE: &str = "stats.json";
+
+/// Runs of one step that succeeded, and the time they took together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepTimes {
+    pub count: u64,
+    pub total_ms: u64,
+}
+
+impl StepTimes {
+    pub fn average(&self) -> Duration {
+        Duration::from_millis(self.total_ms.checked_div(self.count).unwrap_or(0))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    /// When counting started, Unix seconds.
+    pub since: i64,
+    pub launches_completed: u64,
+    /// Launch runs that stopped at a failed step; a resumed launch that
+    /// then finishes counts in both.
+    pub launches_failed: u64,
+    /// Launch runs per network, however they ended.
+    pub networks: BTreeMap<String, u64>,
+    /// Failed runs by [`Error::category`].
+    pub failures: BTreeMap<String, u64>,
+    /// Per step, by [`Step::as_str`].
+    pub steps: BTreeMap<String, StepTimes>,
+}
+
+impl Stats {
+    pub fn launches(&self) -> u64 {
+        self.launches_completed + self.launches_failed
+    }
+
+    pub fn step_done(&mut self, step: Step, took: Duration) {
+        let times = self.steps.entry(step.as_str().to_string()).or_default();
+        times.count += 1;
+        times.total_ms = times
+            .total_ms
+            .saturating_add(took.as_millis().try_into().unwrap_or(u64::MAX));
+    }
+
+    pub fn launch_completed(&mut self, network: Network) {
+        self.launches_completed += 1;
+        *self.networks.entry(network.to_string()).or_default() += 1;
+    }
+
+    pub fn launch_failed(&mut self, network: Network, error: &Error) {
+        self.launches_failed += 1;
+        *self.networks.entry(network.to_string()).or_default() += 1;
+        *self
+            .failures
+            .entry(error.category().to_string())
+            .or_default() += 1;
+    }
+}
+
+/// The statistics file in the config directory.
+pub struct StatsFile {
+    path: PathBuf,
+}
+
+impl StatsFile {
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        StatsFile { path: path.into() }
+    }
+
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(Config::dir()?.join(STATS_FILE)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The statistics so far; empty ones when nothing was recorded yet.
+    pub fn load(&self) -> Result<Stats> {
+        if !self.path.exists() {
+            return Ok(Stats::default());
+        }
+        let raw = fs::read_to_string(&self.path)?;
+        serde_json::from_str(&raw)
+            .map_err(|e| Error::Config(format!("{}: {e}", self.path.display())))
+    }
+
+    /// Applies `change` to the statistics and saves them.
+    pub fn update(&self, change: impl FnOnce(&mut Stats)) -> Result<()> {
+        let mut stats = self.load()?;
+        if stats.since == 0 {
+            stats.since = now_unix();
+        }
+        change(&mut stats);
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&stats)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn reset(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Fetching off-chain metadata and uploading it to decentralized storage.
+
+use std::io::Read;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::cache::RpcCache;
+use crate::error::{Error, Result};
+
+pub const DEFAULT_IPFS_GATEWAY: &str = "https://gateway.pinata.cloud/ipfs/";
+const ARWEAVE_GATEWAY: &str = "https://arweave.net/";
+const PINATA_API: &str = "https://api.pinata.cloud/pinning";
+/// Upper bound on downloaded files; token logos and metadata are far smaller.
+const MAX_DOWNLOAD_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Somewhere off-chain metadata and images can be published. Implementations
+/// return a URI that wallets can fetch over HTTPS.
+pub trait Uploader {
+    fn upload_json(&self, name: &str, json: &Value) -> Result<String>;
+    fn upload_file(&self, name: &str, content_type: &str, bytes: &[u8]) -> Result<String>;
+}
+
+/// Rewrites `ipfs://` and `ar://` URIs to HTTPS gateway URLs.
+pub fn resolve_uri(uri: &str, ipfs_gateway: &str) -> String {
+    if let Some(path) = uri.strip_prefix("ipfs://") {
+        format!("{ipfs_gateway}{}", path.trim_start_matches("ipfs/"))
+    } else if let Some(id) = uri.strip_prefix("ar://") {
+        format!("{ARWEAVE_GATEWAY}{id}")
+    } else {
+        uri.to_string()
+    }
+}
+
+/// Downloads a file, returning its bytes and content type.
+pub fn fetch(uri: &str, ipfs_gateway: &str) -> Result<(Vec<u8>, String)> {
+    let url = resolve_uri(uri, ipfs_gateway);
+    let response = ureq::get(&url)
+        .call()
+        .map_err(|e| Error::Http(format!("GET {url}: {e}")))?;
+    let content_type = response.content_type().to_string();
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_DOWNLOAD_BYTES)
+        .read_to_end(&mut bytes)?;
+    Ok((bytes, content_type))
+}
+
+pub fn fetch_json(uri: &str, ipfs_gateway: &str) -> Result<Value> {
+    let (bytes, _) = fetch(uri, ipfs_gateway)?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| Error::InvalidInput(format!("{uri} does not contain valid JSON: {e}")))
+}
+
+/// Like [`fetch_json`], but reuses a copy fetched within `cache`'s TTL.
+pub fn fetch_json_cached(cache: Option<&RpcCache>, uri: &str, ipfs_gateway: &str) -> Result<Value> {
+    let Some(cache) = cache else {
+        return fetch_json(uri, ipfs_gateway);
+    };
+    let key = ["off-chain", uri];
+    if let Some(json) = cache.get_entry(&key) {
+        return Ok(json);
+    }
+    let json = fetch_json(uri, ipfs_gateway)?;
+    cache.put_entry(&key, &json);
+    Ok(json)
+}
+
+/// Pins content to IPFS through the Pinata API.
+pub struct PinataUploader {
+    api_key: String,
+    secret_api_key: String,
+    gateway: String,
+    agent: ureq::Agent,
+}
+
+#[derive(Deserialize)]
+struct PinResponse {
+    #[serde(rename = "IpfsHash")]
+    ipfs_hash: String,
+}
+
+impl PinataUploader {
+    pub fn new(api_key: String, secret_api_key: String, gateway: impl Into<String>) -> Self {
+        PinataUploader {
+            api_key,
+            secret_api_key,
+            gateway: gateway.into(),
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    fn request(&self, endpoint: &str) -> ureq::Request {
+        self.agent
+            .post(&format!("{PINATA_API}/{endpoint}"))
+            .set("pinata_api_key", &self.api_key)
+            .set("pinata_secret_api_key", &self.secret_api_key)
+    }
+
+    fn finish(&self, result: std::result::Result<ureq::Response, ureq::Error>) -> Result<String> {
+        let response = result.map_err(|e| Error::Http(format!("Pinata upload failed: {e}")))?;
+        let pinned: PinResponse = response
+            .into_json()
+            .map_err(|e| Error::Http(format!("unexpected Pinata response: {e}")))?;
+        Ok(format!("{}{}", self.gateway, pinned.ipfs_hash))
+    }
+}
+
+impl Uploader for PinataUploader {
+    fn upload_json(&self, name: &str, json: &Value) -> Result<String> {
+        let body = json!({ "pinataContent": json, "pinataMetadata": { "name": name } });
+        self.finish(self.request("pinJSONToIPFS").send_json(body))
+    }
+
+    fn upload_file(&self, name: &str, content_type: &str, bytes: &[u8]) -> Result<String> {
+        const BOUNDARY: &str = "----launch-solana-upload-boundary";
+        let mut body = Vec::with_capacity(bytes.len() + 512);
+        body.extend_from_slice(
+            format!(
+                "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{name}\"\r\nContent-Type: {content_type}\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(
+            format!(
+                "\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"pinataMetadata\"\r\n\r\n{}\r\n--{BOUNDARY}--\r\n",
+                json!({ "name": name })
+            )
+            .as_bytes(),
+        );
+        self.finish(
+            self.request("pinFileToIPFS")
+                .set(
+                    "Content-Type",
+                    &format!("multipart/form-data; boundary={BOUNDARY}"),
+                )
+                .send_bytes(&body),
+        )
+    }
+}
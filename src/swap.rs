@@ -0,0 +1,207 @@
+//! A small test buy of a freshly launched token through Jupiter, proving
+//! it can actually be bought end to end once a pool exists. The route is
+//! quoted with a slippage limit, the returned transaction is signed and
+//! sent, and the tokens received are checked against the quote's minimum.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "native")]
+use base64::Engine;
+#[cfg(feature = "native")]
+use base64::engine::general_purpose::STANDARD as BASE64;
+#[cfg(feature = "native")]
+use serde_json::{Value, json};
+
+#[cfg(feature = "native")]
+use crate::amount::TokenAmount;
+#[cfg(feature = "native")]
+use crate::error::{Error, Result};
+#[cfg(feature = "native")]
+use crate::keypair::Keypair;
+use crate::keypair::Signature;
+#[cfg(feature = "native")]
+use crate::launch::now_unix;
+#[cfg(feature = "native")]
+use crate::programs::WRAPPED_SOL_MINT;
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::RpcClient;
+#[cfg(feature = "native")]
+use crate::token::{self, TokenAccount};
+#[cfg(feature = "native")]
+use crate::tx;
+
+/// Jupiter's free swap API.
+pub const DEFAULT_SWAP_API: &str = "https://lite-api.jup.ag/swap/v1";
+/// Refuses slippage limits above this; a test buy has no reason to accept more.
+pub const MAX_SLIPPAGE_BPS: u16 = 5_000;
+
+/// Proof that the token was bought through a public route.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestBuy {
+    pub mint: Pubkey,
+    pub buyer: Pubkey,
+    pub signature: Signature,
+    pub lamports_in: u64,
+    /// Base units the quote promised, and the least the slippage limit accepted.
+    pub quoted_out: u64,
+    pub minimum_out: u64,
+    /// Base units that actually arrived.
+    pub received: u64,
+    pub slippage_bps: u16,
+    pub price_impact_pct: String,
+    /// AMMs the route went through, e.g. `Raydium CPMM`.
+    pub route: Vec<String>,
+    /// Unix seconds.
+    pub at: i64,
+}
+
+/// A route from Jupiter's `/quote`. The raw response is kept because
+/// `/swap` wants it back unchanged.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub raw: Value,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub minimum_out: u64,
+    pub price_impact_pct: String,
+    pub route: Vec<String>,
+}
+
+#[cfg(feature = "native")]
+fn http(context: &str, e: impl std::fmt::Display) -> Error {
+    Error::Http(format!("{context}: {e}"))
+}
+
+#[cfg(feature = "native")]
+fn number(raw: &Value, field: &str) -> Result<u64> {
+    raw[field]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| http("quote", format!("missing or bad `{field}`")))
+}
+
+#[cfg(feature = "native")]
+pub fn quote(api: &str, output_mint: &Pubkey, lamports: u64, slippage_bps: u16) -> Result<Quote> {
+    if slippage_bps > MAX_SLIPPAGE_BPS {
+        return Err(Error::InvalidInput(format!(
+            "slippage of {slippage_bps} bps is above the {MAX_SLIPPAGE_BPS} bps limit"
+        )));
+    }
+    let url = format!("{}/quote", api.trim_end_matches('/'));
+    let raw: Value = ureq::get(&url)
+        .query("inputMint", &WRAPPED_SOL_MINT.to_string())
+        .query("outputMint", &output_mint.to_string())
+        .query("amount", &lamports.to_string())
+        .query("slippageBps", &slippage_bps.to_string())
+        .call()
+        .map_err(|e| match e {
+            ureq::Error::Status(_, response) => http(
+                &format!("GET {url}"),
+                response.into_string().unwrap_or_default(),
+            ),
+            e => http(&format!("GET {url}"), e),
+        })?
+        .into_json()
+        .map_err(|e| http(&format!("GET {url}"), e))?;
+    if raw["outputMint"].as_str() != Some(&output_mint.to_string()) {
+        return Err(http("quote", "route ends in a different mint"));
+    }
+    let route = raw["routePlan"]
+        .as_array()
+        .map(|legs| {
+            legs.iter()
+                .filter_map(|leg| leg["swapInfo"]["label"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Quote {
+        in_amount: number(&raw, "inAmount")?,
+        out_amount: number(&raw, "outAmount")?,
+        minimum_out: number(&raw, "otherAmountThreshold")?,
+        price_impact_pct: raw["priceImpactPct"].as_str().unwrap_or("0").to_string(),
+        route,
+        raw,
+    })
+}
+
+/// The unsigned swap transaction for `quote`, paid and signed by `user`.
+#[cfg(feature = "native")]
+pub fn swap_transaction(api: &str, quote: &Quote, user: &Pubkey) -> Result<Vec<u8>> {
+    let url = format!("{}/swap", api.trim_end_matches('/'));
+    let response: Value = ureq::post(&url)
+        .send_json(json!({
+            "quoteResponse": quote.raw,
+            "userPublicKey": user.to_string(),
+            "wrapAndUnwrapSol": true,
+            "dynamicComputeUnitLimit": true,
+        }))
+        .map_err(|e| http(&format!("POST {url}"), e))?
+        .into_json()
+        .map_err(|e| http(&format!("POST {url}"), e))?;
+    let encoded = response["swapTransaction"]
+        .as_str()
+        .ok_or_else(|| http(&format!("POST {url}"), "no swapTransaction in the response"))?;
+    BASE64
+        .decode(encoded)
+        .map_err(|e| http(&format!("POST {url}"), format!("bad base64: {e}")))
+}
+
+#[cfg(feature = "native")]
+fn token_balance(
+    rpc: &RpcClient,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<u64> {
+    let ata = token::associated_token_address(owner, mint, program_id);
+    Ok(match rpc.get_account(&ata)?.value {
+        Some(account) => TokenAccount::unpack(&account.data)?.amount,
+        None => 0,
+    })
+}
+
+/// Buys `mint` for `lamports` through Jupiter and checks that at least the
+/// quote's slippage-limited minimum arrived.
+#[cfg(feature = "native")]
+pub fn test_buy(
+    rpc: &RpcClient,
+    api: &str,
+    buyer: &Keypair,
+    mint: &Pubkey,
+    lamports: u64,
+    slippage_bps: u16,
+) -> Result<TestBuy> {
+    let key = buyer.pubkey();
+    let program_id = token::fetch_mint(rpc, mint)?.program_id;
+    let before = token_balance(rpc, &key, mint, &program_id)?;
+    let quote = quote(api, mint, lamports, slippage_bps)?;
+    let wire = swap_transaction(api, &quote, &key)?;
+    let (signed, signature) = tx::sign_wire_transaction(&wire, buyer)?;
+    rpc.send_raw_transaction(&signed)?;
+    rpc.confirm_transaction(&signature)?;
+    let received = token_balance(rpc, &key, mint, &program_id)?.saturating_sub(before);
+    if received < quote.minimum_out {
+        let decimals = token::fetch_mint(rpc, mint)?.mint.decimals;
+        let show = |raw: u64| TokenAmount::from_raw(raw as u128, decimals);
+        return Err(Error::InvalidInput(format!(
+            "test buy {signature} received {}, less than the minimum {}",
+            show(received),
+            show(quote.minimum_out)
+        )));
+    }
+    Ok(TestBuy {
+        mint: *mint,
+        buyer: key,
+        signature,
+        lamports_in: quote.in_amount,
+        quoted_out: quote.out_amount,
+        minimum_out: quote.minimum_out,
+        received,
+        slippage_bps,
+        price_impact_pct: quote.price_impact_pct,
+        route: quote.route,
+        at: now_unix(),
+    })
+}
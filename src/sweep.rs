@@ -0,0 +1,155 @@
+//! Treasury sweeps: moving a token's whole balance out of several wallets
+//! into one treasury account. The wallets only sign; a separate payer
+//! covers fees and the treasury account's rent, so emptied launch wallets
+//! don't need SOL of their own.
+
+use serde::Serialize;
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+use crate::extension;
+use crate::keypair::{Keypair, Signature};
+use crate::memo;
+use crate::pack::Packer;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+use crate::token::{self, TokenAccount};
+use crate::tx::{Hash, Instruction, Message};
+
+/// One token account to empty into the treasury.
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepMove {
+    pub wallet: Pubkey,
+    pub account: Pubkey,
+    pub amount: TokenAmount,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepPlan {
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    pub treasury: Pubkey,
+    pub treasury_account: Pubkey,
+    pub moves: Vec<SweepMove>,
+}
+
+impl SweepPlan {
+    /// Finds every non-empty account of `mint` owned by `wallets`. The
+    /// treasury's own account is never a source.
+    pub fn build(
+        rpc: &RpcClient,
+        mint: &Pubkey,
+        wallets: &[Pubkey],
+        treasury: &Pubkey,
+    ) -> Result<Self> {
+        let info = token::fetch_mint(rpc, mint)?;
+        if extension::find(&info.extensions, extension::TRANSFER_HOOK).is_some() {
+            return Err(Error::InvalidInput(format!(
+                "{mint} has a transfer hook; sweeping needs the hook's extra accounts, \
+                 which are not resolved"
+            )));
+        }
+        let program_id = info.program_id;
+        let treasury_account = token::associated_token_address(treasury, mint, &program_id);
+        let mut moves = Vec::new();
+        for wallet in wallets {
+            for keyed in rpc.get_token_accounts_by_owner(wallet, &program_id)? {
+                let account = TokenAccount::unpack(&keyed.account.data)?;
+                if account.mint == *mint && account.amount > 0 && keyed.pubkey != treasury_account {
+                    moves.push(SweepMove {
+                        wallet: *wallet,
+                        account: keyed.pubkey,
+                        amount: TokenAmount::from_raw(account.amount as u128, info.mint.decimals),
+                    });
+                }
+            }
+        }
+        Ok(SweepPlan {
+            mint: *mint,
+            program_id,
+            decimals: info.mint.decimals,
+            treasury: *treasury,
+            treasury_account,
+            moves,
+        })
+    }
+
+    /// Keeps only the moves out of `wallets`, e.g. those the user confirmed.
+    pub fn retain_wallets(&mut self, wallets: &[Pubkey]) {
+        self.moves.retain(|m| wallets.contains(&m.wallet));
+    }
+
+    pub fn total(&self) -> TokenAmount {
+        let raw = self.moves.iter().map(|m| m.amount.raw()).sum();
+        TokenAmount::from_raw(raw, self.decimals)
+    }
+
+    fn transfer(&self, m: &SweepMove) -> Result<Instruction> {
+        Ok(token::transfer_checked(
+            &self.program_id,
+            &m.account,
+            &self.mint,
+            &self.treasury_account,
+            &m.wallet,
+            m.amount.to_u64()?,
+            self.decimals,
+        ))
+    }
+}
+
+/// A transaction of the sweep and the moves it carried.
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepBatch {
+    pub moves: Vec<SweepMove>,
+    pub signature: Signature,
+}
+
+/// Packs the moves into as few transactions as fit, the first also
+/// creating the treasury account, and sends them in order. `signers` must
+/// hold the keypair of every source wallet.
+pub fn execute(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    plan: &SweepPlan,
+    memo_text: Option<&str>,
+) -> Result<Vec<SweepBatch>> {
+    let payer_key = payer.pubkey();
+    let mut create = Some(token::create_associated_token_account_idempotent(
+        &payer_key,
+        &plan.treasury,
+        &plan.mint,
+        &plan.program_id,
+    ));
+    let mut items = Vec::new();
+    for m in &plan.moves {
+        let mut ixs: Vec<Instruction> = create.take().into_iter().collect();
+        ixs.push(plan.transfer(m)?);
+        items.push((m.clone(), ixs));
+    }
+    let prefix = memo_text
+        .map(|text| memo::memo(text, &[&payer_key]))
+        .into_iter()
+        .collect();
+    let batches = Packer::new(payer_key).with_prefix(prefix).pack(items)?;
+
+    let mut sent = Vec::new();
+    for (moves, ixs) in batches {
+        let needed = Message::new(&ixs, &payer_key, Hash::default())
+            .signer_keys()
+            .to_vec();
+        let mut keys: Vec<&Keypair> = vec![payer];
+        for key in &needed[1..] {
+            let signer = signers
+                .iter()
+                .find(|k| k.pubkey() == *key)
+                .ok_or_else(|| Error::InvalidInput(format!("no keypair for wallet {key}")))?;
+            keys.push(signer);
+        }
+        let tx = rpc.build_signed(&ixs, &payer_key, &keys)?;
+        let signature = rpc.send_and_confirm(&tx)?;
+        sent.push(SweepBatch { moves, signature });
+    }
+    Ok(sent)
+}
@@ -0,0 +1,169 @@
+//! System program instructions.
+
+use crate::error::{Error, Result};
+use crate::layout::{Reader, Writer};
+use crate::pubkey::Pubkey;
+use crate::tx::{AccountMeta, Hash, Instruction};
+
+pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey::new([0; 32]);
+
+/// Lamports per SOL.
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+/// Base fee per transaction signature, before any priority fee.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+pub const RECENT_BLOCKHASHES_SYSVAR_ID: Pubkey =
+    Pubkey::from_str_const("SysvarRecentB1ockHashes11111111111111111111");
+pub const RENT_SYSVAR_ID: Pubkey =
+    Pubkey::from_str_const("SysvarRent111111111111111111111111111111111");
+
+/// Rent-exempt minimum balance for an account of `data_len` bytes at the
+/// current mainnet rent rate, for when no RPC endpoint is available to ask.
+pub const fn rent_exempt_minimum(data_len: usize) -> u64 {
+    // (account header + data) * lamports per byte-year * two years
+    (128 + data_len as u64) * 3480 * 2
+}
+
+/// Size of a durable nonce account.
+pub const NONCE_ACCOUNT_LEN: usize = 80;
+
+const IX_CREATE_ACCOUNT: u32 = 0;
+const IX_TRANSFER: u32 = 2;
+const IX_ADVANCE_NONCE_ACCOUNT: u32 = 4;
+const IX_WITHDRAW_NONCE_ACCOUNT: u32 = 5;
+const IX_INITIALIZE_NONCE_ACCOUNT: u32 = 6;
+
+pub fn create_account(
+    payer: &Pubkey,
+    new_account: &Pubkey,
+    lamports: u64,
+    space: u64,
+    owner: &Pubkey,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u32(IX_CREATE_ACCOUNT)
+        .u64(lamports)
+        .u64(space)
+        .pubkey(owner);
+    Instruction {
+        program_id: SYSTEM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*new_account, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+pub fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let mut w = Writer::new();
+    w.u32(IX_TRANSFER).u64(lamports);
+    Instruction {
+        program_id: SYSTEM_PROGRAM_ID,
+        accounts: vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+        data: w.into_inner(),
+    }
+}
+
+/// Creates and initializes a durable nonce account controlled by `authority`.
+pub fn create_nonce_account(
+    payer: &Pubkey,
+    nonce: &Pubkey,
+    authority: &Pubkey,
+    lamports: u64,
+) -> [Instruction; 2] {
+    let mut w = Writer::new();
+    w.u32(IX_INITIALIZE_NONCE_ACCOUNT).pubkey(authority);
+    [
+        create_account(
+            payer,
+            nonce,
+            lamports,
+            NONCE_ACCOUNT_LEN as u64,
+            &SYSTEM_PROGRAM_ID,
+        ),
+        Instruction {
+            program_id: SYSTEM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*nonce, false),
+                AccountMeta::new_readonly(RECENT_BLOCKHASHES_SYSVAR_ID, false),
+                AccountMeta::new_readonly(RENT_SYSVAR_ID, false),
+            ],
+            data: w.into_inner(),
+        },
+    ]
+}
+
+/// Must be the first instruction of a transaction that uses the stored
+/// nonce in place of a recent blockhash.
+pub fn advance_nonce_account(nonce: &Pubkey, authority: &Pubkey) -> Instruction {
+    let mut w = Writer::new();
+    w.u32(IX_ADVANCE_NONCE_ACCOUNT);
+    Instruction {
+        program_id: SYSTEM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*nonce, false),
+            AccountMeta::new_readonly(RECENT_BLOCKHASHES_SYSVAR_ID, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// Whether `ix` advances a durable nonce.
+pub fn is_advance_nonce_account(ix: &Instruction) -> bool {
+    ix.program_id == SYSTEM_PROGRAM_ID
+        && ix.data.get(..4) == Some(&IX_ADVANCE_NONCE_ACCOUNT.to_le_bytes()[..])
+}
+
+/// Moves `lamports` out of a nonce account; withdrawing all of them
+/// closes it.
+pub fn withdraw_nonce_account(
+    nonce: &Pubkey,
+    authority: &Pubkey,
+    to: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u32(IX_WITHDRAW_NONCE_ACCOUNT).u64(lamports);
+    Instruction {
+        program_id: SYSTEM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*nonce, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(RECENT_BLOCKHASHES_SYSVAR_ID, false),
+            AccountMeta::new_readonly(RENT_SYSVAR_ID, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// The state of an initialized durable nonce account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceState {
+    pub authority: Pubkey,
+    /// Use as the transaction's recent blockhash.
+    pub nonce: Hash,
+}
+
+impl NonceState {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        let _version = r.u32()?;
+        if r.u32()? != 1 {
+            return Err(Error::InvalidAccountData(
+                "nonce account is not initialized".into(),
+            ));
+        }
+        Ok(NonceState {
+            authority: r.pubkey()?,
+            nonce: Hash::new(r.pubkey()?.to_bytes()),
+        })
+    }
+}
+
+/// Formats lamports as SOL without going through floats.
+pub fn format_sol(lamports: u64) -> String {
+    crate::amount::TokenAmount::from_raw(lamports as u128, 9).to_string()
+}
@@ -0,0 +1,513 @@
+//! SPL Token / Token-2022 account layouts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount::TokenAmount;
+use crate::error::{Error, Result};
+#[cfg(feature = "native")]
+use crate::extension;
+use crate::extension::Extension;
+use crate::layout::{Reader, Writer};
+use crate::pubkey::Pubkey;
+#[cfg(feature = "native")]
+use crate::rpc::{AccountFilter, DataSlice, RpcClient};
+use crate::system::SYSTEM_PROGRAM_ID;
+use crate::tx::{AccountMeta, Instruction};
+
+pub const TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PEnvDFhLUQnxWC");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+pub const MINT_LEN: usize = 82;
+pub const ACCOUNT_LEN: usize = 165;
+
+/// Offset of the Token-2022 account-type discriminator that follows the base layout.
+const ACCOUNT_TYPE_OFFSET: usize = ACCOUNT_LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+const IX_SET_AUTHORITY: u8 = 6;
+const IX_CLOSE_ACCOUNT: u8 = 9;
+const IX_FREEZE_ACCOUNT: u8 = 10;
+const IX_THAW_ACCOUNT: u8 = 11;
+const IX_TRANSFER_CHECKED: u8 = 12;
+const IX_MINT_TO_CHECKED: u8 = 14;
+const IX_BURN_CHECKED: u8 = 15;
+const IX_SYNC_NATIVE: u8 = 17;
+const IX_INITIALIZE_ACCOUNT3: u8 = 18;
+const IX_INITIALIZE_MINT2: u8 = 20;
+const IX_ATA_CREATE_IDEMPOTENT: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mint {
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+impl Mint {
+    pub fn supply_amount(&self) -> TokenAmount {
+        TokenAmount::from_raw(self.supply as u128, self.decimals)
+    }
+
+    /// `amount` in base units for a mint instruction, refused up front if
+    /// it would take the supply past what a u64 holds, which the token
+    /// program fails with a bare overflow error, or past `cap`.
+    pub fn mintable(&self, amount: TokenAmount, cap: Option<TokenAmount>) -> Result<u64> {
+        let current = self.supply_amount();
+        let limit = TokenAmount::from_raw(u64::MAX as u128, self.decimals);
+        let refuse = |bound: &str, max: TokenAmount| {
+            let room = max
+                .checked_sub(current)
+                .unwrap_or(TokenAmount::zero(self.decimals));
+            Error::InvalidInput(format!(
+                "minting {amount} would take the supply of {current} past {bound} of {max}; \
+                 at most {room} more can be minted"
+            ))
+        };
+        let after = current
+            .checked_add(amount)
+            .filter(|after| *after <= limit)
+            .ok_or_else(|| refuse("the token program's limit", limit))?;
+        if let Some(cap) = cap
+            && after > cap
+        {
+            return Err(refuse("the cap", cap));
+        }
+        amount.to_u64()
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        if data.len() < MINT_LEN {
+            return Err(Error::InvalidAccountData(format!(
+                "mint account is {} bytes, expected at least {MINT_LEN}",
+                data.len()
+            )));
+        }
+        if data.len() > MINT_LEN && data.get(ACCOUNT_TYPE_OFFSET) != Some(&ACCOUNT_TYPE_MINT) {
+            return Err(Error::InvalidAccountData("account is not a mint".into()));
+        }
+        let mut r = Reader::new(data);
+        Ok(Mint {
+            mint_authority: r.coption_pubkey()?,
+            supply: r.u64()?,
+            decimals: r.u8()?,
+            is_initialized: r.u8()? != 0,
+            freeze_authority: r.coption_pubkey()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountState {
+    Uninitialized,
+    Initialized,
+    Frozen,
+}
+
+impl AccountState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AccountState::Uninitialized => "uninitialized",
+            AccountState::Initialized => "initialized",
+            AccountState::Frozen => "frozen",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub delegate: Option<Pubkey>,
+    pub state: AccountState,
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Pubkey>,
+}
+
+impl TokenAccount {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        if data.len() < ACCOUNT_LEN {
+            return Err(Error::InvalidAccountData(format!(
+                "token account is {} bytes, expected at least {ACCOUNT_LEN}",
+                data.len()
+            )));
+        }
+        if data.len() > ACCOUNT_LEN && data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_ACCOUNT {
+            return Err(Error::InvalidAccountData(
+                "account is not a token account".into(),
+            ));
+        }
+        let mut r = Reader::new(data);
+        Ok(TokenAccount {
+            mint: r.pubkey()?,
+            owner: r.pubkey()?,
+            amount: r.u64()?,
+            delegate: r.coption_pubkey()?,
+            state: match r.u8()? {
+                0 => AccountState::Uninitialized,
+                1 => AccountState::Initialized,
+                2 => AccountState::Frozen,
+                other => {
+                    return Err(Error::InvalidAccountData(format!(
+                        "unknown account state {other}"
+                    )));
+                }
+            },
+            is_native: r.coption_u64()?,
+            delegated_amount: r.u64()?,
+            close_authority: r.coption_pubkey()?,
+        })
+    }
+}
+
+/// The SPL Token instructions that move or change supply. Token-2022 shares
+/// these discriminators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenInstruction {
+    Transfer { amount: u64 },
+    MintTo { amount: u64 },
+    Burn { amount: u64 },
+    TransferChecked { amount: u64, decimals: u8 },
+    MintToChecked { amount: u64, decimals: u8 },
+    BurnChecked { amount: u64, decimals: u8 },
+}
+
+impl TokenInstruction {
+    /// Decodes instruction data; returns `None` for instructions outside this subset.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let (&tag, rest) = data.split_first()?;
+        let amount = u64::from_le_bytes(rest.get(..8)?.try_into().ok()?);
+        let decimals = rest.get(8).copied();
+        Some(match tag {
+            3 => TokenInstruction::Transfer { amount },
+            7 => TokenInstruction::MintTo { amount },
+            8 => TokenInstruction::Burn { amount },
+            12 => TokenInstruction::TransferChecked {
+                amount,
+                decimals: decimals?,
+            },
+            14 => TokenInstruction::MintToChecked {
+                amount,
+                decimals: decimals?,
+            },
+            15 => TokenInstruction::BurnChecked {
+                amount,
+                decimals: decimals?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// A mint account together with its owning program and the slot it was read at.
+#[derive(Debug, Clone)]
+pub struct MintAccount {
+    pub program_id: Pubkey,
+    pub mint: Mint,
+    /// Token-2022 extensions; always empty for legacy mints.
+    pub extensions: Vec<Extension>,
+    pub slot: u64,
+}
+
+#[cfg(feature = "native")]
+pub fn fetch_mint(rpc: &RpcClient, address: &Pubkey) -> Result<MintAccount> {
+    let response = rpc.get_account(address)?;
+    let account = response.value.ok_or(Error::AccountNotFound(*address))?;
+    if !rpc.programs().is_token_program(&account.owner) {
+        return Err(Error::InvalidInput(format!(
+            "{address} is owned by {}, not a token program",
+            account.owner
+        )));
+    }
+    Ok(MintAccount {
+        program_id: account.owner,
+        mint: Mint::unpack(&account.data)?,
+        extensions: extension::parse(&account.data)?,
+        slot: response.context.slot,
+    })
+}
+
+/// Lists every token account of `mint`, returning the slot the scan was taken at.
+#[cfg(feature = "native")]
+pub fn fetch_token_accounts(
+    rpc: &RpcClient,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(u64, Vec<(Pubkey, TokenAccount)>)> {
+    let mut filters = vec![AccountFilter::Memcmp {
+        offset: 0,
+        bytes: mint.to_bytes().to_vec(),
+    }];
+    // Token-2022 accounts carry extensions, so only legacy accounts have a fixed size.
+    if *program_id == rpc.programs().token {
+        filters.push(AccountFilter::DataSize(ACCOUNT_LEN as u64));
+    }
+    // Token-2022 extensions are not needed here; the slice drops them.
+    let slice = DataSlice {
+        offset: 0,
+        length: ACCOUNT_LEN,
+    };
+    let response = rpc.get_program_accounts(program_id, &filters, Some(slice))?;
+    let accounts = response
+        .value
+        .into_iter()
+        .filter_map(|keyed| {
+            let token = TokenAccount::unpack(&keyed.account.data).ok()?;
+            (token.mint == *mint).then_some((keyed.pubkey, token))
+        })
+        .collect();
+    Ok((response.context.slot, accounts))
+}
+
+/// Address of the associated token account of `owner` for `mint`.
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_bytes(), program_id.as_bytes(), mint.as_bytes()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Creates the associated token account unless it already exists.
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint, program_id), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(*program_id, false),
+        ],
+        data: vec![IX_ATA_CREATE_IDEMPOTENT],
+    }
+}
+
+pub fn initialize_mint2(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    decimals: u8,
+    mint_authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_INITIALIZE_MINT2)
+        .u8(decimals)
+        .pubkey(mint_authority)
+        .option(freeze_authority, |w, k| {
+            w.pubkey(k);
+        });
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*mint, false)],
+        data: w.into_inner(),
+    }
+}
+
+/// Initializes a token account already allocated at `account`.
+pub fn initialize_account3(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_INITIALIZE_ACCOUNT3).pubkey(owner);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+pub fn transfer_checked(
+    program_id: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_TRANSFER_CHECKED).u64(amount).u8(decimals);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// Closes an empty token account, sending its rent to `destination`.
+pub fn close_account(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![IX_CLOSE_ACCOUNT],
+    }
+}
+
+/// Brings a wrapped SOL account's token balance up to the lamports sent to
+/// it.
+pub fn sync_native(program_id: &Pubkey, account: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*account, false)],
+        data: vec![IX_SYNC_NATIVE],
+    }
+}
+
+/// Freezes a token account of `mint`, signed by its freeze authority.
+pub fn freeze_account(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    freeze_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*freeze_authority, true),
+        ],
+        data: vec![IX_FREEZE_ACCOUNT],
+    }
+}
+
+/// Undoes [`freeze_account`].
+pub fn thaw_account(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    freeze_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*freeze_authority, true),
+        ],
+        data: vec![IX_THAW_ACCOUNT],
+    }
+}
+
+pub fn mint_to_checked(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_MINT_TO_CHECKED).u64(amount).u8(decimals);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+/// Burns `amount` from a token account, signed by its owner.
+pub fn burn_checked(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_BURN_CHECKED).u64(amount).u8(decimals);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: w.into_inner(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorityType {
+    MintTokens = 0,
+    FreezeAccount = 1,
+    AccountOwner = 2,
+    CloseAccount = 3,
+}
+
+impl AuthorityType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuthorityType::MintTokens => "mint",
+            AuthorityType::FreezeAccount => "freeze",
+            AuthorityType::AccountOwner => "owner",
+            AuthorityType::CloseAccount => "close",
+        }
+    }
+}
+
+/// Changes or, with `new_authority` of `None`, permanently removes an authority.
+pub fn set_authority(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    current_authority: &Pubkey,
+    authority_type: AuthorityType,
+    new_authority: Option<&Pubkey>,
+) -> Instruction {
+    let mut w = Writer::new();
+    w.u8(IX_SET_AUTHORITY)
+        .u8(authority_type as u8)
+        .option(new_authority, |w, k| {
+            w.pubkey(k);
+        });
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*current_authority, true),
+        ],
+        data: w.into_inner(),
+    }
+}
@@ -0,0 +1,156 @@
+//! Transcripts of interactive runs: the command line, every question asked
+//! and its answer, and how the run ended.
+//!
+//! `--transcript PATH` writes one as the run goes, so a run that crashes
+//! still leaves the questions it got through. `transcript replay` runs the
+//! recorded command again and answers its questions from the transcript;
+//! edit the answers in the file first to take a different path, or pass
+//! `--edit` to be offered each recorded answer in turn. Replays stop at a
+//! question the transcript doesn't have in that place, rather than guess.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::launch::now_unix;
+
+/// The flag that records a transcript; left out of the recorded arguments.
+pub const TRANSCRIPT_FLAG: &str = "--transcript";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prompt {
+    pub question: String,
+    pub answer: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finish {
+    pub at: i64,
+    /// The error the run ended with; `None` for success.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Transcript {
+    /// Command-line arguments after the program name.
+    pub args: Vec<String>,
+    pub started_at: i64,
+    #[serde(default)]
+    pub prompts: Vec<Prompt>,
+    #[serde(default)]
+    pub finish: Option<Finish>,
+}
+
+impl Transcript {
+    /// A transcript of a run with `args`, `--transcript` and its value
+    /// taken out.
+    pub fn new(args: impl IntoIterator<Item = String>) -> Self {
+        let mut kept = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == TRANSCRIPT_FLAG {
+                args.next();
+            } else if !arg.starts_with(&format!("{TRANSCRIPT_FLAG}=")) {
+                kept.push(arg);
+            }
+        }
+        Transcript {
+            args: kept,
+            started_at: now_unix(),
+            prompts: Vec::new(),
+            finish: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| Error::InvalidInput(format!("{}: {e}", path.display())))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Answers the questions of a run, from the terminal or a transcript, and
+/// records them.
+#[derive(Debug, Default)]
+pub struct Prompter {
+    recording: Option<(PathBuf, Transcript)>,
+    recorded: VecDeque<Prompt>,
+    replaying: bool,
+    edit: bool,
+}
+
+impl Prompter {
+    /// Writes `transcript` to `path` now and after every answer.
+    pub fn record(mut self, path: PathBuf, transcript: Transcript) -> Result<Self> {
+        transcript.save(&path)?;
+        self.recording = Some((path, transcript));
+        Ok(self)
+    }
+
+    /// Answers from `transcript`, in order; with `edit`, each recorded
+    /// answer is offered as the default instead.
+    pub fn replay(mut self, transcript: &Transcript, edit: bool) -> Self {
+        self.recorded = transcript.prompts.iter().cloned().collect();
+        self.replaying = true;
+        self.edit = edit;
+        self
+    }
+
+    /// The answer to `question`. `ask` reads one from the user, given the
+    /// recorded answer as a default when editing a replay.
+    pub fn answer(
+        &mut self,
+        question: &str,
+        ask: impl FnOnce(&str, Option<&str>) -> Result<String>,
+    ) -> Result<String> {
+        let answer = if self.replaying {
+            let Some(prompt) = self.recorded.pop_front() else {
+                return Err(Error::InvalidInput(format!(
+                    "the transcript has no answer to \"{question}\""
+                )));
+            };
+            if prompt.question != question {
+                return Err(Error::InvalidInput(format!(
+                    "the transcript answers \"{}\" here, but the run asks \"{question}\"",
+                    prompt.question
+                )));
+            }
+            if self.edit {
+                ask(question, Some(&prompt.answer))?
+            } else {
+                eprintln!("{question} {} (from the transcript)", prompt.answer);
+                prompt.answer
+            }
+        } else {
+            ask(question, None)?
+        };
+        if let Some((path, transcript)) = &mut self.recording {
+            transcript.prompts.push(Prompt {
+                question: question.to_string(),
+                answer: answer.clone(),
+            });
+            transcript.save(path)?;
+        }
+        Ok(answer)
+    }
+
+    /// Records how the run ended.
+    pub fn finish(&mut self, error: Option<String>) -> Result<()> {
+        if let Some((path, transcript)) = &mut self.recording {
+            transcript.finish = Some(Finish {
+                at: now_unix(),
+                error,
+            });
+            transcript.save(path)?;
+        }
+        Ok(())
+    }
+}
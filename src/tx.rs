@@ -0,0 +1,646 @@
+//! Legacy and version 0 transaction construction and wire serialization,
+//! and signing serialized transactions built elsewhere.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::pubkey::Pubkey;
+use crate::signer::Signer;
+
+/// Largest serialized transaction the network accepts.
+pub const PACKET_DATA_SIZE: usize = 1232;
+
+/// A recent blockhash.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Hash([u8; 32]);
+
+impl Hash {
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        Hash(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl FromStr for Hash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let key: Pubkey = s.parse()?;
+        Ok(Hash(key.to_bytes()))
+    }
+}
+
+impl fmt::Display for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&bs58::encode(self.0).into_string())
+    }
+}
+
+impl fmt::Debug for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hash({self})")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AccountMeta {
+    pub fn new(pubkey: Pubkey, is_signer: bool) -> Self {
+        AccountMeta {
+            pubkey,
+            is_signer,
+            is_writable: true,
+        }
+    }
+
+    pub fn new_readonly(pubkey: Pubkey, is_signer: bool) -> Self {
+        AccountMeta {
+            pubkey,
+            is_signer,
+            is_writable: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageHeader {
+    pub num_required_signatures: u8,
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub header: MessageHeader,
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Hash,
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+/// Every key the instructions use as (key, is_signer, is_writable), merged
+/// so each carries its strongest role, in message order: writable signers
+/// with `payer` first, read-only signers, writable and read-only others.
+fn ordered_metas(instructions: &[Instruction], payer: &Pubkey) -> Vec<(Pubkey, bool, bool)> {
+    let mut metas: Vec<(Pubkey, bool, bool)> = vec![(*payer, true, true)];
+    let mut add = |key: Pubkey, signer: bool, writable: bool| match metas
+        .iter_mut()
+        .find(|(k, ..)| *k == key)
+    {
+        Some(meta) => {
+            meta.1 |= signer;
+            meta.2 |= writable;
+        }
+        None => metas.push((key, signer, writable)),
+    };
+    for ix in instructions {
+        for meta in &ix.accounts {
+            add(meta.pubkey, meta.is_signer, meta.is_writable);
+        }
+    }
+    for ix in instructions {
+        add(ix.program_id, false, false);
+    }
+
+    // Stable sort keeps the payer first among writable signers.
+    metas.sort_by_key(|&(_, signer, writable)| match (signer, writable) {
+        (true, true) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (false, false) => 3,
+    });
+    metas
+}
+
+fn header_of(metas: &[(Pubkey, bool, bool)]) -> MessageHeader {
+    MessageHeader {
+        num_required_signatures: metas.iter().filter(|m| m.1).count() as u8,
+        num_readonly_signed_accounts: metas.iter().filter(|m| m.1 && !m.2).count() as u8,
+        num_readonly_unsigned_accounts: metas.iter().filter(|m| !m.1 && !m.2).count() as u8,
+    }
+}
+
+impl Message {
+    /// Compiles instructions into a message with `payer` as the first signer.
+    pub fn new(instructions: &[Instruction], payer: &Pubkey, recent_blockhash: Hash) -> Self {
+        let metas = ordered_metas(instructions, payer);
+        let header = header_of(&metas);
+        let account_keys: Vec<Pubkey> = metas.into_iter().map(|(k, ..)| k).collect();
+        let index_of = |key: &Pubkey| account_keys.iter().position(|k| k == key).unwrap() as u8;
+        let instructions = instructions
+            .iter()
+            .map(|ix| CompiledInstruction {
+                program_id_index: index_of(&ix.program_id),
+                accounts: ix.accounts.iter().map(|m| index_of(&m.pubkey)).collect(),
+                data: ix.data.clone(),
+            })
+            .collect();
+
+        Message {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+        }
+    }
+
+    pub fn signer_keys(&self) -> &[Pubkey] {
+        &self.account_keys[..self.header.num_required_signatures as usize]
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = vec![
+            self.header.num_required_signatures,
+            self.header.num_readonly_signed_accounts,
+            self.header.num_readonly_unsigned_accounts,
+        ];
+        write_compact_u16(&mut out, self.account_keys.len());
+        for key in &self.account_keys {
+            out.extend_from_slice(key.as_bytes());
+        }
+        out.extend_from_slice(self.recent_blockhash.as_bytes());
+        write_compact_u16(&mut out, self.instructions.len());
+        for ix in &self.instructions {
+            out.push(ix.program_id_index);
+            write_compact_u16(&mut out, ix.accounts.len());
+            out.extend_from_slice(&ix.accounts);
+            write_compact_u16(&mut out, ix.data.len());
+            out.extend_from_slice(&ix.data);
+        }
+        out
+    }
+
+    /// Parses a legacy message as produced by [`Message::serialize`].
+    pub fn deserialize(data: &[u8]) -> Result<Self> {
+        fn take<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8]> {
+            let bytes = data
+                .get(*pos..*pos + len)
+                .ok_or_else(|| Error::InvalidInput("message is truncated".into()))?;
+            *pos += len;
+            Ok(bytes)
+        }
+        let mut pos = 0;
+        let header = take(data, &mut pos, 3)?;
+        if header[0] & 0x80 != 0 {
+            return Err(Error::InvalidInput(
+                "versioned messages are not supported here".into(),
+            ));
+        }
+        let header = MessageHeader {
+            num_required_signatures: header[0],
+            num_readonly_signed_accounts: header[1],
+            num_readonly_unsigned_accounts: header[2],
+        };
+        let key_count = read_compact_u16(data, &mut pos)?;
+        let mut account_keys = Vec::with_capacity(key_count);
+        for _ in 0..key_count {
+            account_keys.push(Pubkey::new(take(data, &mut pos, 32)?.try_into().unwrap()));
+        }
+        let recent_blockhash = Hash::new(take(data, &mut pos, 32)?.try_into().unwrap());
+        let count = read_compact_u16(data, &mut pos)?;
+        let mut instructions = Vec::with_capacity(count);
+        for _ in 0..count {
+            let program_id_index = take(data, &mut pos, 1)?[0];
+            let len = read_compact_u16(data, &mut pos)?;
+            let accounts = take(data, &mut pos, len)?.to_vec();
+            let len = read_compact_u16(data, &mut pos)?;
+            let ix_data = take(data, &mut pos, len)?.to_vec();
+            let in_range = std::iter::once(&program_id_index)
+                .chain(&accounts)
+                .all(|&i| (i as usize) < key_count);
+            if !in_range {
+                return Err(Error::InvalidInput(
+                    "message refers to an account it does not list".into(),
+                ));
+            }
+            instructions.push(CompiledInstruction {
+                program_id_index,
+                accounts,
+                data: ix_data,
+            });
+        }
+        if pos != data.len() {
+            return Err(Error::InvalidInput(
+                "unexpected bytes after the message".into(),
+            ));
+        }
+        if header.num_required_signatures as usize > key_count {
+            return Err(Error::InvalidInput(
+                "message has more signers than accounts".into(),
+            ));
+        }
+        Ok(Message {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub signatures: Vec<Signature>,
+    pub message: Message,
+}
+
+impl Transaction {
+    pub fn new_unsigned(message: Message) -> Self {
+        let signatures =
+            vec![Signature::default(); message.header.num_required_signatures as usize];
+        Transaction {
+            signatures,
+            message,
+        }
+    }
+
+    /// Signs the message with every signer whose key is a required signer.
+    pub fn sign<S: Signer + ?Sized>(&mut self, signers: &[&S]) -> Result<()> {
+        let message = self.message.serialize();
+        for signer in signers {
+            let key = signer.pubkey();
+            let position = self
+                .message
+                .signer_keys()
+                .iter()
+                .position(|k| *k == key)
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!("{key} is not a signer of this transaction"))
+                })?;
+            self.signatures[position] = signer.try_sign_message(&message)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signatures.iter().all(|s| *s != Signature::default())
+    }
+
+    /// The first signature, which identifies the transaction on-chain.
+    pub fn signature(&self) -> Signature {
+        self.signatures.first().copied().unwrap_or_default()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_compact_u16(&mut out, self.signatures.len());
+        for signature in &self.signatures {
+            out.extend_from_slice(signature.as_bytes());
+        }
+        out.extend_from_slice(&self.message.serialize());
+        out
+    }
+}
+
+/// An address lookup table's contents: keys a version 0 message can name
+/// by a one-byte index into the table instead of listing in full.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTable {
+    pub key: Pubkey,
+    pub addresses: Vec<Pubkey>,
+}
+
+/// The keys a version 0 message loads from one lookup table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageAddressTableLookup {
+    pub account_key: Pubkey,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// A version 0 message: a legacy message whose non-signer accounts may be
+/// loaded from lookup tables. Indexes past the static keys refer to the
+/// loaded keys, every table's writable keys first, then its read-only ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageV0 {
+    pub header: MessageHeader,
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Hash,
+    pub instructions: Vec<CompiledInstruction>,
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+impl MessageV0 {
+    /// Compiles instructions like [`Message::new`], loading every account
+    /// found in `tables` from there, except signers and invoked programs,
+    /// which the runtime requires to be listed in the message itself.
+    pub fn new(
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        recent_blockhash: Hash,
+        tables: &[LookupTable],
+    ) -> Self {
+        let programs: HashSet<Pubkey> = instructions.iter().map(|ix| ix.program_id).collect();
+        let mut static_metas = Vec::new();
+        let mut lookups: Vec<MessageAddressTableLookup> = tables
+            .iter()
+            .map(|table| MessageAddressTableLookup {
+                account_key: table.key,
+                writable_indexes: Vec::new(),
+                readonly_indexes: Vec::new(),
+            })
+            .collect();
+        for meta in ordered_metas(instructions, payer) {
+            let (key, signer, writable) = meta;
+            let found = (!signer && !programs.contains(&key))
+                .then(|| {
+                    tables.iter().enumerate().find_map(|(t, table)| {
+                        let index = table.addresses.iter().position(|a| *a == key)?;
+                        Some((t, u8::try_from(index).ok()?))
+                    })
+                })
+                .flatten();
+            match found {
+                Some((t, index)) if writable => lookups[t].writable_indexes.push(index),
+                Some((t, index)) => lookups[t].readonly_indexes.push(index),
+                None => static_metas.push(meta),
+            }
+        }
+
+        let header = header_of(&static_metas);
+        let account_keys: Vec<Pubkey> = static_metas.into_iter().map(|(k, ..)| k).collect();
+        let loaded = |indexes: fn(&MessageAddressTableLookup) -> &[u8]| {
+            lookups.iter().zip(tables).flat_map(move |(lookup, table)| {
+                indexes(lookup)
+                    .iter()
+                    .map(|&i| table.addresses[usize::from(i)])
+            })
+        };
+        let mut all_keys = account_keys.clone();
+        all_keys.extend(loaded(|l| &l.writable_indexes));
+        all_keys.extend(loaded(|l| &l.readonly_indexes));
+        let index_of = |key: &Pubkey| all_keys.iter().position(|k| k == key).unwrap() as u8;
+        let instructions = instructions
+            .iter()
+            .map(|ix| CompiledInstruction {
+                program_id_index: index_of(&ix.program_id),
+                accounts: ix.accounts.iter().map(|m| index_of(&m.pubkey)).collect(),
+                data: ix.data.clone(),
+            })
+            .collect();
+        let address_table_lookups = lookups
+            .into_iter()
+            .filter(|l| !l.writable_indexes.is_empty() || !l.readonly_indexes.is_empty())
+            .collect();
+
+        MessageV0 {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups,
+        }
+    }
+
+    pub fn signer_keys(&self) -> &[Pubkey] {
+        &self.account_keys[..self.header.num_required_signatures as usize]
+    }
+
+    /// Every account the message uses, listed or loaded; the runtime locks
+    /// each of them.
+    pub fn account_count(&self) -> usize {
+        self.account_keys.len()
+            + self
+                .address_table_lookups
+                .iter()
+                .map(|l| l.writable_indexes.len() + l.readonly_indexes.len())
+                .sum::<usize>()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let legacy = Message {
+            header: self.header,
+            account_keys: self.account_keys.clone(),
+            recent_blockhash: self.recent_blockhash,
+            instructions: self.instructions.clone(),
+        };
+        let mut out = vec![0x80];
+        out.extend_from_slice(&legacy.serialize());
+        write_compact_u16(&mut out, self.address_table_lookups.len());
+        for lookup in &self.address_table_lookups {
+            out.extend_from_slice(lookup.account_key.as_bytes());
+            write_compact_u16(&mut out, lookup.writable_indexes.len());
+            out.extend_from_slice(&lookup.writable_indexes);
+            write_compact_u16(&mut out, lookup.readonly_indexes.len());
+            out.extend_from_slice(&lookup.readonly_indexes);
+        }
+        out
+    }
+}
+
+/// A legacy or version 0 message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionedMessage {
+    Legacy(Message),
+    V0(MessageV0),
+}
+
+impl VersionedMessage {
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            VersionedMessage::Legacy(m) => &m.header,
+            VersionedMessage::V0(m) => &m.header,
+        }
+    }
+
+    pub fn signer_keys(&self) -> &[Pubkey] {
+        match self {
+            VersionedMessage::Legacy(m) => m.signer_keys(),
+            VersionedMessage::V0(m) => m.signer_keys(),
+        }
+    }
+
+    pub fn account_count(&self) -> usize {
+        match self {
+            VersionedMessage::Legacy(m) => m.account_keys.len(),
+            VersionedMessage::V0(m) => m.account_count(),
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        match self {
+            VersionedMessage::Legacy(m) => m.serialize(),
+            VersionedMessage::V0(m) => m.serialize(),
+        }
+    }
+}
+
+/// A transaction carrying a [`VersionedMessage`]; with a legacy message
+/// it serializes exactly as [`Transaction`] does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionedTransaction {
+    pub signatures: Vec<Signature>,
+    pub message: VersionedMessage,
+}
+
+impl VersionedTransaction {
+    pub fn new_unsigned(message: VersionedMessage) -> Self {
+        let signatures =
+            vec![Signature::default(); message.header().num_required_signatures as usize];
+        VersionedTransaction {
+            signatures,
+            message,
+        }
+    }
+
+    /// Signs the message with every signer whose key is a required signer.
+    pub fn sign<S: Signer + ?Sized>(&mut self, signers: &[&S]) -> Result<()> {
+        let message = self.message.serialize();
+        for signer in signers {
+            let key = signer.pubkey();
+            let position = self
+                .message
+                .signer_keys()
+                .iter()
+                .position(|k| *k == key)
+                .ok_or_else(|| {
+                    Error::InvalidInput(format!("{key} is not a signer of this transaction"))
+                })?;
+            self.signatures[position] = signer.try_sign_message(&message)?;
+        }
+        Ok(())
+    }
+
+    /// The first signature, which identifies the transaction on-chain.
+    pub fn signature(&self) -> Signature {
+        self.signatures.first().copied().unwrap_or_default()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_compact_u16(&mut out, self.signatures.len());
+        for signature in &self.signatures {
+            out.extend_from_slice(signature.as_bytes());
+        }
+        out.extend_from_slice(&self.message.serialize());
+        out
+    }
+}
+
+fn read_compact_u16(data: &[u8], pos: &mut usize) -> Result<usize> {
+    let mut value = 0usize;
+    for shift in [0, 7, 14] {
+        let byte = *data
+            .get(*pos)
+            .ok_or_else(|| Error::InvalidInput("transaction is truncated".into()))?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::InvalidInput(
+        "bad length prefix in transaction".into(),
+    ))
+}
+
+/// Adds `signer`'s signature to a serialized transaction built elsewhere,
+/// legacy or versioned, such as one returned by a swap API. Returns the
+/// signed wire bytes and the transaction's first signature.
+pub fn sign_wire_transaction<S: Signer + ?Sized>(
+    wire: &[u8],
+    signer: &S,
+) -> Result<(Vec<u8>, Signature)> {
+    let mut pos = 0;
+    let count = read_compact_u16(wire, &mut pos)?;
+    let signatures_at = pos;
+    let message_at = signatures_at + count * 64;
+    let message = wire
+        .get(message_at..)
+        .filter(|m| !m.is_empty())
+        .ok_or_else(|| Error::InvalidInput("transaction is truncated".into()))?;
+    // Versioned messages start with 0x80 | version before the header.
+    let header_at = usize::from(message[0] & 0x80 != 0);
+    let required = *message
+        .get(header_at)
+        .ok_or_else(|| Error::InvalidInput("transaction is truncated".into()))?
+        as usize;
+    if required != count {
+        return Err(Error::InvalidInput(format!(
+            "transaction has {count} signature slots for {required} signers"
+        )));
+    }
+    let mut keys_at = header_at + 3;
+    let key_count = read_compact_u16(message, &mut keys_at)?;
+    let key = signer.pubkey();
+    let position = (0..required.min(key_count))
+        .find(|i| {
+            message.get(keys_at + i * 32..keys_at + (i + 1) * 32) == Some(&key.as_bytes()[..])
+        })
+        .ok_or_else(|| Error::InvalidInput(format!("{key} is not a signer of this transaction")))?;
+    let signature = signer.try_sign_message(message)?;
+    let mut signed = wire.to_vec();
+    let slot = signatures_at + position * 64;
+    signed[slot..slot + 64].copy_from_slice(signature.as_bytes());
+    let mut first = [0u8; 64];
+    first.copy_from_slice(&signed[signatures_at..signatures_at + 64]);
+    Ok((signed, Signature::new(first)))
+}
+
+/// Splits a serialized transaction, legacy or versioned, into its first
+/// signature, its message and the keys required to sign it.
+pub fn inspect_wire_transaction(wire: &[u8]) -> Result<(Signature, &[u8], Vec<Pubkey>)> {
+    let truncated = || Error::InvalidInput("transaction is truncated".into());
+    let mut pos = 0;
+    let count = read_compact_u16(wire, &mut pos)?;
+    let message_at = pos + count * 64;
+    let message = wire
+        .get(message_at..)
+        .filter(|m| !m.is_empty())
+        .ok_or_else(truncated)?;
+    let header_at = usize::from(message[0] & 0x80 != 0);
+    let required = *message.get(header_at).ok_or_else(truncated)? as usize;
+    let mut keys_at = header_at + 3;
+    let key_count = read_compact_u16(message, &mut keys_at)?;
+    let signers = (0..required.min(key_count))
+        .map(|i| {
+            message
+                .get(keys_at + i * 32..keys_at + (i + 1) * 32)
+                .map(|k| Pubkey::new(k.try_into().unwrap()))
+                .ok_or_else(truncated)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let first = wire
+        .get(pos..pos + 64)
+        .map_or_else(Signature::default, |s| {
+            Signature::new(s.try_into().unwrap())
+        });
+    Ok((first, message, signers))
+}
+
+fn write_compact_u16(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        byte |= 0x80;
+        out.push(byte);
+    }
+}
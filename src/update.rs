@@ -0,0 +1,227 @@
+//! `self-update`: replacing the running binary with the build for this
+//! platform from the latest GitHub release of a configured repository.
+//!
+//! A release carries one binary per platform, named as [`asset_name`]
+//! gives, a `SHA256SUMS` file listing their digests as `sha256sum` writes
+//! them, and `SHA256SUMS.sig`, the base58 ed25519 signature by the key
+//! pinned in the config of the release manifest: the line
+//! `solanaapp release <version>` followed by that file, as
+//! [`signed_manifest`] builds it. Nothing is replaced unless the signature
+//! verifies for the release's own version and the download matches its
+//! listed digest, so a release page that was tampered with can't push a
+//! binary of someone else's, nor an older signed release under a newer tag.
+
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::pubkey::Pubkey;
+
+const GITHUB_API: &str = "https://api.github.com";
+const CHECKSUMS: &str = "SHA256SUMS";
+const CHECKSUMS_SIGNATURE: &str = "SHA256SUMS.sig";
+/// Largest binary accepted; a release asset beyond this is refused.
+const MAX_BINARY_BYTES: u64 = 256 * 1024 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// The version this binary was built as.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+impl Release {
+    /// The release's version, its tag without a leading `v`.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    fn asset(&self, name: &str) -> Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .ok_or_else(|| Error::InvalidInput(format!("release {} has no {name}", self.tag_name)))
+    }
+}
+
+/// The release asset holding the binary for the platform this runs on,
+/// e.g. `solanaapp-x86_64-linux`.
+pub fn asset_name() -> String {
+    format!(
+        "solanaapp-{}-{}{}",
+        env::consts::ARCH,
+        env::consts::OS,
+        env::consts::EXE_SUFFIX
+    )
+}
+
+/// Whether `candidate` is a later version than `current`, both
+/// `MAJOR.MINOR.PATCH`. Pre-release and build suffixes are not compared.
+pub fn is_newer(candidate: &str, current: &str) -> Result<bool> {
+    let parse = |v: &str| -> Result<[u64; 3]> {
+        let core = v.split(['-', '+']).next().unwrap_or(v);
+        let parts: Vec<u64> = core
+            .split('.')
+            .map(str::parse)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| Error::InvalidInput(format!("'{v}' is not a version")))?;
+        parts
+            .try_into()
+            .map_err(|_| Error::InvalidInput(format!("'{v}' is not MAJOR.MINOR.PATCH")))
+    };
+    Ok(parse(candidate)? > parse(current)?)
+}
+
+/// The hex digest `checksums` lists for `name`.
+pub fn listed_digest<'a>(checksums: &'a str, name: &str) -> Option<&'a str> {
+    checksums.lines().find_map(|line| {
+        let (digest, file) = line.split_once(char::is_whitespace)?;
+        // sha256sum marks binary-mode entries with a leading '*'.
+        let file = file.trim_start().trim_start_matches('*');
+        (file == name).then_some(digest)
+    })
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// What a release's signature covers: its version, then its checksums.
+pub fn signed_manifest(version: &str, checksums: &[u8]) -> Vec<u8> {
+    let mut manifest = format!("solanaapp release {version}\n").into_bytes();
+    manifest.extend_from_slice(checksums);
+    manifest
+}
+
+/// Checks that `signature` is `key`'s over the manifest of `version` with
+/// `checksums`, and that `binary` matches the digest listed for `name`.
+pub fn verify(
+    version: &str,
+    checksums: &[u8],
+    signature: &str,
+    key: &Pubkey,
+    name: &str,
+    binary: &[u8],
+) -> Result<()> {
+    let signature: Signature = signature.parse()?;
+    if !signature.verify(key, &signed_manifest(version, checksums)) {
+        return Err(Error::InvalidInput(format!(
+            "{CHECKSUMS} is not signed by {key} for release {version}; not updating"
+        )));
+    }
+    let checksums = std::str::from_utf8(checksums)
+        .map_err(|_| Error::InvalidInput(format!("{CHECKSUMS} is not text")))?;
+    let listed = listed_digest(checksums, name)
+        .ok_or_else(|| Error::InvalidInput(format!("{CHECKSUMS} doesn't list {name}")))?;
+    let actual = sha256_hex(binary);
+    if !listed.eq_ignore_ascii_case(&actual) {
+        return Err(Error::InvalidInput(format!(
+            "{name} has digest {actual}, but {CHECKSUMS} lists {listed}; not updating"
+        )));
+    }
+    Ok(())
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(TIMEOUT).build()
+}
+
+fn download(agent: &ureq::Agent, url: &str, limit: u64) -> Result<Vec<u8>> {
+    let response = agent
+        .get(url)
+        .call()
+        .map_err(|e| Error::Http(format!("GET {url}: {e}")))?;
+    let mut bytes = Vec::new();
+    response.into_reader().take(limit).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 == limit {
+        return Err(Error::InvalidInput(format!(
+            "{url} is larger than {limit} bytes"
+        )));
+    }
+    Ok(bytes)
+}
+
+/// The latest release of `repo`, given as `OWNER/NAME`.
+pub fn latest_release(repo: &str) -> Result<Release> {
+    let url = format!("{GITHUB_API}/repos/{repo}/releases/latest");
+    agent()
+        .get(&url)
+        .set("Accept", "application/vnd.github+json")
+        .call()
+        .map_err(|e| Error::Http(format!("GET {url}: {e}")))?
+        .into_json()
+        .map_err(|e| Error::Http(format!("GET {url}: unexpected response: {e}")))
+}
+
+/// Downloads this platform's binary from `release` and checks it against
+/// the checksums signed for the release's version. Returns the verified
+/// bytes.
+pub fn fetch_verified(release: &Release, key: &Pubkey) -> Result<Vec<u8>> {
+    let agent = agent();
+    let name = asset_name();
+    let binary = release.asset(&name)?;
+    let checksums = download(
+        &agent,
+        &release.asset(CHECKSUMS)?.browser_download_url,
+        1024 * 1024,
+    )?;
+    let signature = download(
+        &agent,
+        &release.asset(CHECKSUMS_SIGNATURE)?.browser_download_url,
+        1024,
+    )?;
+    let signature = String::from_utf8_lossy(&signature);
+    let bytes = download(&agent, &binary.browser_download_url, MAX_BINARY_BYTES)?;
+    verify(
+        release.version(),
+        &checksums,
+        &signature,
+        key,
+        &name,
+        &bytes,
+    )?;
+    Ok(bytes)
+}
+
+/// Puts `binary` in place of the executable at `exe`: written next to it
+/// first, then renamed over it, so an interrupted update leaves the old
+/// binary working. Returns where the old binary was kept, on platforms
+/// that can't replace a running executable.
+pub fn replace(exe: &Path, binary: &[u8]) -> Result<Option<PathBuf>> {
+    let staged = exe.with_extension("new");
+    fs::write(&staged, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    let kept = if cfg!(windows) {
+        let old = exe.with_extension("old");
+        fs::rename(exe, &old)?;
+        Some(old)
+    } else {
+        None
+    };
+    fs::rename(&staged, exe)?;
+    Ok(kept)
+}
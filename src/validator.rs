@@ -0,0 +1,166 @@
+//! A throwaway `solana-test-validator` for running launches end to end
+//! without touching a public cluster. The Metaplex metadata program is not
+//! part of the validator's genesis, so it is cloned from mainnet on start.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::keypair::Signature;
+use crate::metadata::METADATA_PROGRAM_ID;
+use crate::network::Network;
+use crate::process;
+use crate::pubkey::Pubkey;
+use crate::rpc::RpcClient;
+
+pub const VALIDATOR_BIN: &str = "solana-test-validator";
+/// How long the validator has to start answering, unless configured.
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(90);
+
+#[derive(Debug, Clone)]
+pub struct ValidatorOptions {
+    pub rpc_port: u16,
+    pub faucet_port: u16,
+    /// Cluster to clone the metadata program from; `None` skips the clone,
+    /// which leaves metadata steps failing but needs no network access.
+    pub clone_from: Option<String>,
+    /// Fresh ledger directory; removed again when the validator stops.
+    pub ledger: PathBuf,
+    /// How long to wait for its RPC to answer before giving up.
+    pub startup_timeout: Duration,
+    /// The validator binary, when it is not to be looked for; see
+    /// [`process::find_tool`].
+    pub program: Option<PathBuf>,
+}
+
+impl Default for ValidatorOptions {
+    fn default() -> Self {
+        ValidatorOptions {
+            rpc_port: 8899,
+            faucet_port: 9900,
+            clone_from: Some(Network::Mainnet.default_rpc_url().into()),
+            ledger: std::env::temp_dir()
+                .join(format!("launch-solana-ledger-{}", std::process::id())),
+            startup_timeout: STARTUP_TIMEOUT,
+            program: None,
+        }
+    }
+}
+
+/// A running validator, stopped and its ledger deleted on drop.
+pub struct TestValidator {
+    child: Child,
+    ledger: PathBuf,
+    url: String,
+}
+
+impl TestValidator {
+    /// Whether `solana-test-validator` can be found and run.
+    pub fn is_installed() -> bool {
+        let Ok(program) = process::find_tool(VALIDATOR_BIN, None) else {
+            return false;
+        };
+        Command::new(program)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+
+    /// Starts the validator and waits until its RPC answers.
+    pub fn start(options: &ValidatorOptions) -> Result<Self> {
+        let program = match &options.program {
+            Some(program) => program.clone(),
+            None => process::find_tool(VALIDATOR_BIN, None)?,
+        };
+        let mut command = Command::new(&program);
+        command
+            .arg("--reset")
+            .arg("--quiet")
+            .arg("--ledger")
+            .arg(&options.ledger)
+            .args(["--rpc-port", &options.rpc_port.to_string()])
+            .args(["--faucet-port", &options.faucet_port.to_string()]);
+        if let Some(url) = &options.clone_from {
+            command.args(["--url", url]).args([
+                "--clone-upgradeable-program",
+                &METADATA_PROGRAM_ID.to_string(),
+            ]);
+        }
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => {
+                    Error::Config(format!("{} could not be run", program.display()))
+                }
+                _ => Error::Io(e),
+            })?;
+        let mut validator = TestValidator {
+            child,
+            ledger: options.ledger.clone(),
+            url: format!("http://127.0.0.1:{}", options.rpc_port),
+        };
+        validator.wait_ready(options.startup_timeout)?;
+        Ok(validator)
+    }
+
+    fn wait_ready(&mut self, timeout: Duration) -> Result<()> {
+        let rpc = RpcClient::new(self.url.clone());
+        let started = Instant::now();
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = self.child.stderr.take() {
+                    let _ = io::Read::read_to_string(&mut pipe, &mut stderr);
+                }
+                return Err(Error::Config(format!(
+                    "{VALIDATOR_BIN} exited with {status}: {}",
+                    stderr.trim()
+                )));
+            }
+            // The first slot is only confirmed once the ledger is usable.
+            if rpc.get_slot().is_ok_and(|slot| slot > 0) {
+                return Ok(());
+            }
+            if started.elapsed() > timeout {
+                return Err(Error::Timeout(format!(
+                    "{VALIDATOR_BIN} did not answer on {} within {}s",
+                    self.url,
+                    timeout.as_secs()
+                )));
+            }
+            thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn rpc(&self) -> RpcClient {
+        RpcClient::new(self.url.clone())
+    }
+
+    /// Funds `pubkey` from the validator's faucet and waits for it to land.
+    pub fn airdrop(&self, pubkey: &Pubkey, lamports: u64) -> Result<Signature> {
+        let rpc = self.rpc();
+        let signature = rpc.request_airdrop(pubkey, lamports)?;
+        rpc.confirm_transaction(&signature)?;
+        Ok(signature)
+    }
+}
+
+impl Drop for TestValidator {
+    fn drop(&mut self) {
+        process::terminate(&mut self.child);
+        let _ = fs::remove_dir_all(&self.ledger);
+    }
+}
@@ -0,0 +1,321 @@
+//! Grinding for mint addresses that start or end with chosen text.
+//!
+//! Candidates are derived from a base seed and a counter (see [`derive`]),
+//! so a finished search only has to remember the winning counter: whoever
+//! holds the base seed can rebuild the key. The search itself runs on a
+//! [`Backend`]. [`Cpu`] is the portable one; an accelerated backend only has
+//! to reproduce [`derive`] and [`Pattern::matches`] for its results to be
+//! usable everywhere else.
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::keypair::Keypair;
+use crate::pubkey::Pubkey;
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+/// Salts a worker claims at a time.
+const BATCH: u64 = 4_096;
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Prefix,
+    Suffix,
+}
+
+/// Text an address should start or end with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    text: String,
+    position: Position,
+    ignore_case: bool,
+}
+
+fn in_alphabet(c: char) -> bool {
+    BASE58_ALPHABET.contains(c)
+}
+
+/// How many characters of the alphabet `c` stands for.
+fn variants(c: char, ignore_case: bool) -> usize {
+    if !ignore_case {
+        return 1;
+    }
+    let lower = c.to_ascii_lowercase();
+    let upper = c.to_ascii_uppercase();
+    if lower == upper {
+        1
+    } else {
+        usize::from(in_alphabet(lower)) + usize::from(in_alphabet(upper))
+    }
+}
+
+impl Pattern {
+    pub fn new(text: &str, position: Position, ignore_case: bool) -> Result<Self> {
+        if text.is_empty() {
+            return Err(Error::InvalidInput("vanity pattern is empty".into()));
+        }
+        for c in text.chars() {
+            let possible = in_alphabet(c)
+                || (ignore_case
+                    && (in_alphabet(c.to_ascii_lowercase())
+                        || in_alphabet(c.to_ascii_uppercase())));
+            if !possible {
+                return Err(Error::InvalidInput(format!(
+                    "'{c}' in '{text}' never appears in an address (base58 has no 0, O, I or l)"
+                )));
+            }
+        }
+        Ok(Pattern {
+            text: text.to_string(),
+            position,
+            ignore_case,
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn matches(&self, address: &str) -> bool {
+        let n = self.text.len();
+        if address.len() < n {
+            return false;
+        }
+        let part = match self.position {
+            Position::Prefix => &address[..n],
+            Position::Suffix => &address[address.len() - n..],
+        };
+        if self.ignore_case {
+            part.eq_ignore_ascii_case(&self.text)
+        } else {
+            part == self.text
+        }
+    }
+
+    /// Expected keys to try before one matches. Approximate for prefixes,
+    /// whose first character is not quite uniform.
+    pub fn difficulty(&self) -> f64 {
+        self.text
+            .chars()
+            .map(|c| 58.0 / variants(c, self.ignore_case) as f64)
+            .product()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = match self.position {
+            Position::Prefix => "starting with",
+            Position::Suffix => "ending with",
+        };
+        write!(f, "{position} '{}'", self.text)?;
+        if self.ignore_case {
+            f.write_str(" (any case)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Any one of several patterns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search {
+    patterns: Vec<Pattern>,
+}
+
+impl Search {
+    pub fn new(patterns: Vec<Pattern>) -> Result<Self> {
+        if patterns.is_empty() {
+            return Err(Error::InvalidInput("no vanity pattern given".into()));
+        }
+        Ok(Search { patterns })
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    /// Index of the first pattern `address` matches.
+    pub fn find(&self, address: &str) -> Option<usize> {
+        self.patterns.iter().position(|p| p.matches(address))
+    }
+
+    /// Expected keys to try before any pattern matches.
+    pub fn difficulty(&self) -> f64 {
+        1.0 / self
+            .patterns
+            .iter()
+            .map(|p| 1.0 / p.difficulty())
+            .sum::<f64>()
+    }
+}
+
+/// The candidate key for `salt`: an ed25519 key whose seed is the SHA-256
+/// of a fixed tag, `base` and `salt` as 8 little-endian bytes.
+pub fn derive(base: &[u8; 32], salt: u64) -> Keypair {
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(b"launch-solana vanity")
+        .chain_update(base)
+        .chain_update(salt.to_le_bytes())
+        .finalize()
+        .into();
+    Keypair::from_seed(&seed)
+}
+
+/// Something that tests candidate keys.
+pub trait Backend: Sync {
+    fn name(&self) -> &str;
+
+    /// Tries the salts in `salts` in order, adding each to `attempts`, and
+    /// returns the first whose key matches `search`. Gives up with `None`
+    /// soon after `stop` is set.
+    fn search(
+        &self,
+        base: &[u8; 32],
+        search: &Search,
+        salts: Range<u64>,
+        stop: &AtomicBool,
+        attempts: &AtomicU64,
+    ) -> Option<u64>;
+}
+
+/// Derives and encodes every candidate on the calling thread.
+pub struct Cpu;
+
+impl Backend for Cpu {
+    fn name(&self) -> &str {
+        "cpu"
+    }
+
+    fn search(
+        &self,
+        base: &[u8; 32],
+        search: &Search,
+        salts: Range<u64>,
+        stop: &AtomicBool,
+        attempts: &AtomicU64,
+    ) -> Option<u64> {
+        let mut tried = 0;
+        let mut found = None;
+        for salt in salts {
+            tried += 1;
+            if search
+                .find(&derive(base, salt).pubkey().to_string())
+                .is_some()
+            {
+                found = Some(salt);
+                break;
+            }
+            if tried % 256 == 0 {
+                attempts.fetch_add(256, Ordering::Relaxed);
+                tried = 0;
+                if stop.load(Ordering::Relaxed) {
+                    return None;
+                }
+            }
+        }
+        attempts.fetch_add(tried, Ordering::Relaxed);
+        found
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    pub attempts: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Keys tried per second so far.
+    pub fn rate(&self) -> f64 {
+        self.attempts as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub salt: u64,
+    pub address: Pubkey,
+    /// Index into [`Search::patterns`].
+    pub pattern: usize,
+    pub progress: Progress,
+}
+
+/// Number of workers to use when none is given: one per core.
+pub fn default_workers() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Runs `backend` on `workers` threads, each claiming a batch of salts at a
+/// time, until a key matches. `report` is called about once a second and
+/// stops the search by returning `false`, in which case `None` comes back.
+pub fn grind(
+    backend: &dyn Backend,
+    base: &[u8; 32],
+    search: &Search,
+    workers: usize,
+    mut report: impl FnMut(&Progress) -> bool,
+) -> Option<Found> {
+    let started = Instant::now();
+    let next = AtomicU64::new(0);
+    let attempts = AtomicU64::new(0);
+    let stop = AtomicBool::new(false);
+    let running = AtomicUsize::new(workers.max(1));
+    let winner: Mutex<Option<u64>> = Mutex::new(None);
+    thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            scope.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    let start = next.fetch_add(BATCH, Ordering::Relaxed);
+                    let Some(end) = start.checked_add(BATCH) else {
+                        break;
+                    };
+                    if let Some(salt) = backend.search(base, search, start..end, &stop, &attempts) {
+                        winner
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .get_or_insert(salt);
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+                running.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+        let mut last_report = Instant::now();
+        while running.load(Ordering::Relaxed) > 0 {
+            thread::sleep(Duration::from_millis(50));
+            if last_report.elapsed() >= REPORT_INTERVAL && !stop.load(Ordering::Relaxed) {
+                last_report = Instant::now();
+                let progress = Progress {
+                    attempts: attempts.load(Ordering::Relaxed),
+                    elapsed: started.elapsed(),
+                };
+                if !report(&progress) {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    });
+    let salt = winner.into_inner().unwrap_or_else(|e| e.into_inner())?;
+    let address = derive(base, salt).pubkey();
+    Some(Found {
+        salt,
+        address,
+        pattern: search.find(&address.to_string())?,
+        progress: Progress {
+            attempts: attempts.load(Ordering::Relaxed),
+            elapsed: started.elapsed(),
+        },
+    })
+}
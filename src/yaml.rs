@@ -0,0 +1,263 @@
+//! The subset of YAML used by plan files, read into a `serde_json::Value`
+//! so the usual serde derives apply: block mappings and sequences, plain,
+//! single- and double-quoted scalars, `[]`/`{}` and comments. Anchors, tags,
+//! flow collections and multi-line scalars are rejected rather than
+//! misread.
+//!
+//! Plain scalars that are whole numbers become JSON numbers and
+//! `true`/`false`/`null`/`~` become their JSON counterparts; everything
+//! else, including `1_000_000` and `12.5`, stays a string so amounts are
+//! never rounded through a float.
+
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+
+struct Line<'a> {
+    /// 1-based, for messages.
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+fn error(line: usize, message: impl std::fmt::Display) -> Error {
+    Error::InvalidInput(format!("line {line}: {message}"))
+}
+
+/// Cuts a trailing comment: `#` at the start or after whitespace, outside quotes.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            // A quote only opens a string at the start of a token, so
+            // `don't` in a plain scalar is just an apostrophe.
+            (None, '"' | '\'') if previous.is_whitespace() => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if previous.is_whitespace() => return &text[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    text
+}
+
+fn lines(source: &str) -> Result<Vec<Line<'_>>> {
+    let mut lines = Vec::new();
+    for (i, raw) in source.lines().enumerate() {
+        let number = i + 1;
+        let text = strip_comment(raw).trim_end();
+        let content = text.trim_start_matches(' ');
+        if content.is_empty() || (number == 1 && content == "---") {
+            continue;
+        }
+        if content.starts_with('\t') {
+            return Err(error(number, "indent with spaces, not tabs"));
+        }
+        lines.push(Line {
+            number,
+            indent: text.len() - content.len(),
+            text: content,
+        });
+    }
+    Ok(lines)
+}
+
+/// Splits `key: value` at the first `:` followed by a space or the end of
+/// the line, outside quotes.
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') if previous.is_whitespace() => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, ':') => {
+                let rest = &text[i + 1..];
+                if rest.is_empty() || rest.starts_with(' ') {
+                    return Some((&text[..i], rest.trim()));
+                }
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+fn scalar(line: usize, text: &str) -> Result<Value> {
+    if let Some(body) = text.strip_prefix('"') {
+        let body = body
+            .strip_suffix('"')
+            .ok_or_else(|| error(line, "unterminated double-quoted string"))?;
+        let mut out = String::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            out.push(match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some(c @ ('"' | '\\' | '/')) => c,
+                other => {
+                    return Err(error(
+                        line,
+                        format!("unsupported escape \\{}", other.unwrap_or(' ')),
+                    ));
+                }
+            });
+        }
+        return Ok(Value::String(out));
+    }
+    if let Some(body) = text.strip_prefix('\'') {
+        let body = body
+            .strip_suffix('\'')
+            .ok_or_else(|| error(line, "unterminated single-quoted string"))?;
+        return Ok(Value::String(body.replace("''", "'")));
+    }
+    match text {
+        "" | "~" | "null" => return Ok(Value::Null),
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        "[]" => return Ok(Value::Array(Vec::new())),
+        "{}" => return Ok(Value::Object(Map::new())),
+        _ => {}
+    }
+    if text.starts_with(['[', '{', '&', '*', '!', '|', '>']) {
+        return Err(error(
+            line,
+            format!(
+                "'{text}': flow collections, anchors, tags and block scalars are not supported"
+            ),
+        ));
+    }
+    if let Ok(n) = text.parse::<u64>() {
+        return Ok(Value::from(n));
+    }
+    if let Ok(n) = text.parse::<i64>() {
+        return Ok(Value::from(n));
+    }
+    Ok(Value::String(text.to_string()))
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Line<'a>> {
+        self.lines.get(self.pos)
+    }
+
+    /// The block starting at the current line, whose indent is `indent`.
+    fn block(&mut self, indent: usize) -> Result<Value> {
+        match self.peek() {
+            Some(line) if is_item(line.text) => self.sequence(indent),
+            Some(_) => self.mapping(indent),
+            None => Ok(Value::Null),
+        }
+    }
+
+    /// The value after `key:` or `-` with nothing else on the line: a
+    /// nested block if one follows, else null.
+    fn nested(&mut self, parent: usize, sequence_allowed_at_parent: bool) -> Result<Value> {
+        match self.peek() {
+            Some(next) if next.indent > parent => {
+                let indent = next.indent;
+                self.block(indent)
+            }
+            Some(next)
+                if sequence_allowed_at_parent && next.indent == parent && is_item(next.text) =>
+            {
+                self.sequence(parent)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value> {
+        let mut map = Map::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent || (line.indent == indent && is_item(line.text)) {
+                break;
+            }
+            let number = line.number;
+            if line.indent > indent {
+                return Err(error(number, "unexpected indentation"));
+            }
+            let (key, rest) = split_key(line.text).ok_or_else(|| {
+                error(
+                    number,
+                    format!("expected 'key: value', got '{}'", line.text),
+                )
+            })?;
+            let key = match scalar(number, key)? {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            self.pos += 1;
+            let value = if rest.is_empty() {
+                self.nested(indent, true)?
+            } else {
+                scalar(number, rest)?
+            };
+            if map.insert(key.clone(), value).is_some() {
+                return Err(error(number, format!("duplicate key '{key}'")));
+            }
+        }
+        Ok(Value::Object(map))
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value> {
+        let mut items = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent != indent || !is_item(line.text) {
+                if line.indent > indent {
+                    return Err(error(line.number, "unexpected indentation"));
+                }
+                break;
+            }
+            let number = line.number;
+            let rest = line.text[1..].trim_start();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.nested(indent, false)?);
+            } else if split_key(rest).is_some() || is_item(rest) {
+                // `- key: value` opens a mapping whose keys line up with `key`.
+                let inner = indent + (line.text.len() - rest.len());
+                self.lines[self.pos] = Line {
+                    number,
+                    indent: inner,
+                    text: rest,
+                };
+                items.push(self.block(inner)?);
+            } else {
+                self.pos += 1;
+                items.push(scalar(number, rest)?);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+}
+
+/// Parses a YAML document in the supported subset.
+pub fn parse(source: &str) -> Result<Value> {
+    let lines = lines(source)?;
+    let Some(first) = lines.first() else {
+        return Ok(Value::Null);
+    };
+    let indent = first.indent;
+    let mut parser = Parser { lines, pos: 0 };
+    let value = parser.block(indent)?;
+    if let Some(line) = parser.peek() {
+        return Err(error(line.number, "unexpected indentation"));
+    }
+    Ok(value)
+}
@@ -0,0 +1,133 @@
+//! The audit log's hash chain, written through the log and checked by
+//! `verify`, including logs altered after the fact.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use solanaapp::Error;
+use solanaapp::audit::{self, AuditLog};
+use solanaapp::pubkey::Pubkey;
+
+const SIGNER: Pubkey = Pubkey::new([7; 32]);
+
+/// A fresh log path for `name`, gone from any earlier run.
+fn log_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("solanaapp-audit-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{name}.jsonl"));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+/// A log of `count` entries, one per distinct message.
+fn written(name: &str, count: u8) -> PathBuf {
+    let path = log_path(name);
+    let log = AuditLog::open(&path, "test").unwrap();
+    for i in 0..count {
+        log.record_message(&[i], &[SIGNER], None).unwrap();
+    }
+    path
+}
+
+fn lines(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+fn broken(path: &Path) -> String {
+    match audit::verify(path) {
+        Err(Error::InvalidInput(reason)) => reason,
+        other => panic!("expected a broken chain, got {other:?}"),
+    }
+}
+
+#[test]
+fn appended_entries_verify() {
+    let path = written("intact", 3);
+    let verified = audit::verify(&path).unwrap();
+    assert_eq!(verified.entries, 3);
+    // Reopening continues the chain rather than starting a new one.
+    let log = AuditLog::open(&path, "test").unwrap();
+    log.record_message(b"later", &[SIGNER], None).unwrap();
+    // The same message twice is logged once.
+    log.record_message(b"later", &[SIGNER], None).unwrap();
+    let after = audit::verify(&path).unwrap();
+    assert_eq!(after.entries, 4);
+    assert_ne!(after.head, verified.head);
+}
+
+#[test]
+fn reordered_lines_break_the_chain() {
+    let path = written("reordered", 3);
+    let mut text = lines(&path);
+    text.swap(1, 2);
+    fs::write(&path, text.join("\n") + "\n").unwrap();
+    let reason = broken(&path);
+    assert!(reason.starts_with("audit log line 2:"), "{reason}");
+    assert!(reason.contains("removed or reordered"), "{reason}");
+}
+
+#[test]
+fn removed_line_breaks_the_chain() {
+    let path = written("removed", 3);
+    let mut text = lines(&path);
+    text.remove(0);
+    fs::write(&path, text.join("\n") + "\n").unwrap();
+    assert!(broken(&path).starts_with("audit log line 1:"));
+}
+
+#[test]
+fn truncated_tail_is_caught_by_the_noted_head() {
+    let path = written("truncated", 3);
+    let head = audit::verify(&path).unwrap().head;
+    let text = lines(&path);
+    fs::write(&path, text[..2].join("\n") + "\n").unwrap();
+    // Still a valid chain, only shorter; the head noted earlier tells.
+    let verified = audit::verify(&path).unwrap();
+    assert_eq!(verified.entries, 2);
+    assert_ne!(verified.head, head);
+    // A line cut off mid-write is neither verified nor continued.
+    fs::write(&path, text.join("\n") + "\n" + &text[0][..20]).unwrap();
+    assert!(broken(&path).starts_with("audit log line 4:"));
+    assert!(AuditLog::open(&path, "test").is_err());
+}
+
+#[test]
+fn edited_line_breaks_the_chain() {
+    let path = written("edited", 2);
+    let text = fs::read_to_string(&path).unwrap().replacen(
+        "\"purpose\":\"test\"",
+        "\"purpose\":\"edit\"",
+        1,
+    );
+    fs::write(&path, text).unwrap();
+    assert!(broken(&path).contains("it was modified"));
+}
+
+#[test]
+fn concurrent_appenders_keep_one_chain() {
+    let path = log_path("concurrent");
+    // Separate handles on one file, as separate processes would hold.
+    let logs: Vec<AuditLog> = (0..4)
+        .map(|_| AuditLog::open(&path, "test").unwrap())
+        .collect();
+    let writers: Vec<_> = logs
+        .into_iter()
+        .enumerate()
+        .map(|(w, log)| {
+            thread::spawn(move || {
+                for i in 0..25u8 {
+                    log.record_message(&[w as u8, i], &[SIGNER], None).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    assert_eq!(audit::verify(&path).unwrap().entries, 100);
+}
@@ -0,0 +1,146 @@
+//! Cron schedules: which expressions parse, and the times they pick.
+
+use solanaapp::Error;
+use solanaapp::cron::Cron;
+use solanaapp::time::{format_rfc3339, parse_rfc3339};
+
+fn cron(expression: &str) -> Cron {
+    expression.parse().expect("schedule parses")
+}
+
+/// The next `count` times `expression` picks after `from`.
+fn next(expression: &str, from: &str, count: usize) -> Vec<String> {
+    let cron = cron(expression);
+    let mut at = parse_rfc3339(from).unwrap();
+    (0..count)
+        .map(|_| {
+            at = cron.next_after(at).expect("a time within the search");
+            format_rfc3339(at)
+        })
+        .collect()
+}
+
+#[test]
+fn fields_reject_values_outside_their_range() {
+    for expression in [
+        "60 * * * *",
+        "* 24 * * *",
+        "* * 0 * *",
+        "* * 32 * *",
+        "* * * 0 *",
+        "* * * 13 *",
+        "* * * * 8",
+        "5-1 * * * *",
+        "*/0 * * * *",
+        "1,,2 * * * *",
+        "a * * * *",
+        "* * * *",
+        "* * * * * *",
+        "@yearly",
+    ] {
+        assert!(
+            matches!(expression.parse::<Cron>(), Err(Error::InvalidInput(_))),
+            "{expression}"
+        );
+    }
+    for expression in ["59 23 31 12 7", "0 0 1 1 0", "0-59/15 0,12 1-31 */3 1-5"] {
+        cron(expression);
+    }
+}
+
+#[test]
+fn steps_ranges_and_lists_pick_the_right_minutes() {
+    assert_eq!(
+        next("*/20 * * * *", "2024-05-01T10:05:00Z", 4),
+        [
+            "2024-05-01T10:20:00Z",
+            "2024-05-01T10:40:00Z",
+            "2024-05-01T11:00:00Z",
+            "2024-05-01T11:20:00Z"
+        ]
+    );
+    assert_eq!(
+        next("50/5 9-10 * * *", "2024-05-01T09:52:00Z", 3),
+        [
+            "2024-05-01T09:55:00Z",
+            "2024-05-01T10:50:00Z",
+            "2024-05-01T10:55:00Z"
+        ]
+    );
+    // Strictly after: a time on the schedule is not picked again.
+    assert_eq!(
+        next("0,30 12 * * *", "2024-05-01T12:00:00Z", 2),
+        ["2024-05-01T12:30:00Z", "2024-05-02T12:00:00Z"]
+    );
+}
+
+#[test]
+fn shorthands_and_sunday_as_seven() {
+    // 2024-05-01 was a Wednesday.
+    assert_eq!(
+        next("@weekly", "2024-05-01T00:00:00Z", 1),
+        ["2024-05-05T00:00:00Z"]
+    );
+    assert_eq!(
+        next("0 0 * * 7", "2024-05-01T00:00:00Z", 1),
+        ["2024-05-05T00:00:00Z"]
+    );
+    assert_eq!(
+        next("@monthly", "2024-05-01T00:00:00Z", 1),
+        ["2024-06-01T00:00:00Z"]
+    );
+    assert_eq!(
+        next("@hourly", "2024-12-31T23:30:00Z", 1),
+        ["2025-01-01T00:00:00Z"]
+    );
+}
+
+#[test]
+fn either_day_field_matches_when_both_are_restricted() {
+    // The 13th, or any Friday.
+    assert_eq!(
+        next("0 0 13 * 5", "2024-09-01T00:00:00Z", 4),
+        [
+            "2024-09-06T00:00:00Z",
+            "2024-09-13T00:00:00Z",
+            "2024-09-20T00:00:00Z",
+            "2024-09-27T00:00:00Z"
+        ]
+    );
+    assert_eq!(
+        next("0 0 13 * 5", "2024-09-27T00:00:00Z", 2),
+        ["2024-10-04T00:00:00Z", "2024-10-11T00:00:00Z"]
+    );
+    assert_eq!(
+        next("0 0 13 * 5", "2024-10-11T00:00:00Z", 1),
+        ["2024-10-13T00:00:00Z"]
+    );
+}
+
+#[test]
+fn one_restricted_day_field_alone_decides() {
+    // Only the day of month restricted: the 13th, whatever the weekday.
+    assert_eq!(
+        next("0 0 13 * *", "2024-09-01T00:00:00Z", 2),
+        ["2024-09-13T00:00:00Z", "2024-10-13T00:00:00Z"]
+    );
+    // Only the weekday restricted: Fridays, whatever the date.
+    assert_eq!(
+        next("0 0 * * 5", "2024-09-01T00:00:00Z", 2),
+        ["2024-09-06T00:00:00Z", "2024-09-13T00:00:00Z"]
+    );
+}
+
+#[test]
+fn rare_dates_are_found() {
+    assert_eq!(
+        next("0 0 29 2 *", "2024-03-01T00:00:00Z", 1),
+        ["2028-02-29T00:00:00Z"]
+    );
+    assert_eq!(
+        next("0 0 31 * *", "2024-04-01T00:00:00Z", 2),
+        ["2024-05-31T00:00:00Z", "2024-07-31T00:00:00Z"]
+    );
+    // A date no month has never comes.
+    assert_eq!(cron("0 0 30 2 *").next_after(0), None);
+}
@@ -0,0 +1,67 @@
+//! Emission runs sharing one journal, replayed from a recorded mint.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use solanaapp::amount::TokenAmount;
+use solanaapp::emission::{self, EmissionEvent, Emitter, Terms};
+use solanaapp::fixture::Replay;
+use solanaapp::keypair::Keypair;
+use solanaapp::pubkey::Pubkey;
+use solanaapp::rpc::RpcClient;
+use solanaapp::time::parse_rfc3339;
+use solanaapp::token::TOKEN_PROGRAM_ID;
+
+/// A fresh journal path for `name`, gone from any earlier run.
+fn journal_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("solanaapp-emission-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{name}.jsonl"));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn replay(name: &str) -> RpcClient {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{name}.jsonl"));
+    RpcClient::replay(Replay::load(&path).expect("fixture loads"))
+}
+
+fn terms() -> Terms {
+    Terms {
+        mint: Pubkey::new([0x4d; 32]),
+        program_id: TOKEN_PROGRAM_ID,
+        recipient: Pubkey::new([2; 32]),
+        amount: TokenAmount::parse("100", 6).unwrap(),
+        schedule: "0 0 * * *".parse().unwrap(),
+        cap: TokenAmount::parse("1000", 6).unwrap(),
+        starts_at: parse_rfc3339("2024-05-01T00:00:00Z").unwrap(),
+    }
+}
+
+#[test]
+fn second_emitter_sees_the_first_ones_mint() {
+    let path = journal_path("shared");
+    emission::start(&path, terms()).unwrap();
+    // The fixture answers a single mint; a second would find no response.
+    let rpc = replay("emission");
+    let authority = Keypair::from_seed(&[1; 32]);
+    // Both opened before either runs, as two `emission run` processes.
+    let mut first = Emitter::open(&rpc, &authority, &path).unwrap();
+    let mut second = Emitter::open(&rpc, &authority, &path).unwrap();
+    let now = parse_rfc3339("2024-05-02T00:00:30Z").unwrap();
+
+    let events = first.poll(now).expect("first run mints");
+    assert!(
+        matches!(events.last(), Some(EmissionEvent::Minted { .. })),
+        "{events:?}"
+    );
+    let minted = first.state().minted;
+    assert!(!minted.is_zero());
+
+    let events = second.poll(now).expect("nothing left to mint");
+    assert!(events.is_empty(), "{events:?}");
+    assert_eq!(second.state().minted, minted);
+    assert_eq!(second.state().paid_until, first.state().paid_until);
+}
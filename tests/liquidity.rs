@@ -0,0 +1,71 @@
+//! Splitting liquidity across fee tiers, the price each pool would open
+//! at once the split is rounded, and measuring a pool against its baseline.
+
+use solanaapp::Error;
+use solanaapp::amount::TokenAmount;
+use solanaapp::liquidity::{FeeTier, PoolStatus, plan_deployments};
+use solanaapp::pubkey::Pubkey;
+
+const fn tier(index: u16) -> FeeTier {
+    FeeTier { index }
+}
+
+#[test]
+fn even_split_opens_every_tier_at_the_overall_price() {
+    let token = TokenAmount::from_raw(1_000_000_000, 6);
+    let quote = TokenAmount::from_raw(3_000_000_000, 9);
+    let deployments =
+        plan_deployments(token, quote, &[(tier(0), 5000), (tier(1), 5000)], 10).unwrap();
+    assert_eq!(deployments.len(), 2);
+    for deployment in &deployments {
+        assert_eq!(deployment.token.raw(), 500_000_000);
+        assert_eq!(deployment.quote.raw(), 1_500_000_000);
+    }
+}
+
+#[test]
+fn rounding_that_skews_a_small_tier_is_refused() {
+    // 0.5% of 1999 quote units rounds down to 9 against 5 tokens: the
+    // small pool would open at 1.8 where the rest open near 1.999.
+    let token = TokenAmount::from_raw(1000, 0);
+    let quote = TokenAmount::from_raw(1999, 0);
+    let shares = [(tier(0), 9950), (tier(1), 50)];
+    match plan_deployments(token, quote, &shares, 100) {
+        Err(Error::InvalidInput(reason)) => {
+            assert!(reason.starts_with("fee tier 1 would open at"), "{reason}");
+            assert!(reason.contains("intended 1.999"), "{reason}");
+        }
+        other => panic!("expected the skewed tier to be refused, got {other:?}"),
+    }
+    // A tolerance wide enough for the skew lets it through.
+    assert_eq!(
+        plan_deployments(token, quote, &shares, 1100).unwrap().len(),
+        2
+    );
+}
+
+fn pool(token: u128, quote: u128, lp_supply: u128) -> PoolStatus {
+    PoolStatus {
+        pool: Pubkey::new([1; 32]),
+        mint: Pubkey::new([2; 32]),
+        quote_mint: Pubkey::new([3; 32]),
+        token_reserve: TokenAmount::from_raw(token, 0),
+        quote_reserve: TokenAmount::from_raw(quote, 0),
+        lp_supply: TokenAmount::from_raw(lp_supply, 0),
+        lp_held: TokenAmount::from_raw(lp_supply, 0),
+    }
+}
+
+#[test]
+fn a_pool_without_lp_supply_has_no_baseline() {
+    let empty = pool(0, 0, 0);
+    assert_eq!(empty.root_per_lp(), None);
+    assert_eq!(empty.baseline(0), None);
+
+    let baseline = pool(100, 400, 200).baseline(0).unwrap();
+    assert_eq!(baseline.root_per_lp, 1.0);
+    assert_eq!(empty.performance(&baseline), None);
+    // Fees grew the reserves a quarter at the same price and supply.
+    let grown = pool(125, 500, 200).performance(&baseline).unwrap();
+    assert!((grown.fee_growth - 0.25).abs() < 1e-12);
+}
@@ -0,0 +1,103 @@
+//! Runs a full launch against `solana-test-validator`. Skipped when the
+//! Solana CLI tools are not installed; cloning the metadata program needs
+//! access to mainnet RPC.
+
+use solanaapp::amount::TokenAmount;
+use solanaapp::keypair::Keypair;
+use solanaapp::launch::{self, Links, Listing};
+use solanaapp::manager::SolanaTokenManager;
+use solanaapp::metadata;
+use solanaapp::network::Network;
+use solanaapp::session::{LaunchParams, LaunchSession, Step};
+use solanaapp::system::LAMPORTS_PER_SOL;
+use solanaapp::token::{self, TokenAccount};
+use solanaapp::validator::{TestValidator, ValidatorOptions};
+
+fn start_validator() -> Option<TestValidator> {
+    if !TestValidator::is_installed() {
+        eprintln!("solana-test-validator not installed; skipping");
+        return None;
+    }
+    let options = ValidatorOptions {
+        rpc_port: 18899,
+        faucet_port: 19900,
+        ledger: std::env::temp_dir().join(format!("launch-solana-it-{}", std::process::id())),
+        ..ValidatorOptions::default()
+    };
+    Some(TestValidator::start(&options).expect("validator starts"))
+}
+
+#[test]
+fn launch_end_to_end() {
+    let Some(validator) = start_validator() else {
+        return;
+    };
+    let rpc = validator.rpc();
+    let payer = Keypair::from_seed(&[7; 32]);
+    validator
+        .airdrop(&payer.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .expect("airdrop");
+
+    let params = LaunchParams {
+        name: "Integration Token".into(),
+        symbol: "ITEST".into(),
+        uri: "https://example.com/itest.json".into(),
+        supply: TokenAmount::parse("1000000", 6).unwrap(),
+        token_program: token::TOKEN_PROGRAM_ID,
+        freeze_authority: false,
+        revoke_mint_authority: true,
+        is_mutable: false,
+        description: None,
+        image: None,
+        links: Links::default(),
+        listing: Listing::default(),
+        transfer_hook: None,
+        group: None,
+        memo: None,
+        supply_account: None,
+        compute_unit_limit: None,
+        compute_unit_price: None,
+        metadata_program: None,
+    };
+    let mut session = LaunchSession::new(
+        "it-launch".into(),
+        Network::Localnet,
+        payer.pubkey(),
+        params,
+        launch::now_unix(),
+    );
+
+    // Stop after the first step and pick the session up with a fresh
+    // manager, as `session resume` does after an interruption.
+    SolanaTokenManager::new(&rpc, &payer)
+        .execute(&mut session, Step::CreateMint)
+        .expect("create mint");
+    let manager = SolanaTokenManager::new(&rpc, &payer);
+    while let Some(step) = session.next_step() {
+        manager
+            .execute(&mut session, step)
+            .unwrap_or_else(|e| panic!("{}: {e}", step.as_str()));
+    }
+    assert!(session.is_complete());
+
+    let mint = session.mint.expect("mint recorded");
+    let on_chain = token::fetch_mint(&rpc, &mint).expect("mint exists");
+    assert_eq!(on_chain.mint.supply, 1_000_000_000_000);
+    assert_eq!(on_chain.mint.decimals, 6);
+    assert_eq!(on_chain.mint.mint_authority, None);
+    assert_eq!(on_chain.mint.freeze_authority, None);
+
+    let account = session.token_account.expect("token account recorded");
+    let data = rpc.get_account(&account).unwrap().value.unwrap().data;
+    let holding = TokenAccount::unpack(&data).unwrap();
+    assert_eq!(holding.owner, payer.pubkey());
+    assert_eq!(holding.amount, 1_000_000_000_000);
+
+    let metadata = metadata::fetch_metadata(&rpc, &mint)
+        .unwrap()
+        .expect("metadata account");
+    assert_eq!(metadata.data.name, "Integration Token");
+    assert_eq!(metadata.data.symbol, "ITEST");
+    assert_eq!(metadata.data.uri, "https://example.com/itest.json");
+    assert!(!metadata.is_mutable);
+}
@@ -0,0 +1,191 @@
+//! Signing policy checks against messages built the way the commands build
+//! them.
+
+use solanaapp::Error;
+use solanaapp::memo;
+use solanaapp::policy::{Moved, Policy, decode};
+use solanaapp::programs::Programs;
+use solanaapp::pubkey::Pubkey;
+use solanaapp::system::{self, SYSTEM_PROGRAM_ID};
+use solanaapp::token::{self, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use solanaapp::tx::{AccountMeta, Hash, Instruction, Message};
+
+const PAYER: Pubkey = Pubkey::new([1; 32]);
+const OTHER: Pubkey = Pubkey::new([2; 32]);
+const MINT: Pubkey = Pubkey::new([3; 32]);
+
+fn policy(json: &str) -> Policy {
+    Policy::from_json(json, Programs::default()).expect("policy parses")
+}
+
+fn message(instructions: &[Instruction]) -> Vec<u8> {
+    Message::new(instructions, &PAYER, Hash::default()).serialize()
+}
+
+fn refused(result: solanaapp::Result<()>) -> String {
+    match result {
+        Err(Error::InvalidInput(reason)) => reason,
+        other => panic!("expected a refusal, got {other:?}"),
+    }
+}
+
+fn raw(program_id: Pubkey, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(PAYER, true)],
+        data,
+    }
+}
+
+/// `create_account_with_seed`: base, seed, lamports, space, owner.
+fn create_account_with_seed(seed: &str, lamports: u64) -> Instruction {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(OTHER.as_bytes());
+    data.extend_from_slice(&(seed.len() as u64).to_le_bytes());
+    data.extend_from_slice(seed.as_bytes());
+    data.extend_from_slice(&lamports.to_le_bytes());
+    data.extend_from_slice(&165u64.to_le_bytes());
+    data.extend_from_slice(TOKEN_PROGRAM_ID.as_bytes());
+    raw(SYSTEM_PROGRAM_ID, data)
+}
+
+/// `transfer_with_seed`: lamports, seed, owner.
+fn transfer_with_seed(lamports: u64) -> Instruction {
+    let mut data = 11u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+    data.extend_from_slice(&4u64.to_le_bytes());
+    data.extend_from_slice(b"seed");
+    data.extend_from_slice(SYSTEM_PROGRAM_ID.as_bytes());
+    raw(SYSTEM_PROGRAM_ID, data)
+}
+
+/// Token-2022 `transfer_checked_with_fee`: amount, decimals, fee.
+fn transfer_checked_with_fee(amount: u64) -> Instruction {
+    let mut data = vec![26, 1];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(6);
+    data.extend_from_slice(&10u64.to_le_bytes());
+    raw(TOKEN_2022_PROGRAM_ID, data)
+}
+
+#[test]
+fn listed_instructions_within_caps_are_allowed() {
+    let policy = policy(
+        r#"{ "rules": [
+            { "program": "system", "instructions": ["transfer"], "max_lamports": 1000 },
+            { "program": "token", "instructions": ["mint_to_checked"], "max_amount": 500 }
+        ] }"#,
+    );
+    let ixs = [
+        system::transfer(&PAYER, &OTHER, 1000),
+        token::mint_to_checked(&TOKEN_PROGRAM_ID, &MINT, &OTHER, &PAYER, 500, 6),
+    ];
+    policy.check(&message(&ixs)).unwrap();
+}
+
+#[test]
+fn unlisted_program_or_instruction_is_refused() {
+    let policy = policy(r#"{ "rules": [{ "program": "system", "instructions": ["transfer"] }] }"#);
+    let reason = refused(policy.check(&message(&[memo::memo("hi", &[&PAYER])])));
+    assert!(reason.contains("instruction 1"), "{reason}");
+    let ixs = [
+        system::transfer(&PAYER, &OTHER, 1),
+        system::advance_nonce_account(&OTHER, &PAYER),
+    ];
+    let reason = refused(policy.check(&message(&ixs)));
+    assert!(
+        reason.contains("instruction 2 (system advance_nonce_account)"),
+        "{reason}"
+    );
+}
+
+#[test]
+fn caps_are_summed_across_instructions() {
+    let policy = policy(r#"{ "rules": [{ "program": "system", "max_lamports": 1000 }] }"#);
+    let ixs = [
+        system::transfer(&PAYER, &OTHER, 600),
+        system::transfer(&PAYER, &MINT, 600),
+    ];
+    let reason = refused(policy.check(&message(&ixs)));
+    assert!(reason.contains("1200 lamports"), "{reason}");
+    policy.check(&message(&ixs[..1])).unwrap();
+}
+
+#[test]
+fn seeded_system_instructions_count_against_the_lamport_cap() {
+    let policy = policy(r#"{ "rules": [{ "program": "system", "max_lamports": 1000 }] }"#);
+    assert_eq!(
+        decode(
+            &Programs::default(),
+            &SYSTEM_PROGRAM_ID,
+            &create_account_with_seed("vault", 700).data
+        )
+        .lamports,
+        Moved::Exactly(700)
+    );
+    let ixs = [
+        create_account_with_seed("vault", 700),
+        transfer_with_seed(400),
+    ];
+    let reason = refused(policy.check(&message(&ixs)));
+    assert!(reason.contains("1100 lamports"), "{reason}");
+}
+
+#[test]
+fn transfer_with_fee_counts_against_the_amount_cap() {
+    let policy = policy(r#"{ "rules": [{ "program": "token-2022", "max_amount": 100 }] }"#);
+    policy
+        .check(&message(&[transfer_checked_with_fee(100)]))
+        .unwrap();
+    let reason = refused(policy.check(&message(&[transfer_checked_with_fee(101)])));
+    assert!(reason.contains("101 base units"), "{reason}");
+}
+
+#[test]
+fn unreadable_amount_is_refused_only_under_a_cap() {
+    // A confidential transfer keeps its amount encrypted.
+    let confidential = raw(TOKEN_2022_PROGRAM_ID, vec![27, 7]);
+    let capped = policy(r#"{ "rules": [{ "program": "token-2022", "max_amount": 100 }] }"#);
+    let reason = refused(capped.check(&message(std::slice::from_ref(&confidential))));
+    assert!(reason.contains("can't be read"), "{reason}");
+    let uncapped = policy(r#"{ "rules": [{ "program": "token-2022" }] }"#);
+    uncapped.check(&message(&[confidential])).unwrap();
+}
+
+#[test]
+fn token_rules_follow_the_deployed_program() {
+    let deployed = Programs {
+        token: Pubkey::new([9; 32]),
+        ..Programs::default()
+    };
+    let policy = Policy::from_json(
+        r#"{ "rules": [{ "program": "token", "max_amount": 10 }] }"#,
+        deployed,
+    )
+    .unwrap();
+    let at = |program: &Pubkey, amount| {
+        message(&[token::transfer_checked(
+            program, &OTHER, &MINT, &OTHER, &PAYER, amount, 6,
+        )])
+    };
+    policy.check(&at(&deployed.token, 10)).unwrap();
+    refused(policy.check(&at(&deployed.token, 11)));
+    refused(policy.check(&at(&TOKEN_PROGRAM_ID, 1)));
+}
+
+#[test]
+fn caps_on_programs_whose_amounts_are_not_read_are_rejected() {
+    for json in [
+        r#"{ "rules": [{ "program": "system", "max_amount": 1 }] }"#,
+        r#"{ "rules": [{ "program": "token", "max_lamports": 1 }] }"#,
+        r#"{ "rules": [{ "program": "nowhere" }] }"#,
+    ] {
+        assert!(
+            matches!(
+                Policy::from_json(json, Programs::default()),
+                Err(Error::Config(_))
+            ),
+            "{json}"
+        );
+    }
+}
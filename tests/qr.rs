@@ -0,0 +1,302 @@
+//! The QR encoder, checked by reading its symbols back: the format and
+//! version bits against the standard's tables, every block's error
+//! correction by its syndromes, and the data segment by decoding it.
+
+use solanaapp::Error;
+use solanaapp::qr::QrCode;
+
+/// Level M per version: error correction per block, data codewords of a
+/// short block, short blocks, long blocks.
+const BLOCKS: [(usize, usize, usize, usize); 10] = [
+    (10, 16, 1, 0),
+    (16, 28, 1, 0),
+    (26, 44, 1, 0),
+    (18, 32, 2, 0),
+    (24, 43, 2, 0),
+    (16, 27, 4, 0),
+    (18, 31, 4, 0),
+    (22, 38, 2, 2),
+    (22, 36, 3, 2),
+    (26, 43, 4, 1),
+];
+const ALIGNMENT: [&[usize]; 10] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+];
+/// Format information of level M by mask, from the standard.
+const FORMAT_M: [u32; 8] = [
+    0b101010000010010,
+    0b101000100100101,
+    0b101111001111100,
+    0b101101101001011,
+    0b100010111111001,
+    0b100000011001110,
+    0b100111110010111,
+    0b100101010100000,
+];
+/// Version information of versions 7 to 10, from the standard.
+const VERSION_INFO: [u32; 4] = [0x07C94, 0x085BC, 0x09A99, 0x0A4D3];
+
+fn version_of(code: &QrCode) -> usize {
+    (code.size() - 17) / 4
+}
+
+/// Modules the data does not use: finders with their separators and the
+/// format areas, timing, alignment and version information.
+fn function_map(version: usize, size: usize) -> Vec<bool> {
+    let finder =
+        |x: usize, y: usize| (x < 9 && (y < 9 || y >= size - 8)) || (x >= size - 8 && y < 9);
+    let mut map = vec![false; size * size];
+    for y in 0..size {
+        for x in 0..size {
+            let version_info =
+                version >= 7 && ((x >= size - 11 && y < 6) || (y >= size - 11 && x < 6));
+            map[y * size + x] = finder(x, y) || x == 6 || y == 6 || version_info;
+        }
+    }
+    let centres = ALIGNMENT[version - 1];
+    for &cx in centres {
+        for &cy in centres {
+            if finder(cx, cy) {
+                continue;
+            }
+            for y in cy - 2..=cy + 2 {
+                for x in cx - 2..=cx + 2 {
+                    map[y * size + x] = true;
+                }
+            }
+        }
+    }
+    map
+}
+
+fn masked(mask: u32, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (x / 3 + y / 2).is_multiple_of(2),
+        5 => x * y % 2 + x * y % 3 == 0,
+        6 => (x * y % 2 + x * y % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + x * y % 3).is_multiple_of(2),
+    }
+}
+
+/// The format bits beside the top-left finder, and their copy split
+/// between the other two.
+fn format_bits(code: &QrCode) -> (u32, u32) {
+    let size = code.size();
+    let bit = |dark: bool, i: usize| u32::from(dark) << i;
+    let mut first = 0;
+    for i in 0..=5 {
+        first |= bit(code.get(8, i), i);
+    }
+    first |= bit(code.get(8, 7), 6) | bit(code.get(8, 8), 7) | bit(code.get(7, 8), 8);
+    for i in 9..15 {
+        first |= bit(code.get(14 - i, 8), i);
+    }
+    let mut second = 0;
+    for i in 0..8 {
+        second |= bit(code.get(size - 1 - i, 8), i);
+    }
+    for i in 8..15 {
+        second |= bit(code.get(8, size - 15 + i), i);
+    }
+    (first, second)
+}
+
+/// The codewords in placement order, unmasked.
+fn read_codewords(code: &QrCode, mask: u32) -> Vec<u8> {
+    let size = code.size();
+    let function = function_map(version_of(code), size);
+    let mut bits = Vec::new();
+    let mut right = size - 1;
+    loop {
+        if right == 6 {
+            right = 5;
+        }
+        let upward = (right + 1) & 2 == 0;
+        for vert in 0..size {
+            let y = if upward { size - 1 - vert } else { vert };
+            for x in [right, right - 1] {
+                if !function[y * size + x] {
+                    bits.push(code.get(x, y) ^ masked(mask, x, y));
+                }
+            }
+        }
+        if right < 2 {
+            break;
+        }
+        right -= 2;
+    }
+    bits.chunks_exact(8)
+        .map(|byte| byte.iter().fold(0, |acc, &b| (acc << 1) | u8::from(b)))
+        .collect()
+}
+
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let (mut x, mut y, mut z) = (u16::from(x), y, 0u16);
+    while y != 0 {
+        if y & 1 == 1 {
+            z ^= x;
+        }
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11D;
+        }
+        y >>= 1;
+    }
+    z as u8
+}
+
+/// Whether `block`, data then error correction, evaluates to zero at the
+/// generator's roots, as an undamaged Reed-Solomon codeword does.
+fn syndromes_vanish(block: &[u8], ec_len: usize) -> bool {
+    let mut root = 1u8;
+    for _ in 0..ec_len {
+        let value = block.iter().fold(0u8, |acc, &c| gf_mul(acc, root) ^ c);
+        if value != 0 {
+            return false;
+        }
+        root = gf_mul(root, 2);
+    }
+    true
+}
+
+/// Reads `code` back to its text, checking its structure on the way.
+fn decode(code: &QrCode) -> String {
+    let version = version_of(code);
+    assert_eq!(code.size(), 17 + 4 * version);
+
+    let (first, second) = format_bits(code);
+    assert_eq!(first, second, "format copies differ");
+    let mask = FORMAT_M
+        .iter()
+        .position(|&f| f == first)
+        .unwrap_or_else(|| panic!("{first:015b} is not level M format information"))
+        as u32;
+    if version >= 7 {
+        let size = code.size();
+        let mut info = 0;
+        for i in 0..18 {
+            let (a, b) = (size - 11 + i % 3, i / 3);
+            assert_eq!(code.get(a, b), code.get(b, a), "version copies differ");
+            info |= u32::from(code.get(a, b)) << i;
+        }
+        assert_eq!(info, VERSION_INFO[version - 7]);
+    }
+
+    let (ec_len, short, short_blocks, long_blocks) = BLOCKS[version - 1];
+    let block_count = short_blocks + long_blocks;
+    let codewords = read_codewords(code, mask);
+    let mut blocks: Vec<Vec<u8>> = vec![Vec::new(); block_count];
+    let mut next = codewords.iter().copied();
+    for i in 0..=short {
+        for (b, block) in blocks.iter_mut().enumerate() {
+            if i < short || b >= short_blocks {
+                block.push(next.next().unwrap());
+            }
+        }
+    }
+    for _ in 0..ec_len {
+        for block in &mut blocks {
+            block.push(next.next().unwrap());
+        }
+    }
+    let mut data = Vec::new();
+    for (b, block) in blocks.iter().enumerate() {
+        assert!(syndromes_vanish(block, ec_len), "block {b} is corrupt");
+        data.extend_from_slice(&block[..block.len() - ec_len]);
+    }
+
+    let bit = |i: usize| (data[i / 8] >> (7 - i % 8)) & 1;
+    let read = |from: usize, len: usize| {
+        (from..from + len).fold(0, |acc, i| acc << 1 | usize::from(bit(i)))
+    };
+    assert_eq!(read(0, 4), 0b0100, "not byte mode");
+    let count_bits = if version < 10 { 8 } else { 16 };
+    let len = read(4, count_bits);
+    let start = 4 + count_bits;
+    let text: Vec<u8> = (0..len).map(|i| read(start + 8 * i, 8) as u8).collect();
+    let end = start + 8 * len;
+    let terminator = (data.len() * 8 - end).min(4);
+    assert_eq!(read(end, terminator), 0, "no terminator");
+    let padded = (end + terminator).div_ceil(8);
+    for (i, &pad) in data[padded..].iter().enumerate() {
+        assert_eq!(pad, [0xEC, 0x11][i % 2], "bad pad codeword");
+    }
+    String::from_utf8(text).unwrap()
+}
+
+#[test]
+fn encoded_text_reads_back() {
+    for text in [
+        "",
+        "A",
+        "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU",
+        "solana:7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU?amount=1.5&label=Fixture%20Token",
+        "https://claim.example.com/?wallet=7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU&mint=EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+    ] {
+        assert_eq!(decode(&QrCode::encode(text).unwrap()), text);
+    }
+}
+
+#[test]
+fn every_version_reads_back_at_its_capacity() {
+    // Bytes that fit in versions 1 to 10 at level M.
+    let capacities = [14, 26, 42, 62, 84, 106, 122, 152, 180, 213];
+    for (i, &capacity) in capacities.iter().enumerate() {
+        let text: String = (0..capacity)
+            .map(|j| char::from(b'a' + (j % 26) as u8))
+            .collect();
+        let code = QrCode::encode(&text).unwrap();
+        assert_eq!(version_of(&code), i + 1);
+        assert_eq!(decode(&code), text);
+        if i + 1 < capacities.len() {
+            let over = QrCode::encode(&format!("{text}z")).unwrap();
+            assert_eq!(version_of(&over), i + 2);
+        }
+    }
+}
+
+#[test]
+fn finder_patterns_sit_in_three_corners() {
+    let code = QrCode::encode("finder").unwrap();
+    let size = code.size();
+    for (left, top) in [(0, 0), (size - 7, 0), (0, size - 7)] {
+        for dy in 0..7 {
+            for dx in 0..7 {
+                let ring = dx.min(dy).min(6 - dx).min(6 - dy);
+                assert_eq!(code.get(left + dx, top + dy), ring != 1, "({dx}, {dy})");
+            }
+        }
+    }
+    assert!(!code.get(size, 0) && !code.get(0, size));
+}
+
+#[test]
+fn too_long_text_is_refused() {
+    let text = "x".repeat(214);
+    assert!(matches!(QrCode::encode(&text), Err(Error::InvalidInput(_))));
+}
+
+#[test]
+fn terminal_drawing_has_a_quiet_zone() {
+    let code = QrCode::encode("A").unwrap();
+    let drawn = code.to_terminal();
+    let rows: Vec<&str> = drawn.lines().collect();
+    // Two modules of quiet zone on each side, two rows per line.
+    let side = code.size() + 4;
+    assert_eq!(rows.len(), side.div_ceil(2));
+    assert!(rows.iter().all(|row| row.chars().count() == side));
+    assert!(rows[0].chars().all(|c| c == '█'));
+}
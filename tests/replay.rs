@@ -0,0 +1,276 @@
+//! Launch pipeline and CLI runs replayed from recorded RPC fixtures in
+//! `tests/fixtures`, so they run without network access. Record new ones
+//! with `--record tests/fixtures/<name>.jsonl` against devnet; set
+//! `UPDATE_SNAPSHOTS=1` to rewrite the expected CLI output.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use solanaapp::amount::TokenAmount;
+use solanaapp::config::Config;
+use solanaapp::fixture::Replay;
+use solanaapp::history;
+use solanaapp::keypair::Keypair;
+use solanaapp::launch::{self, LaunchStore, Links, Listing};
+use solanaapp::manager::SolanaTokenManager;
+use solanaapp::metadata;
+use solanaapp::network::Network;
+use solanaapp::pipeline::{self, LaunchAccounts};
+use solanaapp::pubkey::Pubkey;
+use solanaapp::rpc::RpcClient;
+use solanaapp::session::{LaunchParams, LaunchSession, Step};
+use solanaapp::token;
+use solanaapp::{Error, Result};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{name}.jsonl"))
+}
+
+fn replay(name: &str) -> RpcClient {
+    RpcClient::replay(Replay::load(&fixture(name)).expect("fixture loads"))
+}
+
+fn session(payer: &Keypair) -> LaunchSession {
+    let params = LaunchParams {
+        name: "Fixture Token".into(),
+        symbol: "FIX".into(),
+        uri: "https://example.com/fix.json".into(),
+        supply: TokenAmount::parse("1000000000", 6).unwrap(),
+        token_program: token::TOKEN_PROGRAM_ID,
+        freeze_authority: false,
+        revoke_mint_authority: false,
+        is_mutable: true,
+        description: None,
+        image: None,
+        links: Links::default(),
+        listing: Listing::default(),
+        transfer_hook: None,
+        group: None,
+        memo: None,
+        supply_account: None,
+        compute_unit_limit: None,
+        compute_unit_price: None,
+        metadata_program: None,
+    };
+    LaunchSession::new(
+        "fixture".into(),
+        Network::Devnet,
+        payer.pubkey(),
+        params,
+        launch::now_unix(),
+    )
+}
+
+fn run(manager: &SolanaTokenManager, session: &mut LaunchSession) -> Result<()> {
+    while let Some(step) = session.next_step() {
+        manager.execute(session, step)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn launch_completes_every_step() {
+    let rpc = replay("launch");
+    let payer = Keypair::from_seed(&[1; 32]);
+    let manager = SolanaTokenManager::new(&rpc, &payer);
+    let mut session = session(&payer);
+    run(&manager, &mut session).expect("launch replays");
+
+    assert!(session.is_complete());
+    assert!(session.last_error.is_none());
+    let steps: Vec<Step> = session.completed.iter().map(|r| r.step).collect();
+    assert_eq!(
+        steps,
+        [
+            Step::CreateMint,
+            Step::CreateTokenAccount,
+            Step::MintSupply,
+            Step::CreateMetadata
+        ]
+    );
+    assert_eq!(
+        session.mint,
+        Some(manager.mint_keypair(&session).expect("mint key").pubkey())
+    );
+    assert!(session.token_account.is_some());
+}
+
+#[test]
+fn failed_step_is_recorded_and_resumes() {
+    let rpc = replay("launch_insufficient_funds");
+    let payer = Keypair::from_seed(&[1; 32]);
+    let manager = SolanaTokenManager::new(&rpc, &payer);
+    let mut session = session(&payer);
+
+    let err = manager
+        .execute(&mut session, Step::CreateMint)
+        .expect_err("unfunded payer fails");
+    assert!(matches!(err, Error::SimulationFailed(_)), "{err}");
+    assert_eq!(session.next_step(), Some(Step::CreateMint));
+    assert!(
+        session
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("never been funded")
+    );
+
+    manager
+        .execute(&mut session, Step::CreateMint)
+        .expect("retry succeeds");
+    assert!(session.last_error.is_none());
+    assert_eq!(session.next_step(), Some(Step::CreateTokenAccount));
+}
+
+#[test]
+fn rejected_transaction_is_explained_from_its_logs() {
+    let rpc = replay("launch_not_rent_exempt");
+    let payer = Keypair::from_seed(&[1; 32]);
+    let manager = SolanaTokenManager::new(&rpc, &payer);
+    let mut session = session(&payer);
+
+    let err = manager
+        .execute(&mut session, Step::CreateMint)
+        .expect_err("token program rejects the mint");
+    let Error::SimulationFailed(reason) = &err else {
+        panic!("expected a rejected simulation, got {err}");
+    };
+    assert!(reason.contains("rent exempt"), "{reason}");
+    assert!(reason.contains("instruction 1, error 0x0"), "{reason}");
+    assert!(session.pending.is_none());
+    assert_eq!(session.next_step(), Some(Step::CreateMint));
+}
+
+#[test]
+fn metadata_is_decoded_or_reported_missing() {
+    let mint = Pubkey::new([0x4d; 32]);
+    let found = metadata::fetch_metadata(&replay("metadata"), &mint)
+        .unwrap()
+        .expect("metadata account");
+    assert_eq!(found.data.name, "Fixture Token");
+    assert_eq!(found.data.symbol, "FIX");
+    assert_eq!(found.data.uri, "https://example.com/fix.json");
+    assert!(found.is_mutable);
+
+    assert!(
+        metadata::fetch_metadata(&replay("metadata_missing"), &mint)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn existing_metadata_is_updated_not_created() {
+    let mint = Pubkey::new([0x4d; 32]);
+    let existing = metadata::fetch_metadata(&replay("metadata"), &mint)
+        .unwrap()
+        .expect("metadata account");
+    let payer = Keypair::from_seed(&[1; 32]);
+    let params = session(&payer).params;
+
+    let accounts = LaunchAccounts {
+        authority: existing.update_authority,
+        mint,
+        token_account: Pubkey::new([0x4e; 32]),
+    };
+    let created = pipeline::metadata_instructions(&params, &accounts, None).unwrap();
+    let updated = pipeline::metadata_instructions(&params, &accounts, Some(&existing)).unwrap();
+    assert_ne!(created[0].data[0], updated[0].data[0]);
+    assert_eq!(updated[0].accounts.len(), 2);
+
+    let stranger = LaunchAccounts {
+        authority: payer.pubkey(),
+        ..accounts
+    };
+    let err = pipeline::metadata_instructions(&params, &stranger, Some(&existing))
+        .expect_err("not the update authority");
+    assert!(err.to_string().contains("update authority"), "{err}");
+}
+
+#[test]
+fn metadata_step_update_keeps_the_replaced_version() {
+    // The mint already has metadata pointing at old.json, which the
+    // launch's metadata step replaces with new.json.
+    let rpc = replay("metadata_update");
+    let payer = Keypair::from_seed(&[1; 32]);
+    let manager = SolanaTokenManager::new(&rpc, &payer);
+    let mut session = session(&payer);
+    session.params.uri = "fixture://new.json".into();
+    let mint = manager.mint_keypair(&session).expect("mint key").pubkey();
+    session.mint = Some(mint);
+
+    let dir = std::env::temp_dir().join(format!("solanaapp-history-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = LaunchStore::open(&dir).unwrap();
+    let (result, kept) = history::backed_up(&store, &Config::default(), &rpc, &[mint], || {
+        manager.execute(&mut session, Step::CreateMetadata)
+    })
+    .expect("snapshot taken");
+    result.expect("metadata updated");
+    assert_eq!(kept.expect("version kept"), [mint]);
+
+    let record = store
+        .load(&mint)
+        .unwrap()
+        .expect("record rebuilt from the chain");
+    assert_eq!(record.uri, "fixture://new.json");
+    let [version] = record.metadata_history.as_slice() else {
+        panic!("expected one version, got {:?}", record.metadata_history);
+    };
+    assert_eq!(version.data.uri, "fixture://old.json");
+    assert_eq!(version.data.name, "Fixture Token");
+    assert!(version.replaced_at > 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn unrecorded_call_is_an_error() {
+    let err = replay("metadata_missing")
+        .get_slot()
+        .expect_err("not recorded");
+    assert!(err.to_string().contains("no recorded response"), "{err}");
+}
+
+/// Runs the CLI against a fixture and compares stdout with a snapshot.
+fn assert_cli_snapshot(fixture_name: &str, args: &[&str], snapshot: &str) {
+    let config = std::env::temp_dir().join(format!(
+        "launch-solana-replay-{}-{snapshot}",
+        std::process::id()
+    ));
+    let output = Command::new(env!("CARGO_BIN_EXE_solanaapp"))
+        .env("XDG_CONFIG_HOME", &config)
+        .arg("--replay")
+        .arg(fixture(fixture_name))
+        .args(args)
+        .output()
+        .expect("binary runs");
+    let _ = std::fs::remove_dir_all(&config);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{snapshot}.txt"));
+    let actual = String::from_utf8(output.stdout).unwrap();
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+    }
+    assert_eq!(actual, std::fs::read_to_string(&path).unwrap());
+}
+
+const FIXTURE_MINT: &str = "AEFQCtjuv83LJX5PVdqGv7BHTSoXqiUYLqsF8PvNxrLa";
+
+#[test]
+fn reconcile_output_matches_snapshot() {
+    assert_cli_snapshot("reconcile", &["reconcile", FIXTURE_MINT], "reconcile");
+}
+
+#[test]
+fn holders_output_matches_snapshot() {
+    assert_cli_snapshot("holders", &["holders", FIXTURE_MINT], "holders");
+}
@@ -0,0 +1,87 @@
+//! The plan-file YAML subset: what it reads, and the line it blames for
+//! what it doesn't.
+
+use serde_json::json;
+use solanaapp::Error;
+use solanaapp::yaml;
+
+fn rejected(source: &str) -> String {
+    match yaml::parse(source) {
+        Err(Error::InvalidInput(reason)) => reason,
+        other => panic!("expected {source:?} to be rejected, got {other:?}"),
+    }
+}
+
+#[test]
+fn supported_subset_reads_as_json() {
+    let source = "\
+---
+# a launch plan
+name: Fixture Token   # trailing comment
+symbol: 'FIX'
+supply: 1000000
+price: 12.5
+underscored: 1_000_000
+note: \"tab\\there, # not a comment\"
+quote: 'it''s'
+mutable: true
+image: ~
+steps:
+  - mint
+  - kind: airdrop
+    recipients: []
+    extra: {}
+  -
+    kind: pool
+wallets:
+- 7xKX
+- don't
+";
+    assert_eq!(
+        yaml::parse(source).unwrap(),
+        json!({
+            "name": "Fixture Token",
+            "symbol": "FIX",
+            "supply": 1_000_000,
+            "price": "12.5",
+            "underscored": "1_000_000",
+            "note": "tab\there, # not a comment",
+            "quote": "it's",
+            "mutable": true,
+            "image": null,
+            "steps": [
+                "mint",
+                { "kind": "airdrop", "recipients": [], "extra": {} },
+                { "kind": "pool" }
+            ],
+            "wallets": ["7xKX", "don't"]
+        })
+    );
+    assert_eq!(yaml::parse("# nothing\n").unwrap(), json!(null));
+}
+
+#[test]
+fn malformed_documents_name_their_line() {
+    for (source, line, why) in [
+        ("a: 1\n\tb: 2\n", 2, "tabs"),
+        ("a: \"open\n", 1, "unterminated double-quoted"),
+        ("a: 'open\n", 1, "unterminated single-quoted"),
+        ("a: \"\\x41\"\n", 1, "unsupported escape"),
+        ("a: 1\nb: [1, 2]\n", 2, "flow collections"),
+        ("a: &anchor 1\n", 1, "anchors"),
+        ("a: !tag 1\n", 1, "tags"),
+        ("a: |\n  text\n", 1, "block scalars"),
+        ("a: 1\na: 2\n", 2, "duplicate key 'a'"),
+        ("a: 1\n  b: 2\n", 2, "unexpected indentation"),
+        ("a:\n  - 1\n    - 2\n", 3, "unexpected indentation"),
+        ("  a: 1\nb: 2\n", 2, "unexpected indentation"),
+        ("a: 1\njust text\n", 2, "expected 'key: value'"),
+        ("- 1\nb: 2\n", 2, "unexpected indentation"),
+    ] {
+        let reason = rejected(source);
+        assert!(
+            reason.starts_with(&format!("line {line}: ")) && reason.contains(why),
+            "{source:?}: {reason}"
+        );
+    }
+}